@@ -0,0 +1,180 @@
+//! Benchmarks for the search and edit hot paths
+//!
+//! Run `cargo bench --bench hot_paths -- --save-baseline main` on the reference commit,
+//! then `cargo bench --bench hot_paths -- --baseline main` on the change. After the
+//! comparison, any benchmark whose mean time grew by more than the budget (default 10%,
+//! `CATS_BENCH_BUDGET=<percent>` to override) fails the run.
+
+use cats::editing::EditTool;
+use cats::testing::{generate_corpus, CorpusOptions};
+use cats::{json_to_tool_args, ReplaceTextTool, SearchDirTool, Tool, ToolArgs, ToolState};
+use criterion::{criterion_group, BatchSize, Criterion};
+use std::path::{Path, PathBuf};
+use std::sync::{Arc, Mutex};
+use std::time::SystemTime;
+
+const DEFAULT_BUDGET_PERCENT: f64 = 10.0;
+const GROUPS: &[&str] = &["search_dir", "fuzzy_match", "large_file_edit"];
+
+fn state() -> Arc<Mutex<ToolState>> {
+    Arc::new(Mutex::new(ToolState::new()))
+}
+
+fn search_dir(c: &mut Criterion) {
+    let temp = tempfile::TempDir::new().unwrap();
+    let options = CorpusOptions {
+        source_files: 2_000,
+        noise_files: 2_000,
+        ..CorpusOptions::default()
+    };
+    generate_corpus(temp.path(), &options).unwrap();
+    let root = temp.path().to_string_lossy().to_string();
+    let mut tool = SearchDirTool::new();
+    let state = state();
+
+    let mut group = c.benchmark_group("search_dir");
+    group.sample_size(20);
+    group.bench_function("literal_2k_files", |b| {
+        let args = ToolArgs::from_args(&["CATS_NEEDLE", &root]);
+        b.iter(|| tool.execute(&args, &state).unwrap())
+    });
+    group.bench_function("no_match_2k_files", |b| {
+        let args = ToolArgs::from_args(&["absent_token_xyz", &root]);
+        b.iter(|| tool.execute(&args, &state).unwrap())
+    });
+    group.finish();
+}
+
+/// A file of `lines` numbered lines
+fn large_file(lines: usize) -> String {
+    (0..lines)
+        .map(|i| {
+            format!(
+                "    let value_{} = compute({}, \"item {}\");\n",
+                i,
+                i * 7,
+                i
+            )
+        })
+        .collect()
+}
+
+fn write_file(dir: &Path, contents: &str) -> PathBuf {
+    let path = dir.join("large.rs");
+    std::fs::write(&path, contents).unwrap();
+    path
+}
+
+fn fuzzy_match(c: &mut Criterion) {
+    let temp = tempfile::TempDir::new().unwrap();
+    let path = write_file(temp.path(), &large_file(2_000));
+    let mut tool = EditTool::new();
+    let state = state();
+    // Close to line 1500 but not exact, so matching falls back to fuzzy search
+    let args = json_to_tool_args(
+        "edit",
+        serde_json::json!({
+            "path": path.to_string_lossy(),
+            "old_text": "    let value_1500 = compute(10500, \"itme 1500\");",
+            "new_text": "    let value_1500 = 0;",
+            "preview": true
+        }),
+    )
+    .unwrap();
+
+    let mut group = c.benchmark_group("fuzzy_match");
+    group.sample_size(20);
+    group.bench_function("2k_lines", |b| {
+        b.iter(|| tool.execute(&args, &state).unwrap())
+    });
+    group.finish();
+}
+
+fn large_file_edit(c: &mut Criterion) {
+    let temp = tempfile::TempDir::new().unwrap();
+    let contents = large_file(50_000);
+    let path = write_file(temp.path(), &contents);
+    let path_str = path.to_string_lossy().to_string();
+    let mut tool = ReplaceTextTool::new();
+    let state = state();
+    let args = ToolArgs::from_args(&[&path_str, "let value_49999 = compute", "let last = compute"]);
+
+    let mut group = c.benchmark_group("large_file_edit");
+    group.sample_size(20);
+    group.bench_function("replace_text_50k_lines", |b| {
+        b.iter_batched(
+            || std::fs::write(&path, &contents).unwrap(),
+            |_| {
+                let result = tool.execute(&args, &state).unwrap();
+                assert!(result.success, "{}", result.message);
+            },
+            BatchSize::PerIteration,
+        )
+    });
+    group.finish();
+}
+
+/// Relative mean change, in percent, of every benchmark criterion compared since `since`
+fn mean_changes(criterion_dir: &Path, since: SystemTime) -> Vec<(String, f64)> {
+    let mut changes = Vec::new();
+    for group in GROUPS {
+        let Ok(entries) = std::fs::read_dir(criterion_dir.join(group)) else {
+            continue;
+        };
+        for entry in entries.flatten() {
+            let estimates = entry.path().join("change").join("estimates.json");
+            // Skip comparisons left over from earlier runs
+            let fresh = std::fs::metadata(&estimates)
+                .and_then(|m| m.modified())
+                .is_ok_and(|modified| modified >= since);
+            if !fresh {
+                continue;
+            }
+            let Ok(text) = std::fs::read_to_string(&estimates) else {
+                continue;
+            };
+            let value: serde_json::Value = serde_json::from_str(&text).unwrap_or_default();
+            if let Some(change) = value["mean"]["point_estimate"].as_f64() {
+                let name = format!("{}/{}", group, entry.file_name().to_string_lossy());
+                changes.push((name, change * 100.0));
+            }
+        }
+    }
+    changes
+}
+
+/// Fail the run if a benchmark regressed beyond the budget
+fn enforce_budget(since: SystemTime) {
+    let budget = std::env::var("CATS_BENCH_BUDGET")
+        .ok()
+        .and_then(|v| v.parse::<f64>().ok())
+        .unwrap_or(DEFAULT_BUDGET_PERCENT);
+    let target = std::env::var_os("CARGO_TARGET_DIR")
+        .map(PathBuf::from)
+        .unwrap_or_else(|| Path::new(env!("CARGO_MANIFEST_DIR")).join("target"));
+
+    let over: Vec<String> = mean_changes(&target.join("criterion"), since)
+        .into_iter()
+        .filter(|(_, change)| *change > budget)
+        .map(|(name, change)| format!("{}: +{:.1}%", name, change))
+        .collect();
+    if !over.is_empty() {
+        eprintln!("Benchmarks over the {}% budget:", budget);
+        for line in &over {
+            eprintln!("  {}", line);
+        }
+        std::process::exit(1);
+    }
+}
+
+criterion_group!(benches, search_dir, fuzzy_match, large_file_edit);
+
+fn main() {
+    let start = SystemTime::now();
+    benches();
+    Criterion::default().configure_from_args().final_summary();
+    // `cargo test --benches` runs each benchmark once without measuring
+    if std::env::args().any(|a| a == "--bench") {
+        enforce_budget(start);
+    }
+}
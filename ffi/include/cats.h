@@ -0,0 +1,90 @@
+/*
+ * cats.h - C interface to the CATS toolset
+ *
+ * Requests and responses are JSON, the same as `cats exec --stdin`:
+ *
+ *     CatsRegistry *reg = cats_registry_new_in("/path/to/workspace");
+ *     if (!reg) { fprintf(stderr, "%s\n", cats_last_error()); return 1; }
+ *
+ *     char *out = cats_execute(reg, "search_dir", "{\"search_term\": \"TODO\"}");
+ *     puts(out);               // {"success": true, "message": "...", "data": {...}}
+ *     cats_string_free(out);
+ *
+ *     cats_registry_free(reg);
+ *
+ * Input strings are NUL-terminated UTF-8. Strings returned by cats_execute,
+ * cats_execute_request, cats_validate_args and cats_tool_schemas belong to the
+ * caller and must be released with cats_string_free. A registry is not
+ * thread-safe: serialize the calls made on one registry, or create one
+ * registry per thread.
+ */
+
+#ifndef CATS_H
+#define CATS_H
+
+#ifdef __cplusplus
+extern "C" {
+#endif
+
+/* Opaque registry handle */
+typedef struct CatsRegistry CatsRegistry;
+
+/* Library version; static, do not free */
+const char *cats_version(void);
+
+/*
+ * Create a registry with every built-in tool, applying CATS_* environment
+ * overrides. Returns NULL on failure; see cats_last_error.
+ */
+CatsRegistry *cats_registry_new(void);
+
+/*
+ * Create a registry for a workspace: loads its cats.toml, runs sessions in
+ * it and applies CATS_* environment overrides. Returns NULL on failure; see
+ * cats_last_error.
+ */
+CatsRegistry *cats_registry_new_in(const char *workspace_root);
+
+/* Free a registry; NULL is ignored */
+void cats_registry_free(CatsRegistry *registry);
+
+/*
+ * Execute a tool with a JSON object of arguments (args_json may be NULL).
+ * Always returns a JSON response; failures set "success" to false and carry
+ * an "error" object with "kind" and "message". Results with images carry an
+ * "attachments" array of {"mime_type", "description", "data" (base64) or "path"}.
+ */
+char *cats_execute(CatsRegistry *registry, const char *tool_name, const char *args_json);
+
+/*
+ * Execute a complete request:
+ * {"tool": "...", "args": {...}, "id": ..., "session": "..."}
+ * "id" is echoed in the response and "session" selects the registry session.
+ */
+char *cats_execute_request(CatsRegistry *registry, const char *request_json);
+
+/*
+ * Check a JSON object of arguments against a tool's schema without running
+ * the tool. Returns {"valid": ..., "violations": [{"path", "message"}],
+ * "report": "..."}, where "report" is a correction prompt for the model, or
+ * an "error" object when the tool is unknown or args_json is not JSON.
+ */
+char *cats_validate_args(const CatsRegistry *registry, const char *tool_name, const char *args_json);
+
+/* OpenAI function schemas of the registered tools as a JSON array; NULL if registry is NULL */
+char *cats_tool_schemas(const CatsRegistry *registry);
+
+/* Free a string returned by this library; NULL is ignored */
+void cats_string_free(char *value);
+
+/*
+ * Description of the last failed cats_registry_new* call on this thread, or
+ * NULL. Valid until the next failure on the same thread; do not free.
+ */
+const char *cats_last_error(void);
+
+#ifdef __cplusplus
+}
+#endif
+
+#endif /* CATS_H */
@@ -0,0 +1,376 @@
+//! C ABI for CATS
+//!
+//! A stable C interface so editors and runtimes written in other languages can host the
+//! toolset: create a registry, execute tools with JSON in and JSON out, and free what
+//! the library returned. `include/cats.h` declares the functions.
+//!
+//! Requests and responses use the same JSON as `cats exec --stdin`: a request is
+//! `{"tool": "...", "args": {...}, "id": ..., "session": "..."}`. A response carries
+//! `success`, `message` and `data`, or an `error` object when the call could not run.
+//! Strings passed in must be NUL-terminated UTF-8. Strings returned by `cats_execute*`,
+//! `cats_validate_args` and `cats_tool_schemas` are owned by the caller and released
+//! with `cats_string_free`. A registry is not thread-safe; serialize the calls made on one
+//! registry.
+
+use cats::config::Config;
+use cats::{execute_json_line, RegistryBuilder, ToolRegistry};
+use serde_json::Value;
+use std::cell::RefCell;
+use std::ffi::{c_char, CStr, CString};
+use std::panic::{catch_unwind, AssertUnwindSafe};
+use std::path::Path;
+use std::ptr;
+
+/// Opaque registry handle
+pub struct CatsRegistry {
+    registry: ToolRegistry,
+}
+
+thread_local! {
+    static LAST_ERROR: RefCell<Option<CString>> = const { RefCell::new(None) };
+}
+
+fn set_last_error(message: impl Into<String>) {
+    let message = CString::new(message.into().replace('\0', " ")).unwrap_or_default();
+    LAST_ERROR.with(|last| *last.borrow_mut() = Some(message));
+}
+
+/// Borrow a C string argument as UTF-8
+///
+/// # Safety
+///
+/// `value` must be null or point to a NUL-terminated string.
+unsafe fn str_arg<'a>(value: *const c_char, name: &str) -> Result<&'a str, String> {
+    if value.is_null() {
+        return Err(format!("{} is null", name));
+    }
+    CStr::from_ptr(value)
+        .to_str()
+        .map_err(|e| format!("{} is not valid UTF-8: {}", name, e))
+}
+
+/// Hand a string to the caller, who frees it with `cats_string_free`
+fn into_c_string(value: String) -> *mut c_char {
+    CString::new(value.replace('\0', "\\u0000"))
+        .map(CString::into_raw)
+        .unwrap_or(ptr::null_mut())
+}
+
+fn error_response(kind: &str, message: impl Into<String>) -> *mut c_char {
+    let response = serde_json::json!({
+        "success": false,
+        "error": {"kind": kind, "message": message.into()}
+    });
+    into_c_string(response.to_string())
+}
+
+/// Run `f`, turning a panic into an in-band error response
+fn guarded(f: impl FnOnce() -> Value) -> *mut c_char {
+    match catch_unwind(AssertUnwindSafe(f)) {
+        Ok(response) => into_c_string(response.to_string()),
+        Err(_) => error_response("panic", "The tool panicked"),
+    }
+}
+
+fn new_registry(builder: Result<RegistryBuilder, String>) -> *mut CatsRegistry {
+    let built = catch_unwind(AssertUnwindSafe(|| builder.map(RegistryBuilder::build)));
+    match built {
+        Ok(Ok(registry)) => Box::into_raw(Box::new(CatsRegistry { registry })),
+        Ok(Err(message)) => {
+            set_last_error(message);
+            ptr::null_mut()
+        }
+        Err(_) => {
+            set_last_error("Creating the registry panicked");
+            ptr::null_mut()
+        }
+    }
+}
+
+/// Version of the library, as a static string the caller must not free
+#[no_mangle]
+pub extern "C" fn cats_version() -> *const c_char {
+    concat!(env!("CARGO_PKG_VERSION"), "\0").as_ptr().cast()
+}
+
+/// Create a registry with every built-in tool, applying `CATS_*` environment overrides
+///
+/// Returns null on failure; `cats_last_error` then describes it.
+#[no_mangle]
+pub extern "C" fn cats_registry_new() -> *mut CatsRegistry {
+    new_registry(RegistryBuilder::new().env().map_err(|e| e.to_string()))
+}
+
+/// Create a registry for the workspace at `workspace_root`
+///
+/// Loads `cats.toml` from the workspace, runs every session there and applies
+/// `CATS_*` environment overrides. Returns null on failure; `cats_last_error` then
+/// describes it.
+///
+/// # Safety
+///
+/// `workspace_root` must be null or point to a NUL-terminated string.
+#[no_mangle]
+pub unsafe extern "C" fn cats_registry_new_in(workspace_root: *const c_char) -> *mut CatsRegistry {
+    let builder = str_arg(workspace_root, "workspace_root").and_then(|root| {
+        let config = Config::load(Path::new(root)).map_err(|e| format!("{:#}", e))?;
+        RegistryBuilder::new()
+            .config(config)
+            .sandbox_root(root)
+            .env()
+            .map_err(|e| e.to_string())
+    });
+    new_registry(builder)
+}
+
+/// Free a registry from `cats_registry_new*`; null is ignored
+///
+/// # Safety
+///
+/// `registry` must be null or a pointer returned by `cats_registry_new*` that has not
+/// been freed.
+#[no_mangle]
+pub unsafe extern "C" fn cats_registry_free(registry: *mut CatsRegistry) {
+    if !registry.is_null() {
+        drop(Box::from_raw(registry));
+    }
+}
+
+/// Execute `tool_name` with a JSON object of arguments; `args_json` may be null
+///
+/// Returns a JSON response the caller frees with `cats_string_free`. Failures,
+/// including invalid arguments to this function, are reported in the response.
+///
+/// # Safety
+///
+/// `registry` must be a live registry handle; `tool_name` and `args_json` must be null
+/// or point to NUL-terminated strings.
+#[no_mangle]
+pub unsafe extern "C" fn cats_execute(
+    registry: *mut CatsRegistry,
+    tool_name: *const c_char,
+    args_json: *const c_char,
+) -> *mut c_char {
+    let Some(registry) = registry.as_mut() else {
+        return error_response("invalid_args", "registry is null");
+    };
+    let tool_name = match str_arg(tool_name, "tool_name") {
+        Ok(name) => name,
+        Err(message) => return error_response("invalid_args", message),
+    };
+    let mut request = serde_json::json!({"tool": tool_name});
+    if !args_json.is_null() {
+        let text = match str_arg(args_json, "args_json") {
+            Ok(text) => text,
+            Err(message) => return error_response("invalid_args", message),
+        };
+        match serde_json::from_str::<Value>(text) {
+            Ok(args) => request["args"] = args,
+            Err(e) => return error_response("json", format!("Invalid args_json: {}", e)),
+        }
+    }
+    guarded(|| execute_json_line(&mut registry.registry, &request.to_string()))
+}
+
+/// Execute a complete JSON request: `{"tool": ..., "args": ..., "id": ..., "session": ...}`
+///
+/// Returns a JSON response the caller frees with `cats_string_free`; `id` is echoed.
+///
+/// # Safety
+///
+/// `registry` must be a live registry handle; `request_json` must be null or point to
+/// a NUL-terminated string.
+#[no_mangle]
+pub unsafe extern "C" fn cats_execute_request(
+    registry: *mut CatsRegistry,
+    request_json: *const c_char,
+) -> *mut c_char {
+    let Some(registry) = registry.as_mut() else {
+        return error_response("invalid_args", "registry is null");
+    };
+    match str_arg(request_json, "request_json") {
+        Ok(request) => guarded(|| execute_json_line(&mut registry.registry, request)),
+        Err(message) => error_response("invalid_args", message),
+    }
+}
+
+/// Check a JSON object of arguments against a tool's schema without running the tool
+///
+/// Returns `{"valid": bool, "violations": [{"path", "message"}], "report": "..."}`,
+/// where `report` is a correction prompt for the model, or an `error` response when
+/// the tool is unknown or `args_json` is not JSON. Free the result with
+/// `cats_string_free`.
+///
+/// # Safety
+///
+/// `registry` must be a live registry handle; `tool_name` and `args_json` must be null
+/// or point to NUL-terminated strings.
+#[no_mangle]
+pub unsafe extern "C" fn cats_validate_args(
+    registry: *const CatsRegistry,
+    tool_name: *const c_char,
+    args_json: *const c_char,
+) -> *mut c_char {
+    let Some(registry) = registry.as_ref() else {
+        return error_response("invalid_args", "registry is null");
+    };
+    let (tool_name, text) = match (
+        str_arg(tool_name, "tool_name"),
+        str_arg(args_json, "args_json"),
+    ) {
+        (Ok(tool_name), Ok(text)) => (tool_name, text),
+        (Err(message), _) | (_, Err(message)) => return error_response("invalid_args", message),
+    };
+    let args = match serde_json::from_str::<Value>(text) {
+        Ok(args) => args,
+        Err(e) => return error_response("json", format!("Invalid args_json: {}", e)),
+    };
+    guarded(
+        || match registry.registry.validate_against_schema(tool_name, &args) {
+            Ok(violations) => serde_json::json!({
+                "valid": violations.is_empty(),
+                "report": (!violations.is_empty())
+                    .then(|| cats::schema::violation_report(tool_name, &violations)),
+                "violations": violations,
+            }),
+            Err(e) => serde_json::json!({
+                "success": false,
+                "error": {"kind": e.kind(), "message": e.to_string()}
+            }),
+        },
+    )
+}
+
+/// OpenAI function schemas of the registered tools, as a JSON array
+///
+/// Returns null if `registry` is null; free the result with `cats_string_free`.
+///
+/// # Safety
+///
+/// `registry` must be null or a live registry handle.
+#[no_mangle]
+pub unsafe extern "C" fn cats_tool_schemas(registry: *const CatsRegistry) -> *mut c_char {
+    match registry.as_ref() {
+        Some(registry) => {
+            into_c_string(Value::from(registry.registry.get_all_schemas()).to_string())
+        }
+        None => ptr::null_mut(),
+    }
+}
+
+/// Free a string returned by this library; null is ignored
+///
+/// # Safety
+///
+/// `value` must be null or a string returned by `cats_execute*` or `cats_tool_schemas`
+/// that has not been freed.
+#[no_mangle]
+pub unsafe extern "C" fn cats_string_free(value: *mut c_char) {
+    if !value.is_null() {
+        drop(CString::from_raw(value));
+    }
+}
+
+/// Description of the last failed `cats_registry_new*` call on this thread, or null
+///
+/// The string stays valid until the next failure on the same thread; do not free it.
+#[no_mangle]
+pub extern "C" fn cats_last_error() -> *const c_char {
+    LAST_ERROR.with(|last| {
+        last.borrow()
+            .as_ref()
+            .map_or(ptr::null(), |message| message.as_ptr())
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// Take ownership of a returned string and parse it
+    fn response(value: *mut c_char) -> Value {
+        assert!(!value.is_null());
+        let text = unsafe { CStr::from_ptr(value) }
+            .to_str()
+            .unwrap()
+            .to_string();
+        unsafe { cats_string_free(value) };
+        serde_json::from_str(&text).unwrap()
+    }
+
+    #[test]
+    fn test_execute_round_trip() {
+        let temp = tempfile::TempDir::new().unwrap();
+        std::fs::write(temp.path().join("a.txt"), "needle\n").unwrap();
+        let root = CString::new(temp.path().to_str().unwrap()).unwrap();
+
+        unsafe {
+            let registry = cats_registry_new_in(root.as_ptr());
+            assert!(!registry.is_null());
+
+            let tool = CString::new("search_dir").unwrap();
+            let args = CString::new(
+                serde_json::json!({"search_term": "needle", "dir": temp.path()}).to_string(),
+            )
+            .unwrap();
+            let found = response(cats_execute(registry, tool.as_ptr(), args.as_ptr()));
+            assert_eq!(found["success"], true, "{}", found);
+            assert_eq!(found["data"]["total_matches"], 1);
+
+            let request = CString::new(r#"{"tool": "opn", "id": 7}"#).unwrap();
+            let unknown = response(cats_execute_request(registry, request.as_ptr()));
+            assert_eq!(unknown["success"], false);
+            assert_eq!(unknown["id"], 7);
+            assert_eq!(unknown["error"]["kind"], "tool_not_found");
+
+            let bad = CString::new("{").unwrap();
+            let invalid = response(cats_execute(registry, tool.as_ptr(), bad.as_ptr()));
+            assert_eq!(invalid["error"]["kind"], "json");
+
+            let misspelled = CString::new(r#"{"search_trm": "needle"}"#).unwrap();
+            let checked = response(cats_validate_args(
+                registry,
+                tool.as_ptr(),
+                misspelled.as_ptr(),
+            ));
+            assert_eq!(checked["valid"], false, "{}", checked);
+            assert_eq!(checked["violations"].as_array().unwrap().len(), 2);
+            assert!(checked["report"]
+                .as_str()
+                .unwrap()
+                .contains("did you mean 'search_term'?"));
+            let checked = response(cats_validate_args(registry, tool.as_ptr(), args.as_ptr()));
+            assert_eq!(checked["valid"], true, "{}", checked);
+
+            let schemas = response(cats_tool_schemas(registry));
+            assert!(schemas
+                .as_array()
+                .unwrap()
+                .iter()
+                .any(|s| s["function"]["name"] == "search_dir"));
+
+            cats_registry_free(registry);
+        }
+    }
+
+    #[test]
+    fn test_null_arguments_and_last_error() {
+        unsafe {
+            let null = response(cats_execute(ptr::null_mut(), ptr::null(), ptr::null()));
+            assert_eq!(null["error"]["message"], "registry is null");
+            assert!(cats_tool_schemas(ptr::null()).is_null());
+            cats_registry_free(ptr::null_mut());
+            cats_string_free(ptr::null_mut());
+
+            let temp = tempfile::TempDir::new().unwrap();
+            std::fs::write(temp.path().join("cats.toml"), "[window\n").unwrap();
+            let root = CString::new(temp.path().to_str().unwrap()).unwrap();
+            assert!(cats_registry_new_in(root.as_ptr()).is_null());
+            let error = CStr::from_ptr(cats_last_error()).to_str().unwrap();
+            assert!(error.contains("Failed to parse"), "{}", error);
+
+            let version = CStr::from_ptr(cats_version()).to_str().unwrap();
+            assert_eq!(version, env!("CARGO_PKG_VERSION"));
+        }
+    }
+}
@@ -0,0 +1,19 @@
+//! Generated files and edits applied through `Span::replace` keep the text outside
+//! the edit and the line endings intact
+
+#![no_main]
+
+use cats::testing::{check_edit, check_eol_preserved, FileOptions, Generator};
+use libfuzzer_sys::fuzz_target;
+
+fuzz_target!(|seed: u64| {
+    let mut generator = Generator::new(seed);
+    let content = generator.file(&FileOptions::default());
+    let Some(edit) = generator.edit(&content) else {
+        return;
+    };
+    let mut after = content.clone();
+    assert!(edit.span.replace(&mut after, &edit.new_text));
+    check_edit(&content, &after, &edit).unwrap();
+    check_eol_preserved(&content, &after).unwrap();
+});
@@ -0,0 +1,35 @@
+//! Matches in normalized text map back to spans of the original that normalize to
+//! text containing the match
+
+#![no_main]
+
+use arbitrary::Arbitrary;
+use cats::editing::{NormalizationOptions, NormalizedText};
+use libfuzzer_sys::fuzz_target;
+
+#[derive(Debug, Arbitrary)]
+struct Input {
+    text: String,
+    needle: String,
+    flags: u8,
+}
+
+fuzz_target!(|input: Input| {
+    let options = NormalizationOptions {
+        normalize_eol: input.flags & 1 != 0,
+        trim_lines: input.flags & 2 != 0,
+        normalize_whitespace: input.flags & 4 != 0,
+        ignore_case: input.flags & 8 != 0,
+    };
+    let normalized = NormalizedText::new(&input.text, &options);
+    let needle = NormalizedText::new(&input.needle, &options).into_string();
+    if needle.is_empty() {
+        return;
+    }
+    for (start, matched) in normalized.as_str().match_indices(needle.as_str()) {
+        let span = normalized.to_original(start, start + matched.len());
+        assert!(input.text.is_char_boundary(span.start()));
+        assert!(input.text.is_char_boundary(span.end()));
+        assert!(!span.slice(&input.text).is_empty());
+    }
+});
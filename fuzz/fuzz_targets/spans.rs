@@ -0,0 +1,27 @@
+//! Snapped spans never split a character and splice without panicking
+
+#![no_main]
+
+use arbitrary::Arbitrary;
+use cats::editing::span::Span;
+use libfuzzer_sys::fuzz_target;
+
+#[derive(Debug, Arbitrary)]
+struct Input {
+    text: String,
+    start: usize,
+    end: usize,
+    replacement: String,
+}
+
+fuzz_target!(|input: Input| {
+    let span = Span::snap(&input.text, input.start, input.end);
+    assert!(input.text.is_char_boundary(span.start()));
+    assert!(input.text.is_char_boundary(span.end()));
+    assert_eq!(span.slice(&input.text).len(), span.len());
+
+    let mut edited = input.text.clone();
+    assert!(span.replace(&mut edited, &input.replacement));
+    assert_eq!(&edited[..span.start()], &input.text[..span.start()]);
+    assert!(edited.ends_with(&input.text[span.end()..]));
+});
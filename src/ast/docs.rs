@@ -0,0 +1,542 @@
+//! Documentation attached to definitions
+//!
+//! Each language keeps its docs in a different place: Rust in `///` and `/** */`
+//! comments above the item (past its attributes), Python in the docstring that opens a
+//! function or class body (or follows a module-level assignment), JavaScript and
+//! TypeScript in the JSDoc block above the declaration or its `export`, and Go in the
+//! comment lines directly above the declaration. Plain comments count as docs where
+//! the language has no dedicated syntax, as long as no blank line separates them from
+//! the definition.
+
+use super::goto_definition::split_symbol;
+use super::inspect_type::one_line;
+use super::{definition_nodes, is_test_path, parse, source_files, Definition, Language};
+use crate::core::{DangerLevel, Tool, ToolArgs, ToolCategory, ToolError, ToolResult};
+use crate::state::ToolState;
+use crate::utils::suggest::path_not_found;
+use anyhow::Result;
+use serde::Serialize;
+use serde_json::{json, Value};
+use std::path::{Path, PathBuf};
+use std::sync::{Arc, Mutex};
+use tree_sitter::Node;
+
+/// Definitions shown in full; the rest are only counted
+const MAX_MATCHES: usize = 5;
+
+/// Longest signature kept, in characters
+const MAX_SIGNATURE_CHARS: usize = 300;
+
+fn text<'a>(node: Node, source: &'a str) -> &'a str {
+    node.utf8_text(source.as_bytes()).unwrap_or("")
+}
+
+/// Outer doc comments (`///` and `/** */`) directly above a Rust item, skipping attributes
+fn rust_doc_comment(node: Node, source: &str) -> Option<String> {
+    let mut lines = Vec::new();
+    let mut current = node.prev_sibling();
+    while let Some(sibling) = current {
+        match sibling.kind() {
+            "attribute_item" => {}
+            "line_comment" => {
+                let comment = text(sibling, source);
+                match comment.strip_prefix("///") {
+                    Some(doc) if !doc.starts_with('/') => {
+                        lines.push(doc.strip_prefix(' ').unwrap_or(doc).trim_end().to_string())
+                    }
+                    _ => break,
+                }
+            }
+            "block_comment" => {
+                let comment = text(sibling, source);
+                match comment.strip_prefix("/**") {
+                    Some(doc) => {
+                        let doc = doc.trim_end_matches("*/");
+                        for line in doc.lines().rev() {
+                            let line = line.trim().trim_start_matches('*').trim();
+                            lines.push(line.to_string());
+                        }
+                    }
+                    None => break,
+                }
+            }
+            _ => break,
+        }
+        current = sibling.prev_sibling();
+    }
+    lines.reverse();
+    let docs = lines.join("\n").trim().to_string();
+    (!docs.is_empty()).then_some(docs)
+}
+
+/// The text of a comment without its markers: `//`, `#`, `/* */` or a JSDoc `/** */`
+fn comment_text(comment: &str) -> String {
+    if let Some(block) = comment.strip_prefix("/*") {
+        let block = block.strip_prefix('*').unwrap_or(block);
+        return block
+            .trim_end_matches("*/")
+            .lines()
+            .map(|line| {
+                let line = line.trim();
+                let line = line.strip_prefix('*').unwrap_or(line);
+                line.strip_prefix(' ').unwrap_or(line).trim_end()
+            })
+            .collect::<Vec<_>>()
+            .join("\n");
+    }
+    let line = comment
+        .strip_prefix("//")
+        .or_else(|| comment.strip_prefix('#'))
+        .unwrap_or(comment);
+    line.strip_prefix(' ')
+        .unwrap_or(line)
+        .trim_end()
+        .to_string()
+}
+
+/// The comments directly above `node`, with no blank line or code in between
+///
+/// A comment that ends a line of code belongs to that code and stops the search.
+fn leading_comments(node: Node, source: &str) -> Option<String> {
+    let mut comments = Vec::new();
+    let mut next_row = node.start_position().row;
+    let mut current = node.prev_sibling();
+    while let Some(sibling) = current {
+        if sibling.kind() != "comment" || sibling.end_position().row + 1 < next_row {
+            break;
+        }
+        let trailing = sibling
+            .prev_sibling()
+            .is_some_and(|before| before.end_position().row == sibling.start_position().row);
+        if trailing {
+            break;
+        }
+        comments.push(comment_text(text(sibling, source)));
+        next_row = sibling.start_position().row;
+        current = sibling.prev_sibling();
+    }
+    comments.reverse();
+    let docs = comments.join("\n").trim().to_string();
+    (!docs.is_empty()).then_some(docs)
+}
+
+/// Python's `inspect.cleandoc`: strip quotes, then the common indentation of every line
+/// after the first, and surrounding blank lines
+fn clean_docstring(literal: &str) -> String {
+    let body = literal.trim_start_matches(|c: char| "rRuUbBfF".contains(c));
+    let quote = ["\"\"\"", "'''", "\"", "'"]
+        .into_iter()
+        .find(|q| body.starts_with(q))
+        .unwrap_or("");
+    let body = body
+        .strip_prefix(quote)
+        .and_then(|b| b.strip_suffix(quote))
+        .unwrap_or(body);
+    let mut lines: Vec<&str> = body.lines().collect();
+    let indent = lines
+        .iter()
+        .skip(1)
+        .filter(|line| !line.trim().is_empty())
+        .map(|line| line.len() - line.trim_start().len())
+        .min()
+        .unwrap_or(0);
+    if let Some(first) = lines.first_mut() {
+        *first = first.trim();
+    }
+    let cleaned: Vec<&str> = lines
+        .iter()
+        .enumerate()
+        .map(|(i, line)| {
+            if i == 0 {
+                line.trim_end()
+            } else {
+                line.get(indent..).unwrap_or("").trim_end()
+            }
+        })
+        .collect();
+    cleaned.join("\n").trim_matches('\n').to_string()
+}
+
+/// The string literal of an `expression_statement` made of just a string
+fn docstring_of(statement: Option<Node>, source: &str) -> Option<String> {
+    let statement = statement.filter(|s| s.kind() == "expression_statement")?;
+    let string = statement.named_child(0).filter(|s| s.kind() == "string")?;
+    let docs = clean_docstring(text(string, source));
+    (!docs.is_empty()).then_some(docs)
+}
+
+fn python_doc_comment(node: Node, source: &str) -> Option<String> {
+    if node.kind() == "assignment" {
+        // An attribute docstring on the next statement, else `#` comments above
+        let statement = node.parent()?;
+        return docstring_of(statement.next_named_sibling(), source)
+            .or_else(|| leading_comments(statement, source));
+    }
+    let docstring = node
+        .child_by_field_name("body")
+        .and_then(|body| docstring_of(body.named_child(0), source));
+    let anchor = node
+        .parent()
+        .filter(|p| p.kind() == "decorated_definition")
+        .unwrap_or(node);
+    docstring.or_else(|| leading_comments(anchor, source))
+}
+
+fn script_doc_comment(node: Node, source: &str) -> Option<String> {
+    // `const f = () => {}` is documented above its declaration statement
+    let mut anchor = node;
+    if anchor.kind() == "variable_declarator" {
+        anchor = anchor.parent().unwrap_or(anchor);
+    }
+    if let Some(export) = anchor.parent().filter(|p| p.kind() == "export_statement") {
+        anchor = export;
+    }
+    leading_comments(anchor, source)
+}
+
+fn go_doc_comment(node: Node, source: &str) -> Option<String> {
+    // A spec in a `type (...)` group has its own comment; a lone spec uses the declaration's
+    leading_comments(node, source).or_else(|| {
+        let declaration = node
+            .parent()
+            .filter(|p| p.kind().ends_with("_declaration"))?;
+        let first_spec = declaration
+            .named_child(0)
+            .is_some_and(|first| first == node);
+        first_spec
+            .then(|| leading_comments(declaration, source))
+            .flatten()
+    })
+}
+
+/// The documentation of the definition at `node`, with comment markers and docstring
+/// quotes removed
+pub fn doc_comment(language: Language, node: Node, source: &str) -> Option<String> {
+    match language {
+        Language::Rust => rust_doc_comment(node, source),
+        Language::Python => python_doc_comment(node, source),
+        Language::JavaScript | Language::TypeScript | Language::Tsx => {
+            script_doc_comment(node, source)
+        }
+        Language::Go => go_doc_comment(node, source),
+    }
+}
+
+/// The declaration of `node` on one line, up to its body
+pub(super) fn signature(node: Node, source: &str) -> String {
+    let body = node.child_by_field_name("body").or_else(|| {
+        node.child_by_field_name("value")
+            .and_then(|value| value.child_by_field_name("body"))
+    });
+    let end = body.map_or_else(
+        || {
+            let start = node.start_byte();
+            start
+                + source[start..node.end_byte()]
+                    .find('\n')
+                    .unwrap_or(node.end_byte() - start)
+        },
+        |body| body.start_byte(),
+    );
+    let signature = one_line(source[node.start_byte()..end].trim_end());
+    let signature = signature
+        .trim_end_matches([' ', '{', ':', '=', '>'])
+        .trim_end();
+    match signature.char_indices().nth(MAX_SIGNATURE_CHARS) {
+        Some((cut, _)) => format!("{}...", &signature[..cut]),
+        None => signature.to_string(),
+    }
+}
+
+/// A definition of the requested symbol with its documentation
+#[derive(Debug, Clone, Serialize)]
+pub struct SymbolDocs {
+    pub path: PathBuf,
+    #[serde(flatten)]
+    pub definition: Definition,
+    pub signature: String,
+    pub docs: Option<String>,
+}
+
+/// Every definition named `name` in `source`, with its documentation
+pub fn symbol_docs(language: Language, path: &Path, source: &str, name: &str) -> Vec<SymbolDocs> {
+    let Some(tree) = parse(language, source) else {
+        return Vec::new();
+    };
+    definition_nodes(language, &tree, source)
+        .into_iter()
+        .filter(|(definition, _)| definition.name == name)
+        .map(|(definition, node)| SymbolDocs {
+            path: path.to_path_buf(),
+            definition,
+            signature: signature(node, source),
+            docs: doc_comment(language, node, source),
+        })
+        .collect()
+}
+
+/// Tool returning the doc comment or docstring of a function, type or class
+pub struct GetDocsTool {
+    name: String,
+}
+
+impl GetDocsTool {
+    pub fn new() -> Self {
+        Self {
+            name: "get_docs".to_string(),
+        }
+    }
+
+    fn parse_params(args: &ToolArgs) -> Result<(String, Option<String>), ToolError> {
+        let symbol = args
+            .get_named_arg("symbol")
+            .or_else(|| args.get_arg(0))
+            .map(|s| s.trim())
+            .filter(|s| !s.is_empty())
+            .ok_or_else(|| ToolError::InvalidArgs {
+                message: "Usage: get_docs <symbol> [path]".to_string(),
+            })?;
+        let path = match args.get_named_arg("symbol") {
+            Some(_) => args.get_named_arg("path").or_else(|| args.get_arg(0)),
+            None => args.get_named_arg("path").or_else(|| args.get_arg(1)),
+        };
+        Ok((symbol.to_string(), path.cloned()))
+    }
+
+    fn describe(docs: &SymbolDocs, root: &Path) -> String {
+        let definition = &docs.definition;
+        let name = match &definition.container {
+            Some(container) => format!("{}::{}", container, definition.name),
+            None => definition.name.clone(),
+        };
+        format!(
+            "{}:{}: {} {}\n  {}\n\n{}",
+            docs.path.strip_prefix(root).unwrap_or(&docs.path).display(),
+            definition.line,
+            definition.kind,
+            name,
+            docs.signature,
+            docs.docs.as_deref().unwrap_or("(no documentation)")
+        )
+    }
+}
+
+impl Default for GetDocsTool {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl Tool for GetDocsTool {
+    fn name(&self) -> &str {
+        &self.name
+    }
+
+    fn description(&self) -> &str {
+        "Show the doc comment or docstring of a function, method, type or class with its \
+         signature, without opening the file; qualify the symbol (`Type::method`, \
+         `module.func`) or pass a file or directory to narrow the search"
+    }
+
+    fn signature(&self) -> &str {
+        "get_docs <symbol> [path]"
+    }
+
+    fn category(&self) -> ToolCategory {
+        ToolCategory::Navigation
+    }
+
+    fn danger_level(&self) -> DangerLevel {
+        DangerLevel::ReadOnly
+    }
+
+    fn validate_args(&self, args: &ToolArgs) -> Result<(), ToolError> {
+        Self::parse_params(args).map(|_| ())
+    }
+
+    fn execute(&mut self, args: &ToolArgs, state: &Arc<Mutex<ToolState>>) -> Result<ToolResult> {
+        let (symbol, path) = Self::parse_params(args)?;
+        let (qualifier, name) = split_symbol(&symbol);
+        let root = state
+            .lock()
+            .map_err(|e| anyhow::anyhow!("Failed to lock state: {}", e))?
+            .working_directory
+            .clone();
+        let scope = path.map_or_else(|| root.clone(), |path| root.join(path));
+        let files = if scope.is_file() {
+            let language = Language::from_path(&scope);
+            match (language, std::fs::read_to_string(&scope)) {
+                (Some(language), Ok(content)) => vec![(scope.clone(), language, content)],
+                _ => {
+                    return Ok(ToolResult::error(format!(
+                        "{} is not a Rust, Python, JavaScript, TypeScript or Go source file",
+                        scope.display()
+                    )))
+                }
+            }
+        } else if scope.is_dir() {
+            source_files(&scope, name)
+        } else {
+            return Ok(path_not_found(
+                format!("Path not found: {}", scope.display()),
+                &scope,
+            ));
+        };
+
+        let mut found: Vec<SymbolDocs> = files
+            .iter()
+            .flat_map(|(path, language, content)| symbol_docs(*language, path, content, name))
+            .collect();
+        // Qualified matches first, then documented ones, then non-test files
+        found.sort_by_key(|docs| {
+            let qualified = qualifier.is_none_or(|q| {
+                docs.definition.container.as_deref() == Some(q)
+                    || docs.path.file_stem().is_some_and(|s| s == q)
+            });
+            (
+                !qualified,
+                docs.docs.is_none(),
+                is_test_path(&docs.path),
+                docs.path.clone(),
+                docs.definition.line,
+            )
+        });
+
+        if found.is_empty() {
+            return Ok(ToolResult::error(format!(
+                "No definition of '{}' found under {}",
+                symbol,
+                scope.display()
+            )));
+        }
+        let shown = &found[..found.len().min(MAX_MATCHES)];
+        let mut message = shown
+            .iter()
+            .map(|docs| Self::describe(docs, &root))
+            .collect::<Vec<_>>()
+            .join("\n\n---\n\n");
+        if found.len() > MAX_MATCHES {
+            message.push_str(&format!(
+                "\n\n... {} more definition(s); qualify the symbol or pass a path to narrow it",
+                found.len() - MAX_MATCHES
+            ));
+        }
+
+        let matches: Vec<Value> = shown
+            .iter()
+            .map(|docs| {
+                let mut value = json!(docs);
+                value["path"] = json!(docs.path.strip_prefix(&root).unwrap_or(&docs.path));
+                value
+            })
+            .collect();
+        Ok(ToolResult::success_with_data(
+            message,
+            json!({
+                "symbol": symbol,
+                "matches": matches,
+                "total": found.len(),
+            }),
+        ))
+    }
+
+    fn get_parameters_schema(&self) -> Value {
+        json!({
+            "type": "object",
+            "properties": {
+                "symbol": {
+                    "type": "string",
+                    "description": "Name of the function, method, type or class; qualify it (`Type::method`, `module.func`) to disambiguate"
+                },
+                "path": {
+                    "type": "string",
+                    "description": "File or directory to search (default: the working directory)"
+                }
+            },
+            "required": ["symbol"]
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::fs;
+    use tempfile::TempDir;
+
+    fn docs_of(language: Language, source: &str, name: &str) -> Vec<Option<String>> {
+        symbol_docs(language, Path::new("x"), source, name)
+            .into_iter()
+            .map(|d| d.docs)
+            .collect()
+    }
+
+    #[test]
+    fn test_doc_comment_per_language() {
+        let rust = "/// Adds two numbers.\n///\n/// Wraps on overflow.\n#[inline]\npub fn add(a: u8, b: u8) -> u8 { a.wrapping_add(b) }\n";
+        assert_eq!(
+            docs_of(Language::Rust, rust, "add"),
+            vec![Some("Adds two numbers.\n\nWraps on overflow.".to_string())]
+        );
+
+        let python = "# helper, not docs\n\nclass Shape:\n    \"\"\"A shape.\n\n    Has an area.\n    \"\"\"\n\n    @property\n    def area(self):\n        return 0\n\n# Default scale\nSCALE = 2\n";
+        assert_eq!(
+            docs_of(Language::Python, python, "Shape"),
+            vec![Some("A shape.\n\nHas an area.".to_string())]
+        );
+        assert_eq!(docs_of(Language::Python, python, "area"), vec![None]);
+        assert_eq!(
+            docs_of(Language::Python, python, "SCALE"),
+            vec![Some("Default scale".to_string())]
+        );
+
+        let ts = "/**\n * Loads a user.\n * @param id the user id\n */\nexport const load = async (id: number) => {};\n\nlet x = 1; // trailing\nfunction plain() {}\n";
+        assert_eq!(
+            docs_of(Language::TypeScript, ts, "load"),
+            vec![Some("Loads a user.\n@param id the user id".to_string())]
+        );
+        assert_eq!(docs_of(Language::TypeScript, ts, "plain"), vec![None]);
+
+        let go = "package p\n\n// Server serves.\ntype Server struct{}\n\n// Run starts it.\n// It blocks.\nfunc (s *Server) Run() {}\n";
+        assert_eq!(
+            docs_of(Language::Go, go, "Server"),
+            vec![Some("Server serves.".to_string())]
+        );
+        assert_eq!(
+            docs_of(Language::Go, go, "Run"),
+            vec![Some("Run starts it.\nIt blocks.".to_string())]
+        );
+    }
+
+    #[test]
+    fn test_get_docs_tool() {
+        let temp_dir = TempDir::new().unwrap();
+        fs::create_dir_all(temp_dir.path().join("src")).unwrap();
+        fs::write(
+            temp_dir.path().join("src/lib.rs"),
+            "pub struct Point;\n\nimpl Point {\n    /// Origin point.\n    pub fn new() -> Self {\n        Point\n    }\n}\n\npub fn new() {}\n",
+        )
+        .unwrap();
+        let mut tool_state = ToolState::new();
+        tool_state.working_directory = temp_dir.path().to_path_buf();
+        let state = Arc::new(Mutex::new(tool_state));
+        let mut tool = GetDocsTool::new();
+
+        let result = tool
+            .execute(&ToolArgs::from_args(&["Point::new"]), &state)
+            .unwrap();
+        assert!(result.success, "{}", result.message);
+        assert!(result.message.starts_with(
+            "src/lib.rs:5: function Point::new\n  pub fn new() -> Self\n\nOrigin point."
+        ));
+        let data = result.data.unwrap();
+        assert_eq!(data["total"], 2);
+        assert_eq!(data["matches"][0]["path"], "src/lib.rs");
+        assert_eq!(data["matches"][1]["docs"], Value::Null);
+
+        let result = tool
+            .execute(&ToolArgs::from_args(&["missing", "src/lib.rs"]), &state)
+            .unwrap();
+        assert!(!result.success);
+    }
+}
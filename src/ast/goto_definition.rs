@@ -0,0 +1,353 @@
+//! Heuristic go-to-definition without a language server
+
+use super::imports::imported_files;
+use super::{definitions, is_test_path, source_files, Definition, Language};
+use crate::core::{DangerLevel, Tool, ToolArgs, ToolCategory, ToolError, ToolResult};
+use crate::file_navigation::DEFAULT_WINDOW_SIZE;
+use crate::state::ToolState;
+use anyhow::{Context, Result};
+use serde_json::{json, Value};
+use std::collections::HashSet;
+use std::path::{Path, PathBuf};
+use std::sync::{Arc, Mutex};
+
+/// Maximum number of alternatives listed when a symbol is ambiguous
+const MAX_ALTERNATIVES: usize = 10;
+
+/// A definition site of the requested symbol
+#[derive(Debug, Clone)]
+struct Candidate {
+    path: PathBuf,
+    definition: Definition,
+    /// Lower is more likely: qualifier mismatch, distance in the import graph, test file
+    rank: (bool, u8, bool),
+}
+
+impl Candidate {
+    fn to_json(&self, root: &Path) -> Value {
+        json!({
+            "path": self.path.strip_prefix(root).unwrap_or(&self.path),
+            "line": self.definition.line,
+            "kind": self.definition.kind,
+            "container": self.definition.container,
+        })
+    }
+
+    fn describe(&self, root: &Path) -> String {
+        let container = self
+            .definition
+            .container
+            .as_ref()
+            .map(|c| format!(" in {}", c))
+            .unwrap_or_default();
+        format!(
+            "{}:{}: {} {}{}",
+            self.path.strip_prefix(root).unwrap_or(&self.path).display(),
+            self.definition.line,
+            self.definition.kind,
+            self.definition.name,
+            container
+        )
+    }
+}
+
+/// Split `Type::method`, `module.func` or `pkg.Func` into its qualifier and name
+pub(super) fn split_symbol(symbol: &str) -> (Option<&str>, &str) {
+    let symbol = symbol.trim();
+    match symbol.rfind([':', '.']) {
+        Some(index) => {
+            let qualifier = symbol[..index].trim_end_matches([':', '.']);
+            let qualifier = qualifier
+                .rsplit([':', '.'])
+                .next()
+                .filter(|q| !q.is_empty());
+            (qualifier, &symbol[index + 1..])
+        }
+        None => (None, symbol),
+    }
+}
+
+/// Tool jumping the window to the most likely definition of a symbol using tree-sitter
+pub struct GotoDefinitionTool {
+    name: String,
+}
+
+impl GotoDefinitionTool {
+    pub fn new() -> Self {
+        Self {
+            name: "goto_definition".to_string(),
+        }
+    }
+
+    fn parse_params(args: &ToolArgs) -> Result<(String, Option<String>), ToolError> {
+        let obj = if let Some(json_str) = args.get_named_arg("json") {
+            serde_json::from_str(json_str).map_err(ToolError::Json)?
+        } else if !args.named_args.is_empty() {
+            serde_json::to_value(&args.named_args).map_err(ToolError::Json)?
+        } else {
+            json!({"symbol": args.get_arg(0), "path": args.get_arg(1)})
+        };
+        let symbol = obj
+            .get("symbol")
+            .and_then(|s| s.as_str())
+            .map(str::trim)
+            .filter(|s| !s.is_empty())
+            .ok_or_else(|| ToolError::InvalidArgs {
+                message: "Usage: goto_definition <symbol> [path]".to_string(),
+            })?;
+        let path = obj.get("path").and_then(|p| p.as_str()).map(String::from);
+        Ok((symbol.to_string(), path))
+    }
+}
+
+impl Default for GotoDefinitionTool {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl Tool for GotoDefinitionTool {
+    fn name(&self) -> &str {
+        &self.name
+    }
+
+    fn description(&self) -> &str {
+        "Jump the window to the most likely definition of <symbol> without a language server, \
+         preferring definitions in the current file and the files it imports; lists the \
+         alternatives when the symbol is ambiguous"
+    }
+
+    fn signature(&self) -> &str {
+        "goto_definition <symbol> [path]"
+    }
+
+    fn category(&self) -> ToolCategory {
+        ToolCategory::Navigation
+    }
+
+    fn danger_level(&self) -> DangerLevel {
+        DangerLevel::ReadOnly
+    }
+
+    fn validate_args(&self, args: &ToolArgs) -> Result<(), ToolError> {
+        Self::parse_params(args).map(|_| ())
+    }
+
+    fn execute(&mut self, args: &ToolArgs, state: &Arc<Mutex<ToolState>>) -> Result<ToolResult> {
+        let (symbol, path) = Self::parse_params(args)?;
+        let (qualifier, name) = split_symbol(&symbol);
+        let (root, origin) = {
+            let state_guard = state
+                .lock()
+                .map_err(|e| anyhow::anyhow!("Failed to lock state: {}", e))?;
+            let root = state_guard.working_directory.clone();
+            let origin = match path {
+                Some(path) if Path::new(&path).is_absolute() => Some(PathBuf::from(path)),
+                Some(path) => Some(root.join(path)),
+                None => state_guard.current_file.clone(),
+            };
+            (root, origin)
+        };
+
+        // The import graph of the file the lookup starts from
+        let origin_source = origin
+            .as_ref()
+            .and_then(|o| std::fs::read_to_string(o).ok());
+        let (origin_dir, imported): (Option<PathBuf>, HashSet<PathBuf>) =
+            match (&origin, &origin_source) {
+                (Some(origin), Some(source)) => (
+                    origin.parent().map(Path::to_path_buf),
+                    Language::from_path(origin)
+                        .map(|language| imported_files(language, origin, source, &root))
+                        .unwrap_or_default()
+                        .into_iter()
+                        .collect(),
+                ),
+                _ => (None, HashSet::new()),
+            };
+
+        let mut candidates: Vec<Candidate> = source_files(&root, name)
+            .into_iter()
+            .flat_map(|(path, language, content)| {
+                definitions(language, &content)
+                    .into_iter()
+                    .filter(|d| d.name == name)
+                    .map(|definition| {
+                        let qualified = match qualifier {
+                            None => true,
+                            Some(q) => {
+                                definition.container.as_deref() == Some(q)
+                                    || path.file_stem().is_some_and(|s| s == q)
+                                    || path
+                                        .parent()
+                                        .and_then(Path::file_name)
+                                        .is_some_and(|d| d == q)
+                            }
+                        };
+                        let distance = if origin.as_ref() == Some(&path) {
+                            0
+                        } else if imported.contains(&path) {
+                            1
+                        } else if origin_dir.as_deref() == path.parent() {
+                            2
+                        } else {
+                            3
+                        };
+                        Candidate {
+                            rank: (!qualified, distance, is_test_path(&path)),
+                            path: path.clone(),
+                            definition,
+                        }
+                    })
+                    .collect::<Vec<_>>()
+            })
+            .collect();
+        candidates.sort_by(|a, b| {
+            (a.rank, &a.path, a.definition.line).cmp(&(b.rank, &b.path, b.definition.line))
+        });
+
+        let Some(best) = candidates.first().cloned() else {
+            return Ok(ToolResult::error(format!(
+                "No definition of '{}' found under {}",
+                symbol,
+                root.display()
+            )));
+        };
+
+        let content = std::fs::read_to_string(&best.path).context("Failed to read file")?;
+        let lines: Vec<String> = content.lines().map(|s| s.to_string()).collect();
+        let display = {
+            let mut state_guard = state
+                .lock()
+                .map_err(|e| anyhow::anyhow!("Failed to lock state: {}", e))?;
+            state_guard.open_file(best.path.clone(), lines, DEFAULT_WINDOW_SIZE)?;
+            let file_state = state_guard
+                .get_current_file_state_mut()
+                .ok_or_else(|| anyhow::anyhow!("Failed to get file state"))?;
+            file_state.goto_line(best.definition.line);
+            let display = file_state.get_window_with_line_numbers();
+            state_guard.push_history(format!(
+                "Jumped to definition of {} at {}:{}",
+                symbol,
+                best.path.display(),
+                best.definition.line
+            ));
+            display
+        };
+
+        let alternatives: Vec<&Candidate> =
+            candidates.iter().skip(1).take(MAX_ALTERNATIVES).collect();
+        let mut message = format!("Definition of {}: {}", symbol, best.describe(&root));
+        if !alternatives.is_empty() {
+            message.push_str(&format!(
+                "\n\nAmbiguous: {} other definition(s) found:\n{}",
+                candidates.len() - 1,
+                alternatives
+                    .iter()
+                    .map(|c| format!("  {}", c.describe(&root)))
+                    .collect::<Vec<_>>()
+                    .join("\n")
+            ));
+        }
+        message.push_str(&format!("\n\n{}", display.join("\n")));
+
+        Ok(ToolResult::success_with_data(
+            message,
+            json!({
+                "symbol": symbol,
+                "definition": best.to_json(&root),
+                "ambiguous": !alternatives.is_empty(),
+                "alternatives": alternatives.iter().map(|c| c.to_json(&root)).collect::<Vec<_>>(),
+                "window": display
+            }),
+        ))
+    }
+
+    fn get_parameters_schema(&self) -> Value {
+        json!({
+            "type": "object",
+            "properties": {
+                "symbol": {
+                    "type": "string",
+                    "description": "Name to look up; qualify it (`Type::method`, `module.func`) to disambiguate"
+                },
+                "path": {
+                    "type": "string",
+                    "description": "File the symbol is used in (defaults to the currently open file); its imports rank first"
+                }
+            },
+            "required": ["symbol"]
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::fs;
+    use tempfile::TempDir;
+
+    fn write(root: &Path, path: &str, content: &str) {
+        let path = root.join(path);
+        fs::create_dir_all(path.parent().unwrap()).unwrap();
+        fs::write(path, content).unwrap();
+    }
+
+    #[test]
+    fn test_split_symbol() {
+        assert_eq!(split_symbol("Point::new"), (Some("Point"), "new"));
+        assert_eq!(split_symbol("models.User"), (Some("models"), "User"));
+        assert_eq!(split_symbol("a::b::c"), (Some("b"), "c"));
+        assert_eq!(split_symbol("main"), (None, "main"));
+    }
+
+    #[test]
+    fn test_goto_definition_prefers_imported_file() {
+        let dir = TempDir::new().unwrap();
+        let root = dir.path();
+        write(
+            root,
+            "app/main.py",
+            "from .shapes import area\n\nprint(area(2))\n",
+        );
+        write(
+            root,
+            "app/shapes.py",
+            "import math\n\n\ndef area(r):\n    return math.pi * r * r\n",
+        );
+        write(
+            root,
+            "app/legacy.py",
+            "def area(r):\n    return 3 * r * r\n",
+        );
+        write(root, "tests/test_area.py", "def area():\n    pass\n");
+
+        let state = Arc::new(Mutex::new(ToolState::new()));
+        state.lock().unwrap().working_directory = root.to_path_buf();
+        let mut tool = GotoDefinitionTool::new();
+
+        let args = ToolArgs::from_args(&["area", "app/main.py"]);
+        let result = tool.execute(&args, &state).unwrap();
+        assert!(result.success, "{}", result.message);
+        let data = result.data.unwrap();
+        assert_eq!(data["definition"]["path"], "app/shapes.py");
+        assert_eq!(data["definition"]["line"], 4);
+        assert_eq!(data["ambiguous"], true);
+        assert_eq!(data["alternatives"][0]["path"], "app/legacy.py");
+        // Test helpers rank last
+        assert_eq!(data["alternatives"][1]["path"], "tests/test_area.py");
+        assert!(result.message.contains("def area(r):"));
+
+        let guard = state.lock().unwrap();
+        assert_eq!(guard.current_file, Some(root.join("app/shapes.py")));
+        drop(guard);
+
+        // A qualifier picks the matching module even without an import
+        let args = ToolArgs::from_args(&["legacy.area"]);
+        let data = tool.execute(&args, &state).unwrap().data.unwrap();
+        assert_eq!(data["definition"]["path"], "app/legacy.py");
+
+        let args = ToolArgs::from_args(&["missing_symbol"]);
+        assert!(!tool.execute(&args, &state).unwrap().success);
+    }
+}
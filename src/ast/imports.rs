@@ -0,0 +1,285 @@
+//! Import graph approximation
+//!
+//! Resolves the project files a source file imports, so definition lookups can prefer
+//! the candidate that is actually in scope. Resolution is heuristic: only relative and
+//! crate-local imports are followed, and anything that does not map to a file on disk
+//! (standard library, third-party packages) is ignored.
+
+use super::Language;
+use crate::editing::refactor::CrateLayout;
+use regex::Regex;
+use std::fs;
+use std::path::{Path, PathBuf};
+use std::sync::OnceLock;
+
+/// Project files imported by `file`, deduplicated
+pub fn imported_files(language: Language, file: &Path, source: &str, root: &Path) -> Vec<PathBuf> {
+    let mut files = match language {
+        Language::Rust => rust_imports(file, source),
+        Language::Python => python_imports(file, source, root),
+        Language::JavaScript | Language::TypeScript | Language::Tsx => script_imports(file, source),
+        Language::Go => go_imports(file, source, root),
+    };
+    let mut seen = std::collections::HashSet::new();
+    files.retain(|f| f != file && seen.insert(f.clone()));
+    files
+}
+
+fn regex(cell: &'static OnceLock<Regex>, pattern: &str) -> &'static Regex {
+    cell.get_or_init(|| Regex::new(pattern).expect("valid import regex"))
+}
+
+/// `use crate::a::b::Item;`, `use super::x::{..}` and `mod child;`
+fn rust_imports(file: &Path, source: &str) -> Vec<PathBuf> {
+    static USE: OnceLock<Regex> = OnceLock::new();
+    static MOD: OnceLock<Regex> = OnceLock::new();
+    let Some(layout) = CrateLayout::find(file) else {
+        return Vec::new();
+    };
+    let Some(current) = layout.module_segments(file) else {
+        return Vec::new();
+    };
+
+    let mut files = Vec::new();
+    let uses = regex(
+        &USE,
+        r"(?m)^\s*(?:pub(?:\([^)]*\))?\s+)?use\s+((?:crate|super|self)(?:::\w+)*)",
+    );
+    for caps in uses.captures_iter(source) {
+        let mut segments = match rust_module_path(&current, &caps[1]) {
+            Some(segments) => segments,
+            None => continue,
+        };
+        // `use crate::a::b::Item` may name a module or an item; try the longest module
+        while !segments.is_empty() {
+            if let Some(path) = layout.module_file(&segments) {
+                files.push(path);
+                break;
+            }
+            segments.pop();
+        }
+    }
+    let mods = regex(&MOD, r"(?m)^\s*(?:pub(?:\([^)]*\))?\s+)?mod\s+(\w+)\s*;");
+    for caps in mods.captures_iter(source) {
+        // `a.rs`, `a/mod.rs` and `lib.rs` all declare children of their own module
+        let mut segments = current.clone();
+        segments.push(caps[1].to_string());
+        files.extend(layout.module_file(&segments));
+    }
+    files
+}
+
+/// Absolute module segments of a `crate::`/`super::`/`self::` path
+fn rust_module_path(current: &[String], path: &str) -> Option<Vec<String>> {
+    let mut parts = path.split("::");
+    let mut segments = match parts.next()? {
+        "crate" => Vec::new(),
+        "self" => current.to_vec(),
+        "super" => {
+            let mut parent = current.to_vec();
+            parent.pop()?;
+            parent
+        }
+        _ => return None,
+    };
+    for part in parts {
+        if part == "super" {
+            segments.pop()?;
+        } else {
+            segments.push(part.to_string());
+        }
+    }
+    Some(segments)
+}
+
+/// `import a.b`, `from a.b import c` and `from .x import y`
+fn python_imports(file: &Path, source: &str, root: &Path) -> Vec<PathBuf> {
+    static IMPORT: OnceLock<Regex> = OnceLock::new();
+    let imports = regex(
+        &IMPORT,
+        r"(?m)^\s*(?:from\s+(\.*)([\w.]*)\s+import\s+([\w, ]+)|import\s+([\w.]+))",
+    );
+    let dir = file.parent().unwrap_or(root);
+
+    let mut files = Vec::new();
+    for caps in imports.captures_iter(source) {
+        if let Some(module) = caps.get(4) {
+            files.extend(python_module(root, module.as_str()));
+            continue;
+        }
+        let dots = caps.get(1).map_or(0, |m| m.as_str().len());
+        let module = caps.get(2).map_or("", |m| m.as_str());
+        let base = if dots == 0 {
+            root.to_path_buf()
+        } else {
+            let mut base = dir.to_path_buf();
+            for _ in 1..dots {
+                base.pop();
+            }
+            base
+        };
+        let package = if module.is_empty() {
+            base
+        } else {
+            base.join(module.replace('.', "/"))
+        };
+        files.extend(python_file(&package));
+        // `from pkg import submodule` imports a module rather than a name
+        for name in caps[3].split(',') {
+            let name = name.split_whitespace().next().unwrap_or("");
+            if !name.is_empty() {
+                files.extend(python_file(&package.join(name)));
+            }
+        }
+    }
+    files
+}
+
+fn python_module(root: &Path, module: &str) -> Option<PathBuf> {
+    python_file(&root.join(module.replace('.', "/")))
+}
+
+/// `x.py` or `x/__init__.py`
+fn python_file(base: &Path) -> Option<PathBuf> {
+    [base.with_extension("py"), base.join("__init__.py")]
+        .into_iter()
+        .find(|p| p.is_file())
+}
+
+/// Relative `import .. from './x'`, `export .. from './x'` and `require('./x')`
+fn script_imports(file: &Path, source: &str) -> Vec<PathBuf> {
+    static IMPORT: OnceLock<Regex> = OnceLock::new();
+    let imports = regex(
+        &IMPORT,
+        r#"(?:\bfrom\s*|\bimport\s*\(?\s*|\brequire\s*\(\s*)['"](\.{1,2}/[^'"]+)['"]"#,
+    );
+    let dir = file.parent().unwrap_or(Path::new("."));
+    imports
+        .captures_iter(source)
+        .filter_map(|caps| {
+            let base = dir.join(&caps[1]);
+            if base.is_file() {
+                return Some(base);
+            }
+            const EXTENSIONS: [&str; 6] = ["ts", "tsx", "js", "jsx", "mjs", "cjs"];
+            EXTENSIONS
+                .iter()
+                .map(|ext| PathBuf::from(format!("{}.{}", base.display(), ext)))
+                .chain(
+                    EXTENSIONS
+                        .iter()
+                        .map(|ext| base.join(format!("index.{}", ext))),
+                )
+                .find(|p| p.is_file())
+        })
+        .collect()
+}
+
+/// Files of the same package and of imported packages inside this module
+fn go_imports(file: &Path, source: &str, root: &Path) -> Vec<PathBuf> {
+    static IMPORT: OnceLock<Regex> = OnceLock::new();
+    static MODULE: OnceLock<Regex> = OnceLock::new();
+    let mut files = file.parent().map(go_package_files).unwrap_or_default();
+
+    let module = fs::read_to_string(root.join("go.mod"))
+        .ok()
+        .and_then(|go_mod| {
+            regex(&MODULE, r"(?m)^module\s+(\S+)")
+                .captures(&go_mod)
+                .map(|caps| caps[1].to_string())
+        });
+    let Some(module) = module else {
+        return files;
+    };
+    let imports = regex(&IMPORT, r#""([^"\s]+)""#);
+    // Only the import block(s) are scanned, not every string literal in the file
+    let header_end = source
+        .find("\nfunc ")
+        .or_else(|| source.find("\ntype "))
+        .unwrap_or(source.len());
+    for caps in imports.captures_iter(&source[..header_end]) {
+        if let Some(relative) = caps[1].strip_prefix(&module) {
+            files.extend(go_package_files(
+                &root.join(relative.trim_start_matches('/')),
+            ));
+        }
+    }
+    files
+}
+
+fn go_package_files(dir: &Path) -> Vec<PathBuf> {
+    let mut files: Vec<PathBuf> = fs::read_dir(dir)
+        .into_iter()
+        .flatten()
+        .flatten()
+        .map(|entry| entry.path())
+        .filter(|p| p.extension().is_some_and(|e| e == "go"))
+        .collect();
+    files.sort();
+    files
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use tempfile::TempDir;
+
+    fn write(root: &Path, path: &str, content: &str) -> PathBuf {
+        let path = root.join(path);
+        fs::create_dir_all(path.parent().unwrap()).unwrap();
+        fs::write(&path, content).unwrap();
+        path
+    }
+
+    #[test]
+    fn test_imported_files_per_language() {
+        let dir = TempDir::new().unwrap();
+        let root = dir.path();
+
+        write(root, "Cargo.toml", "[package]\nname = \"demo\"\n");
+        let lib = write(
+            root,
+            "src/lib.rs",
+            "pub mod net;\nuse crate::util::helpers::greet;\n",
+        );
+        let helpers = write(root, "src/util/helpers.rs", "pub fn greet() {}\n");
+        write(root, "src/util/mod.rs", "pub mod helpers;\n");
+        let net = write(root, "src/net.rs", "use super::util::helpers;\n");
+        let source = fs::read_to_string(&lib).unwrap();
+        assert_eq!(
+            imported_files(Language::Rust, &lib, &source, root),
+            vec![helpers.clone(), net.clone()]
+        );
+        let source = fs::read_to_string(&net).unwrap();
+        assert_eq!(
+            imported_files(Language::Rust, &net, &source, root),
+            vec![helpers]
+        );
+
+        let app = write(
+            root,
+            "app/main.py",
+            "from .models import User\nimport app.util\n",
+        );
+        let models = write(root, "app/models.py", "class User: pass\n");
+        let util = write(root, "app/util.py", "");
+        let source = fs::read_to_string(&app).unwrap();
+        assert_eq!(
+            imported_files(Language::Python, &app, &source, root),
+            vec![models, util]
+        );
+
+        let index = write(
+            root,
+            "web/index.ts",
+            "import { api } from './api';\nconst x = require(\"./lib\");\n",
+        );
+        let api = write(root, "web/api.ts", "export function api() {}\n");
+        let lib_js = write(root, "web/lib/index.js", "");
+        let source = fs::read_to_string(&index).unwrap();
+        assert_eq!(
+            imported_files(Language::TypeScript, &index, &source, root),
+            vec![api, lib_js]
+        );
+    }
+}
@@ -0,0 +1,519 @@
+//! Shape of a Rust type: fields, variants and methods with their docs
+
+use super::docs::doc_comment;
+use super::{is_test_path, parse, source_files, Language};
+use crate::core::{DangerLevel, Tool, ToolArgs, ToolCategory, ToolError, ToolResult};
+use crate::state::ToolState;
+use anyhow::Result;
+use serde::Serialize;
+use serde_json::{json, Value};
+use std::path::{Path, PathBuf};
+use std::sync::{Arc, Mutex};
+use tree_sitter::Node;
+
+/// A struct field, or a method of a trait or impl block
+#[derive(Debug, Clone, Serialize)]
+pub struct Member {
+    pub name: String,
+    /// Field type, or the method signature up to its body
+    pub signature: String,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub docs: Option<String>,
+    pub line: usize,
+}
+
+/// An enum variant with its payload (`(u32, String)`, `{ id: u64 }` or empty)
+#[derive(Debug, Clone, Serialize)]
+pub struct Variant {
+    pub name: String,
+    pub fields: String,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub docs: Option<String>,
+    pub line: usize,
+}
+
+/// Methods from one `impl` block
+#[derive(Debug, Clone, Serialize)]
+pub struct ImplBlock {
+    /// The `impl` header, e.g. `impl<T> Display for Wrapper<T>`
+    pub header: String,
+    pub path: PathBuf,
+    pub line: usize,
+    pub methods: Vec<Member>,
+}
+
+/// A struct, enum or trait definition
+#[derive(Debug, Clone, Serialize)]
+pub struct TypeShape {
+    pub name: String,
+    /// `struct`, `enum`, `union` or `trait`
+    pub kind: String,
+    pub path: PathBuf,
+    pub line: usize,
+    /// The declaration header, e.g. `pub struct Wrapper<T: Clone>`
+    pub header: String,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub docs: Option<String>,
+    pub fields: Vec<Member>,
+    pub variants: Vec<Variant>,
+    /// Methods declared by a trait
+    pub trait_methods: Vec<Member>,
+    pub impls: Vec<ImplBlock>,
+}
+
+fn text<'a>(node: Node, source: &'a str) -> &'a str {
+    node.utf8_text(source.as_bytes()).unwrap_or("")
+}
+
+/// Collapse runs of whitespace so multi-line signatures fit on one line
+pub(super) fn one_line(text: &str) -> String {
+    text.split_whitespace()
+        .collect::<Vec<_>>()
+        .join(" ")
+        .replace("( ", "(")
+        .replace(" )", ")")
+        .replace(",)", ")")
+}
+
+/// Source text of `node` up to (not including) its `body` field
+fn header(node: Node, source: &str) -> String {
+    let end = node
+        .child_by_field_name("body")
+        .map_or(node.end_byte(), |body| body.start_byte());
+    let header = &source[node.start_byte()..end];
+    one_line(header.trim_end().trim_end_matches(';'))
+}
+
+/// The type name an `impl` block targets, without generics or references
+fn impl_target(node: Node, source: &str) -> Option<String> {
+    let ty = node.child_by_field_name("type")?;
+    let ty = text(ty, source).trim_start_matches('&').trim();
+    let ty = ty.strip_prefix("mut ").unwrap_or(ty);
+    let name = ty.split('<').next()?.rsplit("::").next()?;
+    Some(name.trim().to_string())
+}
+
+/// Every node of `root` (depth first) for which `f` returns true
+fn find_nodes<'t>(root: Node<'t>, f: &dyn Fn(Node) -> bool) -> Vec<Node<'t>> {
+    let mut found = Vec::new();
+    let mut cursor = root.walk();
+    let mut stack = vec![root];
+    while let Some(node) = stack.pop() {
+        if f(node) {
+            found.push(node);
+        }
+        let children: Vec<_> = node.children(&mut cursor).collect();
+        stack.extend(children.into_iter().rev());
+    }
+    found
+}
+
+/// Functions declared directly in a trait or impl body
+fn methods(body: Node, source: &str) -> Vec<Member> {
+    let mut cursor = body.walk();
+    body.named_children(&mut cursor)
+        .filter(|child| matches!(child.kind(), "function_item" | "function_signature_item"))
+        .filter_map(|function| {
+            Some(Member {
+                name: text(function.child_by_field_name("name")?, source).to_string(),
+                signature: header(function, source),
+                docs: doc_comment(Language::Rust, function, source),
+                line: function.start_position().row + 1,
+            })
+        })
+        .collect()
+}
+
+fn fields(body: Node, source: &str) -> Vec<Member> {
+    let mut cursor = body.walk();
+    let mut index = 0;
+    body.named_children(&mut cursor)
+        .filter_map(|field| match field.kind() {
+            "field_declaration" => {
+                let name = text(field.child_by_field_name("name")?, source).to_string();
+                let signature = one_line(text(field.child_by_field_name("type")?, source));
+                let visibility = field
+                    .named_child(0)
+                    .filter(|c| c.kind() == "visibility_modifier")
+                    .map(|v| format!("{} ", text(v, source)))
+                    .unwrap_or_default();
+                Some(Member {
+                    signature: format!("{}{}: {}", visibility, name, signature),
+                    name,
+                    docs: doc_comment(Language::Rust, field, source),
+                    line: field.start_position().row + 1,
+                })
+            }
+            // Tuple struct fields are named by position; their types are the other children
+            "visibility_modifier" | "attribute_item" | "line_comment" | "block_comment" => None,
+            _ => {
+                let name = index.to_string();
+                index += 1;
+                Some(Member {
+                    name,
+                    signature: one_line(text(field, source)),
+                    docs: None,
+                    line: field.start_position().row + 1,
+                })
+            }
+        })
+        .collect()
+}
+
+fn variants(body: Node, source: &str) -> Vec<Variant> {
+    let mut cursor = body.walk();
+    body.named_children(&mut cursor)
+        .filter(|child| child.kind() == "enum_variant")
+        .filter_map(|variant| {
+            let name = text(variant.child_by_field_name("name")?, source).to_string();
+            let mut fields = match variant.child_by_field_name("body") {
+                Some(body) if body.kind() == "field_declaration_list" => {
+                    format!(" {}", one_line(text(body, source)))
+                }
+                Some(body) => one_line(text(body, source)),
+                None => String::new(),
+            };
+            if let Some(value) = variant.child_by_field_name("value") {
+                fields.push_str(&format!(" = {}", text(value, source)));
+            }
+            Some(Variant {
+                name,
+                fields,
+                docs: doc_comment(Language::Rust, variant, source),
+                line: variant.start_position().row + 1,
+            })
+        })
+        .collect()
+}
+
+/// Find the Rust type `name` under `root` with the methods of every `impl` block for it
+///
+/// Non-test definitions are preferred; other definitions with the same name are
+/// returned as `(path, line)` pairs.
+pub fn inspect_type(root: &Path, name: &str) -> Option<(TypeShape, Vec<(PathBuf, usize)>)> {
+    let mut shapes = Vec::new();
+    let mut impls = Vec::new();
+    for (path, language, source) in source_files(root, name) {
+        if language != Language::Rust {
+            continue;
+        }
+        let Some(tree) = parse(language, &source) else {
+            continue;
+        };
+        let is_named = |node: Node| {
+            node.child_by_field_name("name")
+                .is_some_and(|n| text(n, &source) == name)
+        };
+        for node in find_nodes(tree.root_node(), &|n| {
+            matches!(
+                n.kind(),
+                "struct_item" | "enum_item" | "union_item" | "trait_item" | "impl_item"
+            )
+        }) {
+            if node.kind() == "impl_item" {
+                if impl_target(node, &source).as_deref() == Some(name) {
+                    if let Some(body) = node.child_by_field_name("body") {
+                        impls.push(ImplBlock {
+                            header: header(node, &source),
+                            path: path.clone(),
+                            line: node.start_position().row + 1,
+                            methods: methods(body, &source),
+                        });
+                    }
+                }
+                continue;
+            }
+            if !is_named(node) {
+                continue;
+            }
+            let body = node.child_by_field_name("body");
+            let kind = node.kind().trim_end_matches("_item").to_string();
+            shapes.push(TypeShape {
+                name: name.to_string(),
+                fields: match (kind.as_str(), body) {
+                    ("struct" | "union", Some(body)) => fields(body, &source),
+                    _ => Vec::new(),
+                },
+                variants: match (kind.as_str(), body) {
+                    ("enum", Some(body)) => variants(body, &source),
+                    _ => Vec::new(),
+                },
+                trait_methods: match (kind.as_str(), body) {
+                    ("trait", Some(body)) => methods(body, &source),
+                    _ => Vec::new(),
+                },
+                kind,
+                path: path.clone(),
+                line: node.start_position().row + 1,
+                header: header(node, &source),
+                docs: doc_comment(Language::Rust, node, &source),
+                impls: Vec::new(),
+            });
+        }
+    }
+
+    shapes.sort_by(|a, b| (is_test_path(&a.path), &a.path).cmp(&(is_test_path(&b.path), &b.path)));
+    let mut shapes = shapes.into_iter();
+    let mut shape = shapes.next()?;
+    impls.sort_by(|a, b| (&a.path, a.line).cmp(&(&b.path, b.line)));
+    shape.impls = impls;
+    Some((shape, shapes.map(|s| (s.path, s.line)).collect()))
+}
+
+fn push_member(lines: &mut Vec<String>, indent: &str, signature: &str, docs: &Option<String>) {
+    match docs.as_ref().and_then(|d| d.lines().next()) {
+        Some(summary) => lines.push(format!("{}{}  // {}", indent, signature, summary)),
+        None => lines.push(format!("{}{}", indent, signature)),
+    }
+}
+
+/// Tool showing the fields, variants and methods of a Rust type
+pub struct InspectTypeTool {
+    name: String,
+}
+
+impl InspectTypeTool {
+    pub fn new() -> Self {
+        Self {
+            name: "inspect_type".to_string(),
+        }
+    }
+
+    fn parse_params(args: &ToolArgs) -> Result<(String, Option<String>), ToolError> {
+        let obj = if let Some(json_str) = args.get_named_arg("json") {
+            serde_json::from_str(json_str).map_err(ToolError::Json)?
+        } else if !args.named_args.is_empty() {
+            serde_json::to_value(&args.named_args).map_err(ToolError::Json)?
+        } else {
+            json!({"type_name": args.get_arg(0), "path": args.get_arg(1)})
+        };
+        let type_name = obj
+            .get("type_name")
+            .and_then(|s| s.as_str())
+            .map(str::trim)
+            .filter(|s| !s.is_empty())
+            .ok_or_else(|| ToolError::InvalidArgs {
+                message: "Usage: inspect_type <TypeName> [path]".to_string(),
+            })?;
+        let path = obj.get("path").and_then(|p| p.as_str()).map(String::from);
+        Ok((type_name.to_string(), path))
+    }
+}
+
+impl Default for InspectTypeTool {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl Tool for InspectTypeTool {
+    fn name(&self) -> &str {
+        &self.name
+    }
+
+    fn description(&self) -> &str {
+        "Show the fields, variants and methods (with doc summaries) of a Rust struct, enum \
+         or trait, including every impl block for it"
+    }
+
+    fn signature(&self) -> &str {
+        "inspect_type <TypeName> [path]"
+    }
+
+    fn category(&self) -> ToolCategory {
+        ToolCategory::Navigation
+    }
+
+    fn danger_level(&self) -> DangerLevel {
+        DangerLevel::ReadOnly
+    }
+
+    fn validate_args(&self, args: &ToolArgs) -> Result<(), ToolError> {
+        Self::parse_params(args).map(|_| ())
+    }
+
+    fn execute(&mut self, args: &ToolArgs, state: &Arc<Mutex<ToolState>>) -> Result<ToolResult> {
+        let (type_name, path) = Self::parse_params(args)?;
+        // `crate::module::Type` and `Type<T>` are looked up as `Type`
+        let name = type_name
+            .split('<')
+            .next()
+            .and_then(|n| n.rsplit("::").next())
+            .unwrap_or(&type_name)
+            .trim()
+            .to_string();
+        let working_directory = state
+            .lock()
+            .map_err(|e| anyhow::anyhow!("Failed to lock state: {}", e))?
+            .working_directory
+            .clone();
+        let root = match path {
+            Some(path) => working_directory.join(path),
+            None => working_directory.clone(),
+        };
+
+        let Some((shape, others)) = inspect_type(&root, &name) else {
+            return Ok(ToolResult::error(format!(
+                "No Rust struct, enum or trait named '{}' found under {}",
+                name,
+                root.display()
+            )));
+        };
+        let relative = |p: &Path| {
+            p.strip_prefix(&working_directory)
+                .unwrap_or(p)
+                .display()
+                .to_string()
+        };
+
+        let mut lines = vec![format!(
+            "{} ({}:{})",
+            shape.header,
+            relative(&shape.path),
+            shape.line
+        )];
+        if let Some(docs) = &shape.docs {
+            lines.extend(docs.lines().map(|l| format!("  {}", l)));
+        }
+        if !shape.fields.is_empty() {
+            lines.push("\nFields:".to_string());
+            for field in &shape.fields {
+                push_member(&mut lines, "  ", &field.signature, &field.docs);
+            }
+        }
+        if !shape.variants.is_empty() {
+            lines.push("\nVariants:".to_string());
+            for variant in &shape.variants {
+                let signature = format!("{}{}", variant.name, variant.fields);
+                push_member(&mut lines, "  ", &signature, &variant.docs);
+            }
+        }
+        if !shape.trait_methods.is_empty() {
+            lines.push("\nTrait methods:".to_string());
+            for method in &shape.trait_methods {
+                push_member(&mut lines, "  ", &method.signature, &method.docs);
+            }
+        }
+        for block in &shape.impls {
+            lines.push(format!(
+                "\n{} ({}:{})",
+                block.header,
+                relative(&block.path),
+                block.line
+            ));
+            for method in &block.methods {
+                push_member(&mut lines, "  ", &method.signature, &method.docs);
+            }
+        }
+        if !others.is_empty() {
+            lines.push(format!(
+                "\nOther definitions named {}: {}",
+                name,
+                others
+                    .iter()
+                    .map(|(p, l)| format!("{}:{}", relative(p), l))
+                    .collect::<Vec<_>>()
+                    .join(", ")
+            ));
+        }
+
+        state
+            .lock()
+            .map_err(|e| anyhow::anyhow!("Failed to lock state: {}", e))?
+            .push_history(format!("Inspected type {}", name));
+
+        let mut data = serde_json::to_value(&shape)?;
+        data["path"] = json!(relative(&shape.path));
+        data["other_definitions"] = others
+            .iter()
+            .map(|(p, l)| json!({"path": relative(p), "line": l}))
+            .collect();
+        Ok(ToolResult::success_with_data(lines.join("\n"), data))
+    }
+
+    fn get_parameters_schema(&self) -> Value {
+        json!({
+            "type": "object",
+            "properties": {
+                "type_name": {
+                    "type": "string",
+                    "description": "Name of the struct, enum or trait (a module path or generics are ignored)"
+                },
+                "path": {
+                    "type": "string",
+                    "description": "Directory or file to search (defaults to the working directory)"
+                }
+            },
+            "required": ["type_name"]
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::fs;
+    use tempfile::TempDir;
+
+    #[test]
+    fn test_inspect_type_collects_fields_variants_and_impls() {
+        let dir = TempDir::new().unwrap();
+        let root = dir.path();
+        fs::create_dir_all(root.join("src")).unwrap();
+        fs::write(
+            root.join("src/shape.rs"),
+            "/// A shape on the canvas\n#[derive(Debug)]\npub struct Shape<T> {\n    /// Unique id\n    pub id: u64,\n    points: Vec<(T, T)>,\n}\n\npub enum Kind {\n    /// Closed polygon\n    Polygon { sides: u8 },\n    Line(f32, f32),\n    Empty = 3,\n}\n\nimpl<T> Shape<T> {\n    /// Create an empty shape\n    pub fn new(id: u64) -> Self {\n        Self { id, points: Vec::new() }\n    }\n}\n",
+        )
+        .unwrap();
+        fs::write(
+            root.join("src/display.rs"),
+            "impl<T> std::fmt::Display for Shape<T> {\n    fn fmt(\n        &self,\n        f: &mut std::fmt::Formatter,\n    ) -> std::fmt::Result {\n        write!(f, \"shape\")\n    }\n}\n",
+        )
+        .unwrap();
+
+        let (shape, others) = inspect_type(root, "Shape").unwrap();
+        assert!(others.is_empty());
+        assert_eq!(shape.kind, "struct");
+        assert_eq!(shape.header, "pub struct Shape<T>");
+        assert_eq!(shape.docs.as_deref(), Some("A shape on the canvas"));
+        let fields: Vec<_> = shape.fields.iter().map(|f| f.signature.as_str()).collect();
+        assert_eq!(fields, vec!["pub id: u64", "points: Vec<(T, T)>"]);
+        assert_eq!(shape.fields[0].docs.as_deref(), Some("Unique id"));
+        assert_eq!(shape.impls.len(), 2);
+        assert_eq!(
+            shape.impls[0].methods[0].signature,
+            "fn fmt(&self, f: &mut std::fmt::Formatter) -> std::fmt::Result"
+        );
+        assert_eq!(shape.impls[1].header, "impl<T> Shape<T>");
+        assert_eq!(
+            shape.impls[1].methods[0].docs.as_deref(),
+            Some("Create an empty shape")
+        );
+
+        let (kind, _) = inspect_type(root, "Kind").unwrap();
+        let variants: Vec<_> = kind
+            .variants
+            .iter()
+            .map(|v| format!("{}{}", v.name, v.fields))
+            .collect();
+        assert_eq!(
+            variants,
+            vec!["Polygon { sides: u8 }", "Line(f32, f32)", "Empty = 3"]
+        );
+        assert_eq!(kind.variants[0].docs.as_deref(), Some("Closed polygon"));
+
+        let state = Arc::new(Mutex::new(ToolState::new()));
+        state.lock().unwrap().working_directory = root.to_path_buf();
+        let mut tool = InspectTypeTool::new();
+        let result = tool
+            .execute(&ToolArgs::from_args(&["crate::shape::Shape"]), &state)
+            .unwrap();
+        assert!(result.success, "{}", result.message);
+        assert!(result.message.contains("pub id: u64  // Unique id"));
+        assert_eq!(result.data.unwrap()["path"], "src/shape.rs");
+        let missing = tool
+            .execute(&ToolArgs::from_args(&["Missing"]), &state)
+            .unwrap();
+        assert!(!missing.success);
+    }
+}
@@ -0,0 +1,435 @@
+//! Syntax-aware code navigation built on tree-sitter
+//!
+//! Parses Rust, Python, JavaScript, TypeScript and Go with their tree-sitter grammars
+//! and extracts definitions (functions, types, classes, constants, ...) so tools can
+//! locate symbols without a language server.
+
+pub mod docs;
+pub mod goto_definition;
+pub mod imports;
+pub mod inspect_type;
+pub mod symbols;
+pub mod test_map;
+
+pub use docs::GetDocsTool;
+pub use goto_definition::GotoDefinitionTool;
+pub use inspect_type::InspectTypeTool;
+pub use symbols::{GotoSymbolTool, ListSymbolsTool, ShowFunctionTool};
+pub use test_map::TestMapTool;
+
+use crate::search::ConfigurableFilter;
+use serde::Serialize;
+use std::path::{Path, PathBuf};
+use tree_sitter::{Node, Parser, Query, QueryCursor, StreamingIterator, Tree};
+use walkdir::WalkDir;
+
+/// A language with a bundled tree-sitter grammar
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Serialize)]
+#[serde(rename_all = "snake_case")]
+pub enum Language {
+    Rust,
+    Python,
+    JavaScript,
+    TypeScript,
+    Tsx,
+    Go,
+}
+
+impl Language {
+    /// Every supported language
+    pub const ALL: [Language; 6] = [
+        Language::Rust,
+        Language::Python,
+        Language::JavaScript,
+        Language::TypeScript,
+        Language::Tsx,
+        Language::Go,
+    ];
+
+    /// The language of `path`, by extension
+    pub fn from_path(path: &Path) -> Option<Self> {
+        match path.extension()?.to_str()? {
+            "rs" => Some(Language::Rust),
+            "py" | "pyi" => Some(Language::Python),
+            "js" | "jsx" | "mjs" | "cjs" => Some(Language::JavaScript),
+            "ts" | "mts" | "cts" => Some(Language::TypeScript),
+            "tsx" => Some(Language::Tsx),
+            "go" => Some(Language::Go),
+            _ => None,
+        }
+    }
+
+    /// The tree-sitter grammar
+    pub fn grammar(self) -> tree_sitter::Language {
+        match self {
+            Language::Rust => tree_sitter_rust::LANGUAGE.into(),
+            Language::Python => tree_sitter_python::LANGUAGE.into(),
+            Language::JavaScript => tree_sitter_javascript::LANGUAGE.into(),
+            Language::TypeScript => tree_sitter_typescript::LANGUAGE_TYPESCRIPT.into(),
+            Language::Tsx => tree_sitter_typescript::LANGUAGE_TSX.into(),
+            Language::Go => tree_sitter_go::LANGUAGE.into(),
+        }
+    }
+
+    /// Query capturing each definition as `@<kind>` with its name as `@name`
+    fn definitions_query(self) -> &'static str {
+        match self {
+            Language::Rust => RUST_DEFINITIONS,
+            Language::Python => PYTHON_DEFINITIONS,
+            Language::JavaScript => JAVASCRIPT_DEFINITIONS,
+            Language::TypeScript | Language::Tsx => TYPESCRIPT_DEFINITIONS,
+            Language::Go => GO_DEFINITIONS,
+        }
+    }
+}
+
+const RUST_DEFINITIONS: &str = r#"
+(function_item name: (identifier) @name) @function
+(function_signature_item name: (identifier) @name) @function
+(struct_item name: (type_identifier) @name) @struct
+(union_item name: (type_identifier) @name) @struct
+(enum_item name: (type_identifier) @name) @enum
+(enum_variant name: (identifier) @name) @variant
+(trait_item name: (type_identifier) @name) @trait
+(type_item name: (type_identifier) @name) @type
+(const_item name: (identifier) @name) @constant
+(static_item name: (identifier) @name) @constant
+(mod_item name: (identifier) @name) @module
+(macro_definition name: (identifier) @name) @macro
+"#;
+
+const PYTHON_DEFINITIONS: &str = r#"
+(function_definition name: (identifier) @name) @function
+(class_definition name: (identifier) @name) @class
+(module (expression_statement (assignment left: (identifier) @name) @variable))
+"#;
+
+const JAVASCRIPT_DEFINITIONS: &str = r#"
+(function_declaration name: (identifier) @name) @function
+(generator_function_declaration name: (identifier) @name) @function
+(class_declaration name: (identifier) @name) @class
+(method_definition name: (property_identifier) @name) @method
+(variable_declarator name: (identifier) @name value: [(arrow_function) (function_expression)]) @function
+"#;
+
+const TYPESCRIPT_DEFINITIONS: &str = r#"
+(function_declaration name: (identifier) @name) @function
+(generator_function_declaration name: (identifier) @name) @function
+(function_signature name: (identifier) @name) @function
+(class_declaration name: (type_identifier) @name) @class
+(abstract_class_declaration name: (type_identifier) @name) @class
+(method_definition name: (property_identifier) @name) @method
+(method_signature name: (property_identifier) @name) @method
+(interface_declaration name: (type_identifier) @name) @interface
+(type_alias_declaration name: (type_identifier) @name) @type
+(enum_declaration name: (identifier) @name) @enum
+(variable_declarator name: (identifier) @name value: [(arrow_function) (function_expression)]) @function
+"#;
+
+const GO_DEFINITIONS: &str = r#"
+(function_declaration name: (identifier) @name) @function
+(method_declaration name: (field_identifier) @name) @method
+(type_spec name: (type_identifier) @name) @type
+(const_spec name: (identifier) @name) @constant
+(var_spec name: (identifier) @name) @variable
+"#;
+
+/// A definition found in a source file
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+pub struct Definition {
+    pub name: String,
+    /// `function`, `struct`, `class`, `method`, ...
+    pub kind: String,
+    /// Enclosing type, trait, class or module, e.g. the `impl` type of a method
+    pub container: Option<String>,
+    /// 1-based line of the definition's first line
+    pub line: usize,
+    /// 1-based line of its last line
+    pub end_line: usize,
+}
+
+/// Parse `source` as `language`
+pub fn parse(language: Language, source: &str) -> Option<Tree> {
+    let mut parser = Parser::new();
+    parser.set_language(&language.grammar()).ok()?;
+    parser.parse(source, None)
+}
+
+/// A place where `source` does not parse
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+pub struct SyntaxError {
+    /// 1-based line
+    pub line: usize,
+    /// 1-based column
+    pub column: usize,
+    pub message: String,
+}
+
+/// Syntax errors in `source`, in source order: text the grammar cannot place and
+/// tokens the parser had to assume, such as a missing closing brace
+pub fn syntax_errors(language: Language, source: &str) -> Vec<SyntaxError> {
+    let Some(tree) = parse(language, source) else {
+        return Vec::new();
+    };
+    let mut errors = Vec::new();
+    let mut pending = vec![tree.root_node()];
+    while let Some(node) = pending.pop() {
+        if !node.has_error() {
+            continue;
+        }
+        let message = if node.is_missing() {
+            Some(format!("missing `{}`", node.kind()))
+        } else if node.is_error() {
+            let text = node.utf8_text(source.as_bytes()).unwrap_or_default();
+            let text = text.lines().next().unwrap_or_default().trim();
+            Some(match text.char_indices().nth(40) {
+                Some((end, _)) => format!("unexpected `{}...`", &text[..end]),
+                None => format!("unexpected `{}`", text),
+            })
+        } else {
+            None
+        };
+        match message {
+            Some(message) => errors.push(SyntaxError {
+                line: node.start_position().row + 1,
+                column: node.start_position().column + 1,
+                message,
+            }),
+            None => {
+                let mut cursor = node.walk();
+                let children: Vec<Node> = node.children(&mut cursor).collect();
+                pending.extend(children.into_iter().rev());
+            }
+        }
+    }
+    errors
+}
+
+/// Lines of the comments in `source` with their 1-based line numbers, in source order
+pub fn comment_lines(language: Language, source: &str) -> Vec<(usize, String)> {
+    let Some(tree) = parse(language, source) else {
+        return Vec::new();
+    };
+    let mut lines = Vec::new();
+    let mut pending = vec![tree.root_node()];
+    while let Some(node) = pending.pop() {
+        // `line_comment`, `block_comment` and plain `comment`; doc comments nest inside
+        if node.kind().ends_with("comment") {
+            let text = node.utf8_text(source.as_bytes()).unwrap_or_default();
+            let first = node.start_position().row + 1;
+            lines.extend(
+                text.lines()
+                    .enumerate()
+                    .map(|(offset, line)| (first + offset, line.to_string())),
+            );
+            continue;
+        }
+        let mut cursor = node.walk();
+        let children: Vec<Node> = node.children(&mut cursor).collect();
+        pending.extend(children.into_iter().rev());
+    }
+    lines
+}
+
+/// Name of the type, trait, class or module enclosing `node`, if any
+fn container_name(node: Node, source: &str) -> Option<String> {
+    let mut current = node.parent();
+    while let Some(ancestor) = current {
+        let field = match ancestor.kind() {
+            "impl_item" => Some("type"),
+            "trait_item"
+            | "mod_item"
+            | "class_definition"
+            | "class_declaration"
+            | "abstract_class_declaration"
+            | "interface_declaration" => Some("name"),
+            _ => None,
+        };
+        if let Some(name) = field.and_then(|f| ancestor.child_by_field_name(f)) {
+            let text = name.utf8_text(source.as_bytes()).ok()?;
+            // `impl<T> Foo<T>` is reported as `Foo`
+            return Some(text.split('<').next().unwrap_or(text).trim().to_string());
+        }
+        current = ancestor.parent();
+    }
+    // Go methods name their receiver type instead of nesting
+    if node.kind() == "method_declaration" {
+        let receiver = node.child_by_field_name("receiver")?;
+        let text = receiver.utf8_text(source.as_bytes()).ok()?;
+        let ty = text
+            .trim_matches(|c| c == '(' || c == ')')
+            .split_whitespace()
+            .last()?;
+        return Some(ty.trim_start_matches('*').to_string());
+    }
+    None
+}
+
+/// All definitions in `source`, in source order
+pub fn definitions(language: Language, source: &str) -> Vec<Definition> {
+    let Some(tree) = parse(language, source) else {
+        return Vec::new();
+    };
+    definition_nodes(language, &tree, source)
+        .into_iter()
+        .map(|(definition, _)| definition)
+        .collect()
+}
+
+/// [`definitions`] of an already parsed `tree`, each with its syntax node
+pub(crate) fn definition_nodes<'t>(
+    language: Language,
+    tree: &'t Tree,
+    source: &str,
+) -> Vec<(Definition, Node<'t>)> {
+    let grammar = language.grammar();
+    let query = match Query::new(&grammar, language.definitions_query()) {
+        Ok(query) => query,
+        Err(e) => {
+            tracing::warn!("Invalid definitions query for {:?}: {}", language, e);
+            return Vec::new();
+        }
+    };
+    let names = query.capture_names();
+
+    let mut found = Vec::new();
+    let mut cursor = QueryCursor::new();
+    let mut matches = cursor.matches(&query, tree.root_node(), source.as_bytes());
+    while let Some(m) = matches.next() {
+        let mut name = None;
+        let mut definition = None;
+        for capture in m.captures {
+            match names[capture.index as usize] {
+                "name" => name = capture.node.utf8_text(source.as_bytes()).ok(),
+                kind => definition = Some((kind, capture.node)),
+            }
+        }
+        if let (Some(name), Some((kind, node))) = (name, definition) {
+            found.push((
+                Definition {
+                    name: name.to_string(),
+                    kind: kind.to_string(),
+                    container: container_name(node, source),
+                    line: node.start_position().row + 1,
+                    end_line: node.end_position().row + 1,
+                },
+                node,
+            ));
+        }
+    }
+    found.sort_by_key(|(d, _)| (d.line, d.end_line));
+    found
+}
+
+/// Whether `path` looks like a test file or lives in a test directory
+pub(crate) fn is_test_path(path: &Path) -> bool {
+    path.components().any(|c| {
+        let part = c.as_os_str().to_string_lossy();
+        part == "tests"
+            || part == "test"
+            || part == "__tests__"
+            || part.starts_with("test_")
+            || part.contains("_test.")
+            || part.contains(".test.")
+            || part.contains(".spec.")
+    })
+}
+
+/// Every source file under `root` that mentions `name`, with its language and content
+pub(crate) fn source_files(root: &Path, name: &str) -> Vec<(PathBuf, Language, String)> {
+    let filter = ConfigurableFilter::new(None);
+    WalkDir::new(root)
+        .follow_links(false)
+        .sort_by_file_name()
+        .into_iter()
+        .filter_entry(|e| {
+            e.path() == root
+                || !e.file_type().is_dir()
+                || filter.should_include_path(e.path().strip_prefix(root).unwrap_or(e.path()))
+        })
+        .filter_map(|entry| entry.ok())
+        .filter(|entry| entry.file_type().is_file())
+        .filter_map(|entry| {
+            let language = Language::from_path(entry.path())?;
+            let content = std::fs::read_to_string(entry.path()).ok()?;
+            content
+                .contains(name)
+                .then(|| (entry.path().to_path_buf(), language, content))
+        })
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_definitions_per_language() {
+        let rust = "struct Point { x: i32 }\n\nimpl Point {\n    fn new() -> Self { Point { x: 0 } }\n}\n\nconst LIMIT: u8 = 3;\n";
+        let defs = definitions(Language::Rust, rust);
+        let summary: Vec<_> = defs
+            .iter()
+            .map(|d| {
+                (
+                    d.name.as_str(),
+                    d.kind.as_str(),
+                    d.container.as_deref(),
+                    d.line,
+                )
+            })
+            .collect();
+        assert_eq!(
+            summary,
+            vec![
+                ("Point", "struct", None, 1),
+                ("new", "function", Some("Point"), 4),
+                ("LIMIT", "constant", None, 7),
+            ]
+        );
+
+        let python = "class Shape:\n    def area(self):\n        return 0\n\nSCALE = 2\n";
+        let defs = definitions(Language::Python, python);
+        assert_eq!(defs[1].name, "area");
+        assert_eq!(defs[1].container.as_deref(), Some("Shape"));
+        assert_eq!(defs[2].kind, "variable");
+
+        let ts = "export interface User { id: number }\nexport const load = async () => {};\nclass Repo { find() {} }\n";
+        let names: Vec<_> = definitions(Language::TypeScript, ts)
+            .into_iter()
+            .map(|d| (d.name, d.kind))
+            .collect();
+        assert_eq!(
+            names,
+            vec![
+                ("User".to_string(), "interface".to_string()),
+                ("load".to_string(), "function".to_string()),
+                ("Repo".to_string(), "class".to_string()),
+                ("find".to_string(), "method".to_string()),
+            ]
+        );
+
+        let go = "package main\n\ntype Server struct{}\n\nfunc (s *Server) Start() {}\n";
+        let defs = definitions(Language::Go, go);
+        assert_eq!(defs[1].name, "Start");
+        assert_eq!(defs[1].container.as_deref(), Some("Server"));
+
+        let js = "function main() {}\n";
+        assert_eq!(definitions(Language::JavaScript, js)[0].name, "main");
+    }
+
+    #[test]
+    fn test_syntax_errors() {
+        assert!(syntax_errors(Language::Rust, "fn main() {}\n").is_empty());
+        let errors = syntax_errors(Language::Rust, "fn main() {\n    let x = 1\n}\n");
+        assert_eq!(errors.len(), 1);
+        assert_eq!(
+            (errors[0].line, errors[0].message.as_str()),
+            (2, "missing `;`")
+        );
+        let errors = syntax_errors(Language::Rust, "fn main() {\n    let x = 1;\n");
+        assert_eq!(errors[0].line, 1);
+
+        let errors = syntax_errors(Language::Python, "def f(:\n    return 1\n");
+        assert_eq!(errors[0].line, 1);
+    }
+}
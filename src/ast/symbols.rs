@@ -0,0 +1,895 @@
+//! Symbol-aware navigation within files
+//!
+//! `list_symbols` outlines the definitions of a file or directory, `goto_symbol` moves
+//! the window to a definition in one file, and `show_function` prints a function's full
+//! source, attributes and decorators included. All three read definitions from the
+//! syntax tree, so nesting, multi-line signatures and bodies are exact where
+//! indentation heuristics guess. [`elided_bodies`] gives the function bodies `filemap`
+//! folds away.
+
+use super::docs::signature;
+use super::goto_definition::split_symbol;
+use super::{definition_nodes, is_test_path, parse, source_files, Definition, Language};
+use crate::core::{DangerLevel, Tool, ToolArgs, ToolCategory, ToolError, ToolResult};
+use crate::file_navigation::DEFAULT_WINDOW_SIZE;
+use crate::state::ToolState;
+use crate::utils::suggest::{closest_matches, format_suggestions, path_not_found};
+use anyhow::Result;
+use serde::Serialize;
+use serde_json::{json, Value};
+use std::path::{Path, PathBuf};
+use std::sync::{Arc, Mutex};
+use tree_sitter::Node;
+
+/// Symbols listed in a `list_symbols` message; the data holds them all
+const MAX_LISTED_SYMBOLS: usize = 300;
+
+/// Longest function `show_function` prints, in lines
+const MAX_FUNCTION_LINES: usize = 400;
+
+/// Bodies shorter than this many lines are kept by [`elided_bodies`]
+const MIN_ELIDED_LINES: usize = 5;
+
+/// Number of closest names suggested for an unknown symbol
+const MAX_SUGGESTIONS: usize = 5;
+
+fn is_function(definition: &Definition) -> bool {
+    definition.kind == "function" || definition.kind == "method"
+}
+
+/// The body of a function node: its `body` field, or that of the arrow function or
+/// function expression a JavaScript variable is bound to
+fn function_body(node: Node) -> Option<Node> {
+    node.child_by_field_name("body").or_else(|| {
+        node.child_by_field_name("value")
+            .and_then(|value| value.child_by_field_name("body"))
+    })
+}
+
+/// 1-based, inclusive line ranges of function bodies worth folding in an outline view
+///
+/// A brace body keeps its opening line, so the signature stays visible; an indented
+/// Python body is folded whole. Functions nested in a folded body are folded with it.
+pub fn elided_bodies(language: Language, source: &str) -> Option<Vec<(usize, usize)>> {
+    let tree = parse(language, source)?;
+    let mut ranges: Vec<(usize, usize)> = Vec::new();
+    for (definition, node) in definition_nodes(language, &tree, source) {
+        if !is_function(&definition) {
+            continue;
+        }
+        let Some(body) = function_body(node) else {
+            continue;
+        };
+        let braced = source[body.start_byte()..].starts_with('{');
+        let start = body.start_position().row + 1 + usize::from(braced);
+        let end = node.end_position().row + 1;
+        let folded = ranges.last().is_some_and(|&(_, last)| start <= last);
+        if !folded && end + 1 >= start + MIN_ELIDED_LINES {
+            ranges.push((start, end));
+        }
+    }
+    Some(ranges)
+}
+
+/// The statement holding the definition at `node`: its decorated definition, `export`
+/// or, for a function bound to a JavaScript variable, the declaration
+fn statement(node: Node) -> Node {
+    let mut anchor = node;
+    if anchor.kind() == "variable_declarator" {
+        anchor = anchor.parent().unwrap_or(anchor);
+    }
+    anchor
+        .parent()
+        .filter(|p| matches!(p.kind(), "decorated_definition" | "export_statement"))
+        .unwrap_or(anchor)
+}
+
+/// The first line of `node` including its attributes, decorators and `export`
+fn first_line(language: Language, node: Node) -> usize {
+    let mut anchor = statement(node);
+    if language == Language::Rust {
+        while let Some(attribute) = anchor
+            .prev_sibling()
+            .filter(|s| s.kind() == "attribute_item")
+        {
+            anchor = attribute;
+        }
+    }
+    anchor.start_position().row + 1
+}
+
+/// Whether `sibling` documents the definition below it: a Rust attribute or doc comment,
+/// or any comment in the other languages
+fn documents(language: Language, sibling: Node, source: &str) -> bool {
+    let text = &source[sibling.start_byte()..sibling.end_byte()];
+    match (language, sibling.kind()) {
+        (Language::Rust, "attribute_item") => true,
+        (Language::Rust, "line_comment") => text.starts_with("///") && !text.starts_with("////"),
+        (Language::Rust, "block_comment") => text.starts_with("/**"),
+        (Language::Rust, _) => false,
+        (_, kind) => kind == "comment",
+    }
+}
+
+/// 1-based row after `node`'s last character
+fn last_line(node: Node) -> usize {
+    let end = node.end_position();
+    // A node ending with its newline ends at column 0 of the next row
+    if end.column == 0 && end.row > node.start_position().row {
+        end.row
+    } else {
+        end.row + 1
+    }
+}
+
+/// A function with a body and every line that goes with it
+#[derive(Debug, Clone, Serialize)]
+pub struct FunctionExtent {
+    #[serde(flatten)]
+    pub definition: Definition,
+    /// First line, including attributes, decorators, `export` and the comments directly
+    /// above that document it
+    pub start_line: usize,
+    /// Last line, including the end of a JavaScript variable declaration
+    pub end_line: usize,
+    /// Indentation of a Python function that is the only statement of its block, which
+    /// needs a `pass` in its place
+    pub sole_statement_indent: Option<String>,
+}
+
+/// Functions and methods with a body matching `symbol` (`name`, `Type::name` or
+/// `module.name`), with the lines removing them takes
+pub fn function_extents(
+    language: Language,
+    path: &Path,
+    source: &str,
+    symbol: &str,
+) -> Vec<FunctionExtent> {
+    let (qualifier, name) = split_symbol(symbol);
+    let Some(tree) = parse(language, source) else {
+        return Vec::new();
+    };
+    definition_nodes(language, &tree, source)
+        .into_iter()
+        .filter(|(definition, node)| {
+            definition.name == name && is_function(definition) && function_body(*node).is_some()
+        })
+        .filter(|(definition, _)| {
+            qualifier.is_none_or(|q| {
+                definition.container.as_deref() == Some(q)
+                    || path.file_stem().is_some_and(|s| s == q)
+            })
+        })
+        .map(|(definition, node)| {
+            let outer = statement(node);
+            let mut first = outer;
+            let mut start_row = outer.start_position().row;
+            while let Some(sibling) = first.prev_sibling() {
+                let adjacent = sibling.end_position().row + 1 >= start_row;
+                let trailing = sibling.prev_sibling().is_some_and(|before| {
+                    before.end_position().row == sibling.start_position().row
+                });
+                if !adjacent || trailing || !documents(language, sibling, source) {
+                    break;
+                }
+                first = sibling;
+                start_row = sibling.start_position().row;
+            }
+            let sole_statement_indent = outer
+                .parent()
+                .filter(|block| language == Language::Python && block.kind() == "block")
+                .filter(|block| {
+                    let mut cursor = block.walk();
+                    let statements = block
+                        .named_children(&mut cursor)
+                        .filter(|child| child.kind() != "comment")
+                        .count();
+                    statements == 1
+                })
+                .map(|_| {
+                    let line_start = source[..outer.start_byte()]
+                        .rfind('\n')
+                        .map_or(0, |i| i + 1);
+                    source[line_start..outer.start_byte()].to_string()
+                });
+            FunctionExtent {
+                definition,
+                start_line: start_row + 1,
+                end_line: last_line(outer),
+                sole_statement_indent,
+            }
+        })
+        .collect()
+}
+
+/// A definition found by name, with what the tools show of it
+#[derive(Debug, Clone, Serialize)]
+pub struct SymbolMatch {
+    pub path: PathBuf,
+    #[serde(flatten)]
+    pub definition: Definition,
+    pub signature: String,
+    /// First line including attributes and decorators
+    pub start_line: usize,
+}
+
+/// Definitions in `source` matching `symbol` (`name`, `Type::name` or `module.name`)
+pub fn find_symbol(
+    language: Language,
+    path: &Path,
+    source: &str,
+    symbol: &str,
+) -> Vec<SymbolMatch> {
+    let (qualifier, name) = split_symbol(symbol);
+    let Some(tree) = parse(language, source) else {
+        return Vec::new();
+    };
+    definition_nodes(language, &tree, source)
+        .into_iter()
+        .filter(|(definition, _)| definition.name == name)
+        .filter(|(definition, _)| {
+            qualifier.is_none_or(|q| {
+                definition.container.as_deref() == Some(q)
+                    || path.file_stem().is_some_and(|s| s == q)
+            })
+        })
+        .map(|(definition, node)| SymbolMatch {
+            path: path.to_path_buf(),
+            signature: signature(node, source),
+            start_line: first_line(language, node),
+            definition,
+        })
+        .collect()
+}
+
+/// A definition with its nesting depth in the file's outline
+#[derive(Debug, Clone, Serialize)]
+pub struct OutlineEntry {
+    #[serde(flatten)]
+    pub definition: Definition,
+    pub depth: usize,
+    pub signature: String,
+}
+
+/// The definitions of `source` in order, each with how many definitions enclose it
+pub fn outline(language: Language, source: &str) -> Vec<OutlineEntry> {
+    let Some(tree) = parse(language, source) else {
+        return Vec::new();
+    };
+    let mut enclosing: Vec<usize> = Vec::new();
+    definition_nodes(language, &tree, source)
+        .into_iter()
+        .map(|(definition, node)| {
+            while enclosing
+                .last()
+                .is_some_and(|&end| end < definition.end_line)
+            {
+                enclosing.pop();
+            }
+            let depth = enclosing.len();
+            enclosing.push(definition.end_line);
+            OutlineEntry {
+                signature: signature(node, source),
+                definition,
+                depth,
+            }
+        })
+        .collect()
+}
+
+fn lock_root(state: &Arc<Mutex<ToolState>>) -> Result<(PathBuf, Option<PathBuf>)> {
+    let state_guard = state
+        .lock()
+        .map_err(|e| anyhow::anyhow!("Failed to lock state: {}", e))?;
+    Ok((
+        state_guard.working_directory.clone(),
+        state_guard.current_file.clone(),
+    ))
+}
+
+fn unsupported(path: &Path) -> ToolResult {
+    ToolResult::error(format!(
+        "{} is not a Rust, Python, JavaScript, TypeScript or Go source file",
+        path.display()
+    ))
+}
+
+/// Source files to search: the file at `scope`, or those under it mentioning `name`
+fn files_in(scope: &Path, name: &str) -> Result<Vec<(PathBuf, Language, String)>, ToolResult> {
+    if scope.is_file() {
+        let language = Language::from_path(scope).ok_or_else(|| unsupported(scope))?;
+        let content = std::fs::read_to_string(scope)
+            .map_err(|e| ToolResult::error(format!("Failed to read {}: {}", scope.display(), e)))?;
+        Ok(vec![(scope.to_path_buf(), language, content)])
+    } else if scope.is_dir() {
+        Ok(source_files(scope, name))
+    } else {
+        Err(path_not_found(
+            format!("Path not found: {}", scope.display()),
+            scope,
+        ))
+    }
+}
+
+/// Error for a symbol not found, suggesting the closest definition names
+fn symbol_not_found(
+    symbol: &str,
+    scope: &Path,
+    files: &[(PathBuf, Language, String)],
+    functions_only: bool,
+) -> ToolResult {
+    let names: Vec<String> = files
+        .iter()
+        .flat_map(|(_, language, content)| super::definitions(*language, content))
+        .filter(|d| !functions_only || is_function(d))
+        .map(|d| d.name)
+        .collect();
+    let (_, name) = split_symbol(symbol);
+    let suggestions = closest_matches(name, names.iter().map(String::as_str), MAX_SUGGESTIONS);
+    ToolResult::error(format!(
+        "No {} '{}' found in {}{}",
+        if functions_only {
+            "function"
+        } else {
+            "definition"
+        },
+        symbol,
+        scope.display(),
+        format_suggestions(&suggestions)
+    ))
+}
+
+fn relative(path: &Path, root: &Path) -> PathBuf {
+    path.strip_prefix(root).unwrap_or(path).to_path_buf()
+}
+
+/// Tool outlining the definitions of a file or directory
+pub struct ListSymbolsTool {
+    name: String,
+}
+
+impl ListSymbolsTool {
+    pub fn new() -> Self {
+        Self {
+            name: "list_symbols".to_string(),
+        }
+    }
+
+    fn describe(entry: &OutlineEntry) -> String {
+        let definition = &entry.definition;
+        // A method outside its type's definition (a Rust `impl`, a Go receiver) is qualified
+        let name = match (&definition.container, entry.depth) {
+            (Some(container), 0) => format!("{}::{}", container, definition.name),
+            _ => definition.name.clone(),
+        };
+        let mut line = format!(
+            "{}{} {} [{}-{}]",
+            "  ".repeat(entry.depth + 1),
+            definition.kind,
+            name,
+            definition.line,
+            definition.end_line
+        );
+        if is_function(definition) {
+            line.push_str(&format!(": {}", entry.signature));
+        }
+        line
+    }
+}
+
+impl Default for ListSymbolsTool {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl Tool for ListSymbolsTool {
+    fn name(&self) -> &str {
+        &self.name
+    }
+
+    fn description(&self) -> &str {
+        "Outline the functions, methods, types and classes of a source file (or every file \
+         under a directory) with their line ranges, nesting and function signatures, parsed \
+         with tree-sitter; --kind keeps only one kind of definition"
+    }
+
+    fn signature(&self) -> &str {
+        "list_symbols [path] [--kind=<function|method|struct|class|...>]"
+    }
+
+    fn category(&self) -> ToolCategory {
+        ToolCategory::Navigation
+    }
+
+    fn danger_level(&self) -> DangerLevel {
+        DangerLevel::ReadOnly
+    }
+
+    fn validate_args(&self, _args: &ToolArgs) -> Result<(), ToolError> {
+        Ok(())
+    }
+
+    fn execute(&mut self, args: &ToolArgs, state: &Arc<Mutex<ToolState>>) -> Result<ToolResult> {
+        let (root, current) = lock_root(state)?;
+        let scope = match args.get_named_arg("path").or_else(|| args.get_arg(0)) {
+            Some(path) => root.join(path),
+            None => current.unwrap_or_else(|| root.clone()),
+        };
+        let kind = args.get_named_arg("kind").map(String::as_str);
+        let files = match files_in(&scope, "") {
+            Ok(files) => files,
+            Err(result) => return Ok(result),
+        };
+
+        let mut message = String::new();
+        let mut listed = 0;
+        let mut total = 0;
+        let mut data = Vec::new();
+        for (path, language, content) in &files {
+            let entries: Vec<OutlineEntry> = outline(*language, content)
+                .into_iter()
+                .filter(|entry| kind.is_none_or(|k| entry.definition.kind == k))
+                .collect();
+            if entries.is_empty() {
+                continue;
+            }
+            total += entries.len();
+            if listed < MAX_LISTED_SYMBOLS {
+                message.push_str(&format!(
+                    "{}{}:\n",
+                    if message.is_empty() { "" } else { "\n" },
+                    relative(path, &root).display()
+                ));
+                for entry in entries.iter().take(MAX_LISTED_SYMBOLS - listed) {
+                    message.push_str(&Self::describe(entry));
+                    message.push('\n');
+                    listed += 1;
+                }
+            }
+            data.push(json!({
+                "path": relative(path, &root),
+                "language": language,
+                "symbols": entries,
+            }));
+        }
+
+        if total == 0 {
+            return Ok(ToolResult::error(format!(
+                "No {} found in {}",
+                kind.map_or("definitions".to_string(), |k| format!("{} definitions", k)),
+                scope.display()
+            )));
+        }
+        if total > listed {
+            message.push_str(&format!(
+                "... {} more symbol(s); pass a file or --kind to narrow the outline\n",
+                total - listed
+            ));
+        }
+        Ok(ToolResult::success_with_data(
+            message.trim_end().to_string(),
+            json!({ "files": data, "total": total }),
+        ))
+    }
+
+    fn get_parameters_schema(&self) -> Value {
+        json!({
+            "type": "object",
+            "properties": {
+                "path": {
+                    "type": "string",
+                    "description": "File or directory to outline (defaults to the open file, else the working directory)"
+                },
+                "kind": {
+                    "type": "string",
+                    "description": "Only list definitions of this kind: function, method, struct, enum, trait, class, interface, type, constant, ..."
+                }
+            }
+        })
+    }
+}
+
+/// Tool moving the window to a definition within one file
+pub struct GotoSymbolTool {
+    name: String,
+}
+
+impl GotoSymbolTool {
+    pub fn new() -> Self {
+        Self {
+            name: "goto_symbol".to_string(),
+        }
+    }
+}
+
+impl Default for GotoSymbolTool {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl Tool for GotoSymbolTool {
+    fn name(&self) -> &str {
+        &self.name
+    }
+
+    fn description(&self) -> &str {
+        "Open a file (default: the current one) with the window at the definition of <symbol> \
+         in it, e.g. `goto_symbol Parser::parse src/parser.rs`; use goto_definition to search \
+         the whole project"
+    }
+
+    fn signature(&self) -> &str {
+        "goto_symbol <symbol> [path]"
+    }
+
+    fn category(&self) -> ToolCategory {
+        ToolCategory::Navigation
+    }
+
+    fn danger_level(&self) -> DangerLevel {
+        DangerLevel::ReadOnly
+    }
+
+    fn validate_args(&self, args: &ToolArgs) -> Result<(), ToolError> {
+        match args.get_named_arg("symbol").or_else(|| args.get_arg(0)) {
+            Some(symbol) if !symbol.trim().is_empty() => Ok(()),
+            _ => Err(ToolError::InvalidArgs {
+                message: "Usage: goto_symbol <symbol> [path]".to_string(),
+            }),
+        }
+    }
+
+    fn execute(&mut self, args: &ToolArgs, state: &Arc<Mutex<ToolState>>) -> Result<ToolResult> {
+        self.validate_args(args)?;
+        let symbol = match args.get_named_arg("symbol") {
+            Some(symbol) => symbol.trim().to_string(),
+            None => args
+                .get_arg(0)
+                .map(|s| s.trim().to_string())
+                .unwrap_or_default(),
+        };
+        let path = match args.get_named_arg("symbol") {
+            Some(_) => args.get_named_arg("path").or_else(|| args.get_arg(0)),
+            None => args.get_named_arg("path").or_else(|| args.get_arg(1)),
+        };
+        let (root, current) = lock_root(state)?;
+        let file = match (path, current) {
+            (Some(path), _) => root.join(path),
+            (None, Some(current)) => current,
+            (None, None) => {
+                return Ok(ToolResult::error(
+                    "No file is open; pass the file to search as the second argument",
+                ))
+            }
+        };
+        if file.is_dir() {
+            return Ok(ToolResult::error(format!(
+                "{} is a directory; goto_symbol searches one file (use goto_definition for a project-wide lookup)",
+                file.display()
+            )));
+        }
+        let files = match files_in(&file, "") {
+            Ok(files) => files,
+            Err(result) => return Ok(result),
+        };
+        let (_, language, content) = &files[0];
+        let matches = find_symbol(*language, &file, content, &symbol);
+        let Some(best) = matches.first() else {
+            return Ok(symbol_not_found(&symbol, &file, &files, false));
+        };
+
+        let lines: Vec<String> = content.lines().map(|s| s.to_string()).collect();
+        let display = {
+            let mut state_guard = state
+                .lock()
+                .map_err(|e| anyhow::anyhow!("Failed to lock state: {}", e))?;
+            state_guard.open_file(file.clone(), lines, DEFAULT_WINDOW_SIZE)?;
+            let file_state = state_guard
+                .get_current_file_state_mut()
+                .ok_or_else(|| anyhow::anyhow!("Failed to get file state"))?;
+            file_state.goto_line(best.definition.line);
+            let display = file_state.get_window_with_line_numbers();
+            state_guard.push_history(format!(
+                "Jumped to {} at {}:{}",
+                symbol,
+                file.display(),
+                best.definition.line
+            ));
+            display
+        };
+
+        let mut message = format!(
+            "{} {} at {}:{}",
+            best.definition.kind,
+            symbol,
+            relative(&file, &root).display(),
+            best.definition.line
+        );
+        if matches.len() > 1 {
+            message.push_str(&format!(
+                "\n\nAlso defined at line(s) {}",
+                matches[1..]
+                    .iter()
+                    .map(|m| m.definition.line.to_string())
+                    .collect::<Vec<_>>()
+                    .join(", ")
+            ));
+        }
+        message.push_str(&format!("\n\n{}", display.join("\n")));
+        Ok(ToolResult::success_with_data(
+            message,
+            json!({
+                "symbol": symbol,
+                "path": relative(&file, &root),
+                "definition": best.definition,
+                "others": matches[1..].iter().map(|m| &m.definition).collect::<Vec<_>>(),
+                "window": display,
+            }),
+        ))
+    }
+
+    fn get_parameters_schema(&self) -> Value {
+        json!({
+            "type": "object",
+            "properties": {
+                "symbol": {
+                    "type": "string",
+                    "description": "Definition to jump to; qualify methods as `Type::method` to pick one"
+                },
+                "path": {
+                    "type": "string",
+                    "description": "File to search (defaults to the currently open file)"
+                }
+            },
+            "required": ["symbol"]
+        })
+    }
+}
+
+/// Tool printing the full source of a function or method
+pub struct ShowFunctionTool {
+    name: String,
+}
+
+impl ShowFunctionTool {
+    pub fn new() -> Self {
+        Self {
+            name: "show_function".to_string(),
+        }
+    }
+
+    fn params(args: &ToolArgs) -> Result<(String, Option<String>), ToolError> {
+        let symbol = args
+            .get_named_arg("name")
+            .or_else(|| args.get_arg(0))
+            .map(|s| s.trim())
+            .filter(|s| !s.is_empty())
+            .ok_or_else(|| ToolError::InvalidArgs {
+                message: "Usage: show_function <name> [path]".to_string(),
+            })?;
+        let path = match args.get_named_arg("name") {
+            Some(_) => args.get_named_arg("path").or_else(|| args.get_arg(0)),
+            None => args.get_named_arg("path").or_else(|| args.get_arg(1)),
+        };
+        Ok((symbol.to_string(), path.cloned()))
+    }
+}
+
+impl Default for ShowFunctionTool {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl Tool for ShowFunctionTool {
+    fn name(&self) -> &str {
+        &self.name
+    }
+
+    fn description(&self) -> &str {
+        "Print the complete source of a function or method with line numbers, including its \
+         attributes or decorators, without opening the file; qualify it (`Type::method`, \
+         `module.func`) or pass a file or directory to narrow the search"
+    }
+
+    fn signature(&self) -> &str {
+        "show_function <name> [path]"
+    }
+
+    fn category(&self) -> ToolCategory {
+        ToolCategory::Navigation
+    }
+
+    fn danger_level(&self) -> DangerLevel {
+        DangerLevel::ReadOnly
+    }
+
+    fn validate_args(&self, args: &ToolArgs) -> Result<(), ToolError> {
+        Self::params(args).map(|_| ())
+    }
+
+    fn execute(&mut self, args: &ToolArgs, state: &Arc<Mutex<ToolState>>) -> Result<ToolResult> {
+        let (symbol, path) = Self::params(args)?;
+        let (_, name) = split_symbol(&symbol);
+        let (root, _) = lock_root(state)?;
+        let scope = path.map_or_else(|| root.clone(), |path| root.join(path));
+        let files = match files_in(&scope, name) {
+            Ok(files) => files,
+            Err(result) => return Ok(result),
+        };
+
+        let mut matches: Vec<(SymbolMatch, &str)> = files
+            .iter()
+            .flat_map(|(path, language, content)| {
+                find_symbol(*language, path, content, &symbol)
+                    .into_iter()
+                    .filter(|m| is_function(&m.definition))
+                    .map(move |m| (m, content.as_str()))
+            })
+            .collect();
+        matches.sort_by_key(|(m, _)| (is_test_path(&m.path), m.path.clone(), m.definition.line));
+        let Some((best, content)) = matches.first() else {
+            // The files searched only mention the name; suggestions need every file
+            let all = match scope.is_dir() {
+                true => source_files(&scope, ""),
+                false => files.clone(),
+            };
+            return Ok(symbol_not_found(&symbol, &scope, &all, true));
+        };
+
+        let lines: Vec<&str> = content.lines().collect();
+        let end = best.definition.end_line.min(lines.len());
+        let shown_end = end.min(best.start_line + MAX_FUNCTION_LINES - 1);
+        let mut message = format!(
+            "{}:{}-{}: {} {}\n",
+            relative(&best.path, &root).display(),
+            best.start_line,
+            end,
+            best.definition.kind,
+            symbol
+        );
+        for (index, line) in lines[best.start_line - 1..shown_end].iter().enumerate() {
+            message.push_str(&format!("{:4} | {}\n", best.start_line + index, line));
+        }
+        if shown_end < end {
+            message.push_str(&format!(
+                "     | ... {} more lines (open the file at line {} to read them)\n",
+                end - shown_end,
+                shown_end + 1
+            ));
+        }
+        if matches.len() > 1 {
+            message.push_str(&format!(
+                "\nOther definitions:\n{}",
+                matches[1..]
+                    .iter()
+                    .map(|(m, _)| format!(
+                        "  {}:{}: {}",
+                        relative(&m.path, &root).display(),
+                        m.definition.line,
+                        m.signature
+                    ))
+                    .collect::<Vec<_>>()
+                    .join("\n")
+            ));
+        }
+
+        let mut definition = json!(best);
+        definition["path"] = json!(relative(&best.path, &root));
+        Ok(ToolResult::success_with_data(
+            message.trim_end().to_string(),
+            json!({
+                "symbol": symbol,
+                "definition": definition,
+                "truncated": shown_end < end,
+                "others": matches[1..]
+                    .iter()
+                    .map(|(m, _)| json!({
+                        "path": relative(&m.path, &root),
+                        "line": m.definition.line,
+                        "signature": m.signature,
+                    }))
+                    .collect::<Vec<_>>(),
+            }),
+        ))
+    }
+
+    fn get_parameters_schema(&self) -> Value {
+        json!({
+            "type": "object",
+            "properties": {
+                "name": {
+                    "type": "string",
+                    "description": "Function or method name; qualify it (`Type::method`, `module.func`) to disambiguate"
+                },
+                "path": {
+                    "type": "string",
+                    "description": "File or directory to search (default: the working directory)"
+                }
+            },
+            "required": ["name"]
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::fs;
+    use tempfile::TempDir;
+
+    const RUST: &str = "struct Point;\n\nimpl Point {\n    #[inline]\n    fn new() -> Self {\n        let p = Point;\n        let q = p;\n        let r = q;\n        let s = r;\n        s\n    }\n\n    fn tiny(&self) {}\n}\n";
+
+    #[test]
+    fn test_outline_and_elided_bodies() {
+        let python = "class Shape:\n    def area(self):\n        return 0\n\n    def grow(self,\n             by):\n        a = 1\n        b = 2\n        c = 3\n        d = 4\n        return by\n";
+        let entries: Vec<(usize, String)> = outline(Language::Python, python)
+            .into_iter()
+            .map(|e| (e.depth, e.definition.name))
+            .collect();
+        assert_eq!(
+            entries,
+            vec![
+                (0, "Shape".to_string()),
+                (1, "area".to_string()),
+                (1, "grow".to_string())
+            ]
+        );
+        // The multi-line signature stays visible, the indented body is folded
+        assert_eq!(elided_bodies(Language::Python, python), Some(vec![(7, 11)]));
+        // A brace body keeps its opening line
+        assert_eq!(elided_bodies(Language::Rust, RUST), Some(vec![(6, 11)]));
+
+        let matches = find_symbol(Language::Rust, Path::new("x.rs"), RUST, "Point::new");
+        assert_eq!(matches.len(), 1);
+        assert_eq!((matches[0].start_line, matches[0].definition.line), (4, 5));
+        assert!(find_symbol(Language::Rust, Path::new("x.rs"), RUST, "Other::new").is_empty());
+    }
+
+    #[test]
+    fn test_symbol_tools() {
+        let temp_dir = TempDir::new().unwrap();
+        let root = temp_dir.path();
+        fs::create_dir_all(root.join("src")).unwrap();
+        fs::write(root.join("src/point.rs"), RUST).unwrap();
+        let mut tool_state = ToolState::new();
+        tool_state.working_directory = root.to_path_buf();
+        let state = Arc::new(Mutex::new(tool_state));
+
+        let result = ListSymbolsTool::new()
+            .execute(&ToolArgs::from_args(&["src/point.rs"]), &state)
+            .unwrap();
+        assert!(result.success, "{}", result.message);
+        assert_eq!(
+            result.message,
+            "src/point.rs:\n  struct Point [1-1]\n  function Point::new [5-11]: fn new() -> Self\n  function Point::tiny [13-13]: fn tiny(&self)"
+        );
+
+        let result = ShowFunctionTool::new()
+            .execute(&ToolArgs::from_args(&["Point::new"]), &state)
+            .unwrap();
+        assert!(result.success, "{}", result.message);
+        assert!(result
+            .message
+            .starts_with("src/point.rs:4-11: function Point::new\n   4 |     #[inline]\n"));
+        let result = ShowFunctionTool::new()
+            .execute(&ToolArgs::from_args(&["tinny"]), &state)
+            .unwrap();
+        assert!(!result.success);
+        assert!(
+            result.message.contains("Did you mean: tiny?"),
+            "{}",
+            result.message
+        );
+
+        let result = GotoSymbolTool::new()
+            .execute(&ToolArgs::from_args(&["tiny", "src/point.rs"]), &state)
+            .unwrap();
+        assert!(result.success, "{}", result.message);
+        let guard = state.lock().unwrap();
+        assert_eq!(guard.current_file, Some(root.join("src/point.rs")));
+        assert_eq!(result.data.unwrap()["definition"]["line"], 13);
+    }
+}
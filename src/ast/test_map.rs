@@ -0,0 +1,618 @@
+//! Mapping source files to the tests that cover them
+//!
+//! A test file is linked to a source file when its name follows the language's
+//! convention for that file (`test_x.py`/`x_test.py`, `x.test.ts`/`x.spec.ts`,
+//! `x_test.go`, `tests/x.rs`) or when it imports it. Rust files with a `#[cfg(test)]`
+//! module count as tested by themselves. Source files that define functions but have
+//! none of these are reported as lacking tests, with the conventional place to add them.
+
+use super::imports::imported_files;
+use super::{definitions, is_test_path, source_files, Language};
+use crate::core::{DangerLevel, Tool, ToolArgs, ToolCategory, ToolError, ToolResult};
+use crate::editing::refactor::CrateLayout;
+use crate::state::ToolState;
+use crate::utils::suggest::path_not_found;
+use anyhow::Result;
+use regex::Regex;
+use serde::Serialize;
+use serde_json::json;
+use std::collections::{BTreeMap, BTreeSet};
+use std::path::{Path, PathBuf};
+use std::sync::{Arc, Mutex, OnceLock};
+
+/// Source files listed in the message; the data holds them all
+const MAX_LISTED: usize = 200;
+
+/// How a test file was linked to a source file
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Serialize)]
+#[serde(rename_all = "snake_case")]
+pub enum LinkKind {
+    /// The test file is named after the source file
+    Naming,
+    /// The test file imports the source file
+    Import,
+}
+
+/// A test file covering a source file
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+pub struct TestLink {
+    pub path: PathBuf,
+    pub via: Vec<LinkKind>,
+}
+
+/// A source file and the tests found for it
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+pub struct SourceTests {
+    pub path: PathBuf,
+    pub language: Language,
+    /// Functions and methods defined in the file
+    pub functions: usize,
+    /// Whether the file holds its own tests (a Rust `#[cfg(test)]` module)
+    pub inline: bool,
+    pub tests: Vec<TestLink>,
+}
+
+impl SourceTests {
+    /// Whether the file defines functions but no test was found for it
+    pub fn untested(&self) -> bool {
+        self.functions > 0 && !self.inline && self.tests.is_empty()
+    }
+}
+
+/// Source and test files of a tree, linked
+#[derive(Debug, Clone, Default, Serialize)]
+pub struct TestMap {
+    pub sources: Vec<SourceTests>,
+    /// Every test file found, whether or not it was linked
+    pub tests: Vec<PathBuf>,
+}
+
+/// Whether `path` holds tests: [`is_test_path`], plus Rust `tests.rs` modules and
+/// pytest's `conftest.py`
+fn is_test_file(path: &Path) -> bool {
+    let stem = path.file_stem().map(|s| s.to_string_lossy().into_owned());
+    is_test_path(path) || matches!(stem.as_deref(), Some("tests" | "test" | "conftest"))
+}
+
+/// Language family, so `a.test.js` can cover `a.ts` but not `a.py`
+fn family(language: Language) -> u8 {
+    match language {
+        Language::JavaScript | Language::TypeScript | Language::Tsx => 0,
+        Language::Rust => 1,
+        Language::Python => 2,
+        Language::Go => 3,
+    }
+}
+
+/// Name tests use to refer to a source file: its stem, or its directory for `mod.rs`,
+/// `__init__.py` and `index.js`
+fn subject(path: &Path) -> Option<String> {
+    let stem = path.file_stem()?.to_string_lossy();
+    match stem.as_ref() {
+        "mod" | "__init__" | "index" => path
+            .parent()?
+            .file_name()
+            .map(|d| d.to_string_lossy().into_owned()),
+        _ => Some(stem.into_owned()),
+    }
+}
+
+/// The source stem a test file is named after: `test_x.py`, `x_test.go`, `x.spec.ts`
+fn tested_subject(path: &Path) -> Option<String> {
+    let stem = path.file_stem()?.to_string_lossy();
+    let stem = stem
+        .strip_suffix(".test")
+        .or_else(|| stem.strip_suffix(".spec"))
+        .unwrap_or(&stem);
+    let stem = stem.strip_prefix("test_").unwrap_or(stem);
+    let stem = stem
+        .strip_suffix("_tests")
+        .or_else(|| stem.strip_suffix("_test"))
+        .unwrap_or(stem);
+    match stem {
+        "" | "test" | "tests" | "conftest" | "mod" | "__init__" | "index" => None,
+        _ => Some(stem.to_string()),
+    }
+}
+
+/// Library modules a Rust integration test reaches through `use <crate>::a::b`
+fn rust_crate_imports(file: &Path, source: &str) -> Vec<PathBuf> {
+    static USE: OnceLock<Regex> = OnceLock::new();
+    let Some(layout) = CrateLayout::find(file) else {
+        return Vec::new();
+    };
+    let uses = USE
+        .get_or_init(|| Regex::new(r"(?m)^\s*use\s+(\w+)((?:::\w+)*)").expect("valid use regex"));
+    let mut files = Vec::new();
+    for caps in uses.captures_iter(source) {
+        if caps[1] != layout.lib_name {
+            continue;
+        }
+        let mut segments: Vec<String> = caps[2]
+            .split("::")
+            .filter(|s| !s.is_empty())
+            .map(str::to_string)
+            .collect();
+        // The path may end in an item rather than a module; take the longest module
+        loop {
+            if let Some(path) = layout.module_file(&segments) {
+                files.push(path);
+                break;
+            }
+            if segments.pop().is_none() {
+                break;
+            }
+        }
+    }
+    files
+}
+
+/// Number of components `a` and `b` share from the start
+fn shared_prefix(a: &Path, b: &Path) -> usize {
+    a.components()
+        .zip(b.components())
+        .take_while(|(x, y)| x == y)
+        .count()
+}
+
+/// Link every source file under `root` to its tests
+pub fn test_map(root: &Path) -> TestMap {
+    let files = source_files(root, "");
+    let (tests, sources): (Vec<_>, Vec<_>) = files
+        .into_iter()
+        .partition(|(path, _, _)| is_test_file(path.strip_prefix(root).unwrap_or(path)));
+
+    let index: BTreeMap<&Path, usize> = sources
+        .iter()
+        .enumerate()
+        .map(|(i, (path, _, _))| (path.as_path(), i))
+        .collect();
+    let mut links: Vec<BTreeMap<PathBuf, BTreeSet<LinkKind>>> =
+        vec![BTreeMap::new(); sources.len()];
+
+    for (test, language, content) in &tests {
+        // Naming: the closest source file of the same family and subject
+        if let Some(name) = tested_subject(test) {
+            let candidates: Vec<usize> = sources
+                .iter()
+                .enumerate()
+                .filter(|(_, (path, source_language, _))| {
+                    family(*source_language) == family(*language)
+                        && subject(path).as_deref() == Some(name.as_str())
+                        // Go tests live in their package's directory
+                        && (*language != Language::Go || path.parent() == test.parent())
+                })
+                .map(|(i, _)| i)
+                .collect();
+            let closest = candidates
+                .iter()
+                .map(|&i| shared_prefix(&sources[i].0, test))
+                .max();
+            for &i in &candidates {
+                if Some(shared_prefix(&sources[i].0, test)) == closest {
+                    links[i]
+                        .entry(test.clone())
+                        .or_default()
+                        .insert(LinkKind::Naming);
+                }
+            }
+        }
+
+        // Imports; a Go test sees its whole package, which says nothing about coverage
+        let mut imported = imported_files(*language, test, content, root);
+        if *language == Language::Rust {
+            imported.extend(rust_crate_imports(test, content));
+        }
+        for path in imported {
+            if *language == Language::Go && path.parent() == test.parent() {
+                continue;
+            }
+            if let Some(&i) = index.get(path.as_path()) {
+                links[i]
+                    .entry(test.clone())
+                    .or_default()
+                    .insert(LinkKind::Import);
+            }
+        }
+    }
+
+    let sources = sources
+        .into_iter()
+        .zip(links)
+        .map(|((path, language, content), links)| SourceTests {
+            functions: definitions(language, &content)
+                .iter()
+                .filter(|d| d.kind == "function" || d.kind == "method")
+                .count(),
+            inline: language == Language::Rust
+                && (content.contains("#[cfg(test)]") || content.contains("#[test]")),
+            tests: links
+                .into_iter()
+                .map(|(path, via)| TestLink {
+                    path,
+                    via: via.into_iter().collect(),
+                })
+                .collect(),
+            path,
+            language,
+        })
+        .collect();
+    TestMap {
+        sources,
+        tests: tests.into_iter().map(|(path, _, _)| path).collect(),
+    }
+}
+
+/// Where tests for `path` conventionally go, relative to `root`
+pub fn suggested_test_path(root: &Path, path: &Path, language: Language) -> PathBuf {
+    let relative = path.strip_prefix(root).unwrap_or(path);
+    let stem = subject(path).unwrap_or_default();
+    let dir = relative.parent().unwrap_or(Path::new(""));
+    let extension = path
+        .extension()
+        .map(|e| e.to_string_lossy().into_owned())
+        .unwrap_or_default();
+    match language {
+        // Unit tests go in the file's own `#[cfg(test)] mod tests`
+        Language::Rust => relative.to_path_buf(),
+        // A top-level `tests/` package is used when Python tests already live there
+        Language::Python
+            if std::fs::read_dir(root.join("tests")).is_ok_and(|entries| {
+                entries
+                    .flatten()
+                    .any(|e| e.path().extension().is_some_and(|x| x == "py"))
+            }) =>
+        {
+            PathBuf::from("tests").join(format!("test_{}.py", stem))
+        }
+        Language::Python => dir.join(format!("test_{}.py", stem)),
+        Language::Go => dir.join(format!("{}_test.go", stem)),
+        Language::JavaScript | Language::TypeScript | Language::Tsx => {
+            dir.join(format!("{}.test.{}", stem, extension))
+        }
+    }
+}
+
+/// Tool mapping source files to their tests and listing the ones without
+pub struct TestMapTool {
+    name: String,
+}
+
+impl TestMapTool {
+    pub fn new() -> Self {
+        Self {
+            name: "test_map".to_string(),
+        }
+    }
+
+    fn describe(source: &SourceTests, root: &Path) -> String {
+        let relative = |path: &Path| {
+            path.strip_prefix(root)
+                .unwrap_or(path)
+                .display()
+                .to_string()
+        };
+        let mut parts: Vec<String> = source
+            .tests
+            .iter()
+            .map(|link| {
+                let via = link
+                    .via
+                    .iter()
+                    .map(|kind| match kind {
+                        LinkKind::Naming => "name",
+                        LinkKind::Import => "import",
+                    })
+                    .collect::<Vec<_>>()
+                    .join(", ");
+                format!("{} ({})", relative(&link.path), via)
+            })
+            .collect();
+        if source.inline {
+            parts.insert(0, "inline tests".to_string());
+        }
+        if parts.is_empty() {
+            return format!(
+                "  {} ({} functions) -> add tests in {}",
+                relative(&source.path),
+                source.functions,
+                suggested_test_path(root, &source.path, source.language).display()
+            );
+        }
+        format!("  {} <- {}", relative(&source.path), parts.join("; "))
+    }
+}
+
+impl Default for TestMapTool {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl Tool for TestMapTool {
+    fn name(&self) -> &str {
+        &self.name
+    }
+
+    fn description(&self) -> &str {
+        "Map source files to their test files (by naming convention and imports; Rust #[cfg(test)] modules count as tests) and list source files with functions but no tests, with the conventional place to add them. Pass a file or directory to narrow the report, --untested to list only files lacking tests"
+    }
+
+    fn signature(&self) -> &str {
+        "test_map [<path>] [--untested]"
+    }
+
+    fn category(&self) -> ToolCategory {
+        ToolCategory::Navigation
+    }
+
+    fn danger_level(&self) -> DangerLevel {
+        DangerLevel::ReadOnly
+    }
+
+    fn validate_args(&self, _args: &ToolArgs) -> Result<(), ToolError> {
+        Ok(())
+    }
+
+    fn execute(&mut self, args: &ToolArgs, state: &Arc<Mutex<ToolState>>) -> Result<ToolResult> {
+        let root = state
+            .lock()
+            .map_err(|e| anyhow::anyhow!("Failed to lock state: {}", e))?
+            .working_directory
+            .clone();
+        let path = args.get_named_arg("path").or_else(|| args.get_arg(0));
+        let untested_only = args.get_named_arg("untested").map(String::as_str) == Some("true");
+        let scope = path.map_or_else(|| root.clone(), |path| root.join(path));
+        if !scope.exists() {
+            return Ok(path_not_found(
+                format!("Path not found: {}", scope.display()),
+                &scope,
+            ));
+        }
+
+        let map = test_map(&root);
+        let relative = |path: &Path| path.strip_prefix(&root).unwrap_or(path).to_path_buf();
+
+        // A test file in scope reports the sources it covers
+        if scope.is_file() && map.tests.contains(&scope) {
+            let covered: Vec<&SourceTests> = map
+                .sources
+                .iter()
+                .filter(|source| source.tests.iter().any(|link| link.path == scope))
+                .collect();
+            let message = if covered.is_empty() {
+                format!(
+                    "{} is a test file, but no source file could be linked to it",
+                    relative(&scope).display()
+                )
+            } else {
+                format!(
+                    "{} tests:\n{}",
+                    relative(&scope).display(),
+                    covered
+                        .iter()
+                        .map(|source| format!("  {}", relative(&source.path).display()))
+                        .collect::<Vec<_>>()
+                        .join("\n")
+                )
+            };
+            return Ok(ToolResult::success_with_data(
+                message,
+                json!({
+                    "test": relative(&scope),
+                    "sources": covered.iter().map(|s| relative(&s.path)).collect::<Vec<_>>(),
+                }),
+            ));
+        }
+
+        let sources: Vec<&SourceTests> = map
+            .sources
+            .iter()
+            .filter(|source| source.path.starts_with(&scope))
+            .collect();
+        if sources.is_empty() {
+            return Ok(ToolResult::error(format!(
+                "No Rust, Python, JavaScript, TypeScript or Go source files under {}",
+                scope.display()
+            )));
+        }
+        let untested: Vec<&SourceTests> =
+            sources.iter().copied().filter(|s| s.untested()).collect();
+        let tested: Vec<&SourceTests> = sources
+            .iter()
+            .copied()
+            .filter(|s| s.inline || !s.tests.is_empty())
+            .collect();
+
+        let label = match relative(&scope) {
+            path if path.as_os_str().is_empty() => ".".to_string(),
+            path => path.display().to_string(),
+        };
+        let mut message = format!(
+            "{} source file(s) under {}: {} with tests, {} without, {} without functions",
+            sources.len(),
+            label,
+            tested.len(),
+            untested.len(),
+            sources.len() - tested.len() - untested.len()
+        );
+        let mut listed = 0;
+        let mut section = |title: &str, entries: &[&SourceTests], message: &mut String| {
+            if entries.is_empty() {
+                return;
+            }
+            message.push_str(&format!("\n\n{}:", title));
+            for source in entries {
+                if listed == MAX_LISTED {
+                    message.push_str("\n  ... (more in the result data)");
+                    return;
+                }
+                message.push('\n');
+                message.push_str(&Self::describe(source, &root));
+                listed += 1;
+            }
+        };
+        section("Without tests", &untested, &mut message);
+        if !untested_only {
+            section("Tested", &tested, &mut message);
+        }
+
+        let entry = |source: &SourceTests| {
+            json!({
+                "path": relative(&source.path),
+                "language": source.language,
+                "functions": source.functions,
+                "inline": source.inline,
+                "tests": source.tests.iter().map(|link| json!({
+                    "path": relative(&link.path),
+                    "via": link.via,
+                })).collect::<Vec<_>>(),
+                "suggested_test": source.untested().then(|| {
+                    suggested_test_path(&root, &source.path, source.language)
+                }),
+            })
+        };
+        let shown: Vec<&SourceTests> = if untested_only {
+            untested.clone()
+        } else {
+            sources.clone()
+        };
+        Ok(ToolResult::success_with_data(
+            message,
+            json!({
+                "sources": shown.iter().map(|s| entry(s)).collect::<Vec<_>>(),
+                "untested": untested.iter().map(|s| relative(&s.path)).collect::<Vec<_>>(),
+                "test_files": map.tests.iter().filter(|t| t.starts_with(&scope) || scope.is_file()).map(|t| relative(t)).collect::<Vec<_>>(),
+            }),
+        ))
+    }
+
+    fn get_parameters_schema(&self) -> serde_json::Value {
+        json!({
+            "type": "object",
+            "properties": {
+                "path": {
+                    "type": "string",
+                    "description": "File or directory to report on (default: the working directory); a test file lists the sources it covers"
+                },
+                "untested": {
+                    "type": "boolean",
+                    "description": "List only source files without tests",
+                    "default": false
+                }
+            }
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::fs;
+    use tempfile::TempDir;
+
+    fn write(root: &Path, path: &str, content: &str) {
+        let path = root.join(path);
+        fs::create_dir_all(path.parent().unwrap()).unwrap();
+        fs::write(path, content).unwrap();
+    }
+
+    #[test]
+    fn test_map_links_by_naming_and_imports() {
+        let dir = TempDir::new().unwrap();
+        let root = dir.path();
+        write(root, "Cargo.toml", "[package]\nname = \"demo\"\n");
+        write(
+            root,
+            "src/lib.rs",
+            "pub mod parse;\npub mod render;\npub mod util;\n",
+        );
+        write(root, "src/parse.rs", "pub fn parse() {}\n");
+        write(
+            root,
+            "src/render.rs",
+            "pub fn render() {}\n\n#[cfg(test)]\nmod tests {}\n",
+        );
+        write(root, "src/util.rs", "pub fn helper() {}\n");
+        write(
+            root,
+            "tests/parsing.rs",
+            "use demo::parse::parse;\n\n#[test]\nfn t() { parse() }\n",
+        );
+        write(root, "app/models.py", "def load():\n    pass\n");
+        write(root, "app/views.py", "def show():\n    pass\n");
+        write(root, "app/consts.py", "LIMIT = 3\n");
+        write(root, "app/test_views.py", "def test_show():\n    pass\n");
+        write(root, "web/a.ts", "export function a() {}\n");
+        write(root, "web/a.spec.ts", "import { a } from './a';\n");
+
+        let map = test_map(root);
+        let find = |path: &str| {
+            map.sources
+                .iter()
+                .find(|s| s.path == root.join(path))
+                .unwrap()
+        };
+        assert_eq!(find("src/parse.rs").tests[0].via, vec![LinkKind::Import]);
+        assert!(find("src/render.rs").inline);
+        assert!(find("src/util.rs").untested());
+        assert!(!find("src/lib.rs").untested());
+        assert_eq!(
+            find("app/views.py").tests,
+            vec![TestLink {
+                path: root.join("app/test_views.py"),
+                via: vec![LinkKind::Naming],
+            }]
+        );
+        assert!(find("app/models.py").untested());
+        assert!(!find("app/consts.py").untested());
+        assert_eq!(
+            find("web/a.ts").tests[0].via,
+            vec![LinkKind::Naming, LinkKind::Import]
+        );
+        assert_eq!(map.tests.len(), 3);
+        assert_eq!(
+            suggested_test_path(root, &root.join("app/models.py"), Language::Python),
+            PathBuf::from("app/test_models.py")
+        );
+    }
+
+    #[test]
+    fn test_test_map_tool() {
+        let dir = TempDir::new().unwrap();
+        let root = dir.path();
+        write(root, "pkg/server.go", "package pkg\n\nfunc Serve() {}\n");
+        write(
+            root,
+            "pkg/server_test.go",
+            "package pkg\n\nfunc TestServe(t *testing.T) {}\n",
+        );
+        write(root, "pkg/client.go", "package pkg\n\nfunc Dial() {}\n");
+        let mut tool_state = ToolState::new();
+        tool_state.working_directory = root.to_path_buf();
+        let state = Arc::new(Mutex::new(tool_state));
+        let mut tool = TestMapTool::new();
+
+        let mut named = std::collections::HashMap::new();
+        named.insert("untested".to_string(), "true".to_string());
+        let result = tool
+            .execute(&ToolArgs::with_named_args(vec![], named), &state)
+            .unwrap();
+        assert!(result.success, "{}", result.message);
+        assert!(result
+            .message
+            .contains("pkg/client.go (1 functions) -> add tests in pkg/client_test.go"));
+        assert!(!result.message.contains("server.go <-"));
+        let data = result.data.unwrap();
+        assert_eq!(data["untested"], json!(["pkg/client.go"]));
+
+        let result = tool
+            .execute(&ToolArgs::from_args(&["pkg/server_test.go"]), &state)
+            .unwrap();
+        assert!(
+            result.message.contains("pkg/server.go"),
+            "{}",
+            result.message
+        );
+    }
+}
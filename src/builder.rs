@@ -0,0 +1,826 @@
+//! Registry construction
+//!
+//! [`RegistryBuilder`] collects every setting a registry can start with and registers
+//! the built-in tools accordingly; the `create_tool_registry*` functions are shorthands
+//! for common builders. Settings come from code, from a [`Config`] (usually
+//! `cats.toml`) and from `CATS_*` environment variables, which containers can set
+//! without a config file:
+//!
+//! | Variable | Setting |
+//! |---|---|
+//! | `CATS_CONFIG` | Path of a `cats.toml` to load |
+//! | `CATS_SANDBOX_ROOT` | Working directory of every session, and the root file tools are confined to |
+//! | `CATS_OPEN_WINDOW_SIZE` | Default window of `open` |
+//! | `CATS_COMMAND_TIMEOUT` | Timeout of `run_command`, in seconds |
+//! | `CATS_DRY_RUN` | `true` or `false` |
+//! | `CATS_CATEGORIES` | Comma-separated tool categories to keep |
+//! | `CATS_MAX_DANGER_LEVEL` | Most severe danger level to keep |
+//! | `CATS_VERBOSITY` | `normal` or `compact` |
+//! | `CATS_REPEAT_THRESHOLD` | Identical calls before a loop warning; `0` turns it off |
+//! | `CATS_STRICT_ARGS` | `true` or `false` |
+//! | `CATS_PROVENANCE` | `true` or `false` |
+//!
+//! Explicit builder settings take precedence over the config; environment variables
+//! are applied by [`RegistryBuilder::env`] and override whatever was set before it.
+
+#[cfg(feature = "ast")]
+use crate::ast::{
+    GetDocsTool, GotoDefinitionTool, GotoSymbolTool, InspectTypeTool, ListSymbolsTool,
+    ShowFunctionTool, TestMapTool,
+};
+use crate::config::{Config, ShowConfigTool};
+use crate::core::{
+    BeforeToolHook, DangerLevel, ToolArgs, ToolCategory, ToolDescriptor, ToolError, ToolRegistry,
+    WorkspaceGuard, DEFAULT_SESSION,
+};
+#[cfg(feature = "data")]
+use crate::data::QueryDataTool;
+#[cfg(feature = "ast")]
+use crate::diagnostics::LocalizeTool;
+use crate::diagnostics::{AnalyzeLogTool, ExplainErrorsTool, TraceToSourceTool};
+use crate::editing::protection::ProtectedPaths;
+use crate::editing::{
+    ApplyPatchTool, CopyPathTool, CreateDirectoryTool, CreateFileTool, CreateMigrationTool,
+    CreateSymlinkTool, DeleteLineTool, DeletePathTool, DeleteTextTool, EnvDiffTool, EnvSyncTool,
+    FormatJsonTool, InsertTextTool, MovePathTool, OverwriteFileTool, PurgeTrashTool,
+    RenameModuleTool, ReplaceTextTool, SetPermissionsTool, TouchTool,
+};
+#[cfg(feature = "ast")]
+use crate::editing::{DeleteFunctionTool, UpdateCallersTool};
+#[cfg(feature = "ast")]
+use crate::execution::VerifyChangeTool;
+use crate::execution::{
+    AuditDependenciesTool, CompareBenchmarksTool, RunBenchmarksTool, RunCommandTool,
+    RunPrecommitTool, RunTestsTool, UpdateSnapshotsTool,
+};
+use crate::file_navigation::{CreateTool, GotoTool, OpenTool, ScrollTool};
+#[cfg(feature = "net")]
+use crate::forge::{CreatePrTool, FetchIssueTool, FetchPrTool};
+#[cfg(feature = "lsp")]
+use crate::lsp::{LspDefinitionTool, LspDiagnosticsTool, LspHoverTool, LspPool};
+use crate::messages::{MessageStyle, Verbosity};
+use crate::search::{FindFileTool, QueryJsonTool, SearchDirTool, SearchFileTool};
+use crate::state::{AnnotateHistoryTool, CreateScratchDirTool, RedoTool, StateTool, UndoTool};
+#[cfg(feature = "yaml")]
+use crate::utils::CiInfoTool;
+use crate::utils::{
+    ClassifyTaskTool, CountTokensTool, DependencyReportTool, DescribeToolTool, FilemapTool,
+    ListTasksTool, ReadDocTool, RepoBriefTool, SubmitTool, WorkspaceMembersTool,
+};
+#[cfg(feature = "git")]
+use crate::vcs::{CodeOwnersTool, ExportPatchTool, GitCommitTool, GitDiffTool, GitStatusTool};
+use std::path::PathBuf;
+use std::time::Duration;
+
+/// Prefix of the environment variables read by [`RegistryBuilder::env`]
+pub const ENV_PREFIX: &str = "CATS_";
+
+/// Builder for a [`ToolRegistry`] holding the built-in tools
+#[derive(Default)]
+pub struct RegistryBuilder {
+    config: Option<Config>,
+    open_window_size: Option<usize>,
+    command_timeout: Option<Duration>,
+    sandbox_root: Option<PathBuf>,
+    dry_run: bool,
+    categories: Option<Vec<ToolCategory>>,
+    max_danger_level: Option<DangerLevel>,
+    hooks: Vec<BeforeToolHook>,
+    message_style: Option<MessageStyle>,
+    verbosity: Option<Verbosity>,
+    repeat_threshold: Option<Option<usize>>,
+    strict_args: bool,
+    provenance: Option<bool>,
+    journal: Option<bool>,
+}
+
+impl RegistryBuilder {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Apply a workspace configuration; see [`crate::config`]
+    pub fn config(mut self, config: Config) -> Self {
+        self.config = Some(config);
+        self
+    }
+
+    /// Default number of lines `open` shows
+    pub fn open_window_size(mut self, lines: usize) -> Self {
+        self.open_window_size = Some(lines);
+        self
+    }
+
+    /// Timeout of `run_command`
+    pub fn command_timeout(mut self, timeout: Duration) -> Self {
+        self.command_timeout = Some(timeout);
+        self
+    }
+
+    /// Working directory of every session: commands run there, and navigation, editing
+    /// and search tools are refused paths outside it; see [`WorkspaceGuard`]
+    pub fn sandbox_root(mut self, root: impl Into<PathBuf>) -> Self {
+        self.sandbox_root = Some(root.into());
+        self
+    }
+
+    /// Record who changed which lines in `.cats/provenance.json`; see
+    /// [`ToolRegistry::set_provenance`]
+    pub fn provenance(mut self, on: bool) -> Self {
+        self.provenance = Some(on);
+        self
+    }
+
+    /// Journal file changes for `cats recover`; see [`ToolRegistry::set_journaling`]
+    pub fn journal(mut self, on: bool) -> Self {
+        self.journal = Some(on);
+        self
+    }
+
+    /// Report instead of running tools above `read_only`; see [`ToolRegistry::set_dry_run`]
+    pub fn dry_run(mut self, dry_run: bool) -> Self {
+        self.dry_run = dry_run;
+        self
+    }
+
+    /// Keep only the tools in `categories`
+    pub fn categories(mut self, categories: &[ToolCategory]) -> Self {
+        self.categories = Some(categories.to_vec());
+        self
+    }
+
+    /// Keep only the tools at most as dangerous as `level`, e.g. `ReadOnly` for a
+    /// reviewer agent
+    pub fn max_danger_level(mut self, level: DangerLevel) -> Self {
+        self.max_danger_level = Some(level);
+        self
+    }
+
+    /// Run `hook` before every tool call; see [`ToolRegistry::add_before_hook`]
+    pub fn before_hook(
+        mut self,
+        hook: impl Fn(&ToolDescriptor, &ToolArgs) -> Result<(), String> + Send + Sync + 'static,
+    ) -> Self {
+        self.hooks.push(Box::new(hook));
+        self
+    }
+
+    pub fn message_style(mut self, style: MessageStyle) -> Self {
+        self.message_style = Some(style);
+        self
+    }
+
+    pub fn verbosity(mut self, verbosity: Verbosity) -> Self {
+        self.verbosity = Some(verbosity);
+        self
+    }
+
+    /// See [`ToolRegistry::set_repeat_threshold`]
+    pub fn repeat_threshold(mut self, threshold: Option<usize>) -> Self {
+        self.repeat_threshold = Some(threshold);
+        self
+    }
+
+    /// Reject arguments that do not match the tool's schema; see
+    /// [`ToolRegistry::set_strict_args`]
+    pub fn strict_args(mut self, strict: bool) -> Self {
+        self.strict_args = strict;
+        self
+    }
+
+    /// Apply the `CATS_*` variables of the process environment
+    pub fn env(self) -> Result<Self, ToolError> {
+        self.env_vars(std::env::vars())
+    }
+
+    /// Apply `CATS_*` variables from `vars`, ignoring any others
+    pub fn env_vars<I, K, V>(mut self, vars: I) -> Result<Self, ToolError>
+    where
+        I: IntoIterator<Item = (K, V)>,
+        K: AsRef<str>,
+        V: AsRef<str>,
+    {
+        fn invalid(name: &str, value: &str, reason: impl std::fmt::Display) -> ToolError {
+            ToolError::InvalidArgs {
+                message: format!("Invalid {}{} '{}': {}", ENV_PREFIX, name, value, reason),
+            }
+        }
+
+        fn flag(name: &str, value: &str) -> Result<bool, ToolError> {
+            match value.to_ascii_lowercase().as_str() {
+                "1" | "true" | "yes" => Ok(true),
+                "0" | "false" | "no" | "" => Ok(false),
+                _ => Err(invalid(name, value, "expected true or false")),
+            }
+        }
+
+        let mut vars: Vec<(String, String)> = vars
+            .into_iter()
+            .filter_map(|(key, value)| {
+                let name = key.as_ref().strip_prefix(ENV_PREFIX)?.to_string();
+                Some((name, value.as_ref().trim().to_string()))
+            })
+            .collect();
+        // The config file first, so the other variables override it
+        vars.sort_by_key(|(name, _)| name != "CONFIG");
+
+        for (name, value) in vars {
+            match name.as_str() {
+                "CONFIG" => {
+                    let config = Config::load_file(std::path::Path::new(&value))
+                        .map_err(|e| invalid(&name, &value, format!("{:#}", e)))?;
+                    self.config = Some(config);
+                }
+                "SANDBOX_ROOT" => self.sandbox_root = Some(PathBuf::from(value)),
+                "OPEN_WINDOW_SIZE" => {
+                    let lines = value.parse().map_err(|e| invalid(&name, &value, e))?;
+                    self.open_window_size = Some(lines);
+                }
+                "COMMAND_TIMEOUT" => {
+                    let seconds = value.parse().map_err(|e| invalid(&name, &value, e))?;
+                    self.command_timeout = Some(Duration::from_secs(seconds));
+                }
+                "DRY_RUN" => self.dry_run = flag(&name, &value)?,
+                "STRICT_ARGS" => self.strict_args = flag(&name, &value)?,
+                "PROVENANCE" => self.provenance = Some(flag(&name, &value)?),
+                "JOURNAL" => self.journal = Some(flag(&name, &value)?),
+                "CATEGORIES" => {
+                    let categories = value
+                        .split(',')
+                        .map(str::trim)
+                        .filter(|c| !c.is_empty())
+                        .map(str::parse)
+                        .collect::<Result<Vec<ToolCategory>, _>>()?;
+                    self.categories = Some(categories);
+                }
+                "MAX_DANGER_LEVEL" => self.max_danger_level = Some(value.parse()?),
+                "VERBOSITY" => self.verbosity = Some(value.parse()?),
+                "REPEAT_THRESHOLD" => {
+                    let count: usize = value.parse().map_err(|e| invalid(&name, &value, e))?;
+                    self.repeat_threshold = Some((count > 0).then_some(count));
+                }
+                _ => {}
+            }
+        }
+        Ok(self)
+    }
+
+    /// Build the registry
+    pub fn build(self) -> ToolRegistry {
+        let config = self.config;
+        let open_window_size = self.open_window_size.or_else(|| {
+            config
+                .as_ref()
+                .and_then(|config| config.window.open_window_size)
+        });
+        let mut run_command = RunCommandTool::new_with_policy(
+            config
+                .as_ref()
+                .map(|config| config.commands.clone())
+                .unwrap_or_default(),
+        );
+        if let Some(timeout) = self.command_timeout {
+            run_command = run_command.with_timeout(timeout.as_secs());
+        }
+
+        let mut overwrite_file = OverwriteFileTool::new();
+        if let Some(percent) = config
+            .as_ref()
+            .and_then(|config| config.editing.max_shrink_percent)
+        {
+            overwrite_file = overwrite_file.with_max_shrink_percent(percent);
+        }
+        let provenance = self.provenance.unwrap_or_else(|| {
+            config
+                .as_ref()
+                .is_some_and(|config| config.editing.provenance)
+        });
+        let journal = self.journal.unwrap_or_else(|| {
+            config
+                .as_ref()
+                .and_then(|config| config.editing.journal)
+                .unwrap_or(false)
+        });
+
+        let mut registry = ToolRegistry::new();
+        register_tools(&mut registry, open_window_size, run_command, overwrite_file);
+
+        if let Some(config) = &config {
+            if let Some(filtering) = &config.search_filtering {
+                registry.set_search_filtering(filtering.clone());
+            }
+            if let Some(protection) = &config.protection {
+                registry.set_protected_paths(ProtectedPaths::from_config(protection));
+            }
+        }
+        // Issue and pull request tools, working on the repository `[forge]` names
+        #[cfg(feature = "net")]
+        {
+            let forge = config
+                .as_ref()
+                .map(|config| config.forge.clone())
+                .unwrap_or_default();
+            registry.register(Box::new(FetchIssueTool::new(forge.clone())));
+            registry.register(Box::new(FetchPrTool::new(forge.clone())));
+            registry.register(Box::new(CreatePrTool::new(forge)));
+        }
+        registry.register(Box::new(ShowConfigTool::new(config)));
+
+        if let Some(root) = self.sandbox_root {
+            registry.set_workspace_guard(Some(WorkspaceGuard::new(&root)));
+            registry
+                .session(DEFAULT_SESSION)
+                .set_working_directory(root);
+        }
+        if let Some(categories) = &self.categories {
+            registry.retain_categories(categories);
+        }
+        if let Some(level) = self.max_danger_level {
+            registry.retain(|tool| tool.danger_level() <= level);
+        }
+        for hook in self.hooks {
+            registry.add_before_hook(hook);
+        }
+        registry.set_dry_run(self.dry_run);
+        registry.set_strict_args(self.strict_args);
+        registry.set_provenance(provenance);
+        registry.set_journaling(journal);
+        if let Some(style) = self.message_style {
+            registry.set_message_style(style);
+        }
+        if let Some(verbosity) = self.verbosity {
+            registry.set_verbosity(verbosity);
+        }
+        if let Some(threshold) = self.repeat_threshold {
+            registry.set_repeat_threshold(threshold);
+        }
+        registry
+    }
+}
+
+/// Register the built-in tools
+fn register_tools(
+    registry: &mut ToolRegistry,
+    open_window_size: Option<usize>,
+    run_command: RunCommandTool,
+    overwrite_file: OverwriteFileTool,
+) {
+    // Command execution tool (NEW - replaces direct bash)
+    registry.register(Box::new(run_command));
+    registry.register(Box::new(RunTestsTool::new()));
+    registry.register(Box::new(UpdateSnapshotsTool::new()));
+    #[cfg(feature = "ast")]
+    registry.register(Box::new(VerifyChangeTool::new()));
+    registry.register(Box::new(RunBenchmarksTool::new()));
+    registry.register(Box::new(CompareBenchmarksTool::new()));
+    registry.register(Box::new(RunPrecommitTool::new()));
+    registry.register(Box::new(AuditDependenciesTool::new()));
+
+    // Diagnostics tools
+    registry.register(Box::new(ExplainErrorsTool::new()));
+    registry.register(Box::new(AnalyzeLogTool::new()));
+    registry.register(Box::new(TraceToSourceTool::new()));
+    #[cfg(feature = "ast")]
+    registry.register(Box::new(LocalizeTool::new()));
+
+    // Language server tools, sharing servers started on first use
+    #[cfg(feature = "lsp")]
+    {
+        let lsp = LspPool::new();
+        registry.register(Box::new(LspDiagnosticsTool::new(lsp.clone())));
+        registry.register(Box::new(LspHoverTool::new(lsp.clone())));
+        registry.register(Box::new(LspDefinitionTool::new(lsp)));
+    }
+
+    // Syntax-aware navigation that works without a language server
+    #[cfg(feature = "ast")]
+    {
+        registry.register(Box::new(GotoDefinitionTool::new()));
+        registry.register(Box::new(InspectTypeTool::new()));
+        registry.register(Box::new(GetDocsTool::new()));
+        registry.register(Box::new(ListSymbolsTool::new()));
+        registry.register(Box::new(GotoSymbolTool::new()));
+        registry.register(Box::new(ShowFunctionTool::new()));
+        registry.register(Box::new(TestMapTool::new()));
+    }
+
+    // File navigation tools
+    registry.register(Box::new(OpenTool::new_with_open_window_size(
+        open_window_size,
+    )));
+    registry.register(Box::new(GotoTool::new()));
+    registry.register(Box::new(ScrollTool::new("scroll_up", true)));
+    registry.register(Box::new(ScrollTool::new("scroll_down", false)));
+    registry.register(Box::new(CreateTool::new()));
+
+    // Search tools
+    registry.register(Box::new(FindFileTool::new()));
+    registry.register(Box::new(SearchFileTool::new()));
+    registry.register(Box::new(SearchDirTool::new()));
+    registry.register(Box::new(QueryJsonTool::new()));
+
+    // Editing tools - New specialized tools
+    registry.register(Box::new(CreateFileTool::new()));
+    registry.register(Box::new(ReplaceTextTool::new()));
+    registry.register(Box::new(InsertTextTool::new()));
+    registry.register(Box::new(DeleteTextTool::new()));
+    registry.register(Box::new(DeleteLineTool::new()));
+    registry.register(Box::new(overwrite_file));
+    #[cfg(feature = "ast")]
+    registry.register(Box::new(DeleteFunctionTool::new()));
+    registry.register(Box::new(ApplyPatchTool::new()));
+
+    // File management tools
+    registry.register(Box::new(DeletePathTool::new()));
+    registry.register(Box::new(PurgeTrashTool::new()));
+    registry.register(Box::new(MovePathTool::new()));
+    registry.register(Box::new(CopyPathTool::new()));
+    registry.register(Box::new(RenameModuleTool::new()));
+    #[cfg(feature = "ast")]
+    registry.register(Box::new(UpdateCallersTool::new()));
+    registry.register(Box::new(CreateDirectoryTool::new()));
+    registry.register(Box::new(SetPermissionsTool::new()));
+    registry.register(Box::new(TouchTool::new()));
+    registry.register(Box::new(EnvDiffTool::new()));
+    registry.register(Box::new(EnvSyncTool::new()));
+    registry.register(Box::new(CreateMigrationTool::new()));
+    registry.register(Box::new(FormatJsonTool::new()));
+    registry.register(Box::new(CreateSymlinkTool::new()));
+
+    // State management
+    registry.register(Box::new(StateTool::new()));
+    registry.register(Box::new(AnnotateHistoryTool::new()));
+    registry.register(Box::new(CreateScratchDirTool::new()));
+    registry.register(Box::new(UndoTool::new()));
+    registry.register(Box::new(RedoTool::new()));
+
+    // Version control tools
+    #[cfg(feature = "git")]
+    {
+        registry.register(Box::new(ExportPatchTool::new()));
+        registry.register(Box::new(CodeOwnersTool::new()));
+        registry.register(Box::new(GitStatusTool::new()));
+        registry.register(Box::new(GitDiffTool::new()));
+        registry.register(Box::new(GitCommitTool::new()));
+    }
+
+    // Utility tools
+    registry.register(Box::new(CountTokensTool::new()));
+    registry.register(Box::new(FilemapTool::new()));
+    #[cfg(feature = "yaml")]
+    registry.register(Box::new(CiInfoTool::new()));
+    registry.register(Box::new(ListTasksTool::new()));
+    registry.register(Box::new(WorkspaceMembersTool::new()));
+    registry.register(Box::new(DependencyReportTool::new()));
+    registry.register(Box::new(RepoBriefTool::new()));
+    registry.register(Box::new(ReadDocTool::new()));
+    #[cfg(feature = "data")]
+    registry.register(Box::new(QueryDataTool::new()));
+    registry.register(Box::new(SubmitTool::new()));
+    registry.register(Box::new(ClassifyTaskTool::new()));
+    registry.register(Box::new(DescribeToolTool::new(registry.catalog())));
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_env_overrides() {
+        let temp = tempfile::TempDir::new().unwrap();
+        let config = temp.path().join("agent.toml");
+        std::fs::write(&config, "[window]\nopen_window_size = 10\n").unwrap();
+
+        let builder = RegistryBuilder::new()
+            .open_window_size(50)
+            .env_vars([
+                ("CATS_OPEN_WINDOW_SIZE", "20"),
+                ("CATS_CONFIG", config.to_str().unwrap()),
+                ("CATS_DRY_RUN", "true"),
+                ("CATS_CATEGORIES", "search, navigation"),
+                ("CATS_VERBOSITY", "compact"),
+                ("CATS_REPEAT_THRESHOLD", "0"),
+                ("CATS_STRICT_ARGS", "yes"),
+                ("HOME", "/root"),
+            ])
+            .unwrap();
+        assert_eq!(builder.open_window_size, Some(20));
+        assert!(builder.config.unwrap().source.is_some());
+        assert!(builder.dry_run);
+        assert_eq!(
+            builder.categories,
+            Some(vec![ToolCategory::Search, ToolCategory::Navigation])
+        );
+        assert_eq!(builder.verbosity, Some(Verbosity::Compact));
+        assert_eq!(builder.repeat_threshold, Some(None));
+        assert!(builder.strict_args);
+
+        let error = RegistryBuilder::new()
+            .env_vars([("CATS_COMMAND_TIMEOUT", "soon")])
+            .err()
+            .unwrap();
+        assert!(error.to_string().contains("CATS_COMMAND_TIMEOUT 'soon'"));
+        assert!(RegistryBuilder::new()
+            .env_vars([("CATS_MAX_DANGER_LEVEL", "harmless")])
+            .is_err());
+        assert!(RegistryBuilder::new()
+            .env_vars([("CATS_STRICT_ARGS", "maybe")])
+            .is_err());
+    }
+
+    #[test]
+    fn test_builder_profile_hooks_and_dry_run() {
+        let temp = tempfile::TempDir::new().unwrap();
+        let file = temp.path().join("a.txt");
+        std::fs::write(&file, "keep\n").unwrap();
+        let path = file.to_string_lossy().to_string();
+
+        let mut reviewer = RegistryBuilder::new()
+            .max_danger_level(DangerLevel::ReadOnly)
+            .build();
+        assert!(reviewer.list_tools().contains(&"search_dir".to_string()));
+        assert!(!reviewer.list_tools().contains(&"delete_path".to_string()));
+        assert!(reviewer
+            .execute_tool("run_command", &ToolArgs::from_args(&["true"]))
+            .is_err());
+
+        let mut dry = RegistryBuilder::new()
+            .sandbox_root(temp.path())
+            .dry_run(true)
+            .before_hook(|tool, _| match tool.name.as_str() {
+                "move_path" => Err("moves need approval".to_string()),
+                _ => Ok(()),
+            })
+            .build();
+        let deleted = dry
+            .execute_tool("delete_path", &ToolArgs::from_args(&[&path]))
+            .unwrap();
+        assert!(deleted.success);
+        assert_eq!(deleted.data.unwrap()["dry_run"], true);
+        assert!(file.exists());
+
+        let moved = dry
+            .execute_tool("move_path", &ToolArgs::from_args(&[&path, "b.txt"]))
+            .unwrap();
+        assert!(!moved.success);
+        assert!(moved.message.contains("moves need approval"));
+
+        let command = dry
+            .execute_tool("run_command", &ToolArgs::from_args(&["touch b.txt"]))
+            .unwrap();
+        assert_eq!(command.data.unwrap()["dry_run"], true);
+        assert!(!temp.path().join("b.txt").exists());
+
+        // Read-only tools still run, in the sandbox root
+        let state = dry
+            .execute_tool("_state", &ToolArgs::from_args(&[]))
+            .unwrap();
+        assert!(state.success);
+        assert!(state.data.unwrap().get("dry_run").is_none());
+        assert!(state.message.contains(&temp.path().display().to_string()));
+    }
+
+    #[test]
+    fn test_builder_sandbox_root_confines_paths() {
+        let root = tempfile::TempDir::new().unwrap();
+        let elsewhere = tempfile::TempDir::new().unwrap();
+        let inside = root.path().join("a.txt");
+        let outside = elsewhere.path().join("b.txt");
+        std::fs::write(&inside, "a\n").unwrap();
+        std::fs::write(&outside, "b\n").unwrap();
+        let mut registry = RegistryBuilder::new().sandbox_root(root.path()).build();
+        assert!(registry.workspace_guard().is_some());
+
+        let refused = registry
+            .execute_tool("open", &ToolArgs::from_args(&["/etc/hosts"]))
+            .unwrap();
+        assert!(!refused.success);
+        assert!(refused
+            .message
+            .contains("/etc/hosts is outside the workspace root"));
+        assert_eq!(refused.data.unwrap()["refused"], true);
+
+        let escape = format!("{}/../{}", root.path().display(), "x.txt");
+        for path in [outside.to_string_lossy().to_string(), escape] {
+            let result = registry
+                .execute_tool("delete_path", &ToolArgs::from_args(&[&path]))
+                .unwrap();
+            assert!(!result.success, "{}", path);
+        }
+        assert!(outside.exists());
+
+        let copied = registry
+            .execute_tool(
+                "copy_path",
+                &ToolArgs::from_args(&[&inside.to_string_lossy(), &outside.to_string_lossy()]),
+            )
+            .unwrap();
+        assert!(!copied.success);
+        assert_eq!(std::fs::read_to_string(&outside).unwrap(), "b\n");
+
+        let deleted = registry
+            .execute_tool(
+                "delete_path",
+                &ToolArgs::from_args(&[&inside.to_string_lossy()]),
+            )
+            .unwrap();
+        assert!(deleted.success, "{}", deleted.message);
+        assert!(!inside.exists());
+    }
+
+    #[test]
+    fn test_builder_sandbox_root_confines_patch_headers() {
+        let root = tempfile::TempDir::new().unwrap();
+        let elsewhere = tempfile::TempDir::new().unwrap();
+        let absolute = elsewhere.path().join("absolute.txt");
+        let mut registry = RegistryBuilder::new().sandbox_root(root.path()).build();
+
+        let relative = "../escaped.txt".to_string();
+        for path in [relative, absolute.to_string_lossy().to_string()] {
+            let patch = format!("--- /dev/null\n+++ b/{}\n@@ -0,0 +1 @@\n+escaped\n", path);
+            let mut args = ToolArgs::from_args(&[]);
+            args.named_args.insert("patch".to_string(), patch);
+            let result = registry.execute_tool("apply_patch", &args).unwrap();
+            assert!(!result.success, "{}", path);
+            assert_eq!(result.data.unwrap()["refused"], true, "{}", path);
+        }
+        assert!(!absolute.exists());
+        assert!(!std::env::current_dir()
+            .unwrap()
+            .join("../escaped.txt")
+            .exists());
+    }
+
+    #[test]
+    fn test_builder_sandbox_root_resolves_relative_paths_in_the_root() {
+        // The test process runs in the crate directory, not in the sandbox root
+        let root = tempfile::TempDir::new().unwrap();
+        assert_ne!(std::env::current_dir().unwrap(), root.path());
+        let mut registry = RegistryBuilder::new().sandbox_root(root.path()).build();
+
+        let mut args = ToolArgs::from_args(&[]);
+        args.named_args.insert(
+            "patch".to_string(),
+            "--- /dev/null\n+++ b/new.txt\n@@ -0,0 +1 @@\n+new\n".to_string(),
+        );
+        let patched = registry.execute_tool("apply_patch", &args).unwrap();
+        assert!(patched.success, "{}", patched.message);
+        assert!(root.path().join("new.txt").is_file());
+
+        let created = registry
+            .execute_tool("create_file", &ToolArgs::from_args(&["rel.txt", "rel\n"]))
+            .unwrap();
+        assert!(created.success, "{}", created.message);
+        assert!(root.path().join("rel.txt").is_file());
+
+        let counted = registry
+            .execute_tool("count_tokens", &ToolArgs::from_args(&["rel.txt"]))
+            .unwrap();
+        assert!(counted.success, "{}", counted.message);
+
+        let escaped = registry
+            .execute_tool("create_file", &ToolArgs::from_args(&["../out.txt", "x\n"]))
+            .unwrap();
+        assert_eq!(escaped.data.unwrap()["refused"], true);
+    }
+
+    #[test]
+    fn test_builder_sandbox_root_confines_utility_tools() {
+        let root = tempfile::TempDir::new().unwrap();
+        let mut registry = RegistryBuilder::new().sandbox_root(root.path()).build();
+        for (tool, args) in [
+            ("filemap", vec!["/etc/hostname"]),
+            ("count_tokens", vec!["/etc/passwd"]),
+            ("read_doc", vec!["--path=/etc/passwd"]),
+            ("analyze_log", vec!["/etc/passwd"]),
+            ("run_tests", vec!["/etc"]),
+        ] {
+            let result = registry
+                .execute_tool(tool, &ToolArgs::from_args(&args))
+                .unwrap();
+            assert!(!result.success, "{}", tool);
+            assert_eq!(result.data.unwrap()["refused"], true, "{}", tool);
+        }
+    }
+
+    #[test]
+    fn test_builder_sandbox_root_checks_every_path_parameter() {
+        let root = tempfile::TempDir::new().unwrap();
+        let elsewhere = tempfile::TempDir::new().unwrap();
+        let outside = elsewhere.path().join("x").to_string_lossy().to_string();
+        let registry = RegistryBuilder::new().sandbox_root(root.path()).build();
+        let guard = registry.workspace_guard().unwrap();
+        let mut unchecked = Vec::new();
+        // String parameters that mention files but hold no path of their own
+        let not_paths = [
+            ("apply_patch", "patch"),
+            ("create_pr", "body"),
+            ("create_scratch_dir", "label"),
+            ("export_patch", "body"),
+            ("filemap", "diagram"),
+            ("find_file", "file_name"),
+            ("git_commit", "message"),
+            ("inspect_type", "type_name"),
+            ("query_json", "expression"),
+            ("run_benchmarks", "package"),
+            ("run_tests", "framework"),
+            ("run_tests", "package"),
+            ("search_dir", "package"),
+        ];
+
+        for name in registry.list_tools() {
+            let tool = registry.get_tool(&name).unwrap();
+            let schema = tool.get_parameters_schema();
+            let Some(properties) = schema["properties"].as_object() else {
+                continue;
+            };
+            for (param, spec) in properties {
+                let description = spec["description"].as_str().unwrap_or_default();
+                let mentions_path = ["path", "file", "directory"]
+                    .iter()
+                    .any(|word| description.to_lowercase().contains(word));
+                if spec["type"] != "string"
+                    || !mentions_path
+                    || not_paths.contains(&(name.as_str(), param.as_str()))
+                {
+                    continue;
+                }
+                let mut args = ToolArgs::from_args(&[]);
+                args.named_args.insert(param.clone(), outside.clone());
+                if guard.escaping_paths(tool, &args, root.path()).is_empty() {
+                    unchecked.push(format!("{}.{}", name, param));
+                }
+            }
+        }
+        assert!(unchecked.is_empty(), "not checked: {:?}", unchecked);
+    }
+
+    #[test]
+    fn test_builder_journal_is_opt_in() {
+        let temp = tempfile::TempDir::new().unwrap();
+        let journal_dir = temp.path().join(crate::state::journal::JOURNAL_DIR);
+        let write = |registry: &mut ToolRegistry, name: &str| {
+            let path = temp.path().join(name);
+            let result = registry
+                .execute_tool(
+                    "create_file",
+                    &ToolArgs::from_args(&[&path.to_string_lossy(), "one\n"]),
+                )
+                .unwrap();
+            assert!(result.success, "{}", result.message);
+        };
+
+        let mut registry = RegistryBuilder::new().sandbox_root(temp.path()).build();
+        write(&mut registry, "a.txt");
+        assert!(!journal_dir.exists());
+
+        let mut registry = RegistryBuilder::new()
+            .sandbox_root(temp.path())
+            .env_vars([("CATS_JOURNAL", "1")])
+            .unwrap()
+            .build();
+        write(&mut registry, "b.txt");
+        assert_eq!(std::fs::read_dir(&journal_dir).unwrap().count(), 1);
+    }
+
+    #[test]
+    fn test_builder_provenance_records_calls() {
+        let temp = tempfile::TempDir::new().unwrap();
+        let root = temp.path().canonicalize().unwrap();
+        let file = root.join("notes.md");
+        let mut registry = RegistryBuilder::new()
+            .sandbox_root(&root)
+            .provenance(true)
+            .build();
+
+        let mut args = ToolArgs::from_args(&[&file.to_string_lossy(), "one\ntwo\n"]);
+        args.named_args
+            .insert(crate::core::CALL_ID_ARG.to_string(), "call_1".to_string());
+        let created = registry.execute_tool("create_file", &args).unwrap();
+        assert!(created.success, "{}", created.message);
+        assert_eq!(created.data.unwrap()["provenance"]["call_id"], "call_1");
+        let replaced = registry
+            .execute_tool(
+                "replace_text",
+                &ToolArgs::from_args(&[&file.to_string_lossy(), "two", "2"]),
+            )
+            .unwrap();
+        assert!(replaced.success, "{}", replaced.message);
+
+        let log = crate::state::provenance::ProvenanceLog::load(&root).unwrap();
+        let records: Vec<_> = log
+            .records
+            .iter()
+            .map(|r| (r.path.as_str(), r.tool.as_str(), r.regions[0].start))
+            .collect();
+        assert_eq!(
+            records,
+            [
+                ("notes.md", "create_file", 1),
+                ("notes.md", "replace_text", 2)
+            ]
+        );
+        assert_eq!(log.records[0].call_id, "call_1");
+        assert_eq!(log.records[1].session, DEFAULT_SESSION);
+    }
+}
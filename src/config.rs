@@ -0,0 +1,421 @@
+//! Workspace configuration from `cats.toml`
+//!
+//! A [`Config`] collects the settings a workspace can pin in one file at its root:
+//!
+//! ```toml
+//! [search_filtering]
+//! exclude_dirs = ["target", "vendor"]
+//!
+//! [protection]
+//! paths = ["Cargo.lock", "migrations/**"]
+//!
+//! [window]
+//! open_window_size = 200
+//!
+//! [commands]
+//! deny = ["git push", "docker"]
+//!
+//! [formatters]
+//! rs = "rustfmt {path}"
+//!
+//! [lint]
+//! py = "ruff check {path}"
+//!
+//! [forge]
+//! repository = "owner/name"
+//! token_env = "GITHUB_TOKEN"
+//!
+//! [verify]
+//! steps = ["syntax", "check"]
+//! check = { ts = "npx tsc --noEmit -p ." }
+//!
+//! [editing]
+//! max_shrink_percent = 70
+//! provenance = true
+//! journal = true
+//! ```
+//!
+//! Every section is optional. [`Config::load`] reads the file and
+//! `create_tool_registry_with_config` applies it: search filters and protected paths
+//! to every session, the window size to `open`, the deny list to `run_command`.
+//! Formatter and linter commands are keyed by file extension and looked up with
+//! [`Config::formatter_for`] and [`Config::linter_for`]. The `[forge]` table points
+//! `fetch_issue`, `fetch_pr` and `create_pr` (`net` feature) at a GitHub or GitLab
+//! repository. The `[verify]` table picks the steps `verify_change` runs and the
+//! check command per file extension. The `[editing]` table sets how much
+//! `overwrite_file` may shrink a file without confirmation and turns on provenance
+//! records and the operation journal. The `show_config` tool reports the configuration in effect.
+
+use crate::core::{DangerLevel, Tool, ToolArgs, ToolError, ToolResult};
+use crate::editing::protection::ProtectionConfig;
+use crate::search::SearchFilteringConfig;
+use crate::state::ToolState;
+use anyhow::{Context, Result};
+use serde::{Deserialize, Serialize};
+use std::collections::BTreeMap;
+use std::path::{Path, PathBuf};
+use std::sync::{Arc, Mutex};
+
+/// Name of the workspace configuration file
+pub const CONFIG_FILE: &str = "cats.toml";
+
+/// `[window]` table: sizes of rendered file windows
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct WindowConfig {
+    /// Lines `open` shows when the call does not pass `window_size`
+    pub open_window_size: Option<usize>,
+}
+
+/// `[commands]` table: which shell commands `run_command` refuses
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct CommandPolicy {
+    /// Commands containing any of these strings (case-insensitive) are refused
+    pub deny: Vec<String>,
+}
+
+impl CommandPolicy {
+    /// The deny entry `command` matches, if any
+    pub fn denied_pattern(&self, command: &str) -> Option<&str> {
+        let command = command.to_lowercase();
+        self.deny
+            .iter()
+            .find(|pattern| !pattern.is_empty() && command.contains(&pattern.to_lowercase()))
+            .map(String::as_str)
+    }
+}
+
+/// `[forge]` table: the GitHub or GitLab repository the forge tools work on
+///
+/// Unset values are detected from the `origin` remote of the working directory.
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct ForgeConfig {
+    /// `github` or `gitlab`
+    pub provider: Option<String>,
+    /// `owner/name`, or `group/subgroup/name` on GitLab
+    pub repository: Option<String>,
+    /// API base URL, for GitHub Enterprise or a self-hosted GitLab
+    pub api_url: Option<String>,
+    /// Environment variable holding the access token, `GITHUB_TOKEN` or `GITLAB_TOKEN`
+    /// by default; the token itself never goes in the file
+    pub token_env: Option<String>,
+}
+
+/// `[verify]` table: the pipeline `verify_change` runs after an edit
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct VerifyConfig {
+    /// Steps to run, from `syntax`, `check` and `tests`; all of them when empty
+    pub steps: Vec<String>,
+    /// Check command per file extension, replacing the built-in `cargo check`;
+    /// `{path}` is the file and `{package}` its workspace package
+    pub check: BTreeMap<String, String>,
+}
+
+/// `[editing]` table: safety limits and bookkeeping of the editing tools
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct EditingConfig {
+    /// Largest share of its lines, in percent, that `overwrite_file` may remove from a
+    /// file without `allow_shrink`; 50 by default, 100 turns the check off
+    pub max_shrink_percent: Option<u8>,
+    /// Attribute every file change to its session and tool call in
+    /// `.cats/provenance.json`
+    pub provenance: bool,
+    /// Journal file changes under `.cats/journal/` for `cats recover`; off for
+    /// embedders and on in the CLI unless set
+    pub journal: Option<bool>,
+}
+
+/// Settings of a workspace, usually loaded from `cats.toml`
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+#[serde(default)]
+pub struct Config {
+    /// `[search_filtering]`: paths searches and listings skip
+    pub search_filtering: Option<SearchFilteringConfig>,
+    /// `[protection]`: paths destructive tools refuse to touch
+    pub protection: Option<ProtectionConfig>,
+    pub window: WindowConfig,
+    pub commands: CommandPolicy,
+    /// `[formatters]`: formatter command per file extension; `{path}` is the file
+    pub formatters: BTreeMap<String, String>,
+    /// `[lint]`: linter command per file extension; `{path}` is the file
+    pub lint: BTreeMap<String, String>,
+    pub forge: ForgeConfig,
+    pub verify: VerifyConfig,
+    pub editing: EditingConfig,
+    /// File the configuration was read from
+    #[serde(skip)]
+    pub source: Option<PathBuf>,
+}
+
+impl Config {
+    /// Load `cats.toml` from `workspace_root`, or the defaults if there is none
+    pub fn load(workspace_root: &Path) -> Result<Self> {
+        let path = workspace_root.join(CONFIG_FILE);
+        if !path.exists() {
+            return Ok(Self::default());
+        }
+        Self::load_file(&path)
+    }
+
+    /// Load a configuration file at any path
+    pub fn load_file(path: &Path) -> Result<Self> {
+        let content = std::fs::read_to_string(path)
+            .with_context(|| format!("Failed to read {}", path.display()))?;
+        let mut config = Self::from_toml_str(&content)
+            .with_context(|| format!("Failed to parse {}", path.display()))?;
+        config.source = Some(path.to_path_buf());
+        Ok(config)
+    }
+
+    /// Parse a configuration from TOML text
+    pub fn from_toml_str(content: &str) -> Result<Self> {
+        Ok(toml::from_str(content)?)
+    }
+
+    /// The configuration as TOML text
+    pub fn to_toml_string(&self) -> Result<String> {
+        Ok(toml::to_string_pretty(self)?)
+    }
+
+    /// Formatter command for `path`, with `{path}` filled in
+    pub fn formatter_for(&self, path: &Path) -> Option<String> {
+        Self::command_for(&self.formatters, path)
+    }
+
+    /// Linter command for `path`, with `{path}` filled in
+    pub fn linter_for(&self, path: &Path) -> Option<String> {
+        Self::command_for(&self.lint, path)
+    }
+
+    fn command_for(commands: &BTreeMap<String, String>, path: &Path) -> Option<String> {
+        let extension = path.extension()?.to_str()?;
+        let command = commands.get(extension)?;
+        Some(command.replace("{path}", &path.to_string_lossy()))
+    }
+}
+
+/// Tool that shows the workspace configuration in effect
+pub struct ShowConfigTool {
+    name: String,
+    /// Configuration the registry was built with; when unset, `cats.toml` is read from
+    /// the session's working directory
+    config: Option<Config>,
+}
+
+impl ShowConfigTool {
+    pub fn new(config: Option<Config>) -> Self {
+        Self {
+            name: "show_config".to_string(),
+            config,
+        }
+    }
+}
+
+impl Default for ShowConfigTool {
+    fn default() -> Self {
+        Self::new(None)
+    }
+}
+
+impl Tool for ShowConfigTool {
+    fn name(&self) -> &str {
+        &self.name
+    }
+
+    fn description(&self) -> &str {
+        "Show the workspace configuration (cats.toml) in effect: search filters, protected paths, window size, command policy, formatters, linters and forge repository"
+    }
+
+    fn signature(&self) -> &str {
+        "show_config"
+    }
+
+    fn danger_level(&self) -> DangerLevel {
+        DangerLevel::ReadOnly
+    }
+
+    fn validate_args(&self, _args: &ToolArgs) -> Result<(), ToolError> {
+        Ok(())
+    }
+
+    fn execute(&mut self, _args: &ToolArgs, state: &Arc<Mutex<ToolState>>) -> Result<ToolResult> {
+        let mut state = state
+            .lock()
+            .map_err(|e| anyhow::anyhow!("Failed to lock state: {}", e))?;
+        let config = match &self.config {
+            Some(config) => config.clone(),
+            None => match Config::load(&state.working_directory) {
+                Ok(config) => config,
+                Err(e) => return Ok(ToolResult::error(format!("{:#}", e))),
+            },
+        };
+
+        let protected: Vec<String> = state
+            .protected_paths()
+            .patterns()
+            .into_iter()
+            .map(str::to_string)
+            .collect();
+        let filtering = state.search_filter().config;
+
+        let mut message = match &config.source {
+            Some(path) => format!("Configuration from {}:\n", path.display()),
+            None if self.config.is_some() => "Configuration passed to the registry:\n".to_string(),
+            None => format!(
+                "No {} in {}; using defaults\n",
+                CONFIG_FILE,
+                state.working_directory.display()
+            ),
+        };
+        let text = config.to_toml_string()?;
+        if !text.trim().is_empty() {
+            message.push_str(&text);
+            if !text.ends_with('\n') {
+                message.push('\n');
+            }
+        }
+        message.push_str(&format!(
+            "\nIn effect:\n  Excluded directories: {}\n  Protected paths: {}",
+            filtering
+                .exclude_dirs
+                .clone()
+                .unwrap_or_default()
+                .join(", "),
+            protected.join(", ")
+        ));
+
+        Ok(ToolResult::success_with_data(
+            message,
+            serde_json::json!({
+                "source": config.source,
+                "config": config,
+                "effective": {
+                    "search_filtering": filtering,
+                    "protected_paths": protected
+                }
+            }),
+        ))
+    }
+
+    fn get_parameters_schema(&self) -> serde_json::Value {
+        serde_json::json!({
+            "type": "object",
+            "properties": {},
+            "required": []
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const SAMPLE: &str = r#"
+[search_filtering]
+exclude_dirs = ["vendor"]
+
+[protection]
+paths = ["Cargo.lock"]
+include_defaults = false
+
+[window]
+open_window_size = 40
+
+[commands]
+deny = ["git push"]
+
+[formatters]
+rs = "rustfmt {path}"
+
+[lint]
+py = "ruff check {path}"
+
+[forge]
+repository = "acme/widgets"
+token_env = "ACME_TOKEN"
+
+[verify]
+steps = ["syntax", "check"]
+check = { py = "mypy {path}" }
+
+[editing]
+max_shrink_percent = 80
+provenance = true
+journal = false
+"#;
+
+    #[test]
+    fn test_load_cats_toml() {
+        let temp = tempfile::TempDir::new().unwrap();
+        let missing = Config::load(temp.path()).unwrap();
+        assert!(missing.source.is_none());
+        assert!(missing.commands.deny.is_empty());
+
+        std::fs::write(temp.path().join(CONFIG_FILE), SAMPLE).unwrap();
+        let config = Config::load(temp.path()).unwrap();
+        assert_eq!(config.source, Some(temp.path().join(CONFIG_FILE)));
+        assert_eq!(
+            config.search_filtering.as_ref().unwrap().exclude_dirs,
+            Some(vec!["vendor".to_string()])
+        );
+        assert_eq!(
+            config.protection.as_ref().unwrap().paths,
+            vec!["Cargo.lock"]
+        );
+        assert_eq!(config.window.open_window_size, Some(40));
+        assert_eq!(
+            config.commands.denied_pattern("GIT PUSH origin main"),
+            Some("git push")
+        );
+        assert_eq!(config.commands.denied_pattern("git status"), None);
+        assert_eq!(
+            config.formatter_for(Path::new("src/lib.rs")).as_deref(),
+            Some("rustfmt src/lib.rs")
+        );
+        assert_eq!(
+            config.linter_for(Path::new("a.py")).as_deref(),
+            Some("ruff check a.py")
+        );
+        assert_eq!(config.linter_for(Path::new("a.rs")), None);
+        assert_eq!(config.forge.repository.as_deref(), Some("acme/widgets"));
+        assert_eq!(config.forge.provider, None);
+        assert_eq!(config.verify.steps, vec!["syntax", "check"]);
+        assert_eq!(config.verify.check["py"], "mypy {path}");
+        assert_eq!(config.editing.max_shrink_percent, Some(80));
+        assert!(config.editing.provenance);
+        assert_eq!(config.editing.journal, Some(false));
+
+        // The TOML rendering parses back to the same settings
+        let round_trip = Config::from_toml_str(&config.to_toml_string().unwrap()).unwrap();
+        assert_eq!(round_trip.window, config.window);
+        assert_eq!(round_trip.formatters, config.formatters);
+        assert_eq!(round_trip.forge, config.forge);
+
+        std::fs::write(temp.path().join(CONFIG_FILE), "[window\n").unwrap();
+        let error = Config::load(temp.path()).unwrap_err();
+        assert!(format!("{:#}", error).contains("Failed to parse"));
+    }
+
+    #[test]
+    fn test_show_config_reads_working_directory() {
+        let temp = tempfile::TempDir::new().unwrap();
+        std::fs::write(temp.path().join(CONFIG_FILE), SAMPLE).unwrap();
+        let mut tool_state = ToolState::new();
+        tool_state.set_working_directory(temp.path());
+        let state = Arc::new(Mutex::new(tool_state));
+
+        let result = ShowConfigTool::default()
+            .execute(&ToolArgs::from_args(&[]), &state)
+            .unwrap();
+        assert!(result.success, "{}", result.message);
+        assert!(result.message.contains("open_window_size = 40"));
+        assert!(result.message.contains("Protected paths: Cargo.lock"));
+        let data = result.data.unwrap();
+        assert_eq!(data["config"]["commands"]["deny"][0], "git push");
+        assert_eq!(data["effective"]["protected_paths"][0], "Cargo.lock");
+    }
+}
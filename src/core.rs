@@ -1,9 +1,13 @@
 //! Core traits and types for the simpaticoder tools system
 
+use crate::messages::Verbosity;
+use crate::stream::{ResultChunk, ResultStream, StreamControl};
 use anyhow::Result;
 use serde::{Deserialize, Serialize};
+use std::collections::BTreeMap;
 use std::collections::HashMap;
-use std::sync::{Arc, Mutex};
+use std::path::{Path, PathBuf};
+use std::sync::{Arc, Mutex, RwLock};
 use thiserror::Error;
 
 /// Error types for tool operations
@@ -13,8 +17,14 @@ pub enum ToolError {
     FileNotFound { path: String },
     #[error("Invalid arguments: {message}")]
     InvalidArgs { message: String },
-    #[error("Tool not found: {name}")]
-    ToolNotFound { name: String },
+    #[error(
+        "Tool not found: {name}{}",
+        crate::utils::suggest::format_suggestions(suggestions)
+    )]
+    ToolNotFound {
+        name: String,
+        suggestions: Vec<String>,
+    },
     #[error("Linting failed: {errors:?}")]
     LintingFailed { errors: Vec<String> },
     #[error("IO error: {0}")]
@@ -25,6 +35,77 @@ pub enum ToolError {
     Json(#[from] serde_json::Error),
 }
 
+impl From<anyhow::Error> for ToolError {
+    /// Recover the error class of a tool's execution error; anything unclassified is
+    /// reported as invalid arguments
+    fn from(error: anyhow::Error) -> Self {
+        let error = match error.downcast::<ToolError>() {
+            Ok(error) => return error,
+            Err(error) => error,
+        };
+        let error = match error.downcast::<std::io::Error>() {
+            Ok(error) => return ToolError::Io(error),
+            Err(error) => error,
+        };
+        let error = match error.downcast::<regex::Error>() {
+            Ok(error) => return ToolError::Regex(error),
+            Err(error) => error,
+        };
+        match error.downcast::<serde_json::Error>() {
+            Ok(error) => ToolError::Json(error),
+            Err(error) => ToolError::InvalidArgs {
+                message: format!("{:#}", error),
+            },
+        }
+    }
+}
+
+impl ToolError {
+    /// Process exit code used by the CLI for this error class
+    pub fn exit_code(&self) -> i32 {
+        match self {
+            ToolError::InvalidArgs { .. } => 2,
+            ToolError::ToolNotFound { .. } => 3,
+            ToolError::FileNotFound { .. } => 4,
+            ToolError::LintingFailed { .. } => 5,
+            ToolError::Io(_) => 6,
+            ToolError::Regex(_) => 7,
+            ToolError::Json(_) => 8,
+        }
+    }
+
+    /// Stable, machine-readable name of the error class
+    pub fn kind(&self) -> &'static str {
+        match self {
+            ToolError::FileNotFound { .. } => "file_not_found",
+            ToolError::InvalidArgs { .. } => "invalid_args",
+            ToolError::ToolNotFound { .. } => "tool_not_found",
+            ToolError::LintingFailed { .. } => "linting_failed",
+            ToolError::Io(_) => "io",
+            ToolError::Regex(_) => "regex",
+            ToolError::Json(_) => "json",
+        }
+    }
+
+    /// Suggestions attached to the error, if any
+    pub fn suggestions(&self) -> &[String] {
+        match self {
+            ToolError::ToolNotFound { suggestions, .. } => suggestions,
+            _ => &[],
+        }
+    }
+
+    /// Structured representation of the error for machine consumers
+    pub fn to_json(&self) -> serde_json::Value {
+        serde_json::json!({
+            "code": self.exit_code(),
+            "kind": self.kind(),
+            "message": self.to_string(),
+            "suggestions": self.suggestions()
+        })
+    }
+}
+
 /// Arguments passed to tool execution
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct ToolArgs {
@@ -61,6 +142,28 @@ impl ToolArgs {
         }
     }
 
+    /// Create ToolArgs from CLI tokens, treating `key=value` tokens as named arguments
+    /// when `key` is one of `named_keys` or the `json` passthrough. Every other token
+    /// stays positional, so values such as `a=b` in replacement text are preserved.
+    pub fn from_cli_tokens(tokens: &[String], named_keys: &[&str]) -> Self {
+        let mut positional = Vec::new();
+        let mut named = HashMap::new();
+
+        for token in tokens {
+            match token.split_once('=') {
+                Some((key, value)) if key == "json" || named_keys.contains(&key) => {
+                    named.insert(key.to_string(), value.to_string());
+                }
+                _ => positional.push(token.clone()),
+            }
+        }
+
+        Self {
+            args: positional,
+            named_args: named,
+        }
+    }
+
     /// Create ToolArgs with named arguments
     pub fn with_named_args(args: Vec<String>, named_args: HashMap<String, String>) -> Self {
         Self { args, named_args }
@@ -71,6 +174,16 @@ impl ToolArgs {
         self.args.get(index)
     }
 
+    /// Total size in bytes of all positional and named argument text
+    pub fn size_in_bytes(&self) -> usize {
+        self.args.iter().map(|a| a.len()).sum::<usize>()
+            + self
+                .named_args
+                .iter()
+                .map(|(k, v)| k.len() + v.len())
+                .sum::<usize>()
+    }
+
     /// Get named argument
     pub fn get_named_arg(&self, name: &str) -> Option<&String> {
         self.named_args.get(name)
@@ -93,6 +206,88 @@ pub struct ToolResult {
     pub success: bool,
     pub message: String,
     pub data: Option<serde_json::Value>,
+    /// Non-text artifacts such as rendered diagrams, for multimodal models and UIs
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub attachments: Vec<Attachment>,
+}
+
+/// A non-text artifact of a tool result, such as a rendered diagram
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct Attachment {
+    /// MIME type, e.g. `image/svg+xml` or `image/png`
+    pub mime_type: String,
+    /// What the attachment shows, usable as alt text
+    pub description: String,
+    /// Serialized as `data` (base64) or `path`
+    #[serde(flatten)]
+    pub content: AttachmentContent,
+}
+
+/// Where the bytes of an [`Attachment`] are
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum AttachmentContent {
+    /// The bytes themselves, base64-encoded in JSON
+    Data(#[serde(with = "base64_bytes")] Vec<u8>),
+    /// A file holding the bytes
+    Path(std::path::PathBuf),
+}
+
+impl Attachment {
+    /// An attachment carrying its bytes
+    pub fn from_bytes(
+        mime_type: impl Into<String>,
+        description: impl Into<String>,
+        bytes: Vec<u8>,
+    ) -> Self {
+        Self {
+            mime_type: mime_type.into(),
+            description: description.into(),
+            content: AttachmentContent::Data(bytes),
+        }
+    }
+
+    /// An attachment stored in the file at `path`
+    pub fn from_path(
+        mime_type: impl Into<String>,
+        description: impl Into<String>,
+        path: impl Into<std::path::PathBuf>,
+    ) -> Self {
+        Self {
+            mime_type: mime_type.into(),
+            description: description.into(),
+            content: AttachmentContent::Path(path.into()),
+        }
+    }
+
+    /// An SVG image
+    pub fn svg(description: impl Into<String>, svg: String) -> Self {
+        Self::from_bytes("image/svg+xml", description, svg.into_bytes())
+    }
+
+    /// The bytes of the attachment, reading them from its file if needed
+    pub fn bytes(&self) -> std::io::Result<std::borrow::Cow<'_, [u8]>> {
+        match &self.content {
+            AttachmentContent::Data(bytes) => Ok(std::borrow::Cow::Borrowed(bytes)),
+            AttachmentContent::Path(path) => std::fs::read(path).map(std::borrow::Cow::Owned),
+        }
+    }
+}
+
+mod base64_bytes {
+    use base64::Engine;
+    use serde::{Deserialize, Deserializer, Serializer};
+
+    pub fn serialize<S: Serializer>(bytes: &[u8], serializer: S) -> Result<S::Ok, S::Error> {
+        serializer.serialize_str(&base64::engine::general_purpose::STANDARD.encode(bytes))
+    }
+
+    pub fn deserialize<'de, D: Deserializer<'de>>(deserializer: D) -> Result<Vec<u8>, D::Error> {
+        let encoded = String::deserialize(deserializer)?;
+        base64::engine::general_purpose::STANDARD
+            .decode(encoded)
+            .map_err(serde::de::Error::custom)
+    }
 }
 
 impl ToolResult {
@@ -102,6 +297,7 @@ impl ToolResult {
             success: true,
             message: message.into(),
             data: None,
+            attachments: Vec::new(),
         }
     }
 
@@ -111,15 +307,33 @@ impl ToolResult {
             success: true,
             message: message.into(),
             data: Some(data),
+            attachments: Vec::new(),
         }
     }
 
+    /// Error class of a failed result, when it has one: a missing path (see
+    /// [`crate::utils::suggest::path_not_found`]) is [`ToolError::FileNotFound`]
+    pub fn failure(&self) -> Option<ToolError> {
+        let data = self.data.as_ref().filter(|_| !self.success)?;
+        if data.get("error").and_then(|e| e.as_str()) != Some("file_not_found") {
+            return None;
+        }
+        Some(ToolError::FileNotFound {
+            path: data
+                .get("path")
+                .and_then(|p| p.as_str())
+                .unwrap_or_default()
+                .to_string(),
+        })
+    }
+
     /// Create error result
     pub fn error(message: impl Into<String>) -> Self {
         Self {
             success: false,
             message: message.into(),
             data: None,
+            attachments: Vec::new(),
         }
     }
 
@@ -129,10 +343,183 @@ impl ToolResult {
             success: false,
             message: message.into(),
             data: Some(data),
+            attachments: Vec::new(),
+        }
+    }
+
+    /// Set `key` in `data`, creating the data object if needed
+    pub fn insert_data(&mut self, key: &str, value: serde_json::Value) {
+        let data = self
+            .data
+            .get_or_insert_with(|| serde_json::Value::Object(Default::default()));
+        if let Some(obj) = data.as_object_mut() {
+            obj.insert(key.to_string(), value);
+        }
+    }
+
+    /// Add an attachment, e.g. a rendered diagram
+    pub fn add_attachment(&mut self, attachment: Attachment) {
+        self.attachments.push(attachment);
+    }
+
+    /// Append `warnings` to the message and to a `warnings` array in `data`
+    pub fn add_warnings(&mut self, warnings: Vec<String>) {
+        self.add_warnings_styled(warnings, &crate::messages::MessageStyle::standard());
+    }
+
+    /// [`ToolResult::add_warnings`], rendering each message line with `style`
+    pub fn add_warnings_styled(
+        &mut self,
+        warnings: Vec<String>,
+        style: &crate::messages::MessageStyle,
+    ) {
+        if warnings.is_empty() {
+            return;
+        }
+        for warning in &warnings {
+            self.message.push('\n');
+            self.message.push_str(&style.render(
+                crate::messages::MessageKey::Warning,
+                &[("warning", warning)],
+            ));
+        }
+        let data = self
+            .data
+            .get_or_insert_with(|| serde_json::Value::Object(Default::default()));
+        if let Some(obj) = data.as_object_mut() {
+            let existing = obj
+                .entry("warnings")
+                .or_insert_with(|| serde_json::Value::Array(Vec::new()));
+            if let Some(existing) = existing.as_array_mut() {
+                existing.extend(warnings.into_iter().map(serde_json::Value::String));
+            }
+        }
+    }
+}
+
+/// Broad area a tool works in, used to group tool lists and build tool profiles
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, PartialOrd, Ord, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum ToolCategory {
+    /// Opening, scrolling and jumping around files and symbols
+    Navigation,
+    /// Changing file contents and managing files
+    Editing,
+    /// Finding files and text
+    Search,
+    /// Running commands, tests and benchmarks
+    Execution,
+    /// Version control
+    Vcs,
+    /// Everything else: state, diagnostics, token counting, task bookkeeping
+    Utility,
+}
+
+impl ToolCategory {
+    /// All categories, in display order
+    pub const ALL: [ToolCategory; 6] = [
+        ToolCategory::Navigation,
+        ToolCategory::Editing,
+        ToolCategory::Search,
+        ToolCategory::Execution,
+        ToolCategory::Vcs,
+        ToolCategory::Utility,
+    ];
+
+    pub fn as_str(self) -> &'static str {
+        match self {
+            ToolCategory::Navigation => "navigation",
+            ToolCategory::Editing => "editing",
+            ToolCategory::Search => "search",
+            ToolCategory::Execution => "execution",
+            ToolCategory::Vcs => "vcs",
+            ToolCategory::Utility => "utility",
         }
     }
 }
 
+impl std::fmt::Display for ToolCategory {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.write_str(self.as_str())
+    }
+}
+
+impl std::str::FromStr for ToolCategory {
+    type Err = ToolError;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        Self::ALL
+            .into_iter()
+            .find(|category| category.as_str().eq_ignore_ascii_case(s))
+            .ok_or_else(|| ToolError::InvalidArgs {
+                message: format!(
+                    "Unknown tool category '{}'; expected one of: {}",
+                    s,
+                    Self::ALL.map(|c| c.as_str()).join(", ")
+                ),
+            })
+    }
+}
+
+/// What the worst case of running a tool can do, ordered from least to most severe
+///
+/// Lets approval hooks, profiles and UIs apply blanket policies such as "auto-approve
+/// `read_only`, prompt for `destructive`" without maintaining lists of tool names.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, PartialOrd, Ord, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum DangerLevel {
+    /// Reads files or session state only
+    ReadOnly,
+    /// Creates or edits files; the previous content stays recoverable from the edit
+    ModifiesFiles,
+    /// Deletes files or replaces whole contents
+    Destructive,
+    /// Runs commands or programs, which can do anything
+    ExecutesCode,
+}
+
+impl DangerLevel {
+    /// All levels, least severe first
+    pub const ALL: [DangerLevel; 4] = [
+        DangerLevel::ReadOnly,
+        DangerLevel::ModifiesFiles,
+        DangerLevel::Destructive,
+        DangerLevel::ExecutesCode,
+    ];
+
+    pub fn as_str(self) -> &'static str {
+        match self {
+            DangerLevel::ReadOnly => "read_only",
+            DangerLevel::ModifiesFiles => "modifies_files",
+            DangerLevel::Destructive => "destructive",
+            DangerLevel::ExecutesCode => "executes_code",
+        }
+    }
+}
+
+impl std::fmt::Display for DangerLevel {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.write_str(self.as_str())
+    }
+}
+
+impl std::str::FromStr for DangerLevel {
+    type Err = ToolError;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        Self::ALL
+            .into_iter()
+            .find(|level| level.as_str().eq_ignore_ascii_case(s))
+            .ok_or_else(|| ToolError::InvalidArgs {
+                message: format!(
+                    "Unknown danger level '{}'; expected one of: {}",
+                    s,
+                    Self::ALL.map(|l| l.as_str()).join(", ")
+                ),
+            })
+    }
+}
+
 /// Main trait for all tools
 pub trait Tool: Send + Sync {
     /// Get the tool name
@@ -144,6 +531,44 @@ pub trait Tool: Send + Sync {
     /// Get the tool usage/signature
     fn signature(&self) -> &str;
 
+    /// Area the tool works in; tools that do not say are utilities
+    fn category(&self) -> ToolCategory {
+        ToolCategory::Utility
+    }
+
+    /// Worst case of running the tool; tools that do not say are assumed to execute
+    /// code, so blanket policies never under-classify them
+    fn danger_level(&self) -> DangerLevel {
+        DangerLevel::ExecutesCode
+    }
+
+    /// Whether calls may run alongside other parallel-safe calls in
+    /// [`ToolRegistry::execute_batch`]
+    ///
+    /// By default, read-only tools outside navigation are: they change no files and do
+    /// not move the session's window. Tools that read or change state the order of a
+    /// batch matters for say no.
+    fn is_parallel_safe(&self) -> bool {
+        self.danger_level() == DangerLevel::ReadOnly && self.category() != ToolCategory::Navigation
+    }
+
+    /// Whether running a call twice in a row gives the same result as running it once,
+    /// so an exact duplicate of a call in the same batch can share its result.
+    ///
+    /// By default, read-only tools are. Tools whose every call moves or adds something,
+    /// such as scrolling, say no.
+    fn is_idempotent(&self) -> bool {
+        self.danger_level() == DangerLevel::ReadOnly
+    }
+
+    /// Message of a successful `result` at [`Verbosity::Compact`]: counts and key line
+    /// numbers only. `None` keeps the first line of the full message.
+    ///
+    /// [`Verbosity::Compact`]: crate::messages::Verbosity::Compact
+    fn compact_message(&self, _result: &ToolResult) -> Option<String> {
+        None
+    }
+
     /// Validate arguments before execution
     fn validate_args(&self, args: &ToolArgs) -> Result<(), ToolError>;
 
@@ -154,6 +579,33 @@ pub trait Tool: Send + Sync {
         state: &Arc<Mutex<crate::state::ToolState>>,
     ) -> Result<ToolResult>;
 
+    /// The arguments of a call as a JSON object keyed by parameter name, reported in
+    /// results as `resolved_args`; see [`resolve_args`](crate::llm::converter::resolve_args)
+    fn resolve_args(&self, args: &ToolArgs) -> serde_json::Value {
+        crate::llm::converter::resolve_args(self.signature(), &self.get_parameters_schema(), args)
+    }
+
+    /// Paths a call reads or changes, as checked by [`WorkspaceGuard`]
+    ///
+    /// By default, the resolved arguments named like paths (`path`, `file`, `dir`,
+    /// `source`, `destination`, `root`, `*_path`) and the entries of `paths`. Tools that
+    /// name path parameters otherwise, resolve them against another argument or take
+    /// paths from their input, like the headers of a patch, report those instead.
+    fn accessed_paths(&self, args: &ToolArgs) -> Vec<PathBuf> {
+        path_arguments(&self.named_args(args), |name| {
+            crate::llm::converter::is_path_param(name) || name == "paths"
+        })
+    }
+
+    /// The arguments of a call keyed by parameter name, with paths as given
+    fn named_args(&self, args: &ToolArgs) -> serde_json::Value {
+        serde_json::Value::Object(crate::llm::converter::named_args(
+            self.signature(),
+            &self.get_parameters_schema(),
+            args,
+        ))
+    }
+
     /// Get OpenAI function schema for this tool
     fn get_openai_schema(&self) -> serde_json::Value {
         serde_json::json!({
@@ -176,49 +628,880 @@ pub trait Tool: Send + Sync {
     }
 }
 
+/// Static description of a registered tool
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ToolDescriptor {
+    pub name: String,
+    pub description: String,
+    pub signature: String,
+    pub category: ToolCategory,
+    pub danger_level: DangerLevel,
+    pub parameters: serde_json::Value,
+}
+
+impl ToolDescriptor {
+    /// Capture the descriptor of a tool
+    pub fn from_tool(tool: &dyn Tool) -> Self {
+        Self {
+            name: tool.name().to_string(),
+            description: tool.description().to_string(),
+            signature: tool.signature().to_string(),
+            category: tool.category(),
+            danger_level: tool.danger_level(),
+            parameters: tool.get_parameters_schema(),
+        }
+    }
+}
+
+/// Shared, read-only view of the tools registered in a registry.
+///
+/// Cloning a catalog is cheap; all clones observe later registrations.
+#[derive(Debug, Clone, Default)]
+pub struct ToolCatalog {
+    entries: Arc<RwLock<BTreeMap<String, ToolDescriptor>>>,
+}
+
+impl ToolCatalog {
+    fn insert(&self, descriptor: ToolDescriptor) {
+        if let Ok(mut entries) = self.entries.write() {
+            entries.insert(descriptor.name.clone(), descriptor);
+        }
+    }
+
+    fn remove(&self, name: &str) {
+        if let Ok(mut entries) = self.entries.write() {
+            entries.remove(name);
+        }
+    }
+
+    /// Look up a tool descriptor by name
+    pub fn get(&self, name: &str) -> Option<ToolDescriptor> {
+        self.entries.read().ok()?.get(name).cloned()
+    }
+
+    /// Names of all cataloged tools, sorted
+    pub fn names(&self) -> Vec<String> {
+        self.entries
+            .read()
+            .map(|entries| entries.keys().cloned().collect())
+            .unwrap_or_default()
+    }
+}
+
+/// Id of the session used when a call names none
+pub const DEFAULT_SESSION: &str = "default";
+
+/// Reserved named argument selecting the session a call runs in
+pub const SESSION_ARG: &str = "session";
+
+/// Reserved named argument setting the verbosity of one call (`normal` or `compact`)
+pub const VERBOSITY_ARG: &str = "verbosity";
+
+/// Reserved named argument carrying the caller's id for a call, such as the model's
+/// tool call id; provenance records attribute changes to it
+pub const CALL_ID_ARG: &str = "call_id";
+
+/// Called before a tool runs, e.g. to ask for approval; `Err(reason)` refuses the call
+///
+/// The descriptor carries the tool's category and danger level, so a hook can apply
+/// blanket policies without knowing tool names.
+pub type BeforeToolHook =
+    Box<dyn Fn(&ToolDescriptor, &ToolArgs) -> Result<(), String> + Send + Sync>;
+
+/// Non-empty string values of the parameters of `resolved` that `is_path` accepts,
+/// including the entries of arrays
+pub fn path_arguments(
+    resolved: &serde_json::Value,
+    is_path: impl Fn(&str) -> bool,
+) -> Vec<PathBuf> {
+    let Some(params) = resolved.as_object() else {
+        return Vec::new();
+    };
+    params
+        .iter()
+        .filter(|(name, _)| is_path(name))
+        .flat_map(|(_, value)| match value {
+            serde_json::Value::Array(values) => values.iter().collect(),
+            value => vec![value],
+        })
+        .filter_map(serde_json::Value::as_str)
+        .filter(|path| !path.is_empty())
+        .map(PathBuf::from)
+        .collect()
+}
+
+/// Confines the paths tools access to a workspace root
+///
+/// Every call is checked before it runs. Each path it would access
+/// ([`Tool::accessed_paths`]) is resolved like the tool resolves it, against the
+/// session's working directory with `..` removed and existing symlinks followed, and a
+/// call naming a path outside the root is refused. Commands that `run_command` runs are
+/// not inspected.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct WorkspaceGuard {
+    root: PathBuf,
+}
+
+impl WorkspaceGuard {
+    pub fn new(root: impl AsRef<Path>) -> Self {
+        Self {
+            root: crate::state::resolve_path(root.as_ref()),
+        }
+    }
+
+    /// The resolved root
+    pub fn root(&self) -> &Path {
+        &self.root
+    }
+
+    /// Whether `path`, relative to `working_directory`, resolves to the root or a path
+    /// below it
+    pub fn contains(&self, path: &Path, working_directory: &Path) -> bool {
+        crate::state::resolve_path(&working_directory.join(path)).starts_with(&self.root)
+    }
+
+    /// Paths a call of `tool` in `working_directory` would access that lie outside the
+    /// root
+    pub fn escaping_paths(
+        &self,
+        tool: &dyn Tool,
+        args: &ToolArgs,
+        working_directory: &Path,
+    ) -> Vec<String> {
+        tool.accessed_paths(args)
+            .into_iter()
+            .filter(|path| !self.contains(path, working_directory))
+            .map(|path| path.to_string_lossy().to_string())
+            .collect()
+    }
+
+    /// The refusal of a call of `tool` in `working_directory` naming paths outside the
+    /// root, if it does
+    pub fn check(
+        &self,
+        tool: &dyn Tool,
+        args: &ToolArgs,
+        working_directory: &Path,
+    ) -> Option<ToolResult> {
+        let outside = self.escaping_paths(tool, args, working_directory);
+        if outside.is_empty() {
+            return None;
+        }
+        let reason = format!(
+            "{} {} outside the workspace root {}",
+            outside.join(", "),
+            if outside.len() == 1 { "is" } else { "are" },
+            self.root.display()
+        );
+        Some(ToolResult::error_with_data(
+            format!("{} was refused: {}", tool.name(), reason),
+            serde_json::json!({
+                "refused": true,
+                "reason": reason,
+                "outside_root": outside,
+                "root": self.root.to_string_lossy()
+            }),
+        ))
+    }
+}
+
+/// Result of a call that a before-tool hook, the workspace guard or dry-run mode
+/// stopped, if any
+fn intercept(
+    tool: &dyn Tool,
+    args: &ToolArgs,
+    hooks: &[BeforeToolHook],
+    guard: Option<&WorkspaceGuard>,
+    working_directory: &Path,
+    dry_run: bool,
+) -> Option<ToolResult> {
+    if !hooks.is_empty() {
+        let descriptor = ToolDescriptor::from_tool(tool);
+        if let Some(reason) = hooks.iter().find_map(|hook| hook(&descriptor, args).err()) {
+            return Some(ToolResult::error_with_data(
+                format!("{} was refused: {}", tool.name(), reason),
+                serde_json::json!({
+                    "refused": true,
+                    "reason": reason
+                }),
+            ));
+        }
+    }
+    if let Some(refusal) = guard.and_then(|guard| guard.check(tool, args, working_directory)) {
+        return Some(refusal);
+    }
+    if dry_run && tool.danger_level() > DangerLevel::ReadOnly {
+        return Some(ToolResult::success_with_data(
+            format!(
+                "Dry run: {} was not executed ({})",
+                tool.name(),
+                tool.danger_level()
+            ),
+            serde_json::json!({
+                "dry_run": true,
+                "tool": tool.name(),
+                "danger_level": tool.danger_level(),
+                "args": args
+            }),
+        ));
+    }
+    None
+}
+
+/// Reject a call whose arguments do not match the tool's schema, listing every problem
+fn check_strict_args(tool: &dyn Tool, args: &ToolArgs) -> Result<(), ToolError> {
+    let resolved = tool.resolve_args(args);
+    let mut violations = crate::schema::validate(&tool.get_parameters_schema(), &resolved);
+    if violations.is_empty() {
+        return Ok(());
+    }
+    // Positionals beyond the signature resolve to `arg<N>`; name them for what they are
+    for violation in violations.iter_mut() {
+        if let Some(index) = violation
+            .message
+            .strip_prefix("unknown parameter 'arg")
+            .and_then(|rest| rest.split('\'').next())
+            .and_then(|index| index.parse::<usize>().ok())
+        {
+            violation.message = format!(
+                "unexpected positional argument {} ('{}'); usage: {}",
+                index + 1,
+                args.args.get(index).map_or("", String::as_str),
+                tool.signature()
+            );
+        }
+    }
+    Err(ToolError::InvalidArgs {
+        message: crate::schema::violation_report(tool.name(), &violations),
+    })
+}
+
+/// Registry settings a call runs with, borrowed apart from the tools so calls of
+/// different tools can run at once
+struct CallContext<'a> {
+    events: &'a crate::events::EventSender,
+    before_hooks: &'a [BeforeToolHook],
+    guard: Option<&'a WorkspaceGuard>,
+    dry_run: bool,
+    strict_args: bool,
+    verbosity: Verbosity,
+}
+
+impl CallContext<'_> {
+    fn run(
+        &self,
+        tool: &mut dyn Tool,
+        session: &str,
+        name: &str,
+        args: &ToolArgs,
+        state: Arc<Mutex<crate::state::ToolState>>,
+        stream: ResultStream,
+    ) -> Result<ToolResult, ToolError> {
+        let verbosity = match args.get_named_arg(VERBOSITY_ARG) {
+            Some(value) => value.parse::<Verbosity>()?,
+            None => self.verbosity,
+        };
+        let call_id = args.get_named_arg(CALL_ID_ARG).cloned();
+        let without_reserved;
+        let args = if args.named_args.contains_key(VERBOSITY_ARG) || call_id.is_some() {
+            let mut stripped = args.clone();
+            stripped.named_args.remove(VERBOSITY_ARG);
+            stripped.named_args.remove(CALL_ID_ARG);
+            without_reserved = stripped;
+            &without_reserved
+        } else {
+            args
+        };
+        if self.strict_args {
+            check_strict_args(&*tool, args)?;
+        }
+
+        let span = tracing::info_span!(
+            "tool",
+            tool = name,
+            args_bytes = args.size_in_bytes() as u64,
+            duration_us = tracing::field::Empty,
+            bytes_read = tracing::field::Empty,
+            bytes_written = tracing::field::Empty,
+            success = tracing::field::Empty,
+        );
+        let _entered = span.enter();
+        let started = std::time::Instant::now();
+        let io_before = crate::profiling::io_counters();
+        crate::events::emit(
+            Some(self.events),
+            crate::events::ToolEvent::ToolStarted {
+                session: session.to_string(),
+                tool: name.to_string(),
+                args: args.clone(),
+            },
+        );
+
+        // Journal the tool's file mutations as one batch
+        let mut repeats = 0;
+        let mut working_directory = PathBuf::new();
+        if let Ok(mut state) = state.lock() {
+            repeats = state.recent_calls.record(name, args);
+            working_directory = state.working_directory.clone();
+            state.touched_paths.clear();
+            state.stream = stream.clone();
+            if let Some(journal) = state.journal.as_mut() {
+                journal.begin_batch(name);
+            }
+        }
+
+        // Validate arguments, give hooks, the workspace guard and dry-run mode a say, then
+        // execute the tool
+        let mut result = tool.validate_args(args).and_then(|_| {
+            if let Some(result) = intercept(
+                &*tool,
+                args,
+                self.before_hooks,
+                self.guard,
+                &working_directory,
+                self.dry_run,
+            ) {
+                return Ok(result);
+            }
+            tool.execute(args, &state).map_err(ToolError::from)
+        });
+
+        let mut warnings = Vec::new();
+        let mut verified = None;
+        let mut repeated = None;
+        let mut provenance = None;
+        let mut style = crate::messages::MessageStyle::standard();
+        if let Ok(mut state) = state.lock() {
+            style = state.message_style.clone();
+            state.stream = ResultStream::default();
+            if let Some(journal) = state.journal.as_mut() {
+                if let Err(e) = journal.commit_batch() {
+                    tracing::warn!("Failed to commit journal batch: {}", e);
+                }
+            }
+            let touched = std::mem::take(&mut state.touched_paths);
+            // Keep edits of tools that record no history of their own undoable
+            if !state.pending_edits.is_empty() {
+                state.push_history(name.to_string());
+            }
+            // Repeating a call is only a loop while nothing changes
+            if !touched.is_empty() {
+                state.recent_calls.clear();
+            } else if state.recent_calls.is_repetition(repeats) {
+                let hint = crate::state::repetition::loop_hint(name);
+                warnings.push(style.render(
+                    crate::messages::MessageKey::RepeatedCall,
+                    &[("tool", &name), ("count", &repeats), ("hint", &hint)],
+                ));
+                repeated = Some(serde_json::json!({"count": repeats, "hint": hint}));
+            }
+            // Content writes are read back by the writer; report the combined outcome
+            verified = touched
+                .iter()
+                .filter_map(|t| t.verified)
+                .reduce(|a, b| a && b);
+            warnings.extend(
+                touched
+                    .iter()
+                    .filter(|t| t.verified == Some(false))
+                    .map(|t| {
+                        style.render(
+                            crate::messages::MessageKey::ReadBackMismatch,
+                            &[("path", &t.path.display())],
+                        )
+                    }),
+            );
+            warnings.extend(crate::editing::artifacts::check_touched(
+                &state.working_directory,
+                &touched,
+            ));
+            if state.provenance && !touched.is_empty() {
+                let call = crate::state::provenance::CallInfo {
+                    session,
+                    tool: name,
+                    call_id: call_id.as_deref(),
+                };
+                match crate::state::provenance::record(&state.working_directory, &call, &touched) {
+                    Ok(call_id) => provenance = Some(call_id),
+                    Err(e) => tracing::warn!("Failed to record provenance: {}", e),
+                }
+            }
+            for entry in &touched {
+                crate::events::emit(
+                    Some(self.events),
+                    crate::events::ToolEvent::FileModified {
+                        session: session.to_string(),
+                        path: entry.path.clone(),
+                        change_kind: entry.kind,
+                        diff: entry.diff.clone(),
+                        renamed_from: entry.from.clone(),
+                    },
+                );
+            }
+        }
+        if let Ok(result) = result.as_mut() {
+            result.insert_data("resolved_args", tool.resolve_args(args));
+            if verbosity == Verbosity::Compact && result.success {
+                result.message = tool.compact_message(result).unwrap_or_else(|| {
+                    result
+                        .message
+                        .lines()
+                        .next()
+                        .unwrap_or_default()
+                        .to_string()
+                });
+            }
+            if let Some(verified) = verified {
+                result.insert_data("verified", serde_json::Value::Bool(verified));
+            }
+            if let Some(repeated) = repeated {
+                result.insert_data("repeated_call", repeated);
+            }
+            if let Some(call_id) = provenance {
+                result.insert_data(
+                    "provenance",
+                    serde_json::json!({
+                        "call_id": call_id,
+                        "file": crate::state::provenance::PROVENANCE_FILE
+                    }),
+                );
+            }
+            if stream.is_active() {
+                result.insert_data(
+                    "streamed",
+                    serde_json::json!({
+                        "chunks": stream.chunks_sent(),
+                        "stopped": stream.is_stopped()
+                    }),
+                );
+            }
+            result.add_warnings_styled(warnings, &style);
+        }
+
+        let io = crate::profiling::io_counters().since(io_before);
+        span.record("duration_us", started.elapsed().as_micros() as u64);
+        span.record("bytes_read", io.bytes_read);
+        span.record("bytes_written", io.bytes_written);
+        span.record(
+            "success",
+            result.as_ref().map(|r| r.success).unwrap_or(false),
+        );
+        crate::events::emit(
+            Some(self.events),
+            crate::events::ToolEvent::ToolFinished {
+                session: session.to_string(),
+                tool: name.to_string(),
+                success: result.as_ref().map(|r| r.success).unwrap_or(false),
+                duration_ms: started.elapsed().as_millis() as u64,
+                message: match &result {
+                    Ok(result) => result.message.clone(),
+                    Err(e) => e.to_string(),
+                },
+            },
+        );
+        result
+    }
+}
+
+/// How one call of [`ToolRegistry::execute_batch`] went
+#[derive(Debug)]
+pub struct BatchOutcome {
+    pub result: Result<ToolResult, ToolError>,
+    /// Wall-clock time of the call, including hooks
+    pub duration: std::time::Duration,
+}
+
+impl BatchOutcome {
+    fn time(call: impl FnOnce() -> Result<ToolResult, ToolError>) -> Self {
+        let started = std::time::Instant::now();
+        let result = call();
+        Self {
+            result,
+            duration: started.elapsed(),
+        }
+    }
+
+    /// Whether the call returned a successful result
+    pub fn succeeded(&self) -> bool {
+        matches!(&self.result, Ok(result) if result.success)
+    }
+}
+
 /// Registry for managing available tools
+///
+/// Tool state is kept per session: calls run against the default session unless they
+/// pass a `session` argument or go through [`ToolRegistry::session`], so several agents
+/// can share one registry without seeing each other's open files, windows or history.
 pub struct ToolRegistry {
     tools: HashMap<String, Box<dyn Tool>>,
     state: Arc<Mutex<crate::state::ToolState>>,
+    sessions: HashMap<String, Arc<Mutex<crate::state::ToolState>>>,
+    catalog: ToolCatalog,
+    events: crate::events::EventSender,
+    verbosity: Verbosity,
+    before_hooks: Vec<BeforeToolHook>,
+    guard: Option<WorkspaceGuard>,
+    dry_run: bool,
+    strict_args: bool,
+}
+
+/// A registry bound to one session, from [`ToolRegistry::session`]
+pub struct SessionHandle<'a> {
+    registry: &'a mut ToolRegistry,
+    id: String,
+}
+
+impl SessionHandle<'_> {
+    /// The session id
+    pub fn id(&self) -> &str {
+        &self.id
+    }
+
+    /// Execute a tool in this session
+    pub fn execute_tool(&mut self, name: &str, args: &ToolArgs) -> Result<ToolResult, ToolError> {
+        let state = self.registry.session_state(&self.id);
+        self.registry
+            .execute_with_state(&self.id, name, args, state, ResultStream::default())
+    }
+
+    /// Get this session's tool state
+    pub fn get_state(&mut self) -> Arc<Mutex<crate::state::ToolState>> {
+        self.registry.session_state(&self.id)
+    }
+
+    /// Run this session's tools, including `run_command`, in `path`
+    pub fn set_working_directory(&mut self, path: impl Into<std::path::PathBuf>) {
+        if let Ok(mut state) = self.get_state().lock() {
+            state.set_working_directory(path);
+        }
+    }
+
+    /// Record a plan marker in this session's history
+    pub fn annotate_history(&mut self, text: impl Into<String>) {
+        if let Ok(mut state) = self.get_state().lock() {
+            state.annotate_history(text);
+        }
+    }
 }
 
 impl ToolRegistry {
     /// Create a new empty tool registry
     pub fn new() -> Self {
+        let events = crate::events::channel();
+        let mut state = crate::state::ToolState::new();
+        state.events = Some(events.clone());
         Self {
             tools: HashMap::new(),
-            state: Arc::new(Mutex::new(crate::state::ToolState::new())),
+            state: Arc::new(Mutex::new(state)),
+            sessions: HashMap::new(),
+            catalog: ToolCatalog::default(),
+            events,
+            verbosity: Verbosity::default(),
+            before_hooks: Vec::new(),
+            guard: None,
+            dry_run: false,
+            strict_args: false,
         }
     }
 
     /// Register a tool
     pub fn register(&mut self, tool: Box<dyn Tool>) {
         let name = tool.name().to_string();
+        self.catalog
+            .insert(ToolDescriptor::from_tool(tool.as_ref()));
         self.tools.insert(name, tool);
     }
 
+    /// Get a shared catalog of the registered tools
+    pub fn catalog(&self) -> ToolCatalog {
+        self.catalog.clone()
+    }
+
     /// Execute a tool by name
+    ///
+    /// A `session` named argument runs the call in that session (created on first use),
+    /// and a `verbosity` named argument overrides the registry's verbosity for the call;
+    /// both are removed before the tool sees its arguments.
     pub fn execute_tool(&mut self, name: &str, args: &ToolArgs) -> Result<ToolResult, ToolError> {
-        let tool = self
-            .tools
-            .get_mut(name)
-            .ok_or_else(|| ToolError::ToolNotFound {
-                name: name.to_string(),
-            })?;
+        self.execute_routed(name, args, ResultStream::default())
+    }
 
-        // Validate arguments
-        tool.validate_args(args)?;
+    /// Execute a tool by name, passing the chunks it streams to `on_chunk` as they are
+    /// produced
+    ///
+    /// Returning [`StreamControl::Stop`] from `on_chunk` ends the call early; the tool
+    /// then returns the output it has so far. The final result's `data` gains a
+    /// `streamed` object with the number of `chunks` delivered and whether the consumer
+    /// `stopped` the call. Tools that do not stream deliver no chunks.
+    ///
+    /// [`StreamControl::Stop`]: crate::stream::StreamControl::Stop
+    pub fn execute_tool_streaming(
+        &mut self,
+        name: &str,
+        args: &ToolArgs,
+        on_chunk: impl FnMut(&ResultChunk) -> StreamControl + Send + 'static,
+    ) -> Result<ToolResult, ToolError> {
+        self.execute_routed(name, args, ResultStream::new(name, on_chunk))
+    }
 
-        // Execute tool
-        tool.execute(args, &self.state)
-            .map_err(|e| ToolError::InvalidArgs {
-                message: e.to_string(),
-            })
+    /// Run a call in the session its `session` argument names, or the default session
+    fn execute_routed(
+        &mut self,
+        name: &str,
+        args: &ToolArgs,
+        stream: ResultStream,
+    ) -> Result<ToolResult, ToolError> {
+        match args.get_named_arg(SESSION_ARG) {
+            Some(_) => {
+                let (session, args, state) = self.route(args);
+                self.execute_with_state(&session, name, &args, state, stream)
+            }
+            None => {
+                let state = Arc::clone(&self.state);
+                self.execute_with_state(DEFAULT_SESSION, name, args, state, stream)
+            }
+        }
+    }
+
+    /// The session a call runs in, its arguments without `session`, and the session state
+    fn route(
+        &mut self,
+        args: &ToolArgs,
+    ) -> (String, ToolArgs, Arc<Mutex<crate::state::ToolState>>) {
+        let mut args = args.clone();
+        match args.named_args.remove(SESSION_ARG) {
+            Some(session) => {
+                let state = self.session_state(&session);
+                (session, args, state)
+            }
+            None => (DEFAULT_SESSION.to_string(), args, Arc::clone(&self.state)),
+        }
+    }
+
+    /// Execute a batch of calls, running parallel-safe ones concurrently
+    ///
+    /// Consecutive calls whose tools are [parallel-safe](Tool::is_parallel_safe) run
+    /// on up to `max_parallel` threads; calls of the same tool run one after another on
+    /// the same thread. Every other call waits for the calls before it and runs alone, so
+    /// edits, commands and navigation keep their order and a read after an edit sees the
+    /// edit. Results are in the order of `calls`. With `max_parallel` at 1 this is the
+    /// same as calling [`execute_tool`](Self::execute_tool) for each call.
+    ///
+    /// With `stop_on_failure`, no call starts after one failed, either with an error
+    /// or an unsuccessful result; the calls left out are `None`. Calls already running
+    /// alongside the failed one finish.
+    pub fn execute_batch(
+        &mut self,
+        calls: &[(String, ToolArgs)],
+        max_parallel: usize,
+        stop_on_failure: bool,
+    ) -> Vec<Option<BatchOutcome>> {
+        let mut results: Vec<Option<BatchOutcome>> = calls.iter().map(|_| None).collect();
+        let failed = |results: &[Option<BatchOutcome>]| {
+            stop_on_failure && results.iter().flatten().any(|outcome| !outcome.succeeded())
+        };
+        let mut wave = Vec::new();
+        for (index, (name, args)) in calls.iter().enumerate() {
+            let parallel = max_parallel > 1
+                && self
+                    .tools
+                    .get(name)
+                    .is_some_and(|tool| tool.is_parallel_safe());
+            if parallel {
+                wave.push(index);
+                continue;
+            }
+            self.execute_wave(
+                calls,
+                &std::mem::take(&mut wave),
+                max_parallel,
+                &mut results,
+            );
+            if failed(&results) {
+                return results;
+            }
+            results[index] = Some(BatchOutcome::time(|| self.execute_tool(name, args)));
+            if failed(&results) {
+                return results;
+            }
+        }
+        self.execute_wave(calls, &wave, max_parallel, &mut results);
+        results
+    }
+
+    /// Run the parallel-safe calls at `wave` of `calls` concurrently
+    fn execute_wave(
+        &mut self,
+        calls: &[(String, ToolArgs)],
+        wave: &[usize],
+        max_parallel: usize,
+        results: &mut [Option<BatchOutcome>],
+    ) {
+        if let [index] = wave {
+            let (name, args) = &calls[*index];
+            results[*index] = Some(BatchOutcome::time(|| self.execute_tool(name, args)));
+        }
+        if wave.len() < 2 {
+            return;
+        }
+
+        // Each tool's calls run in order on one worker, which borrows that tool alone
+        let mut by_tool: BTreeMap<&str, Vec<(usize, String, ToolArgs, _)>> = BTreeMap::new();
+        for &index in wave {
+            let (name, args) = &calls[index];
+            let (session, args, state) = self.route(args);
+            by_tool
+                .entry(name.as_str())
+                .or_default()
+                .push((index, session, args, state));
+        }
+        let (tools, context) = self.split();
+        let mut tools: HashMap<&str, &mut Box<dyn Tool>> = tools
+            .iter_mut()
+            .filter(|(name, _)| by_tool.contains_key(name.as_str()))
+            .map(|(name, tool)| (name.as_str(), tool))
+            .collect();
+        let workers = max_parallel.min(by_tool.len());
+        let mut jobs: Vec<Vec<_>> = (0..workers).map(|_| Vec::new()).collect();
+        for (i, (name, calls)) in by_tool.into_iter().enumerate() {
+            let tool = tools.remove(name).expect("wave tools are registered");
+            jobs[i % workers].push((name, tool, calls));
+        }
+
+        let context = &context;
+        std::thread::scope(|scope| {
+            let handles: Vec<_> = jobs
+                .into_iter()
+                .map(|job| {
+                    scope.spawn(move || {
+                        let mut done = Vec::new();
+                        for (name, tool, calls) in job {
+                            for (index, session, args, state) in calls {
+                                let outcome = BatchOutcome::time(|| {
+                                    context.run(
+                                        tool.as_mut(),
+                                        &session,
+                                        name,
+                                        &args,
+                                        state,
+                                        ResultStream::default(),
+                                    )
+                                });
+                                done.push((index, outcome));
+                            }
+                        }
+                        done
+                    })
+                })
+                .collect();
+            for handle in handles {
+                let done = handle
+                    .join()
+                    .unwrap_or_else(|panic| std::panic::resume_unwind(panic));
+                for (index, outcome) in done {
+                    results[index] = Some(outcome);
+                }
+            }
+        });
+    }
+
+    /// Execute a tool in the session `id`, creating the session if needed
+    pub fn execute_tool_in_session(
+        &mut self,
+        id: &str,
+        name: &str,
+        args: &ToolArgs,
+    ) -> Result<ToolResult, ToolError> {
+        self.session(id).execute_tool(name, args)
+    }
+
+    fn execute_with_state(
+        &mut self,
+        session: &str,
+        name: &str,
+        args: &ToolArgs,
+        state: Arc<Mutex<crate::state::ToolState>>,
+        stream: ResultStream,
+    ) -> Result<ToolResult, ToolError> {
+        if !self.tools.contains_key(name) {
+            return Err(ToolError::ToolNotFound {
+                name: name.to_string(),
+                suggestions: self.suggest_tool_names(name),
+            });
+        }
+        let (tools, context) = self.split();
+        let tool = tools.get_mut(name).expect("checked above");
+        context.run(tool.as_mut(), session, name, args, state, stream)
+    }
+
+    /// The tools, and the settings calls run with, borrowed separately
+    fn split(&mut self) -> (&mut HashMap<String, Box<dyn Tool>>, CallContext<'_>) {
+        (
+            &mut self.tools,
+            CallContext {
+                events: &self.events,
+                before_hooks: &self.before_hooks,
+                guard: self.guard.as_ref(),
+                dry_run: self.dry_run,
+                strict_args: self.strict_args,
+                verbosity: self.verbosity,
+            },
+        )
     }
 
-    /// List all registered tool names
+    /// List all registered tool names, sorted
     pub fn list_tools(&self) -> Vec<String> {
-        self.tools.keys().cloned().collect()
+        let mut names: Vec<String> = self.tools.keys().cloned().collect();
+        names.sort();
+        names
+    }
+
+    /// Registered tool names grouped by category, each group sorted by name
+    pub fn tools_by_category(&self) -> BTreeMap<ToolCategory, Vec<String>> {
+        let mut groups: BTreeMap<ToolCategory, Vec<String>> = BTreeMap::new();
+        for (name, tool) in &self.tools {
+            groups
+                .entry(tool.category())
+                .or_default()
+                .push(name.clone());
+        }
+        for names in groups.values_mut() {
+            names.sort();
+        }
+        groups
+    }
+
+    /// Keep only the tools for which `keep` returns true
+    pub fn retain(&mut self, mut keep: impl FnMut(&dyn Tool) -> bool) {
+        let catalog = &self.catalog;
+        self.tools.retain(|name, tool| {
+            let kept = keep(tool.as_ref());
+            if !kept {
+                catalog.remove(name);
+            }
+            kept
+        });
+    }
+
+    /// Keep only the tools in `categories`, e.g. to build a read-only navigation and
+    /// search profile
+    pub fn retain_categories(&mut self, categories: &[ToolCategory]) {
+        self.retain(|tool| categories.contains(&tool.category()));
+    }
+
+    /// Keep only the tools whose danger level is at most `max`, e.g.
+    /// `DangerLevel::ReadOnly` for an agent that may only look around
+    pub fn retain_danger_at_most(&mut self, max: DangerLevel) {
+        self.retain(|tool| tool.danger_level() <= max);
+    }
+
+    /// Suggest registered tool names that are close to an unknown name
+    pub fn suggest_tool_names(&self, name: &str) -> Vec<String> {
+        crate::utils::suggest::closest_matches(
+            name,
+            self.tools.keys().map(|k| k.as_str()),
+            crate::utils::suggest::MAX_SUGGESTIONS,
+        )
     }
 
     /// Get tool by name
@@ -226,18 +1509,241 @@ impl ToolRegistry {
         self.tools.get(name).map(|t| t.as_ref())
     }
 
-    /// Get OpenAI function schemas for all tools
+    /// Get OpenAI function schemas for all tools, sorted by tool name
     pub fn get_all_schemas(&self) -> Vec<serde_json::Value> {
-        self.tools
-            .values()
-            .map(|tool| tool.get_openai_schema())
+        self.list_tools()
+            .iter()
+            .map(|name| self.tools[name].get_openai_schema())
             .collect()
     }
 
+    /// Check model-produced arguments for `tool_name` against its parameter schema
+    /// without running the tool
+    ///
+    /// `value` is the arguments object as the model wrote it; the registry-level
+    /// `session` and `verbosity` keys are ignored. An empty list means the arguments are
+    /// valid. [`crate::schema::violation_report`] turns the violations into a correction
+    /// prompt.
+    pub fn validate_against_schema(
+        &self,
+        tool_name: &str,
+        value: &serde_json::Value,
+    ) -> Result<Vec<crate::schema::SchemaViolation>, ToolError> {
+        let tool = self
+            .get_tool(tool_name)
+            .ok_or_else(|| ToolError::ToolNotFound {
+                name: tool_name.to_string(),
+                suggestions: self.suggest_tool_names(tool_name),
+            })?;
+        let mut value = value.clone();
+        if let Some(object) = value.as_object_mut() {
+            object.remove(SESSION_ARG);
+            object.remove(VERBOSITY_ARG);
+        }
+        Ok(crate::schema::validate(
+            &tool.get_parameters_schema(),
+            &value,
+        ))
+    }
+
+    /// Parameter schemas of all tools as one JSON Schema document
+    ///
+    /// Each tool's schema is under `$defs/<tool name>` with the tool description, so
+    /// any JSON Schema validator can check a call against `#/$defs/<tool name>`.
+    pub fn export_schemas(&self) -> serde_json::Value {
+        let defs: serde_json::Map<String, serde_json::Value> = self
+            .list_tools()
+            .into_iter()
+            .map(|name| {
+                let tool = &self.tools[&name];
+                let mut schema = tool.get_parameters_schema();
+                if let Some(object) = schema.as_object_mut() {
+                    object
+                        .entry("description")
+                        .or_insert_with(|| tool.description().into());
+                }
+                (name, schema)
+            })
+            .collect();
+        serde_json::json!({
+            "$schema": crate::schema::SCHEMA_DIALECT,
+            "$defs": defs
+        })
+    }
+
     /// Get the current tool state
     pub fn get_state(&self) -> Arc<Mutex<crate::state::ToolState>> {
         Arc::clone(&self.state)
     }
+
+    /// Subscribe to the events published as tools run in any session
+    ///
+    /// `recv` blocks until the next event and `try_recv` returns at once; async code
+    /// polls with `try_recv` or waits in a blocking task.
+    pub fn subscribe(&self) -> crate::events::EventReceiver {
+        self.events.subscribe()
+    }
+
+    /// Bind the registry to session `id`, creating the session if needed
+    pub fn session(&mut self, id: &str) -> SessionHandle<'_> {
+        SessionHandle {
+            registry: self,
+            id: id.to_string(),
+        }
+    }
+
+    /// State of session `id`, created from the default session's settings on first use
+    fn session_state(&mut self, id: &str) -> Arc<Mutex<crate::state::ToolState>> {
+        if id == DEFAULT_SESSION {
+            return Arc::clone(&self.state);
+        }
+        let default = &self.state;
+        let events = &self.events;
+        let state = self.sessions.entry(id.to_string()).or_insert_with(|| {
+            let mut session = match default.lock() {
+                Ok(default) => default.new_session(),
+                Err(_) => crate::state::ToolState::new(),
+            };
+            session.events = Some(events.clone());
+            Arc::new(Mutex::new(session))
+        });
+        Arc::clone(state)
+    }
+
+    /// Ids of all sessions, the default session first
+    pub fn list_sessions(&self) -> Vec<String> {
+        let mut ids: Vec<String> = self.sessions.keys().cloned().collect();
+        ids.sort();
+        ids.insert(0, DEFAULT_SESSION.to_string());
+        ids
+    }
+
+    /// Drop session `id` and its state; the default session cannot be closed
+    pub fn close_session(&mut self, id: &str) -> bool {
+        self.sessions.remove(id).is_some()
+    }
+
+    /// Record a plan marker ("starting step 3: write tests") in the default session's
+    /// history, shown by `_state` and `submit`
+    pub fn annotate_history(&self, text: impl Into<String>) {
+        if let Ok(mut state) = self.state.lock() {
+            state.annotate_history(text);
+        }
+    }
+
+    /// Run `hook` before every tool call; a hook returning `Err(reason)` refuses the call
+    pub fn add_before_hook(
+        &mut self,
+        hook: impl Fn(&ToolDescriptor, &ToolArgs) -> Result<(), String> + Send + Sync + 'static,
+    ) {
+        self.before_hooks.push(Box::new(hook));
+    }
+
+    /// Refuse navigation, editing and search calls naming paths outside the guard's
+    /// root; `None` lifts the confinement
+    pub fn set_workspace_guard(&mut self, guard: Option<WorkspaceGuard>) {
+        self.guard = guard;
+    }
+
+    /// The guard confining path arguments, if any
+    pub fn workspace_guard(&self) -> Option<&WorkspaceGuard> {
+        self.guard.as_ref()
+    }
+
+    /// In dry-run mode, tools above `read_only` report what they would run instead of
+    /// running
+    pub fn set_dry_run(&mut self, dry_run: bool) {
+        self.dry_run = dry_run;
+    }
+
+    /// In strict mode, a call whose arguments do not match the tool's schema fails with
+    /// `invalid_args` before the tool runs
+    ///
+    /// Unknown parameters, missing required ones, wrong types and values outside an
+    /// `enum` or range are all reported at once. Without strict mode, tools ignore
+    /// parameters they do not know.
+    pub fn set_strict_args(&mut self, strict: bool) {
+        self.strict_args = strict;
+    }
+
+    /// Set the verbosity of calls that do not pass a `verbosity` argument
+    pub fn set_verbosity(&mut self, verbosity: Verbosity) {
+        self.verbosity = verbosity;
+    }
+
+    /// Set the templates tools render their messages with
+    ///
+    /// Applies to every existing session and to sessions created later.
+    pub fn set_message_style(&self, style: crate::messages::MessageStyle) {
+        for state in std::iter::once(&self.state).chain(self.sessions.values()) {
+            if let Ok(mut state) = state.lock() {
+                state.message_style = style.clone();
+            }
+        }
+    }
+
+    /// Attribute every file change to its session, tool and call in
+    /// `.cats/provenance.json` of the session's working directory; see
+    /// [`crate::state::provenance`]
+    ///
+    /// Applies to every existing session and to sessions created later.
+    pub fn set_provenance(&self, on: bool) {
+        for state in std::iter::once(&self.state).chain(self.sessions.values()) {
+            if let Ok(mut state) = state.lock() {
+                state.provenance = on;
+            }
+        }
+    }
+
+    /// Journal every file change under `.cats/journal/` of the session's working
+    /// directory, so `cats recover` can roll back a batch a crash cut short; see
+    /// [`crate::state::journal`]
+    ///
+    /// Applies to every existing session and to sessions created later.
+    pub fn set_journaling(&self, on: bool) {
+        for state in std::iter::once(&self.state).chain(self.sessions.values()) {
+            if let Ok(mut state) = state.lock() {
+                state.set_journaling(on);
+            }
+        }
+    }
+
+    /// Warn when a session calls the same tool with the same arguments `threshold`
+    /// times among its recent calls with no file changing in between; `None` turns the
+    /// warning off
+    ///
+    /// Applies to every existing session and to sessions created later.
+    pub fn set_repeat_threshold(&self, threshold: Option<usize>) {
+        for state in std::iter::once(&self.state).chain(self.sessions.values()) {
+            if let Ok(mut state) = state.lock() {
+                state.recent_calls.threshold = threshold;
+            }
+        }
+    }
+
+    /// Replace the search filter rules used by searches and listings
+    ///
+    /// Takes precedence over the `[search_filtering]` table of `cats.toml`. Applies to
+    /// every existing session and to sessions created later.
+    pub fn set_search_filtering(&self, filtering: crate::search::SearchFilteringConfig) {
+        for state in std::iter::once(&self.state).chain(self.sessions.values()) {
+            if let Ok(mut state) = state.lock() {
+                state.search_filtering = Some(filtering.clone());
+            }
+        }
+    }
+
+    /// Replace the protected path globs enforced by destructive tools
+    ///
+    /// Takes precedence over the `[protection]` table of `cats.toml`. Applies to every
+    /// existing session and to sessions created later.
+    pub fn set_protected_paths(&self, paths: crate::editing::protection::ProtectedPaths) {
+        for state in std::iter::once(&self.state).chain(self.sessions.values()) {
+            if let Ok(mut state) = state.lock() {
+                state.protected_paths = Some(paths.clone());
+            }
+        }
+    }
 }
 
 impl Default for ToolRegistry {
@@ -298,6 +1804,130 @@ mod tests {
         }
     }
 
+    /// A read-only lookup that takes a while and records how many calls overlap
+    struct SlowLookup {
+        name: String,
+        running: Arc<std::sync::atomic::AtomicUsize>,
+        peak: Arc<std::sync::atomic::AtomicUsize>,
+    }
+
+    impl Tool for SlowLookup {
+        fn name(&self) -> &str {
+            &self.name
+        }
+
+        fn description(&self) -> &str {
+            "Slow lookup for testing"
+        }
+
+        fn signature(&self) -> &str {
+            "slow <arg>"
+        }
+
+        fn category(&self) -> ToolCategory {
+            ToolCategory::Search
+        }
+
+        fn danger_level(&self) -> DangerLevel {
+            DangerLevel::ReadOnly
+        }
+
+        fn validate_args(&self, _args: &ToolArgs) -> Result<(), ToolError> {
+            Ok(())
+        }
+
+        fn execute(
+            &mut self,
+            args: &ToolArgs,
+            _state: &Arc<Mutex<crate::state::ToolState>>,
+        ) -> Result<ToolResult> {
+            use std::sync::atomic::Ordering;
+            let running = self.running.fetch_add(1, Ordering::SeqCst) + 1;
+            self.peak.fetch_max(running, Ordering::SeqCst);
+            std::thread::sleep(std::time::Duration::from_millis(100));
+            self.running.fetch_sub(1, Ordering::SeqCst);
+            Ok(ToolResult::success(format!(
+                "{} {}",
+                self.name,
+                args.get_arg(0).map_or("", String::as_str)
+            )))
+        }
+    }
+
+    #[test]
+    fn test_execute_batch_runs_lookups_in_parallel() {
+        let running = Arc::new(std::sync::atomic::AtomicUsize::new(0));
+        let peak = Arc::new(std::sync::atomic::AtomicUsize::new(0));
+        let mut registry = ToolRegistry::new();
+        for name in ["slow_a", "slow_b"] {
+            registry.register(Box::new(SlowLookup {
+                name: name.to_string(),
+                running: Arc::clone(&running),
+                peak: Arc::clone(&peak),
+            }));
+        }
+        registry.register(Box::new(MockTool::new("mock")));
+        let call = |name: &str, arg: &str| (name.to_string(), ToolArgs::from_args(&[arg]));
+        let calls = [
+            call("slow_a", "1"),
+            call("slow_b", "2"),
+            call("slow_a", "3"),
+            call("mock", "4"),
+            call("slow_b", "5"),
+            call("missing", "6"),
+        ];
+
+        let messages = |outcomes: Vec<Option<BatchOutcome>>| -> Vec<String> {
+            outcomes
+                .into_iter()
+                .map(|outcome| match outcome.map(|outcome| outcome.result) {
+                    Some(Ok(result)) => result.message,
+                    Some(Err(e)) => e.kind().to_string(),
+                    None => "skipped".to_string(),
+                })
+                .collect()
+        };
+        let expected = [
+            "slow_a 1",
+            "slow_b 2",
+            "slow_a 3",
+            "Mock tool mock executed with 1 args",
+            "slow_b 5",
+            "tool_not_found",
+        ];
+
+        assert_eq!(messages(registry.execute_batch(&calls, 4, false)), expected);
+        assert_eq!(peak.load(std::sync::atomic::Ordering::SeqCst), 2);
+
+        peak.store(0, std::sync::atomic::Ordering::SeqCst);
+        assert_eq!(messages(registry.execute_batch(&calls, 1, false)), expected);
+        assert_eq!(peak.load(std::sync::atomic::Ordering::SeqCst), 1);
+
+        let outcomes = registry.execute_batch(&calls[..1], 1, true);
+        assert!(outcomes[0].as_ref().unwrap().duration >= std::time::Duration::from_millis(100));
+
+        // A failure stops the batch; the lookup running beside it still finishes
+        let calls = [
+            call("slow_a", "7"),
+            call("missing", "2"),
+            call("slow_b", "9"),
+            call("mock", "4"),
+        ];
+        assert_eq!(
+            messages(registry.execute_batch(&calls, 4, true)),
+            ["slow_a 7", "tool_not_found", "skipped", "skipped"]
+        );
+        assert_eq!(
+            messages(registry.execute_batch(&calls, 4, false)),
+            [
+                "slow_a 7",
+                "tool_not_found",
+                "slow_b 9",
+                "Mock tool mock executed with 1 args"
+            ]
+        );
+    }
+
     #[test]
     fn test_tool_args_creation() {
         let args = ToolArgs::from_args(&["arg1", "arg2"]);
@@ -306,6 +1936,19 @@ mod tests {
         assert_eq!(args.get_arg(1), Some(&"arg2".to_string()));
     }
 
+    #[test]
+    fn test_tool_args_from_cli_tokens() {
+        let tokens: Vec<String> = ["path=src/lib.rs", "a=b", "json={}", "plain"]
+            .iter()
+            .map(|t| t.to_string())
+            .collect();
+        let args = ToolArgs::from_cli_tokens(&tokens, &["path"]);
+
+        assert_eq!(args.get_named_arg("path"), Some(&"src/lib.rs".to_string()));
+        assert_eq!(args.get_named_arg("json"), Some(&"{}".to_string()));
+        assert_eq!(args.args, vec!["a=b".to_string(), "plain".to_string()]);
+    }
+
     #[test]
     fn test_tool_result_creation() {
         let result = ToolResult::success("Test message");
@@ -339,6 +1982,43 @@ mod tests {
         assert!(result.is_err());
     }
 
+    #[test]
+    fn test_unknown_tool_suggestions() {
+        let mut registry = ToolRegistry::new();
+        registry.register(Box::new(MockTool::new("search_file")));
+        registry.register(Box::new(MockTool::new("open")));
+
+        let args = ToolArgs::from_args(&["x"]);
+        match registry.execute_tool("serch_file", &args) {
+            Err(ToolError::ToolNotFound { suggestions, .. }) => {
+                assert_eq!(suggestions, vec!["search_file".to_string()]);
+            }
+            other => panic!("expected ToolNotFound, got {:?}", other),
+        }
+
+        let err = registry.execute_tool("serch_file", &args).unwrap_err();
+        assert!(err.to_string().contains("Did you mean: search_file?"));
+    }
+
+    #[test]
+    fn test_error_exit_codes_and_json() {
+        let not_found = ToolError::ToolNotFound {
+            name: "serch".to_string(),
+            suggestions: vec!["search".to_string()],
+        };
+        let invalid = ToolError::InvalidArgs {
+            message: "bad".to_string(),
+        };
+        assert_ne!(not_found.exit_code(), invalid.exit_code());
+        assert_ne!(not_found.exit_code(), 0);
+        assert_ne!(not_found.exit_code(), 1);
+
+        let json = not_found.to_json();
+        assert_eq!(json["code"], 3);
+        assert_eq!(json["kind"], "tool_not_found");
+        assert_eq!(json["suggestions"][0], "search");
+    }
+
     #[test]
     fn test_tool_validation() {
         let mut registry = ToolRegistry::new();
@@ -349,4 +2029,146 @@ mod tests {
         let result = registry.execute_tool("test_tool", &empty_args);
         assert!(result.is_err());
     }
+
+    #[test]
+    fn test_generated_path_warnings() {
+        let temp_dir = tempfile::TempDir::new().unwrap();
+        let mut registry = ToolRegistry::new();
+        registry.register(Box::new(crate::editing::CreateFileTool::new()));
+        {
+            let state = registry.get_state();
+            let mut state = state.lock().unwrap();
+            state.working_directory = temp_dir.path().to_path_buf();
+            state.journal = None;
+        }
+
+        let generated = temp_dir.path().join("target/gen.rs");
+        let args = ToolArgs::from_args(&[generated.to_str().unwrap(), "fn main() {}"]);
+        let result = registry.execute_tool("create_file", &args).unwrap();
+        assert!(result.success);
+        assert!(result.message.contains("Warning:"));
+        let warnings = &result.data.unwrap()["warnings"];
+        assert!(warnings[0]
+            .as_str()
+            .unwrap()
+            .contains("generated directory target/"));
+
+        let source = temp_dir.path().join("src/gen.rs");
+        let args = ToolArgs::from_args(&[source.to_str().unwrap(), "fn main() {}"]);
+        let result = registry.execute_tool("create_file", &args).unwrap();
+        assert!(!result.message.contains("Warning:"));
+        assert_eq!(result.data.unwrap()["verified"], true);
+    }
+
+    #[test]
+    fn test_sessions_have_separate_state() {
+        let temp_dir = tempfile::TempDir::new().unwrap();
+        let mut registry = ToolRegistry::new();
+        registry.register(Box::new(crate::editing::CreateFileTool::new()));
+        {
+            let state = registry.get_state();
+            let mut state = state.lock().unwrap();
+            state.working_directory = temp_dir.path().to_path_buf();
+            state.journal = None;
+        }
+
+        let a = temp_dir.path().join("a.txt");
+        let args = ToolArgs::with_named_args(
+            vec![a.to_string_lossy().to_string(), "a".to_string()],
+            HashMap::from([(SESSION_ARG.to_string(), "agent-1".to_string())]),
+        );
+        // The session argument is stripped, so the positional arguments still apply
+        assert!(registry.execute_tool("create_file", &args).unwrap().success);
+
+        let b = temp_dir.path().join("b.txt");
+        let args = ToolArgs::from_args(&[b.to_str().unwrap(), "b"]);
+        let mut session = registry.session("agent-2");
+        assert!(session.execute_tool("create_file", &args).unwrap().success);
+
+        assert_eq!(
+            registry.list_sessions(),
+            vec!["default", "agent-1", "agent-2"]
+        );
+        let history = |state: Arc<Mutex<crate::state::ToolState>>| {
+            let state = state.lock().unwrap();
+            assert_eq!(state.working_directory, temp_dir.path());
+            state
+                .history
+                .iter()
+                .map(|h| h.operation.clone())
+                .collect::<Vec<_>>()
+        };
+        assert!(history(registry.get_state()).is_empty());
+        assert_eq!(
+            history(registry.session("agent-1").get_state()),
+            vec![format!("Created file: {}", a.display())]
+        );
+        assert!(registry.close_session("agent-1"));
+        assert!(!registry.close_session(DEFAULT_SESSION));
+    }
+
+    #[test]
+    fn test_session_working_directory_resolves_relative_paths() {
+        let temp_dir = tempfile::TempDir::new().unwrap();
+        let mut registry = ToolRegistry::new();
+        registry.register(Box::new(crate::editing::CreateFileTool::new()));
+        registry.register(Box::new(crate::editing::DeletePathTool::new()));
+        let mut session = registry.session("agent-1");
+        session.set_working_directory(temp_dir.path());
+
+        let args = ToolArgs::from_args(&["rel.txt", "content"]);
+        assert!(session.execute_tool("create_file", &args).unwrap().success);
+        assert!(temp_dir.path().join("rel.txt").is_file());
+        assert!(!Path::new("rel.txt").exists());
+
+        let args = ToolArgs::from_args(&["--path=rel.txt", "--permanent=true"]);
+        assert!(session.execute_tool("delete_path", &args).unwrap().success);
+        assert!(!temp_dir.path().join("rel.txt").exists());
+    }
+
+    #[test]
+    fn test_subscribe_receives_tool_events() {
+        use crate::events::{ChangeKind, ToolEvent};
+
+        let temp_dir = tempfile::TempDir::new().unwrap();
+        let mut registry = ToolRegistry::new();
+        registry.register(Box::new(crate::editing::CreateFileTool::new()));
+        registry.get_state().lock().unwrap().journal = None;
+        let events = registry.subscribe();
+
+        let path = temp_dir.path().join("a.txt");
+        let args = ToolArgs::from_args(&[path.to_str().unwrap(), "a"]);
+        registry
+            .session("agent-1")
+            .execute_tool("create_file", &args)
+            .unwrap();
+
+        match events.try_recv().unwrap() {
+            ToolEvent::ToolStarted { session, tool, .. } => {
+                assert_eq!(
+                    (session.as_str(), tool.as_str()),
+                    ("agent-1", "create_file")
+                )
+            }
+            other => panic!("unexpected event {:?}", other),
+        }
+        match events.try_recv().unwrap() {
+            ToolEvent::FileModified {
+                path: modified,
+                change_kind,
+                diff,
+                ..
+            } => {
+                assert_eq!(modified, path);
+                assert_eq!(change_kind, ChangeKind::Created);
+                assert!(diff.unwrap().contains("+a"));
+            }
+            other => panic!("unexpected event {:?}", other),
+        }
+        assert!(matches!(
+            events.try_recv().unwrap(),
+            ToolEvent::ToolFinished { success: true, .. }
+        ));
+        assert!(events.try_recv().is_err());
+    }
 }
@@ -0,0 +1,792 @@
+//! SQL queries over data files (`data` feature)
+//!
+//! `query_data` loads CSV, TSV, JSON (an array of objects or one object per line) and
+//! Parquet files into an in-memory SQLite database, one table per file named after the
+//! file stem, and runs a read-only query against them. Column types are inferred from
+//! the values: a column holding only integers is `INTEGER`, only numbers `REAL`,
+//! anything else `TEXT`, and nested JSON values are stored as JSON text so SQLite's
+//! `json_extract` can reach into them. Results are capped at a row limit and rendered as
+//! a table with long cells cut short; the full row count is still reported.
+
+use crate::core::{DangerLevel, Tool, ToolArgs, ToolError, ToolResult};
+use crate::state::ToolState;
+use anyhow::{Context, Result};
+use rusqlite::types::Value as SqlValue;
+use rusqlite::Connection;
+use serde::Serialize;
+use serde_json::Value;
+use std::collections::HashSet;
+use std::fs::File;
+use std::path::{Path, PathBuf};
+use std::sync::{Arc, Mutex};
+use std::time::{Duration, Instant};
+
+/// Rows shown when no `--limit` is given
+pub const DEFAULT_LIMIT: usize = 50;
+/// Largest accepted `--limit`
+pub const MAX_LIMIT: usize = 1000;
+/// Longest cell, in characters, shown in the rendered table
+const MAX_CELL_CHARS: usize = 60;
+/// How long a query may run before it is interrupted
+const QUERY_TIMEOUT: Duration = Duration::from_secs(30);
+
+/// File formats `query_data` can load
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "snake_case")]
+pub enum DataFormat {
+    Csv,
+    Tsv,
+    Json,
+    JsonLines,
+    Parquet,
+}
+
+impl DataFormat {
+    /// Format of `path`, from its extension
+    pub fn detect(path: &Path) -> Option<Self> {
+        let extension = path.extension()?.to_str()?.to_ascii_lowercase();
+        Some(match extension.as_str() {
+            "csv" => Self::Csv,
+            "tsv" | "tab" => Self::Tsv,
+            "json" => Self::Json,
+            "jsonl" | "ndjson" => Self::JsonLines,
+            "parquet" | "pq" => Self::Parquet,
+            _ => return None,
+        })
+    }
+}
+
+/// A column of a loaded table
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+pub struct Column {
+    pub name: String,
+    /// `INTEGER`, `REAL` or `TEXT`
+    pub sql_type: &'static str,
+}
+
+/// A data file loaded into memory, ready to become a SQLite table
+#[derive(Debug, Clone)]
+pub struct Table {
+    pub columns: Vec<Column>,
+    pub rows: Vec<Vec<SqlValue>>,
+}
+
+impl Table {
+    /// Build a table from rows of values, inferring each column's type
+    fn new(names: Vec<String>, rows: Vec<Vec<SqlValue>>) -> Self {
+        let columns = unique_names(names)
+            .into_iter()
+            .enumerate()
+            .map(|(i, name)| Column {
+                name,
+                sql_type: infer_type(rows.iter().filter_map(|row| row.get(i))),
+            })
+            .collect();
+        Self { columns, rows }
+    }
+}
+
+/// Column names with blanks filled in and repeats numbered, so each is a valid column
+fn unique_names(names: Vec<String>) -> Vec<String> {
+    let mut seen = HashSet::new();
+    names
+        .into_iter()
+        .enumerate()
+        .map(|(i, name)| {
+            let name = name.trim();
+            let base = if name.is_empty() {
+                format!("column_{}", i + 1)
+            } else {
+                name.to_string()
+            };
+            let mut candidate = base.clone();
+            let mut n = 2;
+            while !seen.insert(candidate.to_lowercase()) {
+                candidate = format!("{}_{}", base, n);
+                n += 1;
+            }
+            candidate
+        })
+        .collect()
+}
+
+fn infer_type<'a>(values: impl Iterator<Item = &'a SqlValue>) -> &'static str {
+    let mut sql_type = "INTEGER";
+    for value in values {
+        match value {
+            SqlValue::Null | SqlValue::Integer(_) => {}
+            SqlValue::Real(_) => sql_type = "REAL",
+            SqlValue::Text(_) | SqlValue::Blob(_) => return "TEXT",
+        }
+    }
+    sql_type
+}
+
+/// Value of a CSV cell: empty cells are NULL, numbers are numbers
+fn parse_cell(cell: &str) -> SqlValue {
+    if cell.is_empty() {
+        SqlValue::Null
+    } else if let Ok(n) = cell.parse::<i64>() {
+        SqlValue::Integer(n)
+    } else if let Some(x) = cell
+        .parse::<f64>()
+        .ok()
+        .filter(|x| x.is_finite() && cell.chars().any(|c| c.is_ascii_digit()))
+    {
+        SqlValue::Real(x)
+    } else {
+        SqlValue::Text(cell.to_string())
+    }
+}
+
+fn json_to_sql(value: &Value) -> SqlValue {
+    match value {
+        Value::Null => SqlValue::Null,
+        Value::Bool(b) => SqlValue::Integer(*b as i64),
+        Value::Number(n) => match n.as_i64() {
+            Some(i) => SqlValue::Integer(i),
+            None => n.as_f64().map_or(SqlValue::Null, SqlValue::Real),
+        },
+        Value::String(s) => SqlValue::Text(s.clone()),
+        Value::Array(_) | Value::Object(_) => SqlValue::Text(value.to_string()),
+    }
+}
+
+/// Parse delimited text with a header row
+pub fn parse_delimited(text: &str, delimiter: u8) -> Result<Table> {
+    let mut reader = csv::ReaderBuilder::new()
+        .delimiter(delimiter)
+        .flexible(true)
+        .from_reader(text.as_bytes());
+    let names: Vec<String> = reader.headers()?.iter().map(str::to_string).collect();
+    let mut rows = Vec::new();
+    for (i, record) in reader.records().enumerate() {
+        let record = record.with_context(|| format!("Invalid record {}", i + 1))?;
+        let mut row: Vec<SqlValue> = record.iter().map(parse_cell).collect();
+        row.resize(names.len(), SqlValue::Null);
+        rows.push(row);
+    }
+    Ok(Table::new(names, rows))
+}
+
+/// Tabulate JSON records: columns are the union of the objects' keys in first-seen
+/// order, and a record that is not an object becomes a single `value` column
+fn records_to_table(records: Vec<Value>) -> Table {
+    let mut names: Vec<String> = Vec::new();
+    let mut known = HashSet::new();
+    for record in &records {
+        let keys: Vec<&str> = match record {
+            Value::Object(map) => map.keys().map(String::as_str).collect(),
+            _ => vec!["value"],
+        };
+        for key in keys {
+            if known.insert(key.to_string()) {
+                names.push(key.to_string());
+            }
+        }
+    }
+    let rows = records
+        .iter()
+        .map(|record| {
+            names
+                .iter()
+                .map(|name| match record {
+                    Value::Object(map) => map.get(name).map_or(SqlValue::Null, json_to_sql),
+                    other if name == "value" => json_to_sql(other),
+                    _ => SqlValue::Null,
+                })
+                .collect()
+        })
+        .collect();
+    Table::new(names, rows)
+}
+
+/// Parse a JSON array of records, or a single record
+pub fn parse_json(text: &str) -> Result<Table> {
+    let records = match serde_json::from_str(text).context("Invalid JSON")? {
+        Value::Array(records) => records,
+        record => vec![record],
+    };
+    Ok(records_to_table(records))
+}
+
+/// Parse one JSON record per line, skipping blank lines
+pub fn parse_json_lines(text: &str) -> Result<Table> {
+    let records = text
+        .lines()
+        .enumerate()
+        .filter(|(_, line)| !line.trim().is_empty())
+        .map(|(i, line)| {
+            serde_json::from_str(line).with_context(|| format!("Invalid JSON on line {}", i + 1))
+        })
+        .collect::<Result<Vec<Value>>>()?;
+    Ok(records_to_table(records))
+}
+
+/// Read the rows of a Parquet file
+pub fn parse_parquet(path: &Path) -> Result<Table> {
+    use parquet::file::reader::{FileReader, SerializedFileReader};
+
+    let reader = SerializedFileReader::new(File::open(path)?)?;
+    let names: Vec<String> = reader
+        .metadata()
+        .file_metadata()
+        .schema_descr()
+        .root_schema()
+        .get_fields()
+        .iter()
+        .map(|field| field.name().to_string())
+        .collect();
+    let mut rows = Vec::new();
+    for row in reader.get_row_iter(None)? {
+        let row = row?;
+        let mut values = vec![SqlValue::Null; names.len()];
+        for (name, field) in row.get_column_iter() {
+            if let Some(i) = names.iter().position(|n| n == name) {
+                values[i] = json_to_sql(&field.to_json_value());
+            }
+        }
+        rows.push(values);
+    }
+    Ok(Table::new(names, rows))
+}
+
+/// Load `path` in the format its extension names
+pub fn load_file(path: &Path) -> Result<Table> {
+    let format = DataFormat::detect(path).with_context(|| {
+        format!(
+            "Unsupported data file {} (expected .csv, .tsv, .json, .jsonl, .ndjson or .parquet)",
+            path.display()
+        )
+    })?;
+    let read = || {
+        std::fs::read_to_string(path).with_context(|| format!("Failed to read {}", path.display()))
+    };
+    match format {
+        DataFormat::Csv => parse_delimited(&read()?, b','),
+        DataFormat::Tsv => parse_delimited(&read()?, b'\t'),
+        DataFormat::Json => parse_json(&read()?),
+        DataFormat::JsonLines => parse_json_lines(&read()?),
+        DataFormat::Parquet => parse_parquet(path),
+    }
+    .with_context(|| format!("Failed to load {}", path.display()))
+}
+
+/// SQL table name for a file: its stem, lowercased, with anything but letters, digits
+/// and `_` replaced
+pub fn table_name(path: &Path) -> String {
+    let stem = path
+        .file_stem()
+        .map(|stem| stem.to_string_lossy().to_lowercase())
+        .unwrap_or_default();
+    let name: String = stem
+        .chars()
+        .map(|c| if c.is_ascii_alphanumeric() { c } else { '_' })
+        .collect();
+    match name.chars().next() {
+        None => "data".to_string(),
+        Some(c) if c.is_ascii_digit() => format!("t_{}", name),
+        Some(_) => name,
+    }
+}
+
+fn quote_identifier(name: &str) -> String {
+    format!("\"{}\"", name.replace('"', "\"\""))
+}
+
+/// A table created in the query database
+#[derive(Debug, Clone, Serialize)]
+pub struct LoadedTable {
+    pub name: String,
+    pub file: String,
+    pub columns: Vec<Column>,
+    pub rows: usize,
+}
+
+impl LoadedTable {
+    fn schema(&self) -> String {
+        let columns: Vec<String> = self
+            .columns
+            .iter()
+            .map(|column| format!("{} {}", column.name, column.sql_type))
+            .collect();
+        format!(
+            "{}({}) -- {} row(s) from {}",
+            self.name,
+            columns.join(", "),
+            self.rows,
+            self.file
+        )
+    }
+}
+
+/// Create `table` as `name` in `conn`
+pub fn create_table(conn: &mut Connection, name: &str, table: &Table) -> Result<()> {
+    let columns: Vec<String> = table
+        .columns
+        .iter()
+        .map(|column| format!("{} {}", quote_identifier(&column.name), column.sql_type))
+        .collect();
+    let tx = conn.transaction()?;
+    tx.execute(
+        &format!(
+            "CREATE TABLE {} ({})",
+            quote_identifier(name),
+            columns.join(", ")
+        ),
+        [],
+    )?;
+    if !table.columns.is_empty() {
+        let placeholders = vec!["?"; table.columns.len()].join(", ");
+        let mut insert = tx.prepare(&format!(
+            "INSERT INTO {} VALUES ({})",
+            quote_identifier(name),
+            placeholders
+        ))?;
+        for row in &table.rows {
+            insert.execute(rusqlite::params_from_iter(row))?;
+        }
+    }
+    tx.commit()?;
+    Ok(())
+}
+
+/// The outcome of a query: the first rows and how many there were in all
+#[derive(Debug, Clone, Serialize)]
+pub struct QueryResult {
+    pub columns: Vec<String>,
+    pub rows: Vec<Vec<Value>>,
+    pub total_rows: usize,
+}
+
+fn sql_to_json(value: SqlValue) -> Value {
+    match value {
+        SqlValue::Null => Value::Null,
+        SqlValue::Integer(i) => i.into(),
+        SqlValue::Real(x) => serde_json::Number::from_f64(x).map_or(Value::Null, Value::Number),
+        SqlValue::Text(s) => Value::String(s),
+        SqlValue::Blob(bytes) => Value::String(format!("<{} byte blob>", bytes.len())),
+    }
+}
+
+/// Run the read-only statement `sql`, keeping the first `limit` rows
+///
+/// Statements that could change the database are refused, and the query is interrupted
+/// once `timeout` has passed.
+pub fn run_query(
+    conn: &Connection,
+    sql: &str,
+    limit: usize,
+    timeout: Duration,
+) -> Result<QueryResult> {
+    let mut stmt = conn.prepare(sql)?;
+    if !stmt.readonly() {
+        anyhow::bail!("Only read-only queries are allowed (SELECT, WITH, EXPLAIN, ...)");
+    }
+    let columns: Vec<String> = stmt.column_names().into_iter().map(String::from).collect();
+    let deadline = Instant::now() + timeout;
+    conn.progress_handler(1000, Some(move || Instant::now() > deadline));
+    let mut result = QueryResult {
+        columns,
+        rows: Vec::new(),
+        total_rows: 0,
+    };
+    let outcome = (|| -> rusqlite::Result<()> {
+        let mut rows = stmt.query([])?;
+        while let Some(row) = rows.next()? {
+            if result.rows.len() < limit {
+                result.rows.push(
+                    (0..result.columns.len())
+                        .map(|i| row.get::<_, SqlValue>(i).map(sql_to_json))
+                        .collect::<rusqlite::Result<_>>()?,
+                );
+            }
+            result.total_rows += 1;
+        }
+        Ok(())
+    })();
+    conn.progress_handler(0, None::<fn() -> bool>);
+    match outcome {
+        Err(rusqlite::Error::SqliteFailure(e, _))
+            if e.code == rusqlite::ErrorCode::OperationInterrupted =>
+        {
+            anyhow::bail!("Query interrupted after {}s", timeout.as_secs())
+        }
+        other => other?,
+    }
+    Ok(result)
+}
+
+fn truncate(text: &str) -> String {
+    let text = text.replace(['\n', '\r'], " ").replace('|', "\\|");
+    if text.chars().count() <= MAX_CELL_CHARS {
+        return text;
+    }
+    let kept: String = text.chars().take(MAX_CELL_CHARS - 3).collect();
+    format!("{}...", kept)
+}
+
+/// Render `result` as a pipe table
+pub fn render_table(result: &QueryResult) -> String {
+    let cells: Vec<Vec<String>> = result
+        .rows
+        .iter()
+        .map(|row| {
+            row.iter()
+                .map(|value| match value {
+                    Value::Null => "NULL".to_string(),
+                    Value::String(s) => truncate(s),
+                    other => truncate(&other.to_string()),
+                })
+                .collect()
+        })
+        .collect();
+    let headers: Vec<String> = result.columns.iter().map(|c| truncate(c)).collect();
+    let widths: Vec<usize> = headers
+        .iter()
+        .enumerate()
+        .map(|(i, header)| {
+            cells
+                .iter()
+                .map(|row| row[i].chars().count())
+                .chain([header.chars().count(), 3])
+                .max()
+                .unwrap_or(3)
+        })
+        .collect();
+    let line = |row: &[String]| {
+        let padded: Vec<String> = row
+            .iter()
+            .zip(&widths)
+            .map(|(cell, width)| format!("{:<width$}", cell, width = width))
+            .collect();
+        format!("| {} |", padded.join(" | "))
+    };
+    let separator: Vec<String> = widths.iter().map(|width| "-".repeat(*width)).collect();
+    let mut lines = vec![line(&headers), format!("| {} |", separator.join(" | "))];
+    lines.extend(cells.iter().map(|row| line(row)));
+    lines.join("\n")
+}
+
+/// Tool running SQL over CSV, JSON and Parquet files
+pub struct QueryDataTool {
+    name: String,
+}
+
+impl QueryDataTool {
+    pub fn new() -> Self {
+        Self {
+            name: "query_data".to_string(),
+        }
+    }
+
+    fn limit(args: &ToolArgs) -> Result<usize, ToolError> {
+        match args.get_named_arg("limit") {
+            None => Ok(DEFAULT_LIMIT),
+            Some(limit) => match limit.parse::<usize>() {
+                Ok(limit) if (1..=MAX_LIMIT).contains(&limit) => Ok(limit),
+                _ => Err(ToolError::InvalidArgs {
+                    message: format!(
+                        "Invalid limit '{}': expected a number from 1 to {}",
+                        limit, MAX_LIMIT
+                    ),
+                }),
+            },
+        }
+    }
+}
+
+impl Default for QueryDataTool {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl Tool for QueryDataTool {
+    fn name(&self) -> &str {
+        &self.name
+    }
+
+    fn description(&self) -> &str {
+        "Run a read-only SQL query (SQLite dialect) over CSV, TSV, JSON, JSON Lines or Parquet files, each loaded as a table named after its file stem, and return the first rows as a table"
+    }
+
+    fn signature(&self) -> &str {
+        "query_data <file>[,<file>...] <sql> [--limit=N]"
+    }
+
+    fn danger_level(&self) -> DangerLevel {
+        DangerLevel::ReadOnly
+    }
+
+    fn validate_args(&self, args: &ToolArgs) -> Result<(), ToolError> {
+        if args
+            .get_named_arg("file")
+            .or_else(|| args.get_arg(0))
+            .is_none()
+        {
+            return Err(ToolError::InvalidArgs {
+                message: "Missing required argument: file".to_string(),
+            });
+        }
+        let sql = args.get_named_arg("sql").or_else(|| {
+            args.get_arg(if args.get_named_arg("file").is_some() {
+                0
+            } else {
+                1
+            })
+        });
+        if sql.is_none_or(|sql| sql.trim().is_empty()) {
+            return Err(ToolError::InvalidArgs {
+                message: "Missing required argument: sql".to_string(),
+            });
+        }
+        Self::limit(args).map(|_| ())
+    }
+
+    fn execute(&mut self, args: &ToolArgs, state: &Arc<Mutex<ToolState>>) -> Result<ToolResult> {
+        let named_file = args.get_named_arg("file");
+        let files = named_file
+            .or_else(|| args.get_arg(0))
+            .map_or("", String::as_str);
+        let sql = args
+            .get_named_arg("sql")
+            .or_else(|| args.get_arg(if named_file.is_some() { 0 } else { 1 }))
+            .map_or("", String::as_str);
+        let limit = Self::limit(args).map_err(|e| anyhow::anyhow!("{}", e))?;
+        let working_directory = state
+            .lock()
+            .map_err(|e| anyhow::anyhow!("Failed to lock state: {}", e))?
+            .working_directory
+            .clone();
+
+        let mut conn = Connection::open_in_memory()?;
+        let mut tables: Vec<LoadedTable> = Vec::new();
+        for file in files.split(',').map(str::trim).filter(|f| !f.is_empty()) {
+            let path: PathBuf = working_directory.join(file);
+            if !path.is_file() {
+                return Ok(crate::utils::suggest::path_not_found(
+                    format!("File {} not found", path.display()),
+                    &path,
+                ));
+            }
+            let table = match load_file(&path) {
+                Ok(table) => table,
+                Err(e) => return Ok(ToolResult::error(format!("{:#}", e))),
+            };
+            let base = table_name(&path);
+            let mut name = base.clone();
+            let mut n = 2;
+            while tables.iter().any(|t| t.name == name) {
+                name = format!("{}_{}", base, n);
+                n += 1;
+            }
+            create_table(&mut conn, &name, &table)?;
+            tables.push(LoadedTable {
+                name,
+                file: file.to_string(),
+                columns: table.columns,
+                rows: table.rows.len(),
+            });
+        }
+        // `data` always names the first file, whatever it is called
+        if let Some(first) = tables.first().filter(|t| t.name != "data") {
+            conn.execute(
+                &format!(
+                    "CREATE VIEW data AS SELECT * FROM {}",
+                    quote_identifier(&first.name)
+                ),
+                [],
+            )?;
+        }
+        let schemas: Vec<String> = tables.iter().map(LoadedTable::schema).collect();
+
+        let result = match run_query(&conn, sql, limit, QUERY_TIMEOUT) {
+            Ok(result) => result,
+            Err(e) => {
+                return Ok(ToolResult::error_with_data(
+                    format!("Query failed: {:#}\n\nTables:\n{}", e, schemas.join("\n")),
+                    serde_json::json!({"tables": tables}),
+                ))
+            }
+        };
+
+        let shown = result.rows.len();
+        let message = if result.columns.is_empty() {
+            "Query returned no columns".to_string()
+        } else if shown < result.total_rows {
+            format!(
+                "{}\n\nShowing {} of {} row(s); raise --limit (max {}) or narrow the query to see more",
+                render_table(&result),
+                shown,
+                result.total_rows,
+                MAX_LIMIT
+            )
+        } else {
+            format!("{}\n\n{} row(s)", render_table(&result), result.total_rows)
+        };
+        Ok(ToolResult::success_with_data(
+            message,
+            serde_json::json!({
+                "tables": tables,
+                "columns": result.columns,
+                "rows": result.rows,
+                "total_rows": result.total_rows,
+                "truncated": shown < result.total_rows,
+            }),
+        ))
+    }
+
+    fn get_parameters_schema(&self) -> serde_json::Value {
+        serde_json::json!({
+            "type": "object",
+            "properties": {
+                "file": {
+                    "type": "string",
+                    "description": "Data file to query (.csv, .tsv, .json, .jsonl, .ndjson, .parquet), or several separated by commas; each becomes a table named after its file stem, and the first is also available as `data`"
+                },
+                "sql": {
+                    "type": "string",
+                    "description": "Read-only SQLite query, e.g. SELECT status, count(*) FROM data GROUP BY status"
+                },
+                "limit": {
+                    "type": "integer",
+                    "description": "Rows to return (default 50, at most 1000); the total row count is always reported"
+                }
+            },
+            "required": ["file", "sql"]
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use tempfile::TempDir;
+
+    fn query(dir: &Path, file: &str, sql: &str) -> ToolResult {
+        let mut tool = QueryDataTool::new();
+        let mut tool_state = ToolState::new();
+        tool_state.working_directory = dir.to_path_buf();
+        let state = Arc::new(Mutex::new(tool_state));
+        let args = ToolArgs::with_named_args(
+            vec![],
+            [("file", file), ("sql", sql)]
+                .into_iter()
+                .map(|(k, v)| (k.to_string(), v.to_string()))
+                .collect(),
+        );
+        tool.validate_args(&args).unwrap();
+        tool.execute(&args, &state).unwrap()
+    }
+
+    #[test]
+    fn test_query_joins_csv_and_json() {
+        let temp_dir = TempDir::new().unwrap();
+        std::fs::write(
+            temp_dir.path().join("orders.csv"),
+            "id,user_id,total\n1,1,9.5\n2,2,\n3,1,20\n",
+        )
+        .unwrap();
+        std::fs::write(
+            temp_dir.path().join("users.jsonl"),
+            "{\"id\": 1, \"name\": \"ada\", \"tags\": [\"admin\"]}\n{\"id\": 2, \"name\": \"bob\"}\n",
+        )
+        .unwrap();
+
+        let result = query(
+            temp_dir.path(),
+            "orders.csv, users.jsonl",
+            "SELECT u.name, count(*) AS n, sum(o.total) AS total FROM orders o JOIN users u ON u.id = o.user_id GROUP BY u.name ORDER BY u.name",
+        );
+        assert!(result.success, "{}", result.message);
+        let data = result.data.unwrap();
+        assert_eq!(data["tables"][0]["columns"][2]["sql_type"], "REAL");
+        assert_eq!(data["tables"][1]["columns"][2]["name"], "tags");
+        assert_eq!(
+            data["rows"],
+            serde_json::json!([["ada", 2, 29.5], ["bob", 1, null]])
+        );
+        assert!(result.message.contains("| ada "));
+
+        let result = query(temp_dir.path(), "users.jsonl", "SELECT * FROM data");
+        assert_eq!(result.data.unwrap()["total_rows"], 2);
+    }
+
+    #[test]
+    fn test_query_refuses_writes_and_reports_schema() {
+        let temp_dir = TempDir::new().unwrap();
+        std::fs::write(temp_dir.path().join("a.csv"), "x\n1\n").unwrap();
+
+        let result = query(temp_dir.path(), "a.csv", "DELETE FROM a");
+        assert!(!result.success);
+        assert!(result.message.contains("read-only"));
+
+        let result = query(temp_dir.path(), "a.csv", "SELECT y FROM a");
+        assert!(!result.success);
+        assert!(
+            result.message.contains("a(x INTEGER)"),
+            "{}",
+            result.message
+        );
+    }
+
+    #[test]
+    fn test_query_parquet_with_limit() {
+        use parquet::data_type::{ByteArray, ByteArrayType, Int64Type};
+        use parquet::file::properties::WriterProperties;
+        use parquet::file::writer::SerializedFileWriter;
+        use parquet::schema::parser::parse_message_type;
+
+        let temp_dir = TempDir::new().unwrap();
+        let path = temp_dir.path().join("2024-events.parquet");
+        let schema = Arc::new(
+            parse_message_type(
+                "message schema { REQUIRED INT64 id; REQUIRED BYTE_ARRAY kind (UTF8); }",
+            )
+            .unwrap(),
+        );
+        let mut writer = SerializedFileWriter::new(
+            File::create(&path).unwrap(),
+            schema,
+            Arc::new(WriterProperties::builder().build()),
+        )
+        .unwrap();
+        let mut row_group = writer.next_row_group().unwrap();
+        let mut column = row_group.next_column().unwrap().unwrap();
+        column
+            .typed::<Int64Type>()
+            .write_batch(&[1, 2, 3], None, None)
+            .unwrap();
+        column.close().unwrap();
+        let mut column = row_group.next_column().unwrap().unwrap();
+        let kinds: Vec<ByteArray> = ["open", "close", "open"]
+            .iter()
+            .map(|kind| ByteArray::from(*kind))
+            .collect();
+        column
+            .typed::<ByteArrayType>()
+            .write_batch(&kinds, None, None)
+            .unwrap();
+        column.close().unwrap();
+        row_group.close().unwrap();
+        writer.close().unwrap();
+
+        let mut tool = QueryDataTool::new();
+        let mut tool_state = ToolState::new();
+        tool_state.working_directory = temp_dir.path().to_path_buf();
+        let state = Arc::new(Mutex::new(tool_state));
+        let args = ToolArgs::from_args(&[
+            "2024-events.parquet",
+            "SELECT id, kind FROM t_2024_events ORDER BY id",
+            "--limit=2",
+        ]);
+        tool.validate_args(&args).unwrap();
+        let result = tool.execute(&args, &state).unwrap();
+        assert!(result.success, "{}", result.message);
+        let data = result.data.unwrap();
+        assert_eq!(data["rows"], serde_json::json!([[1, "open"], [2, "close"]]));
+        assert_eq!(data["total_rows"], 3);
+        assert!(result.message.contains("Showing 2 of 3 row(s)"));
+    }
+}
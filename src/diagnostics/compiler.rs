@@ -0,0 +1,204 @@
+//! Parsers for compiler and type-checker diagnostics
+//!
+//! Supports `cargo`/`rustc` JSON messages (`--message-format=json`), `pyright
+//! --outputjson`, and `tsc` text output.
+
+use regex::Regex;
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+
+/// Severity of a diagnostic, ordered from most to least severe
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum Severity {
+    Error,
+    Warning,
+    Note,
+}
+
+impl Severity {
+    fn parse(level: &str) -> Self {
+        match level.to_lowercase().as_str() {
+            "error" | "error: internal compiler error" => Severity::Error,
+            "warning" => Severity::Warning,
+            _ => Severity::Note,
+        }
+    }
+
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            Severity::Error => "error",
+            Severity::Warning => "warning",
+            Severity::Note => "note",
+        }
+    }
+}
+
+/// A single compiler diagnostic with its primary location
+#[derive(Debug, Clone, PartialEq, Eq, Hash, Serialize, Deserialize)]
+pub struct Diagnostic {
+    pub file: String,
+    /// 1-based line number
+    pub line: usize,
+    /// 1-based column number
+    pub column: usize,
+    pub severity: Severity,
+    /// Diagnostic code such as `E0308`, `TS2322` or a pyright rule name
+    pub code: Option<String>,
+    pub message: String,
+    /// Primary span label (rustc), when present
+    pub label: Option<String>,
+}
+
+/// Parse diagnostics from any supported format, auto-detected per input
+pub fn parse_diagnostics(input: &str) -> Vec<Diagnostic> {
+    let trimmed = input.trim_start();
+    if trimmed.starts_with('{') {
+        // A single pyright report is one JSON document
+        if let Ok(value) = serde_json::from_str::<Value>(trimmed) {
+            if value.get("generalDiagnostics").is_some() {
+                return parse_pyright(&value);
+            }
+        }
+    }
+
+    let mut diagnostics = Vec::new();
+    let mut text_lines = Vec::new();
+    for line in input.lines() {
+        match serde_json::from_str::<Value>(line) {
+            Ok(value) if value.is_object() => diagnostics.extend(parse_rustc_message(&value)),
+            _ => text_lines.push(line),
+        }
+    }
+    diagnostics.extend(parse_tsc(&text_lines.join("\n")));
+    diagnostics
+}
+
+/// Parse one cargo (`reason: compiler-message`) or raw rustc JSON message
+fn parse_rustc_message(value: &Value) -> Option<Diagnostic> {
+    let message = match value.get("reason").and_then(|r| r.as_str()) {
+        Some("compiler-message") => value.get("message")?,
+        Some(_) => return None,
+        None => value,
+    };
+
+    let spans = message.get("spans")?.as_array()?;
+    let span = spans
+        .iter()
+        .find(|s| s.get("is_primary").and_then(|p| p.as_bool()) == Some(true))
+        .or_else(|| spans.first())?;
+
+    Some(Diagnostic {
+        file: span.get("file_name")?.as_str()?.to_string(),
+        line: span.get("line_start")?.as_u64()? as usize,
+        column: span
+            .get("column_start")
+            .and_then(|c| c.as_u64())
+            .unwrap_or(1) as usize,
+        severity: Severity::parse(message.get("level")?.as_str()?),
+        code: message
+            .get("code")
+            .and_then(|c| c.get("code"))
+            .and_then(|c| c.as_str())
+            .map(|c| c.to_string()),
+        message: message.get("message")?.as_str()?.to_string(),
+        label: span
+            .get("label")
+            .and_then(|l| l.as_str())
+            .map(|l| l.to_string()),
+    })
+}
+
+/// Parse a `pyright --outputjson` report (0-based positions)
+fn parse_pyright(report: &Value) -> Vec<Diagnostic> {
+    report
+        .get("generalDiagnostics")
+        .and_then(|d| d.as_array())
+        .map(|entries| {
+            entries
+                .iter()
+                .filter_map(|entry| {
+                    let start = entry.get("range")?.get("start")?;
+                    Some(Diagnostic {
+                        file: entry.get("file")?.as_str()?.to_string(),
+                        line: start.get("line")?.as_u64()? as usize + 1,
+                        column: start.get("character")?.as_u64()? as usize + 1,
+                        severity: Severity::parse(
+                            entry
+                                .get("severity")
+                                .and_then(|s| s.as_str())
+                                .unwrap_or("error"),
+                        ),
+                        code: entry
+                            .get("rule")
+                            .and_then(|r| r.as_str())
+                            .map(|r| r.to_string()),
+                        message: entry.get("message")?.as_str()?.to_string(),
+                        label: None,
+                    })
+                })
+                .collect()
+        })
+        .unwrap_or_default()
+}
+
+/// Parse `tsc` output in either `file(line,col): error TS1: msg` or
+/// `file:line:col - error TS1: msg` (pretty) form
+fn parse_tsc(text: &str) -> Vec<Diagnostic> {
+    let plain = Regex::new(r"^(.+?)\((\d+),(\d+)\): (error|warning) (TS\d+): (.*)$").unwrap();
+    let pretty = Regex::new(r"^(.+?):(\d+):(\d+) - (error|warning) (TS\d+): (.*)$").unwrap();
+
+    text.lines()
+        .filter_map(|line| plain.captures(line).or_else(|| pretty.captures(line)))
+        .map(|caps| Diagnostic {
+            file: caps[1].to_string(),
+            line: caps[2].parse().unwrap_or(1),
+            column: caps[3].parse().unwrap_or(1),
+            severity: Severity::parse(&caps[4]),
+            code: Some(caps[5].to_string()),
+            message: caps[6].to_string(),
+            label: None,
+        })
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parse_cargo_json() {
+        let input = r#"{"reason":"compiler-artifact","package_id":"x"}
+{"reason":"compiler-message","message":{"level":"error","message":"mismatched types","code":{"code":"E0308"},"spans":[{"file_name":"src/lib.rs","line_start":3,"column_start":5,"is_primary":true,"label":"expected `u32`, found `&str`"}]}}
+{"reason":"compiler-message","message":{"level":"error","message":"aborting due to 1 previous error","code":null,"spans":[]}}"#;
+        let diagnostics = parse_diagnostics(input);
+        assert_eq!(diagnostics.len(), 1);
+        assert_eq!(diagnostics[0].code.as_deref(), Some("E0308"));
+        assert_eq!(diagnostics[0].line, 3);
+        assert_eq!(diagnostics[0].severity, Severity::Error);
+    }
+
+    #[test]
+    fn test_parse_pyright_json() {
+        let input = r#"{"version":"1.1","generalDiagnostics":[{"file":"/p/app.py","severity":"error","message":"\"foo\" is not defined","range":{"start":{"line":9,"character":4},"end":{"line":9,"character":7}},"rule":"reportUndefinedVariable"}]}"#;
+        let diagnostics = parse_diagnostics(input);
+        assert_eq!(diagnostics.len(), 1);
+        assert_eq!(diagnostics[0].line, 10);
+        assert_eq!(diagnostics[0].column, 5);
+        assert_eq!(
+            diagnostics[0].code.as_deref(),
+            Some("reportUndefinedVariable")
+        );
+    }
+
+    #[test]
+    fn test_parse_tsc_text() {
+        let input =
+            "src/a.ts(4,7): error TS2322: Type 'string' is not assignable to type 'number'.\n\
+                     src/b.ts:1:1 - warning TS6133: 'x' is declared but its value is never read.";
+        let diagnostics = parse_diagnostics(input);
+        assert_eq!(diagnostics.len(), 2);
+        assert_eq!(diagnostics[0].file, "src/a.ts");
+        assert_eq!(diagnostics[1].severity, Severity::Warning);
+    }
+}
@@ -0,0 +1,694 @@
+//! Heuristic bug localization
+//!
+//! `localize` takes a bug description, an error message or a stack trace and ranks
+//! the workspace files and functions most likely involved. It combines three
+//! signals: frames of any stack trace in the input, identifiers from the input
+//! matched against definitions, paths and file contents, and files changed in the
+//! working tree or the last few commits. Every score comes with the reasons behind
+//! it so the ranking can be judged before acting on it.
+
+use super::trace::{parse_trace, resolve_frames};
+use crate::ast::{self, Definition, Language};
+use crate::core::{DangerLevel, Tool, ToolArgs, ToolCategory, ToolError, ToolResult};
+use crate::search::ConfigurableFilter;
+use crate::state::ToolState;
+use crate::vcs::git;
+use anyhow::Result;
+use regex::Regex;
+use serde::Serialize;
+use std::collections::{BTreeSet, HashMap};
+use std::fmt::Write as _;
+use std::path::{Path, PathBuf};
+use std::sync::{Arc, Mutex, OnceLock};
+use walkdir::WalkDir;
+
+/// Candidates listed by default
+const DEFAULT_LIMIT: usize = 10;
+
+/// Upper bound for `--limit`
+const MAX_LIMIT: usize = 50;
+
+/// Functions listed under each candidate file
+const MAX_SYMBOLS: usize = 3;
+
+/// Source files larger than this are not scanned
+const MAX_FILE_BYTES: u64 = 512 * 1024;
+
+/// Commits whose changed files count as recent
+const RECENT_COMMITS: usize = 10;
+
+/// Words too common in bug reports, traces and code to point anywhere
+const STOPWORDS: &[&str] = &[
+    "about",
+    "after",
+    "all",
+    "and",
+    "any",
+    "are",
+    "async",
+    "bad",
+    "before",
+    "bug",
+    "but",
+    "call",
+    "called",
+    "can",
+    "cannot",
+    "case",
+    "class",
+    "const",
+    "crash",
+    "crashes",
+    "def",
+    "does",
+    "doesn",
+    "error",
+    "errors",
+    "exception",
+    "expected",
+    "fail",
+    "failed",
+    "failing",
+    "fails",
+    "false",
+    "file",
+    "fix",
+    "fn",
+    "for",
+    "from",
+    "function",
+    "get",
+    "got",
+    "has",
+    "have",
+    "home",
+    "how",
+    "impl",
+    "import",
+    "instead",
+    "into",
+    "issue",
+    "its",
+    "let",
+    "lib",
+    "line",
+    "main",
+    "module",
+    "new",
+    "none",
+    "not",
+    "null",
+    "panic",
+    "panicked",
+    "panics",
+    "pub",
+    "raise",
+    "return",
+    "returns",
+    "run",
+    "self",
+    "should",
+    "some",
+    "src",
+    "struct",
+    "test",
+    "tests",
+    "than",
+    "that",
+    "the",
+    "then",
+    "there",
+    "this",
+    "thread",
+    "traceback",
+    "true",
+    "undefined",
+    "use",
+    "usr",
+    "value",
+    "was",
+    "when",
+    "where",
+    "which",
+    "while",
+    "with",
+    "work",
+    "works",
+    "wrong",
+];
+
+fn regex(cell: &'static OnceLock<Regex>, pattern: &str) -> &'static Regex {
+    cell.get_or_init(|| Regex::new(pattern).expect("valid regex"))
+}
+
+static IDENTIFIER: OnceLock<Regex> = OnceLock::new();
+
+/// A term from the query, weighted by how much it looks like code
+#[derive(Debug, Clone, PartialEq)]
+pub struct Term {
+    /// Lowercased identifier
+    pub text: String,
+    /// 2.0 for identifiers such as `parse_config` or `parseConfig`, 1.0 for words
+    pub weight: f64,
+}
+
+/// Identifiers in `query` worth searching for, most code-like first
+pub fn query_terms(query: &str) -> Vec<Term> {
+    let identifier = regex(&IDENTIFIER, r"[A-Za-z_][A-Za-z0-9_]*");
+    let mut terms: Vec<Term> = Vec::new();
+    for m in identifier.find_iter(query) {
+        let word = m.as_str().trim_matches('_');
+        let text = word.to_lowercase();
+        if text.len() < 3 || STOPWORDS.contains(&text.as_str()) {
+            continue;
+        }
+        let code_like = word.contains('_')
+            || word.chars().any(|c| c.is_ascii_digit())
+            || word.chars().skip(1).any(|c| c.is_uppercase());
+        let weight = if code_like { 2.0 } else { 1.0 };
+        match terms.iter_mut().find(|t| t.text == text) {
+            Some(existing) => existing.weight = existing.weight.max(weight),
+            None => terms.push(Term { text, weight }),
+        }
+    }
+    terms.sort_by(|a, b| b.weight.total_cmp(&a.weight));
+    terms
+}
+
+/// A function, type or other definition inside a candidate file
+#[derive(Debug, Clone, Serialize)]
+pub struct SymbolCandidate {
+    pub name: String,
+    pub kind: String,
+    pub container: Option<String>,
+    pub line: usize,
+    pub end_line: usize,
+    pub score: f64,
+}
+
+/// A file ranked by `localize`
+#[derive(Debug, Clone, Serialize)]
+pub struct Candidate {
+    /// Path relative to the workspace root
+    pub path: PathBuf,
+    pub score: f64,
+    /// Why the file scored, strongest signal first
+    pub reasons: Vec<String>,
+    /// Definitions in the file that matched, best first
+    pub symbols: Vec<SymbolCandidate>,
+}
+
+/// Scores gathered for one file before ranking
+#[derive(Default)]
+struct Evidence {
+    score: f64,
+    reasons: Vec<(f64, String)>,
+    symbols: HashMap<usize, f64>,
+}
+
+impl Evidence {
+    fn add(&mut self, score: f64, reason: String) {
+        self.score += score;
+        self.reasons.push((score, reason));
+    }
+}
+
+/// A scanned source file
+struct SourceFile {
+    relative: PathBuf,
+    definitions: Vec<Definition>,
+    /// For each term, the 1-based lines mentioning it
+    mentions: Vec<Vec<usize>>,
+}
+
+impl SourceFile {
+    /// Index of the innermost definition spanning `line`
+    fn enclosing(&self, line: usize) -> Option<usize> {
+        self.definitions
+            .iter()
+            .enumerate()
+            .filter(|(_, d)| d.line <= line && line <= d.end_line)
+            .min_by_key(|(_, d)| d.end_line - d.line)
+            .map(|(i, _)| i)
+    }
+}
+
+/// Source files under `root` with their definitions and where each term appears
+fn scan(root: &Path, filter: &ConfigurableFilter, terms: &[Term]) -> Vec<SourceFile> {
+    let identifier = regex(&IDENTIFIER, r"[A-Za-z_][A-Za-z0-9_]*");
+    WalkDir::new(root)
+        .follow_links(false)
+        .sort_by_file_name()
+        .into_iter()
+        .filter_entry(|e| {
+            e.path() == root
+                || filter.should_include_path(e.path().strip_prefix(root).unwrap_or(e.path()))
+        })
+        .filter_map(|entry| entry.ok())
+        .filter(|entry| entry.file_type().is_file())
+        .filter(|entry| entry.metadata().is_ok_and(|m| m.len() <= MAX_FILE_BYTES))
+        .filter_map(|entry| {
+            let language = Language::from_path(entry.path())?;
+            let content = std::fs::read_to_string(entry.path()).ok()?;
+            let mut mentions = vec![Vec::new(); terms.len()];
+            for (index, line) in content.lines().enumerate() {
+                for m in identifier.find_iter(line) {
+                    let word = m.as_str().trim_matches('_').to_lowercase();
+                    if let Some(t) = terms.iter().position(|t| t.text == word) {
+                        if mentions[t].last() != Some(&(index + 1)) {
+                            mentions[t].push(index + 1);
+                        }
+                    }
+                }
+            }
+            Some(SourceFile {
+                relative: entry
+                    .path()
+                    .strip_prefix(root)
+                    .unwrap_or(entry.path())
+                    .to_path_buf(),
+                definitions: ast::definitions(language, &content),
+                mentions,
+            })
+        })
+        .collect()
+}
+
+/// Files with uncommitted changes, then files touched by each of the last commits,
+/// relative to `root`; empty outside a git repository
+fn recent_changes(root: &Path) -> (BTreeSet<PathBuf>, Vec<Vec<PathBuf>>) {
+    let lines = |output: Result<String>| -> Vec<PathBuf> {
+        output
+            .unwrap_or_default()
+            .lines()
+            .filter(|l| !l.is_empty())
+            .map(PathBuf::from)
+            .collect()
+    };
+    let mut uncommitted: BTreeSet<PathBuf> = lines(git(
+        root,
+        &["diff", "--name-only", "--relative", "HEAD", "--"],
+    ))
+    .into_iter()
+    .collect();
+    uncommitted.extend(lines(git(
+        root,
+        &["ls-files", "--others", "--exclude-standard"],
+    )));
+
+    let limit = format!("-n{}", RECENT_COMMITS);
+    let log = git(
+        root,
+        &["log", &limit, "--name-only", "--relative", "--format=%x00"],
+    )
+    .unwrap_or_default();
+    let commits = log
+        .split('\0')
+        .skip(1)
+        .map(|files| lines(Ok(files.to_string())))
+        .collect();
+    (uncommitted, commits)
+}
+
+/// Rank the files and functions under `root` most likely related to `query`
+pub fn localize(root: &Path, filter: ConfigurableFilter, query: &str) -> Vec<Candidate> {
+    let terms = query_terms(query);
+    let files = scan(root, &filter, &terms);
+    let mut evidence: HashMap<usize, Evidence> = HashMap::new();
+    let by_path: HashMap<&Path, usize> = files
+        .iter()
+        .enumerate()
+        .map(|(i, f)| (f.relative.as_path(), i))
+        .collect();
+
+    // Stack frames: the innermost project frame is the strongest signal there is
+    let mut trace = parse_trace(query);
+    resolve_frames(&mut trace, root, filter);
+    let project_frames = trace.frames.iter().filter(|f| f.in_workspace);
+    for (rank, frame) in project_frames.enumerate() {
+        let Some(&index) = frame.resolved.as_deref().and_then(|p| by_path.get(p)) else {
+            continue;
+        };
+        let score = 8.0 / (rank + 1) as f64;
+        let entry = evidence.entry(index).or_default();
+        entry.add(
+            score,
+            format!("stack frame #{} at line {}", rank + 1, frame.line),
+        );
+        if let Some(symbol) = files[index].enclosing(frame.line) {
+            *entry.symbols.entry(symbol).or_default() += score;
+        }
+    }
+
+    // Query terms, discounted by how many files mention them
+    for (t, term) in terms.iter().enumerate() {
+        let spread = files.iter().filter(|f| !f.mentions[t].is_empty()).count();
+        let idf = term.weight / (1.0 + (spread.max(1) as f64).ln());
+        for (index, file) in files.iter().enumerate() {
+            let mut defined = Vec::new();
+            for (d, definition) in file.definitions.iter().enumerate() {
+                if definition.name.to_lowercase() == term.text {
+                    defined.push(d);
+                }
+            }
+            let stem = file
+                .relative
+                .file_stem()
+                .map(|s| s.to_string_lossy().to_lowercase())
+                .unwrap_or_default();
+            let in_path = file.relative.components().any(|c| {
+                let part = c.as_os_str().to_string_lossy().to_lowercase();
+                part == term.text || part.starts_with(&format!("{}.", term.text))
+            }) || stem == term.text;
+            let mentions = &file.mentions[t];
+            if defined.is_empty() && !in_path && mentions.is_empty() {
+                continue;
+            }
+
+            let entry = evidence.entry(index).or_default();
+            if !defined.is_empty() {
+                entry.add(3.0 * idf, format!("defines `{}`", term.text));
+                for d in defined {
+                    *entry.symbols.entry(d).or_default() += 3.0 * idf;
+                }
+            }
+            if in_path {
+                entry.add(2.0 * idf, format!("path matches `{}`", term.text));
+            }
+            if !mentions.is_empty() {
+                let count = mentions.len().min(5) as f64;
+                entry.add(
+                    0.4 * idf * count,
+                    format!("mentions `{}` ({}x)", term.text, mentions.len()),
+                );
+                let enclosing: BTreeSet<usize> =
+                    mentions.iter().filter_map(|&l| file.enclosing(l)).collect();
+                for symbol in enclosing {
+                    *entry.symbols.entry(symbol).or_default() += 0.5 * idf;
+                }
+            }
+        }
+    }
+
+    // Recent changes break ties between files the other signals point at equally
+    let (uncommitted, commits) = recent_changes(root);
+    for (index, file) in files.iter().enumerate() {
+        let mut change = None;
+        if uncommitted.contains(&file.relative) {
+            change = Some((2.0, "uncommitted changes".to_string()));
+        } else if let Some(age) = commits.iter().position(|c| c.contains(&file.relative)) {
+            let reason = match age {
+                0 => "changed in the last commit".to_string(),
+                n => format!("changed {} commits ago", n),
+            };
+            change = Some((1.5 / (age + 1) as f64, reason));
+        }
+        if let Some((score, reason)) = change {
+            evidence.entry(index).or_default().add(score, reason);
+        }
+    }
+
+    let mut candidates: Vec<Candidate> = evidence
+        .into_iter()
+        .map(|(index, mut evidence)| {
+            let file = &files[index];
+            if ast::is_test_path(&file.relative) {
+                evidence.score *= 0.7;
+            }
+            evidence
+                .reasons
+                .sort_by(|a, b| b.0.total_cmp(&a.0).then_with(|| a.1.cmp(&b.1)));
+            let mut symbols: Vec<SymbolCandidate> = evidence
+                .symbols
+                .into_iter()
+                .map(|(d, score)| {
+                    let definition = &file.definitions[d];
+                    SymbolCandidate {
+                        name: definition.name.clone(),
+                        kind: definition.kind.clone(),
+                        container: definition.container.clone(),
+                        line: definition.line,
+                        end_line: definition.end_line,
+                        score: round(score),
+                    }
+                })
+                .collect();
+            symbols.sort_by(|a, b| b.score.total_cmp(&a.score).then(a.line.cmp(&b.line)));
+            symbols.truncate(MAX_SYMBOLS);
+            Candidate {
+                path: file.relative.clone(),
+                score: round(evidence.score),
+                reasons: evidence.reasons.into_iter().map(|(_, r)| r).collect(),
+                symbols,
+            }
+        })
+        .collect();
+    candidates.sort_by(|a, b| b.score.total_cmp(&a.score).then(a.path.cmp(&b.path)));
+    candidates
+}
+
+fn round(score: f64) -> f64 {
+    (score * 100.0).round() / 100.0
+}
+
+/// Tool ranking the files and functions most likely related to a bug
+pub struct LocalizeTool {
+    name: String,
+}
+
+impl LocalizeTool {
+    pub fn new() -> Self {
+        Self {
+            name: "localize".to_string(),
+        }
+    }
+
+    fn limit(args: &ToolArgs) -> Result<usize, ToolError> {
+        match args.get_named_arg("limit") {
+            None => Ok(DEFAULT_LIMIT),
+            Some(limit) => limit
+                .parse::<usize>()
+                .ok()
+                .filter(|&n| n > 0)
+                .map(|n| n.min(MAX_LIMIT))
+                .ok_or_else(|| ToolError::InvalidArgs {
+                    message: format!("Invalid limit '{}': expected a positive number", limit),
+                }),
+        }
+    }
+
+    fn render(candidate: &Candidate, rank: usize) -> String {
+        let mut text = format!(
+            "{}. {} (score {})\n     {}",
+            rank,
+            candidate.path.display(),
+            candidate.score,
+            candidate.reasons.join("; ")
+        );
+        for symbol in &candidate.symbols {
+            let name = match &symbol.container {
+                Some(container) => format!("{}::{}", container, symbol.name),
+                None => symbol.name.clone(),
+            };
+            let _ = write!(
+                text,
+                "\n     - {} {} (lines {}-{})",
+                symbol.kind, name, symbol.line, symbol.end_line
+            );
+        }
+        text
+    }
+}
+
+impl Default for LocalizeTool {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl Tool for LocalizeTool {
+    fn name(&self) -> &str {
+        &self.name
+    }
+
+    fn description(&self) -> &str {
+        "Rank the files and functions most likely related to a bug from a description, error message or stack trace, combining stack frames, matching definitions, paths and mentions, and recent git changes. Use it to pick where to look before sweeping with search_dir"
+    }
+
+    fn signature(&self) -> &str {
+        "localize <query_or_trace> [--limit=N]"
+    }
+
+    fn category(&self) -> ToolCategory {
+        ToolCategory::Search
+    }
+
+    fn danger_level(&self) -> DangerLevel {
+        DangerLevel::ReadOnly
+    }
+
+    fn validate_args(&self, args: &ToolArgs) -> Result<(), ToolError> {
+        let query = args
+            .get_named_arg("query_or_trace")
+            .or_else(|| args.get_arg(0));
+        if query.is_none_or(|query| query.trim().is_empty()) {
+            return Err(ToolError::InvalidArgs {
+                message: "Missing required argument: query_or_trace".to_string(),
+            });
+        }
+        Self::limit(args).map(|_| ())
+    }
+
+    fn execute(&mut self, args: &ToolArgs, state: &Arc<Mutex<ToolState>>) -> Result<ToolResult> {
+        let Some(query) = args
+            .get_named_arg("query_or_trace")
+            .or_else(|| args.get_arg(0))
+        else {
+            return Ok(ToolResult::error(
+                "Missing required argument: query_or_trace",
+            ));
+        };
+        let limit = Self::limit(args).map_err(|e| anyhow::anyhow!("{}", e))?;
+        let (root, filter) = {
+            let mut state_guard = state
+                .lock()
+                .map_err(|e| anyhow::anyhow!("Failed to lock state: {}", e))?;
+            (
+                state_guard.working_directory.clone(),
+                state_guard.search_filter(),
+            )
+        };
+
+        let candidates = localize(&root, filter, query);
+        let terms: Vec<String> = query_terms(query).into_iter().map(|t| t.text).collect();
+        if candidates.is_empty() {
+            return Ok(ToolResult::error_with_data(
+                format!(
+                    "No candidates found in {}. Searched for: {}",
+                    root.display(),
+                    if terms.is_empty() {
+                        "(no usable identifiers in the query)".to_string()
+                    } else {
+                        terms.join(", ")
+                    }
+                ),
+                serde_json::json!({ "terms": terms }),
+            ));
+        }
+
+        let shown = &candidates[..candidates.len().min(limit)];
+        let mut message = format!(
+            "Top {} of {} candidate file(s), most likely first:",
+            shown.len(),
+            candidates.len()
+        );
+        for (rank, candidate) in shown.iter().enumerate() {
+            message.push('\n');
+            message.push_str(&Self::render(candidate, rank + 1));
+        }
+        Ok(ToolResult::success_with_data(
+            message,
+            serde_json::json!({
+                "terms": terms,
+                "total": candidates.len(),
+                "candidates": shown,
+            }),
+        ))
+    }
+
+    fn get_parameters_schema(&self) -> serde_json::Value {
+        serde_json::json!({
+            "type": "object",
+            "properties": {
+                "query_or_trace": {
+                    "type": "string",
+                    "description": "Bug description, error message or stack trace"
+                },
+                "limit": {
+                    "type": "integer",
+                    "description": "Candidates to list (default 10, at most 50)"
+                }
+            },
+            "required": ["query_or_trace"]
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::fs;
+    use tempfile::TempDir;
+
+    #[test]
+    fn test_query_terms() {
+        let terms = query_terms("Crash in parse_config when the timeout is 0; parseConfig fails");
+        let summary: Vec<(&str, f64)> = terms.iter().map(|t| (t.text.as_str(), t.weight)).collect();
+        assert_eq!(
+            summary,
+            vec![
+                ("parse_config", 2.0),
+                ("parseconfig", 2.0),
+                ("timeout", 1.0)
+            ]
+        );
+    }
+
+    #[test]
+    fn test_localize_ranks_trace_definitions_and_changes() {
+        let temp_dir = TempDir::new().unwrap();
+        let root = temp_dir.path();
+        fs::create_dir_all(root.join("app")).unwrap();
+        fs::write(
+            root.join("app/config.py"),
+            "def load(path):\n    return parse_timeout(path)\n\n\ndef parse_timeout(value):\n    return int(value)\n",
+        )
+        .unwrap();
+        fs::write(
+            root.join("app/server.py"),
+            "from app.config import load\n\n\ndef start():\n    load('x')\n",
+        )
+        .unwrap();
+        fs::write(root.join("app/other.py"), "def unrelated():\n    pass\n").unwrap();
+        git(root, &["init", "-q", "-b", "main"]).unwrap();
+        git(root, &["config", "user.name", "Test"]).unwrap();
+        git(root, &["config", "user.email", "test@example.com"]).unwrap();
+        git(root, &["add", "."]).unwrap();
+        git(root, &["commit", "-q", "-m", "Initial commit"]).unwrap();
+        fs::write(
+            root.join("app/other.py"),
+            "def unrelated():\n    return 1\n",
+        )
+        .unwrap();
+
+        let candidates = localize(
+            root,
+            ConfigurableFilter::new(None),
+            "ValueError in parse_timeout when the config has a unit",
+        );
+        assert_eq!(candidates[0].path, PathBuf::from("app/config.py"));
+        assert_eq!(candidates[0].symbols[0].name, "parse_timeout");
+        assert!(candidates[0]
+            .reasons
+            .contains(&"defines `parse_timeout`".to_string()));
+        let other = candidates
+            .iter()
+            .find(|c| c.path == Path::new("app/other.py"))
+            .unwrap();
+        assert_eq!(other.reasons, vec!["uncommitted changes".to_string()]);
+
+        let trace = "Traceback (most recent call last):\n  File \"/ci/app/server.py\", line 5, in start\n    load('x')\n  File \"/ci/app/config.py\", line 6, in parse_timeout\n    return int(value)\nValueError: invalid literal for int() with base 10: 'x'\n";
+        let mut tool_state = ToolState::new();
+        tool_state.working_directory = root.to_path_buf();
+        let state = Arc::new(Mutex::new(tool_state));
+        let result = LocalizeTool::new()
+            .execute(&ToolArgs::from_args(&[trace]), &state)
+            .unwrap();
+        assert!(result.success, "{}", result.message);
+        let candidates = &result.data.as_ref().unwrap()["candidates"];
+        assert_eq!(candidates[0]["path"], "app/config.py");
+        assert_eq!(candidates[0]["reasons"][0], "stack frame #1 at line 6");
+        assert_eq!(candidates[1]["path"], "app/server.py");
+        assert_eq!(candidates[1]["symbols"][0]["name"], "start");
+    }
+}
@@ -0,0 +1,748 @@
+//! Log file summaries
+//!
+//! `analyze_log` turns a large log into its distinct messages. Lines are first joined
+//! into events, so a Python traceback, a Java or JavaScript stack, a Rust panic or a Go
+//! goroutine dump is one event with its headline rather than dozens of frame lines.
+//! Each event is reduced to a signature by dropping its leading timestamp and masking
+//! what varies between occurrences (numbers, UUIDs, hex ids, IP addresses, quoted
+//! strings), and events with the same signature are counted together. For stack traces
+//! the signature also names the frame the error was raised in, so one exception thrown
+//! from two places is two signatures.
+
+use crate::core::{DangerLevel, Tool, ToolArgs, ToolError, ToolResult};
+use crate::execution::output::clean_output;
+use crate::profiling;
+use crate::state::ToolState;
+use crate::utils::suggest::path_not_found;
+use anyhow::Result;
+use regex::Regex;
+use serde::Serialize;
+use std::collections::{BTreeMap, HashMap};
+use std::fs::File;
+use std::io::{Read, Seek, SeekFrom};
+use std::path::{Path, PathBuf};
+use std::sync::{Arc, Mutex, OnceLock};
+
+/// Signatures listed per section when no `--top` is given
+const DEFAULT_TOP: usize = 10;
+/// Bytes read from the end of larger logs
+const MAX_LOG_BYTES: u64 = 64 * 1024 * 1024;
+/// Lines kept from a stack trace sample
+const MAX_SAMPLE_LINES: usize = 12;
+/// Longest line shown in a sample
+const MAX_LINE_CHARS: usize = 300;
+
+fn regex(cell: &'static OnceLock<Regex>, pattern: &str) -> &'static Regex {
+    cell.get_or_init(|| Regex::new(pattern).expect("valid log regex"))
+}
+
+/// Severity of a log event
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash, Serialize)]
+#[serde(rename_all = "snake_case")]
+pub enum LogLevel {
+    Unknown,
+    Debug,
+    Info,
+    Warning,
+    Error,
+    Fatal,
+}
+
+impl LogLevel {
+    pub fn as_str(self) -> &'static str {
+        match self {
+            Self::Unknown => "unknown",
+            Self::Debug => "debug",
+            Self::Info => "info",
+            Self::Warning => "warning",
+            Self::Error => "error",
+            Self::Fatal => "fatal",
+        }
+    }
+
+    /// The first level keyword in `line`, e.g. `ERROR`, `warn` or `CRITICAL`
+    pub fn detect(line: &str) -> Option<Self> {
+        static LEVEL: OnceLock<Regex> = OnceLock::new();
+        let keyword = regex(
+            &LEVEL,
+            r"(?i)\b(fatal|panic|critical|crit|emerg|alert|severe|error|err|warning|warn|info|notice|debug|trace)\b",
+        )
+        .find(line)?
+        .as_str()
+        .to_ascii_lowercase();
+        Some(match keyword.as_str() {
+            "fatal" | "panic" | "critical" | "crit" | "emerg" | "alert" => Self::Fatal,
+            "severe" | "error" | "err" => Self::Error,
+            "warning" | "warn" => Self::Warning,
+            "info" | "notice" => Self::Info,
+            _ => Self::Debug,
+        })
+    }
+}
+
+/// A log entry: a headline and the lines that continue it, such as stack frames
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct LogEvent {
+    /// 1-based line number of the headline
+    pub line: usize,
+    pub lines: Vec<String>,
+}
+
+impl LogEvent {
+    fn has_traceback(&self) -> bool {
+        self.lines
+            .iter()
+            .any(|line| line.starts_with("Traceback (most recent call last)"))
+    }
+
+    /// Whether the event carries a stack trace or panic report
+    pub fn is_trace(&self) -> bool {
+        static FRAME: OnceLock<Regex> = OnceLock::new();
+        let frame = regex(
+            &FRAME,
+            r#"^(\s+at\s|\s+File "|\s*\d+:\s|goroutine \d+ \[|Traceback \(most recent call last\))"#,
+        );
+        self.lines[0].contains("panicked at")
+            || (self.lines.len() > 1 && self.lines[1..].iter().any(|line| frame.is_match(line)))
+    }
+
+    /// The line naming the error: a Python traceback's final exception line, a Rust
+    /// panic's location with its message, or else the headline
+    fn title(&self) -> String {
+        let headline = strip_timestamp(&self.lines[0]);
+        if self.has_traceback() {
+            if let Some(exception) = self.lines.iter().rev().find(|line| {
+                !line.starts_with(char::is_whitespace)
+                    && !line.starts_with("Traceback")
+                    && !line.starts_with("During handling")
+                    && !line.starts_with("The above exception")
+            }) {
+                return exception.trim().to_string();
+            }
+        }
+        if headline.contains("panicked at") && headline.ends_with(':') && self.lines.len() > 1 {
+            return format!("{} {}", headline, self.lines[1].trim());
+        }
+        headline.to_string()
+    }
+
+    /// The frame the error was raised in
+    fn top_frame(&self) -> Option<&str> {
+        if self.has_traceback() {
+            return self
+                .lines
+                .iter()
+                .rev()
+                .find(|line| line.trim_start().starts_with("File \""))
+                .map(|line| line.trim());
+        }
+        if let Some(i) = self
+            .lines
+            .iter()
+            .position(|line| line.starts_with("goroutine "))
+        {
+            return self.lines.get(i + 1).map(|line| line.trim());
+        }
+        self.lines[1..]
+            .iter()
+            .find(|line| line.trim_start().starts_with("at "))
+            .map(|line| line.trim())
+    }
+
+    /// What occurrences of the same problem have in common
+    pub fn signature(&self) -> String {
+        let title = mask(&self.title());
+        match self.top_frame().filter(|_| self.is_trace()) {
+            Some(frame) => format!("{} @ {}", title, mask_frame(frame)),
+            None => title,
+        }
+    }
+
+    pub fn level(&self) -> LogLevel {
+        match LogLevel::detect(&self.lines[0]) {
+            Some(level) => level,
+            None if self.is_trace() => LogLevel::Error,
+            None => LogLevel::Unknown,
+        }
+    }
+}
+
+/// Split `text` into events, joining stack frames and other continuation lines onto
+/// the line they belong to
+pub fn split_events(text: &str) -> Vec<LogEvent> {
+    static CONTINUATION: OnceLock<Regex> = OnceLock::new();
+    let continuation = regex(
+        &CONTINUATION,
+        r"^(Caused by:|Suppressed:|During handling of the above exception|The above exception was the direct cause|Traceback \(most recent call last\)|stack backtrace:|note: run with|goroutine \d+ \[|\.\.\. \d+ more)",
+    );
+    let mut events: Vec<LogEvent> = Vec::new();
+    // Inside a Go goroutine dump, where function lines are not indented
+    let mut in_goroutine = false;
+    for (i, line) in text.lines().enumerate() {
+        if line.trim().is_empty() {
+            in_goroutine = false;
+            continue;
+        }
+        let joins = events.last().is_some_and(|event| {
+            let previous = event.lines.last().map_or("", String::as_str);
+            line.starts_with(char::is_whitespace)
+                || continuation.is_match(line)
+                || in_goroutine
+                // A Python traceback ends with the unindented exception line
+                || (event.has_traceback() && previous.starts_with(char::is_whitespace))
+                // A Rust panic's message follows `panicked at <location>:`
+                || (event.lines.len() == 1
+                    && previous.contains("panicked at")
+                    && previous.ends_with(':'))
+        });
+        if line.starts_with("goroutine ") {
+            in_goroutine = true;
+        }
+        match events.last_mut() {
+            Some(event) if joins => event.lines.push(line.to_string()),
+            _ => events.push(LogEvent {
+                line: i + 1,
+                lines: vec![line.to_string()],
+            }),
+        }
+    }
+    events
+}
+
+/// `line` without a leading timestamp such as `2024-05-01T12:00:00.123Z`,
+/// `[12:00:00]`, `May  1 12:00:00` or a Unix time
+pub fn strip_timestamp(line: &str) -> &str {
+    match leading_timestamp(line) {
+        Some(timestamp) => line[timestamp.len()..].trim_start(),
+        None => line,
+    }
+}
+
+fn leading_timestamp(line: &str) -> Option<&str> {
+    static TIMESTAMP: OnceLock<Regex> = OnceLock::new();
+    regex(
+        &TIMESTAMP,
+        r"^\[?(\d{4}-\d{2}-\d{2}[T ]\d{2}:\d{2}:\d{2}(?:[.,]\d+)?(?:Z|\s?[+-]\d{2}:?\d{2})?|\d{4}/\d{2}/\d{2} \d{2}:\d{2}:\d{2}(?:\.\d+)?|[A-Z][a-z]{2} +\d{1,2} \d{2}:\d{2}:\d{2}|\d{2}:\d{2}:\d{2}(?:[.,]\d+)?|\d{10}(?:\.\d+)?)\]?",
+    )
+    .find(line)
+    .map(|m| m.as_str())
+}
+
+/// Replace the parts of a message that vary between occurrences with placeholders
+pub fn mask(text: &str) -> String {
+    static PATTERNS: OnceLock<Vec<(Regex, &'static str)>> = OnceLock::new();
+    let patterns = PATTERNS.get_or_init(|| {
+        [
+            (r#""[^"]*"|(^|[^\w])'[^']*'"#, "$1<str>"),
+            (
+                r"\b[0-9a-fA-F]{8}-[0-9a-fA-F]{4}-[0-9a-fA-F]{4}-[0-9a-fA-F]{4}-[0-9a-fA-F]{12}\b",
+                "<uuid>",
+            ),
+            (
+                r"\d{4}-\d{2}-\d{2}[T ]\d{2}:\d{2}:\d{2}(?:[.,]\d+)?(?:Z|[+-]\d{2}:?\d{2})?",
+                "<time>",
+            ),
+            (r"\b\d{1,3}(?:\.\d{1,3}){3}(?::\d+)?\b", "<ip>"),
+            (r"\b0x[0-9a-fA-F]+\b", "<hex>"),
+        ]
+        .into_iter()
+        .map(|(pattern, placeholder)| {
+            (
+                Regex::new(pattern).expect("valid log mask regex"),
+                placeholder,
+            )
+        })
+        .collect()
+    });
+    static HEX: OnceLock<Regex> = OnceLock::new();
+    static NUMBER: OnceLock<Regex> = OnceLock::new();
+    let mut masked = text.trim().to_string();
+    for (pattern, placeholder) in patterns {
+        masked = pattern.replace_all(&masked, *placeholder).into_owned();
+    }
+    // Ids such as commit hashes, but not long plain numbers or words
+    masked = regex(&HEX, r"\b[0-9a-fA-F]{7,}\b")
+        .replace_all(&masked, |caps: &regex::Captures| {
+            let id = &caps[0];
+            if id.bytes().all(|b| b.is_ascii_digit()) || id.bytes().all(|b| b.is_ascii_alphabetic())
+            {
+                id.to_string()
+            } else {
+                "<hex>".to_string()
+            }
+        })
+        .into_owned();
+    // Numbers, keeping a unit suffix such as `ms`
+    regex(&NUMBER, r"\b\d+(?:\.\d+)?([a-zA-Z]{0,3})\b")
+        .replace_all(&masked, "<n>$1")
+        .into_owned()
+}
+
+/// A frame with only its addresses and column masked, keeping file and line
+fn mask_frame(frame: &str) -> String {
+    static ADDRESS: OnceLock<Regex> = OnceLock::new();
+    regex(&ADDRESS, r"\+0x[0-9a-fA-F]+|\b0x[0-9a-fA-F]+\b")
+        .replace_all(frame, "<hex>")
+        .into_owned()
+}
+
+fn truncate_line(line: &str) -> String {
+    if line.chars().count() <= MAX_LINE_CHARS {
+        return line.to_string();
+    }
+    let kept: String = line.chars().take(MAX_LINE_CHARS - 3).collect();
+    format!("{}...", kept)
+}
+
+/// Events sharing a signature
+#[derive(Debug, Clone, Serialize)]
+pub struct LogCluster {
+    pub signature: String,
+    pub level: LogLevel,
+    pub count: usize,
+    pub trace: bool,
+    pub first_line: usize,
+    pub last_line: usize,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub first_seen: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub last_seen: Option<String>,
+    /// Distinct occurrences, whole events for stack traces
+    pub samples: Vec<String>,
+}
+
+/// Summary of a log
+#[derive(Debug, Clone, Serialize)]
+pub struct LogSummary {
+    pub lines: usize,
+    pub events: usize,
+    pub levels: BTreeMap<LogLevel, usize>,
+    /// Clusters by descending count
+    pub clusters: Vec<LogCluster>,
+}
+
+/// Cluster the events of `text`, keeping up to `samples` distinct samples per cluster
+pub fn analyze(text: &str, samples: usize) -> LogSummary {
+    let events = split_events(text);
+    let mut levels = BTreeMap::new();
+    let mut clusters: Vec<LogCluster> = Vec::new();
+    let mut index: HashMap<String, usize> = HashMap::new();
+    for event in &events {
+        let level = event.level();
+        *levels.entry(level).or_insert(0) += 1;
+        let timestamp = leading_timestamp(&event.lines[0])
+            .map(|t| t.trim_matches(|c| c == '[' || c == ']').to_string());
+        let trace = event.is_trace();
+        let sample = if trace {
+            let mut lines: Vec<String> = event
+                .lines
+                .iter()
+                .take(MAX_SAMPLE_LINES)
+                .map(|line| truncate_line(line))
+                .collect();
+            if event.lines.len() > MAX_SAMPLE_LINES {
+                lines.push(format!(
+                    "... {} more line(s)",
+                    event.lines.len() - MAX_SAMPLE_LINES
+                ));
+            }
+            lines.join("\n")
+        } else {
+            truncate_line(&event.lines[0])
+        };
+
+        let signature = event.signature();
+        let i = *index.entry(signature.clone()).or_insert_with(|| {
+            clusters.push(LogCluster {
+                signature,
+                level,
+                count: 0,
+                trace,
+                first_line: event.line,
+                last_line: event.line,
+                first_seen: timestamp.clone(),
+                last_seen: None,
+                samples: Vec::new(),
+            });
+            clusters.len() - 1
+        });
+        let cluster = &mut clusters[i];
+        cluster.count += 1;
+        cluster.level = cluster.level.max(level);
+        cluster.last_line = event.line;
+        if timestamp.is_some() {
+            cluster.last_seen = timestamp;
+        }
+        let distinct = sample != cluster.samples.last().map_or("", String::as_str)
+            && !cluster.samples.contains(&sample);
+        if cluster.samples.len() < samples && distinct {
+            cluster.samples.push(sample);
+        }
+    }
+    clusters.sort_by(|a, b| b.count.cmp(&a.count).then(a.first_line.cmp(&b.first_line)));
+    LogSummary {
+        lines: text.lines().count(),
+        events: events.len(),
+        levels,
+        clusters,
+    }
+}
+
+/// Read `path`, keeping only the last `MAX_LOG_BYTES` of larger files
+///
+/// Returns the text and the number of bytes skipped from the start.
+fn read_log(path: &Path) -> std::io::Result<(String, u64)> {
+    let mut file = File::open(path)?;
+    let size = file.metadata()?.len();
+    let skipped = size.saturating_sub(MAX_LOG_BYTES);
+    file.seek(SeekFrom::Start(skipped))?;
+    let mut bytes = Vec::new();
+    file.read_to_end(&mut bytes)?;
+    profiling::record_read(bytes.len());
+    let mut text = clean_output(&bytes, false);
+    if skipped > 0 {
+        // Drop the partial first line
+        text = text
+            .split_once('\n')
+            .map_or(String::new(), |(_, rest)| rest.to_string());
+    }
+    Ok((text, skipped))
+}
+
+/// Tool summarizing a log file into counted error signatures with samples
+pub struct AnalyzeLogTool {
+    name: String,
+}
+
+impl AnalyzeLogTool {
+    pub fn new() -> Self {
+        Self {
+            name: "analyze_log".to_string(),
+        }
+    }
+
+    fn number(args: &ToolArgs, name: &str, default: usize, max: usize) -> Result<usize, ToolError> {
+        match args.get_named_arg(name) {
+            None => Ok(default),
+            Some(value) => match value.parse::<usize>() {
+                Ok(n) if (1..=max).contains(&n) => Ok(n),
+                _ => Err(ToolError::InvalidArgs {
+                    message: format!(
+                        "Invalid {} '{}': expected a number from 1 to {}",
+                        name, value, max
+                    ),
+                }),
+            },
+        }
+    }
+
+    fn render_cluster(rank: usize, cluster: &LogCluster) -> String {
+        let lines = if cluster.count == 1 {
+            format!("line {}", cluster.first_line)
+        } else {
+            format!("lines {}-{}", cluster.first_line, cluster.last_line)
+        };
+        let seen = match (&cluster.first_seen, &cluster.last_seen) {
+            (Some(first), Some(last)) if cluster.count > 1 => format!(", {} to {}", first, last),
+            (Some(first), _) => format!(", {}", first),
+            _ => String::new(),
+        };
+        let mut text = format!(
+            "{}. {}x [{}] {} ({}{})",
+            rank,
+            cluster.count,
+            cluster.level.as_str(),
+            truncate_line(&cluster.signature),
+            lines,
+            seen
+        );
+        for sample in &cluster.samples {
+            for line in sample.lines() {
+                text.push_str("\n     ");
+                text.push_str(line);
+            }
+        }
+        text
+    }
+}
+
+impl Default for AnalyzeLogTool {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl Tool for AnalyzeLogTool {
+    fn name(&self) -> &str {
+        &self.name
+    }
+
+    fn description(&self) -> &str {
+        "Summarize a log file: counts by level, the most frequent error and warning signatures (similar lines clustered, stack traces grouped by exception and frame) with first/last occurrence and representative samples"
+    }
+
+    fn signature(&self) -> &str {
+        "analyze_log <path> [--top=N] [--samples=N]"
+    }
+
+    fn danger_level(&self) -> DangerLevel {
+        DangerLevel::ReadOnly
+    }
+
+    fn validate_args(&self, args: &ToolArgs) -> Result<(), ToolError> {
+        if args
+            .get_named_arg("path")
+            .or_else(|| args.get_arg(0))
+            .is_none()
+        {
+            return Err(ToolError::InvalidArgs {
+                message: "Missing required argument: path".to_string(),
+            });
+        }
+        Self::number(args, "top", DEFAULT_TOP, 100)?;
+        Self::number(args, "samples", 1, 5)?;
+        Ok(())
+    }
+
+    fn execute(&mut self, args: &ToolArgs, state: &Arc<Mutex<ToolState>>) -> Result<ToolResult> {
+        let Some(file) = args.get_named_arg("path").or_else(|| args.get_arg(0)) else {
+            return Ok(ToolResult::error("Missing required argument: path"));
+        };
+        let top =
+            Self::number(args, "top", DEFAULT_TOP, 100).map_err(|e| anyhow::anyhow!("{}", e))?;
+        let samples = Self::number(args, "samples", 1, 5).map_err(|e| anyhow::anyhow!("{}", e))?;
+        let path: PathBuf = state
+            .lock()
+            .map_err(|e| anyhow::anyhow!("Failed to lock state: {}", e))?
+            .working_directory
+            .join(file);
+        if !path.is_file() {
+            return Ok(path_not_found(
+                format!("File not found: {}", path.display()),
+                &path,
+            ));
+        }
+        let (text, skipped) = read_log(&path)?;
+        let summary = analyze(&text, samples);
+
+        let levels: Vec<String> = summary
+            .levels
+            .iter()
+            .rev()
+            .map(|(level, count)| format!("{} {}", count, level.as_str()))
+            .collect();
+        let mut message = format!(
+            "{}: {} line(s), {} event(s) ({})",
+            file,
+            summary.lines,
+            summary.events,
+            levels.join(", ")
+        );
+        if skipped > 0 {
+            message.push_str(&format!(
+                "\nOnly the last {} MiB were analyzed ({} bytes skipped)",
+                MAX_LOG_BYTES / (1024 * 1024),
+                skipped
+            ));
+        }
+        let sections = [
+            ("Top error signatures", LogLevel::Error..=LogLevel::Fatal),
+            (
+                "Top warning signatures",
+                LogLevel::Warning..=LogLevel::Warning,
+            ),
+        ];
+        for (title, range) in sections {
+            let clusters: Vec<&LogCluster> = summary
+                .clusters
+                .iter()
+                .filter(|cluster| range.contains(&cluster.level))
+                .collect();
+            if clusters.is_empty() {
+                continue;
+            }
+            message.push_str(&format!(
+                "\n\n{} ({} distinct{}):",
+                title,
+                clusters.len(),
+                if clusters.len() > top {
+                    format!(", showing {}", top)
+                } else {
+                    String::new()
+                }
+            ));
+            for (i, cluster) in clusters.iter().take(top).enumerate() {
+                message.push('\n');
+                message.push_str(&Self::render_cluster(i + 1, cluster));
+            }
+        }
+        let traces = summary.clusters.iter().filter(|c| c.trace).count();
+        if traces > 0 {
+            message.push_str(&format!(
+                "\n\n{} distinct stack trace(s) in {} event(s)",
+                traces,
+                summary
+                    .clusters
+                    .iter()
+                    .filter(|c| c.trace)
+                    .map(|c| c.count)
+                    .sum::<usize>()
+            ));
+        }
+        let frequent: Vec<String> = summary
+            .clusters
+            .iter()
+            .filter(|cluster| cluster.level < LogLevel::Warning)
+            .take(5)
+            .map(|cluster| {
+                format!(
+                    "  {}x [{}] {}",
+                    cluster.count,
+                    cluster.level.as_str(),
+                    truncate_line(&cluster.signature)
+                )
+            })
+            .collect();
+        if !frequent.is_empty() {
+            message.push_str("\n\nMost frequent other lines:\n");
+            message.push_str(&frequent.join("\n"));
+        }
+
+        let reported: Vec<&LogCluster> = summary
+            .clusters
+            .iter()
+            .filter(|cluster| cluster.level >= LogLevel::Warning)
+            .take(top * 2)
+            .collect();
+        Ok(ToolResult::success_with_data(
+            message,
+            serde_json::json!({
+                "path": path.display().to_string(),
+                "lines": summary.lines,
+                "events": summary.events,
+                "skipped_bytes": skipped,
+                "levels": summary
+                    .levels
+                    .iter()
+                    .map(|(level, count)| (level.as_str().to_string(), *count))
+                    .collect::<BTreeMap<_, _>>(),
+                "distinct_signatures": summary.clusters.len(),
+                "clusters": reported,
+            }),
+        ))
+    }
+
+    fn get_parameters_schema(&self) -> serde_json::Value {
+        serde_json::json!({
+            "type": "object",
+            "properties": {
+                "path": {
+                    "type": "string",
+                    "description": "Log file to analyze (only the last 64 MiB of larger files are read)"
+                },
+                "top": {
+                    "type": "integer",
+                    "description": "Signatures listed per level (default 10, at most 100)"
+                },
+                "samples": {
+                    "type": "integer",
+                    "description": "Distinct samples shown per signature (default 1, at most 5)"
+                }
+            },
+            "required": ["path"]
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use tempfile::TempDir;
+
+    const LOG: &str = r#"2024-05-01T10:00:00Z INFO request id=1 took 12ms
+2024-05-01T10:00:01Z ERROR db: connection to 10.0.0.5:5432 refused (attempt 1)
+2024-05-01T10:00:02Z INFO request id=2 took 30ms
+2024-05-01T10:00:03Z ERROR db: connection to 10.0.0.6:5432 refused (attempt 2)
+2024-05-01T10:00:04Z WARN slow query "SELECT 1" took 2.5s
+2024-05-01T10:00:05Z ERROR unhandled exception in handler
+Traceback (most recent call last):
+  File "app/views.py", line 42, in index
+    return render(user)
+  File "app/render.py", line 7, in render
+    raise ValueError("bad user 17")
+ValueError: bad user 17
+2024-05-01T10:00:06Z ERROR unhandled exception in handler
+Traceback (most recent call last):
+  File "app/views.py", line 42, in index
+    return render(user)
+  File "app/render.py", line 7, in render
+    raise ValueError("bad user 99")
+ValueError: bad user 99
+Exception in thread "main" java.lang.IllegalStateException: closed
+	at com.example.Pool.get(Pool.java:88)
+	at com.example.Main.main(Main.java:12)
+thread 'main' panicked at src/main.rs:4:5:
+index out of bounds: the len is 3 but the index is 7
+note: run with `RUST_BACKTRACE=1` environment variable to display a backtrace
+2024-05-01T10:00:09Z INFO request id=3 took 8ms
+"#;
+
+    #[test]
+    fn test_split_events_joins_traces() {
+        let events = split_events(LOG);
+        assert_eq!(events.len(), 10);
+        assert_eq!(events[5].lines.len(), 7);
+        assert!(events[5].is_trace());
+        assert_eq!(
+            events[5].signature(),
+            r#"ValueError: bad user <n> @ File "app/render.py", line 7, in render"#
+        );
+        assert_eq!(events[7].lines.len(), 3);
+        assert_eq!(
+            events[8].signature(),
+            "thread <str> panicked at src/main.rs:<n>:<n>: index out of bounds: the len is <n> but the index is <n>"
+        );
+        assert_eq!(events[8].level(), LogLevel::Error);
+    }
+
+    #[test]
+    fn test_analyze_clusters_and_counts() {
+        let summary = analyze(LOG, 2);
+        assert_eq!(summary.levels[&LogLevel::Info], 3);
+        let db = summary
+            .clusters
+            .iter()
+            .find(|c| c.signature.starts_with("ERROR db"))
+            .unwrap();
+        assert_eq!(
+            db.signature,
+            "ERROR db: connection to <ip> refused (attempt <n>)"
+        );
+        assert_eq!((db.count, db.first_line, db.last_line), (2, 2, 4));
+        assert_eq!(db.first_seen.as_deref(), Some("2024-05-01T10:00:01Z"));
+        assert_eq!(db.samples.len(), 2);
+        let traceback = summary
+            .clusters
+            .iter()
+            .find(|c| c.signature.starts_with("ValueError"))
+            .unwrap();
+        assert_eq!(traceback.count, 2);
+        assert!(traceback.trace);
+        assert!(traceback.samples[0].contains("raise ValueError"));
+
+        let temp_dir = TempDir::new().unwrap();
+        std::fs::write(temp_dir.path().join("app.log"), LOG.repeat(50)).unwrap();
+        let mut tool_state = ToolState::new();
+        tool_state.working_directory = temp_dir.path().to_path_buf();
+        let state = Arc::new(Mutex::new(tool_state));
+        let result = AnalyzeLogTool::new()
+            .execute(&ToolArgs::from_args(&["app.log", "--top=3"]), &state)
+            .unwrap();
+        assert!(result.success, "{}", result.message);
+        assert!(result
+            .message
+            .contains("100x [error] ERROR db: connection to <ip> refused"));
+        assert!(result.message.contains("showing 3"));
+        assert!(result.message.len() < 4000, "{}", result.message);
+    }
+}
@@ -0,0 +1,349 @@
+//! Diagnostics tools
+//!
+//! Turns raw compiler and type-checker output into compact, file-grouped reports
+//! with code frames so agents can go straight from an error to the edit.
+
+use crate::core::{DangerLevel, Tool, ToolArgs, ToolError, ToolResult};
+use crate::execution::output::clean_output;
+use crate::file_navigation::read_range;
+use crate::profiling;
+use crate::state::{session_path, ToolState};
+use anyhow::Result;
+use std::collections::BTreeMap;
+use std::path::{Path, PathBuf};
+use std::process::Command;
+use std::sync::{Arc, Mutex};
+
+pub mod compiler;
+#[cfg(feature = "ast")]
+pub mod localize;
+pub mod log;
+pub mod trace;
+
+pub use compiler::{parse_diagnostics, Diagnostic, Severity};
+#[cfg(feature = "ast")]
+pub use localize::LocalizeTool;
+pub use log::AnalyzeLogTool;
+pub use trace::TraceToSourceTool;
+
+/// Default number of context lines shown around each diagnostic
+const DEFAULT_CONTEXT_LINES: usize = 2;
+
+/// Default cap on diagnostics reported per file
+const DEFAULT_MAX_PER_FILE: usize = 10;
+
+/// Tool that consolidates compiler diagnostics into a per-file report with code frames
+pub struct ExplainErrorsTool {
+    name: String,
+}
+
+impl ExplainErrorsTool {
+    pub fn new() -> Self {
+        Self {
+            name: "explain_errors".to_string(),
+        }
+    }
+
+    /// Render a code frame around `line`, marking the diagnostic line and column
+    fn code_frame(path: &Path, line: usize, column: usize, context: usize) -> Option<String> {
+        let lines = read_range(path, line.saturating_sub(context), line + context).ok()?;
+        if lines.is_empty() {
+            return None;
+        }
+        let width = lines.last().map(|(n, _)| n.to_string().len()).unwrap_or(1);
+        let mut frame = Vec::new();
+        for (number, text) in lines {
+            let marker = if number == line { ">" } else { " " };
+            frame.push(format!(
+                "  {} {:>width$} | {}",
+                marker,
+                number,
+                text,
+                width = width
+            ));
+            if number == line && column > 0 {
+                frame.push(format!(
+                    "    {:>width$} | {}^",
+                    "",
+                    " ".repeat(column - 1),
+                    width = width
+                ));
+            }
+        }
+        Some(frame.join("\n"))
+    }
+
+    /// Group diagnostics by file and render the consolidated report
+    pub fn render_report(
+        diagnostics: &[Diagnostic],
+        root: &Path,
+        context: usize,
+        max_per_file: usize,
+    ) -> String {
+        let mut by_file: BTreeMap<&str, Vec<&Diagnostic>> = BTreeMap::new();
+        for diagnostic in diagnostics {
+            by_file
+                .entry(&diagnostic.file)
+                .or_default()
+                .push(diagnostic);
+        }
+
+        let count = |severity: Severity| {
+            diagnostics
+                .iter()
+                .filter(|d| d.severity == severity)
+                .count()
+        };
+        let mut report = vec![format!(
+            "{} error(s), {} warning(s) in {} file(s)",
+            count(Severity::Error),
+            count(Severity::Warning),
+            by_file.len()
+        )];
+
+        // Files with the most errors first
+        let mut files: Vec<(&str, Vec<&Diagnostic>)> = by_file.into_iter().collect();
+        files.sort_by_key(|(file, diags)| {
+            let errors = diags
+                .iter()
+                .filter(|d| d.severity == Severity::Error)
+                .count();
+            (std::cmp::Reverse(errors), file.to_string())
+        });
+
+        for (file, mut diags) in files {
+            diags.sort_by_key(|d| (d.severity, d.line, d.column));
+            report.push(String::new());
+            report.push(format!("{} ({} diagnostic(s))", file, diags.len()));
+
+            let path = if Path::new(file).is_absolute() {
+                PathBuf::from(file)
+            } else {
+                root.join(file)
+            };
+            for diagnostic in diags.iter().take(max_per_file) {
+                let code = diagnostic
+                    .code
+                    .as_ref()
+                    .map(|c| format!("[{}]", c))
+                    .unwrap_or_default();
+                let label = diagnostic
+                    .label
+                    .as_ref()
+                    .map(|l| format!(" ({})", l))
+                    .unwrap_or_default();
+                report.push(format!(
+                    "  {}{} {}:{} {}{}",
+                    diagnostic.severity.as_str(),
+                    code,
+                    diagnostic.line,
+                    diagnostic.column,
+                    diagnostic.message,
+                    label
+                ));
+                if let Some(frame) =
+                    Self::code_frame(&path, diagnostic.line, diagnostic.column, context)
+                {
+                    report.push(frame);
+                }
+            }
+            if diags.len() > max_per_file {
+                report.push(format!(
+                    "  ... {} more diagnostic(s) in this file",
+                    diags.len() - max_per_file
+                ));
+            }
+        }
+
+        report.join("\n")
+    }
+}
+
+impl Default for ExplainErrorsTool {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl Tool for ExplainErrorsTool {
+    fn name(&self) -> &str {
+        &self.name
+    }
+
+    fn description(&self) -> &str {
+        "Group cargo/rustc JSON, tsc or pyright diagnostics by file with code frames into one compact error report"
+    }
+
+    fn signature(&self) -> &str {
+        "explain_errors [--diagnostics=<text>] [--file=<path>] [--command=<cmd>] [--root=<dir>] [--context=<n>]"
+    }
+
+    fn danger_level(&self) -> DangerLevel {
+        // The `command` argument runs a shell command to collect diagnostics
+        DangerLevel::ExecutesCode
+    }
+
+    fn validate_args(&self, args: &ToolArgs) -> Result<(), ToolError> {
+        let sources = ["diagnostics", "file", "command"]
+            .iter()
+            .filter(|key| args.get_named_arg(key).is_some())
+            .count();
+        if sources == 0 && args.get_arg(0).is_none() {
+            return Err(ToolError::InvalidArgs {
+                message: "explain_errors requires one of --diagnostics, --file or --command"
+                    .to_string(),
+            });
+        }
+        Ok(())
+    }
+
+    fn execute(&mut self, args: &ToolArgs, state: &Arc<Mutex<ToolState>>) -> Result<ToolResult> {
+        let root = match args.get_named_arg("root") {
+            Some(root) => session_path(state, root),
+            None => {
+                let state_guard = state
+                    .lock()
+                    .map_err(|e| anyhow::anyhow!("Failed to lock state: {}", e))?;
+                state_guard.working_directory.clone()
+            }
+        };
+        let context = args
+            .get_named_arg("context")
+            .and_then(|c| c.parse().ok())
+            .unwrap_or(DEFAULT_CONTEXT_LINES);
+        let max_per_file = args
+            .get_named_arg("max_per_file")
+            .and_then(|c| c.parse().ok())
+            .unwrap_or(DEFAULT_MAX_PER_FILE);
+
+        let input = if let Some(text) = args.get_named_arg("diagnostics") {
+            text.clone()
+        } else if let Some(file) = args.get_named_arg("file").or_else(|| args.get_arg(0)) {
+            let path = session_path(state, file);
+            if !path.exists() {
+                return Ok(crate::utils::suggest::path_not_found(
+                    format!("File not found: {}", file),
+                    &path,
+                ));
+            }
+            profiling::read_to_string(&path)?
+        } else if let Some(command) = args.get_named_arg("command") {
+            let output = Command::new("sh")
+                .arg("-c")
+                .arg(command)
+                .current_dir(&root)
+                .output()
+                .map_err(|e| anyhow::anyhow!("Failed to run '{}': {}", command, e))?;
+            format!(
+                "{}\n{}",
+                clean_output(&output.stdout, false),
+                clean_output(&output.stderr, false)
+            )
+        } else {
+            return Ok(ToolResult::error("No diagnostics source provided"));
+        };
+
+        let mut diagnostics = parse_diagnostics(&input);
+        let mut seen = std::collections::HashSet::new();
+        diagnostics.retain(|d| seen.insert(d.clone()));
+
+        if diagnostics.is_empty() {
+            return Ok(ToolResult::success_with_data(
+                "No diagnostics found",
+                serde_json::json!({"diagnostics": [], "errors": 0, "warnings": 0}),
+            ));
+        }
+
+        let report = Self::render_report(&diagnostics, &root, context, max_per_file);
+        let errors = diagnostics
+            .iter()
+            .filter(|d| d.severity == Severity::Error)
+            .count();
+        let warnings = diagnostics
+            .iter()
+            .filter(|d| d.severity == Severity::Warning)
+            .count();
+
+        Ok(ToolResult::success_with_data(
+            report,
+            serde_json::json!({
+                "diagnostics": diagnostics,
+                "errors": errors,
+                "warnings": warnings
+            }),
+        ))
+    }
+
+    fn get_parameters_schema(&self) -> serde_json::Value {
+        serde_json::json!({
+            "type": "object",
+            "properties": {
+                "diagnostics": {
+                    "type": "string",
+                    "description": "Raw diagnostics: cargo --message-format=json lines, tsc output or pyright --outputjson report"
+                },
+                "file": {
+                    "type": "string",
+                    "description": "Path to a file containing diagnostics"
+                },
+                "command": {
+                    "type": "string",
+                    "description": "Command to run whose output contains diagnostics, e.g. 'cargo check --message-format=json'"
+                },
+                "root": {
+                    "type": "string",
+                    "description": "Directory relative diagnostic paths are resolved against (defaults to the working directory)"
+                },
+                "context": {
+                    "type": "integer",
+                    "description": "Lines of context shown around each diagnostic (default 2)"
+                },
+                "max_per_file": {
+                    "type": "integer",
+                    "description": "Maximum diagnostics reported per file (default 10)"
+                }
+            },
+            "required": []
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::fs;
+    use tempfile::TempDir;
+
+    #[test]
+    fn test_explain_errors_groups_with_code_frames() {
+        let temp_dir = TempDir::new().unwrap();
+        fs::create_dir(temp_dir.path().join("src")).unwrap();
+        fs::write(
+            temp_dir.path().join("src/lib.rs"),
+            "fn f() -> u32 {\n    \"x\"\n}\n",
+        )
+        .unwrap();
+
+        let diagnostics = r#"{"reason":"compiler-message","message":{"level":"error","message":"mismatched types","code":{"code":"E0308"},"spans":[{"file_name":"src/lib.rs","line_start":2,"column_start":5,"is_primary":true,"label":"expected `u32`, found `&str`"}]}}"#;
+        let mut named = std::collections::HashMap::new();
+        named.insert("diagnostics".to_string(), format!("{0}\n{0}", diagnostics));
+        named.insert(
+            "root".to_string(),
+            temp_dir.path().to_string_lossy().to_string(),
+        );
+
+        let mut tool = ExplainErrorsTool::new();
+        let state = Arc::new(Mutex::new(ToolState::new()));
+        let result = tool
+            .execute(&ToolArgs::with_named_args(vec![], named), &state)
+            .unwrap();
+
+        assert!(result.success);
+        assert!(result
+            .message
+            .starts_with("1 error(s), 0 warning(s) in 1 file(s)"));
+        assert!(result.message.contains("error[E0308] 2:5 mismatched types"));
+        assert!(result.message.contains("> 2 |     \"x\""));
+        assert_eq!(result.data.unwrap()["errors"], 1);
+    }
+}
@@ -0,0 +1,603 @@
+//! Stack trace frames resolved to workspace files
+//!
+//! `trace_to_source` reads a pasted stack trace (a Rust panic and backtrace, a Python
+//! traceback, a Node.js, JVM or Go stack) and finds each frame's file in the working
+//! directory. Paths recorded on another machine, such as a CI runner's checkout, are
+//! matched by their longest suffix that exists under the root, and bare file names (JVM
+//! frames) by searching the workspace. Frames in the standard library or in installed
+//! dependencies are kept but marked as outside the workspace.
+
+use super::log::LogLevel;
+use crate::core::{DangerLevel, Tool, ToolArgs, ToolCategory, ToolError, ToolResult};
+use crate::file_navigation::{read_range, DEFAULT_WINDOW_SIZE};
+use crate::profiling;
+use crate::search::ConfigurableFilter;
+use crate::state::ToolState;
+use anyhow::Result;
+use regex::Regex;
+use serde::Serialize;
+use std::collections::HashMap;
+use std::fmt::Write as _;
+use std::path::{Component, Path, PathBuf};
+use std::sync::{Arc, Mutex, OnceLock};
+use walkdir::WalkDir;
+
+/// Frames listed in the message; the data carries all of them
+const MAX_LISTED_FRAMES: usize = 30;
+
+/// Path fragments of standard library and installed dependency sources
+const LIBRARY_MARKERS: &[&str] = &[
+    "/rustc/",
+    "/.cargo/registry/",
+    "/.cargo/git/",
+    "/.rustup/",
+    "site-packages",
+    "dist-packages",
+    "node_modules",
+    "/lib/python",
+    "node:",
+    "<frozen",
+    "/go/src/runtime/",
+    "/usr/local/go/",
+];
+
+fn regex(cell: &'static OnceLock<Regex>, pattern: &str) -> &'static Regex {
+    cell.get_or_init(|| Regex::new(pattern).expect("valid stack trace regex"))
+}
+
+/// Language a stack trace comes from
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "snake_case")]
+pub enum TraceKind {
+    Rust,
+    Python,
+    Node,
+    Jvm,
+    Go,
+}
+
+/// One frame of a stack trace
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+pub struct Frame {
+    pub kind: TraceKind,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub function: Option<String>,
+    /// The path as written in the trace
+    pub file: String,
+    pub line: usize,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub column: Option<usize>,
+    /// The workspace file it resolved to
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub resolved: Option<PathBuf>,
+    /// Whether the frame is in the project's own code
+    pub in_workspace: bool,
+    /// The source line, for resolved frames
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub source: Option<String>,
+}
+
+/// A parsed stack trace, innermost frame first
+#[derive(Debug, Clone, Serialize)]
+pub struct StackTrace {
+    /// The error message: the exception line, panic message or first line
+    pub error: Option<String>,
+    pub frames: Vec<Frame>,
+}
+
+fn frame(
+    kind: TraceKind,
+    function: Option<&str>,
+    file: &str,
+    line: &str,
+    column: Option<&str>,
+) -> Option<Frame> {
+    Some(Frame {
+        kind,
+        function: function
+            .map(str::trim)
+            .filter(|f| !f.is_empty())
+            .map(str::to_string),
+        file: file.trim_start_matches("file://").to_string(),
+        line: line.parse().ok()?,
+        column: column.and_then(|c| c.parse().ok()),
+        resolved: None,
+        in_workspace: false,
+        source: None,
+    })
+}
+
+/// Parse the frames of `text`, which may mix log lines and several traces
+///
+/// Python tracebacks list the innermost call last; their frames are reversed so that
+/// every trace starts at the frame that failed.
+pub fn parse_trace(text: &str) -> StackTrace {
+    static PYTHON: OnceLock<Regex> = OnceLock::new();
+    static RUST_PANIC: OnceLock<Regex> = OnceLock::new();
+    static RUST_AT: OnceLock<Regex> = OnceLock::new();
+    static RUST_SYMBOL: OnceLock<Regex> = OnceLock::new();
+    static JVM: OnceLock<Regex> = OnceLock::new();
+    static NODE: OnceLock<Regex> = OnceLock::new();
+    static GO: OnceLock<Regex> = OnceLock::new();
+    let python = regex(&PYTHON, r#"^\s*File "([^"]+)", line (\d+)(?:, in (.+))?"#);
+    let rust_panic = regex(
+        &RUST_PANIC,
+        r"panicked at (?:'.*', )?([^\s:']+\.rs):(\d+):(\d+)",
+    );
+    let rust_at = regex(&RUST_AT, r"^\s+at (\S+\.rs):(\d+)(?::(\d+))?\s*$");
+    let rust_symbol = regex(&RUST_SYMBOL, r"^\s*\d+:\s+(?:0x[0-9a-f]+ - )?(.+)$");
+    let jvm = regex(&JVM, r"^\s*at ([\w$.<>/]+)\(([^():]+):(\d+)\)");
+    let node = regex(&NODE, r"^\s*at (?:(.+?) \()?(\S+?):(\d+):(\d+)\)?\s*$");
+    let go = regex(&GO, r"^\s+(\S+\.go):(\d+)(?: \+0x[0-9a-f]+)?\s*$");
+
+    let lines: Vec<&str> = text.lines().collect();
+    let mut frames = Vec::new();
+    let mut python_frames = Vec::new();
+    let mut error = None;
+    for (i, line) in lines.iter().enumerate() {
+        let previous = i.checked_sub(1).map(|p| lines[p]);
+        if let Some(caps) = python.captures(line) {
+            python_frames.extend(frame(
+                TraceKind::Python,
+                caps.get(3).map(|m| m.as_str()),
+                &caps[1],
+                &caps[2],
+                None,
+            ));
+            continue;
+        }
+        // The unindented line ending a traceback names the exception, which says more
+        // than a log line announcing it
+        if !python_frames.is_empty() && !line.starts_with(char::is_whitespace) {
+            if !line.trim().is_empty() && frames.is_empty() {
+                error = Some(line.trim().to_string());
+            }
+            frames.extend(python_frames.drain(..).rev());
+        }
+        if let Some(caps) = rust_panic.captures(line) {
+            frames.extend(frame(
+                TraceKind::Rust,
+                None,
+                &caps[1],
+                &caps[2],
+                Some(&caps[3]),
+            ));
+            if error.is_none() {
+                let message = match line.trim_end().strip_suffix(':') {
+                    Some(_) => lines.get(i + 1).map(|next| next.trim()),
+                    None => line.split_once(" panicked at ").map(|(_, rest)| rest),
+                };
+                error = message.map(str::to_string);
+            }
+        } else if let Some(caps) = rust_at.captures(line) {
+            let function = previous
+                .and_then(|p| rust_symbol.captures(p))
+                .map(|c| c.get(1).map_or("", |m| m.as_str()).to_string());
+            frames.extend(frame(
+                TraceKind::Rust,
+                function.as_deref(),
+                &caps[1],
+                &caps[2],
+                caps.get(3).map(|m| m.as_str()),
+            ));
+        } else if let Some(caps) = jvm.captures(line) {
+            frames.extend(frame(
+                TraceKind::Jvm,
+                Some(&caps[1]),
+                &caps[2],
+                &caps[3],
+                None,
+            ));
+        } else if let Some(caps) = node.captures(line) {
+            frames.extend(frame(
+                TraceKind::Node,
+                caps.get(1).map(|m| m.as_str()),
+                &caps[2],
+                &caps[3],
+                Some(&caps[4]),
+            ));
+        } else if let Some(caps) = go.captures(line) {
+            let function = previous.map(|p| match p.rfind('(') {
+                Some(paren) => &p[..paren],
+                None => p,
+            });
+            frames.extend(frame(TraceKind::Go, function, &caps[1], &caps[2], None));
+        } else if error.is_none() && python_frames.is_empty() && frames.is_empty() {
+            let trimmed = line.trim();
+            let is_error = LogLevel::detect(trimmed).is_some_and(|level| level >= LogLevel::Error)
+                || trimmed.starts_with("panic: ")
+                || trimmed.contains("Error")
+                || trimmed.contains("Exception");
+            if is_error && !trimmed.starts_with("Traceback") {
+                error = Some(trimmed.to_string());
+            }
+        }
+    }
+    frames.extend(python_frames.drain(..).rev());
+    StackTrace { error, frames }
+}
+
+/// Finds the workspace files trace paths refer to
+struct Resolver<'a> {
+    root: &'a Path,
+    filter: ConfigurableFilter,
+    /// Workspace files by file name, listed on first use
+    by_name: Option<HashMap<String, Vec<PathBuf>>>,
+}
+
+impl Resolver<'_> {
+    fn included(&self, relative: &Path) -> bool {
+        relative
+            .components()
+            .all(|c| !matches!(c, Component::ParentDir))
+            && self.filter.should_include_path(relative)
+    }
+
+    fn files_named(&mut self, name: &str) -> &[PathBuf] {
+        let (root, filter) = (self.root, &self.filter);
+        let by_name = self.by_name.get_or_insert_with(|| {
+            let mut by_name: HashMap<String, Vec<PathBuf>> = HashMap::new();
+            let walker = WalkDir::new(root)
+                .follow_links(false)
+                .sort_by_file_name()
+                .into_iter()
+                .filter_entry(|entry| {
+                    entry.depth() == 0
+                        || filter.should_include_path(
+                            entry.path().strip_prefix(root).unwrap_or(entry.path()),
+                        )
+                });
+            for entry in walker.flatten().filter(|e| e.file_type().is_file()) {
+                if let Ok(relative) = entry.path().strip_prefix(root) {
+                    by_name
+                        .entry(entry.file_name().to_string_lossy().to_string())
+                        .or_default()
+                        .push(relative.to_path_buf());
+                }
+            }
+            by_name
+        });
+        by_name.get(name).map_or(&[], Vec::as_slice)
+    }
+
+    /// The workspace-relative file `file` names, and whether it is project code
+    fn resolve(&mut self, file: &str) -> Option<(PathBuf, bool)> {
+        let library = LIBRARY_MARKERS.iter().any(|marker| file.contains(marker));
+        let path = Path::new(file);
+        if let Ok(relative) = path.strip_prefix(self.root) {
+            if path.is_file() {
+                return Some((relative.to_path_buf(), !library && self.included(relative)));
+            }
+        }
+        // The longest suffix of the path that exists under the root
+        let components: Vec<&str> = path
+            .components()
+            .filter_map(|c| match c {
+                Component::Normal(part) => part.to_str(),
+                _ => None,
+            })
+            .collect();
+        for start in 0..components.len() {
+            let relative: PathBuf = components[start..].iter().collect();
+            if self.root.join(&relative).is_file() {
+                let in_workspace = !library && self.included(&relative);
+                return Some((relative, in_workspace));
+            }
+        }
+        if library {
+            return None;
+        }
+        // A bare file name, as JVM frames give: use it when it is unambiguous
+        let name = components.last()?;
+        match self.files_named(name) {
+            [only] => {
+                let only = only.clone();
+                let in_workspace = self.included(&only);
+                Some((only, in_workspace))
+            }
+            _ => None,
+        }
+    }
+}
+
+/// Resolve the frames of `trace` against the workspace at `root`
+pub fn resolve_frames(trace: &mut StackTrace, root: &Path, filter: ConfigurableFilter) {
+    let mut resolver = Resolver {
+        root,
+        filter,
+        by_name: None,
+    };
+    let mut sources: HashMap<(PathBuf, usize), Option<String>> = HashMap::new();
+    for frame in &mut trace.frames {
+        let Some((relative, in_workspace)) = resolver.resolve(&frame.file) else {
+            continue;
+        };
+        let path = root.join(&relative);
+        let line = frame.line;
+        frame.source = sources
+            .entry((relative.clone(), line))
+            .or_insert_with(|| {
+                read_range(&path, line, line)
+                    .ok()
+                    .and_then(|lines| lines.into_iter().next())
+                    .map(|(_, text)| text.trim().to_string())
+            })
+            .clone();
+        frame.resolved = Some(relative);
+        frame.in_workspace = in_workspace;
+    }
+}
+
+/// Tool resolving stack trace frames to workspace files
+pub struct TraceToSourceTool {
+    name: String,
+}
+
+impl TraceToSourceTool {
+    pub fn new() -> Self {
+        Self {
+            name: "trace_to_source".to_string(),
+        }
+    }
+
+    fn render_frame(frame: &Frame) -> String {
+        let location = match &frame.resolved {
+            Some(path) => format!("{}:{}", path.display(), frame.line),
+            None => format!("{}:{} (not in workspace)", frame.file, frame.line),
+        };
+        let marker = if frame.in_workspace { "*" } else { " " };
+        let mut text = format!("{} {}", marker, location);
+        if let Some(function) = &frame.function {
+            let _ = write!(text, " in {}", function);
+        }
+        if let Some(source) = frame.source.as_deref().filter(|s| !s.is_empty()) {
+            let _ = write!(text, "\n      {}", source);
+        }
+        text
+    }
+}
+
+impl Default for TraceToSourceTool {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl Tool for TraceToSourceTool {
+    fn name(&self) -> &str {
+        &self.name
+    }
+
+    fn description(&self) -> &str {
+        "Parse a stack trace (Rust panic/backtrace, Python traceback, Node.js, JVM or Go stack) and resolve each frame to a workspace file and line, innermost first; open: true also opens the innermost project frame"
+    }
+
+    fn signature(&self) -> &str {
+        "trace_to_source <trace> [--open]"
+    }
+
+    fn category(&self) -> ToolCategory {
+        ToolCategory::Navigation
+    }
+
+    fn danger_level(&self) -> DangerLevel {
+        DangerLevel::ReadOnly
+    }
+
+    fn validate_args(&self, args: &ToolArgs) -> Result<(), ToolError> {
+        let trace = args.get_named_arg("trace").or_else(|| args.get_arg(0));
+        if trace.is_none_or(|trace| trace.trim().is_empty()) {
+            return Err(ToolError::InvalidArgs {
+                message: "Missing required argument: trace".to_string(),
+            });
+        }
+        Ok(())
+    }
+
+    fn execute(&mut self, args: &ToolArgs, state: &Arc<Mutex<ToolState>>) -> Result<ToolResult> {
+        let Some(text) = args.get_named_arg("trace").or_else(|| args.get_arg(0)) else {
+            return Ok(ToolResult::error("Missing required argument: trace"));
+        };
+        let open = args.get_named_arg("open").map(String::as_str) == Some("true");
+        let (root, filter) = {
+            let mut state_guard = state
+                .lock()
+                .map_err(|e| anyhow::anyhow!("Failed to lock state: {}", e))?;
+            (
+                state_guard.working_directory.clone(),
+                state_guard.search_filter(),
+            )
+        };
+
+        let mut trace = parse_trace(text);
+        if trace.frames.is_empty() {
+            return Ok(ToolResult::error(
+                "No stack frames found. Expected a Rust panic or backtrace, a Python traceback, or a Node.js, JVM or Go stack",
+            ));
+        }
+        resolve_frames(&mut trace, &root, filter);
+        let resolved = trace.frames.iter().filter(|f| f.resolved.is_some()).count();
+        let top = trace.frames.iter().find(|f| f.in_workspace);
+
+        let mut message = String::new();
+        if let Some(error) = &trace.error {
+            let _ = writeln!(message, "{}", error);
+        }
+        let _ = write!(
+            message,
+            "{} frame(s), {} resolved to workspace files (innermost first, * marks project code):",
+            trace.frames.len(),
+            resolved
+        );
+        for frame in trace.frames.iter().take(MAX_LISTED_FRAMES) {
+            message.push('\n');
+            message.push_str(&Self::render_frame(frame));
+        }
+        if trace.frames.len() > MAX_LISTED_FRAMES {
+            let _ = write!(
+                message,
+                "\n... {} more frame(s)",
+                trace.frames.len() - MAX_LISTED_FRAMES
+            );
+        }
+
+        if let (true, Some(top)) = (open, top) {
+            let relative = top.resolved.clone().unwrap_or_default();
+            let path = root.join(&relative);
+            let content = profiling::read_to_string(&path)
+                .map_err(|e| anyhow::anyhow!("Failed to read {}: {}", path.display(), e))?;
+            let mut state_guard = state
+                .lock()
+                .map_err(|e| anyhow::anyhow!("Failed to lock state: {}", e))?;
+            state_guard.open_file(
+                path.clone(),
+                content.lines().map(str::to_string).collect(),
+                DEFAULT_WINDOW_SIZE,
+            )?;
+            if let Some(file_state) = state_guard.get_current_file_state_mut() {
+                file_state.goto_line(top.line);
+                let _ = write!(
+                    message,
+                    "\n\nOpened {} at line {}:\n{}",
+                    relative.display(),
+                    top.line,
+                    file_state.window_display()
+                );
+            }
+            state_guard.push_history(format!(
+                "Opened {} at line {} from a stack trace",
+                relative.display(),
+                top.line
+            ));
+        } else if top.is_none() {
+            message.push_str("\n\nNo frame is in the project's own code");
+        }
+
+        Ok(ToolResult::success_with_data(
+            message,
+            serde_json::json!({
+                "error": trace.error,
+                "frames": trace.frames,
+                "top_frame": top.map(|f| serde_json::json!({
+                    "path": f.resolved.as_ref().map(|p| root.join(p).display().to_string()),
+                    "line": f.line,
+                })),
+                "opened": open && top.is_some(),
+            }),
+        ))
+    }
+
+    fn get_parameters_schema(&self) -> serde_json::Value {
+        serde_json::json!({
+            "type": "object",
+            "properties": {
+                "trace": {
+                    "type": "string",
+                    "description": "The stack trace or failure output containing it"
+                },
+                "open": {
+                    "type": "boolean",
+                    "description": "Open the innermost frame in the project's own code, like `open <file> <line>`"
+                }
+            },
+            "required": ["trace"]
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use tempfile::TempDir;
+
+    #[test]
+    fn test_parse_trace_languages() {
+        let rust = "thread 'main' panicked at src/lib.rs:10:5:\nattempt to divide by zero\nstack backtrace:\n   0: app::divide\n             at ./src/lib.rs:10:5\n   1: core::ops::function::FnOnce::call_once\n             at /rustc/abc/library/core/src/ops/function.rs:250:5\n";
+        let trace = parse_trace(rust);
+        assert_eq!(trace.error.as_deref(), Some("attempt to divide by zero"));
+        assert_eq!(trace.frames.len(), 3);
+        assert_eq!(trace.frames[1].function.as_deref(), Some("app::divide"));
+        assert_eq!(trace.frames[1].column, Some(5));
+
+        let python = "Traceback (most recent call last):\n  File \"/ci/work/app/main.py\", line 3, in <module>\n    run()\n  File \"/ci/work/app/util.py\", line 8, in run\n    1 / 0\nZeroDivisionError: division by zero\n";
+        let trace = parse_trace(python);
+        assert_eq!(
+            trace.error.as_deref(),
+            Some("ZeroDivisionError: division by zero")
+        );
+        let files: Vec<&str> = trace.frames.iter().map(|f| f.file.as_str()).collect();
+        assert_eq!(files, vec!["/ci/work/app/util.py", "/ci/work/app/main.py"]);
+
+        let node = "TypeError: x is not a function\n    at render (/home/u/web/src/view.js:12:7)\n    at file:///home/u/web/src/index.mjs:3:1\n    at node:internal/main/run_main_module:28:49\n";
+        let trace = parse_trace(node);
+        assert_eq!(
+            trace.error.as_deref(),
+            Some("TypeError: x is not a function")
+        );
+        assert_eq!(trace.frames.len(), 3);
+        assert_eq!(trace.frames[1].file, "/home/u/web/src/index.mjs");
+        assert_eq!(trace.frames[1].function, None);
+
+        let jvm =
+            "java.lang.IllegalStateException: closed\n\tat com.example.Pool.get(Pool.java:88)\n";
+        assert_eq!(parse_trace(jvm).frames[0].file, "Pool.java");
+
+        let go =
+            "panic: boom\n\ngoroutine 1 [running]:\nmain.run(...)\n\t/src/app/main.go:9 +0x1d\n";
+        let trace = parse_trace(go);
+        assert_eq!(trace.error.as_deref(), Some("panic: boom"));
+        assert_eq!(trace.frames[0].function.as_deref(), Some("main.run"));
+    }
+
+    #[test]
+    fn test_trace_to_source_resolves_and_opens() {
+        let temp_dir = TempDir::new().unwrap();
+        let root = temp_dir.path();
+        std::fs::create_dir_all(root.join("app")).unwrap();
+        std::fs::create_dir_all(root.join(".venv/lib/python3.12/site-packages/lib")).unwrap();
+        std::fs::write(
+            root.join("app/main.py"),
+            "from app.util import run\n\nrun()\n",
+        )
+        .unwrap();
+        std::fs::write(
+            root.join("app/util.py"),
+            "import lib\n\n\ndef run():\n    lib.go()\n",
+        )
+        .unwrap();
+        std::fs::write(
+            root.join(".venv/lib/python3.12/site-packages/lib/__init__.py"),
+            "def go():\n    1 / 0\n",
+        )
+        .unwrap();
+        let venv_file = root.join(".venv/lib/python3.12/site-packages/lib/__init__.py");
+        let trace = format!(
+            "Traceback (most recent call last):\n  File \"/ci/work/app/main.py\", line 3, in <module>\n    run()\n  File \"/ci/work/app/util.py\", line 5, in run\n    lib.go()\n  File \"{}\", line 2, in go\n    1 / 0\nZeroDivisionError: division by zero\n",
+            venv_file.display()
+        );
+
+        let mut tool_state = ToolState::new();
+        tool_state.working_directory = root.to_path_buf();
+        let state = Arc::new(Mutex::new(tool_state));
+        let args = ToolArgs::with_named_args(
+            vec![],
+            [("trace", trace.as_str()), ("open", "true")]
+                .into_iter()
+                .map(|(k, v)| (k.to_string(), v.to_string()))
+                .collect(),
+        );
+        let result = TraceToSourceTool::new().execute(&args, &state).unwrap();
+        assert!(result.success, "{}", result.message);
+        let frames = &result.data.as_ref().unwrap()["frames"];
+        assert_eq!(frames[0]["in_workspace"], false);
+        assert_eq!(frames[1]["resolved"], "app/util.py");
+        assert_eq!(frames[1]["in_workspace"], true);
+        assert_eq!(frames[1]["source"], "lib.go()");
+        assert_eq!(frames[2]["resolved"], "app/main.py");
+        assert!(result.message.contains("Opened app/util.py at line 5"));
+        assert!(state
+            .lock()
+            .unwrap()
+            .is_current_file(&root.join("app/util.py")));
+    }
+}
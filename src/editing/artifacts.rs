@@ -0,0 +1,185 @@
+//! Warnings for edits in generated or ignored locations
+//!
+//! After each tool execution the registry checks the paths the tool wrote (as recorded
+//! by `editing::writer`). Modifying a file inside a generated directory such as
+//! `target/` or `node_modules/` is wasted work the next build overwrites, and creating
+//! a file there or in a path ignored by the root `.gitignore` means it will never be
+//! committed. Either case is reported as a warning on the tool result.
+
+use super::protection::ProtectedPaths;
+use crate::events::ChangeKind;
+use std::fs;
+use std::path::{Component, Path, PathBuf};
+
+/// Directory names whose contents are produced by build tools
+pub const GENERATED_DIRS: &[&str] = &[
+    "target",
+    "node_modules",
+    "dist",
+    "build",
+    "__pycache__",
+    ".pytest_cache",
+    ".mypy_cache",
+    ".tox",
+    ".next",
+    ".nuxt",
+];
+
+/// Directory used by cats itself for the journal and trash, never reported
+const CATS_DIR: &str = ".cats";
+
+/// A path changed by a tool
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct TouchedPath {
+    pub path: PathBuf,
+    pub kind: ChangeKind,
+    /// Previous path of a renamed file
+    pub from: Option<PathBuf>,
+    /// Unified diff of the change for text files that were written or deleted
+    pub diff: Option<String>,
+    /// For content writes, whether reading the file back matched what was written
+    pub verified: Option<bool>,
+}
+
+impl TouchedPath {
+    /// A change of `kind` to `path` with no diff or rename source
+    pub fn new(path: PathBuf, kind: ChangeKind) -> Self {
+        Self {
+            path,
+            kind,
+            from: None,
+            diff: None,
+            verified: None,
+        }
+    }
+
+    /// Whether the path did not exist before the change
+    pub fn created(&self) -> bool {
+        matches!(self.kind, ChangeKind::Created | ChangeKind::Renamed)
+    }
+}
+
+/// Relative components of `path` under `working_dir`, or `None` outside it
+fn relative_components(working_dir: &Path, path: &Path) -> Option<Vec<String>> {
+    let absolute = std::path::absolute(path).unwrap_or_else(|_| path.to_path_buf());
+    let relative = absolute.strip_prefix(working_dir).ok()?;
+    Some(
+        relative
+            .components()
+            .filter_map(|c| match c {
+                Component::Normal(part) => Some(part.to_string_lossy().to_string()),
+                _ => None,
+            })
+            .collect(),
+    )
+}
+
+/// Patterns from the `.gitignore` at the root of `working_dir`
+fn gitignore_patterns(working_dir: &Path) -> ProtectedPaths {
+    // Entries use the same gitignore-style semantics as protected paths
+    let lines: Vec<String> = fs::read_to_string(working_dir.join(".gitignore"))
+        .unwrap_or_default()
+        .lines()
+        .map(str::trim)
+        .filter(|l| !l.is_empty() && !l.starts_with('#') && !l.starts_with('!'))
+        .map(String::from)
+        .collect();
+    ProtectedPaths::new(lines)
+}
+
+/// Warnings for `touched` paths that live in generated or ignored locations
+pub fn check_touched(working_dir: &Path, touched: &[TouchedPath]) -> Vec<String> {
+    if touched.is_empty() {
+        return Vec::new();
+    }
+    let ignored = gitignore_patterns(working_dir);
+    let mut warnings = Vec::new();
+
+    for entry in touched.iter().filter(|t| t.kind != ChangeKind::Deleted) {
+        let Some(components) = relative_components(working_dir, &entry.path) else {
+            continue;
+        };
+        if components.first().map(String::as_str) == Some(CATS_DIR) {
+            continue;
+        }
+
+        // The generated directory is the last path component before the file itself
+        let generated = components[..components.len().saturating_sub(1)]
+            .iter()
+            .position(|c| GENERATED_DIRS.contains(&c.as_str()))
+            .map(|i| components[..=i].join("/"));
+        if let Some(dir) = generated {
+            warnings.push(if entry.created() {
+                format!(
+                    "{} was created inside the generated directory {}/; build tools may delete or overwrite it and it is usually not committed",
+                    entry.path.display(),
+                    dir
+                )
+            } else {
+                format!(
+                    "{} is inside the generated directory {}/; the next build will overwrite this edit, change the source that generates it instead",
+                    entry.path.display(),
+                    dir
+                )
+            });
+            continue;
+        }
+
+        if entry.created() {
+            let mut ancestor = working_dir.to_path_buf();
+            for component in &components {
+                ancestor.push(component);
+                if let Some(pattern) = ignored.matching_pattern(working_dir, &ancestor) {
+                    warnings.push(format!(
+                        "{} was created in a path ignored by .gitignore ('{}') and will not be committed",
+                        entry.path.display(),
+                        pattern
+                    ));
+                    break;
+                }
+            }
+        }
+    }
+    warnings
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use tempfile::TempDir;
+
+    fn touched(path: PathBuf, created: bool) -> TouchedPath {
+        let kind = if created {
+            ChangeKind::Created
+        } else {
+            ChangeKind::Modified
+        };
+        TouchedPath::new(path, kind)
+    }
+
+    #[test]
+    fn test_check_touched() {
+        let temp_dir = TempDir::new().unwrap();
+        let root = temp_dir.path();
+        fs::write(root.join(".gitignore"), "*.log\n/out/\n").unwrap();
+        fs::create_dir_all(root.join("out")).unwrap();
+
+        let warnings = check_touched(
+            root,
+            &[
+                touched(root.join("src/lib.rs"), true),
+                touched(root.join("target/debug/build.rs"), false),
+                touched(root.join("web/node_modules/x/index.js"), true),
+                touched(root.join("out/report.txt"), true),
+                touched(root.join("run.log"), false),
+                touched(root.join(".cats/trash/1/target/a"), true),
+                TouchedPath::new(root.join("dist/old.js"), ChangeKind::Deleted),
+            ],
+        );
+
+        assert_eq!(warnings.len(), 3, "{:#?}", warnings);
+        assert!(warnings[0].contains("generated directory target/; the next build"));
+        assert!(warnings[1].contains("created inside the generated directory web/node_modules/"));
+        assert!(warnings[2].contains("ignored by .gitignore ('/out/')"));
+    }
+}
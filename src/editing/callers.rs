@@ -0,0 +1,587 @@
+//! Update call sites after a function signature change
+//!
+//! Call sites are found with the tree-sitter grammars of the `ast` module, so calls
+//! are matched by the called name (free functions, paths and method calls alike)
+//! rather than by resolved type.
+
+use super::writer;
+use crate::ast::{parse, source_files, Language};
+use crate::core::{DangerLevel, Tool, ToolArgs, ToolCategory, ToolError, ToolResult};
+use crate::state::ToolState;
+use anyhow::Result;
+use serde::Serialize;
+use serde_json::{json, Value};
+use std::collections::BTreeMap;
+use std::path::{Path, PathBuf};
+use std::sync::{Arc, Mutex};
+use tree_sitter::Node;
+
+/// Longest call text shown in the preview before it is elided
+const MAX_PREVIEW_CHARS: usize = 120;
+
+/// The signature change to propagate to call sites
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum SignatureChange {
+    /// Insert `argument` at `position` (appended when `None`)
+    AddArgument {
+        argument: String,
+        position: Option<usize>,
+    },
+    /// Remove the argument at `position`
+    RemoveArgument { position: usize },
+}
+
+/// A call site and the edit it needs
+#[derive(Debug, Clone, Serialize)]
+pub struct CallSite {
+    pub path: PathBuf,
+    pub line: usize,
+    /// The call as written, on one line
+    pub before: String,
+    /// The call after the edit; `None` when it needs manual review
+    pub after: Option<String>,
+    /// Why the call cannot be updated mechanically
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub reason: Option<String>,
+    #[serde(skip)]
+    edit: Option<(usize, usize, String)>,
+}
+
+/// Name of the function called by a call expression's `function` node
+fn callee_name<'a>(node: Node, source: &'a str) -> Option<&'a str> {
+    let field = match node.kind() {
+        "identifier" => return node.utf8_text(source.as_bytes()).ok(),
+        "generic_function" => return callee_name(node.child_by_field_name("function")?, source),
+        "scoped_identifier" => "name",
+        "field_expression" | "selector_expression" => "field",
+        "attribute" => "attribute",
+        "member_expression" => "property",
+        _ => return None,
+    };
+    node.child_by_field_name(field)?
+        .utf8_text(source.as_bytes())
+        .ok()
+}
+
+/// Why a call's arguments cannot be edited by position, if they cannot
+fn unsupported_argument(arguments: &[Node]) -> Option<&'static str> {
+    arguments.iter().find_map(|argument| match argument.kind() {
+        "keyword_argument" => Some("uses keyword arguments"),
+        "list_splat" | "dictionary_splat" | "spread_element" | "variadic_argument" => {
+            Some("uses argument unpacking")
+        }
+        _ => None,
+    })
+}
+
+/// The `(start, end, replacement)` byte edit applying `change` to an argument list
+fn argument_edit(
+    list: Node,
+    arguments: &[Node],
+    change: &SignatureChange,
+) -> Result<(usize, usize, String), String> {
+    if let Some(reason) = unsupported_argument(arguments) {
+        return Err(reason.to_string());
+    }
+    let count = arguments.len();
+    match change {
+        SignatureChange::AddArgument { argument, position } => {
+            let position = position.unwrap_or(count);
+            if position > count {
+                return Err(format!("has only {} argument(s)", count));
+            }
+            Ok(if count == 0 {
+                (
+                    list.start_byte() + 1,
+                    list.start_byte() + 1,
+                    argument.clone(),
+                )
+            } else if position == count {
+                let end = arguments[count - 1].end_byte();
+                (end, end, format!(", {}", argument))
+            } else {
+                let start = arguments[position].start_byte();
+                (start, start, format!("{}, ", argument))
+            })
+        }
+        SignatureChange::RemoveArgument { position } => {
+            let position = *position;
+            if position >= count {
+                return Err(format!("has only {} argument(s)", count));
+            }
+            Ok(if count == 1 {
+                (
+                    arguments[0].start_byte(),
+                    arguments[0].end_byte(),
+                    String::new(),
+                )
+            } else if position + 1 < count {
+                (
+                    arguments[position].start_byte(),
+                    arguments[position + 1].start_byte(),
+                    String::new(),
+                )
+            } else {
+                (
+                    arguments[position - 1].end_byte(),
+                    arguments[position].end_byte(),
+                    String::new(),
+                )
+            })
+        }
+    }
+}
+
+fn preview(text: &str) -> String {
+    let text = text.split_whitespace().collect::<Vec<_>>().join(" ");
+    if text.chars().count() > MAX_PREVIEW_CHARS {
+        let truncated: String = text.chars().take(MAX_PREVIEW_CHARS).collect();
+        format!("{}...", truncated)
+    } else {
+        text
+    }
+}
+
+/// Every call to `name` in `source` with the edit `change` requires
+pub fn call_sites(
+    path: &Path,
+    language: Language,
+    source: &str,
+    name: &str,
+    change: &SignatureChange,
+) -> Vec<CallSite> {
+    let Some(tree) = parse(language, source) else {
+        return Vec::new();
+    };
+    let call_kind = match language {
+        Language::Python => "call",
+        _ => "call_expression",
+    };
+
+    let mut sites = Vec::new();
+    let mut cursor = tree.walk();
+    let mut stack = vec![tree.root_node()];
+    while let Some(node) = stack.pop() {
+        let children: Vec<_> = node.children(&mut cursor).collect();
+        stack.extend(children.into_iter().rev());
+        if node.kind() != call_kind {
+            continue;
+        }
+        let Some(function) = node.child_by_field_name("function") else {
+            continue;
+        };
+        if callee_name(function, source) != Some(name) {
+            continue;
+        }
+        let Some(list) = node.child_by_field_name("arguments") else {
+            continue;
+        };
+
+        let call_text = &source[node.start_byte()..node.end_byte()];
+        let outcome = if list.kind() == "generator_expression" {
+            Err("takes a generator expression".to_string())
+        } else {
+            let mut list_cursor = list.walk();
+            let arguments: Vec<Node> = list
+                .named_children(&mut list_cursor)
+                .filter(|a| !a.kind().contains("comment"))
+                .collect();
+            argument_edit(list, &arguments, change)
+        };
+        let (after, reason, edit) = match outcome {
+            Ok((start, end, replacement)) => {
+                let offset = node.start_byte();
+                let mut after = call_text.to_string();
+                after.replace_range(start - offset..end - offset, &replacement);
+                (Some(preview(&after)), None, Some((start, end, replacement)))
+            }
+            Err(reason) => (None, Some(reason), None),
+        };
+        sites.push(CallSite {
+            path: path.to_path_buf(),
+            line: node.start_position().row + 1,
+            before: preview(call_text),
+            after,
+            reason,
+            edit,
+        });
+    }
+    sites
+}
+
+/// Apply the edits of `sites` (all from one file) to `source`
+///
+/// Edits are applied back to front; one that overlaps an edit already applied (a call
+/// nested in a removed argument) is left alone and marked for manual review.
+fn apply_edits(source: &str, sites: &mut [&mut CallSite]) -> String {
+    let mut updated = source.to_string();
+    sites.sort_by_key(|site| std::cmp::Reverse(site.edit.as_ref().map(|e| e.0)));
+    let mut applied_from = usize::MAX;
+    for site in sites.iter_mut() {
+        let Some((start, end, replacement)) = site.edit.clone() else {
+            continue;
+        };
+        if end > applied_from {
+            site.after = None;
+            site.reason = Some("overlaps another edit".to_string());
+            continue;
+        }
+        updated.replace_range(start..end, &replacement);
+        applied_from = start;
+    }
+    updated
+}
+
+/// Tool previewing (and optionally applying) the call-site edits of a signature change
+pub struct UpdateCallersTool {
+    name: String,
+}
+
+impl UpdateCallersTool {
+    pub fn new() -> Self {
+        Self {
+            name: "update_callers".to_string(),
+        }
+    }
+
+    fn parse_params(args: &ToolArgs) -> Result<serde_json::Map<String, Value>, ToolError> {
+        let value = if let Some(json_str) = args.get_named_arg("json") {
+            serde_json::from_str(json_str).map_err(ToolError::Json)?
+        } else if !args.named_args.is_empty() {
+            serde_json::to_value(&args.named_args).map_err(ToolError::Json)?
+        } else {
+            let names = ["function", "change", "position", "argument"];
+            Value::Object(
+                names
+                    .iter()
+                    .zip(args.args.iter())
+                    .map(|(name, arg)| (name.to_string(), Value::String(arg.clone())))
+                    .collect(),
+            )
+        };
+        match value {
+            Value::Object(obj) if obj.get("function").and_then(|f| f.as_str()).is_some() => Ok(obj),
+            _ => Err(ToolError::InvalidArgs {
+                message: "Missing required parameter: function".to_string(),
+            }),
+        }
+    }
+
+    fn change(obj: &serde_json::Map<String, Value>) -> Result<SignatureChange, String> {
+        let position = match obj.get("position") {
+            None | Some(Value::Null) => None,
+            Some(Value::Number(n)) => Some(n.as_u64().ok_or("position must be 0 or more")?),
+            Some(Value::String(s)) if s.trim().is_empty() => None,
+            Some(Value::String(s)) => {
+                Some(s.trim().parse().map_err(|_| "position must be a number")?)
+            }
+            Some(_) => return Err("position must be a number".to_string()),
+        }
+        .map(|p| p as usize);
+        match obj.get("change").and_then(|c| c.as_str()) {
+            Some("add") => {
+                let argument = obj
+                    .get("argument")
+                    .and_then(|a| a.as_str())
+                    .filter(|a| !a.trim().is_empty())
+                    .ok_or("change 'add' requires the argument text to insert")?;
+                Ok(SignatureChange::AddArgument {
+                    argument: argument.trim().to_string(),
+                    position,
+                })
+            }
+            Some("remove") => Ok(SignatureChange::RemoveArgument {
+                position: position.ok_or("change 'remove' requires a position")?,
+            }),
+            Some(other) => Err(format!(
+                "Unknown change '{}': expected add or remove",
+                other
+            )),
+            None => Err("Missing required parameter: change (add or remove)".to_string()),
+        }
+    }
+}
+
+impl Default for UpdateCallersTool {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl Tool for UpdateCallersTool {
+    fn name(&self) -> &str {
+        &self.name
+    }
+
+    fn description(&self) -> &str {
+        "Find every call of a function after adding or removing a parameter and preview the \
+         edit each call site needs; apply=true performs the mechanical edits and lists the \
+         calls left for manual review"
+    }
+
+    fn signature(&self) -> &str {
+        "update_callers(function: str, change: add|remove, position?: int, argument?: str, path?: str, apply?: bool)"
+    }
+
+    fn category(&self) -> ToolCategory {
+        ToolCategory::Editing
+    }
+
+    fn danger_level(&self) -> DangerLevel {
+        DangerLevel::ModifiesFiles
+    }
+
+    fn validate_args(&self, args: &ToolArgs) -> Result<(), ToolError> {
+        let obj = Self::parse_params(args)?;
+        Self::change(&obj)
+            .map(|_| ())
+            .map_err(|message| ToolError::InvalidArgs { message })
+    }
+
+    fn execute(&mut self, args: &ToolArgs, state: &Arc<Mutex<ToolState>>) -> Result<ToolResult> {
+        let obj = Self::parse_params(args)?;
+        let change = match Self::change(&obj) {
+            Ok(change) => change,
+            Err(message) => return Ok(ToolResult::error(message)),
+        };
+        let function = obj["function"].as_str().unwrap_or_default().trim();
+        // `Type::method` and `obj.method` are matched by the method name
+        let name = function.rsplit([':', '.']).next().unwrap_or(function);
+        let apply = super::bool_param(&obj, "apply").unwrap_or(false);
+        let working_directory = state
+            .lock()
+            .map_err(|e| anyhow::anyhow!("Failed to lock state: {}", e))?
+            .working_directory
+            .clone();
+        let root = match obj.get("path").and_then(|p| p.as_str()) {
+            Some(path) => working_directory.join(path),
+            None => working_directory.clone(),
+        };
+
+        let mut files: BTreeMap<PathBuf, (String, Vec<CallSite>)> = BTreeMap::new();
+        for (path, language, source) in source_files(&root, name) {
+            let sites = call_sites(&path, language, &source, name, &change);
+            if !sites.is_empty() {
+                files.insert(path, (source, sites));
+            }
+        }
+        if files.is_empty() {
+            return Ok(ToolResult::success_with_data(
+                format!("No calls to {} found under {}", function, root.display()),
+                json!({"function": function, "call_sites": [], "applied": false}),
+            ));
+        }
+
+        let mut changed = Vec::new();
+        if apply {
+            for (path, (source, sites)) in files.iter_mut() {
+                let mut editable: Vec<&mut CallSite> = sites.iter_mut().collect();
+                let updated = apply_edits(source, &mut editable);
+                if updated != *source {
+                    writer::write_file(state, path, &updated)?;
+                    changed.push(path.clone());
+                }
+            }
+        }
+
+        let relative = |p: &Path| {
+            p.strip_prefix(&working_directory)
+                .unwrap_or(p)
+                .display()
+                .to_string()
+        };
+        let sites: Vec<&CallSite> = files.values().flat_map(|(_, sites)| sites).collect();
+        let (mechanical, manual): (Vec<&CallSite>, Vec<&CallSite>) =
+            sites.iter().partition(|site| site.after.is_some());
+
+        let mut lines = vec![format!(
+            "{} call site(s) of {} in {} file(s){}",
+            sites.len(),
+            function,
+            files.len(),
+            if apply {
+                format!(
+                    ": updated {} in {} file(s)",
+                    mechanical.len(),
+                    changed.len()
+                )
+            } else {
+                " (preview; pass apply=true to edit)".to_string()
+            }
+        )];
+        for site in &mechanical {
+            lines.push(format!(
+                "  {}:{}: {} -> {}",
+                relative(&site.path),
+                site.line,
+                site.before,
+                site.after.as_deref().unwrap_or_default()
+            ));
+        }
+        if !manual.is_empty() {
+            lines.push(format!("\nNeeds manual review ({}):", manual.len()));
+            for site in &manual {
+                lines.push(format!(
+                    "  {}:{}: {} ({})",
+                    relative(&site.path),
+                    site.line,
+                    site.before,
+                    site.reason.as_deref().unwrap_or_default()
+                ));
+            }
+        }
+
+        if apply {
+            state
+                .lock()
+                .map_err(|e| anyhow::anyhow!("Failed to lock state: {}", e))?
+                .push_history(format!(
+                    "Updated {} call site(s) of {} in {} file(s)",
+                    mechanical.len(),
+                    function,
+                    changed.len()
+                ));
+        }
+
+        let call_sites: Vec<Value> = sites
+            .iter()
+            .map(|site| {
+                let mut value = serde_json::to_value(site).unwrap_or_default();
+                value["path"] = json!(relative(&site.path));
+                value
+            })
+            .collect();
+        Ok(ToolResult::success_with_data(
+            lines.join("\n"),
+            json!({
+                "function": function,
+                "call_sites": call_sites,
+                "manual": manual.len(),
+                "applied": apply,
+                "files_changed": changed.iter().map(|p| relative(p)).collect::<Vec<_>>()
+            }),
+        ))
+    }
+
+    fn get_parameters_schema(&self) -> Value {
+        json!({
+            "type": "object",
+            "properties": {
+                "function": {
+                    "type": "string",
+                    "description": "Name of the changed function; calls are matched by name, including method calls"
+                },
+                "change": {
+                    "type": "string",
+                    "enum": ["add", "remove"],
+                    "description": "Whether a parameter was added or removed"
+                },
+                "position": {
+                    "type": "integer",
+                    "description": "0-based argument index at the call sites (add defaults to appending; required for remove)"
+                },
+                "argument": {
+                    "type": "string",
+                    "description": "Argument text to insert for add, e.g. `None` or `Default::default()`"
+                },
+                "path": {
+                    "type": "string",
+                    "description": "Directory or file to search (defaults to the working directory)"
+                },
+                "apply": {
+                    "type": "boolean",
+                    "description": "Edit the files instead of only previewing (default: false)"
+                }
+            },
+            "required": ["function", "change"]
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::fs;
+    use tempfile::TempDir;
+
+    #[test]
+    fn test_call_sites_per_change() {
+        let add = SignatureChange::AddArgument {
+            argument: "None".to_string(),
+            position: None,
+        };
+        let source = "connect(host)\nclient.connect()\nconnect(*args)\n";
+        let sites = call_sites(Path::new("a.py"), Language::Python, source, "connect", &add);
+        let previews: Vec<_> = sites
+            .iter()
+            .map(|s| (s.after.as_deref(), s.reason.as_deref()))
+            .collect();
+        assert_eq!(
+            previews,
+            vec![
+                (Some("connect(host, None)"), None),
+                (Some("client.connect(None)"), None),
+                (None, Some("uses argument unpacking")),
+            ]
+        );
+
+        let remove = SignatureChange::RemoveArgument { position: 1 };
+        let source = "fn main() {\n    let s = Server::start(addr, 8080, true);\n    s.start(a, b);\n    start(only);\n}\n";
+        let sites = call_sites(Path::new("a.rs"), Language::Rust, source, "start", &remove);
+        let previews: Vec<_> = sites.iter().map(|s| s.after.as_deref()).collect();
+        assert_eq!(
+            previews,
+            vec![Some("Server::start(addr, true)"), Some("s.start(a)"), None]
+        );
+        assert_eq!(sites[2].reason.as_deref(), Some("has only 1 argument(s)"));
+    }
+
+    #[test]
+    fn test_update_callers_applies_mechanical_edits() {
+        let dir = TempDir::new().unwrap();
+        let root = dir.path();
+        fs::write(
+            root.join("app.ts"),
+            "import { fetchUser } from './api';\nfetchUser(id);\nfetchUser(\n  id,\n);\nfetchUser(...ids);\n",
+        )
+        .unwrap();
+        fs::write(
+            root.join("api.ts"),
+            "export function fetchUser(id: number, cache: boolean) {}\n",
+        )
+        .unwrap();
+
+        let state = Arc::new(Mutex::new(ToolState::new()));
+        state.lock().unwrap().working_directory = root.to_path_buf();
+        let mut tool = UpdateCallersTool::new();
+
+        let args =
+            ToolArgs::from_args(&["--function=fetchUser", "--change=add", "--argument=false"]);
+        let preview = tool.execute(&args, &state).unwrap();
+        assert!(preview.success, "{}", preview.message);
+        assert!(preview
+            .message
+            .contains("app.ts:2: fetchUser(id) -> fetchUser(id, false)"));
+        assert!(preview.message.contains("Needs manual review (1)"));
+        assert!(fs::read_to_string(root.join("app.ts"))
+            .unwrap()
+            .contains("fetchUser(id);"));
+
+        let args = ToolArgs::from_args(&[
+            "--function=fetchUser",
+            "--change=add",
+            "--argument=false",
+            "--apply=true",
+        ]);
+        let result = tool.execute(&args, &state).unwrap();
+        assert!(result.success, "{}", result.message);
+        assert_eq!(result.data.unwrap()["files_changed"], json!(["app.ts"]));
+        assert_eq!(
+            fs::read_to_string(root.join("app.ts")).unwrap(),
+            "import { fetchUser } from './api';\nfetchUser(id, false);\nfetchUser(\n  id, false,\n);\nfetchUser(...ids);\n"
+        );
+
+        let args = ToolArgs::from_args(&["--function=fetchUser", "--change=remove"]);
+        assert!(tool.validate_args(&args).is_err());
+    }
+}
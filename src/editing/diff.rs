@@ -0,0 +1,119 @@
+//! Unified diff rendering for file changes
+//!
+//! Editing tools attach a [`DiffPreview`] of what they changed to their result, so the
+//! model can check the exact edit without reopening the file.
+
+use serde::Serialize;
+use similar::{ChangeTag, TextDiff};
+
+/// Lines of unchanged context shown around each hunk
+pub const DEFAULT_CONTEXT_LINES: usize = 3;
+
+/// Diff lines kept in a [`DiffPreview`]
+pub const MAX_PREVIEW_LINES: usize = 200;
+
+/// Render a unified diff between two versions of `path`
+///
+/// Returns an empty string when the contents are identical.
+pub fn unified_diff(old: &str, new: &str, path: &str) -> String {
+    if old == new {
+        return String::new();
+    }
+    TextDiff::from_lines(old, new)
+        .unified_diff()
+        .context_radius(DEFAULT_CONTEXT_LINES)
+        .header(&format!("a/{}", path), &format!("b/{}", path))
+        .to_string()
+}
+
+/// The diff of one edit as reported by an editing tool
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize)]
+pub struct DiffPreview {
+    /// Unified diff with [`DEFAULT_CONTEXT_LINES`] of context, cut after
+    /// [`MAX_PREVIEW_LINES`] lines
+    pub patch: String,
+    pub additions: usize,
+    pub deletions: usize,
+    pub truncated: bool,
+}
+
+impl DiffPreview {
+    /// Diff two versions of `path`
+    pub fn new(old: &str, new: &str, path: &str) -> Self {
+        let (additions, deletions) = TextDiff::from_lines(old, new).iter_all_changes().fold(
+            (0, 0),
+            |(additions, deletions), change| match change.tag() {
+                ChangeTag::Insert => (additions + 1, deletions),
+                ChangeTag::Delete => (additions, deletions + 1),
+                ChangeTag::Equal => (additions, deletions),
+            },
+        );
+        let diff = unified_diff(old, new, path);
+        let total = diff.lines().count();
+        let truncated = total > MAX_PREVIEW_LINES;
+        let patch = if truncated {
+            let mut patch: String = diff
+                .lines()
+                .take(MAX_PREVIEW_LINES)
+                .map(|line| format!("{}\n", line))
+                .collect();
+            patch.push_str(&format!(
+                "... {} more diff lines\n",
+                total - MAX_PREVIEW_LINES
+            ));
+            patch
+        } else {
+            diff
+        };
+        Self {
+            patch,
+            additions,
+            deletions,
+            truncated,
+        }
+    }
+
+    /// `message` followed by a blank line and the diff
+    pub fn append_to(&self, message: String) -> String {
+        if self.patch.is_empty() {
+            return format!("{}\n\n(no changes)", message);
+        }
+        format!(
+            "{} (+{} -{})\n\n{}",
+            message,
+            self.additions,
+            self.deletions,
+            self.patch.trim_end()
+        )
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_unified_diff() {
+        let diff = unified_diff("a\nb\nc\n", "a\nB\nc\n", "x.txt");
+        assert!(diff.starts_with("--- a/x.txt\n+++ b/x.txt\n@@ -1,3 +1,3 @@\n"));
+        assert!(diff.contains("-b\n+B\n"));
+        assert_eq!(unified_diff("same", "same", "x.txt"), "");
+    }
+
+    #[test]
+    fn test_diff_preview() {
+        let preview = DiffPreview::new("a\nb\nc\n", "a\nB\nb2\nc\n", "x.txt");
+        assert_eq!((preview.additions, preview.deletions), (2, 1));
+        assert_eq!(
+            preview.append_to("Edited x.txt".to_string()),
+            "Edited x.txt (+2 -1)\n\n--- a/x.txt\n+++ b/x.txt\n@@ -1,3 +1,4 @@\n a\n-b\n+B\n+b2\n c"
+        );
+
+        let old: String = (0..500).map(|i| format!("{}\n", i)).collect();
+        let preview = DiffPreview::new(&old, "", "x.txt");
+        assert!(preview.truncated);
+        assert_eq!(preview.deletions, 500);
+        assert_eq!(preview.patch.lines().count(), MAX_PREVIEW_LINES + 1);
+        assert!(preview.patch.ends_with("... 303 more diff lines\n"));
+    }
+}
@@ -0,0 +1,666 @@
+//! env_diff and env_sync tools: keep `.env` in step with its `.env.example` template
+//!
+//! Environment files hold secrets, so neither tool reports a value: `env_diff` tells
+//! which template keys are missing or empty in `.env` and which keys only `.env` has,
+//! and `env_sync` appends the missing keys with placeholder values. Writes to `.env`
+//! go through [`writer::write_file_redacted`], which keeps values out of the session
+//! journal and the reported diff.
+
+use super::{protection, writer};
+use crate::core::{DangerLevel, Tool, ToolArgs, ToolCategory, ToolError, ToolResult};
+use crate::state::{session_path, ToolState};
+use anyhow::Result;
+use std::fs;
+use std::path::{Path, PathBuf};
+use std::sync::{Arc, Mutex};
+
+/// Template file names, in the order they are looked for
+pub const TEMPLATE_NAMES: [&str; 5] = [
+    ".env.example",
+    ".env.sample",
+    ".env.template",
+    ".env.dist",
+    ".env.defaults",
+];
+
+/// Value `env_sync` writes for secret-looking keys unless told otherwise
+pub const DEFAULT_PLACEHOLDER: &str = "CHANGE_ME";
+
+/// Key fragments marking a value as a secret, which `env_sync` never copies from the
+/// template
+const SECRET_MARKERS: [&str; 9] = [
+    "SECRET",
+    "TOKEN",
+    "PASSWORD",
+    "PASSWD",
+    "PASS",
+    "KEY",
+    "CREDENTIAL",
+    "PRIVATE",
+    "AUTH",
+];
+
+/// One `KEY=value` assignment of an environment file
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct EnvEntry {
+    pub key: String,
+    /// Unquoted value; never reported by the tools
+    pub value: String,
+    /// Comment lines directly above the assignment, without `#`
+    pub description: Option<String>,
+    /// 1-based line of the assignment
+    pub line: usize,
+}
+
+/// Parse the assignments of a dotenv file
+///
+/// Accepts `export KEY=value`, quoted values (double-quoted ones may span lines) and
+/// trailing ` # comments` after unquoted values. Other lines are skipped.
+pub fn parse_env(text: &str) -> Vec<EnvEntry> {
+    let mut entries = Vec::new();
+    let mut comments: Vec<String> = Vec::new();
+    let mut lines = text.lines().enumerate();
+    while let Some((index, line)) = lines.next() {
+        let trimmed = line.trim();
+        if trimmed.is_empty() {
+            comments.clear();
+            continue;
+        }
+        if let Some(comment) = trimmed.strip_prefix('#') {
+            comments.push(comment.trim().to_string());
+            continue;
+        }
+        let assignment = trimmed.strip_prefix("export ").unwrap_or(trimmed);
+        let Some((key, raw)) = assignment.split_once('=') else {
+            comments.clear();
+            continue;
+        };
+        let key = key.trim();
+        if key.is_empty()
+            || !key
+                .chars()
+                .all(|c| c.is_ascii_alphanumeric() || c == '_' || c == '.')
+        {
+            comments.clear();
+            continue;
+        }
+        let raw = raw.trim();
+        let value = match raw.chars().next() {
+            Some(quote @ ('"' | '\'' | '`')) => {
+                let mut value = raw[1..].to_string();
+                // A double-quoted value runs until its closing quote, possibly lines later
+                while !value.contains(quote) && quote == '"' {
+                    match lines.next() {
+                        Some((_, next)) => {
+                            value.push('\n');
+                            value.push_str(next);
+                        }
+                        None => break,
+                    }
+                }
+                value.split(quote).next().unwrap_or_default().to_string()
+            }
+            _ => raw
+                .split(" #")
+                .next()
+                .unwrap_or_default()
+                .trim()
+                .to_string(),
+        };
+        entries.push(EnvEntry {
+            key: key.to_string(),
+            value,
+            description: (!comments.is_empty()).then(|| comments.join(" ")),
+            line: index + 1,
+        });
+        comments.clear();
+    }
+    entries
+}
+
+/// `text` with every assigned value replaced by `<redacted>`, for diffs of env files
+pub fn redact_env(text: &str) -> String {
+    let mut redacted: String = text
+        .lines()
+        .map(|line| {
+            let trimmed = line.trim_start();
+            if trimmed.starts_with('#') {
+                return line.to_string();
+            }
+            match line.split_once('=') {
+                Some((key, value)) if !value.trim().is_empty() => format!("{}=<redacted>", key),
+                _ => line.to_string(),
+            }
+        })
+        .collect::<Vec<_>>()
+        .join("\n");
+    if text.ends_with('\n') {
+        redacted.push('\n');
+    }
+    redacted
+}
+
+/// Whether `key` names a secret
+pub fn is_secret_key(key: &str) -> bool {
+    let key = key.to_uppercase();
+    key.split(['_', '.'])
+        .any(|part| SECRET_MARKERS.contains(&part))
+        || ["SECRET", "TOKEN", "PASSWORD"]
+            .iter()
+            .any(|marker| key.contains(marker))
+}
+
+/// Key comparison between a template and an environment file
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct EnvDiff {
+    /// Template keys `.env` does not set
+    pub missing: Vec<String>,
+    /// Template keys `.env` sets to an empty value
+    pub empty: Vec<String>,
+    /// Keys of `.env` the template does not list
+    pub extra: Vec<String>,
+}
+
+impl EnvDiff {
+    pub fn new(template: &[EnvEntry], env: &[EnvEntry]) -> Self {
+        let mut diff = Self::default();
+        for entry in template {
+            if diff.missing.contains(&entry.key) || diff.empty.contains(&entry.key) {
+                continue;
+            }
+            // The last assignment of a key is the one that applies
+            match env.iter().rev().find(|e| e.key == entry.key) {
+                None => diff.missing.push(entry.key.clone()),
+                Some(set) if set.value.is_empty() => diff.empty.push(entry.key.clone()),
+                Some(_) => {}
+            }
+        }
+        for entry in env {
+            if !template.iter().any(|t| t.key == entry.key) && !diff.extra.contains(&entry.key) {
+                diff.extra.push(entry.key.clone());
+            }
+        }
+        diff
+    }
+}
+
+/// Template and environment file paths of a call: `example` and `env` are relative to
+/// the directory (positional or `path`, default the working directory)
+fn resolve_files(
+    args: &ToolArgs,
+    state: &Arc<Mutex<ToolState>>,
+) -> Result<Result<(PathBuf, PathBuf), ToolResult>> {
+    let dir = match args.get_named_arg("path").or_else(|| args.get_arg(0)) {
+        Some(path) => session_path(state, path),
+        None => {
+            let state_guard = state
+                .lock()
+                .map_err(|e| anyhow::anyhow!("Failed to lock state: {}", e))?;
+            state_guard.working_directory.clone()
+        }
+    };
+    if !dir.is_dir() {
+        return Ok(Err(crate::utils::suggest::path_not_found(
+            format!("Directory {} not found", dir.display()),
+            &dir,
+        )));
+    }
+    let template = match args.get_named_arg("example") {
+        Some(example) => dir.join(example),
+        None => match TEMPLATE_NAMES
+            .iter()
+            .map(|name| dir.join(name))
+            .find(|path| path.is_file())
+        {
+            Some(template) => template,
+            None => {
+                return Ok(Err(ToolResult::error(format!(
+                    "No env template in {} (looked for {}). Pass example=<file>",
+                    dir.display(),
+                    TEMPLATE_NAMES.join(", ")
+                ))))
+            }
+        },
+    };
+    if !template.is_file() {
+        return Ok(Err(crate::utils::suggest::path_not_found(
+            format!("Template {} not found", template.display()),
+            &template,
+        )));
+    }
+    let env = dir.join(args.get_named_arg("env").map_or(".env", String::as_str));
+    Ok(Ok((template, env)))
+}
+
+/// The directory of a call and the `example` and `env` files it names, for the
+/// workspace guard, which resolves a relative directory like other paths
+fn accessed_files(args: &ToolArgs) -> Vec<PathBuf> {
+    let dir = PathBuf::from(
+        args.get_named_arg("path")
+            .or_else(|| args.get_arg(0))
+            .map_or(".", String::as_str),
+    );
+    let mut paths: Vec<PathBuf> = ["example", "env"]
+        .iter()
+        .filter_map(|name| args.get_named_arg(name))
+        .map(|file| dir.join(file))
+        .collect();
+    paths.push(dir);
+    paths
+}
+
+fn file_name(path: &Path) -> String {
+    path.file_name()
+        .map(|name| name.to_string_lossy().to_string())
+        .unwrap_or_else(|| path.display().to_string())
+}
+
+/// Tool comparing `.env` with its template by key, without revealing values
+pub struct EnvDiffTool {
+    name: String,
+}
+
+impl EnvDiffTool {
+    pub fn new() -> Self {
+        Self {
+            name: "env_diff".to_string(),
+        }
+    }
+}
+
+impl Default for EnvDiffTool {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl Tool for EnvDiffTool {
+    fn name(&self) -> &str {
+        &self.name
+    }
+
+    fn description(&self) -> &str {
+        "List the keys of .env.example (with their comments) and compare them with .env: keys missing or empty in .env and keys only .env has. Values are never shown"
+    }
+
+    fn signature(&self) -> &str {
+        "env_diff(path?: str, example?: str, env?: str)"
+    }
+
+    fn danger_level(&self) -> DangerLevel {
+        DangerLevel::ReadOnly
+    }
+
+    fn validate_args(&self, _args: &ToolArgs) -> Result<(), ToolError> {
+        Ok(())
+    }
+
+    fn accessed_paths(&self, args: &ToolArgs) -> Vec<PathBuf> {
+        accessed_files(args)
+    }
+
+    fn execute(&mut self, args: &ToolArgs, state: &Arc<Mutex<ToolState>>) -> Result<ToolResult> {
+        let (template_path, env_path) = match resolve_files(args, state)? {
+            Ok(files) => files,
+            Err(result) => return Ok(result),
+        };
+        let template = parse_env(&fs::read_to_string(&template_path)?);
+        let env_exists = env_path.is_file();
+        let env = if env_exists {
+            parse_env(&fs::read_to_string(&env_path)?)
+        } else {
+            Vec::new()
+        };
+        let diff = EnvDiff::new(&template, &env);
+        let (template_name, env_name) = (file_name(&template_path), file_name(&env_path));
+
+        let mut message = format!("{}: {} keys", template_name, template.len());
+        if env_exists {
+            message.push_str(&format!("; {}: {} keys", env_name, env.len()));
+        } else {
+            message.push_str(&format!("; {} does not exist", env_name));
+        }
+        let describe = |key: &String| match template
+            .iter()
+            .find(|entry| &entry.key == key)
+            .and_then(|entry| entry.description.as_ref())
+        {
+            Some(description) => format!("  {} - {}", key, description),
+            None => format!("  {}", key),
+        };
+        if !diff.missing.is_empty() {
+            message.push_str(&format!(
+                "\nMissing from {} ({}):\n{}",
+                env_name,
+                diff.missing.len(),
+                diff.missing
+                    .iter()
+                    .map(describe)
+                    .collect::<Vec<_>>()
+                    .join("\n")
+            ));
+        }
+        if !diff.empty.is_empty() {
+            message.push_str(&format!(
+                "\nEmpty in {} ({}):\n{}",
+                env_name,
+                diff.empty.len(),
+                diff.empty
+                    .iter()
+                    .map(describe)
+                    .collect::<Vec<_>>()
+                    .join("\n")
+            ));
+        }
+        if !diff.extra.is_empty() {
+            message.push_str(&format!(
+                "\nNot in {} ({}): {}",
+                template_name,
+                diff.extra.len(),
+                diff.extra.join(", ")
+            ));
+        }
+        if diff.missing.is_empty() && diff.empty.is_empty() && env_exists {
+            message.push_str(&format!("\n{} sets every template key", env_name));
+        } else if !diff.missing.is_empty() {
+            message.push_str("\nRun env_sync to add the missing keys with placeholder values");
+        }
+
+        Ok(ToolResult::success_with_data(
+            message,
+            serde_json::json!({
+                "template": template_path.display().to_string(),
+                "env": env_path.display().to_string(),
+                "env_exists": env_exists,
+                "keys": template.iter().map(|entry| serde_json::json!({
+                    "key": entry.key,
+                    "description": entry.description,
+                    "secret": is_secret_key(&entry.key),
+                    "in_env": !diff.missing.contains(&entry.key),
+                })).collect::<Vec<_>>(),
+                "missing": diff.missing,
+                "empty": diff.empty,
+                "extra": diff.extra,
+            }),
+        ))
+    }
+
+    fn get_parameters_schema(&self) -> serde_json::Value {
+        serde_json::json!({
+            "type": "object",
+            "properties": {
+                "path": {
+                    "type": "string",
+                    "description": "Project directory (defaults to the working directory)"
+                },
+                "example": {
+                    "type": "string",
+                    "description": "Template file relative to path (defaults to the first of .env.example, .env.sample, .env.template, .env.dist, .env.defaults)"
+                },
+                "env": {
+                    "type": "string",
+                    "description": "Environment file relative to path",
+                    "default": ".env"
+                }
+            },
+            "required": []
+        })
+    }
+}
+
+/// Tool adding the template keys `.env` lacks, with placeholder values
+pub struct EnvSyncTool {
+    name: String,
+}
+
+impl EnvSyncTool {
+    pub fn new() -> Self {
+        Self {
+            name: "env_sync".to_string(),
+        }
+    }
+}
+
+impl Default for EnvSyncTool {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl Tool for EnvSyncTool {
+    fn name(&self) -> &str {
+        &self.name
+    }
+
+    fn description(&self) -> &str {
+        "Append the keys of .env.example that .env lacks (creating .env if needed), with their comments. Secret-looking keys get a placeholder, others the template's default; existing values are never changed or shown"
+    }
+
+    fn signature(&self) -> &str {
+        "env_sync(path?: str, example?: str, env?: str, placeholder?: str, allow_protected?: bool)"
+    }
+
+    fn category(&self) -> ToolCategory {
+        ToolCategory::Editing
+    }
+
+    fn danger_level(&self) -> DangerLevel {
+        DangerLevel::ModifiesFiles
+    }
+
+    fn validate_args(&self, args: &ToolArgs) -> Result<(), ToolError> {
+        if let Some(placeholder) = args.get_named_arg("placeholder") {
+            if placeholder.contains('\n') {
+                return Err(ToolError::InvalidArgs {
+                    message: "placeholder must be a single line".to_string(),
+                });
+            }
+        }
+        Ok(())
+    }
+
+    fn accessed_paths(&self, args: &ToolArgs) -> Vec<PathBuf> {
+        accessed_files(args)
+    }
+
+    fn execute(&mut self, args: &ToolArgs, state: &Arc<Mutex<ToolState>>) -> Result<ToolResult> {
+        let (template_path, env_path) = match resolve_files(args, state)? {
+            Ok(files) => files,
+            Err(result) => return Ok(result),
+        };
+        let allow_protected = args
+            .get_named_arg(protection::OVERRIDE_PARAM)
+            .map(String::as_str)
+            == Some("true");
+        if let Some(refused) = protection::check(state, &self.name, &env_path, allow_protected)? {
+            return Ok(refused);
+        }
+        let placeholder = args
+            .get_named_arg("placeholder")
+            .map_or(DEFAULT_PLACEHOLDER, String::as_str);
+
+        let template = parse_env(&fs::read_to_string(&template_path)?);
+        let existing = fs::read_to_string(&env_path).unwrap_or_default();
+        let diff = EnvDiff::new(&template, &parse_env(&existing));
+        let env_name = file_name(&env_path);
+        if diff.missing.is_empty() {
+            return Ok(ToolResult::success_with_data(
+                format!(
+                    "{} already has every key of {}",
+                    env_name,
+                    file_name(&template_path)
+                ),
+                serde_json::json!({"env": env_path.display().to_string(), "added": []}),
+            ));
+        }
+
+        let created = !env_path.exists();
+        let mut contents = existing.clone();
+        if !contents.is_empty() && !contents.ends_with('\n') {
+            contents.push('\n');
+        }
+        if !contents.is_empty() {
+            contents.push('\n');
+        }
+        contents.push_str(&format!("# Added from {}\n", file_name(&template_path)));
+        let mut added = Vec::new();
+        for key in &diff.missing {
+            let Some(entry) = template.iter().find(|entry| &entry.key == key) else {
+                continue;
+            };
+            if let Some(description) = &entry.description {
+                contents.push_str(&format!("# {}\n", description));
+            }
+            let (value, source) = if is_secret_key(key) || entry.value.is_empty() {
+                (placeholder.to_string(), "placeholder")
+            } else {
+                (entry.value.clone(), "template")
+            };
+            let value = if value.contains(char::is_whitespace) || value.contains('#') {
+                format!("\"{}\"", value.replace('"', "\\\""))
+            } else {
+                value
+            };
+            contents.push_str(&format!("{}={}\n", key, value));
+            added.push(serde_json::json!({"key": key, "value_from": source}));
+        }
+
+        writer::write_file_redacted(state, &env_path, &contents, redact_env)
+            .map_err(|e| anyhow::anyhow!("Failed to write {}: {}", env_path.display(), e))?;
+        if let Ok(mut state_guard) = state.lock() {
+            state_guard.push_history(format!(
+                "env_sync: added {} to {}",
+                diff.missing.join(", "),
+                env_path.display()
+            ));
+        }
+
+        let placeholders: Vec<&String> = diff
+            .missing
+            .iter()
+            .filter(|key| {
+                added
+                    .iter()
+                    .any(|a| a["key"] == **key && a["value_from"] == "placeholder")
+            })
+            .collect();
+        let mut message = format!(
+            "Added {} key(s) to {}: {}",
+            added.len(),
+            env_name,
+            diff.missing.join(", ")
+        );
+        if !placeholders.is_empty() {
+            message.push_str(&format!(
+                "\nSet real values for the placeholders: {}",
+                placeholders
+                    .iter()
+                    .map(|key| key.as_str())
+                    .collect::<Vec<_>>()
+                    .join(", ")
+            ));
+        }
+        Ok(ToolResult::success_with_data(
+            message,
+            serde_json::json!({
+                "env": env_path.display().to_string(),
+                "created": created,
+                "added": added,
+            }),
+        ))
+    }
+
+    fn get_parameters_schema(&self) -> serde_json::Value {
+        serde_json::json!({
+            "type": "object",
+            "properties": {
+                "path": {
+                    "type": "string",
+                    "description": "Project directory (defaults to the working directory)"
+                },
+                "example": {
+                    "type": "string",
+                    "description": "Template file relative to path (defaults to the first of .env.example, .env.sample, .env.template, .env.dist, .env.defaults)"
+                },
+                "env": {
+                    "type": "string",
+                    "description": "Environment file relative to path",
+                    "default": ".env"
+                },
+                "placeholder": {
+                    "type": "string",
+                    "description": "Value written for secret-looking keys and keys without a template default",
+                    "default": DEFAULT_PLACEHOLDER
+                },
+                "allow_protected": {
+                    "type": "boolean",
+                    "description": "Write the env file even if it matches a protected path",
+                    "default": false
+                }
+            },
+            "required": []
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use tempfile::TempDir;
+
+    #[test]
+    fn test_parse_and_redact_env() {
+        let text = "# Database\nexport DATABASE_URL=\"postgres://u:p@h/db\"\nPORT=3000 # default\n\nMULTI=\"a\nb\"\nnot an assignment\nEMPTY=\n";
+        let entries = parse_env(text);
+        let keys: Vec<&str> = entries.iter().map(|e| e.key.as_str()).collect();
+        assert_eq!(keys, vec!["DATABASE_URL", "PORT", "MULTI", "EMPTY"]);
+        assert_eq!(entries[0].description.as_deref(), Some("Database"));
+        assert_eq!(entries[1].value, "3000");
+        assert_eq!(entries[2].value, "a\nb");
+        assert_eq!(
+            redact_env("# c\nTOKEN=abc\nEMPTY=\n"),
+            "# c\nTOKEN=<redacted>\nEMPTY=\n"
+        );
+        assert!(is_secret_key("STRIPE_API_KEY"));
+        assert!(is_secret_key("db_password"));
+        assert!(!is_secret_key("PORT"));
+        assert!(!is_secret_key("KEYBOARD_LAYOUT"));
+    }
+
+    #[test]
+    fn test_env_diff_and_sync() {
+        let temp_dir = TempDir::new().unwrap();
+        let dir = temp_dir.path();
+        fs::write(
+            dir.join(".env.example"),
+            "# Stripe secret\nSTRIPE_SECRET_KEY=sk_test_xxx\nPORT=3000\nDEBUG=\n",
+        )
+        .unwrap();
+        fs::write(dir.join(".env"), "DEBUG=\nLOCAL_ONLY=hunter2").unwrap();
+        let state = Arc::new(Mutex::new(ToolState::new()));
+        let args = ToolArgs::from_args(&[dir.to_str().unwrap()]);
+
+        let result = EnvDiffTool::new().execute(&args, &state).unwrap();
+        assert!(result.success, "{}", result.message);
+        assert_eq!(
+            result.message,
+            ".env.example: 3 keys; .env: 2 keys\nMissing from .env (2):\n  STRIPE_SECRET_KEY - Stripe secret\n  PORT\nEmpty in .env (1):\n  DEBUG\nNot in .env.example (1): LOCAL_ONLY\nRun env_sync to add the missing keys with placeholder values"
+        );
+        assert!(!result.message.contains("hunter2"));
+
+        let result = EnvSyncTool::new().execute(&args, &state).unwrap();
+        assert!(result.success, "{}", result.message);
+        assert!(!result.message.contains("sk_test"));
+        assert_eq!(
+            fs::read_to_string(dir.join(".env")).unwrap(),
+            "DEBUG=\nLOCAL_ONLY=hunter2\n\n# Added from .env.example\n# Stripe secret\nSTRIPE_SECRET_KEY=CHANGE_ME\nPORT=3000\n"
+        );
+        let touched = state.lock().unwrap().touched_paths.clone();
+        let diff = touched.last().unwrap().diff.clone().unwrap();
+        assert!(diff.contains("+STRIPE_SECRET_KEY=<redacted>"));
+        assert!(!diff.contains("hunter2"));
+
+        let result = EnvSyncTool::new().execute(&args, &state).unwrap();
+        assert_eq!(result.message, ".env already has every key of .env.example");
+    }
+}
@@ -0,0 +1,352 @@
+//! Pretty-printing and minifying JSON files
+//!
+//! `format_json` reindents a file token by token instead of parsing it into a
+//! `serde_json::Value` and serializing it again, so member order, duplicate keys, number
+//! spellings such as `1.50` or `1e3` and string escapes come out exactly as they went
+//! in. The file is validated first so that invalid JSON is reported with its line and
+//! column and never rewritten.
+
+use super::protection;
+use super::writer;
+use crate::core::{DangerLevel, Tool, ToolArgs, ToolCategory, ToolError, ToolResult};
+use crate::state::{session_path, ToolState};
+use crate::utils::suggest::path_not_found;
+use anyhow::Result;
+use std::fs;
+use std::sync::{Arc, Mutex};
+
+/// How `format_json` lays out a document
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum JsonStyle {
+    /// One member or element per line, nested levels indented by the given string
+    Pretty(String),
+    /// No whitespace between tokens
+    Minified,
+}
+
+impl JsonStyle {
+    /// Parse the `indent` argument: a number of spaces from 0 to 8, or `tab`
+    pub fn from_indent(indent: &str) -> Option<Self> {
+        if indent == "tab" || indent == "\\t" || indent == "\t" {
+            return Some(Self::Pretty("\t".to_string()));
+        }
+        match indent.parse::<usize>().ok()? {
+            0 => Some(Self::Minified),
+            n @ 1..=8 => Some(Self::Pretty(" ".repeat(n))),
+            _ => None,
+        }
+    }
+}
+
+/// Reformat the JSON document `text` in `style`
+///
+/// Fails with serde_json's message, including the line and column, when `text` is not
+/// valid JSON.
+pub fn format_json(text: &str, style: &JsonStyle) -> Result<String, serde_json::Error> {
+    serde_json::from_str::<serde::de::IgnoredAny>(text)?;
+    let indent = match style {
+        JsonStyle::Pretty(indent) => Some(indent.as_str()),
+        JsonStyle::Minified => None,
+    };
+    let newline = |out: &mut String, depth: usize| {
+        if let Some(indent) = indent {
+            out.push('\n');
+            out.push_str(&indent.repeat(depth));
+        }
+    };
+
+    let chars: Vec<char> = text.chars().collect();
+    let mut out = String::with_capacity(text.len());
+    let mut depth = 0;
+    let mut i = 0;
+    while i < chars.len() {
+        let c = chars[i];
+        match c {
+            '"' => {
+                out.push(c);
+                i += 1;
+                while i < chars.len() {
+                    out.push(chars[i]);
+                    match chars[i] {
+                        '\\' => {
+                            i += 1;
+                            if let Some(&escaped) = chars.get(i) {
+                                out.push(escaped);
+                            }
+                        }
+                        '"' => break,
+                        _ => {}
+                    }
+                    i += 1;
+                }
+            }
+            '{' | '[' => {
+                let close = if c == '{' { '}' } else { ']' };
+                let next = chars[i + 1..].iter().position(|c| !c.is_whitespace());
+                if next.is_some_and(|offset| chars[i + 1 + offset] == close) {
+                    out.push(c);
+                    out.push(close);
+                    i += 1 + next.unwrap_or(0);
+                } else {
+                    out.push(c);
+                    depth += 1;
+                    newline(&mut out, depth);
+                }
+            }
+            '}' | ']' => {
+                depth -= 1;
+                newline(&mut out, depth);
+                out.push(c);
+            }
+            ',' => {
+                out.push(c);
+                newline(&mut out, depth);
+            }
+            ':' => {
+                out.push(c);
+                if indent.is_some() {
+                    out.push(' ');
+                }
+            }
+            c if c.is_whitespace() => {}
+            c => out.push(c),
+        }
+        i += 1;
+    }
+    Ok(out)
+}
+
+/// Tool pretty-printing or minifying a JSON file in place
+pub struct FormatJsonTool {
+    name: String,
+}
+
+impl FormatJsonTool {
+    pub fn new() -> Self {
+        Self {
+            name: "format_json".to_string(),
+        }
+    }
+
+    fn style(args: &ToolArgs) -> Result<JsonStyle, ToolError> {
+        if args.get_named_arg("minify").map(String::as_str) == Some("true") {
+            return Ok(JsonStyle::Minified);
+        }
+        let indent = args.get_named_arg("indent").map_or("2", String::as_str);
+        JsonStyle::from_indent(indent).ok_or_else(|| ToolError::InvalidArgs {
+            message: format!(
+                "Invalid indent '{}': expected a number of spaces from 0 to 8, or tab",
+                indent
+            ),
+        })
+    }
+}
+
+impl Default for FormatJsonTool {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl Tool for FormatJsonTool {
+    fn name(&self) -> &str {
+        &self.name
+    }
+
+    fn description(&self) -> &str {
+        "Pretty-print (indent defaults to 2 spaces) or minify a JSON file in place, keeping member order, numbers and escapes as written. Invalid JSON is reported with its position and left untouched; check: true only reports whether the file is formatted"
+    }
+
+    fn signature(&self) -> &str {
+        "format_json(path: str, indent?: int | tab, minify?: bool, check?: bool, allow_protected?: bool)"
+    }
+
+    fn category(&self) -> ToolCategory {
+        ToolCategory::Editing
+    }
+
+    fn danger_level(&self) -> DangerLevel {
+        DangerLevel::ModifiesFiles
+    }
+
+    fn validate_args(&self, args: &ToolArgs) -> Result<(), ToolError> {
+        if args
+            .get_named_arg("path")
+            .or_else(|| args.get_arg(0))
+            .is_none()
+        {
+            return Err(ToolError::InvalidArgs {
+                message: "Missing required parameter: path".to_string(),
+            });
+        }
+        Self::style(args).map(|_| ())
+    }
+
+    fn execute(&mut self, args: &ToolArgs, state: &Arc<Mutex<ToolState>>) -> Result<ToolResult> {
+        let Some(path_str) = args.get_named_arg("path").or_else(|| args.get_arg(0)) else {
+            return Ok(ToolResult::error("Missing required parameter: path"));
+        };
+        let style = Self::style(args).map_err(|e| anyhow::anyhow!("{}", e))?;
+        let check = args.get_named_arg("check").map(String::as_str) == Some("true");
+        let path = session_path(state, path_str);
+        if !path.is_file() {
+            return Ok(path_not_found(
+                format!("File not found: {}", path.display()),
+                &path,
+            ));
+        }
+
+        let original = fs::read_to_string(&path)?;
+        let mut formatted = match format_json(&original, &style) {
+            Ok(formatted) => formatted,
+            Err(e) => {
+                return Ok(ToolResult::error_with_data(
+                    format!("{} is not valid JSON: {}", path.display(), e),
+                    serde_json::json!({
+                        "path": path.display().to_string(),
+                        "line": e.line(),
+                        "column": e.column(),
+                    }),
+                ))
+            }
+        };
+        if matches!(style, JsonStyle::Pretty(_)) || original.ends_with('\n') {
+            formatted.push('\n');
+        }
+        let changed = formatted != original;
+        let data = serde_json::json!({
+            "path": path.display().to_string(),
+            "changed": changed,
+            "bytes_before": original.len(),
+            "bytes_after": formatted.len(),
+        });
+
+        if check || !changed {
+            let message = if changed {
+                format!("{} is not formatted", path.display())
+            } else {
+                format!("{} is already formatted", path.display())
+            };
+            return Ok(ToolResult::success_with_data(message, data));
+        }
+
+        let allow_protected = args
+            .get_named_arg(protection::OVERRIDE_PARAM)
+            .map(String::as_str)
+            == Some("true");
+        if let Some(refused) = protection::check(state, &self.name, &path, allow_protected)? {
+            return Ok(refused);
+        }
+        writer::write_file(state, &path, &formatted)
+            .map_err(|e| anyhow::anyhow!("Failed to write {}: {}", path.display(), e))?;
+        if let Ok(mut state_guard) = state.lock() {
+            state_guard.push_history(format!("format_json: {}", path.display()));
+        }
+        Ok(ToolResult::success_with_data(
+            format!(
+                "{} {} ({} -> {} bytes)",
+                if style == JsonStyle::Minified {
+                    "Minified"
+                } else {
+                    "Formatted"
+                },
+                path.display(),
+                original.len(),
+                formatted.len()
+            ),
+            data,
+        ))
+    }
+
+    fn get_parameters_schema(&self) -> serde_json::Value {
+        serde_json::json!({
+            "type": "object",
+            "properties": {
+                "path": {
+                    "type": "string",
+                    "description": "JSON file to format"
+                },
+                "indent": {
+                    "type": ["integer", "string"],
+                    "description": "Spaces per level from 1 to 8 (0 minifies), or \"tab\"; defaults to 2"
+                },
+                "minify": {
+                    "type": "boolean",
+                    "description": "Remove all whitespace between tokens"
+                },
+                "check": {
+                    "type": "boolean",
+                    "description": "Only report whether the file is already formatted"
+                },
+                "allow_protected": {
+                    "type": "boolean",
+                    "description": "Rewrite the file even if it matches a protected path"
+                }
+            },
+            "required": ["path"]
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use tempfile::TempDir;
+
+    #[test]
+    fn test_format_json_keeps_tokens_as_written() {
+        let text = r#"{"b": 1.50, "a": [1e3, {}, [ ]], "a": "x\"y,{", "c": {"d": null}}"#;
+        assert_eq!(
+            format_json(text, &JsonStyle::Pretty("  ".to_string())).unwrap(),
+            "{\n  \"b\": 1.50,\n  \"a\": [\n    1e3,\n    {},\n    []\n  ],\n  \"a\": \"x\\\"y,{\",\n  \"c\": {\n    \"d\": null\n  }\n}"
+        );
+        assert_eq!(
+            format_json(text, &JsonStyle::Minified).unwrap(),
+            r#"{"b":1.50,"a":[1e3,{},[]],"a":"x\"y,{","c":{"d":null}}"#
+        );
+        let error = format_json("{\n  \"a\": 1,\n}", &JsonStyle::Minified).unwrap_err();
+        assert_eq!(error.line(), 3);
+    }
+
+    #[test]
+    fn test_format_json_tool_rewrites_and_checks() {
+        let temp_dir = TempDir::new().unwrap();
+        let path = temp_dir.path().join("package.json");
+        fs::write(&path, "{\"name\":\"x\",\"files\":[\"a\",\"b\"]}").unwrap();
+        let path_arg = path.to_string_lossy().to_string();
+        let state = Arc::new(Mutex::new(ToolState::new()));
+        let mut tool = FormatJsonTool::new();
+
+        let args = ToolArgs::with_named_args(
+            vec![],
+            [
+                ("path".to_string(), path_arg.clone()),
+                ("check".to_string(), "true".to_string()),
+            ]
+            .into_iter()
+            .collect(),
+        );
+        let result = tool.execute(&args, &state).unwrap();
+        assert!(result.message.contains("is not formatted"));
+
+        let result = tool
+            .execute(&ToolArgs::from_args(&[&path_arg]), &state)
+            .unwrap();
+        assert!(result.success, "{}", result.message);
+        assert_eq!(
+            fs::read_to_string(&path).unwrap(),
+            "{\n  \"name\": \"x\",\n  \"files\": [\n    \"a\",\n    \"b\"\n  ]\n}\n"
+        );
+        let result = tool
+            .execute(&ToolArgs::from_args(&[&path_arg]), &state)
+            .unwrap();
+        assert!(result.message.contains("already formatted"));
+
+        fs::write(&path, "{\"a\": }").unwrap();
+        let result = tool
+            .execute(&ToolArgs::from_args(&[&path_arg]), &state)
+            .unwrap();
+        assert!(!result.success);
+        assert_eq!(fs::read_to_string(&path).unwrap(), "{\"a\": }");
+    }
+}
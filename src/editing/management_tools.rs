@@ -3,8 +3,13 @@
 //! This module provides tools for file and directory management operations
 //! like delete, move, and copy with simple interfaces.
 
-use crate::core::{Tool, ToolArgs, ToolError, ToolResult};
-use crate::state::ToolState;
+use super::bool_param;
+use super::{protection, trash, writer};
+use crate::core::{DangerLevel, Tool, ToolArgs, ToolCategory, ToolError, ToolResult};
+use crate::state::journal::Operation;
+use crate::state::{resolve_path, session_path, ToolState};
+use crate::stream::{ResultStream, StreamControl};
+use crate::utils::suggest::path_not_found;
 use anyhow::Result;
 use std::fs;
 use std::path::PathBuf;
@@ -48,6 +53,12 @@ impl DeletePathTool {
                 }
             }
 
+            if args.len() >= 3 {
+                if let Ok(permanent) = args.get_arg(2).unwrap().parse::<bool>() {
+                    params.insert("permanent".to_string(), serde_json::Value::Bool(permanent));
+                }
+            }
+
             return Ok(serde_json::Value::Object(params));
         }
 
@@ -63,11 +74,19 @@ impl Tool for DeletePathTool {
     }
 
     fn description(&self) -> &str {
-        "Delete a file or directory"
+        "Delete a file or directory by moving it to the session trash (.cats/trash/<timestamp>/); permanent=true removes it for good"
     }
 
     fn signature(&self) -> &str {
-        "delete_path(path: str, recursive?: bool)"
+        "delete_path(path: str, recursive?: bool, permanent?: bool, allow_protected?: bool)"
+    }
+
+    fn category(&self) -> ToolCategory {
+        ToolCategory::Editing
+    }
+
+    fn danger_level(&self) -> DangerLevel {
+        DangerLevel::Destructive
     }
 
     fn validate_args(&self, args: &ToolArgs) -> Result<(), ToolError> {
@@ -97,64 +116,59 @@ impl Tool for DeletePathTool {
             .and_then(|v| v.as_str())
             .ok_or_else(|| anyhow::anyhow!("Invalid path parameter"))?;
 
-        let recursive = obj
-            .get("recursive")
-            .and_then(|v| v.as_bool())
-            .unwrap_or(false);
+        let recursive = bool_param(obj, "recursive").unwrap_or(false);
+        let permanent = bool_param(obj, "permanent").unwrap_or(false);
 
-        let path = PathBuf::from(path_str);
+        let path = session_path(state, path_str);
 
         // Check if path exists
         if !path.exists() {
-            return Ok(ToolResult::error(format!(
-                "Path not found: {}",
-                path.display()
-            )));
+            return Ok(path_not_found(
+                format!("Path not found: {}", path.display()),
+                &path,
+            ));
         }
 
         let is_dir = path.is_dir();
         let is_file = path.is_file();
 
-        // Safety check for important directories
-        let path_name = path.file_name().and_then(|n| n.to_str()).unwrap_or("");
-
-        // Use centralized filter configuration for protected directories instead of a hardcoded list.
-        // Always protect "src" by default, and also protect any directory listed in the search filtering exclude_dirs.
-        let filter = crate::search::filtering::ConfigurableFilter::new(None);
-        let mut protected = false;
-
-        if path_name == "src" {
-            protected = true;
-        }
-
-        if !protected {
-            if let Some(ex_dirs) = &filter.config.exclude_dirs {
-                if ex_dirs.iter().any(|d| d == path_name) {
-                    protected = true;
-                }
-            }
+        let allow_protected = protection::override_requested(obj);
+        if let Some(refused) = protection::check(state, &self.name, &path, allow_protected)? {
+            return Ok(refused);
         }
 
-        if is_dir && protected {
+        if is_dir && !recursive && fs::read_dir(&path).map(|mut d| d.next().is_some())? {
             return Ok(ToolResult::error(format!(
-                "Safety check: Refusing to delete important directory '{}'. Use recursive=true explicitly if needed.",
-                path_name
+                "Directory {} is not empty. Use recursive=true for non-empty directories.",
+                path.display()
             )));
         }
 
+        let working_dir = {
+            let state_guard = state
+                .lock()
+                .map_err(|e| anyhow::anyhow!("Failed to lock state: {}", e))?;
+            state_guard.working_directory.clone()
+        };
+        // Deleting from the trash itself always removes for good
+        let permanent = permanent || trash::is_in_trash(&working_dir, &path);
+
         // Perform the deletion
-        if is_file {
-            fs::remove_file(&path).map_err(|e| anyhow::anyhow!("Failed to delete file: {}", e))?;
-        } else if is_dir {
-            if recursive {
-                fs::remove_dir_all(&path)
+        let trash_path = if permanent {
+            if is_file {
+                writer::remove_file(state, &path)
+                    .map_err(|e| anyhow::anyhow!("Failed to delete file: {}", e))?;
+            } else if is_dir {
+                writer::remove_dir(state, &path, recursive)
                     .map_err(|e| anyhow::anyhow!("Failed to delete directory: {}", e))?;
-            } else {
-                // Try to remove empty directory
-                fs::remove_dir(&path)
-                    .map_err(|e| anyhow::anyhow!("Failed to delete directory (not empty?): {}. Use recursive=true for non-empty directories.", e))?;
             }
-        }
+            None
+        } else {
+            Some(
+                trash::move_to_trash(state, &path)
+                    .map_err(|e| anyhow::anyhow!("Failed to move path to trash: {}", e))?,
+            )
+        };
 
         // Update state
         {
@@ -162,7 +176,7 @@ impl Tool for DeletePathTool {
                 .lock()
                 .map_err(|e| anyhow::anyhow!("Failed to lock state: {}", e))?;
             // If we deleted the currently open file, clear it from state
-            if state_guard.current_file.as_ref() == Some(&path) {
+            if state_guard.is_current_file(&path) {
                 state_guard.current_file = None;
             }
             state_guard.push_history(format!(
@@ -172,16 +186,25 @@ impl Tool for DeletePathTool {
             ));
         }
 
-        Ok(ToolResult::success_with_data(
-            format!(
-                "Successfully deleted {}: {}",
-                if is_dir { "directory" } else { "file" },
-                path.display()
+        let kind = if is_dir { "directory" } else { "file" };
+        let message = match &trash_path {
+            Some(trash_path) => format!(
+                "Moved {} {} to trash: {} (use purge_trash to free the space)",
+                kind,
+                path.display(),
+                trash_path.display()
             ),
+            None => format!("Permanently deleted {}: {}", kind, path.display()),
+        };
+
+        Ok(ToolResult::success_with_data(
+            message,
             serde_json::json!({
                 "path": path.to_string_lossy(),
-                "type": if is_dir { "directory" } else { "file" },
+                "type": kind,
                 "recursive": recursive,
+                "permanent": permanent,
+                "trash_path": trash_path.map(|p| p.to_string_lossy().to_string()),
                 "deleted": true
             }),
         ))
@@ -199,6 +222,16 @@ impl Tool for DeletePathTool {
                     "type": "boolean",
                     "description": "Whether to delete directories and their contents recursively",
                     "default": false
+                },
+                "permanent": {
+                    "type": "boolean",
+                    "description": "Remove the path for good instead of moving it to the session trash",
+                    "default": false
+                },
+                "allow_protected": {
+                    "type": "boolean",
+                    "description": "Delete even if the path matches a protected glob (recorded in the history)",
+                    "default": false
                 }
             },
             "required": ["path"]
@@ -260,7 +293,15 @@ impl Tool for MovePathTool {
     }
 
     fn signature(&self) -> &str {
-        "move_path(source: str, destination: str)"
+        "move_path(source: str, destination: str, allow_protected?: bool)"
+    }
+
+    fn category(&self) -> ToolCategory {
+        ToolCategory::Editing
+    }
+
+    fn danger_level(&self) -> DangerLevel {
+        DangerLevel::ModifiesFiles
     }
 
     fn validate_args(&self, args: &ToolArgs) -> Result<(), ToolError> {
@@ -301,15 +342,20 @@ impl Tool for MovePathTool {
             .and_then(|v| v.as_str())
             .ok_or_else(|| anyhow::anyhow!("Invalid destination parameter"))?;
 
-        let source = PathBuf::from(source_str);
-        let destination = PathBuf::from(dest_str);
+        let source = session_path(state, source_str);
+        let destination = session_path(state, dest_str);
 
         // Check if source exists
         if !source.exists() {
-            return Ok(ToolResult::error(format!(
-                "Source path not found: {}",
-                source.display()
-            )));
+            return Ok(path_not_found(
+                format!("Source path not found: {}", source.display()),
+                &source,
+            ));
+        }
+
+        let allow_protected = protection::override_requested(obj);
+        if let Some(refused) = protection::check(state, &self.name, &source, allow_protected)? {
+            return Ok(refused);
         }
 
         // Check if destination already exists
@@ -330,7 +376,7 @@ impl Tool for MovePathTool {
         let is_dir = source.is_dir();
 
         // Perform the move
-        fs::rename(&source, &destination)
+        writer::rename(state, &source, &destination)
             .map_err(|e| anyhow::anyhow!("Failed to move path: {}", e))?;
 
         // Update state
@@ -338,10 +384,8 @@ impl Tool for MovePathTool {
             let mut state_guard = state
                 .lock()
                 .map_err(|e| anyhow::anyhow!("Failed to lock state: {}", e))?;
-            // If we moved the currently open file, update the path in state
-            if state_guard.current_file.as_ref() == Some(&source) {
-                state_guard.current_file = Some(destination.clone());
-            }
+            // If we moved an open file, follow it to its new path
+            state_guard.rename_file(&source, &destination);
             state_guard.push_history(format!(
                 "Moved {} from {} to {}",
                 if is_dir { "directory" } else { "file" },
@@ -377,6 +421,11 @@ impl Tool for MovePathTool {
                 "destination": {
                     "type": "string",
                     "description": "Full path to the destination"
+                },
+                "allow_protected": {
+                    "type": "boolean",
+                    "description": "Move even if the source matches a protected glob (recorded in the history)",
+                    "default": false
                 }
             },
             "required": ["source", "destination"]
@@ -448,6 +497,14 @@ impl Tool for CopyPathTool {
         "copy_path(source: str, destination: str, recursive?: bool)"
     }
 
+    fn category(&self) -> ToolCategory {
+        ToolCategory::Editing
+    }
+
+    fn danger_level(&self) -> DangerLevel {
+        DangerLevel::ModifiesFiles
+    }
+
     fn validate_args(&self, args: &ToolArgs) -> Result<(), ToolError> {
         let params = self.parse_params(args)?;
 
@@ -491,15 +548,15 @@ impl Tool for CopyPathTool {
             .and_then(|v| v.as_bool())
             .unwrap_or(true); // Default to true for directories
 
-        let source = PathBuf::from(source_str);
-        let destination = PathBuf::from(dest_str);
+        let source = session_path(state, source_str);
+        let destination = session_path(state, dest_str);
 
         // Check if source exists
         if !source.exists() {
-            return Ok(ToolResult::error(format!(
-                "Source path not found: {}",
-                source.display()
-            )));
+            return Ok(path_not_found(
+                format!("Source path not found: {}", source.display()),
+                &source,
+            ));
         }
 
         // Check if destination already exists
@@ -522,8 +579,10 @@ impl Tool for CopyPathTool {
 
         if is_file {
             // Copy file
-            fs::copy(&source, &destination)
-                .map_err(|e| anyhow::anyhow!("Failed to copy file: {}", e))?;
+            writer::journaled(state, Operation::copy(&source, &destination), || {
+                fs::copy(&source, &destination)
+            })
+            .map_err(|e| anyhow::anyhow!("Failed to copy file: {}", e))?;
         } else if is_dir {
             if !recursive {
                 return Ok(ToolResult::error(
@@ -531,9 +590,32 @@ impl Tool for CopyPathTool {
                 ));
             }
 
-            // Copy directory recursively
-            self.copy_dir_recursive(&source, &destination)
-                .map_err(|e| anyhow::anyhow!("Failed to copy directory: {}", e))?;
+            // Copy directory recursively, streaming each copied file
+            let stream = state
+                .lock()
+                .map(|state| state.stream.clone())
+                .unwrap_or_default();
+            writer::journaled(state, Operation::copy(&source, &destination), || {
+                self.copy_dir_recursive(&source, &destination, &stream)
+            })
+            .map_err(|e| anyhow::anyhow!("Failed to copy directory: {}", e))?;
+            if stream.is_stopped() {
+                return Ok(ToolResult::success_with_data(
+                    format!(
+                        "Stopped copying {} to {} early at the stream consumer's request after {} files",
+                        source.display(),
+                        destination.display(),
+                        stream.chunks_sent()
+                    ),
+                    serde_json::json!({
+                        "source": source.to_string_lossy(),
+                        "destination": destination.to_string_lossy(),
+                        "type": "directory",
+                        "recursive": recursive,
+                        "copied": false
+                    }),
+                ));
+            }
         }
 
         // Update state
@@ -590,8 +672,15 @@ impl Tool for CopyPathTool {
 }
 
 impl CopyPathTool {
-    /// Recursively copy directory contents
-    fn copy_dir_recursive(&self, source: &PathBuf, destination: &PathBuf) -> Result<()> {
+    /// Recursively copy directory contents, sending each copied file to `stream`
+    ///
+    /// Returns [`StreamControl::Stop`] when the stream consumer ended the copy early.
+    fn copy_dir_recursive(
+        &self,
+        source: &PathBuf,
+        destination: &PathBuf,
+        stream: &ResultStream,
+    ) -> Result<StreamControl> {
         // Create the destination directory
         fs::create_dir_all(destination)?;
 
@@ -604,14 +693,27 @@ impl CopyPathTool {
 
             if entry_path.is_dir() {
                 // Recursively copy subdirectory
-                self.copy_dir_recursive(&entry_path, &dest_path)?;
+                if self.copy_dir_recursive(&entry_path, &dest_path, stream)? == StreamControl::Stop
+                {
+                    return Ok(StreamControl::Stop);
+                }
             } else {
                 // Copy file
                 fs::copy(&entry_path, &dest_path)?;
+                let control = stream.send_with_data(
+                    format!("Copied {}", dest_path.display()),
+                    serde_json::json!({
+                        "source": entry_path.to_string_lossy(),
+                        "destination": dest_path.to_string_lossy()
+                    }),
+                );
+                if control == StreamControl::Stop {
+                    return Ok(StreamControl::Stop);
+                }
             }
         }
 
-        Ok(())
+        Ok(StreamControl::Continue)
     }
 }
 
@@ -668,6 +770,14 @@ impl Tool for CreateDirectoryTool {
         "create_directory(path: str)"
     }
 
+    fn category(&self) -> ToolCategory {
+        ToolCategory::Editing
+    }
+
+    fn danger_level(&self) -> DangerLevel {
+        DangerLevel::ModifiesFiles
+    }
+
     fn validate_args(&self, args: &ToolArgs) -> Result<(), ToolError> {
         let params = self.parse_params(args)?;
 
@@ -695,7 +805,7 @@ impl Tool for CreateDirectoryTool {
             .and_then(|v| v.as_str())
             .ok_or_else(|| anyhow::anyhow!("Invalid path parameter"))?;
 
-        let path = PathBuf::from(path_str);
+        let path = session_path(state, path_str);
 
         // Check if directory already exists
         if path.exists() {
@@ -706,7 +816,7 @@ impl Tool for CreateDirectoryTool {
         }
 
         // Create the directory (and parents)
-        fs::create_dir_all(&path)
+        writer::create_dir_all(state, &path)
             .map_err(|e| anyhow::anyhow!("Failed to create directory: {}", e))?;
 
         // Update state
@@ -741,130 +851,1165 @@ impl Tool for CreateDirectoryTool {
     }
 }
 
-#[cfg(test)]
-mod tests {
-    use super::*;
-    use tempfile::TempDir;
+/// Tool for permanently removing trashed paths
+pub struct PurgeTrashTool {
+    name: String,
+}
 
-    #[test]
-    fn test_delete_path_tool_file() {
-        let temp_dir = TempDir::new().unwrap();
+impl PurgeTrashTool {
+    pub fn new() -> Self {
+        Self {
+            name: "purge_trash".to_string(),
+        }
+    }
+}
 
-        // Create test file with absolute path
-        let test_file = temp_dir.path().join("test_delete.txt");
-        fs::write(&test_file, "test content").unwrap();
+impl Default for PurgeTrashTool {
+    fn default() -> Self {
+        Self::new()
+    }
+}
 
-        let mut tool = DeletePathTool::new();
-        let state = Arc::new(Mutex::new(ToolState::new()));
+impl Tool for PurgeTrashTool {
+    fn name(&self) -> &str {
+        &self.name
+    }
 
-        let args = ToolArgs::with_named_args(
-            vec![],
-            vec![("path".to_string(), test_file.to_string_lossy().to_string())]
-                .into_iter()
-                .collect(),
-        );
+    fn description(&self) -> &str {
+        "Permanently remove paths moved to the trash by delete_path, optionally only sessions older than a number of days"
+    }
 
-        let result = tool.execute(&args, &state).unwrap();
-        assert!(result.success);
+    fn signature(&self) -> &str {
+        "purge_trash(older_than_days?: int, dry_run?: bool, include_external?: bool)"
+    }
 
-        // Verify file was deleted
-        assert!(!test_file.exists());
+    fn category(&self) -> ToolCategory {
+        ToolCategory::Editing
     }
 
-    #[test]
-    fn test_move_path_tool() {
-        let temp_dir = TempDir::new().unwrap();
+    fn danger_level(&self) -> DangerLevel {
+        DangerLevel::Destructive
+    }
 
-        // Create test file with absolute path
-        let source = temp_dir.path().join("source.txt");
-        fs::write(&source, "test content").unwrap();
-        let destination = temp_dir.path().join("destination.txt");
+    fn validate_args(&self, args: &ToolArgs) -> Result<(), ToolError> {
+        if let Some(days) = args.get_named_arg("older_than_days") {
+            if days.parse::<u64>().is_err() {
+                return Err(ToolError::InvalidArgs {
+                    message: format!("older_than_days must be a whole number, got '{}'", days),
+                });
+            }
+        }
+        Ok(())
+    }
 
-        let mut tool = MovePathTool::new();
-        let state = Arc::new(Mutex::new(ToolState::new()));
+    fn execute(&mut self, args: &ToolArgs, state: &Arc<Mutex<ToolState>>) -> Result<ToolResult> {
+        let older_than_days = args
+            .get_named_arg("older_than_days")
+            .and_then(|d| d.parse::<u64>().ok())
+            .unwrap_or(0);
+        let dry_run = args
+            .get_named_arg("dry_run")
+            .map(|d| d == "true")
+            .unwrap_or(false);
+        let include_external = args
+            .get_named_arg("include_external")
+            .map(|d| d == "true")
+            .unwrap_or(false);
 
-        let args = ToolArgs::with_named_args(
-            vec![],
-            vec![
-                ("source".to_string(), source.to_string_lossy().to_string()),
-                (
-                    "destination".to_string(),
-                    destination.to_string_lossy().to_string(),
-                ),
-            ]
-            .into_iter()
-            .collect(),
-        );
+        let working_dir = {
+            let state_guard = state
+                .lock()
+                .map_err(|e| anyhow::anyhow!("Failed to lock state: {}", e))?;
+            state_guard.working_directory.clone()
+        };
+        let now = std::time::SystemTime::now()
+            .duration_since(std::time::UNIX_EPOCH)
+            .map(|d| d.as_secs())
+            .unwrap_or(0);
+
+        let mut purged = Vec::new();
+        let mut bytes = 0;
+        let mut roots = vec![working_dir.join(trash::TRASH_DIR)];
+        if include_external {
+            roots.push(trash::external_trash_root());
+        }
+        for root in roots {
+            let Ok(entries) = fs::read_dir(&root) else {
+                continue;
+            };
+            for entry in entries.filter_map(|e| e.ok()) {
+                let session_dir = entry.path();
+                if trash::session_age(&session_dir, now) < older_than_days * 24 * 60 * 60 {
+                    continue;
+                }
+                bytes += trash::disk_usage(&session_dir);
+                if !dry_run {
+                    fs::remove_dir_all(&session_dir).map_err(|e| {
+                        anyhow::anyhow!("Failed to purge {}: {}", session_dir.display(), e)
+                    })?;
+                }
+                purged.push(session_dir.to_string_lossy().to_string());
+            }
+        }
 
-        let result = tool.execute(&args, &state).unwrap();
-        assert!(result.success);
+        if !dry_run {
+            let mut state_guard = state
+                .lock()
+                .map_err(|e| anyhow::anyhow!("Failed to lock state: {}", e))?;
+            state_guard.push_history(format!("Purged {} trash session(s)", purged.len()));
+        }
 
-        // Verify file was moved
-        assert!(!source.exists());
-        assert!(destination.exists());
+        let message = if purged.is_empty() {
+            "Trash is empty".to_string()
+        } else {
+            format!(
+                "{} {} trash session(s), {} byte(s):\n{}",
+                if dry_run { "Would purge" } else { "Purged" },
+                purged.len(),
+                bytes,
+                purged.join("\n")
+            )
+        };
 
-        let content = fs::read_to_string(&destination).unwrap();
-        assert_eq!(content, "test content");
+        Ok(ToolResult::success_with_data(
+            message,
+            serde_json::json!({
+                "purged": purged,
+                "bytes": bytes,
+                "dry_run": dry_run
+            }),
+        ))
     }
 
-    #[test]
-    fn test_copy_path_tool() {
-        let temp_dir = TempDir::new().unwrap();
+    fn get_parameters_schema(&self) -> serde_json::Value {
+        serde_json::json!({
+            "type": "object",
+            "properties": {
+                "older_than_days": {
+                    "type": "integer",
+                    "description": "Only purge trash sessions at least this many days old (default: all)"
+                },
+                "dry_run": {
+                    "type": "boolean",
+                    "description": "List what would be purged without removing anything",
+                    "default": false
+                },
+                "include_external": {
+                    "type": "boolean",
+                    "description": "Also purge the shared trash in the system temp directory used for paths outside the working directory",
+                    "default": false
+                }
+            },
+            "required": []
+        })
+    }
+}
 
-        // Create test file with absolute path
-        let source = temp_dir.path().join("source.txt");
-        fs::write(&source, "test content").unwrap();
-        let destination = temp_dir.path().join("copy.txt");
+/// Tool for changing file permissions and the executable bit
+pub struct SetPermissionsTool {
+    name: String,
+}
 
-        let mut tool = CopyPathTool::new();
-        let state = Arc::new(Mutex::new(ToolState::new()));
+impl SetPermissionsTool {
+    pub fn new() -> Self {
+        Self {
+            name: "set_permissions".to_string(),
+        }
+    }
 
-        let args = ToolArgs::with_named_args(
-            vec![],
-            vec![
-                ("source".to_string(), source.to_string_lossy().to_string()),
-                (
-                    "destination".to_string(),
-                    destination.to_string_lossy().to_string(),
-                ),
-            ]
-            .into_iter()
-            .collect(),
-        );
+    /// Parse parameters from ToolArgs
+    fn parse_params(&self, args: &ToolArgs) -> Result<serde_json::Value, ToolError> {
+        // Try to parse as JSON first
+        if let Some(json_str) = args.get_named_arg("json") {
+            return serde_json::from_str(json_str).map_err(ToolError::Json);
+        }
 
-        let result = tool.execute(&args, &state).unwrap();
-        assert!(result.success);
+        // Check if we have structured named arguments
+        if !args.named_args.is_empty() {
+            return serde_json::to_value(&args.named_args).map_err(ToolError::Json);
+        }
 
-        // Verify file was copied
-        assert!(source.exists());
-        assert!(destination.exists());
+        // Fall back to positional arguments: path, mode
+        if !args.is_empty() {
+            let mut params = serde_json::Map::new();
+            params.insert(
+                "path".to_string(),
+                serde_json::Value::String(args.get_arg(0).unwrap().clone()),
+            );
+            if args.len() >= 2 {
+                params.insert(
+                    "mode".to_string(),
+                    serde_json::Value::String(args.get_arg(1).unwrap().clone()),
+                );
+            }
+            return Ok(serde_json::Value::Object(params));
+        }
 
-        let content = fs::read_to_string(&destination).unwrap();
-        assert_eq!(content, "test content");
+        Err(ToolError::InvalidArgs {
+            message: "Insufficient parameters".to_string(),
+        })
     }
 
-    #[test]
-    fn test_create_directory_tool() {
-        let temp_dir = TempDir::new().unwrap();
+    /// The raw `mode` parameter, given as a string or a bare number such as 755
+    fn mode_param(obj: &serde_json::Map<String, serde_json::Value>) -> Option<String> {
+        match obj.get("mode")? {
+            serde_json::Value::String(s) => Some(s.clone()),
+            serde_json::Value::Number(n) => Some(n.to_string()),
+            _ => None,
+        }
+    }
 
-        let mut tool = CreateDirectoryTool::new();
-        let state = Arc::new(Mutex::new(ToolState::new()));
+    /// Parse an octal mode such as "755", "0755" or "0o755"
+    fn parse_mode(mode: &str) -> Option<u32> {
+        let digits = mode.trim().trim_start_matches("0o");
+        u32::from_str_radix(digits, 8).ok().filter(|m| *m <= 0o7777)
+    }
 
-        // Use absolute path for the directory to be created
-        let dir_path = temp_dir.path().join("new_dir/sub_dir");
+    /// Apply `executable` to `mode`: add execute bits wherever read is allowed, or clear them
+    fn with_executable(mode: u32, executable: bool) -> u32 {
+        if executable {
+            mode | ((mode & 0o444) >> 2)
+        } else {
+            mode & !0o111
+        }
+    }
+}
 
-        let args = ToolArgs::with_named_args(
-            vec![],
-            vec![("path".to_string(), dir_path.to_string_lossy().to_string())]
-                .into_iter()
-                .collect(),
-        );
+impl Default for SetPermissionsTool {
+    fn default() -> Self {
+        Self::new()
+    }
+}
 
-        let result = tool.execute(&args, &state).unwrap();
-        assert!(result.success);
+impl Tool for SetPermissionsTool {
+    fn name(&self) -> &str {
+        &self.name
+    }
+
+    fn description(&self) -> &str {
+        "Set a file's permission bits from an octal mode and/or toggle its executable bit (no-op with a warning on Windows)"
+    }
+
+    fn signature(&self) -> &str {
+        "set_permissions(path: str, mode?: str, executable?: bool)"
+    }
+
+    fn category(&self) -> ToolCategory {
+        ToolCategory::Editing
+    }
+
+    fn danger_level(&self) -> DangerLevel {
+        DangerLevel::ModifiesFiles
+    }
+
+    fn validate_args(&self, args: &ToolArgs) -> Result<(), ToolError> {
+        let params = self.parse_params(args)?;
+
+        let obj = params.as_object().ok_or_else(|| ToolError::InvalidArgs {
+            message: "Parameters must be an object".to_string(),
+        })?;
+
+        if !obj.contains_key("path") {
+            return Err(ToolError::InvalidArgs {
+                message: "Missing required parameter: path".to_string(),
+            });
+        }
+
+        let mode = Self::mode_param(obj);
+        if let Some(mode) = &mode {
+            if Self::parse_mode(mode).is_none() {
+                return Err(ToolError::InvalidArgs {
+                    message: format!(
+                        "mode must be an octal permission such as 755, got '{}'",
+                        mode
+                    ),
+                });
+            }
+        }
+
+        if mode.is_none() && bool_param(obj, "executable").is_none() {
+            return Err(ToolError::InvalidArgs {
+                message: "Provide mode, executable, or both".to_string(),
+            });
+        }
+
+        Ok(())
+    }
+
+    fn execute(&mut self, args: &ToolArgs, state: &Arc<Mutex<ToolState>>) -> Result<ToolResult> {
+        let params = self.parse_params(args)?;
+        let obj = params
+            .as_object()
+            .ok_or_else(|| anyhow::anyhow!("Invalid parameters"))?;
+
+        let path_str = obj
+            .get("path")
+            .and_then(|v| v.as_str())
+            .ok_or_else(|| anyhow::anyhow!("Invalid path parameter"))?;
+        let mode = Self::mode_param(obj).and_then(|m| Self::parse_mode(&m));
+        let executable = bool_param(obj, "executable");
+
+        let path = session_path(state, path_str);
+
+        if !path.exists() {
+            return Ok(path_not_found(
+                format!("Path not found: {}", path.display()),
+                &path,
+            ));
+        }
+
+        #[cfg(unix)]
+        {
+            use std::os::unix::fs::PermissionsExt;
+
+            let old_mode = fs::metadata(&path)
+                .map_err(|e| anyhow::anyhow!("Failed to read permissions: {}", e))?
+                .permissions()
+                .mode()
+                & 0o7777;
+            let mut new_mode = mode.unwrap_or(old_mode);
+            if let Some(executable) = executable {
+                new_mode = Self::with_executable(new_mode, executable);
+            }
+
+            fs::set_permissions(&path, fs::Permissions::from_mode(new_mode))
+                .map_err(|e| anyhow::anyhow!("Failed to set permissions: {}", e))?;
+
+            {
+                let mut state_guard = state
+                    .lock()
+                    .map_err(|e| anyhow::anyhow!("Failed to lock state: {}", e))?;
+                state_guard.push_history(format!(
+                    "Set permissions of {}: {:o} -> {:o}",
+                    path.display(),
+                    old_mode,
+                    new_mode
+                ));
+            }
+
+            Ok(ToolResult::success_with_data(
+                format!(
+                    "Changed permissions of {} from {:04o} to {:04o}",
+                    path.display(),
+                    old_mode,
+                    new_mode
+                ),
+                serde_json::json!({
+                    "path": path.to_string_lossy(),
+                    "old_mode": format!("{:04o}", old_mode),
+                    "new_mode": format!("{:04o}", new_mode),
+                    "executable": new_mode & 0o111 != 0,
+                    "applied": true
+                }),
+            ))
+        }
+
+        #[cfg(not(unix))]
+        {
+            let _ = (mode, executable, state);
+            tracing::warn!(
+                "set_permissions is a no-op on this platform: {}",
+                path.display()
+            );
+            Ok(ToolResult::success_with_data(
+                format!(
+                    "Warning: permission bits are not supported on this platform; {} left unchanged",
+                    path.display()
+                ),
+                serde_json::json!({
+                    "path": path.to_string_lossy(),
+                    "applied": false
+                }),
+            ))
+        }
+    }
+
+    fn get_parameters_schema(&self) -> serde_json::Value {
+        serde_json::json!({
+            "type": "object",
+            "properties": {
+                "path": {
+                    "type": "string",
+                    "description": "Full path to the file or directory"
+                },
+                "mode": {
+                    "type": "string",
+                    "description": "Octal permission bits, e.g. \"755\" or \"0644\""
+                },
+                "executable": {
+                    "type": "boolean",
+                    "description": "Add execute permission wherever read is allowed (true) or remove all execute bits (false)"
+                }
+            },
+            "required": ["path"]
+        })
+    }
+}
+
+/// Tool for creating empty files or updating modification times
+pub struct TouchTool {
+    name: String,
+}
+
+impl TouchTool {
+    pub fn new() -> Self {
+        Self {
+            name: "touch".to_string(),
+        }
+    }
+
+    /// Parse parameters from ToolArgs
+    fn parse_params(&self, args: &ToolArgs) -> Result<serde_json::Value, ToolError> {
+        // Try to parse as JSON first
+        if let Some(json_str) = args.get_named_arg("json") {
+            return serde_json::from_str(json_str).map_err(ToolError::Json);
+        }
+
+        // Check if we have structured named arguments
+        if !args.named_args.is_empty() {
+            return serde_json::to_value(&args.named_args).map_err(ToolError::Json);
+        }
+
+        // Fall back to positional arguments: path, mtime
+        if !args.is_empty() {
+            let mut params = serde_json::Map::new();
+            params.insert(
+                "path".to_string(),
+                serde_json::Value::String(args.get_arg(0).unwrap().clone()),
+            );
+            if args.len() >= 2 {
+                params.insert(
+                    "mtime".to_string(),
+                    serde_json::Value::String(args.get_arg(1).unwrap().clone()),
+                );
+            }
+            return Ok(serde_json::Value::Object(params));
+        }
+
+        Err(ToolError::InvalidArgs {
+            message: "Insufficient parameters".to_string(),
+        })
+    }
+
+    fn format_time(time: std::time::SystemTime) -> String {
+        humantime::format_rfc3339_millis(time).to_string()
+    }
+}
+
+impl Default for TouchTool {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl Tool for TouchTool {
+    fn name(&self) -> &str {
+        &self.name
+    }
+
+    fn description(&self) -> &str {
+        "Create an empty file, or set an existing path's modification time to now (or to mtime) and report the old and new timestamps"
+    }
+
+    fn signature(&self) -> &str {
+        "touch(path: str, mtime?: str, no_create?: bool)"
+    }
+
+    fn category(&self) -> ToolCategory {
+        ToolCategory::Editing
+    }
+
+    fn danger_level(&self) -> DangerLevel {
+        DangerLevel::ModifiesFiles
+    }
+
+    fn validate_args(&self, args: &ToolArgs) -> Result<(), ToolError> {
+        let params = self.parse_params(args)?;
+
+        let obj = params.as_object().ok_or_else(|| ToolError::InvalidArgs {
+            message: "Parameters must be an object".to_string(),
+        })?;
+
+        if !obj.contains_key("path") {
+            return Err(ToolError::InvalidArgs {
+                message: "Missing required parameter: path".to_string(),
+            });
+        }
+
+        if let Some(mtime) = obj.get("mtime").and_then(|v| v.as_str()) {
+            if let Err(e) = humantime::parse_rfc3339_weak(mtime) {
+                return Err(ToolError::InvalidArgs {
+                    message: format!(
+                        "mtime must be an RFC 3339 timestamp such as 2024-01-31T12:00:00Z, got '{}': {}",
+                        mtime, e
+                    ),
+                });
+            }
+        }
+
+        Ok(())
+    }
+
+    fn execute(&mut self, args: &ToolArgs, state: &Arc<Mutex<ToolState>>) -> Result<ToolResult> {
+        let params = self.parse_params(args)?;
+        let obj = params
+            .as_object()
+            .ok_or_else(|| anyhow::anyhow!("Invalid parameters"))?;
+
+        let path_str = obj
+            .get("path")
+            .and_then(|v| v.as_str())
+            .ok_or_else(|| anyhow::anyhow!("Invalid path parameter"))?;
+        let mtime = match obj.get("mtime").and_then(|v| v.as_str()) {
+            Some(mtime) => humantime::parse_rfc3339_weak(mtime)
+                .map_err(|e| anyhow::anyhow!("Invalid mtime: {}", e))?,
+            None => std::time::SystemTime::now(),
+        };
+        let no_create = bool_param(obj, "no_create").unwrap_or(false);
+
+        let path = session_path(state, path_str);
+
+        let old_mtime = fs::metadata(&path).and_then(|m| m.modified()).ok();
+        let created = !path.exists();
+        if created {
+            if no_create {
+                return Ok(path_not_found(
+                    format!("Path not found: {}", path.display()),
+                    &path,
+                ));
+            }
+            writer::write_file(state, &path, "")
+                .map_err(|e| anyhow::anyhow!("Failed to create file: {}", e))?;
+        }
+
+        let times = fs::FileTimes::new().set_accessed(mtime).set_modified(mtime);
+        fs::File::options()
+            .write(!path.is_dir())
+            .read(path.is_dir())
+            .open(&path)
+            .and_then(|file| file.set_times(times))
+            .map_err(|e| anyhow::anyhow!("Failed to update timestamps: {}", e))?;
+
+        let new_mtime = Self::format_time(mtime);
+        let old_mtime = old_mtime.map(Self::format_time);
+
+        {
+            let mut state_guard = state
+                .lock()
+                .map_err(|e| anyhow::anyhow!("Failed to lock state: {}", e))?;
+            state_guard.push_history(format!("Touched: {}", path.display()));
+        }
+
+        let message = match &old_mtime {
+            Some(old_mtime) => format!(
+                "Updated modification time of {}: {} -> {}",
+                path.display(),
+                old_mtime,
+                new_mtime
+            ),
+            None => format!("Created empty file {} ({})", path.display(), new_mtime),
+        };
+
+        Ok(ToolResult::success_with_data(
+            message,
+            serde_json::json!({
+                "path": path.to_string_lossy(),
+                "created": created,
+                "old_mtime": old_mtime,
+                "new_mtime": new_mtime
+            }),
+        ))
+    }
+
+    fn get_parameters_schema(&self) -> serde_json::Value {
+        serde_json::json!({
+            "type": "object",
+            "properties": {
+                "path": {
+                    "type": "string",
+                    "description": "Full path to the file to create or touch"
+                },
+                "mtime": {
+                    "type": "string",
+                    "description": "Modification time to set as an RFC 3339 timestamp (default: now)"
+                },
+                "no_create": {
+                    "type": "boolean",
+                    "description": "Fail instead of creating the file when it does not exist",
+                    "default": false
+                }
+            },
+            "required": ["path"]
+        })
+    }
+}
+
+/// Tool for creating symbolic links inside the workspace
+pub struct CreateSymlinkTool {
+    name: String,
+}
+
+impl CreateSymlinkTool {
+    pub fn new() -> Self {
+        Self {
+            name: "create_symlink".to_string(),
+        }
+    }
+
+    /// Parse parameters from ToolArgs
+    fn parse_params(&self, args: &ToolArgs) -> Result<serde_json::Value, ToolError> {
+        // Try to parse as JSON first
+        if let Some(json_str) = args.get_named_arg("json") {
+            return serde_json::from_str(json_str).map_err(ToolError::Json);
+        }
+
+        // Check if we have structured named arguments
+        if !args.named_args.is_empty() {
+            return serde_json::to_value(&args.named_args).map_err(ToolError::Json);
+        }
+
+        // Fall back to positional arguments: target, link_path
+        if args.len() >= 2 {
+            let mut params = serde_json::Map::new();
+            params.insert(
+                "target".to_string(),
+                serde_json::Value::String(args.get_arg(0).unwrap().clone()),
+            );
+            params.insert(
+                "link_path".to_string(),
+                serde_json::Value::String(args.get_arg(1).unwrap().clone()),
+            );
+            return Ok(serde_json::Value::Object(params));
+        }
+
+        Err(ToolError::InvalidArgs {
+            message: "Insufficient parameters".to_string(),
+        })
+    }
+}
+
+impl Default for CreateSymlinkTool {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl Tool for CreateSymlinkTool {
+    fn name(&self) -> &str {
+        &self.name
+    }
+
+    fn description(&self) -> &str {
+        "Create a symbolic link at link_path pointing to target; both must stay inside the working directory unless allow_outside=true"
+    }
+
+    fn signature(&self) -> &str {
+        "create_symlink(target: str, link_path: str, allow_outside?: bool)"
+    }
+
+    fn category(&self) -> ToolCategory {
+        ToolCategory::Editing
+    }
+
+    fn danger_level(&self) -> DangerLevel {
+        DangerLevel::ModifiesFiles
+    }
+
+    fn validate_args(&self, args: &ToolArgs) -> Result<(), ToolError> {
+        let params = self.parse_params(args)?;
+
+        let obj = params.as_object().ok_or_else(|| ToolError::InvalidArgs {
+            message: "Parameters must be an object".to_string(),
+        })?;
+
+        for key in ["target", "link_path"] {
+            if !obj.contains_key(key) {
+                return Err(ToolError::InvalidArgs {
+                    message: format!("Missing required parameter: {}", key),
+                });
+            }
+        }
+
+        Ok(())
+    }
+
+    fn accessed_paths(&self, args: &ToolArgs) -> Vec<PathBuf> {
+        let Ok(params) = self.parse_params(args) else {
+            return Vec::new();
+        };
+        let link = params.get("link_path").and_then(|v| v.as_str());
+        let target = params.get("target").and_then(|v| v.as_str());
+        // The target is checked the way the link resolves it
+        let parent = link
+            .and_then(|link| std::path::Path::new(link).parent())
+            .unwrap_or(std::path::Path::new(""));
+        link.map(PathBuf::from)
+            .into_iter()
+            .chain(target.map(|target| parent.join(target)))
+            .collect()
+    }
+
+    fn execute(&mut self, args: &ToolArgs, state: &Arc<Mutex<ToolState>>) -> Result<ToolResult> {
+        let params = self.parse_params(args)?;
+        let obj = params
+            .as_object()
+            .ok_or_else(|| anyhow::anyhow!("Invalid parameters"))?;
+
+        let target_str = obj
+            .get("target")
+            .and_then(|v| v.as_str())
+            .ok_or_else(|| anyhow::anyhow!("Invalid target parameter"))?;
+        let link_str = obj
+            .get("link_path")
+            .and_then(|v| v.as_str())
+            .ok_or_else(|| anyhow::anyhow!("Invalid link_path parameter"))?;
+        let allow_outside = bool_param(obj, "allow_outside").unwrap_or(false);
+
+        let target = PathBuf::from(target_str);
+        let link = session_path(state, link_str);
+
+        if fs::symlink_metadata(&link).is_ok() {
+            return Ok(ToolResult::error(format!(
+                "Link path already exists: {}",
+                link.display()
+            )));
+        }
+
+        let working_dir = {
+            let state_guard = state
+                .lock()
+                .map_err(|e| anyhow::anyhow!("Failed to lock state: {}", e))?;
+            resolve_path(&state_guard.working_directory)
+        };
+        let link_parent = link
+            .parent()
+            .filter(|p| !p.as_os_str().is_empty())
+            .unwrap_or(std::path::Path::new("."));
+        let resolved_link = resolve_path(link_parent).join(link.file_name().unwrap_or_default());
+        // Relative targets are resolved against the link's directory, like the OS does
+        let resolved_target = resolve_path(&link_parent.join(&target));
+
+        if !allow_outside {
+            for (label, path) in [("link_path", &resolved_link), ("target", &resolved_target)] {
+                if !path.starts_with(&working_dir) {
+                    return Ok(ToolResult::error_with_data(
+                        format!(
+                            "Sandbox check: {} {} resolves outside the working directory {}. Pass allow_outside=true to create it anyway.",
+                            label,
+                            path.display(),
+                            working_dir.display()
+                        ),
+                        serde_json::json!({
+                            "path": path.to_string_lossy(),
+                            "working_directory": working_dir.to_string_lossy()
+                        }),
+                    ));
+                }
+            }
+        }
+
+        if !link_parent.exists() {
+            writer::create_dir_all(state, link_parent)
+                .map_err(|e| anyhow::anyhow!("Failed to create parent directories: {}", e))?;
+        }
+
+        if let Err(e) = writer::symlink(state, &target, &link) {
+            let hint = if cfg!(windows) && e.kind() == std::io::ErrorKind::PermissionDenied {
+                " (creating symlinks on Windows requires Developer Mode or administrator rights)"
+            } else {
+                ""
+            };
+            return Ok(ToolResult::error(format!(
+                "Failed to create symlink {} -> {}: {}{}",
+                link.display(),
+                target.display(),
+                e,
+                hint
+            )));
+        }
+
+        let dangling = !resolved_target.exists();
+        {
+            let mut state_guard = state
+                .lock()
+                .map_err(|e| anyhow::anyhow!("Failed to lock state: {}", e))?;
+            state_guard.push_history(format!(
+                "Created symlink {} -> {}",
+                link.display(),
+                target.display()
+            ));
+        }
+
+        let mut message = format!("Created symlink {} -> {}", link.display(), target.display());
+        if dangling {
+            message.push_str(&format!(
+                "\nWarning: target {} does not exist yet",
+                resolved_target.display()
+            ));
+        }
+
+        Ok(ToolResult::success_with_data(
+            message,
+            serde_json::json!({
+                "link_path": link.to_string_lossy(),
+                "target": target.to_string_lossy(),
+                "resolved_target": resolved_target.to_string_lossy(),
+                "target_type": if resolved_target.is_dir() { "directory" } else { "file" },
+                "dangling": dangling
+            }),
+        ))
+    }
+
+    fn get_parameters_schema(&self) -> serde_json::Value {
+        serde_json::json!({
+            "type": "object",
+            "properties": {
+                "target": {
+                    "type": "string",
+                    "description": "Path the link points to, absolute or relative to the link's directory"
+                },
+                "link_path": {
+                    "type": "string",
+                    "description": "Path of the symbolic link to create"
+                },
+                "allow_outside": {
+                    "type": "boolean",
+                    "description": "Allow the link or its target to resolve outside the working directory",
+                    "default": false
+                }
+            },
+            "required": ["target", "link_path"]
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use tempfile::TempDir;
+
+    #[test]
+    fn test_delete_path_tool_file() {
+        let temp_dir = TempDir::new().unwrap();
+
+        // Create test file with absolute path
+        let test_file = temp_dir.path().join("test_delete.txt");
+        fs::write(&test_file, "test content").unwrap();
+
+        let mut tool = DeletePathTool::new();
+        // Inside the working directory, so the trash stays in the temp dir
+        let mut tool_state = ToolState::new();
+        tool_state.working_directory = temp_dir.path().to_path_buf();
+        let state = Arc::new(Mutex::new(tool_state));
+
+        let args = ToolArgs::with_named_args(
+            vec![],
+            vec![("path".to_string(), test_file.to_string_lossy().to_string())]
+                .into_iter()
+                .collect(),
+        );
+
+        let result = tool.execute(&args, &state).unwrap();
+        assert!(result.success);
+
+        // Verify file was deleted
+        assert!(!test_file.exists());
+        assert!(temp_dir.path().join(trash::TRASH_DIR).exists());
+    }
+
+    #[test]
+    fn test_move_path_tool() {
+        let temp_dir = TempDir::new().unwrap();
+
+        // Create test file with absolute path
+        let source = temp_dir.path().join("source.txt");
+        fs::write(&source, "test content").unwrap();
+        let destination = temp_dir.path().join("destination.txt");
+
+        let mut tool = MovePathTool::new();
+        let state = Arc::new(Mutex::new(ToolState::new()));
+
+        let args = ToolArgs::with_named_args(
+            vec![],
+            vec![
+                ("source".to_string(), source.to_string_lossy().to_string()),
+                (
+                    "destination".to_string(),
+                    destination.to_string_lossy().to_string(),
+                ),
+            ]
+            .into_iter()
+            .collect(),
+        );
+
+        let result = tool.execute(&args, &state).unwrap();
+        assert!(result.success);
+
+        // Verify file was moved
+        assert!(!source.exists());
+        assert!(destination.exists());
+
+        let content = fs::read_to_string(&destination).unwrap();
+        assert_eq!(content, "test content");
+    }
+
+    #[test]
+    fn test_copy_path_tool() {
+        let temp_dir = TempDir::new().unwrap();
+
+        // Create test file with absolute path
+        let source = temp_dir.path().join("source.txt");
+        fs::write(&source, "test content").unwrap();
+        let destination = temp_dir.path().join("copy.txt");
+
+        let mut tool = CopyPathTool::new();
+        let state = Arc::new(Mutex::new(ToolState::new()));
+
+        let args = ToolArgs::with_named_args(
+            vec![],
+            vec![
+                ("source".to_string(), source.to_string_lossy().to_string()),
+                (
+                    "destination".to_string(),
+                    destination.to_string_lossy().to_string(),
+                ),
+            ]
+            .into_iter()
+            .collect(),
+        );
+
+        let result = tool.execute(&args, &state).unwrap();
+        assert!(result.success);
+
+        // Verify file was copied
+        assert!(source.exists());
+        assert!(destination.exists());
+
+        let content = fs::read_to_string(&destination).unwrap();
+        assert_eq!(content, "test content");
+    }
+
+    #[test]
+    fn test_create_directory_tool() {
+        let temp_dir = TempDir::new().unwrap();
+
+        let mut tool = CreateDirectoryTool::new();
+        let state = Arc::new(Mutex::new(ToolState::new()));
+
+        // Use absolute path for the directory to be created
+        let dir_path = temp_dir.path().join("new_dir/sub_dir");
+
+        let args = ToolArgs::with_named_args(
+            vec![],
+            vec![("path".to_string(), dir_path.to_string_lossy().to_string())]
+                .into_iter()
+                .collect(),
+        );
+
+        let result = tool.execute(&args, &state).unwrap();
+        assert!(result.success);
 
         // Verify directory was created
         assert!(dir_path.exists());
         assert!(dir_path.is_dir());
     }
+
+    #[test]
+    fn test_delete_path_moves_to_trash_and_purge() {
+        let temp_dir = TempDir::new().unwrap();
+        let dir = temp_dir.path().join("scratch");
+        fs::create_dir(&dir).unwrap();
+        fs::write(dir.join("out.txt"), "artifact").unwrap();
+
+        let mut tool_state = ToolState::new();
+        tool_state.working_directory = temp_dir.path().to_path_buf();
+        tool_state.journal = None;
+        let state = Arc::new(Mutex::new(tool_state));
+
+        let mut delete = DeletePathTool::new();
+        let args = ToolArgs::from_args(&[dir.to_str().unwrap(), "true"]);
+        let result = delete.execute(&args, &state).unwrap();
+        assert!(result.success);
+        assert!(!dir.exists());
+        let trash_path = PathBuf::from(result.data.unwrap()["trash_path"].as_str().unwrap());
+        assert_eq!(
+            fs::read_to_string(trash_path.join("out.txt")).unwrap(),
+            "artifact"
+        );
+
+        let mut purge = PurgeTrashTool::new();
+        let result = purge
+            .execute(&ToolArgs::from_args(&["--dry_run"]), &state)
+            .unwrap();
+        assert!(result
+            .message
+            .starts_with("Would purge 1 trash session(s), 8 byte(s)"));
+        assert!(trash_path.exists());
+
+        purge.execute(&ToolArgs::from_args(&[]), &state).unwrap();
+        assert!(!trash_path.exists());
+    }
+
+    #[test]
+    fn test_delete_path_permanent() {
+        let temp_dir = TempDir::new().unwrap();
+        let file = temp_dir.path().join("gone.txt");
+        fs::write(&file, "x").unwrap();
+
+        let mut tool_state = ToolState::new();
+        tool_state.working_directory = temp_dir.path().to_path_buf();
+        tool_state.journal = None;
+        let state = Arc::new(Mutex::new(tool_state));
+
+        let args = ToolArgs::from_args(&[file.to_str().unwrap(), "false", "true"]);
+        let result = DeletePathTool::new().execute(&args, &state).unwrap();
+        assert!(result.message.starts_with("Permanently deleted file"));
+        assert!(!file.exists());
+        assert!(!temp_dir.path().join(trash::TRASH_DIR).exists());
+    }
+
+    #[test]
+    fn test_protected_paths_require_override() {
+        let temp_dir = TempDir::new().unwrap();
+        let migrations = temp_dir.path().join("migrations");
+        fs::create_dir(&migrations).unwrap();
+        fs::write(migrations.join("001.sql"), "create table t;").unwrap();
+
+        let mut tool_state = ToolState::new();
+        tool_state.working_directory = temp_dir.path().to_path_buf();
+        tool_state.journal = None;
+        tool_state.protected_paths = Some(protection::ProtectedPaths::new(["migrations/**"]));
+        let state = Arc::new(Mutex::new(tool_state));
+        let file = migrations.join("001.sql");
+
+        let mut delete = DeletePathTool::new();
+        let result = delete
+            .execute(&ToolArgs::from_args(&[file.to_str().unwrap()]), &state)
+            .unwrap();
+        assert!(!result.success);
+        assert_eq!(result.data.unwrap()["protected_by"], "migrations/**");
+        assert!(file.exists());
+
+        let mut move_tool = MovePathTool::new();
+        let dest = temp_dir.path().join("001.sql");
+        let args = ToolArgs::from_args(&[file.to_str().unwrap(), dest.to_str().unwrap()]);
+        assert!(!move_tool.execute(&args, &state).unwrap().success);
+
+        let args = ToolArgs::with_named_args(
+            vec![],
+            vec![
+                ("path".to_string(), file.to_string_lossy().to_string()),
+                ("allow_protected".to_string(), "true".to_string()),
+            ]
+            .into_iter()
+            .collect(),
+        );
+        assert!(delete.execute(&args, &state).unwrap().success);
+        assert!(!file.exists());
+        let state_guard = state.lock().unwrap();
+        assert!(state_guard
+            .history
+            .iter()
+            .any(|h| h.operation.starts_with("Protection override: delete_path")));
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn test_set_permissions_tool() {
+        use std::os::unix::fs::PermissionsExt;
+
+        let temp_dir = TempDir::new().unwrap();
+        let script = temp_dir.path().join("hook.sh");
+        fs::write(&script, "#!/bin/sh\n").unwrap();
+        fs::set_permissions(&script, fs::Permissions::from_mode(0o644)).unwrap();
+
+        let state = Arc::new(Mutex::new(ToolState::new()));
+        let mut tool = SetPermissionsTool::new();
+        let args = ToolArgs::with_named_args(
+            vec![],
+            vec![
+                ("path".to_string(), script.to_string_lossy().to_string()),
+                ("executable".to_string(), "true".to_string()),
+            ]
+            .into_iter()
+            .collect(),
+        );
+        let result = tool.execute(&args, &state).unwrap();
+        assert!(result.success);
+        assert_eq!(result.data.unwrap()["new_mode"], "0755");
+        let mode = fs::metadata(&script).unwrap().permissions().mode() & 0o777;
+        assert_eq!(mode, 0o755);
+
+        let args = ToolArgs::from_args(&[script.to_str().unwrap(), "600"]);
+        tool.execute(&args, &state).unwrap();
+        let mode = fs::metadata(&script).unwrap().permissions().mode() & 0o777;
+        assert_eq!(mode, 0o600);
+
+        let args = ToolArgs::from_args(&[script.to_str().unwrap(), "9"]);
+        assert!(tool.validate_args(&args).is_err());
+    }
+
+    #[test]
+    fn test_touch_tool() {
+        let temp_dir = TempDir::new().unwrap();
+        let marker = temp_dir.path().join(".rebuild");
+
+        let mut tool_state = ToolState::new();
+        tool_state.journal = None;
+        let state = Arc::new(Mutex::new(tool_state));
+        let mut tool = TouchTool::new();
+
+        let args = ToolArgs::from_args(&[marker.to_str().unwrap()]);
+        let result = tool.execute(&args, &state).unwrap();
+        assert!(result.success);
+        assert_eq!(result.data.unwrap()["created"], true);
+        assert_eq!(fs::read_to_string(&marker).unwrap(), "");
+
+        let args = ToolArgs::from_args(&[marker.to_str().unwrap(), "2020-01-02T03:04:05Z"]);
+        let result = tool.execute(&args, &state).unwrap();
+        let data = result.data.unwrap();
+        assert_eq!(data["created"], false);
+        assert!(data["old_mtime"].is_string());
+        assert_eq!(data["new_mtime"], "2020-01-02T03:04:05.000Z");
+        let modified = fs::metadata(&marker).unwrap().modified().unwrap();
+        assert_eq!(
+            humantime::format_rfc3339_seconds(modified).to_string(),
+            "2020-01-02T03:04:05Z"
+        );
+
+        let missing = temp_dir.path().join("missing");
+        let path_arg = format!("--path={}", missing.display());
+        let args = ToolArgs::from_args(&[path_arg.as_str(), "--no_create"]);
+        assert!(!tool.execute(&args, &state).unwrap().success);
+        assert!(!missing.exists());
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn test_create_symlink_tool() {
+        let temp_dir = TempDir::new().unwrap();
+        let working_dir = temp_dir.path().canonicalize().unwrap();
+        fs::create_dir(working_dir.join("hooks")).unwrap();
+        fs::write(working_dir.join("hooks/pre-commit"), "#!/bin/sh\n").unwrap();
+
+        let mut tool_state = ToolState::new();
+        tool_state.working_directory = working_dir.clone();
+        tool_state.journal = None;
+        let state = Arc::new(Mutex::new(tool_state));
+        let mut tool = CreateSymlinkTool::new();
+
+        let link = working_dir.join(".git/hooks/pre-commit");
+        let args = ToolArgs::from_args(&["../../hooks/pre-commit", link.to_str().unwrap()]);
+        let result = tool.execute(&args, &state).unwrap();
+        assert!(result.success, "{}", result.message);
+        assert_eq!(fs::read_to_string(&link).unwrap(), "#!/bin/sh\n");
+        assert_eq!(result.data.unwrap()["dangling"], false);
+
+        let escape = working_dir.join("escape");
+        let args = ToolArgs::from_args(&["/etc/passwd", escape.to_str().unwrap()]);
+        let result = tool.execute(&args, &state).unwrap();
+        assert!(!result.success);
+        assert!(result.message.starts_with("Sandbox check"));
+        assert!(fs::symlink_metadata(&escape).is_err());
+    }
 }
@@ -0,0 +1,748 @@
+//! create_migration tool: new database migrations in the project's own layout
+//!
+//! Migration tools find migrations by directory and file name conventions that differ
+//! per tool, and order them by a timestamp or revision chain that a hand-written file
+//! easily gets wrong. This module detects diesel, sqlx, alembic and knex layouts and
+//! writes the files each expects: timestamped after every existing migration, and for
+//! alembic chained onto the current head revision.
+
+use super::writer;
+use crate::core::{DangerLevel, Tool, ToolArgs, ToolCategory, ToolError, ToolResult};
+use crate::state::ToolState;
+use anyhow::Result;
+use regex::Regex;
+use serde::Serialize;
+use sha2::{Digest, Sha256};
+use std::fs;
+use std::path::{Path, PathBuf};
+use std::sync::{Arc, Mutex};
+use std::time::SystemTime;
+
+/// Migration tools whose layout `create_migration` writes
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "snake_case")]
+pub enum MigrationLayout {
+    /// `migrations/YYYY-MM-DD-HHMMSS_name/{up,down}.sql`
+    Diesel,
+    /// `migrations/YYYYMMDDHHMMSS_name.sql`, or `.up.sql`/`.down.sql` pairs
+    Sqlx,
+    /// `<script_location>/versions/<revision>_name.py` chained by `down_revision`
+    Alembic,
+    /// `migrations/YYYYMMDDHHMMSS_name.js` (or `.ts`) exporting `up` and `down`
+    Knex,
+}
+
+impl MigrationLayout {
+    /// Parse a layout name as accepted by the `layout` tool parameter
+    pub fn from_name(name: &str) -> Option<Self> {
+        match name.to_lowercase().as_str() {
+            "diesel" => Some(Self::Diesel),
+            "sqlx" => Some(Self::Sqlx),
+            "alembic" => Some(Self::Alembic),
+            "knex" => Some(Self::Knex),
+            _ => None,
+        }
+    }
+
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            Self::Diesel => "diesel",
+            Self::Sqlx => "sqlx",
+            Self::Alembic => "alembic",
+            Self::Knex => "knex",
+        }
+    }
+
+    /// Detect the layout of the project in `root` and its migrations directory
+    pub fn detect(root: &Path) -> Option<(Self, PathBuf)> {
+        if let Ok(ini) = fs::read_to_string(root.join("alembic.ini")) {
+            let location = ini
+                .lines()
+                .filter_map(|line| line.split_once('='))
+                .find(|(key, _)| key.trim() == "script_location")
+                .map(|(_, value)| value.trim().trim_start_matches("%(here)s/").to_string())
+                .unwrap_or_else(|| "alembic".to_string());
+            return Some((Self::Alembic, root.join(location).join("versions")));
+        }
+        let knex_directory =
+            Regex::new(r#"directory\s*:\s*['"]([^'"]+)['"]"#).expect("valid regex");
+        for knexfile in ["knexfile.js", "knexfile.ts", "knexfile.cjs", "knexfile.mjs"] {
+            if let Ok(config) = fs::read_to_string(root.join(knexfile)) {
+                let directory = knex_directory
+                    .captures(&config)
+                    .map(|c| c[1].to_string())
+                    .unwrap_or_else(|| "migrations".to_string());
+                return Some((Self::Knex, root.join(directory)));
+            }
+        }
+        if let Ok(config) = fs::read_to_string(root.join("diesel.toml")) {
+            let directory = toml::from_str::<toml::Value>(&config)
+                .ok()
+                .and_then(|config| {
+                    config
+                        .get("migrations_directory")?
+                        .get("dir")?
+                        .as_str()
+                        .map(str::to_string)
+                })
+                .unwrap_or_else(|| "migrations".to_string());
+            return Some((Self::Diesel, root.join(directory)));
+        }
+
+        // Otherwise go by the names of existing migrations, then the crate's dependencies
+        let dir = root.join("migrations");
+        let names = migration_names(&dir);
+        if names.iter().any(|name| diesel_version(name).is_some()) {
+            return Some((Self::Diesel, dir));
+        }
+        if names
+            .iter()
+            .any(|name| numeric_version(name).is_some() && name.ends_with(".sql"))
+        {
+            return Some((Self::Sqlx, dir));
+        }
+        let manifest = fs::read_to_string(root.join("Cargo.toml")).unwrap_or_default();
+        let depends_on = |name: &str| {
+            manifest
+                .lines()
+                .any(|line| line.trim_start().starts_with(name) && line.contains('='))
+        };
+        if depends_on("sqlx") {
+            Some((Self::Sqlx, dir))
+        } else if depends_on("diesel") {
+            Some((Self::Diesel, dir))
+        } else {
+            None
+        }
+    }
+}
+
+/// Entry names of the migrations directory
+fn migration_names(dir: &Path) -> Vec<String> {
+    fs::read_dir(dir)
+        .map(|entries| {
+            entries
+                .filter_map(|entry| entry.ok())
+                .map(|entry| entry.file_name().to_string_lossy().to_string())
+                .collect()
+        })
+        .unwrap_or_default()
+}
+
+/// Version of a `YYYYMMDDHHMMSS_name` migration
+fn numeric_version(name: &str) -> Option<u64> {
+    let (version, _) = name.split_once('_')?;
+    (version.len() == 14 && version.chars().all(|c| c.is_ascii_digit()))
+        .then(|| version.parse().ok())
+        .flatten()
+}
+
+/// Version of a `YYYY-MM-DD-HHMMSS_name` diesel migration, as `YYYYMMDDHHMMSS`
+fn diesel_version(name: &str) -> Option<u64> {
+    let (version, _) = name.split_once('_')?;
+    if version.len() != 17 {
+        return None;
+    }
+    let digits: String = version.chars().filter(|c| *c != '-').collect();
+    (digits.len() == 14 && digits.chars().all(|c| c.is_ascii_digit()))
+        .then(|| digits.parse().ok())
+        .flatten()
+}
+
+/// `now` as `YYYYMMDDHHMMSS` in UTC
+fn timestamp(now: SystemTime) -> u64 {
+    humantime::format_rfc3339_seconds(now)
+        .to_string()
+        .chars()
+        .filter(char::is_ascii_digit)
+        .collect::<String>()
+        .parse()
+        .unwrap_or(0)
+}
+
+/// Migration name as a file name part: lowercase words joined by `_`
+pub fn slugify(name: &str) -> String {
+    let mut slug = String::new();
+    for c in name.trim().chars() {
+        if c.is_ascii_alphanumeric() {
+            slug.push(c.to_ascii_lowercase());
+        } else if !slug.ends_with('_') && !slug.is_empty() {
+            slug.push('_');
+        }
+    }
+    slug.trim_end_matches('_').to_string()
+}
+
+/// `revision` and `down_revision` of the alembic migrations in `versions`, as
+/// (revision, down revisions) pairs
+fn alembic_revisions(versions: &Path) -> Vec<(String, Vec<String>)> {
+    let assignment = |text: &str, key: &str| -> Option<String> {
+        text.lines().find_map(|line| {
+            let (name, value) = line.split_once('=')?;
+            let name = name.split(':').next()?.trim();
+            (name == key).then(|| value.trim().to_string())
+        })
+    };
+    let quoted = Regex::new(r#"['"]([0-9A-Za-z_]+)['"]"#).expect("valid regex");
+    let mut revisions = Vec::new();
+    for name in migration_names(versions) {
+        if !name.ends_with(".py") {
+            continue;
+        }
+        let Ok(text) = fs::read_to_string(versions.join(&name)) else {
+            continue;
+        };
+        let Some(revision) = assignment(&text, "revision")
+            .and_then(|value| quoted.captures(&value).map(|c| c[1].to_string()))
+        else {
+            continue;
+        };
+        let down = assignment(&text, "down_revision")
+            .map(|value| {
+                quoted
+                    .captures_iter(&value)
+                    .map(|c| c[1].to_string())
+                    .collect()
+            })
+            .unwrap_or_default();
+        revisions.push((revision, down));
+    }
+    revisions
+}
+
+/// Python string literal holding `sql` for `op.execute`
+fn python_sql(sql: &str) -> String {
+    format!(
+        "\"\"\"\n{}\n\"\"\"",
+        sql.trim().replace("\"\"\"", "\\\"\\\"\\\"")
+    )
+}
+
+/// JavaScript template literal holding `sql` for `knex.raw`
+fn javascript_sql(sql: &str) -> String {
+    format!(
+        "`\n{}\n`",
+        sql.trim()
+            .replace('\\', "\\\\")
+            .replace('`', "\\`")
+            .replace("${", "\\${")
+    )
+}
+
+/// What `create_migration` writes
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct MigrationPlan {
+    /// Version (timestamp or alembic revision) of the new migration
+    pub version: String,
+    /// Files to create with their content
+    pub files: Vec<(PathBuf, String)>,
+    /// The alembic revision the migration follows
+    pub down_revision: Option<String>,
+}
+
+/// Files of a new migration `name` in `dir`, ordered after the existing ones
+pub fn plan_migration(
+    layout: MigrationLayout,
+    dir: &Path,
+    name: &str,
+    up_sql: Option<&str>,
+    down_sql: Option<&str>,
+    now: SystemTime,
+) -> Result<MigrationPlan, String> {
+    let slug = slugify(name);
+    if slug.is_empty() {
+        return Err(format!(
+            "Migration name '{}' has no letters or digits",
+            name
+        ));
+    }
+    let names = migration_names(dir);
+    let existing = |version: fn(&str) -> Option<u64>| names.iter().filter_map(|n| version(n)).max();
+    let next = |latest: Option<u64>| {
+        let now = timestamp(now);
+        latest.map_or(now, |latest| now.max(latest + 1))
+    };
+    let up = up_sql.map(str::trim).filter(|sql| !sql.is_empty());
+    let down = down_sql.map(str::trim).filter(|sql| !sql.is_empty());
+
+    let plan = match layout {
+        MigrationLayout::Diesel => {
+            let digits = next(existing(diesel_version)).to_string();
+            let version = format!(
+                "{}-{}-{}-{}",
+                &digits[..4],
+                &digits[4..6],
+                &digits[6..8],
+                &digits[8..]
+            );
+            let migration = dir.join(format!("{}_{}", version, slug));
+            MigrationPlan {
+                files: vec![
+                    (
+                        migration.join("up.sql"),
+                        format!("{}\n", up.unwrap_or("-- Your SQL goes here")),
+                    ),
+                    (
+                        migration.join("down.sql"),
+                        format!(
+                            "{}\n",
+                            down.unwrap_or("-- This file should undo anything in `up.sql`")
+                        ),
+                    ),
+                ],
+                version,
+                down_revision: None,
+            }
+        }
+        MigrationLayout::Sqlx => {
+            let version = next(existing(numeric_version)).to_string();
+            // Reversible when asked for a down migration or when the project already is
+            let reversible = down.is_some() || names.iter().any(|n| n.ends_with(".up.sql"));
+            let base = format!("{}_{}", version, slug);
+            let up = format!("{}\n", up.unwrap_or("-- Add migration script here"));
+            let files = if reversible {
+                vec![
+                    (dir.join(format!("{}.up.sql", base)), up),
+                    (
+                        dir.join(format!("{}.down.sql", base)),
+                        format!("{}\n", down.unwrap_or("-- Add down migration script here")),
+                    ),
+                ]
+            } else {
+                vec![(dir.join(format!("{}.sql", base)), up)]
+            };
+            MigrationPlan {
+                version,
+                files,
+                down_revision: None,
+            }
+        }
+        MigrationLayout::Knex => {
+            let version = next(existing(numeric_version)).to_string();
+            let typescript = dir
+                .parent()
+                .is_some_and(|root| root.join("knexfile.ts").is_file())
+                || names.iter().any(|n| n.ends_with(".ts"));
+            let body = |sql: Option<&str>| match sql {
+                Some(sql) => format!("knex.raw({})", javascript_sql(sql)),
+                None => "Promise.resolve()".to_string(),
+            };
+            let (extension, content) = if typescript {
+                (
+                    "ts",
+                    format!(
+                        "import type {{ Knex }} from \"knex\";\n\nexport async function up(knex: Knex): Promise<void> {{\n  await {};\n}}\n\nexport async function down(knex: Knex): Promise<void> {{\n  await {};\n}}\n",
+                        body(up),
+                        body(down)
+                    ),
+                )
+            } else {
+                (
+                    "js",
+                    format!(
+                        "exports.up = function (knex) {{\n  return {};\n}};\n\nexports.down = function (knex) {{\n  return {};\n}};\n",
+                        body(up),
+                        body(down)
+                    ),
+                )
+            };
+            MigrationPlan {
+                files: vec![(
+                    dir.join(format!("{}_{}.{}", version, slug, extension)),
+                    content,
+                )],
+                version,
+                down_revision: None,
+            }
+        }
+        MigrationLayout::Alembic => {
+            let revisions = alembic_revisions(dir);
+            let heads: Vec<&String> = revisions
+                .iter()
+                .map(|(revision, _)| revision)
+                .filter(|revision| {
+                    !revisions
+                        .iter()
+                        .any(|(_, down)| down.iter().any(|d| d == *revision))
+                })
+                .collect();
+            if heads.len() > 1 {
+                let mut heads: Vec<&str> = heads.iter().map(|h| h.as_str()).collect();
+                heads.sort();
+                return Err(format!(
+                    "Alembic has several heads ({}); merge them first (alembic merge heads)",
+                    heads.join(", ")
+                ));
+            }
+            let down_revision = heads.first().map(|head| head.to_string());
+            let digest = Sha256::digest(format!("{}{}{:?}", slug, timestamp(now), down_revision));
+            let version: String = digest
+                .iter()
+                .take(6)
+                .map(|byte| format!("{:02x}", byte))
+                .collect();
+            let created = humantime::format_rfc3339_seconds(now)
+                .to_string()
+                .replace('T', " ")
+                .trim_end_matches('Z')
+                .to_string();
+            let body = |sql: Option<&str>| match sql {
+                Some(sql) => format!("    op.execute({})", python_sql(sql)),
+                None => "    pass".to_string(),
+            };
+            let content = format!(
+                "\"\"\"{}\n\nRevision ID: {}\nRevises: {}\nCreate Date: {}\n\n\"\"\"\nfrom alembic import op\n\n\n# revision identifiers, used by Alembic.\nrevision = '{}'\ndown_revision = {}\nbranch_labels = None\ndepends_on = None\n\n\ndef upgrade():\n{}\n\n\ndef downgrade():\n{}\n",
+                name.trim(),
+                version,
+                down_revision.as_deref().unwrap_or(""),
+                created,
+                version,
+                down_revision
+                    .as_ref()
+                    .map_or("None".to_string(), |down| format!("'{}'", down)),
+                body(up),
+                body(down)
+            );
+            MigrationPlan {
+                files: vec![(dir.join(format!("{}_{}.py", version, slug)), content)],
+                version,
+                down_revision,
+            }
+        }
+    };
+    Ok(plan)
+}
+
+/// Tool creating a migration in the project's migration layout
+pub struct CreateMigrationTool {
+    name: String,
+}
+
+impl CreateMigrationTool {
+    pub fn new() -> Self {
+        Self {
+            name: "create_migration".to_string(),
+        }
+    }
+}
+
+impl Default for CreateMigrationTool {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl Tool for CreateMigrationTool {
+    fn name(&self) -> &str {
+        &self.name
+    }
+
+    fn description(&self) -> &str {
+        "Create a database migration in the project's layout (diesel, sqlx, alembic or knex, auto-detected): correctly timestamped or revision-chained files in the migrations directory, holding up_sql and down_sql"
+    }
+
+    fn signature(&self) -> &str {
+        "create_migration(name: str, up_sql?: str, down_sql?: str, layout?: diesel|sqlx|alembic|knex, dir?: str, path?: str)"
+    }
+
+    fn category(&self) -> ToolCategory {
+        ToolCategory::Editing
+    }
+
+    fn danger_level(&self) -> DangerLevel {
+        DangerLevel::ModifiesFiles
+    }
+
+    fn validate_args(&self, args: &ToolArgs) -> Result<(), ToolError> {
+        if args
+            .get_named_arg("name")
+            .or_else(|| args.get_arg(0))
+            .is_none()
+        {
+            return Err(ToolError::InvalidArgs {
+                message: "Missing required parameter: name".to_string(),
+            });
+        }
+        if let Some(layout) = args.get_named_arg("layout") {
+            if MigrationLayout::from_name(layout).is_none() {
+                return Err(ToolError::InvalidArgs {
+                    message: format!(
+                        "Unknown migration layout '{}'. Expected one of: diesel, sqlx, alembic, knex",
+                        layout
+                    ),
+                });
+            }
+        }
+        Ok(())
+    }
+
+    fn accessed_paths(&self, args: &ToolArgs) -> Vec<PathBuf> {
+        // `dir` is relative to the project root
+        let root = PathBuf::from(args.get_named_arg("path").map_or(".", String::as_str));
+        let mut paths: Vec<PathBuf> = args
+            .get_named_arg("dir")
+            .map(|dir| root.join(dir))
+            .into_iter()
+            .collect();
+        paths.push(root);
+        paths
+    }
+
+    fn execute(&mut self, args: &ToolArgs, state: &Arc<Mutex<ToolState>>) -> Result<ToolResult> {
+        let name = args
+            .get_named_arg("name")
+            .or_else(|| args.get_arg(0))
+            .ok_or_else(|| anyhow::anyhow!("Missing required parameter: name"))?;
+        let root = match args.get_named_arg("path") {
+            Some(path) => PathBuf::from(path),
+            None => {
+                let state_guard = state
+                    .lock()
+                    .map_err(|e| anyhow::anyhow!("Failed to lock state: {}", e))?;
+                state_guard.working_directory.clone()
+            }
+        };
+        if !root.is_dir() {
+            return Ok(crate::utils::suggest::path_not_found(
+                format!("Directory {} not found", root.display()),
+                &root,
+            ));
+        }
+
+        let detected = MigrationLayout::detect(&root);
+        let layout = match args
+            .get_named_arg("layout")
+            .and_then(|layout| MigrationLayout::from_name(layout))
+            .or(detected.as_ref().map(|(layout, _)| *layout))
+        {
+            Some(layout) => layout,
+            None => {
+                return Ok(ToolResult::error(format!(
+                    "No migration layout detected in {} (looked for alembic.ini, knexfile.js, diesel.toml and migrations/). Pass layout=diesel|sqlx|alembic|knex",
+                    root.display()
+                )))
+            }
+        };
+        let dir = match (args.get_named_arg("dir"), &detected) {
+            (Some(dir), _) => root.join(dir),
+            (None, Some((detected, dir))) if *detected == layout => dir.clone(),
+            (None, _) if layout == MigrationLayout::Alembic => {
+                root.join("alembic").join("versions")
+            }
+            (None, _) => root.join("migrations"),
+        };
+
+        let plan = match plan_migration(
+            layout,
+            &dir,
+            name,
+            args.get_named_arg("up_sql").map(String::as_str),
+            args.get_named_arg("down_sql").map(String::as_str),
+            SystemTime::now(),
+        ) {
+            Ok(plan) => plan,
+            Err(message) => return Ok(ToolResult::error(message)),
+        };
+        if let Some((existing, _)) = plan.files.iter().find(|(path, _)| path.exists()) {
+            return Ok(ToolResult::error(format!(
+                "{} already exists",
+                existing.display()
+            )));
+        }
+
+        for (path, content) in &plan.files {
+            if let Some(parent) = path.parent().filter(|parent| !parent.exists()) {
+                writer::create_dir_all(state, parent)
+                    .map_err(|e| anyhow::anyhow!("Failed to create {}: {}", parent.display(), e))?;
+            }
+            writer::write_file(state, path, content)
+                .map_err(|e| anyhow::anyhow!("Failed to write {}: {}", path.display(), e))?;
+        }
+        let created: Vec<String> = plan
+            .files
+            .iter()
+            .map(|(path, _)| path.display().to_string())
+            .collect();
+        if let Ok(mut state_guard) = state.lock() {
+            state_guard.push_history(format!(
+                "create_migration: {} {} ({})",
+                layout.as_str(),
+                plan.version,
+                created.join(", ")
+            ));
+        }
+
+        let mut message = format!(
+            "Created {} migration {}:\n{}",
+            layout.as_str(),
+            plan.version,
+            created
+                .iter()
+                .map(|path| format!("  {}", path))
+                .collect::<Vec<_>>()
+                .join("\n")
+        );
+        if let Some(down_revision) = &plan.down_revision {
+            message.push_str(&format!("\nRevises {}", down_revision));
+        }
+        Ok(ToolResult::success_with_data(
+            message,
+            serde_json::json!({
+                "layout": layout,
+                "directory": dir.display().to_string(),
+                "version": plan.version,
+                "down_revision": plan.down_revision,
+                "files": created,
+            }),
+        ))
+    }
+
+    fn get_parameters_schema(&self) -> serde_json::Value {
+        serde_json::json!({
+            "type": "object",
+            "properties": {
+                "name": {
+                    "type": "string",
+                    "description": "What the migration does, e.g. 'create users table'"
+                },
+                "up_sql": {
+                    "type": "string",
+                    "description": "SQL applying the migration (a placeholder comment when omitted)"
+                },
+                "down_sql": {
+                    "type": "string",
+                    "description": "SQL reverting the migration"
+                },
+                "layout": {
+                    "type": "string",
+                    "enum": ["diesel", "sqlx", "alembic", "knex"],
+                    "description": "Migration tool; detected from the project when omitted"
+                },
+                "dir": {
+                    "type": "string",
+                    "description": "Migrations directory relative to path (detected when omitted)"
+                },
+                "path": {
+                    "type": "string",
+                    "description": "Project root (defaults to the working directory)"
+                }
+            },
+            "required": ["name"]
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::time::{Duration, UNIX_EPOCH};
+    use tempfile::TempDir;
+
+    // 2024-01-31T12:00:00Z
+    fn now() -> SystemTime {
+        UNIX_EPOCH + Duration::from_secs(1_706_702_400)
+    }
+
+    #[test]
+    fn test_plan_timestamped_layouts() {
+        let temp_dir = TempDir::new().unwrap();
+        let dir = temp_dir.path().join("migrations");
+        fs::create_dir_all(dir.join("2099-01-01-000000_future")).unwrap();
+        assert_eq!(
+            MigrationLayout::detect(temp_dir.path()),
+            Some((MigrationLayout::Diesel, dir.clone()))
+        );
+
+        // Ordered after an existing migration with a later timestamp
+        let plan = plan_migration(
+            MigrationLayout::Diesel,
+            &dir,
+            "Create users",
+            Some("CREATE TABLE users (id INT);"),
+            None,
+            now(),
+        )
+        .unwrap();
+        assert_eq!(plan.version, "2099-01-01-000001");
+        assert_eq!(
+            plan.files[0],
+            (
+                dir.join("2099-01-01-000001_create_users/up.sql"),
+                "CREATE TABLE users (id INT);\n".to_string()
+            )
+        );
+
+        let plan =
+            plan_migration(MigrationLayout::Sqlx, &dir, "add-index", None, None, now()).unwrap();
+        assert_eq!(plan.files.len(), 1);
+        assert!(plan.files[0].0.ends_with("20240131120000_add_index.sql"));
+
+        let plan = plan_migration(
+            MigrationLayout::Knex,
+            &dir,
+            "seed",
+            Some("SELECT `x`"),
+            None,
+            now(),
+        )
+        .unwrap();
+        assert!(plan.files[0].0.ends_with("20240131120000_seed.js"));
+        assert_eq!(
+            plan.files[0].1,
+            "exports.up = function (knex) {\n  return knex.raw(`\nSELECT \\`x\\`\n`);\n};\n\nexports.down = function (knex) {\n  return Promise.resolve();\n};\n"
+        );
+    }
+
+    #[test]
+    fn test_alembic_revision_chain() {
+        let temp_dir = TempDir::new().unwrap();
+        let versions = temp_dir.path().join("db/versions");
+        fs::create_dir_all(&versions).unwrap();
+        fs::write(
+            temp_dir.path().join("alembic.ini"),
+            "[alembic]\nscript_location = %(here)s/db\n",
+        )
+        .unwrap();
+        fs::write(
+            versions.join("aaa_first.py"),
+            "revision = 'aaa'\ndown_revision = None\n",
+        )
+        .unwrap();
+        fs::write(
+            versions.join("bbb_second.py"),
+            "revision: str = \"bbb\"\ndown_revision: Union[str, None] = 'aaa'\n",
+        )
+        .unwrap();
+
+        let mut tool = CreateMigrationTool::new();
+        let state = Arc::new(Mutex::new(ToolState::new()));
+        let args = ToolArgs::from_args(&[
+            "--name=add email",
+            "--up_sql=ALTER TABLE users ADD email TEXT",
+            &format!("--path={}", temp_dir.path().display()),
+        ]);
+        let result = tool.execute(&args, &state).unwrap();
+        assert!(result.success, "{}", result.message);
+        assert!(result.message.ends_with("\nRevises bbb"));
+        let file = result.data.unwrap()["files"][0]
+            .as_str()
+            .unwrap()
+            .to_string();
+        assert!(file.ends_with("_add_email.py"));
+        let content = fs::read_to_string(&file).unwrap();
+        assert!(content.contains("down_revision = 'bbb'\n"));
+        assert!(content.contains(
+            "def upgrade():\n    op.execute(\"\"\"\nALTER TABLE users ADD email TEXT\n\"\"\")\n\n\ndef downgrade():\n    pass\n"
+        ));
+
+        // A second head needs merging first
+        fs::write(
+            versions.join("ccc_branch.py"),
+            "revision = 'ccc'\ndown_revision = 'aaa'\n",
+        )
+        .unwrap();
+        let error = plan_migration(MigrationLayout::Alembic, &versions, "x", None, None, now())
+            .unwrap_err();
+        assert!(error.contains("several heads"), "{}", error);
+    }
+}
@@ -1,24 +1,69 @@
 //! Enhanced file editing tools with advanced matching and normalization
 
+pub mod artifacts;
+#[cfg(feature = "ast")]
+pub mod callers;
+pub mod diff;
+pub mod env_file;
+pub mod json_format;
 pub mod management_tools;
+pub mod migrations;
+pub mod normalize;
+pub mod patch;
+pub mod protection;
+pub mod refactor;
+pub mod span;
 pub mod specialized_tools;
+pub mod syntax;
+pub mod trash;
+pub mod truncation;
+pub mod writer;
 
 // Re-export new specialized tools
-pub use management_tools::{CopyPathTool, CreateDirectoryTool, DeletePathTool, MovePathTool};
+#[cfg(feature = "ast")]
+pub use callers::UpdateCallersTool;
+pub use env_file::{EnvDiffTool, EnvSyncTool};
+pub use json_format::FormatJsonTool;
+pub use management_tools::{
+    CopyPathTool, CreateDirectoryTool, CreateSymlinkTool, DeletePathTool, MovePathTool,
+    PurgeTrashTool, SetPermissionsTool, TouchTool,
+};
+pub use migrations::CreateMigrationTool;
+pub use normalize::NormalizedText;
+pub use patch::ApplyPatchTool;
+pub use refactor::RenameModuleTool;
+#[cfg(feature = "ast")]
+pub use specialized_tools::DeleteFunctionTool;
 pub use specialized_tools::{
-    CreateFileTool, DeleteFunctionTool, DeleteLineTool, DeleteTextTool, InsertTextTool,
-    OverwriteFileTool, ReplaceTextTool,
+    CreateFileTool, DeleteLineTool, DeleteTextTool, InsertTextTool, OverwriteFileTool,
+    ReplaceTextTool,
 };
 
-use crate::core::{Tool, ToolArgs, ToolError, ToolResult};
-use crate::state::ToolState;
-use anyhow::Result;
+use crate::core::{DangerLevel, Tool, ToolArgs, ToolCategory, ToolError, ToolResult};
+use crate::profiling;
+use crate::state::{session_path, ToolState};
+use crate::utils::suggest::path_not_found;
+use anyhow::{Context, Result};
+use diff::DiffPreview;
 use regex::Regex;
 use serde::{Deserialize, Serialize};
+use span::Span;
 use std::fs;
 use std::path::PathBuf;
 use std::sync::{Arc, Mutex};
 
+/// Read a boolean parameter given either as a JSON bool or as a "true"/"false" string
+pub(crate) fn bool_param(
+    obj: &serde_json::Map<String, serde_json::Value>,
+    key: &str,
+) -> Option<bool> {
+    match obj.get(key)? {
+        serde_json::Value::Bool(b) => Some(*b),
+        serde_json::Value::String(s) => s.parse().ok(),
+        _ => None,
+    }
+}
+
 /// Edit operation modes
 #[derive(Debug, Clone, Serialize, Deserialize)]
 #[serde(rename_all = "lowercase")]
@@ -112,32 +157,7 @@ impl EditTool {
 
     /// Normalize text according to options
     fn normalize_text(&self, text: &str, options: &NormalizationOptions) -> String {
-        let mut result = text.to_string();
-
-        if options.normalize_eol {
-            result = result.replace("\r\n", "\n").replace("\r", "\n");
-        }
-
-        if options.trim_lines {
-            result = result
-                .lines()
-                .map(|line| line.trim_end())
-                .collect::<Vec<_>>()
-                .join("\n");
-        }
-
-        if options.normalize_whitespace {
-            result = Regex::new(r"\s+")
-                .unwrap()
-                .replace_all(&result, " ")
-                .to_string();
-        }
-
-        if options.ignore_case {
-            result = result.to_lowercase();
-        }
-
-        result
+        NormalizedText::new(text, options).into_string()
     }
 
     /// Calculate similarity between two strings
@@ -167,29 +187,28 @@ impl EditTool {
         options: &MatchingOptions,
         norm_options: &NormalizationOptions,
     ) -> Result<Vec<MatchInfo>, ToolError> {
-        let normalized_content = self.normalize_text(content, norm_options);
+        // Matches are found in the normalized text and mapped back to `content`
+        let normalized = NormalizedText::new(content, norm_options);
+        let normalized_content = normalized.as_str();
         let normalized_pattern = self.normalize_text(pattern, norm_options);
 
         let mut matches = Vec::new();
 
         if options.regex {
             // Regex matching
-            let regex = if norm_options.ignore_case {
-                Regex::new(&format!("(?i){}", normalized_pattern))
-            } else {
-                Regex::new(&normalized_pattern)
-            }
-            .map_err(|e| ToolError::Regex(e))?;
+            let regex =
+                crate::utils::safe_regex::compile(&normalized_pattern, norm_options.ignore_case)?;
 
             for (i, m) in regex
-                .find_iter(&normalized_content)
+                .find_iter(normalized_content)
                 .enumerate()
                 .take(options.max_matches)
             {
+                let span = normalized.to_original(m.start(), m.end());
                 let match_info = self.create_match_info(
                     content,
-                    m.start(),
-                    m.end(),
+                    span.start(),
+                    span.end(),
                     i + 1,
                     options.context_lines,
                     pattern,
@@ -205,11 +224,12 @@ impl EditTool {
             while let Some(pos) = normalized_content[start..].find(&normalized_pattern) {
                 let actual_pos = start + pos;
                 let end_pos = actual_pos + normalized_pattern.len();
+                let span = normalized.to_original(actual_pos, end_pos);
 
                 let match_info = self.create_match_info(
                     content,
-                    actual_pos,
-                    end_pos,
+                    span.start(),
+                    span.end(),
                     index,
                     options.context_lines,
                     pattern,
@@ -243,6 +263,15 @@ impl EditTool {
         norm_options: &NormalizationOptions,
     ) -> Result<Vec<MatchInfo>, ToolError> {
         let lines: Vec<&str> = content.lines().collect();
+        // Byte offset of each line, counting `\r\n` terminators that `lines()` strips
+        let line_offsets: Vec<usize> = content
+            .split_inclusive('\n')
+            .scan(0, |offset, line| {
+                let start = *offset;
+                *offset += line.len();
+                Some(start)
+            })
+            .collect();
         let normalized_pattern = self.normalize_text(pattern, norm_options);
         let mut matches = Vec::new();
 
@@ -251,7 +280,7 @@ impl EditTool {
             let similarity = self.calculate_similarity(&normalized_line, &normalized_pattern);
 
             if similarity >= options.fuzzy_threshold {
-                let char_start = lines[..line_num].iter().map(|l| l.len() + 1).sum::<usize>();
+                let char_start = line_offsets[line_num];
                 let char_end = char_start + line.len();
 
                 let match_info = MatchInfo {
@@ -296,19 +325,21 @@ impl EditTool {
         _original_pattern: &str,
         new_text: &str,
     ) -> MatchInfo {
-        let matched_text = content[start..end].to_string();
+        // Offsets found in normalized text may not be character boundaries of `content`
+        let span = Span::snap(content, start, end);
+        let matched_text = span.slice(content).to_string();
         let lines: Vec<&str> = content.lines().collect();
 
         // Find line number
-        let line_start = content[..start].lines().count();
-        let line_end = content[..end].lines().count();
+        let line_start = span.start_line(content) - 1;
+        let line_end = line_start + span.slice(content).matches('\n').count();
 
         MatchInfo {
             index,
             line_start: line_start + 1,
             line_end: line_end + 1,
-            char_start: start,
-            char_end: end,
+            char_start: span.start(),
+            char_end: span.end(),
             matched_text: matched_text.clone(),
             context_before: self.get_context_before(lines.as_slice(), line_start, context_lines),
             context_after: self.get_context_after(lines.as_slice(), line_end, context_lines),
@@ -329,16 +360,16 @@ impl EditTool {
 
     /// Get context lines after the match
     fn get_context_after(&self, lines: &[&str], line_num: usize, context_lines: usize) -> String {
+        let start = (line_num + 1).min(lines.len());
         let end = (line_num + 1 + context_lines).min(lines.len());
-        lines[(line_num + 1)..end].join("\n")
+        lines[start..end].join("\n")
     }
 
     /// Apply edit to content
     fn apply_edit(&self, content: &str, match_info: &MatchInfo, new_text: &str) -> String {
-        let mut result = String::new();
-        result.push_str(&content[..match_info.char_start]);
-        result.push_str(new_text);
-        result.push_str(&content[match_info.char_end..]);
+        let mut result = content.to_string();
+        Span::snap(content, match_info.char_start, match_info.char_end)
+            .replace(&mut result, new_text);
         result
     }
 
@@ -404,6 +435,15 @@ impl Tool for EditTool {
         "edit {\"path\": \"file.txt\", \"old_text\": \"search\", \"new_text\": \"replace\", ...}"
     }
 
+    fn category(&self) -> ToolCategory {
+        ToolCategory::Editing
+    }
+
+    fn danger_level(&self) -> DangerLevel {
+        // The `overwrite` mode replaces whole files
+        DangerLevel::Destructive
+    }
+
     fn validate_args(&self, args: &ToolArgs) -> Result<(), ToolError> {
         let params = self.parse_params(args)?;
 
@@ -442,7 +482,7 @@ impl Tool for EditTool {
                 .ok_or_else(|| ToolError::InvalidArgs {
                     message: "Invalid path".to_string(),
                 })?;
-        let path_buf = PathBuf::from(path);
+        let path_buf = session_path(state, path);
 
         let mode = obj
             .get("mode")
@@ -653,7 +693,8 @@ impl EditTool {
                 .map_err(|e| anyhow::anyhow!("Failed to create parent directories: {}", e))?;
         }
 
-        fs::write(path, content).map_err(|e| anyhow::anyhow!("Failed to create file: {}", e))?;
+        writer::write_file(state, path, content)
+            .map_err(|e| anyhow::anyhow!("Failed to create file: {}", e))?;
 
         // Update state
         {
@@ -687,14 +728,13 @@ impl EditTool {
         state: &Arc<Mutex<ToolState>>,
     ) -> Result<ToolResult> {
         if !path.exists() {
-            return Ok(ToolResult::error(format!(
-                "File not found: {}",
-                path.display()
-            )));
+            return Ok(path_not_found(
+                format!("File not found: {}", path.display()),
+                path,
+            ));
         }
 
-        let content =
-            fs::read_to_string(path).map_err(|e| anyhow::anyhow!("Failed to read file: {}", e))?;
+        let content = profiling::read_to_string(path).context("Failed to read file")?;
 
         let matches = self.find_matches(&content, old_text, match_options, norm_options)?;
 
@@ -747,26 +787,29 @@ impl EditTool {
             &matches[0] // Single match
         };
 
+        let new_content = self.apply_edit(&content, selected_match, new_text);
+        let diff = DiffPreview::new(&content, &new_content, &path.to_string_lossy());
+
         if preview {
             return Ok(ToolResult::success_with_data(
-                format!(
+                diff.append_to(format!(
                     "Preview: Would replace occurrence {} at line {}",
                     selected_match.index, selected_match.line_start
-                ),
+                )),
                 serde_json::json!({
                     "path": path.to_string_lossy(),
                     "mode": "replace",
                     "preview": true,
                     "match_info": selected_match,
-                    "change_preview": selected_match.preview
+                    "change_preview": selected_match.preview,
+                    "diff": diff
                 }),
             ));
         }
 
         // Apply the edit
-        let new_content = self.apply_edit(&content, selected_match, new_text);
 
-        fs::write(path, &new_content)
+        writer::write_file(state, path, &new_content)
             .map_err(|e| anyhow::anyhow!("Failed to write file: {}", e))?;
 
         // Update state
@@ -774,7 +817,7 @@ impl EditTool {
             let mut state_guard = state
                 .lock()
                 .map_err(|e| anyhow::anyhow!("Failed to lock state: {}", e))?;
-            if state_guard.current_file.as_ref() == Some(path) {
+            if state_guard.is_current_file(path) {
                 let lines: Vec<String> = new_content.lines().map(|s| s.to_string()).collect();
                 state_guard.open_file(path.clone(), lines, 100)?;
             }
@@ -788,14 +831,15 @@ impl EditTool {
         let chars_changed = new_text.len() as i64 - selected_match.matched_text.len() as i64;
 
         Ok(ToolResult::success_with_data(
-            format!(
+            diff.append_to(format!(
                 "Successfully replaced occurrence {} in {}",
                 selected_match.index,
                 path.display()
-            ),
+            )),
             serde_json::json!({
                 "path": path.to_string_lossy(),
                 "mode": "replace",
+                "diff": diff,
                 "lines_changed": (selected_match.line_end - selected_match.line_start + 1),
                 "characters_changed": chars_changed,
                 "applied_at": [EditLocation {
@@ -861,30 +905,34 @@ impl EditTool {
         preview: bool,
         state: &Arc<Mutex<ToolState>>,
     ) -> Result<ToolResult> {
+        let original = profiling::read_to_string(path).unwrap_or_default();
+        let diff = DiffPreview::new(&original, content, &path.to_string_lossy());
         if preview {
             return Ok(ToolResult::success_with_data(
-                format!(
+                diff.append_to(format!(
                     "Preview: Would overwrite file {} with {} characters",
                     path.display(),
                     content.len()
-                ),
+                )),
                 serde_json::json!({
                     "path": path.to_string_lossy(),
                     "mode": "overwrite",
                     "preview": true,
-                    "content_length": content.len()
+                    "content_length": content.len(),
+                    "diff": diff
                 }),
             ));
         }
 
-        fs::write(path, content).map_err(|e| anyhow::anyhow!("Failed to overwrite file: {}", e))?;
+        writer::write_file(state, path, content)
+            .map_err(|e| anyhow::anyhow!("Failed to overwrite file: {}", e))?;
 
         // Update state
         {
             let mut state_guard = state
                 .lock()
                 .map_err(|e| anyhow::anyhow!("Failed to lock state: {}", e))?;
-            if state_guard.current_file.as_ref() == Some(path) {
+            if state_guard.is_current_file(path) {
                 let lines: Vec<String> = content.lines().map(|s| s.to_string()).collect();
                 state_guard.open_file(path.clone(), lines, 100)?;
             }
@@ -892,12 +940,13 @@ impl EditTool {
         }
 
         Ok(ToolResult::success_with_data(
-            format!("Successfully overwritten file {}", path.display()),
+            diff.append_to(format!("Successfully overwritten file {}", path.display())),
             serde_json::json!({
                 "path": path.to_string_lossy(),
                 "mode": "overwrite",
                 "content_length": content.len(),
-                "lines_written": content.lines().count()
+                "lines_written": content.lines().count(),
+                "diff": diff
             }),
         ))
     }
@@ -912,14 +961,13 @@ impl EditTool {
         state: &Arc<Mutex<ToolState>>,
     ) -> Result<ToolResult> {
         if !path.exists() {
-            return Ok(ToolResult::error(format!(
-                "File not found: {}",
-                path.display()
-            )));
+            return Ok(path_not_found(
+                format!("File not found: {}", path.display()),
+                path,
+            ));
         }
 
-        let content =
-            fs::read_to_string(path).map_err(|e| anyhow::anyhow!("Failed to read file: {}", e))?;
+        let content = profiling::read_to_string(path).context("Failed to read file")?;
 
         let mut lines: Vec<String> = content.lines().map(|s| s.to_string()).collect();
 
@@ -931,28 +979,30 @@ impl EditTool {
             )));
         }
 
+        // Insert at the specified line (after line_number-1); past the end appends
+        lines.insert(line_number.min(lines.len()), text.to_string());
+        let new_content = lines.join("\n");
+        let diff = DiffPreview::new(&content, &new_content, &path.to_string_lossy());
+
         if preview {
             return Ok(ToolResult::success_with_data(
-                format!(
+                diff.append_to(format!(
                     "Preview: Would insert text at line {} in {}",
                     line_number,
                     path.display()
-                ),
+                )),
                 serde_json::json!({
                     "path": path.to_string_lossy(),
                     "mode": "insert",
                     "preview": true,
                     "line_number": line_number,
-                    "text": text
+                    "text": text,
+                    "diff": diff
                 }),
             ));
         }
 
-        // Insert at the specified line (after line_number-1)
-        lines.insert(line_number, text.to_string());
-
-        let new_content = lines.join("\n");
-        fs::write(path, &new_content)
+        writer::write_file(state, path, &new_content)
             .map_err(|e| anyhow::anyhow!("Failed to write file: {}", e))?;
 
         // Update state
@@ -960,7 +1010,7 @@ impl EditTool {
             let mut state_guard = state
                 .lock()
                 .map_err(|e| anyhow::anyhow!("Failed to lock state: {}", e))?;
-            if state_guard.current_file.as_ref() == Some(path) {
+            if state_guard.is_current_file(path) {
                 state_guard.open_file(path.clone(), lines.clone(), 100)?;
             }
             state_guard.push_history(format!(
@@ -971,17 +1021,18 @@ impl EditTool {
         }
 
         Ok(ToolResult::success_with_data(
-            format!(
+            diff.append_to(format!(
                 "Successfully inserted text at line {} in {}",
                 line_number,
                 path.display()
-            ),
+            )),
             serde_json::json!({
                 "path": path.to_string_lossy(),
                 "mode": "insert",
                 "line_number": line_number,
                 "lines_changed": 1,
-                "total_lines": lines.len()
+                "total_lines": lines.len(),
+                "diff": diff
             }),
         ))
     }
@@ -1025,6 +1076,57 @@ mod tests {
         println!("✅ Edit tool schema is valid for OpenAI function calling!");
     }
 
+    #[test]
+    fn test_find_matches_never_splits_characters() {
+        let tool = EditTool::new();
+        // Lowercasing İ grows it from 2 to 3 bytes, so normalized offsets run past the
+        // original text; the match must be snapped into it rather than panic
+        let content = "İİ 目標 target";
+        let norm_options = NormalizationOptions {
+            ignore_case: true,
+            ..Default::default()
+        };
+        let options = MatchingOptions {
+            fuzzy: false,
+            ..Default::default()
+        };
+        let matches = tool
+            .find_matches(content, "target", &options, &norm_options)
+            .unwrap();
+        assert_eq!(matches.len(), 1);
+        let span = Span::new(content, matches[0].char_start, matches[0].char_end);
+        assert!(span.is_some());
+        assert_eq!(matches[0].line_start, 1);
+        assert_eq!(matches[0].matched_text, "target");
+        let edited = tool.apply_edit(content, &matches[0], "goal");
+        assert_eq!(edited, "İİ 目標 goal");
+    }
+
+    #[test]
+    fn test_find_matches_maps_normalized_offsets_in_crlf_files() {
+        let tool = EditTool::new();
+        let content = "fn a() {}\r\nfn old()  {\r\n    body();\r\n}\r\nfn z() {}\r\n";
+        let norm_options = NormalizationOptions {
+            normalize_eol: true,
+            normalize_whitespace: true,
+            ..Default::default()
+        };
+        let options = MatchingOptions {
+            fuzzy: false,
+            ..Default::default()
+        };
+        let matches = tool
+            .find_matches(content, "fn old() {\n body();\n}", &options, &norm_options)
+            .unwrap();
+        assert_eq!(matches.len(), 1);
+        assert_eq!(matches[0].line_start, 2);
+        assert_eq!(matches[0].line_end, 4);
+        assert_eq!(matches[0].matched_text, "fn old()  {\r\n    body();\r\n}");
+
+        let edited = tool.apply_edit(content, &matches[0], "fn new() {}");
+        assert_eq!(edited, "fn a() {}\r\nfn new() {}\r\nfn z() {}\r\n");
+    }
+
     #[test]
     fn test_edit_tool_mode_validation() {
         let tool = EditTool::new();
@@ -0,0 +1,231 @@
+//! Text normalization that remembers where every character came from
+//!
+//! Matching options (`normalize_eol`, `trim_lines`, `normalize_whitespace`,
+//! `ignore_case`) search a normalized copy of the file, whose byte offsets differ from
+//! the original as soon as a `\r\n` is folded, whitespace is collapsed or a character
+//! changes length when lowercased. [`NormalizedText`] keeps, for each normalized
+//! character, the original byte range it was derived from, so a match found in the
+//! normalized text can be mapped back with [`NormalizedText::to_original`] before the
+//! original is edited or highlighted.
+
+use super::span::Span;
+use super::NormalizationOptions;
+
+/// A normalized character and the original byte range it stands for
+#[derive(Debug, Clone, Copy)]
+struct Piece {
+    ch: char,
+    start: usize,
+    end: usize,
+}
+
+/// Normalized text with a mapping back to the original
+#[derive(Debug, Clone)]
+pub struct NormalizedText {
+    text: String,
+    pieces: Vec<Piece>,
+    /// Normalized byte offset of each piece, for lookups by offset
+    offsets: Vec<usize>,
+    original_len: usize,
+}
+
+fn normalize_eol(pieces: Vec<Piece>) -> Vec<Piece> {
+    let mut out: Vec<Piece> = Vec::with_capacity(pieces.len());
+    let mut iter = pieces.into_iter().peekable();
+    while let Some(piece) = iter.next() {
+        if piece.ch != '\r' {
+            out.push(piece);
+            continue;
+        }
+        // `\r\n` becomes one `\n` covering both bytes; a lone `\r` becomes `\n`
+        let end = match iter.peek() {
+            Some(next) if next.ch == '\n' => iter.next().map_or(piece.end, |n| n.end),
+            _ => piece.end,
+        };
+        out.push(Piece {
+            ch: '\n',
+            start: piece.start,
+            end,
+        });
+    }
+    out
+}
+
+fn trim_lines(pieces: Vec<Piece>) -> Vec<Piece> {
+    let mut out: Vec<Piece> = Vec::with_capacity(pieces.len());
+    let trim = |out: &mut Vec<Piece>| {
+        while out
+            .last()
+            .is_some_and(|p| p.ch != '\n' && p.ch.is_whitespace())
+        {
+            out.pop();
+        }
+    };
+    for piece in pieces {
+        if piece.ch == '\n' {
+            trim(&mut out);
+        }
+        out.push(piece);
+    }
+    trim(&mut out);
+    out
+}
+
+fn normalize_whitespace(pieces: Vec<Piece>) -> Vec<Piece> {
+    let mut out: Vec<Piece> = Vec::with_capacity(pieces.len());
+    let mut in_run = false;
+    for piece in pieces {
+        if !piece.ch.is_whitespace() {
+            in_run = false;
+            out.push(piece);
+        } else if in_run {
+            // Extend the single space to cover the whole run
+            if let Some(space) = out.last_mut() {
+                space.end = piece.end;
+            }
+        } else {
+            in_run = true;
+            out.push(Piece { ch: ' ', ..piece });
+        }
+    }
+    out
+}
+
+fn lowercase(pieces: Vec<Piece>) -> Vec<Piece> {
+    pieces
+        .into_iter()
+        .flat_map(|piece| piece.ch.to_lowercase().map(move |ch| Piece { ch, ..piece }))
+        .collect()
+}
+
+impl NormalizedText {
+    /// Normalize `original` according to `options`
+    pub fn new(original: &str, options: &NormalizationOptions) -> Self {
+        let mut pieces: Vec<Piece> = original
+            .char_indices()
+            .map(|(start, ch)| Piece {
+                ch,
+                start,
+                end: start + ch.len_utf8(),
+            })
+            .collect();
+        if options.normalize_eol {
+            pieces = normalize_eol(pieces);
+        }
+        if options.trim_lines {
+            pieces = trim_lines(pieces);
+        }
+        if options.normalize_whitespace {
+            pieces = normalize_whitespace(pieces);
+        }
+        if options.ignore_case {
+            pieces = lowercase(pieces);
+        }
+
+        let mut text = String::with_capacity(original.len());
+        let mut offsets = Vec::with_capacity(pieces.len());
+        for piece in &pieces {
+            offsets.push(text.len());
+            text.push(piece.ch);
+        }
+        Self {
+            text,
+            pieces,
+            offsets,
+            original_len: original.len(),
+        }
+    }
+
+    /// The normalized text
+    pub fn as_str(&self) -> &str {
+        &self.text
+    }
+
+    pub fn into_string(self) -> String {
+        self.text
+    }
+
+    /// Index of the piece containing normalized byte `offset`
+    fn piece_at(&self, offset: usize) -> usize {
+        match self.offsets.binary_search(&offset) {
+            Ok(index) => index,
+            Err(index) => index.saturating_sub(1),
+        }
+    }
+
+    /// The original byte range a normalized `start..end` range was derived from
+    ///
+    /// The result covers every original character that contributed to the range, e.g.
+    /// both bytes of a folded `\r\n` or a whole collapsed whitespace run.
+    pub fn to_original(&self, start: usize, end: usize) -> Span {
+        if self.pieces.is_empty() {
+            return Span::from_offsets(0, 0);
+        }
+        let original_start = if start >= self.text.len() {
+            self.pieces.last().map_or(self.original_len, |p| p.end)
+        } else {
+            self.pieces[self.piece_at(start)].start
+        };
+        let original_end = if end <= start {
+            original_start
+        } else {
+            self.pieces[self.piece_at(end - 1)].end
+        };
+        Span::from_offsets(original_start, original_end.max(original_start))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn options(f: impl FnOnce(&mut NormalizationOptions)) -> NormalizationOptions {
+        let mut options = NormalizationOptions {
+            normalize_eol: false,
+            trim_lines: false,
+            normalize_whitespace: false,
+            ignore_case: false,
+        };
+        f(&mut options);
+        options
+    }
+
+    fn original_of<'a>(original: &'a str, normalized: &NormalizedText, needle: &str) -> &'a str {
+        let start = normalized.as_str().find(needle).unwrap();
+        normalized
+            .to_original(start, start + needle.len())
+            .slice(original)
+    }
+
+    #[test]
+    fn test_offsets_map_back_to_original() {
+        let crlf = "first  \r\nsecond\r\nthird\r\n";
+        let normalized = NormalizedText::new(
+            crlf,
+            &options(|o| {
+                o.normalize_eol = true;
+                o.trim_lines = true;
+            }),
+        );
+        assert_eq!(normalized.as_str(), "first\nsecond\nthird\n");
+        assert_eq!(original_of(crlf, &normalized, "third"), "third");
+        assert_eq!(
+            original_of(crlf, &normalized, "second\nthird"),
+            "second\r\nthird"
+        );
+
+        let spaced = "let   x =\t\t1;";
+        let normalized = NormalizedText::new(spaced, &options(|o| o.normalize_whitespace = true));
+        assert_eq!(normalized.as_str(), "let x = 1;");
+        assert_eq!(original_of(spaced, &normalized, "x = 1"), "x =\t\t1");
+
+        // İ lowercases to two characters (3 bytes) but maps back to its 2 bytes
+        let cased = "İİ Target";
+        let normalized = NormalizedText::new(cased, &options(|o| o.ignore_case = true));
+        assert_eq!(original_of(cased, &normalized, "target"), "Target");
+        assert_eq!(original_of(cased, &normalized, "i̇ t"), "İ T");
+
+        let empty = NormalizedText::new("", &options(|_| {}));
+        assert!(empty.to_original(0, 0).is_empty());
+    }
+}
@@ -0,0 +1,772 @@
+//! Applying unified diffs to the workspace
+//!
+//! `apply_patch` takes a diff as `git diff` or a model writes it: with or without
+//! `diff --git` headers, with line counts that are off, or with bare `@@` hunk headers
+//! (see [`crate::vcs::parse_diff`]). Each hunk is placed where its removed and context
+//! lines match the file, searching outward from the line the header names. A hunk that
+//! does not match exactly is retried ignoring whitespace, then with up to
+//! [`MAX_FUZZ`] context lines dropped from each end, the way `patch` does. Every file is
+//! patched in memory first, so a patch that fails anywhere writes nothing.
+
+use super::diff::DiffPreview;
+use super::{protection, writer};
+use crate::core::{DangerLevel, Tool, ToolArgs, ToolCategory, ToolError, ToolResult};
+use crate::profiling;
+use crate::state::ToolState;
+use crate::vcs::{parse_diff, ChangeStatus, FileDiff, Hunk};
+use anyhow::Result;
+use serde::Serialize;
+use std::fs;
+use std::path::{Component, Path, PathBuf};
+use std::sync::{Arc, Mutex};
+
+/// Context lines a hunk may lose at each end and still apply
+pub const MAX_FUZZ: usize = 2;
+
+/// Lines of a failing hunk's expected text shown in the error
+const MAX_EXPECTED_LINES: usize = 8;
+
+/// Where and how a hunk was applied
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+pub struct HunkPlacement {
+    /// 1-based index of the hunk in its file
+    pub hunk: usize,
+    /// 1-based line of the original file the hunk's first matched line was found at
+    pub line: usize,
+    /// Lines between where the header placed the hunk and where it matched
+    pub offset: isize,
+    /// Context lines dropped from each end to make the hunk match
+    pub fuzz: usize,
+    /// Whether lines only matched with whitespace ignored
+    pub ignored_whitespace: bool,
+}
+
+/// A hunk that matched nowhere in its file
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+pub struct HunkFailure {
+    /// 1-based index of the hunk in its file
+    pub hunk: usize,
+    pub header: String,
+    /// Removed and context lines the file was expected to contain
+    pub expected: Vec<String>,
+    /// 1-based lines where the hunk's first expected line occurs
+    pub candidates: Vec<usize>,
+}
+
+/// A hunk split into the lines it expects and the lines it leaves
+struct HunkLines<'a> {
+    /// Hunk lines without `\ No newline` markers, each with its prefix
+    lines: Vec<(char, &'a str)>,
+    /// `Some(false)` when the new side ends without a newline, `Some(true)` when only the
+    /// old side did
+    final_newline: Option<bool>,
+}
+
+impl<'a> HunkLines<'a> {
+    fn new(hunk: &'a Hunk) -> Self {
+        let mut lines = Vec::with_capacity(hunk.lines.len());
+        let mut final_newline = None;
+        for line in &hunk.lines {
+            let mut chars = line.chars();
+            match chars.next() {
+                Some('\\') => {
+                    final_newline = match lines.last() {
+                        Some(('-', _)) => final_newline.or(Some(true)),
+                        _ => Some(false),
+                    }
+                }
+                Some(prefix @ ('+' | '-' | ' ')) => lines.push((prefix, chars.as_str())),
+                // A blank line in a hunk is a context line whose space was stripped
+                _ => lines.push((' ', line.as_str())),
+            }
+        }
+        Self {
+            lines,
+            final_newline,
+        }
+    }
+
+    /// Leading and trailing context lines
+    fn context(&self) -> (usize, usize) {
+        let leading = self.lines.iter().take_while(|(p, _)| *p == ' ').count();
+        let trailing = self
+            .lines
+            .iter()
+            .rev()
+            .take_while(|(p, _)| *p == ' ')
+            .count();
+        (
+            leading.min(self.lines.len()),
+            trailing.min(self.lines.len() - leading),
+        )
+    }
+}
+
+fn lines_match(a: &str, b: &str, ignore_whitespace: bool) -> bool {
+    a == b || (ignore_whitespace && a.split_whitespace().eq(b.split_whitespace()))
+}
+
+/// Apply `hunks` to `content`, returning the new content and where each hunk went
+pub fn apply_hunks(
+    content: &str,
+    hunks: &[Hunk],
+) -> Result<(String, Vec<HunkPlacement>), HunkFailure> {
+    let crlf = content.contains("\r\n");
+    let original: Vec<&str> = content.lines().collect();
+    let mut final_newline = content.is_empty() || content.ends_with('\n');
+    let mut out: Vec<String> = Vec::with_capacity(original.len());
+    let mut cursor = 0usize;
+    let mut drift = 0isize;
+    let mut placements = Vec::with_capacity(hunks.len());
+
+    for (index, hunk) in hunks.iter().enumerate() {
+        let parsed = HunkLines::new(hunk);
+        // Bare `@@` hunks have no position and continue where the last one ended
+        let expected = if hunk.old_start == 0 && hunk.new_start == 0 {
+            cursor
+        } else {
+            let start = hunk.old_start.saturating_sub(1) + usize::from(hunk.old_lines == 0);
+            (start as isize + drift).clamp(cursor as isize, original.len() as isize) as usize
+        };
+        let (leading, trailing) = parsed.context();
+
+        let mut placed = None;
+        'search: for fuzz in 0..=MAX_FUZZ.min(leading.max(trailing)) {
+            let (skip_start, skip_end) = (fuzz.min(leading), fuzz.min(trailing));
+            let lines = &parsed.lines[skip_start..parsed.lines.len() - skip_end];
+            let old: Vec<&str> = lines
+                .iter()
+                .filter(|(p, _)| *p != '+')
+                .map(|(_, l)| *l)
+                .collect();
+            for ignore_whitespace in [false, true] {
+                let target = expected + skip_start;
+                let position = if old.is_empty() {
+                    Some(target.min(original.len()))
+                } else {
+                    (cursor..=original.len().saturating_sub(old.len()))
+                        .filter(|&p| p + old.len() <= original.len())
+                        .filter(|&p| {
+                            old.iter()
+                                .zip(&original[p..])
+                                .all(|(a, b)| lines_match(a, b, ignore_whitespace))
+                        })
+                        .min_by_key(|&p| p.abs_diff(target))
+                };
+                if let Some(position) = position {
+                    placed = Some((position, lines, fuzz, ignore_whitespace, target));
+                    break 'search;
+                }
+            }
+        }
+
+        let Some((position, lines, fuzz, ignore_whitespace, target)) = placed else {
+            let expected_lines: Vec<String> = parsed
+                .lines
+                .iter()
+                .filter(|(p, _)| *p != '+')
+                .map(|(_, l)| l.to_string())
+                .collect();
+            let first = expected_lines.iter().find(|l| !l.trim().is_empty());
+            let candidates = first
+                .map(|first| {
+                    original
+                        .iter()
+                        .enumerate()
+                        .filter(|(_, l)| lines_match(l, first, true))
+                        .map(|(i, _)| i + 1)
+                        .take(5)
+                        .collect()
+                })
+                .unwrap_or_default();
+            return Err(HunkFailure {
+                hunk: index + 1,
+                header: format!(
+                    "@@ -{},{} +{},{} @@",
+                    hunk.old_start, hunk.old_lines, hunk.new_start, hunk.new_lines
+                ),
+                expected: expected_lines
+                    .into_iter()
+                    .take(MAX_EXPECTED_LINES)
+                    .collect(),
+                candidates,
+            });
+        };
+
+        out.extend(original[cursor..position].iter().map(|l| l.to_string()));
+        // Context lines keep the file's text, which may differ in whitespace
+        let mut old_index = position;
+        for (prefix, line) in lines {
+            match prefix {
+                '+' => out.push(line.to_string()),
+                '-' => old_index += 1,
+                _ => {
+                    out.push(original[old_index].to_string());
+                    old_index += 1;
+                }
+            }
+        }
+        cursor = old_index;
+        drift += position as isize - target as isize;
+        if cursor == original.len() {
+            if let Some(newline) = parsed.final_newline {
+                final_newline = newline;
+            }
+        }
+        placements.push(HunkPlacement {
+            hunk: index + 1,
+            line: position + 1,
+            offset: position as isize - target as isize,
+            fuzz,
+            ignored_whitespace: ignore_whitespace,
+        });
+    }
+    out.extend(original[cursor..].iter().map(|l| l.to_string()));
+
+    let mut patched = out.join(if crlf { "\r\n" } else { "\n" });
+    if final_newline && !out.is_empty() {
+        patched.push_str(if crlf { "\r\n" } else { "\n" });
+    }
+    Ok((patched, placements))
+}
+
+/// `file`'s hunks with the old and new sides swapped, to recognize an applied patch
+fn reversed(file: &FileDiff) -> Vec<Hunk> {
+    file.hunks
+        .iter()
+        .map(|hunk| Hunk {
+            old_start: hunk.new_start,
+            old_lines: hunk.new_lines,
+            new_start: hunk.old_start,
+            new_lines: hunk.old_lines,
+            section: hunk.section.clone(),
+            lines: hunk
+                .lines
+                .iter()
+                .map(|line| match line.strip_prefix('+') {
+                    Some(rest) => format!("-{}", rest),
+                    None => match line.strip_prefix('-') {
+                        Some(rest) => format!("+{}", rest),
+                        None => line.clone(),
+                    },
+                })
+                .collect(),
+        })
+        .collect()
+}
+
+/// The outcome of patching one file in memory
+struct PlannedFile {
+    diff: FileDiff,
+    /// File written, or removed when `after` is `None`
+    target: PathBuf,
+    /// Original removed after a rename
+    source: Option<PathBuf>,
+    before: String,
+    after: Option<String>,
+    placements: Vec<HunkPlacement>,
+}
+
+/// Tool applying a unified diff to the files it names
+pub struct ApplyPatchTool {
+    name: String,
+}
+
+impl ApplyPatchTool {
+    pub fn new() -> Self {
+        Self {
+            name: "apply_patch".to_string(),
+        }
+    }
+
+    fn parse_params(&self, args: &ToolArgs) -> Result<serde_json::Value, ToolError> {
+        if let Some(json_str) = args.get_named_arg("json") {
+            return serde_json::from_str(json_str).map_err(ToolError::Json);
+        }
+        if !args.named_args.is_empty() {
+            return serde_json::to_value(&args.named_args).map_err(ToolError::Json);
+        }
+        match args.get_arg(0) {
+            Some(patch) => Ok(serde_json::json!({ "patch": patch })),
+            None => Err(ToolError::InvalidArgs {
+                message: "Missing required parameter: patch".to_string(),
+            }),
+        }
+    }
+
+    /// `path` from a file header below `root`; absolute paths and `..` are refused so a
+    /// patch cannot reach outside the working directory
+    fn header_target(root: &Path, path: &str) -> Result<PathBuf, String> {
+        let relative = Path::new(path);
+        if relative
+            .components()
+            .all(|c| matches!(c, Component::Normal(_) | Component::CurDir))
+        {
+            Ok(root.join(relative))
+        } else {
+            Err(format!(
+                "{}: file headers must name paths relative to the working directory, without ..",
+                path
+            ))
+        }
+    }
+
+    /// Patch one file in memory, or explain why it cannot be patched
+    fn plan(root: &Path, diff: FileDiff) -> Result<PlannedFile, String> {
+        let target = Self::header_target(root, &diff.path)?;
+        if diff.binary {
+            return Err(format!("{}: binary patches are not supported", diff.path));
+        }
+        let source = match diff.status {
+            ChangeStatus::Renamed | ChangeStatus::Copied => {
+                Self::header_target(root, diff.from.as_deref().unwrap_or(&diff.path))?
+            }
+            _ => target.clone(),
+        };
+        let before = if diff.status == ChangeStatus::Added {
+            if target.exists() {
+                return Err(format!(
+                    "{}: the patch creates it, but it already exists",
+                    diff.path
+                ));
+            }
+            String::new()
+        } else {
+            if !source.is_file() {
+                return Err(format!("{}: file not found", source.display()));
+            }
+            profiling::read_to_string(&source)
+                .map_err(|e| format!("{}: {}", source.display(), e))?
+        };
+        if matches!(diff.status, ChangeStatus::Renamed | ChangeStatus::Copied)
+            && target != source
+            && target.exists()
+        {
+            return Err(format!("{}: rename target already exists", diff.path));
+        }
+
+        let (after, placements) = match apply_hunks(&before, &diff.hunks) {
+            Ok(applied) => applied,
+            Err(failure) => {
+                let applied =
+                    !diff.hunks.is_empty() && apply_hunks(&before, &reversed(&diff)).is_ok();
+                let mut message = if applied {
+                    format!(
+                        "{}: hunk {} does not apply; the patch looks already applied",
+                        diff.path, failure.hunk
+                    )
+                } else {
+                    format!(
+                        "{}: hunk {} {} does not match the file. Expected:\n{}",
+                        diff.path,
+                        failure.hunk,
+                        failure.header,
+                        failure
+                            .expected
+                            .iter()
+                            .map(|l| format!("  |{}", l))
+                            .collect::<Vec<_>>()
+                            .join("\n")
+                    )
+                };
+                if !applied && !failure.candidates.is_empty() {
+                    message.push_str(&format!(
+                        "\nIts first line occurs at line(s) {}; reread the file there",
+                        failure
+                            .candidates
+                            .iter()
+                            .map(|l| l.to_string())
+                            .collect::<Vec<_>>()
+                            .join(", ")
+                    ));
+                }
+                return Err(message);
+            }
+        };
+        let after = if diff.status == ChangeStatus::Deleted {
+            if !after.trim().is_empty() {
+                return Err(format!(
+                    "{}: the patch deletes it, but the file has lines the patch does not remove",
+                    diff.path
+                ));
+            }
+            None
+        } else {
+            Some(after)
+        };
+        Ok(PlannedFile {
+            source: (diff.status == ChangeStatus::Renamed && source != target).then_some(source),
+            diff,
+            target,
+            before,
+            after,
+            placements,
+        })
+    }
+
+    fn describe(file: &PlannedFile, preview: &DiffPreview) -> String {
+        let name = match (&file.diff.from, file.diff.status) {
+            (Some(from), ChangeStatus::Renamed | ChangeStatus::Copied) => {
+                format!("{} -> {}", from, file.diff.path)
+            }
+            _ => file.diff.path.clone(),
+        };
+        let mut line = format!(
+            "{} {} (+{} -{})",
+            file.diff.status.code(),
+            name,
+            preview.additions,
+            preview.deletions
+        );
+        for placement in &file.placements {
+            let mut notes = Vec::new();
+            if placement.offset != 0 {
+                notes.push(format!("offset {:+}", placement.offset));
+            }
+            if placement.fuzz > 0 {
+                notes.push(format!("fuzz {}", placement.fuzz));
+            }
+            if placement.ignored_whitespace {
+                notes.push("whitespace ignored".to_string());
+            }
+            if !notes.is_empty() {
+                line.push_str(&format!(
+                    "\n  hunk {} at line {} ({})",
+                    placement.hunk,
+                    placement.line,
+                    notes.join(", ")
+                ));
+            }
+        }
+        line
+    }
+}
+
+impl Default for ApplyPatchTool {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl Tool for ApplyPatchTool {
+    fn name(&self) -> &str {
+        &self.name
+    }
+
+    fn description(&self) -> &str {
+        "Apply a unified diff (as from git diff, with or without a/ b/ prefixes) to the workspace: edits, new files, deletions and renames. Hunks are matched around their stated line, then ignoring whitespace, then with up to 2 context lines dropped; nothing is written unless every hunk applies. dry_run: true only reports where each hunk would go"
+    }
+
+    fn signature(&self) -> &str {
+        "apply_patch(patch: str, dry_run?: bool, allow_protected?: bool)"
+    }
+
+    fn category(&self) -> ToolCategory {
+        ToolCategory::Editing
+    }
+
+    fn danger_level(&self) -> DangerLevel {
+        DangerLevel::ModifiesFiles
+    }
+
+    fn validate_args(&self, args: &ToolArgs) -> Result<(), ToolError> {
+        let params = self.parse_params(args)?;
+        match params.get("patch").and_then(|p| p.as_str()) {
+            Some(patch) if !patch.trim().is_empty() => Ok(()),
+            _ => Err(ToolError::InvalidArgs {
+                message: "Missing required parameter: patch".to_string(),
+            }),
+        }
+    }
+
+    fn accessed_paths(&self, args: &ToolArgs) -> Vec<PathBuf> {
+        let Some(diffs) = self
+            .parse_params(args)
+            .ok()
+            .and_then(|params| params.get("patch")?.as_str().map(parse_diff))
+        else {
+            return Vec::new();
+        };
+        diffs
+            .iter()
+            .flat_map(|diff| std::iter::once(&diff.path).chain(diff.from.as_ref()))
+            .map(PathBuf::from)
+            .collect()
+    }
+
+    fn execute(&mut self, args: &ToolArgs, state: &Arc<Mutex<ToolState>>) -> Result<ToolResult> {
+        let params = self.parse_params(args)?;
+        let obj = params
+            .as_object()
+            .ok_or_else(|| anyhow::anyhow!("Invalid parameters"))?;
+        let patch = obj
+            .get("patch")
+            .and_then(|p| p.as_str())
+            .ok_or_else(|| anyhow::anyhow!("Invalid patch parameter"))?;
+        let dry_run = super::bool_param(obj, "dry_run").unwrap_or(false);
+        let allow_protected = protection::override_requested(obj);
+        let root = state
+            .lock()
+            .map_err(|e| anyhow::anyhow!("Failed to lock state: {}", e))?
+            .working_directory
+            .clone();
+
+        let diffs = parse_diff(patch);
+        if diffs.is_empty() {
+            return Ok(ToolResult::error(
+                "No file changes found in the patch: expected a unified diff with ---/+++ file headers and @@ hunks",
+            ));
+        }
+
+        let mut planned = Vec::with_capacity(diffs.len());
+        let mut failures = Vec::new();
+        for diff in diffs {
+            match Self::plan(&root, diff) {
+                Ok(file) => planned.push(file),
+                Err(message) => failures.push(message),
+            }
+        }
+        if !failures.is_empty() {
+            return Ok(ToolResult::error_with_data(
+                format!(
+                    "Patch not applied; nothing was written. {} of {} file(s) failed:\n{}",
+                    failures.len(),
+                    failures.len() + planned.len(),
+                    failures.join("\n")
+                ),
+                serde_json::json!({
+                    "applied": false,
+                    "failures": failures,
+                }),
+            ));
+        }
+
+        let previews: Vec<DiffPreview> = planned
+            .iter()
+            .map(|file| {
+                DiffPreview::new(
+                    &file.before,
+                    file.after.as_deref().unwrap_or(""),
+                    &file.diff.path,
+                )
+            })
+            .collect();
+        let summary = planned
+            .iter()
+            .zip(&previews)
+            .map(|(file, preview)| Self::describe(file, preview))
+            .collect::<Vec<_>>()
+            .join("\n");
+        let data = |applied: bool| {
+            serde_json::json!({
+                "applied": applied,
+                "dry_run": dry_run,
+                "files": planned
+                    .iter()
+                    .zip(&previews)
+                    .map(|(file, preview)| serde_json::json!({
+                        "path": file.diff.path,
+                        "from": file.diff.from,
+                        "status": file.diff.status,
+                        "hunks": file.placements,
+                        "diff": preview,
+                    }))
+                    .collect::<Vec<_>>(),
+            })
+        };
+        if dry_run {
+            return Ok(ToolResult::success_with_data(
+                format!(
+                    "Patch applies to {} file(s) (dry run, nothing written):\n{}",
+                    planned.len(),
+                    summary
+                ),
+                data(false),
+            ));
+        }
+
+        for file in &planned {
+            for path in std::iter::once(&file.target).chain(&file.source) {
+                if let Some(refused) = protection::check(state, &self.name, path, allow_protected)?
+                {
+                    return Ok(refused);
+                }
+            }
+        }
+        for file in &planned {
+            match &file.after {
+                Some(after) => {
+                    if let Some(parent) = file.target.parent() {
+                        fs::create_dir_all(parent).map_err(|e| {
+                            anyhow::anyhow!("Failed to create parent directories: {}", e)
+                        })?;
+                    }
+                    writer::write_file(state, &file.target, after).map_err(|e| {
+                        anyhow::anyhow!("Failed to write {}: {}", file.target.display(), e)
+                    })?;
+                }
+                None => writer::remove_file(state, &file.target).map_err(|e| {
+                    anyhow::anyhow!("Failed to delete {}: {}", file.target.display(), e)
+                })?,
+            }
+            if let Some(source) = &file.source {
+                writer::remove_file(state, source)
+                    .map_err(|e| anyhow::anyhow!("Failed to delete {}: {}", source.display(), e))?;
+            }
+        }
+
+        {
+            let mut state_guard = state
+                .lock()
+                .map_err(|e| anyhow::anyhow!("Failed to lock state: {}", e))?;
+            for file in &planned {
+                if let Some(after) = &file.after {
+                    if state_guard.is_current_file(&file.target) {
+                        let lines: Vec<String> = after.lines().map(|s| s.to_string()).collect();
+                        state_guard.open_file(file.target.clone(), lines, 100)?;
+                    }
+                }
+            }
+            state_guard.push_history(format!("Applied patch to {} file(s)", planned.len()));
+        }
+
+        Ok(ToolResult::success_with_data(
+            format!("Applied patch to {} file(s):\n{}", planned.len(), summary),
+            data(true),
+        ))
+    }
+
+    fn get_parameters_schema(&self) -> serde_json::Value {
+        serde_json::json!({
+            "type": "object",
+            "properties": {
+                "patch": {
+                    "type": "string",
+                    "description": "Unified diff; paths are relative to the working directory, and a/ b/ prefixes are stripped"
+                },
+                "dry_run": {
+                    "type": "boolean",
+                    "description": "Check that every hunk applies and report where, without writing",
+                    "default": false
+                },
+                "allow_protected": {
+                    "type": "boolean",
+                    "description": "Patch files even if they match a protected path"
+                }
+            },
+            "required": ["patch"]
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use tempfile::TempDir;
+
+    #[test]
+    fn test_apply_hunks_with_offset_whitespace_and_fuzz() {
+        let content =
+            "fn main() {\n    let a = 1;\n    let b = 2;\n    println!(\"{}\", a + b);\n}\n";
+        // Header two lines off, indentation lost, first context line wrong
+        let diff = "--- a/main.rs\n+++ b/main.rs\n@@ -3,4 +3,4 @@\n fn start() {\n let a = 1;\n-let b = 2;\n+let b = 3;\n println!(\"{}\", a + b);\n";
+        let files = parse_diff(diff);
+        let (patched, placements) = apply_hunks(content, &files[0].hunks).unwrap();
+        assert_eq!(
+            patched,
+            "fn main() {\n    let a = 1;\nlet b = 3;\n    println!(\"{}\", a + b);\n}\n"
+        );
+        assert_eq!(placements[0].fuzz, 1);
+        assert!(placements[0].ignored_whitespace);
+        assert_eq!(placements[0].line, 2);
+
+        // Bare headers, and a missing trailing newline on the new side
+        let diff = "--- x\n+++ x\n@@\n-}\n+}\n\\ No newline at end of file\n";
+        let (patched, _) = apply_hunks(content, &parse_diff(diff)[0].hunks).unwrap();
+        assert!(patched.ends_with("a + b);\n}"));
+
+        let failure = apply_hunks(
+            "a\nb\n",
+            &parse_diff("--- x\n+++ x\n@@ -1,2 +1,2 @@\n a\n-c\n+d\n")[0].hunks,
+        )
+        .unwrap_err();
+        assert_eq!(failure.hunk, 1);
+        assert_eq!(failure.candidates, vec![1]);
+    }
+
+    #[test]
+    fn test_apply_patch_tool_is_atomic_and_supports_dry_run() {
+        let temp_dir = TempDir::new().unwrap();
+        let root = temp_dir.path();
+        fs::write(root.join("a.txt"), "one\ntwo\nthree\n").unwrap();
+        fs::write(root.join("old.txt"), "keep\n").unwrap();
+        let mut tool_state = ToolState::new();
+        tool_state.working_directory = root.to_path_buf();
+        let state = Arc::new(Mutex::new(tool_state));
+        let mut tool = ApplyPatchTool::new();
+        let run = |tool: &mut ApplyPatchTool, patch: &str, dry_run: bool| {
+            let mut named = std::collections::HashMap::new();
+            named.insert("patch".to_string(), patch.to_string());
+            named.insert("dry_run".to_string(), dry_run.to_string());
+            tool.execute(&ToolArgs::with_named_args(vec![], named), &state)
+                .unwrap()
+        };
+
+        let patch = "diff --git a/a.txt b/a.txt\n--- a/a.txt\n+++ b/a.txt\n@@ -1,3 +1,3 @@\n one\n-two\n+2\n three\ndiff --git a/new/b.txt b/new/b.txt\nnew file mode 100644\n--- /dev/null\n+++ b/new/b.txt\n@@ -0,0 +1,2 @@\n+hello\n+world\ndiff --git a/old.txt b/renamed.txt\nsimilarity index 100%\nrename from old.txt\nrename to renamed.txt\n";
+        let result = run(&mut tool, patch, true);
+        assert!(result.success, "{}", result.message);
+        assert!(result.message.contains("R old.txt -> renamed.txt"));
+        assert!(!root.join("new/b.txt").exists());
+
+        // One bad file keeps every file untouched
+        let bad = format!(
+            "{}--- a/a.txt\n+++ b/a.txt\n@@ -1 +1 @@\n-missing\n+x\n",
+            patch
+        );
+        let result = run(&mut tool, &bad, false);
+        assert!(!result.success);
+        assert!(
+            result.message.contains("nothing was written"),
+            "{}",
+            result.message
+        );
+        assert_eq!(
+            fs::read_to_string(root.join("a.txt")).unwrap(),
+            "one\ntwo\nthree\n"
+        );
+
+        let result = run(&mut tool, patch, false);
+        assert!(result.success, "{}", result.message);
+        assert_eq!(
+            fs::read_to_string(root.join("a.txt")).unwrap(),
+            "one\n2\nthree\n"
+        );
+        assert_eq!(
+            fs::read_to_string(root.join("new/b.txt")).unwrap(),
+            "hello\nworld\n"
+        );
+        assert_eq!(
+            fs::read_to_string(root.join("renamed.txt")).unwrap(),
+            "keep\n"
+        );
+        assert!(!root.join("old.txt").exists());
+
+        let result = run(
+            &mut tool,
+            "--- a/a.txt\n+++ b/a.txt\n@@ -1,3 +1,3 @@\n one\n-two\n+2\n three\n",
+            false,
+        );
+        assert!(!result.success);
+        assert!(
+            result.message.contains("already applied"),
+            "{}",
+            result.message
+        );
+
+        // Headers cannot leave the working directory
+        let escaping = "--- /dev/null\n+++ b/../escaped.txt\n@@ -0,0 +1 @@\n+x\n";
+        let result = run(&mut tool, escaping, false);
+        assert!(result.message.contains("without .."), "{}", result.message);
+        assert!(!root.join("../escaped.txt").exists());
+    }
+}
@@ -0,0 +1,305 @@
+//! Protected path globs enforced by destructive editing tools
+//!
+//! `delete_path`, `move_path` and `overwrite_file` refuse to touch paths matching a
+//! protected glob unless called with `allow_protected: true`; overrides are recorded in
+//! the session history. Patterns use gitignore-style semantics: a pattern without `/`
+//! matches the file or directory name at any depth, anything else is matched against
+//! the path relative to the working directory, and a trailing `/` restricts a pattern
+//! to directories.
+//!
+//! Patterns come from the `[protection]` table of `cats.toml` (see [`crate::config`]) in
+//! the working directory
+//! or from `ToolRegistry::set_protected_paths`:
+//!
+//! ```toml
+//! [protection]
+//! paths = ["Cargo.lock", "migrations/**", "*.pem"]
+//! include_defaults = true
+//! ```
+
+use crate::core::ToolResult;
+use crate::search::ConfigurableFilter;
+use crate::state::ToolState;
+use anyhow::Result;
+use glob::{MatchOptions, Pattern};
+use serde::{Deserialize, Serialize};
+use std::fs;
+use std::path::{Component, Path};
+use std::sync::{Arc, Mutex};
+
+pub use crate::config::CONFIG_FILE;
+
+/// Parameter that overrides protection for a single call
+pub const OVERRIDE_PARAM: &str = "allow_protected";
+
+/// `[protection]` table of `cats.toml`
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct ProtectionConfig {
+    #[serde(default)]
+    pub paths: Vec<String>,
+    /// Keep the built-in patterns in addition to `paths` (default true)
+    pub include_defaults: Option<bool>,
+}
+
+#[derive(Debug, Clone)]
+struct ProtectedPattern {
+    source: String,
+    pattern: Pattern,
+    anchored: bool,
+    dir_only: bool,
+}
+
+/// A compiled list of protected path globs
+#[derive(Debug, Clone)]
+pub struct ProtectedPaths {
+    patterns: Vec<ProtectedPattern>,
+}
+
+impl ProtectedPaths {
+    /// Compile `patterns`, skipping invalid globs
+    pub fn new<I, S>(patterns: I) -> Self
+    where
+        I: IntoIterator<Item = S>,
+        S: AsRef<str>,
+    {
+        let patterns = patterns
+            .into_iter()
+            .filter_map(|p| {
+                let source = p.as_ref().trim().trim_start_matches("./").to_string();
+                let dir_only = source.ends_with('/');
+                let trimmed = source.trim_end_matches('/');
+                let anchored = trimmed.contains('/');
+                match Pattern::new(trimmed.trim_start_matches('/')) {
+                    Ok(pattern) => Some(ProtectedPattern {
+                        source,
+                        pattern,
+                        anchored,
+                        dir_only,
+                    }),
+                    Err(e) => {
+                        tracing::warn!("Ignoring invalid protected path glob '{}': {}", source, e);
+                        None
+                    }
+                }
+            })
+            .collect();
+        Self { patterns }
+    }
+
+    /// Built-in patterns: `src/` and the directories excluded from search
+    pub fn defaults() -> Self {
+        let mut patterns = vec!["src/".to_string()];
+        if let Some(dirs) = ConfigurableFilter::new(None).config.exclude_dirs {
+            patterns.extend(dirs.into_iter().map(|d| format!("{}/", d)));
+        }
+        Self::new(patterns)
+    }
+
+    /// Protected paths for the workspace rooted at `working_dir`
+    pub fn load(working_dir: &Path) -> Self {
+        #[derive(Deserialize)]
+        struct PartialConfig {
+            protection: Option<ProtectionConfig>,
+        }
+
+        let config = fs::read_to_string(working_dir.join(CONFIG_FILE))
+            .ok()
+            .and_then(|content| toml::from_str::<PartialConfig>(&content).ok())
+            .and_then(|partial| partial.protection)
+            .unwrap_or_default();
+        Self::from_config(&config)
+    }
+
+    /// Protected paths for a `[protection]` table
+    pub fn from_config(config: &ProtectionConfig) -> Self {
+        let mut protected = if config.include_defaults.unwrap_or(true) {
+            Self::defaults()
+        } else {
+            Self::new(Vec::<String>::new())
+        };
+        protected.patterns.extend(Self::new(&config.paths).patterns);
+        protected
+    }
+
+    /// The configured patterns
+    pub fn patterns(&self) -> Vec<&str> {
+        self.patterns.iter().map(|p| p.source.as_str()).collect()
+    }
+
+    /// The first pattern protecting `path`, if any
+    pub fn matching_pattern(&self, working_dir: &Path, path: &Path) -> Option<&str> {
+        let absolute = std::path::absolute(path).unwrap_or_else(|_| path.to_path_buf());
+        let relative = absolute.strip_prefix(working_dir).unwrap_or(&absolute);
+        let relative: Vec<String> = relative
+            .components()
+            .filter_map(|c| match c {
+                Component::Normal(part) => Some(part.to_string_lossy().to_string()),
+                _ => None,
+            })
+            .collect();
+        let relative = relative.join("/");
+        let name = relative.rsplit('/').next().unwrap_or("");
+
+        let options = MatchOptions {
+            case_sensitive: true,
+            require_literal_separator: true,
+            require_literal_leading_dot: false,
+        };
+        let is_dir = path.is_dir();
+        self.patterns
+            .iter()
+            .filter(|p| is_dir || !p.dir_only)
+            .find(|p| {
+                if p.anchored {
+                    p.pattern.matches_with(&relative, options)
+                } else {
+                    p.pattern.matches_with(name, options)
+                }
+            })
+            .map(|p| p.source.as_str())
+    }
+}
+
+impl Default for ProtectedPaths {
+    fn default() -> Self {
+        Self::defaults()
+    }
+}
+
+/// Whether a call asked to override protection (`allow_protected` as bool or string)
+pub fn override_requested(obj: &serde_json::Map<String, serde_json::Value>) -> bool {
+    super::bool_param(obj, OVERRIDE_PARAM).unwrap_or(false)
+}
+
+/// Check `path` against the session's protected paths before `tool` modifies it
+///
+/// Returns the error result to hand back when the path is protected and no override
+/// was given. Overrides are allowed through, logged and recorded in the history.
+pub fn check(
+    state: &Arc<Mutex<ToolState>>,
+    tool: &str,
+    path: &Path,
+    allow_protected: bool,
+) -> Result<Option<ToolResult>> {
+    let mut state_guard = state
+        .lock()
+        .map_err(|e| anyhow::anyhow!("Failed to lock state: {}", e))?;
+    let working_dir = state_guard.working_directory.clone();
+    let pattern = match state_guard
+        .protected_paths()
+        .matching_pattern(&working_dir, path)
+    {
+        Some(pattern) => pattern.to_string(),
+        None => return Ok(None),
+    };
+
+    if !allow_protected {
+        return Ok(Some(ToolResult::error_with_data(
+            format!(
+                "Safety check: '{}' is protected by pattern '{}'. Pass {}=true to {} it anyway.",
+                path.display(),
+                pattern,
+                OVERRIDE_PARAM,
+                tool
+            ),
+            serde_json::json!({
+                "path": path.to_string_lossy(),
+                "protected_by": pattern,
+                "override": OVERRIDE_PARAM
+            }),
+        )));
+    }
+
+    tracing::warn!(
+        "{} overriding protection of {} (pattern '{}')",
+        tool,
+        path.display(),
+        pattern
+    );
+    state_guard.push_history(format!(
+        "Protection override: {} on {} (pattern '{}')",
+        tool,
+        path.display(),
+        pattern
+    ));
+    Ok(None)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use tempfile::TempDir;
+
+    #[test]
+    fn test_matching_pattern() {
+        let root = Path::new("/work");
+        let protected =
+            ProtectedPaths::new(["src", "migrations/**", "*.pem", "/Cargo.lock", "build/"]);
+
+        assert_eq!(
+            protected.matching_pattern(root, Path::new("/work/crates/a/src")),
+            Some("src")
+        );
+        assert_eq!(
+            protected.matching_pattern(root, Path::new("/work/src/main.rs")),
+            None
+        );
+        assert_eq!(
+            protected.matching_pattern(root, Path::new("/work/migrations/001.sql")),
+            Some("migrations/**")
+        );
+        assert_eq!(
+            protected.matching_pattern(root, Path::new("/work/keys/dev.pem")),
+            Some("*.pem")
+        );
+        assert_eq!(
+            protected.matching_pattern(root, Path::new("/work/Cargo.lock")),
+            Some("/Cargo.lock")
+        );
+        assert_eq!(
+            protected.matching_pattern(root, Path::new("/work/sub/Cargo.lock")),
+            None
+        );
+        // Directory-only patterns never match files (or missing paths)
+        assert_eq!(
+            protected.matching_pattern(root, Path::new("/work/build")),
+            None
+        );
+    }
+
+    #[test]
+    fn test_load_from_cats_toml() {
+        let temp_dir = TempDir::new().unwrap();
+        fs::write(
+            temp_dir.path().join(CONFIG_FILE),
+            "[protection]\npaths = [\"*.lock\"]\ninclude_defaults = false\n",
+        )
+        .unwrap();
+
+        let protected = ProtectedPaths::load(temp_dir.path());
+        assert_eq!(protected.patterns(), vec!["*.lock"]);
+    }
+
+    #[test]
+    fn test_check_override_is_recorded() {
+        let temp_dir = TempDir::new().unwrap();
+        let mut tool_state = ToolState::new();
+        tool_state.working_directory = temp_dir.path().to_path_buf();
+        tool_state.protected_paths = Some(ProtectedPaths::new(["*.lock"]));
+        let state = Arc::new(Mutex::new(tool_state));
+        let path = temp_dir.path().join("Cargo.lock");
+
+        let refused = check(&state, "delete_path", &path, false).unwrap().unwrap();
+        assert!(!refused.success);
+        assert_eq!(refused.data.unwrap()["protected_by"], "*.lock");
+
+        assert!(check(&state, "delete_path", &path, true).unwrap().is_none());
+        let state_guard = state.lock().unwrap();
+        assert!(state_guard
+            .history
+            .last()
+            .unwrap()
+            .operation
+            .starts_with("Protection override: delete_path"));
+    }
+}
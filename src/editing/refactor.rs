@@ -0,0 +1,848 @@
+//! Cross-file refactorings for Rust crates
+//!
+//! `rename_module` moves a module file and rewrites the `mod` declarations and `use`
+//! paths that refer to it. The rewrite is textual and best-effort: it covers
+//! `crate::`, `<crate name>::`, `self::` and `super::` paths plus grouped imports, and
+//! reports anything it could not update instead of guessing.
+
+use super::{protection, writer};
+use crate::core::{DangerLevel, Tool, ToolArgs, ToolCategory, ToolError, ToolResult};
+use crate::profiling;
+use crate::state::{session_path, ToolState};
+use crate::utils::suggest::path_not_found;
+use anyhow::Result;
+use regex::Regex;
+use std::fs;
+use std::path::{Path, PathBuf};
+use std::sync::{Arc, Mutex};
+
+/// Layout of the crate containing a module file
+#[derive(Debug, Clone)]
+pub struct CrateLayout {
+    /// Directory containing `Cargo.toml`
+    pub root: PathBuf,
+    /// The `src` directory
+    pub src: PathBuf,
+    /// Name used to refer to the library from binaries and tests
+    pub lib_name: String,
+}
+
+impl CrateLayout {
+    /// Find the crate that contains `path`
+    pub fn find(path: &Path) -> Option<Self> {
+        let absolute = std::path::absolute(path).ok()?;
+        let root = absolute
+            .ancestors()
+            .skip(1)
+            .find(|dir| dir.join("Cargo.toml").is_file())?
+            .to_path_buf();
+        let manifest: toml::Value = fs::read_to_string(root.join("Cargo.toml"))
+            .ok()
+            .and_then(|content| toml::from_str(&content).ok())?;
+        let lib_name = manifest
+            .get("lib")
+            .and_then(|lib| lib.get("name"))
+            .or_else(|| manifest.get("package").and_then(|p| p.get("name")))
+            .and_then(|name| name.as_str())
+            .unwrap_or_default()
+            .replace('-', "_");
+        Some(Self {
+            src: root.join("src"),
+            root,
+            lib_name,
+        })
+    }
+
+    /// Module path segments of `file` (`src/a/b.rs` and `src/a/b/mod.rs` are `[a, b]`)
+    pub fn module_segments(&self, file: &Path) -> Option<Vec<String>> {
+        let absolute = std::path::absolute(file).ok()?;
+        let relative = absolute.strip_prefix(&self.src).ok()?;
+        let mut segments: Vec<String> = relative
+            .iter()
+            .map(|c| c.to_string_lossy().to_string())
+            .collect();
+        if segments.first().map(String::as_str) == Some("bin") {
+            return None;
+        }
+        let last = segments.pop()?;
+        let stem = last.strip_suffix(".rs")?;
+        if segments.is_empty() && (stem == "lib" || stem == "main") {
+            return Some(Vec::new());
+        }
+        if stem != "mod" {
+            segments.push(stem.to_string());
+        }
+        Some(segments)
+    }
+
+    /// The file declaring the children of the module at `segments`, if it exists
+    pub fn module_file(&self, segments: &[String]) -> Option<PathBuf> {
+        if segments.is_empty() {
+            return ["lib.rs", "main.rs"]
+                .iter()
+                .map(|name| self.src.join(name))
+                .find(|path| path.is_file());
+        }
+        let base = self.src.join(segments.join("/"));
+        [base.with_extension("rs"), base.join("mod.rs")]
+            .into_iter()
+            .find(|path| path.is_file())
+    }
+}
+
+/// Regex matching the declaration line of module `name`, capturing its visibility
+fn mod_decl_regex(name: &str) -> Regex {
+    Regex::new(&format!(
+        r"(?m)^[ \t]*(pub(?:\([^)]*\))?[ \t]+)?mod[ \t]+{}[ \t]*;[ \t]*\r?\n?",
+        regex::escape(name)
+    ))
+    .expect("valid module declaration regex")
+}
+
+/// Insert `decl` after the last `mod x;` line, or after the leading inner docs/attributes
+fn insert_mod_decl(content: &str, decl: &str) -> String {
+    let any_decl = Regex::new(r"(?m)^[ \t]*(pub(\([^)]*\))?[ \t]+)?mod[ \t]+\w+[ \t]*;[ \t]*$")
+        .expect("valid module declaration regex");
+    let offset = match any_decl.find_iter(content).last() {
+        Some(m) => content[m.end()..]
+            .find('\n')
+            .map(|i| m.end() + i + 1)
+            .unwrap_or(content.len()),
+        None => {
+            let mut offset = 0;
+            for line in content.split_inclusive('\n') {
+                let trimmed = line.trim_start();
+                if trimmed.starts_with("//!") || trimmed.starts_with("#![") {
+                    offset += line.len();
+                } else {
+                    break;
+                }
+            }
+            offset
+        }
+    };
+
+    let mut result = String::with_capacity(content.len() + decl.len() + 2);
+    result.push_str(&content[..offset]);
+    if offset > 0 && !result.ends_with('\n') {
+        result.push('\n');
+    }
+    let after_header = offset > 0 && !any_decl.is_match(&content[..offset]);
+    if after_header {
+        result.push('\n');
+    }
+    result.push_str(decl);
+    result.push('\n');
+    if after_header && !content[offset..].starts_with('\n') && offset < content.len() {
+        result.push('\n');
+    }
+    result.push_str(&content[offset..]);
+    result
+}
+
+/// Make sure the module at `segments` exists, creating `mod.rs` files as needed
+fn ensure_module(
+    state: &Arc<Mutex<ToolState>>,
+    layout: &CrateLayout,
+    segments: &[String],
+    touched: &mut Vec<String>,
+) -> Result<PathBuf> {
+    if let Some(file) = layout.module_file(segments) {
+        return Ok(file);
+    }
+    let Some((name, parent)) = segments.split_last() else {
+        anyhow::bail!(
+            "Crate root {} has no lib.rs or main.rs",
+            layout.src.display()
+        );
+    };
+    let parent_file = ensure_module(state, layout, parent, touched)?;
+
+    let file = layout.src.join(segments.join("/")).join("mod.rs");
+    writer::create_dir_all(state, file.parent().unwrap_or(&layout.src))?;
+    writer::write_file(state, &file, "")?;
+    touched.push(file.to_string_lossy().to_string());
+
+    let content = profiling::read_to_string(&parent_file)?;
+    let updated = insert_mod_decl(&content, &format!("pub mod {};", name));
+    writer::write_file(state, &parent_file, updated)?;
+    touched.push(parent_file.to_string_lossy().to_string());
+    Ok(file)
+}
+
+/// A declaration added to a parent module by [`register_module`]
+#[derive(Debug, Clone)]
+pub struct ModuleRegistration {
+    /// File the declaration was added to
+    pub parent: PathBuf,
+    /// The inserted line
+    pub line: String,
+    /// Files created or modified, including `parent`
+    pub files_touched: Vec<String>,
+}
+
+/// Declare the newly created source file `path` in its parent module
+///
+/// Rust files get `pub mod name;` in the parent module (creating missing `mod.rs`
+/// files), Python files get `from . import name` in the package's `__init__.py`.
+/// Returns `Ok(None)` when the module is already declared.
+pub fn register_module(
+    state: &Arc<Mutex<ToolState>>,
+    path: &Path,
+) -> Result<Option<ModuleRegistration>> {
+    match path.extension().and_then(|e| e.to_str()) {
+        Some("rs") => register_rust_module(state, path),
+        Some("py") => register_python_module(state, path),
+        _ => anyhow::bail!("only .rs and .py files can be registered as modules"),
+    }
+}
+
+fn register_rust_module(
+    state: &Arc<Mutex<ToolState>>,
+    path: &Path,
+) -> Result<Option<ModuleRegistration>> {
+    let layout = CrateLayout::find(path)
+        .ok_or_else(|| anyhow::anyhow!("no Cargo.toml found above {}", path.display()))?;
+    let segments = layout.module_segments(path).ok_or_else(|| {
+        anyhow::anyhow!(
+            "{} is not a module file under {}",
+            path.display(),
+            layout.src.display()
+        )
+    })?;
+    let Some((name, parent)) = segments.split_last() else {
+        return Ok(None);
+    };
+
+    let mut files_touched = Vec::new();
+    let parent_file = ensure_module(state, &layout, parent, &mut files_touched)?;
+    let content = profiling::read_to_string(&parent_file)?;
+    if mod_decl_regex(name).is_match(&content) {
+        return Ok(None);
+    }
+    let line = format!("pub mod {};", name);
+    writer::write_file(state, &parent_file, insert_mod_decl(&content, &line))?;
+    files_touched.push(parent_file.to_string_lossy().to_string());
+    Ok(Some(ModuleRegistration {
+        parent: parent_file,
+        line,
+        files_touched,
+    }))
+}
+
+fn register_python_module(
+    state: &Arc<Mutex<ToolState>>,
+    path: &Path,
+) -> Result<Option<ModuleRegistration>> {
+    let absolute = std::path::absolute(path)?;
+    let stem = absolute
+        .file_stem()
+        .and_then(|s| s.to_str())
+        .ok_or_else(|| anyhow::anyhow!("invalid module file name: {}", path.display()))?;
+    let dir = absolute
+        .parent()
+        .ok_or_else(|| anyhow::anyhow!("{} has no parent directory", path.display()))?;
+
+    // A new package's __init__.py is registered in the enclosing package
+    let (name, init) = if stem == "__init__" {
+        let name = dir
+            .file_name()
+            .and_then(|n| n.to_str())
+            .ok_or_else(|| anyhow::anyhow!("invalid package directory: {}", dir.display()))?;
+        let init = dir
+            .parent()
+            .map(|parent| parent.join("__init__.py"))
+            .filter(|init| init.is_file())
+            .ok_or_else(|| anyhow::anyhow!("package {} has no enclosing package", name))?;
+        (name.to_string(), init)
+    } else {
+        (stem.to_string(), dir.join("__init__.py"))
+    };
+
+    let content = if init.exists() {
+        profiling::read_to_string(&init)?
+    } else {
+        String::new()
+    };
+    let existing = Regex::new(&format!(
+        r"(?m)^from[ \t]+\.[ \t]+import[ \t]+.*\b{name}\b|^from[ \t]+\.{name}[ \t]+import\b",
+        name = regex::escape(&name)
+    ))
+    .expect("valid import regex");
+    if existing.is_match(&content) {
+        return Ok(None);
+    }
+
+    // Add the import after the last top-level import, or at the end
+    let line = format!("from . import {}", name);
+    let import = Regex::new(r"(?m)^(from|import)[ \t].*$").expect("valid import regex");
+    let updated = match import.find_iter(&content).last() {
+        Some(m) => format!("{}\n{}{}", &content[..m.end()], line, &content[m.end()..]),
+        None if content.is_empty() => format!("{}\n", line),
+        None => format!("{}\n{}\n", content.trim_end_matches('\n'), line),
+    };
+    writer::write_file(state, &init, updated)?;
+    Ok(Some(ModuleRegistration {
+        files_touched: vec![init.to_string_lossy().to_string()],
+        parent: init,
+        line,
+    }))
+}
+
+/// Rewrite `prefix::old` paths to `prefix::new` in `content`
+fn replace_path(content: &str, prefix: &str, old: &[String], new: &[String]) -> String {
+    let pattern = format!(
+        r"\b{}::{}\b",
+        regex::escape(prefix),
+        regex::escape(&old.join("::"))
+    );
+    let replacement = format!("{}::{}", prefix, new.join("::"));
+    Regex::new(&pattern)
+        .expect("valid path regex")
+        .replace_all(content, regex::NoExpand(&replacement))
+        .into_owned()
+}
+
+/// Rename `old` to `new` inside `prefix::parent::{...}` import groups
+fn replace_in_groups(
+    content: &str,
+    prefix: &str,
+    parent: &[String],
+    old: &str,
+    new: &str,
+) -> String {
+    let mut group_path = vec![regex::escape(prefix)];
+    group_path.extend(parent.iter().map(|s| regex::escape(s)));
+    let group = Regex::new(&format!(r"\b{}::\{{[^}}]*\}}", group_path.join("::")))
+        .expect("valid import group regex");
+    let name = Regex::new(&format!(r"\b{}\b", regex::escape(old))).expect("valid name regex");
+    group
+        .replace_all(content, |caps: &regex::Captures| {
+            name.replace_all(&caps[0], regex::NoExpand(new))
+                .into_owned()
+        })
+        .into_owned()
+}
+
+/// Whether `content` names `old` inside a `prefix::parent::{...}` import group
+fn named_in_group(content: &str, prefix: &str, parent: &[String], old: &str) -> bool {
+    let mut group_path = vec![regex::escape(prefix)];
+    group_path.extend(parent.iter().map(|s| regex::escape(s)));
+    Regex::new(&format!(
+        r"\b{}::\{{[^}}]*\b{}\b[^}}]*\}}",
+        group_path.join("::"),
+        regex::escape(old)
+    ))
+    .map(|re| re.is_match(content))
+    .unwrap_or(false)
+}
+
+/// Tool for moving a Rust module and updating references to it
+pub struct RenameModuleTool {
+    name: String,
+}
+
+impl RenameModuleTool {
+    pub fn new() -> Self {
+        Self {
+            name: "rename_module".to_string(),
+        }
+    }
+
+    /// Parse parameters from ToolArgs
+    fn parse_params(&self, args: &ToolArgs) -> Result<serde_json::Value, ToolError> {
+        // Try to parse as JSON first
+        if let Some(json_str) = args.get_named_arg("json") {
+            return serde_json::from_str(json_str).map_err(ToolError::Json);
+        }
+
+        // Check if we have structured named arguments
+        if !args.named_args.is_empty() {
+            return serde_json::to_value(&args.named_args).map_err(ToolError::Json);
+        }
+
+        // Fall back to positional arguments: old_path, new_path
+        if args.len() >= 2 {
+            let mut params = serde_json::Map::new();
+            params.insert(
+                "old_path".to_string(),
+                serde_json::Value::String(args.get_arg(0).unwrap().clone()),
+            );
+            params.insert(
+                "new_path".to_string(),
+                serde_json::Value::String(args.get_arg(1).unwrap().clone()),
+            );
+            return Ok(serde_json::Value::Object(params));
+        }
+
+        Err(ToolError::InvalidArgs {
+            message: "Insufficient parameters".to_string(),
+        })
+    }
+}
+
+impl Default for RenameModuleTool {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl Tool for RenameModuleTool {
+    fn name(&self) -> &str {
+        &self.name
+    }
+
+    fn description(&self) -> &str {
+        "Move or rename a Rust module file and update its mod declarations and use paths across the crate (best-effort; reports files touched and anything left to fix)"
+    }
+
+    fn signature(&self) -> &str {
+        "rename_module(old_path: str, new_path: str, allow_protected?: bool)"
+    }
+
+    fn category(&self) -> ToolCategory {
+        ToolCategory::Editing
+    }
+
+    fn danger_level(&self) -> DangerLevel {
+        DangerLevel::ModifiesFiles
+    }
+
+    fn validate_args(&self, args: &ToolArgs) -> Result<(), ToolError> {
+        let params = self.parse_params(args)?;
+
+        let obj = params.as_object().ok_or_else(|| ToolError::InvalidArgs {
+            message: "Parameters must be an object".to_string(),
+        })?;
+
+        for key in ["old_path", "new_path"] {
+            match obj.get(key).and_then(|v| v.as_str()) {
+                Some(path) if path.ends_with(".rs") => {}
+                Some(path) => {
+                    return Err(ToolError::InvalidArgs {
+                        message: format!("{} must be a .rs file, got '{}'", key, path),
+                    })
+                }
+                None => {
+                    return Err(ToolError::InvalidArgs {
+                        message: format!("Missing required parameter: {}", key),
+                    })
+                }
+            }
+        }
+
+        Ok(())
+    }
+
+    fn execute(&mut self, args: &ToolArgs, state: &Arc<Mutex<ToolState>>) -> Result<ToolResult> {
+        let params = self.parse_params(args)?;
+        let obj = params
+            .as_object()
+            .ok_or_else(|| anyhow::anyhow!("Invalid parameters"))?;
+
+        let old_path = session_path(
+            state,
+            obj.get("old_path")
+                .and_then(|v| v.as_str())
+                .ok_or_else(|| anyhow::anyhow!("Invalid old_path parameter"))?,
+        );
+        let new_path = session_path(
+            state,
+            obj.get("new_path")
+                .and_then(|v| v.as_str())
+                .ok_or_else(|| anyhow::anyhow!("Invalid new_path parameter"))?,
+        );
+
+        if !old_path.is_file() {
+            return Ok(path_not_found(
+                format!("Module file not found: {}", old_path.display()),
+                &old_path,
+            ));
+        }
+        if new_path.exists() {
+            return Ok(ToolResult::error(format!(
+                "Destination already exists: {}",
+                new_path.display()
+            )));
+        }
+
+        let allow_protected = protection::override_requested(obj);
+        if let Some(refused) = protection::check(state, &self.name, &old_path, allow_protected)? {
+            return Ok(refused);
+        }
+
+        let Some(layout) = CrateLayout::find(&old_path) else {
+            return Ok(ToolResult::error(format!(
+                "No Cargo.toml found above {}",
+                old_path.display()
+            )));
+        };
+        let (Some(old_segments), Some(new_segments)) = (
+            layout.module_segments(&old_path),
+            layout.module_segments(&new_path),
+        ) else {
+            return Ok(ToolResult::error(format!(
+                "Both paths must be module files under {} (outside src/bin)",
+                layout.src.display()
+            )));
+        };
+        let (Some((old_name, old_parent)), Some((new_name, new_parent))) =
+            (old_segments.split_last(), new_segments.split_last())
+        else {
+            return Ok(ToolResult::error(
+                "The crate root (lib.rs/main.rs) cannot be renamed".to_string(),
+            ));
+        };
+        let identifier = Regex::new(r"^[A-Za-z_][A-Za-z0-9_]*$").expect("valid identifier regex");
+        if !identifier.is_match(new_name) {
+            return Ok(ToolResult::error(format!(
+                "'{}' is not a valid module name",
+                new_name
+            )));
+        }
+
+        let old_is_mod_rs = old_path.file_name().and_then(|n| n.to_str()) == Some("mod.rs");
+        let new_is_mod_rs = new_path.file_name().and_then(|n| n.to_str()) == Some("mod.rs");
+        if old_is_mod_rs != new_is_mod_rs {
+            return Ok(ToolResult::error(
+                "Moving between `name.rs` and `name/mod.rs` layouts is not supported; keep the same layout".to_string(),
+            ));
+        }
+
+        let mut touched = Vec::new();
+        let mut warnings = Vec::new();
+
+        // Remember the old declaration before anything moves
+        let old_parent_file = layout.module_file(old_parent);
+        let decl_re = mod_decl_regex(old_name);
+        let visibility = old_parent_file
+            .as_ref()
+            .and_then(|file| profiling::read_to_string(file).ok())
+            .and_then(|content| {
+                decl_re
+                    .captures(&content)
+                    .map(|caps| caps.get(1).map(|v| v.as_str().trim().to_string()))
+            });
+        if visibility.is_none() {
+            warnings.push(format!(
+                "No `mod {};` declaration found in the parent module",
+                old_name
+            ));
+        }
+
+        let new_parent_file = ensure_module(state, &layout, new_parent, &mut touched)?;
+
+        // Move the module (and the directory holding its submodules)
+        if old_is_mod_rs {
+            let old_dir = old_path.parent().unwrap_or(&layout.src).to_path_buf();
+            let new_dir = new_path.parent().unwrap_or(&layout.src).to_path_buf();
+            if new_dir.exists() {
+                return Ok(ToolResult::error(format!(
+                    "Destination directory already exists: {}",
+                    new_dir.display()
+                )));
+            }
+            writer::rename(state, &old_dir, &new_dir)
+                .map_err(|e| anyhow::anyhow!("Failed to move module directory: {}", e))?;
+        } else {
+            if let Some(parent) = new_path.parent() {
+                writer::create_dir_all(state, parent)
+                    .map_err(|e| anyhow::anyhow!("Failed to create directories: {}", e))?;
+            }
+            writer::rename(state, &old_path, &new_path)
+                .map_err(|e| anyhow::anyhow!("Failed to move module: {}", e))?;
+            let old_dir = old_path.with_extension("");
+            let new_dir = new_path.with_extension("");
+            if old_dir.is_dir() {
+                if new_dir.exists() {
+                    warnings.push(format!(
+                        "Submodule directory {} was not moved because {} exists",
+                        old_dir.display(),
+                        new_dir.display()
+                    ));
+                } else {
+                    writer::rename(state, &old_dir, &new_dir).map_err(|e| {
+                        anyhow::anyhow!("Failed to move submodule directory: {}", e)
+                    })?;
+                }
+            }
+        }
+        touched.push(new_path.to_string_lossy().to_string());
+
+        // Update the mod declarations
+        let mut visibility = visibility.flatten().unwrap_or_default();
+        if visibility.is_empty() && old_parent != new_parent {
+            // A private module would no longer be reachable from its old users
+            visibility = "pub(crate)".to_string();
+        }
+        let new_decl = if visibility.is_empty() {
+            format!("mod {};", new_name)
+        } else {
+            format!("{} mod {};", visibility, new_name)
+        };
+        if let Some(old_parent_file) = &old_parent_file {
+            let content = profiling::read_to_string(old_parent_file)?;
+            let updated = if *old_parent_file == new_parent_file {
+                decl_re
+                    .replace(&content, |caps: &regex::Captures| {
+                        caps[0].replacen(old_name.as_str(), new_name, 1)
+                    })
+                    .into_owned()
+            } else {
+                decl_re.replace(&content, "").into_owned()
+            };
+            if updated != content {
+                writer::write_file(state, old_parent_file, &updated)?;
+                touched.push(old_parent_file.to_string_lossy().to_string());
+            }
+        }
+        if old_parent_file.as_ref() != Some(&new_parent_file) {
+            let content = profiling::read_to_string(&new_parent_file)?;
+            writer::write_file(
+                state,
+                &new_parent_file,
+                insert_mod_decl(&content, &new_decl),
+            )?;
+            touched.push(new_parent_file.to_string_lossy().to_string());
+        }
+
+        // Rewrite paths across the crate's sources, tests, benches and examples
+        let same_parent = old_parent == new_parent;
+        let relative_path =
+            Regex::new(&format!(r"\b{}\b", regex::escape(&old_segments.join("::"))))
+                .expect("valid path regex");
+        let files = ["src", "tests", "benches", "examples"]
+            .iter()
+            .flat_map(|dir| walkdir::WalkDir::new(layout.root.join(dir)).sort_by_file_name())
+            .filter_map(|e| e.ok())
+            .filter(|e| e.file_type().is_file())
+            .map(|e| e.into_path())
+            .filter(|p| p.extension().and_then(|e| e.to_str()) == Some("rs"));
+        for file in files {
+            let Ok(content) = profiling::read_to_string(&file) else {
+                continue;
+            };
+            let module = layout.module_segments(&file);
+            let mut updated = content.clone();
+
+            let mut prefixes = vec![layout.lib_name.clone()];
+            if module.is_some() {
+                prefixes.push("crate".to_string());
+            }
+            for prefix in prefixes.iter().filter(|p| !p.is_empty()) {
+                updated = replace_path(&updated, prefix, &old_segments, &new_segments);
+                if same_parent {
+                    updated = replace_in_groups(&updated, prefix, old_parent, old_name, new_name);
+                } else if named_in_group(&updated, prefix, old_parent, old_name) {
+                    warnings.push(format!(
+                        "{} imports {} through a grouped `use`; update it by hand",
+                        file.display(),
+                        old_name
+                    ));
+                }
+            }
+
+            if let Some(module) = &module {
+                if same_parent && module.as_slice() == old_parent {
+                    updated = replace_path(
+                        &updated,
+                        "self",
+                        std::slice::from_ref(old_name),
+                        std::slice::from_ref(new_name),
+                    );
+                }
+                let is_sibling = module.split_last().map(|(_, parent)| parent) == Some(old_parent);
+                if same_parent && is_sibling {
+                    updated = replace_path(
+                        &updated,
+                        "super",
+                        std::slice::from_ref(old_name),
+                        std::slice::from_ref(new_name),
+                    );
+                }
+            }
+
+            if old_segments.len() > 1 && relative_path.is_match(&updated) {
+                warnings.push(format!(
+                    "{} still refers to {} through a relative path; update it by hand",
+                    file.display(),
+                    old_segments.join("::")
+                ));
+            }
+
+            if updated != content {
+                writer::write_file(state, &file, &updated)?;
+                let file = file.to_string_lossy().to_string();
+                if !touched.contains(&file) {
+                    touched.push(file);
+                }
+            }
+        }
+
+        if old_parent.len() != new_parent.len() {
+            let moved = profiling::read_to_string(&new_path).unwrap_or_default();
+            if moved.contains("super::") {
+                warnings.push(format!(
+                    "{} uses `super::` paths, which now resolve relative to a different parent",
+                    new_path.display()
+                ));
+            }
+        }
+
+        {
+            let mut state_guard = state
+                .lock()
+                .map_err(|e| anyhow::anyhow!("Failed to lock state: {}", e))?;
+            state_guard.rename_file(&old_path, &new_path);
+            state_guard.push_history(format!(
+                "Renamed module {} to {}",
+                old_segments.join("::"),
+                new_segments.join("::")
+            ));
+        }
+
+        let mut message = format!(
+            "Renamed module {} to {} ({} file(s) touched):\n{}",
+            old_segments.join("::"),
+            new_segments.join("::"),
+            touched.len(),
+            touched.join("\n")
+        );
+        for warning in &warnings {
+            message.push_str(&format!("\nWarning: {}", warning));
+        }
+
+        Ok(ToolResult::success_with_data(
+            message,
+            serde_json::json!({
+                "old_module": old_segments.join("::"),
+                "new_module": new_segments.join("::"),
+                "files_touched": touched,
+                "warnings": warnings
+            }),
+        ))
+    }
+
+    fn get_parameters_schema(&self) -> serde_json::Value {
+        serde_json::json!({
+            "type": "object",
+            "properties": {
+                "old_path": {
+                    "type": "string",
+                    "description": "Current path of the module file (e.g. src/utils/helpers.rs)"
+                },
+                "new_path": {
+                    "type": "string",
+                    "description": "New path of the module file (e.g. src/support/helpers.rs)"
+                },
+                "allow_protected": {
+                    "type": "boolean",
+                    "description": "Move even if the module matches a protected glob (recorded in the history)",
+                    "default": false
+                }
+            },
+            "required": ["old_path", "new_path"]
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use tempfile::TempDir;
+
+    fn write(root: &Path, path: &str, content: &str) {
+        let path = root.join(path);
+        fs::create_dir_all(path.parent().unwrap()).unwrap();
+        fs::write(path, content).unwrap();
+    }
+
+    #[test]
+    fn test_rename_module_updates_references() {
+        let temp_dir = TempDir::new().unwrap();
+        let root = temp_dir.path();
+        write(root, "Cargo.toml", "[package]\nname = \"demo-crate\"\n");
+        write(
+            root,
+            "src/lib.rs",
+            "//! Demo\n\npub mod utils;\n\npub use utils::helpers::greet;\n",
+        );
+        write(
+            root,
+            "src/utils/mod.rs",
+            "pub mod helpers;\npub mod other;\n",
+        );
+        write(root, "src/utils/helpers.rs", "pub fn greet() {}\n");
+        write(
+            root,
+            "src/utils/other.rs",
+            "use super::helpers;\nuse crate::utils::{helpers::greet, other};\n",
+        );
+        write(
+            root,
+            "src/main.rs",
+            "fn main() { demo_crate::utils::helpers::greet(); }\n",
+        );
+
+        let mut tool_state = ToolState::new();
+        tool_state.working_directory = root.to_path_buf();
+        tool_state.journal = None;
+        let state = Arc::new(Mutex::new(tool_state));
+
+        let old_path = root.join("src/utils/helpers.rs");
+        let new_path = root.join("src/utils/greeting.rs");
+        let args = ToolArgs::from_args(&[old_path.to_str().unwrap(), new_path.to_str().unwrap()]);
+        let result = RenameModuleTool::new().execute(&args, &state).unwrap();
+        assert!(result.success, "{}", result.message);
+
+        let read = |p: &str| fs::read_to_string(root.join(p)).unwrap();
+        assert!(new_path.exists() && !old_path.exists());
+        assert_eq!(
+            read("src/utils/mod.rs"),
+            "pub mod greeting;\npub mod other;\n"
+        );
+        assert_eq!(
+            read("src/utils/other.rs"),
+            "use super::greeting;\nuse crate::utils::{greeting::greet, other};\n"
+        );
+        assert!(read("src/main.rs").contains("demo_crate::utils::greeting::greet()"));
+        // `utils::helpers` in lib.rs is a relative path the rewrite does not cover
+        assert!(read("src/lib.rs").contains("pub use utils::helpers::greet;"));
+        let warnings = result.data.unwrap()["warnings"].to_string();
+        assert!(warnings.contains("lib.rs still refers to utils::helpers"));
+    }
+
+    #[test]
+    fn test_rename_module_into_new_parent() {
+        let temp_dir = TempDir::new().unwrap();
+        let root = temp_dir.path();
+        write(root, "Cargo.toml", "[package]\nname = \"demo\"\n");
+        write(
+            root,
+            "src/lib.rs",
+            "//! Demo\n\nmod helpers;\n\nuse crate::helpers::greet;\n",
+        );
+        write(root, "src/helpers.rs", "pub fn greet() {}\n");
+
+        let mut tool_state = ToolState::new();
+        tool_state.working_directory = root.to_path_buf();
+        tool_state.journal = None;
+        let state = Arc::new(Mutex::new(tool_state));
+
+        let args = ToolArgs::from_args(&[
+            root.join("src/helpers.rs").to_str().unwrap(),
+            root.join("src/support/helpers.rs").to_str().unwrap(),
+        ]);
+        let result = RenameModuleTool::new().execute(&args, &state).unwrap();
+        assert!(result.success, "{}", result.message);
+
+        assert_eq!(
+            fs::read_to_string(root.join("src/lib.rs")).unwrap(),
+            "//! Demo\n\npub mod support;\n\nuse crate::support::helpers::greet;\n"
+        );
+        assert_eq!(
+            fs::read_to_string(root.join("src/support/mod.rs")).unwrap(),
+            "pub(crate) mod helpers;\n"
+        );
+    }
+}
@@ -0,0 +1,149 @@
+//! Byte spans that always fall on UTF-8 character boundaries
+//!
+//! Editing code locates text with byte offsets (`str::find`, regex matches, offsets
+//! computed on normalized copies) and then slices or splices the original string.
+//! Slicing at an offset inside a multi-byte character panics, so offsets are wrapped
+//! in a [`Span`] that is checked or snapped to character boundaries before use.
+
+use std::ops::Range;
+
+/// A byte range of a string whose ends are character boundaries of that string
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, PartialOrd, Ord)]
+pub struct Span {
+    start: usize,
+    end: usize,
+}
+
+/// The nearest character boundary at or before `index`, within `text`
+fn floor_boundary(text: &str, index: usize) -> usize {
+    let mut index = index.min(text.len());
+    while !text.is_char_boundary(index) {
+        index -= 1;
+    }
+    index
+}
+
+/// The nearest character boundary at or after `index`, within `text`
+fn ceil_boundary(text: &str, index: usize) -> usize {
+    let mut index = index.min(text.len());
+    while !text.is_char_boundary(index) {
+        index += 1;
+    }
+    index
+}
+
+impl Span {
+    /// The span `start..end` of `text`, if both ends are character boundaries
+    pub fn new(text: &str, start: usize, end: usize) -> Option<Self> {
+        (start <= end && text.is_char_boundary(start) && text.is_char_boundary(end))
+            .then_some(Self { start, end })
+    }
+
+    /// A span from offsets already known to be character boundaries
+    pub(super) fn from_offsets(start: usize, end: usize) -> Self {
+        debug_assert!(start <= end);
+        Self { start, end }
+    }
+
+    /// The smallest span of `text` covering `start..end`, clamped to its length
+    ///
+    /// Use this for offsets that were not computed on `text` itself.
+    pub fn snap(text: &str, start: usize, end: usize) -> Self {
+        let start = floor_boundary(text, start);
+        let end = ceil_boundary(text, end.max(start));
+        Self { start, end }
+    }
+
+    /// Every non-overlapping occurrence of `needle` in `haystack`, left to right
+    ///
+    /// An empty needle has no occurrences.
+    pub fn find_all(haystack: &str, needle: &str) -> Vec<Self> {
+        if needle.is_empty() {
+            return Vec::new();
+        }
+        haystack
+            .match_indices(needle)
+            .map(|(start, matched)| Self {
+                start,
+                end: start + matched.len(),
+            })
+            .collect()
+    }
+
+    pub fn start(&self) -> usize {
+        self.start
+    }
+
+    pub fn end(&self) -> usize {
+        self.end
+    }
+
+    pub fn len(&self) -> usize {
+        self.end - self.start
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.start == self.end
+    }
+
+    pub fn range(&self) -> Range<usize> {
+        self.start..self.end
+    }
+
+    /// The spanned text, or an empty string if `text` is not the string the span was made for
+    pub fn slice<'a>(&self, text: &'a str) -> &'a str {
+        text.get(self.range()).unwrap_or("")
+    }
+
+    /// Replace the spanned text of `text` with `replacement`
+    ///
+    /// Returns false (leaving `text` unchanged) if the span does not fit `text`.
+    pub fn replace(&self, text: &mut String, replacement: &str) -> bool {
+        if text.get(self.range()).is_none() {
+            return false;
+        }
+        text.replace_range(self.range(), replacement);
+        true
+    }
+
+    /// 1-based line number of the span's start in `text`
+    pub fn start_line(&self, text: &str) -> usize {
+        text.as_bytes()[..self.start.min(text.len())]
+            .iter()
+            .filter(|&&b| b == b'\n')
+            .count()
+            + 1
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_spans_respect_char_boundaries() {
+        let text = "let s = \"日本語\"; // 🚀 done\n";
+        // Bytes 10 and 13 are inside 日 and 本
+        assert_eq!(Span::new(text, 10, 13), None);
+        let snapped = Span::snap(text, 10, 13);
+        assert_eq!(snapped.slice(text), "日本");
+        assert_eq!(Span::snap(text, 0, 1000).end(), text.len());
+
+        let rocket = Span::find_all(text, "🚀");
+        assert_eq!(rocket.len(), 1);
+        assert_eq!(rocket[0].len(), 4);
+        assert_eq!(rocket[0].slice(text), "🚀");
+
+        let mut edited = text.to_string();
+        assert!(rocket[0].replace(&mut edited, "✅"));
+        assert_eq!(edited, "let s = \"日本語\"; // ✅ done\n");
+        // A span from another string is refused instead of panicking
+        let wide = Span::snap("🚀🚀🚀🚀", 4, 8);
+        let mut short = "ab".to_string();
+        assert!(!wide.replace(&mut short, "x"));
+        assert_eq!(wide.slice("ab"), "");
+
+        assert!(Span::find_all(text, "").is_empty());
+        assert_eq!(Span::find_all("é\né\n", "é")[1].start_line("é\né\n"), 2);
+    }
+}
@@ -0,0 +1,188 @@
+//! Opt-in syntax validation of writes
+//!
+//! `create_file`, `overwrite_file` and `replace_text` take a `validate_syntax` parameter.
+//! The content about to be written is parsed with the tree-sitter grammar of the file's
+//! language. With `true` the write is refused when the result has more parse errors than
+//! the file had before, which catches output cut off mid-function or with unbalanced
+//! brackets before it lands on disk; with `"warn"` the file is written and the errors
+//! are reported as warnings. Files in languages without a bundled grammar are written
+//! unchecked, as is every file in builds without the `ast` feature.
+
+#[cfg(feature = "ast")]
+use crate::ast::{self, Language, SyntaxError};
+use crate::core::ToolResult;
+use std::path::Path;
+
+/// Parameter enabling the check for a single call
+pub const VALIDATE_PARAM: &str = "validate_syntax";
+
+/// Errors listed in a refusal or warning; the data holds them all
+#[cfg(feature = "ast")]
+const MAX_LISTED: usize = 5;
+
+/// What to do with a write that does not parse
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum SyntaxMode {
+    Off,
+    Reject,
+    Warn,
+}
+
+impl SyntaxMode {
+    /// The mode requested by the `validate_syntax` parameter of `obj`
+    pub fn from_params(obj: &serde_json::Map<String, serde_json::Value>) -> Result<Self, String> {
+        match obj.get(VALIDATE_PARAM) {
+            None | Some(serde_json::Value::Null) | Some(serde_json::Value::Bool(false)) => {
+                Ok(Self::Off)
+            }
+            Some(serde_json::Value::Bool(true)) => Ok(Self::Reject),
+            Some(serde_json::Value::String(s)) => match s.to_lowercase().as_str() {
+                "true" | "reject" => Ok(Self::Reject),
+                "warn" => Ok(Self::Warn),
+                "false" | "off" => Ok(Self::Off),
+                other => Err(format!(
+                    "Invalid {} '{}': expected true, false or \"warn\"",
+                    VALIDATE_PARAM, other
+                )),
+            },
+            Some(other) => Err(format!(
+                "Invalid {} {}: expected true, false or \"warn\"",
+                VALIDATE_PARAM, other
+            )),
+        }
+    }
+}
+
+/// Parse errors of `after`, when it has more than `before` (the current content, `None`
+/// for a new file); the language comes from `path`
+#[cfg(feature = "ast")]
+pub fn introduced_errors(
+    path: &Path,
+    before: Option<&str>,
+    after: &str,
+) -> Option<(Language, Vec<SyntaxError>)> {
+    let language = Language::from_path(path)?;
+    let errors = ast::syntax_errors(language, after);
+    let existing = before.map_or(0, |before| ast::syntax_errors(language, before).len());
+    (errors.len() > existing).then_some((language, errors))
+}
+
+#[cfg(feature = "ast")]
+fn describe(path: &Path, language: Language, errors: &[SyntaxError]) -> String {
+    let mut text = format!(
+        "{} does not parse as {:?} ({} syntax error(s)):",
+        path.display(),
+        language,
+        errors.len()
+    );
+    for error in errors.iter().take(MAX_LISTED) {
+        text.push_str(&format!(
+            "\n  {}:{}: {}",
+            error.line, error.column, error.message
+        ));
+    }
+    if errors.len() > MAX_LISTED {
+        text.push_str(&format!("\n  ... and {} more", errors.len() - MAX_LISTED));
+    }
+    text
+}
+
+/// Check `after` before it replaces `before` at `path`
+///
+/// Returns the refusal to hand back in [`SyntaxMode::Reject`], and in
+/// [`SyntaxMode::Warn`] the warning to add to the result of the write.
+#[cfg(feature = "ast")]
+pub fn check(
+    mode: SyntaxMode,
+    path: &Path,
+    before: Option<&str>,
+    after: &str,
+) -> Result<Option<String>, ToolResult> {
+    if mode == SyntaxMode::Off {
+        return Ok(None);
+    }
+    let Some((language, errors)) = introduced_errors(path, before, after) else {
+        return Ok(None);
+    };
+    let description = describe(path, language, &errors);
+    match mode {
+        SyntaxMode::Reject => Err(ToolResult::error_with_data(
+            format!(
+                "Refused to write: {}\nThe content may be truncated or unbalanced. Fix it, or pass {}: \"warn\" to write it anyway",
+                description, VALIDATE_PARAM
+            ),
+            serde_json::json!({
+                "path": path.to_string_lossy(),
+                "language": language,
+                "syntax_errors": errors,
+                "written": false
+            }),
+        )),
+        _ => Ok(Some(description)),
+    }
+}
+
+/// Without the `ast` feature there is no grammar to parse with, so every write passes
+#[cfg(not(feature = "ast"))]
+pub fn check(
+    _mode: SyntaxMode,
+    _path: &Path,
+    _before: Option<&str>,
+    _after: &str,
+) -> Result<Option<String>, ToolResult> {
+    Ok(None)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_syntax_mode_from_params() {
+        let params = |value: serde_json::Value| {
+            let mut obj = serde_json::Map::new();
+            obj.insert(VALIDATE_PARAM.to_string(), value);
+            SyntaxMode::from_params(&obj)
+        };
+        assert_eq!(
+            SyntaxMode::from_params(&serde_json::Map::new()),
+            Ok(SyntaxMode::Off)
+        );
+        assert_eq!(params(true.into()), Ok(SyntaxMode::Reject));
+        assert_eq!(params("true".into()), Ok(SyntaxMode::Reject));
+        assert_eq!(params("warn".into()), Ok(SyntaxMode::Warn));
+        assert!(params("maybe".into()).is_err());
+    }
+
+    #[test]
+    #[cfg(feature = "ast")]
+    fn test_check_only_counts_new_errors() {
+        let path = Path::new("lib.rs");
+        let broken = "fn a() {\n    let x = 1\n}\n";
+        assert!(matches!(
+            check(SyntaxMode::Reject, path, None, "fn a() {}\n"),
+            Ok(None)
+        ));
+        assert!(matches!(
+            check(SyntaxMode::Reject, Path::new("notes.txt"), None, "fn a( {"),
+            Ok(None)
+        ));
+
+        let refused = check(SyntaxMode::Reject, path, None, broken).unwrap_err();
+        assert!(
+            refused.message.contains("2:14: missing `;`"),
+            "{}",
+            refused.message
+        );
+        let warning = check(SyntaxMode::Warn, path, None, broken)
+            .unwrap()
+            .unwrap();
+        assert!(warning.starts_with("lib.rs does not parse as Rust (1 syntax error(s))"));
+
+        // Editing a file that was already broken is not blamed for its old errors
+        assert!(matches!(
+            check(SyntaxMode::Reject, path, Some(broken), broken),
+            Ok(None)
+        ));
+    }
+}
@@ -0,0 +1,252 @@
+//! Session trash for recoverable deletions
+//!
+//! `delete_path` moves targets into `.cats/trash/<timestamp>/` (under the working
+//! directory, or the system temp directory for paths outside it) instead of removing
+//! them, keeping their path relative to the working directory. `purge_trash` empties it.
+//!
+//! The temp directory trash is shared by every session and nothing else cleans it, so
+//! each deletion into it first drops its sessions older than
+//! [`EXTERNAL_TRASH_MAX_AGE_DAYS`], then the oldest ones until it holds at most
+//! [`EXTERNAL_TRASH_MAX_BYTES`].
+
+use super::writer;
+use crate::state::ToolState;
+use std::fs;
+use std::io;
+use std::path::{Component, Path, PathBuf};
+use std::sync::{Arc, Mutex};
+use std::time::{SystemTime, UNIX_EPOCH};
+
+/// Trash directory relative to the working directory
+pub const TRASH_DIR: &str = ".cats/trash";
+
+/// Age in days after which sessions in the external trash are removed
+pub const EXTERNAL_TRASH_MAX_AGE_DAYS: u64 = 7;
+
+/// Size in bytes the external trash is pruned down to, not counting the current session
+pub const EXTERNAL_TRASH_MAX_BYTES: u64 = 512 * 1024 * 1024;
+
+/// Trash root used for paths outside the working directory
+pub fn external_trash_root() -> PathBuf {
+    std::env::temp_dir().join("cats-trash")
+}
+
+/// Trash root for `path`: the working directory's trash when `path` is inside it
+pub fn trash_root(working_dir: &Path, path: &Path) -> PathBuf {
+    let absolute = std::path::absolute(path).unwrap_or_else(|_| path.to_path_buf());
+    if absolute.starts_with(working_dir) {
+        working_dir.join(TRASH_DIR)
+    } else {
+        external_trash_root()
+    }
+}
+
+/// Whether `path` already lives inside a trash directory
+pub fn is_in_trash(working_dir: &Path, path: &Path) -> bool {
+    let absolute = std::path::absolute(path).unwrap_or_else(|_| path.to_path_buf());
+    absolute.starts_with(working_dir.join(TRASH_DIR)) || absolute.starts_with(external_trash_root())
+}
+
+/// Location of `path` inside the session trash directory `session_dir`
+fn trash_location(working_dir: &Path, session_dir: &Path, path: &Path) -> PathBuf {
+    let absolute = std::path::absolute(path).unwrap_or_else(|_| path.to_path_buf());
+    let relative = absolute.strip_prefix(working_dir).unwrap_or(&absolute);
+    let relative: PathBuf = relative
+        .components()
+        .filter(|c| matches!(c, Component::Normal(_)))
+        .collect();
+
+    // Never overwrite an earlier deletion of the same path
+    let mut candidate = session_dir.join(&relative);
+    let mut counter = 1;
+    while candidate.exists() {
+        candidate = session_dir.join(format!("{}.{}", relative.display(), counter));
+        counter += 1;
+    }
+    candidate
+}
+
+/// Move `path` into the session trash, returning its new location
+pub fn move_to_trash(state: &Arc<Mutex<ToolState>>, path: &Path) -> io::Result<PathBuf> {
+    let (working_dir, session) = {
+        let mut state_guard = state
+            .lock()
+            .map_err(|e| io::Error::other(format!("Failed to lock state: {}", e)))?;
+        let session = state_guard
+            .trash_session
+            .get_or_insert_with(|| {
+                SystemTime::now()
+                    .duration_since(UNIX_EPOCH)
+                    .map(|d| d.as_secs())
+                    .unwrap_or(0)
+                    .to_string()
+            })
+            .clone();
+        (state_guard.working_directory.clone(), session)
+    };
+
+    let root = trash_root(&working_dir, path);
+    let session_dir = root.join(session);
+    if root == external_trash_root() {
+        prune(
+            &root,
+            &session_dir,
+            EXTERNAL_TRASH_MAX_AGE_DAYS * 24 * 60 * 60,
+            EXTERNAL_TRASH_MAX_BYTES,
+        );
+    }
+    let destination = trash_location(&working_dir, &session_dir, path);
+    if let Some(parent) = destination.parent() {
+        fs::create_dir_all(parent)?;
+    }
+
+    match writer::rename(state, path, &destination) {
+        Ok(()) => Ok(destination),
+        // Renames fail across filesystems; fall back to copying and removing
+        Err(_) if path.is_dir() => {
+            copy_dir_all(path, &destination)?;
+            writer::remove_dir(state, path, true)?;
+            Ok(destination)
+        }
+        Err(_) => {
+            fs::copy(path, &destination)?;
+            writer::remove_file(state, path)?;
+            Ok(destination)
+        }
+    }
+}
+
+/// Seconds since the trash session at `path` was created, from its timestamp name or
+/// else its modification time
+pub fn session_age(path: &Path, now: u64) -> u64 {
+    let created = path
+        .file_name()
+        .and_then(|n| n.to_str())
+        .and_then(|n| n.parse::<u64>().ok())
+        .or_else(|| {
+            fs::metadata(path)
+                .and_then(|m| m.modified())
+                .ok()
+                .and_then(|t| t.duration_since(UNIX_EPOCH).ok())
+                .map(|d| d.as_secs())
+        })
+        .unwrap_or(0);
+    now.saturating_sub(created)
+}
+
+/// Remove the sessions under `root` older than `max_age` seconds, then the oldest until
+/// the rest take at most `max_bytes`; `keep` is never removed
+///
+/// Failures are ignored: another process may be pruning the same directory.
+fn prune(root: &Path, keep: &Path, max_age: u64, max_bytes: u64) {
+    let Ok(entries) = fs::read_dir(root) else {
+        return;
+    };
+    let now = SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .map(|d| d.as_secs())
+        .unwrap_or(0);
+    let mut sessions: Vec<(u64, PathBuf)> = entries
+        .filter_map(|e| e.ok())
+        .map(|e| e.path())
+        .filter(|path| path != keep)
+        .map(|path| (session_age(&path, now), path))
+        .collect();
+    // Oldest first
+    sessions.sort_by_key(|(age, _)| std::cmp::Reverse(*age));
+    let mut total: u64 = sessions.iter().map(|(_, path)| disk_usage(path)).sum();
+    for (age, path) in sessions {
+        if age <= max_age && total <= max_bytes {
+            break;
+        }
+        let size = disk_usage(&path);
+        if fs::remove_dir_all(&path).is_ok() {
+            total = total.saturating_sub(size);
+        }
+    }
+}
+
+fn copy_dir_all(source: &Path, destination: &Path) -> io::Result<()> {
+    fs::create_dir_all(destination)?;
+    for entry in fs::read_dir(source)? {
+        let entry = entry?;
+        let target = destination.join(entry.file_name());
+        if entry.file_type()?.is_dir() {
+            copy_dir_all(&entry.path(), &target)?;
+        } else {
+            fs::copy(entry.path(), &target)?;
+        }
+    }
+    Ok(())
+}
+
+/// Total size in bytes of the files under `path`
+pub fn disk_usage(path: &Path) -> u64 {
+    walkdir::WalkDir::new(path)
+        .into_iter()
+        .filter_map(|e| e.ok())
+        .filter_map(|e| e.metadata().ok())
+        .filter(|m| m.is_file())
+        .map(|m| m.len())
+        .sum()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use tempfile::TempDir;
+
+    #[test]
+    fn test_move_to_trash_keeps_relative_path() {
+        let temp_dir = TempDir::new().unwrap();
+        let working_dir = temp_dir.path().to_path_buf();
+        fs::create_dir_all(working_dir.join("src")).unwrap();
+        fs::write(working_dir.join("src/a.rs"), "fn a() {}").unwrap();
+
+        let mut tool_state = ToolState::new();
+        tool_state.working_directory = working_dir.clone();
+        tool_state.journal = None;
+        let state = Arc::new(Mutex::new(tool_state));
+
+        let trashed = move_to_trash(&state, &working_dir.join("src/a.rs")).unwrap();
+        assert!(!working_dir.join("src/a.rs").exists());
+        assert!(trashed.starts_with(working_dir.join(TRASH_DIR)));
+        assert!(trashed.ends_with("src/a.rs"));
+        assert!(is_in_trash(&working_dir, &trashed));
+
+        // A second deletion of the same path gets its own slot
+        fs::write(working_dir.join("src/a.rs"), "fn b() {}").unwrap();
+        let second = move_to_trash(&state, &working_dir.join("src/a.rs")).unwrap();
+        assert!(second.ends_with("src/a.rs.1"));
+        assert_eq!(fs::read_to_string(second).unwrap(), "fn b() {}");
+    }
+
+    #[test]
+    fn test_prune_drops_old_and_oversized_sessions() {
+        let temp_dir = TempDir::new().unwrap();
+        let root = temp_dir.path();
+        let now = SystemTime::now()
+            .duration_since(UNIX_EPOCH)
+            .unwrap()
+            .as_secs();
+        let session = |age: u64, bytes: usize| {
+            let dir = root.join((now - age).to_string());
+            fs::create_dir_all(&dir).unwrap();
+            fs::write(dir.join("f"), vec![0u8; bytes]).unwrap();
+            dir
+        };
+        let expired = session(1000, 1);
+        let older = session(300, 60);
+        let newer = session(200, 60);
+        let current = session(0, 500);
+
+        prune(root, &current, 900, 100);
+        assert!(!expired.exists());
+        assert!(!older.exists(), "the oldest session goes to fit the size");
+        assert!(newer.exists());
+        assert!(
+            current.exists(),
+            "the current session is kept whatever its size"
+        );
+    }
+}
@@ -0,0 +1,466 @@
+//! Detection of truncated or elided content
+//!
+//! Models that run out of output tokens, or that abbreviate what they consider
+//! unchanged, hand `create_file` and `overwrite_file` content that would wipe out most
+//! of a file. Before those writes the content is checked for the usual signs:
+//!
+//! - syntax errors the original did not have, in languages with a bundled grammar
+//!   (Rust, Python, JavaScript, TypeScript, Go)
+//! - in other bracketed languages (C, C++, Java, ...), brackets opened and never closed
+//!   or a string left open, beyond what the original already had; the contents of
+//!   strings, character literals and comments do not count
+//! - a source file that ended with a newline now ending in the middle of an identifier
+//! - when an existing file is replaced, placeholder comments such as
+//!   `// ... existing code ...` or a bare `// ...`, in the comments of a language with
+//!   a grammar, naming what the original's comments did not; and in other bracketed
+//!   languages a bare `...` line the original lacked
+//!
+//! Prose, shell scripts and other files without a grammar are never searched for
+//! placeholders: a `# Keep existing code paths working` there is just text. Without the
+//! `ast` feature there are no grammars, and files in those languages are not checked.
+//!
+//! A suspicious write is refused unless the call passes `allow_suspicious: true`; it is
+//! then written and the findings are reported as warnings.
+
+#[cfg(feature = "ast")]
+use super::syntax;
+#[cfg(feature = "ast")]
+use crate::ast::{self, Language};
+use crate::core::ToolResult;
+use regex::Regex;
+use serde::Serialize;
+use std::collections::HashSet;
+use std::path::Path;
+use std::sync::OnceLock;
+
+/// Parameter writing suspicious content anyway
+pub const OVERRIDE_PARAM: &str = "allow_suspicious";
+
+/// Extensions of bracketed languages without a bundled grammar
+const BRACE_EXTENSIONS: &[&str] = &[
+    "c", "h", "cc", "cpp", "hpp", "java", "kt", "cs", "swift", "scala", "php", "json", "css",
+    "scss",
+];
+
+/// Pairs whose balance is compared
+const BRACKETS: [(char, char); 3] = [('{', '}'), ('(', ')'), ('[', ']')];
+
+/// Whether the call passed `allow_suspicious: true`
+pub fn override_requested(obj: &serde_json::Map<String, serde_json::Value>) -> bool {
+    super::bool_param(obj, OVERRIDE_PARAM).unwrap_or(false)
+}
+
+/// One sign of truncation found in the content about to be written
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+pub struct Suspicion {
+    /// `syntax_error`, `unclosed_bracket`, `unbalanced_quotes`, `ends_mid_identifier` or
+    /// `elision`
+    pub kind: &'static str,
+    /// 1-based line of the content the finding points at
+    pub line: usize,
+    pub message: String,
+}
+
+/// Extension of a bracketed language without a bundled grammar
+fn brace_extension(path: &Path) -> Option<&str> {
+    path.extension()
+        .and_then(|ext| ext.to_str())
+        .filter(|ext| BRACE_EXTENSIONS.contains(ext))
+}
+
+/// What is still open at the end of `content` in a C-like language: the lines of the
+/// unclosed brackets of each pair, and the line of a string that never ends
+///
+/// Strings, character literals and comments are skipped, so `'"'` or `"("` are not
+/// counted. `line_comments` is false for CSS, where `//` appears in unquoted URLs.
+fn open_at_end(content: &str, line_comments: bool) -> ([Vec<usize>; 3], Option<usize>) {
+    let mut open: [Vec<usize>; 3] = Default::default();
+    let mut line = 1;
+    let mut previous = '\n';
+    let mut chars = content.chars().peekable();
+    while let Some(c) = chars.next() {
+        match c {
+            '\n' => line += 1,
+            // A quote after a digit or letter is a digit separator like `1'000`
+            '"' | '\'' if !(c == '\'' && previous.is_alphanumeric()) => {
+                let start = line;
+                let mut closed = false;
+                while let Some(inner) = chars.next() {
+                    match inner {
+                        '\\' => line += usize::from(chars.next() == Some('\n')),
+                        '\n' => line += 1,
+                        _ if inner == c => {
+                            closed = true;
+                            break;
+                        }
+                        _ => {}
+                    }
+                }
+                if !closed {
+                    return (open, Some(start));
+                }
+            }
+            '/' if chars.peek() == Some(&'*') => {
+                chars.next();
+                let mut last = ' ';
+                for inner in chars.by_ref() {
+                    if inner == '\n' {
+                        line += 1;
+                    } else if last == '*' && inner == '/' {
+                        break;
+                    }
+                    last = inner;
+                }
+            }
+            '/' if line_comments && chars.peek() == Some(&'/') => {
+                if chars.by_ref().any(|inner| inner == '\n') {
+                    line += 1;
+                }
+            }
+            _ => {
+                if let Some(pair) = BRACKETS.iter().position(|(opener, _)| *opener == c) {
+                    open[pair].push(line);
+                } else if let Some(pair) = BRACKETS.iter().position(|(_, closer)| *closer == c) {
+                    open[pair].pop();
+                }
+            }
+        }
+        previous = c;
+    }
+    (open, None)
+}
+
+/// A comment line holding a placeholder; `phrase` is set when it says what was left
+/// out, with `what` naming it, and unset for a bare `// ...`
+fn elision_pattern() -> &'static Regex {
+    static ELISION: OnceLock<Regex> = OnceLock::new();
+    ELISION.get_or_init(|| {
+        Regex::new(
+            r"(?i)^\s*(?://+|#+|/\*+|\*|<!--|--|;+)\s*(?:(?P<phrase>.*\b(?P<what>rest of (?:the )?(?:file|code|function|class|module|implementation|method|body)|existing code|(?:remains?|stays?) (?:the same|unchanged)|unchanged code|same as before)\b)|\.\.\.|…)",
+        )
+        .expect("valid elision pattern")
+    })
+}
+
+/// Comment lines of `content`, when the language of `path` has a grammar
+fn comment_lines(path: &Path, content: &str) -> Vec<(usize, String)> {
+    #[cfg(feature = "ast")]
+    if let Some(language) = Language::from_path(path) {
+        return ast::comment_lines(language, content);
+    }
+    #[cfg(not(feature = "ast"))]
+    let _ = (path, content);
+    Vec::new()
+}
+
+/// Signs that `after`, about to replace `before` at `path` (`None` for a new file), was
+/// cut off or abbreviated
+pub fn suspicions(path: &Path, before: Option<&str>, after: &str) -> Vec<Suspicion> {
+    let mut found = Vec::new();
+    #[cfg(feature = "ast")]
+    let grammar = Language::from_path(path).is_some();
+    #[cfg(not(feature = "ast"))]
+    let grammar = false;
+    let brace = brace_extension(path);
+
+    #[cfg(feature = "ast")]
+    if let Some((language, errors)) = syntax::introduced_errors(path, before, after) {
+        let first = &errors[0];
+        found.push(Suspicion {
+            kind: "syntax_error",
+            line: first.line,
+            message: format!(
+                "does not parse as {:?}: {} ({} syntax error(s))",
+                language,
+                first.message,
+                errors.len()
+            ),
+        });
+    }
+
+    if let Some(ext) = brace {
+        let scan = |text: &str| open_at_end(text, ext != "css");
+        let (existing, existing_string) = before.map(scan).unwrap_or_default();
+        let (open, string) = scan(after);
+        for (pair, lines) in open.iter().enumerate() {
+            if lines.len() > existing[pair].len() {
+                let line = lines[existing[pair].len()];
+                found.push(Suspicion {
+                    kind: "unclosed_bracket",
+                    line,
+                    message: format!(
+                        "`{}` opened on line {} is never closed ({} unclosed)",
+                        BRACKETS[pair].0,
+                        line,
+                        lines.len()
+                    ),
+                });
+            }
+        }
+        if let (Some(line), None) = (string, existing_string) {
+            found.push(Suspicion {
+                kind: "unbalanced_quotes",
+                line,
+                message: format!("the string opened on line {} is never closed", line),
+            });
+        }
+    }
+
+    // An identifier at the very end, unless it is the tail of an escape like `\n`; only
+    // an existing file that ended with a newline tells that one is missing
+    let is_word = |c: char| c.is_alphanumeric() || c == '_';
+    let ends_mid_identifier = after.chars().last().is_some_and(is_word)
+        && !after.trim_end_matches(is_word).ends_with('\\');
+    if (grammar || brace.is_some())
+        && ends_mid_identifier
+        && before.is_some_and(|before| before.ends_with('\n'))
+    {
+        let line = after.lines().count();
+        found.push(Suspicion {
+            kind: "ends_mid_identifier",
+            line,
+            message: format!(
+                "ends mid-identifier without a final newline: `{}`",
+                after.lines().last().unwrap_or_default().trim()
+            ),
+        });
+    }
+
+    // A new file has nothing a placeholder could stand in for
+    let Some(before) = before else {
+        return found;
+    };
+    let mut placeholder = |line: usize, text: &str| {
+        found.push(Suspicion {
+            kind: "elision",
+            line,
+            message: format!("placeholder for omitted content: `{}`", text),
+        })
+    };
+    let original = comment_lines(path, before);
+    let original_lines: HashSet<&str> = original.iter().map(|(_, line)| line.trim()).collect();
+    let original_phrases: HashSet<String> = original
+        .iter()
+        .filter_map(|(_, line)| elision_pattern().captures(line))
+        .filter_map(|captures| captures.name("what"))
+        .map(|what| what.as_str().to_lowercase())
+        .collect();
+    for (line, text) in comment_lines(path, after) {
+        let trimmed = text.trim();
+        if original_lines.contains(trimmed) {
+            continue;
+        }
+        let elided =
+            elision_pattern()
+                .captures(&text)
+                .is_some_and(|captures| match captures.name("what") {
+                    Some(what) => !original_phrases.contains(&what.as_str().to_lowercase()),
+                    None => true,
+                });
+        if elided {
+            placeholder(line, trimmed);
+        }
+    }
+    // A bare `...` on its own line is a placeholder in C-like code; grammars report it
+    // as a syntax error, and it is Python's Ellipsis
+    if brace.is_some() {
+        let bare = |line: &str| matches!(line.trim(), "..." | "…");
+        if !before.lines().any(bare) {
+            for (index, text) in after.lines().enumerate() {
+                if bare(text) {
+                    placeholder(index + 1, text.trim());
+                }
+            }
+        }
+    }
+    found
+}
+
+fn describe(path: &Path, found: &[Suspicion]) -> String {
+    let mut text = format!(
+        "{} looks truncated or elided ({} finding(s)):",
+        path.display(),
+        found.len()
+    );
+    for suspicion in found {
+        text.push_str(&format!(
+            "\n  line {}: {}",
+            suspicion.line, suspicion.message
+        ));
+    }
+    text
+}
+
+/// Check `after` before it replaces `before` at `path`
+///
+/// Returns the refusal to hand back when the content is suspicious and `allowed` is
+/// false, and otherwise the warning to add to the result of the write.
+pub fn check(
+    path: &Path,
+    before: Option<&str>,
+    after: &str,
+    allowed: bool,
+) -> Result<Option<String>, ToolResult> {
+    let found = suspicions(path, before, after);
+    if found.is_empty() {
+        return Ok(None);
+    }
+    let description = describe(path, &found);
+    if allowed {
+        return Ok(Some(format!(
+            "{}\nWritten anyway because {} was set",
+            description, OVERRIDE_PARAM
+        )));
+    }
+    Err(ToolResult::error_with_data(
+        format!(
+            "Refused to write: {}\nSend the complete content, or pass {}: true if it is meant to look like this",
+            description, OVERRIDE_PARAM
+        ),
+        serde_json::json!({
+            "path": path.to_string_lossy(),
+            "suspicions": found,
+            "written": false
+        }),
+    ))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    #[cfg(feature = "ast")]
+    fn test_suspicions_in_cut_off_content() {
+        let path = Path::new("lib.rs");
+        let kinds = |before: Option<&str>, after: &str| -> Vec<&'static str> {
+            suspicions(path, before, after)
+                .into_iter()
+                .map(|s| s.kind)
+                .collect()
+        };
+
+        assert!(kinds(None, "fn a() {\n    println!(\"{}\", 1);\n}\n").is_empty());
+        assert_eq!(
+            kinds(
+                Some("fn a() {}\n"),
+                "fn a() {\n    let total = compute(1, 2);\n    tot"
+            ),
+            ["syntax_error", "ends_mid_identifier"]
+        );
+        assert_eq!(
+            kinds(None, "fn a() {\n    let s = \"open;\n}\n"),
+            ["syntax_error"]
+        );
+        assert_eq!(
+            kinds(
+                Some("fn a() {}\nfn b() {}\n"),
+                "fn a() {}\n// ... existing code ...\n"
+            ),
+            ["elision"]
+        );
+
+        // Only what the new content adds counts
+        let broken = "fn a() {\n";
+        assert!(kinds(Some(broken), "fn a() {\n    b();\n").is_empty());
+        assert!(kinds(Some("// ...\n"), "// ...\nfn a() {}\n").is_empty());
+        // Prose and Python's Ellipsis are left alone
+        assert!(suspicions(Path::new("notes.md"), None, "(1) see below...").is_empty());
+        assert!(suspicions(Path::new("a.py"), None, "def f():\n    ...\n").is_empty());
+        assert_eq!(
+            suspicions(
+                Path::new("a.py"),
+                Some("x = 1\n"),
+                "# rest of the file unchanged\n"
+            )
+            .len(),
+            1
+        );
+        // A phrase the original's comments already used is not a placeholder
+        assert!(suspicions(
+            Path::new("a.py"),
+            Some("# Wraps the existing code\nx = 1\n"),
+            "# Calls into existing code\nx = 2\n"
+        )
+        .is_empty());
+
+        let c = |before: Option<&str>, after: &str| -> Vec<&'static str> {
+            suspicions(Path::new("main.c"), before, after)
+                .into_iter()
+                .map(|s| s.kind)
+                .collect()
+        };
+        assert_eq!(
+            c(None, "int main() {\n    return 0;\n"),
+            ["unclosed_bracket"]
+        );
+        // The open string swallows the closing brackets
+        assert_eq!(
+            c(None, "int main() {\n    puts(\"hi);\n}\n"),
+            ["unclosed_bracket", "unclosed_bracket", "unbalanced_quotes"]
+        );
+        assert_eq!(c(Some("int a;\nint b;\n"), "int a;\n...\n"), ["elision"]);
+    }
+
+    #[test]
+    fn test_valid_code_is_not_suspicious() {
+        for (path, content) in [
+            ("a.rs", "fn main() {\n    let q = '\"';\n}\n"),
+            ("b.py", "def wrap(x):\n    return \"(\" + x\n"),
+            ("c.py", "# ...\nx = 1\n"),
+            ("d.js", "const s = \"// rest of the file\";\n"),
+            (
+                "e.c",
+                "char q = '\"';\nchar *s = \"(\"; /* { */\n// [\nint n = 1'000;\n",
+            ),
+            ("f.css", "a { background: url(http://x/y.png); }\n"),
+            ("README.md", "# Guide\n\n## Refactoring existing code\n"),
+            ("script.sh", "# Keep existing code paths working\nrun\n"),
+            ("notes.txt", "-- rest of the file is for later\n"),
+        ] {
+            assert!(
+                suspicions(Path::new(path), None, content).is_empty(),
+                "{}: {:?}",
+                path,
+                suspicions(Path::new(path), None, content)
+            );
+        }
+        // Nor when they replace a file
+        for (path, content) in [
+            ("README.md", "## Refactoring existing code\n"),
+            ("script.sh", "# Keep existing code paths working\n"),
+            ("notes.txt", "-- rest of the file is for later\n"),
+        ] {
+            assert!(suspicions(Path::new(path), Some("old\n"), content).is_empty());
+        }
+        // The same placeholder counts once there is a file it could stand in for
+        #[cfg(feature = "ast")]
+        assert_eq!(
+            suspicions(Path::new("c.py"), Some("x = 1\n"), "# ...\nx = 1\n").len(),
+            1
+        );
+    }
+
+    #[test]
+    #[cfg(feature = "ast")]
+    fn test_check_refuses_unless_allowed() {
+        let path = Path::new("main.go");
+        let refused = check(path, Some("package main\n"), "package ma", false).unwrap_err();
+        assert!(refused
+            .message
+            .starts_with("Refused to write: main.go looks truncated"));
+        assert_eq!(
+            refused.data.unwrap()["suspicions"][0]["kind"],
+            "ends_mid_identifier"
+        );
+
+        let warning = check(path, Some("package main\n"), "package ma", true)
+            .unwrap()
+            .unwrap();
+        assert!(warning.contains("allow_suspicious"));
+        assert!(matches!(
+            check(path, None, "package main\n", false),
+            Ok(None)
+        ));
+    }
+}
@@ -0,0 +1,351 @@
+//! Journaled filesystem mutations shared by the editing tools
+//!
+//! Tools change files through these helpers instead of `std::fs` so that every
+//! mutation is recorded in the session journal (see `state::journal`) before and
+//! after it happens. Journal failures are logged and never block the operation.
+
+use super::artifacts::TouchedPath;
+use super::diff::unified_diff;
+use crate::events::ChangeKind;
+use crate::profiling;
+use crate::state::journal::{Journal, Operation, OperationKind};
+use crate::state::ToolState;
+use std::fmt::Display;
+use std::fs;
+use std::io;
+use std::path::Path;
+use std::sync::{Arc, Mutex};
+
+fn with_journal<R>(
+    state: &Arc<Mutex<ToolState>>,
+    f: impl FnOnce(&mut Journal) -> io::Result<R>,
+) -> Option<R> {
+    let mut state_guard = state.lock().ok()?;
+    with_journal_in(&mut state_guard, f)
+}
+
+fn with_journal_in<R>(
+    state: &mut ToolState,
+    f: impl FnOnce(&mut Journal) -> io::Result<R>,
+) -> Option<R> {
+    let journal = state.journal.as_mut()?;
+    match f(journal) {
+        Ok(value) => Some(value),
+        Err(e) => {
+            tracing::warn!(
+                "Failed to write journal {}: {}",
+                journal.path().display(),
+                e
+            );
+            None
+        }
+    }
+}
+
+/// Remember the path changed by a successful `op` for the post-execution checks and
+/// `FileModified` events
+fn record_touched(state: &Arc<Mutex<ToolState>>, op: &Operation) {
+    if let Ok(mut state_guard) = state.lock() {
+        record_touched_in(&mut state_guard, op);
+    }
+}
+
+fn record_touched_in(state: &mut ToolState, op: &Operation) {
+    let touched = match op.kind {
+        OperationKind::Write => TouchedPath {
+            diff: op.patch.clone(),
+            ..TouchedPath::new(
+                op.path.clone(),
+                if op.before_hash.is_none() {
+                    ChangeKind::Created
+                } else {
+                    ChangeKind::Modified
+                },
+            )
+        },
+        OperationKind::Move => match &op.to {
+            Some(to) => TouchedPath {
+                from: Some(op.path.clone()),
+                ..TouchedPath::new(to.clone(), ChangeKind::Renamed)
+            },
+            None => return,
+        },
+        OperationKind::Copy => match &op.to {
+            Some(to) => TouchedPath::new(to.clone(), ChangeKind::Created),
+            None => return,
+        },
+        OperationKind::CreateDir | OperationKind::Symlink => {
+            TouchedPath::new(op.path.clone(), ChangeKind::Created)
+        }
+        OperationKind::Delete => TouchedPath {
+            diff: op
+                .before
+                .as_deref()
+                .map(|before| unified_diff(before, "", &op.path.to_string_lossy())),
+            ..TouchedPath::new(op.path.clone(), ChangeKind::Deleted)
+        },
+        OperationKind::DeleteDir => TouchedPath::new(op.path.clone(), ChangeKind::Deleted),
+    };
+    state.touched_paths.push(touched);
+}
+
+/// Keep the text before and after a successful write or deletion for `undo`
+///
+/// Files whose previous content was not text cannot be restored and are not recorded.
+fn record_edit(state: &Arc<Mutex<ToolState>>, op: &Operation, after: Option<&[u8]>) {
+    if op.before.is_none() && op.before_hash.is_some() {
+        return;
+    }
+    let after = match after.map(std::str::from_utf8) {
+        Some(Ok(text)) => Some(text.to_string()),
+        Some(Err(_)) => return,
+        None => None,
+    };
+    if let Ok(mut state_guard) = state.lock() {
+        state_guard.record_edit(&op.path, op.before.clone(), after);
+    }
+}
+
+/// Run `action`, recording `op` in the journal before and its outcome after
+pub fn journaled<T, E: Display>(
+    state: &Arc<Mutex<ToolState>>,
+    op: Operation,
+    action: impl FnOnce() -> Result<T, E>,
+) -> Result<T, E> {
+    let intent = with_journal(state, |journal| journal.record_intent(&op));
+    let result = action();
+    if let Some(intent) = intent {
+        with_journal(state, |journal| match &result {
+            Ok(_) => journal.record_applied(intent),
+            Err(e) => journal.record_failed(intent, &e.to_string()),
+        });
+    }
+    if result.is_ok() {
+        record_touched(state, &op);
+    }
+    result
+}
+
+/// Write `contents` to `path`, creating or replacing the file
+///
+/// The file is read back afterwards and the outcome recorded on its `TouchedPath`, so
+/// silent truncation, encoding rewrites or a concurrent writer show up as an
+/// unverified write instead of a success.
+pub fn write_file<P: AsRef<Path>, C: AsRef<[u8]>>(
+    state: &Arc<Mutex<ToolState>>,
+    path: P,
+    contents: C,
+) -> io::Result<()> {
+    let (path, contents) = (path.as_ref(), contents.as_ref());
+    let op = Operation::write(path, contents);
+    journaled(state, op.clone(), || profiling::write(path, contents))?;
+    record_edit(state, &op, Some(contents));
+    record_verified(state, path, contents);
+    Ok(())
+}
+
+/// Read `path` back and record on its `TouchedPath` whether it holds `contents`
+fn record_verified(state: &Arc<Mutex<ToolState>>, path: &Path, contents: &[u8]) {
+    if let Ok(mut state_guard) = state.lock() {
+        record_verified_in(&mut state_guard, path, contents);
+    }
+}
+
+fn record_verified_in(state: &mut ToolState, path: &Path, contents: &[u8]) {
+    let verified = fs::read(path).is_ok_and(|written| written == contents);
+    if !verified {
+        tracing::warn!("{} did not read back as written", path.display());
+    }
+    if let Some(touched) = state
+        .touched_paths
+        .iter_mut()
+        .rev()
+        .find(|t| t.path == path)
+    {
+        touched.verified = Some(verified);
+    }
+}
+
+/// Put `path` back to `contents`, deleting it for `None`, on behalf of `undo` and `redo`
+///
+/// Takes the already locked state. The change is journaled and reported like any other
+/// write but not recorded as a new edit, so undoing does not itself become undoable.
+pub(crate) fn restore_file(
+    state: &mut ToolState,
+    path: &Path,
+    contents: Option<&str>,
+) -> io::Result<()> {
+    let op = match contents {
+        Some(contents) => Operation::write(path, contents.as_bytes()),
+        None => Operation::delete(path),
+    };
+    let intent = with_journal_in(state, |journal| journal.record_intent(&op));
+    let result = match contents {
+        Some(contents) => profiling::write(path, contents.as_bytes()),
+        None => fs::remove_file(path),
+    };
+    if let Some(intent) = intent {
+        with_journal_in(state, |journal| match &result {
+            Ok(_) => journal.record_applied(intent),
+            Err(e) => journal.record_failed(intent, &e.to_string()),
+        });
+    }
+    result?;
+    record_touched_in(state, &op);
+    if let Some(contents) = contents {
+        record_verified_in(state, path, contents.as_bytes());
+    }
+    Ok(())
+}
+
+/// Write `contents` to `path` without keeping its text in the journal
+///
+/// For files holding secrets such as `.env`: the journal records the hashes and a patch
+/// of the `redact`ed before and after text, but no previous content, so the write can be
+/// audited but not rolled back from the journal.
+pub fn write_file_redacted(
+    state: &Arc<Mutex<ToolState>>,
+    path: &Path,
+    contents: &str,
+    redact: impl Fn(&str) -> String,
+) -> io::Result<()> {
+    let mut op = Operation::write(path, contents.as_bytes());
+    let before = op.before.take().unwrap_or_default();
+    op.patch = Some(unified_diff(
+        &redact(&before),
+        &redact(contents),
+        &path.to_string_lossy(),
+    ));
+    journaled(state, op, || profiling::write(path, contents.as_bytes()))?;
+    record_verified(state, path, contents.as_bytes());
+    Ok(())
+}
+
+/// Delete the file at `path`
+pub fn remove_file(state: &Arc<Mutex<ToolState>>, path: &Path) -> io::Result<()> {
+    let op = Operation::delete(path);
+    journaled(state, op.clone(), || fs::remove_file(path))?;
+    record_edit(state, &op, None);
+    Ok(())
+}
+
+/// Delete the directory at `path`, including its contents when `recursive`
+pub fn remove_dir(state: &Arc<Mutex<ToolState>>, path: &Path, recursive: bool) -> io::Result<()> {
+    journaled(state, Operation::delete_dir(path), || {
+        if recursive {
+            fs::remove_dir_all(path)
+        } else {
+            fs::remove_dir(path)
+        }
+    })
+}
+
+/// Move `from` to `to`
+pub fn rename(state: &Arc<Mutex<ToolState>>, from: &Path, to: &Path) -> io::Result<()> {
+    journaled(state, Operation::rename(from, to), || fs::rename(from, to))
+}
+
+/// Create the directory `path` and any missing parents
+pub fn create_dir_all(state: &Arc<Mutex<ToolState>>, path: &Path) -> io::Result<()> {
+    journaled(state, Operation::create_dir(path), || {
+        fs::create_dir_all(path)
+    })
+}
+
+/// Create a symbolic link at `link` pointing to `target`
+///
+/// On Windows the link is created as a directory link when `target` (relative to the
+/// link's parent) is a directory, which requires Developer Mode or elevated rights.
+pub fn symlink(state: &Arc<Mutex<ToolState>>, target: &Path, link: &Path) -> io::Result<()> {
+    journaled(state, Operation::symlink(target, link), || {
+        #[cfg(unix)]
+        {
+            std::os::unix::fs::symlink(target, link)
+        }
+        #[cfg(windows)]
+        {
+            let resolved = link
+                .parent()
+                .map(|parent| parent.join(target))
+                .unwrap_or_else(|| target.to_path_buf());
+            if resolved.is_dir() {
+                std::os::windows::fs::symlink_dir(target, link)
+            } else {
+                std::os::windows::fs::symlink_file(target, link)
+            }
+        }
+        #[cfg(not(any(unix, windows)))]
+        {
+            let _ = (target, link);
+            Err(io::Error::new(
+                io::ErrorKind::Unsupported,
+                "symbolic links are not supported on this platform",
+            ))
+        }
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::state::journal::{recover, RecoverOptions};
+    use tempfile::TempDir;
+
+    #[test]
+    fn test_write_file_is_journaled() {
+        let temp_dir = TempDir::new().unwrap();
+        let journal_dir = temp_dir.path().join("journal");
+        let mut tool_state = ToolState::new();
+        tool_state.journal = Some(Journal::new(journal_dir.clone()));
+        let state = Arc::new(Mutex::new(tool_state));
+
+        let path = temp_dir.path().join("a.txt");
+        write_file(&state, &path, "hello\n").unwrap();
+
+        assert_eq!(fs::read_to_string(&path).unwrap(), "hello\n");
+        let report = recover(&journal_dir, RecoverOptions::default()).unwrap();
+        let op = &report.batches[0].operations[0];
+        assert_eq!(op.op.before_hash, None);
+        assert!(op.op.patch.as_ref().unwrap().contains("+hello"));
+    }
+
+    #[test]
+    fn test_write_file_is_verified() {
+        let temp_dir = TempDir::new().unwrap();
+        let state = Arc::new(Mutex::new(ToolState::new()));
+
+        let path = temp_dir.path().join("a.txt");
+        write_file(&state, &path, "hello\n").unwrap();
+        let touched = &state.lock().unwrap().touched_paths;
+        assert_eq!(touched.len(), 1);
+        assert_eq!(touched[0].verified, Some(true));
+    }
+
+    #[test]
+    fn test_changes_are_recorded_with_diffs() {
+        let temp_dir = TempDir::new().unwrap();
+        let state = Arc::new(Mutex::new(ToolState::new()));
+        let (a, b) = (temp_dir.path().join("a.txt"), temp_dir.path().join("b.txt"));
+
+        write_file(&state, &a, "one\n").unwrap();
+        write_file(&state, &a, "two\n").unwrap();
+        rename(&state, &a, &b).unwrap();
+        remove_file(&state, &b).unwrap();
+
+        let touched = std::mem::take(&mut state.lock().unwrap().touched_paths);
+        let kinds: Vec<_> = touched.iter().map(|t| t.kind).collect();
+        assert_eq!(
+            kinds,
+            vec![
+                ChangeKind::Created,
+                ChangeKind::Modified,
+                ChangeKind::Renamed,
+                ChangeKind::Deleted
+            ]
+        );
+        let modified = touched[1].diff.as_deref().unwrap();
+        assert!(modified.contains("-one") && modified.contains("+two"));
+        assert_eq!(touched[2].from.as_deref(), Some(a.as_path()));
+        assert!(touched[3].diff.as_deref().unwrap().contains("-two"));
+    }
+}
@@ -0,0 +1,173 @@
+//! Tool execution events
+//!
+//! The registry publishes typed events as tools run, so UIs and loggers can follow an
+//! agent in real time instead of polling `_state` or scraping stdout. Subscribe with
+//! `ToolRegistry::subscribe`; events sent while nobody is subscribed are dropped, and
+//! a receiver that falls [`EVENT_CAPACITY`] events behind misses the newer ones until
+//! it catches up.
+//!
+//! The channel is built on `std::sync::mpsc`, one bounded queue per receiver, so the
+//! library needs no async runtime. Async code can drain a receiver with `try_recv` or
+//! from a blocking task.
+
+use crate::core::ToolArgs;
+use serde::{Deserialize, Serialize};
+use std::path::PathBuf;
+use std::sync::mpsc::{self, SyncSender, TrySendError};
+use std::sync::{Arc, Mutex, PoisonError};
+
+/// Number of events buffered per receiver before newer ones are dropped
+pub const EVENT_CAPACITY: usize = 1024;
+
+/// Sending half of the event channel, shared by the registry and every session state
+///
+/// Clones publish to the same receivers.
+#[derive(Debug, Clone, Default)]
+pub struct EventSender {
+    receivers: Arc<Mutex<Vec<SyncSender<ToolEvent>>>>,
+}
+
+/// Receiving half of the event channel, from `ToolRegistry::subscribe`
+pub type EventReceiver = mpsc::Receiver<ToolEvent>;
+
+impl EventSender {
+    /// A new receiver of every event sent from now on
+    pub fn subscribe(&self) -> EventReceiver {
+        let (sender, receiver) = mpsc::sync_channel(EVENT_CAPACITY);
+        self.receivers
+            .lock()
+            .unwrap_or_else(PoisonError::into_inner)
+            .push(sender);
+        receiver
+    }
+
+    /// Number of receivers still subscribed, as of the last send
+    pub fn receiver_count(&self) -> usize {
+        self.receivers
+            .lock()
+            .unwrap_or_else(PoisonError::into_inner)
+            .len()
+    }
+
+    /// Queue `event` for every receiver, forgetting the dropped ones
+    pub fn send(&self, event: ToolEvent) {
+        self.receivers
+            .lock()
+            .unwrap_or_else(PoisonError::into_inner)
+            .retain(|receiver| match receiver.try_send(event.clone()) {
+                Ok(()) | Err(TrySendError::Full(_)) => true,
+                Err(TrySendError::Disconnected(_)) => false,
+            });
+    }
+}
+
+/// How a tool changed a path
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum ChangeKind {
+    Created,
+    Modified,
+    Deleted,
+    /// Moved here from another path
+    Renamed,
+}
+
+/// Output stream of a running command
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum OutputStream {
+    Stdout,
+    Stderr,
+}
+
+/// An event published while tools execute
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(tag = "event", rename_all = "snake_case")]
+pub enum ToolEvent {
+    /// A tool call is about to run
+    ToolStarted {
+        session: String,
+        tool: String,
+        args: ToolArgs,
+    },
+    /// A tool call returned; `success` is false for errors and failed results
+    ToolFinished {
+        session: String,
+        tool: String,
+        success: bool,
+        duration_ms: u64,
+        message: String,
+    },
+    /// A tool changed a path, published once per file operation so editors can
+    /// refresh buffers as the agent works
+    FileModified {
+        session: String,
+        path: PathBuf,
+        change_kind: ChangeKind,
+        /// Unified diff of the change for text files that were written or deleted
+        #[serde(default, skip_serializing_if = "Option::is_none")]
+        diff: Option<String>,
+        /// Previous path of a renamed file
+        #[serde(default, skip_serializing_if = "Option::is_none")]
+        renamed_from: Option<PathBuf>,
+    },
+    /// A line (or partial line) of output from a command started by `run_command`
+    CommandOutputChunk {
+        command: String,
+        stream: OutputStream,
+        chunk: String,
+    },
+}
+
+/// Create the sending half of a new event channel
+pub fn channel() -> EventSender {
+    EventSender::default()
+}
+
+/// Publish `event` if anyone is listening
+pub fn emit(sender: Option<&EventSender>, event: ToolEvent) {
+    if let Some(sender) = sender {
+        if sender.receiver_count() > 0 {
+            sender.send(event);
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn chunk(n: usize) -> ToolEvent {
+        ToolEvent::CommandOutputChunk {
+            command: "seq".to_string(),
+            stream: OutputStream::Stdout,
+            chunk: n.to_string(),
+        }
+    }
+
+    #[test]
+    fn test_channel_fans_out_and_bounds_receivers() {
+        let sender = channel();
+        emit(Some(&sender), chunk(0));
+        let slow = sender.subscribe();
+        let dropped = sender.subscribe();
+        drop(dropped);
+        assert_eq!(sender.receiver_count(), 2);
+
+        for n in 1..=EVENT_CAPACITY + 1 {
+            emit(Some(&sender.clone()), chunk(n));
+        }
+        assert_eq!(sender.receiver_count(), 1);
+        let received: Vec<String> = slow
+            .try_iter()
+            .map(|event| match event {
+                ToolEvent::CommandOutputChunk { chunk, .. } => chunk,
+                _ => unreachable!(),
+            })
+            .collect();
+        // Nothing from before the subscription, and the newest past the capacity is lost
+        assert_eq!(received.len(), EVENT_CAPACITY);
+        assert_eq!(received[0], "1");
+        assert_eq!(received.last().unwrap(), &EVENT_CAPACITY.to_string());
+    }
+}
@@ -0,0 +1,854 @@
+//! audit_dependencies tool: known vulnerabilities in a project's dependencies
+//!
+//! Runs the ecosystem's audit tool when it is installed (cargo-audit, npm audit,
+//! pip-audit) with JSON output and normalizes what each reports into one finding
+//! structure: package, installed version, advisory, severity and fixed versions. The
+//! lockfile fills in installed versions the audit tool leaves out and whether each
+//! vulnerable package is a direct dependency.
+
+use super::output::clean_output;
+use crate::core::{DangerLevel, Tool, ToolArgs, ToolCategory, ToolError, ToolResult};
+use crate::state::{session_path, ToolState};
+use crate::utils::dependencies::{normalize_python_name, parse_lockfile};
+use anyhow::{Context, Result};
+use serde::Serialize;
+use std::path::Path;
+use std::process::Command;
+use std::sync::{Arc, Mutex};
+
+/// Output lines of a failed audit run included in the message
+const OUTPUT_TAIL_LINES: usize = 20;
+
+/// Findings listed in the message, most severe first
+const LISTED_FINDINGS: usize = 30;
+
+/// Audit tools `audit_dependencies` can drive
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "kebab-case")]
+pub enum Auditor {
+    /// cargo-audit, checking `Cargo.lock` against the RustSec advisory database
+    CargoAudit,
+    /// npm audit, checking `package-lock.json` against the npm registry
+    NpmAudit,
+    /// pip-audit, checking requirements or a Python project against PyPI and OSV
+    PipAudit,
+}
+
+impl Auditor {
+    /// Parse an ecosystem name as accepted by the `ecosystem` tool parameter
+    pub fn from_name(name: &str) -> Option<Self> {
+        match name.to_lowercase().as_str() {
+            "cargo" | "rust" | "cargo-audit" => Some(Self::CargoAudit),
+            "npm" | "node" | "javascript" | "npm-audit" => Some(Self::NpmAudit),
+            "python" | "pip" | "poetry" | "pip-audit" => Some(Self::PipAudit),
+            _ => None,
+        }
+    }
+
+    /// Canonical tool name
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            Self::CargoAudit => "cargo-audit",
+            Self::NpmAudit => "npm-audit",
+            Self::PipAudit => "pip-audit",
+        }
+    }
+
+    /// How to install the tool
+    fn install_hint(&self) -> &'static str {
+        match self {
+            Self::CargoAudit => "cargo install cargo-audit",
+            Self::NpmAudit => "install Node.js, which ships npm",
+            Self::PipAudit => "pip install pip-audit",
+        }
+    }
+
+    /// Auditors for the manifests and lockfiles in `dir`
+    pub fn detect(dir: &Path) -> Vec<Self> {
+        let has = |name: &str| dir.join(name).is_file();
+        let mut auditors = Vec::new();
+        if has("Cargo.lock") || has("Cargo.toml") {
+            auditors.push(Self::CargoAudit);
+        }
+        // npm audit needs a lockfile to know what is installed
+        if has("package-lock.json") || has("npm-shrinkwrap.json") {
+            auditors.push(Self::NpmAudit);
+        }
+        if ["requirements.txt", "pyproject.toml", "poetry.lock"]
+            .iter()
+            .any(|name| has(name))
+        {
+            auditors.push(Self::PipAudit);
+        }
+        auditors
+    }
+
+    /// Program and arguments of the JSON audit run in `dir`
+    pub fn command(&self, dir: &Path) -> (&'static str, Vec<String>) {
+        match self {
+            Self::CargoAudit => ("cargo", vec!["audit".into(), "--json".into()]),
+            Self::NpmAudit => ("npm", vec!["audit".into(), "--json".into()]),
+            Self::PipAudit => {
+                let mut args = vec!["--format".to_string(), "json".to_string()];
+                if dir.join("requirements.txt").is_file() {
+                    args.extend(["--requirement".to_string(), "requirements.txt".to_string()]);
+                } else {
+                    // A pyproject.toml project, resolved by pip-audit itself
+                    args.push(".".to_string());
+                }
+                ("pip-audit", args)
+            }
+        }
+    }
+
+    /// Whether the tool is installed
+    fn available(&self) -> bool {
+        let (program, args): (&str, &[&str]) = match self {
+            Self::CargoAudit => ("cargo", &["audit", "--version"]),
+            Self::NpmAudit => ("npm", &["--version"]),
+            Self::PipAudit => ("pip-audit", &["--version"]),
+        };
+        Command::new(program)
+            .args(args)
+            .output()
+            .map(|output| output.status.success())
+            .unwrap_or(false)
+    }
+
+    /// Lockfile whose versions complete this tool's findings
+    fn lockfile(&self) -> &'static str {
+        match self {
+            Self::CargoAudit => "Cargo.lock",
+            Self::NpmAudit => "package-lock.json",
+            Self::PipAudit => "poetry.lock",
+        }
+    }
+
+    /// Normalize the tool's JSON report
+    pub fn parse(&self, report: &str) -> Result<Vec<AuditFinding>> {
+        let report: serde_json::Value = serde_json::from_str(report.trim())
+            .with_context(|| format!("{} did not print a JSON report", self.as_str()))?;
+        Ok(match self {
+            Self::CargoAudit => parse_cargo_audit(&report),
+            Self::NpmAudit => parse_npm_audit(&report),
+            Self::PipAudit => parse_pip_audit(&report),
+        })
+    }
+}
+
+/// Severity of a finding, comparable across ecosystems
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Serialize)]
+#[serde(rename_all = "snake_case")]
+pub enum Severity {
+    /// The advisory carries no severity (pip-audit never reports one)
+    Unknown,
+    /// Not a vulnerability: unmaintained, yanked or unsound packages
+    Info,
+    Low,
+    Moderate,
+    High,
+    Critical,
+}
+
+impl Severity {
+    /// Parse a severity name as used by npm and the `severity` tool parameter
+    pub fn from_name(name: &str) -> Option<Self> {
+        match name.to_lowercase().as_str() {
+            "unknown" => Some(Self::Unknown),
+            "info" | "informational" | "none" => Some(Self::Info),
+            "low" => Some(Self::Low),
+            "moderate" | "medium" => Some(Self::Moderate),
+            "high" => Some(Self::High),
+            "critical" => Some(Self::Critical),
+            _ => None,
+        }
+    }
+
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            Self::Unknown => "unknown",
+            Self::Info => "info",
+            Self::Low => "low",
+            Self::Moderate => "moderate",
+            Self::High => "high",
+            Self::Critical => "critical",
+        }
+    }
+
+    /// Severity band of a CVSS base score
+    pub fn from_score(score: f64) -> Self {
+        match score {
+            s if s >= 9.0 => Self::Critical,
+            s if s >= 7.0 => Self::High,
+            s if s >= 4.0 => Self::Moderate,
+            s if s > 0.0 => Self::Low,
+            _ => Self::Info,
+        }
+    }
+}
+
+/// Base score of a CVSS 3.x vector (`CVSS:3.1/AV:N/AC:L/...`), as RustSec advisories
+/// carry them
+pub fn cvss3_base_score(vector: &str) -> Option<f64> {
+    let mut metrics = std::collections::HashMap::new();
+    for part in vector.split('/').skip(1) {
+        let (metric, value) = part.split_once(':')?;
+        metrics.insert(metric, value);
+    }
+    let changed = *metrics.get("S")? == "C";
+    let attack_vector = match *metrics.get("AV")? {
+        "N" => 0.85,
+        "A" => 0.62,
+        "L" => 0.55,
+        "P" => 0.2,
+        _ => return None,
+    };
+    let complexity = match *metrics.get("AC")? {
+        "L" => 0.77,
+        "H" => 0.44,
+        _ => return None,
+    };
+    let privileges = match (*metrics.get("PR")?, changed) {
+        ("N", _) => 0.85,
+        ("L", false) => 0.62,
+        ("L", true) => 0.68,
+        ("H", false) => 0.27,
+        ("H", true) => 0.5,
+        _ => return None,
+    };
+    let interaction = match *metrics.get("UI")? {
+        "N" => 0.85,
+        "R" => 0.62,
+        _ => return None,
+    };
+    let impact_of = |metric: &str| -> Option<f64> {
+        match *metrics.get(metric)? {
+            "H" => Some(0.56),
+            "L" => Some(0.22),
+            "N" => Some(0.0),
+            _ => None,
+        }
+    };
+    let iss = 1.0 - (1.0 - impact_of("C")?) * (1.0 - impact_of("I")?) * (1.0 - impact_of("A")?);
+    let impact = if changed {
+        7.52 * (iss - 0.029) - 3.25 * (iss - 0.02).powi(15)
+    } else {
+        6.42 * iss
+    };
+    if impact <= 0.0 {
+        return Some(0.0);
+    }
+    let exploitability = 8.22 * attack_vector * complexity * privileges * interaction;
+    let score = if changed {
+        1.08 * (impact + exploitability)
+    } else {
+        impact + exploitability
+    };
+    // CVSS rounds up to one decimal
+    Some((score.min(10.0) * 10.0 - 1e-9).ceil() / 10.0)
+}
+
+/// One problem an audit tool reported, in the same shape for every ecosystem
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct AuditFinding {
+    pub auditor: Auditor,
+    pub package: String,
+    /// Installed version, when known
+    pub version: Option<String>,
+    /// Advisory identifier (RUSTSEC-, GHSA-, PYSEC-, CVE-...)
+    pub advisory: String,
+    /// Other identifiers of the same advisory
+    pub aliases: Vec<String>,
+    /// `vulnerability`, or `unmaintained`, `unsound` or `yanked` for cargo-audit warnings
+    pub kind: String,
+    pub severity: Severity,
+    pub title: String,
+    pub url: Option<String>,
+    /// Versions or upgrades that fix the finding
+    pub fixed_in: Vec<String>,
+    /// Whether the project depends on the package directly, from the lockfile
+    pub direct: Option<bool>,
+}
+
+fn string_list(value: Option<&serde_json::Value>) -> Vec<String> {
+    value
+        .and_then(|value| value.as_array())
+        .map(|items| {
+            items
+                .iter()
+                .filter_map(|item| item.as_str().map(str::to_string))
+                .collect()
+        })
+        .unwrap_or_default()
+}
+
+fn str_field(value: &serde_json::Value, pointer: &str) -> Option<String> {
+    value
+        .pointer(pointer)
+        .and_then(|field| field.as_str())
+        .map(str::to_string)
+}
+
+/// `cargo audit --json`: vulnerabilities plus warnings for unmaintained, unsound and
+/// yanked crates
+fn parse_cargo_audit(report: &serde_json::Value) -> Vec<AuditFinding> {
+    let finding = |entry: &serde_json::Value, kind: String| {
+        let advisory = entry.get("advisory");
+        let severity = if kind != "vulnerability" {
+            Severity::Info
+        } else {
+            advisory
+                .and_then(|advisory| advisory.get("cvss"))
+                .and_then(|cvss| cvss.as_str())
+                .and_then(cvss3_base_score)
+                .map(Severity::from_score)
+                .unwrap_or(Severity::Unknown)
+        };
+        AuditFinding {
+            auditor: Auditor::CargoAudit,
+            package: str_field(entry, "/package/name").unwrap_or_default(),
+            version: str_field(entry, "/package/version"),
+            advisory: str_field(entry, "/advisory/id").unwrap_or_default(),
+            aliases: string_list(advisory.and_then(|advisory| advisory.get("aliases"))),
+            title: str_field(entry, "/advisory/title").unwrap_or_else(|| kind.clone()),
+            kind,
+            severity,
+            url: str_field(entry, "/advisory/url"),
+            fixed_in: string_list(entry.pointer("/versions/patched")),
+            direct: None,
+        }
+    };
+    let mut findings: Vec<AuditFinding> = report
+        .pointer("/vulnerabilities/list")
+        .and_then(|list| list.as_array())
+        .into_iter()
+        .flatten()
+        .map(|entry| finding(entry, "vulnerability".to_string()))
+        .collect();
+    if let Some(warnings) = report.get("warnings").and_then(|w| w.as_object()) {
+        for (kind, entries) in warnings {
+            for entry in entries.as_array().into_iter().flatten() {
+                let kind = str_field(entry, "/kind").unwrap_or_else(|| kind.clone());
+                findings.push(finding(entry, kind));
+            }
+        }
+    }
+    findings
+}
+
+/// `npm audit --json`, in the npm 7+ format (`vulnerabilities` keyed by package) or the
+/// npm 6 one (`advisories` keyed by id)
+fn parse_npm_audit(report: &serde_json::Value) -> Vec<AuditFinding> {
+    let severity = |entry: &serde_json::Value| {
+        entry
+            .get("severity")
+            .and_then(|severity| severity.as_str())
+            .and_then(Severity::from_name)
+            .unwrap_or(Severity::Unknown)
+    };
+    // GitHub advisory URLs end with the GHSA id
+    let advisory_id = |url: Option<&String>, fallback: String| {
+        url.and_then(|url| url.rsplit('/').next())
+            .filter(|id| id.starts_with("GHSA-"))
+            .map(str::to_string)
+            .unwrap_or(fallback)
+    };
+    let mut findings = Vec::new();
+
+    if let Some(vulnerabilities) = report.get("vulnerabilities").and_then(|v| v.as_object()) {
+        for (name, entry) in vulnerabilities {
+            let fixed_in = match entry.get("fixAvailable") {
+                Some(serde_json::Value::Object(fix)) => vec![format!(
+                    "{}@{}{}",
+                    fix.get("name").and_then(|n| n.as_str()).unwrap_or(name),
+                    fix.get("version").and_then(|v| v.as_str()).unwrap_or("?"),
+                    if fix.get("isSemVerMajor").and_then(|m| m.as_bool()) == Some(true) {
+                        " (major upgrade)"
+                    } else {
+                        ""
+                    }
+                )],
+                Some(serde_json::Value::Bool(true)) => vec!["npm audit fix".to_string()],
+                _ => Vec::new(),
+            };
+            // String entries of `via` only name the vulnerable dependency; that one
+            // carries the advisory itself
+            for via in entry
+                .get("via")
+                .and_then(|via| via.as_array())
+                .into_iter()
+                .flatten()
+                .filter(|via| via.is_object())
+            {
+                let url = str_field(via, "/url");
+                let source = via
+                    .get("source")
+                    .map(|source| source.to_string())
+                    .unwrap_or_default();
+                findings.push(AuditFinding {
+                    auditor: Auditor::NpmAudit,
+                    package: str_field(via, "/name").unwrap_or_else(|| name.clone()),
+                    version: None,
+                    advisory: advisory_id(url.as_ref(), source),
+                    aliases: Vec::new(),
+                    kind: "vulnerability".to_string(),
+                    severity: severity(via),
+                    title: str_field(via, "/title").unwrap_or_default(),
+                    url,
+                    fixed_in: fixed_in.clone(),
+                    direct: entry.get("isDirect").and_then(|direct| direct.as_bool()),
+                });
+            }
+        }
+    } else if let Some(advisories) = report.get("advisories").and_then(|a| a.as_object()) {
+        for (id, advisory) in advisories {
+            let url = str_field(advisory, "/url");
+            let patched = str_field(advisory, "/patched_versions")
+                .filter(|patched| patched != "<0.0.0")
+                .into_iter()
+                .collect::<Vec<_>>();
+            let versions: Vec<Option<String>> = match advisory
+                .get("findings")
+                .and_then(|findings| findings.as_array())
+            {
+                Some(entries) if !entries.is_empty() => entries
+                    .iter()
+                    .map(|entry| str_field(entry, "/version"))
+                    .collect(),
+                _ => vec![None],
+            };
+            for version in versions {
+                findings.push(AuditFinding {
+                    auditor: Auditor::NpmAudit,
+                    package: str_field(advisory, "/module_name").unwrap_or_default(),
+                    version,
+                    advisory: advisory_id(url.as_ref(), id.clone()),
+                    aliases: string_list(advisory.get("cves")),
+                    kind: "vulnerability".to_string(),
+                    severity: severity(advisory),
+                    title: str_field(advisory, "/title").unwrap_or_default(),
+                    url: url.clone(),
+                    fixed_in: patched.clone(),
+                    direct: None,
+                });
+            }
+        }
+    }
+    findings
+}
+
+/// `pip-audit --format json`: an object with `dependencies`, or a bare list from
+/// older releases
+fn parse_pip_audit(report: &serde_json::Value) -> Vec<AuditFinding> {
+    let dependencies = report
+        .get("dependencies")
+        .unwrap_or(report)
+        .as_array()
+        .cloned()
+        .unwrap_or_default();
+    let mut findings = Vec::new();
+    for dependency in &dependencies {
+        for vuln in dependency
+            .get("vulns")
+            .and_then(|vulns| vulns.as_array())
+            .into_iter()
+            .flatten()
+        {
+            let advisory = str_field(vuln, "/id").unwrap_or_default();
+            let title = str_field(vuln, "/description")
+                .map(|description| description.lines().next().unwrap_or_default().to_string())
+                .unwrap_or_default();
+            findings.push(AuditFinding {
+                auditor: Auditor::PipAudit,
+                package: str_field(dependency, "/name").unwrap_or_default(),
+                version: str_field(dependency, "/version"),
+                url: Some(format!("https://osv.dev/vulnerability/{}", advisory)),
+                advisory,
+                aliases: string_list(vuln.get("aliases")),
+                kind: "vulnerability".to_string(),
+                severity: Severity::Unknown,
+                title,
+                fixed_in: string_list(vuln.get("fix_versions")),
+                direct: None,
+            });
+        }
+    }
+    findings
+}
+
+/// Fill in installed versions and direct-dependency flags from the lockfile in `dir`
+fn complete_from_lockfile(auditor: Auditor, dir: &Path, findings: &mut [AuditFinding]) {
+    let Ok(lockfile) = parse_lockfile(&dir.join(auditor.lockfile())) else {
+        return;
+    };
+    let normalize = |name: &str| match auditor {
+        Auditor::PipAudit => normalize_python_name(name),
+        _ => name.to_string(),
+    };
+    for finding in findings.iter_mut() {
+        let name = normalize(&finding.package);
+        let mut installed = lockfile.external().filter(|package| {
+            package.name == name
+                && finding
+                    .version
+                    .as_ref()
+                    .is_none_or(|version| &package.version == version)
+        });
+        if let Some(package) = installed.next() {
+            if finding.version.is_none() && installed.next().is_none() {
+                finding.version = Some(package.version.clone());
+            }
+            if finding.direct.is_none() {
+                finding.direct = Some(package.direct);
+            }
+        }
+    }
+}
+
+/// Tool running the installed vulnerability auditors and normalizing their findings
+pub struct AuditDependenciesTool {
+    name: String,
+}
+
+impl AuditDependenciesTool {
+    pub fn new() -> Self {
+        Self {
+            name: "audit_dependencies".to_string(),
+        }
+    }
+
+    fn format_finding(finding: &AuditFinding) -> String {
+        let mut text = format!(
+            "- [{}] {}{} {}",
+            finding.severity.as_str(),
+            finding.package,
+            finding
+                .version
+                .as_ref()
+                .map(|version| format!(" {}", version))
+                .unwrap_or_default(),
+            finding.advisory
+        );
+        if !finding.title.is_empty() {
+            text.push_str(&format!(": {}", finding.title));
+        }
+        if finding.direct == Some(true) {
+            text.push_str(" (direct)");
+        }
+        if !finding.fixed_in.is_empty() {
+            text.push_str(&format!("; fixed in {}", finding.fixed_in.join(", ")));
+        }
+        text
+    }
+
+    /// Run one auditor, returning its findings or why it produced none
+    fn run(auditor: Auditor, dir: &Path) -> Result<Vec<AuditFinding>, String> {
+        let (program, args) = auditor.command(dir);
+        let command = format!("{} {}", program, args.join(" "));
+        let output = Command::new(program)
+            .args(&args)
+            .current_dir(dir)
+            .env("NO_COLOR", "1")
+            .output()
+            .map_err(|e| format!("Failed to run '{}': {}", command, e))?;
+        // Audit tools exit non-zero when they find something; the report says what
+        let stdout = clean_output(&output.stdout, false);
+        auditor.parse(&stdout).map_err(|e| {
+            let stderr = clean_output(&output.stderr, false);
+            let lines: Vec<&str> = stderr.trim_end().lines().collect();
+            let tail = &lines[lines.len().saturating_sub(OUTPUT_TAIL_LINES)..];
+            format!("{:#} ('{}'):\n{}", e, command, tail.join("\n"))
+        })
+    }
+}
+
+impl Default for AuditDependenciesTool {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl Tool for AuditDependenciesTool {
+    fn name(&self) -> &str {
+        &self.name
+    }
+
+    fn description(&self) -> &str {
+        "Check dependencies for known vulnerabilities with cargo-audit, npm audit or pip-audit (whichever apply and are installed) and report the findings in one structure: package, version, advisory, severity, fixed versions and whether the dependency is direct"
+    }
+
+    fn signature(&self) -> &str {
+        "audit_dependencies [path] [--ecosystem=<cargo|npm|python>] [--severity=<low|moderate|high|critical>]"
+    }
+
+    fn category(&self) -> ToolCategory {
+        ToolCategory::Execution
+    }
+
+    fn danger_level(&self) -> DangerLevel {
+        DangerLevel::ExecutesCode
+    }
+
+    fn validate_args(&self, args: &ToolArgs) -> Result<(), ToolError> {
+        if let Some(ecosystem) = args.get_named_arg("ecosystem") {
+            if Auditor::from_name(ecosystem).is_none() {
+                return Err(ToolError::InvalidArgs {
+                    message: format!(
+                        "Unknown ecosystem '{}'. Expected one of: cargo, npm, python",
+                        ecosystem
+                    ),
+                });
+            }
+        }
+        if let Some(severity) = args.get_named_arg("severity") {
+            if Severity::from_name(severity).is_none() {
+                return Err(ToolError::InvalidArgs {
+                    message: format!(
+                        "Unknown severity '{}'. Expected one of: low, moderate, high, critical",
+                        severity
+                    ),
+                });
+            }
+        }
+        Ok(())
+    }
+
+    fn execute(&mut self, args: &ToolArgs, state: &Arc<Mutex<ToolState>>) -> Result<ToolResult> {
+        let dir = match args.get_named_arg("path").or_else(|| args.get_arg(0)) {
+            Some(path) => session_path(state, path),
+            None => {
+                let state_guard = state
+                    .lock()
+                    .map_err(|e| anyhow::anyhow!("Failed to lock state: {}", e))?;
+                state_guard.working_directory.clone()
+            }
+        };
+        if !dir.is_dir() {
+            return Ok(crate::utils::suggest::path_not_found(
+                format!("Directory {} not found", dir.display()),
+                &dir,
+            ));
+        }
+
+        let auditors = match args.get_named_arg("ecosystem") {
+            Some(ecosystem) => Auditor::from_name(ecosystem).into_iter().collect(),
+            None => Auditor::detect(&dir),
+        };
+        if auditors.is_empty() {
+            return Ok(ToolResult::error(format!(
+                "Nothing to audit in {} (looked for Cargo.lock, package-lock.json, requirements.txt, pyproject.toml and poetry.lock)",
+                dir.display()
+            )));
+        }
+        // The minimum severity still reported; findings without one are always kept
+        let minimum = args
+            .get_named_arg("severity")
+            .and_then(|severity| Severity::from_name(severity));
+
+        let mut findings = Vec::new();
+        let mut audited = Vec::new();
+        let mut skipped = Vec::new();
+        let mut errors = Vec::new();
+        for auditor in auditors {
+            if !auditor.available() {
+                skipped.push(format!(
+                    "{} is not installed ({})",
+                    auditor.as_str(),
+                    auditor.install_hint()
+                ));
+                continue;
+            }
+            if let Ok(mut state_guard) = state.lock() {
+                let (program, args) = auditor.command(&dir);
+                state_guard.push_history(format!(
+                    "audit_dependencies: {} {}",
+                    program,
+                    args.join(" ")
+                ));
+            }
+            match Self::run(auditor, &dir) {
+                Ok(mut found) => {
+                    complete_from_lockfile(auditor, &dir, &mut found);
+                    findings.extend(found);
+                    audited.push(auditor);
+                }
+                Err(error) => errors.push(format!("{}: {}", auditor.as_str(), error)),
+            }
+        }
+        if let Some(minimum) = minimum {
+            findings.retain(|finding| {
+                finding.severity == Severity::Unknown || finding.severity >= minimum
+            });
+        }
+        findings.sort_by(|a, b| {
+            b.severity
+                .cmp(&a.severity)
+                .then_with(|| a.package.cmp(&b.package))
+        });
+
+        let vulnerabilities = findings
+            .iter()
+            .filter(|finding| finding.kind == "vulnerability")
+            .count();
+        let data = serde_json::json!({
+            "directory": dir.display().to_string(),
+            "audited": audited,
+            "skipped": skipped,
+            "errors": errors,
+            "vulnerabilities": vulnerabilities,
+            "findings": findings,
+        });
+
+        let mut message = if audited.is_empty() {
+            "No dependency audit could run".to_string()
+        } else if findings.is_empty() {
+            format!(
+                "No known vulnerabilities ({})",
+                audited
+                    .iter()
+                    .map(|auditor| auditor.as_str())
+                    .collect::<Vec<_>>()
+                    .join(", ")
+            )
+        } else {
+            let mut message = format!(
+                "{} vulnerabilit{} and {} warning(s):\n",
+                vulnerabilities,
+                if vulnerabilities == 1 { "y" } else { "ies" },
+                findings.len() - vulnerabilities
+            );
+            for finding in findings.iter().take(LISTED_FINDINGS) {
+                message.push_str(&Self::format_finding(finding));
+                message.push('\n');
+            }
+            if findings.len() > LISTED_FINDINGS {
+                message.push_str(&format!(
+                    "... and {} more\n",
+                    findings.len() - LISTED_FINDINGS
+                ));
+            }
+            message.trim_end().to_string()
+        };
+        for note in skipped.iter().chain(&errors) {
+            message.push_str(&format!("\n{}", note));
+        }
+
+        if audited.is_empty() {
+            return Ok(ToolResult::error_with_data(message, data));
+        }
+        Ok(ToolResult::success_with_data(message, data))
+    }
+
+    fn get_parameters_schema(&self) -> serde_json::Value {
+        serde_json::json!({
+            "type": "object",
+            "properties": {
+                "path": {
+                    "type": "string",
+                    "description": "Project directory to audit (defaults to the working directory)"
+                },
+                "ecosystem": {
+                    "type": "string",
+                    "enum": ["cargo", "npm", "python"],
+                    "description": "Only run this ecosystem's auditor; detected from the project's manifests when omitted"
+                },
+                "severity": {
+                    "type": "string",
+                    "enum": ["low", "moderate", "high", "critical"],
+                    "description": "Minimum severity to report; findings without a severity are always reported"
+                }
+            },
+            "required": []
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::fs;
+    use tempfile::TempDir;
+
+    #[test]
+    fn test_cvss3_base_score() {
+        let critical = "CVSS:3.1/AV:N/AC:L/PR:N/UI:N/S:U/C:H/I:H/A:H";
+        assert_eq!(cvss3_base_score(critical), Some(9.8));
+        let changed = "CVSS:3.0/AV:N/AC:L/PR:L/UI:R/S:C/C:L/I:L/A:N";
+        assert_eq!(cvss3_base_score(changed), Some(5.4));
+        assert_eq!(Severity::from_score(5.4), Severity::Moderate);
+        assert_eq!(cvss3_base_score("not a vector"), None);
+    }
+
+    #[test]
+    fn test_parse_reports() {
+        let cargo = r#"{
+            "vulnerabilities": {"found": true, "count": 1, "list": [{
+                "advisory": {"id": "RUSTSEC-2024-0001", "package": "smallvec", "title": "Buffer overflow",
+                    "url": "https://rustsec.org/advisories/RUSTSEC-2024-0001", "aliases": ["CVE-2024-1"],
+                    "cvss": "CVSS:3.1/AV:N/AC:L/PR:N/UI:N/S:U/C:H/I:H/A:H"},
+                "versions": {"patched": [">=1.6.1"], "unaffected": []},
+                "package": {"name": "smallvec", "version": "1.6.0"}
+            }]},
+            "warnings": {"unmaintained": [{"kind": "unmaintained",
+                "package": {"name": "ansi_term", "version": "0.12.1"},
+                "advisory": {"id": "RUSTSEC-2021-0139", "title": "ansi_term is unmaintained"}}]}
+        }"#;
+        let findings = Auditor::CargoAudit.parse(cargo).unwrap();
+        assert_eq!(findings.len(), 2);
+        assert_eq!(findings[0].severity, Severity::Critical);
+        assert_eq!(findings[0].fixed_in, vec![">=1.6.1"]);
+        assert_eq!(
+            (findings[1].kind.as_str(), findings[1].severity),
+            ("unmaintained", Severity::Info)
+        );
+
+        let npm = r#"{"vulnerabilities": {
+            "minimist": {"name": "minimist", "severity": "critical", "isDirect": false,
+                "via": [{"source": 1096, "name": "minimist", "title": "Prototype Pollution",
+                    "url": "https://github.com/advisories/GHSA-xvch-5gv4-984h", "severity": "critical"}],
+                "fixAvailable": true},
+            "mkdirp": {"name": "mkdirp", "severity": "critical", "isDirect": true, "via": ["minimist"],
+                "fixAvailable": {"name": "mkdirp", "version": "1.0.4", "isSemVerMajor": true}}
+        }}"#;
+        let findings = Auditor::NpmAudit.parse(npm).unwrap();
+        assert_eq!(findings.len(), 1);
+        assert_eq!(findings[0].advisory, "GHSA-xvch-5gv4-984h");
+        assert_eq!(findings[0].direct, Some(false));
+
+        let pip = r#"{"dependencies": [
+            {"name": "jinja2", "version": "3.1.2", "vulns": [{"id": "GHSA-h5c8-rqwp-cp95",
+                "fix_versions": ["3.1.3"], "aliases": ["CVE-2024-22195"], "description": "XSS in xmlattr\nmore"}]},
+            {"name": "idna", "version": "3.7", "vulns": []}
+        ], "fixes": []}"#;
+        let findings = Auditor::PipAudit.parse(pip).unwrap();
+        assert_eq!(findings.len(), 1);
+        assert_eq!(findings[0].title, "XSS in xmlattr");
+        assert_eq!(findings[0].severity, Severity::Unknown);
+        assert!(Auditor::PipAudit.parse("error: not json").is_err());
+    }
+
+    #[test]
+    fn test_complete_from_lockfile() {
+        let temp_dir = TempDir::new().unwrap();
+        fs::write(
+            temp_dir.path().join("package-lock.json"),
+            r#"{"lockfileVersion": 3, "packages": {
+                "": {"dependencies": {"minimist": "^1"}},
+                "node_modules/minimist": {"version": "1.2.5"}
+            }}"#,
+        )
+        .unwrap();
+        assert_eq!(Auditor::detect(temp_dir.path()), vec![Auditor::NpmAudit]);
+        let mut findings = parse_npm_audit(&serde_json::json!({"advisories": {"1179": {
+            "module_name": "minimist", "severity": "moderate", "title": "Prototype Pollution",
+            "patched_versions": ">=1.2.6", "findings": []
+        }}}));
+        complete_from_lockfile(Auditor::NpmAudit, temp_dir.path(), &mut findings);
+        assert_eq!(findings[0].version.as_deref(), Some("1.2.5"));
+        assert_eq!(findings[0].direct, Some(true));
+        assert_eq!(
+            AuditDependenciesTool::format_finding(&findings[0]),
+            "- [moderate] minimist 1.2.5 1179: Prototype Pollution (direct); fixed in >=1.2.6"
+        );
+    }
+}
@@ -0,0 +1,678 @@
+//! Benchmark tools: run_benchmarks and compare_benchmarks
+//!
+//! Parses `cargo bench` (libtest and criterion) and pytest-benchmark results into a
+//! common format that can be saved and diffed to spot regressions.
+
+use super::output::clean_output;
+use super::run_tests::shell_quote;
+use crate::core::{
+    path_arguments, DangerLevel, Tool, ToolArgs, ToolCategory, ToolError, ToolResult,
+};
+use crate::state::{session_path, ToolState};
+use crate::utils::workspace::{resolve_package, WorkspaceKind};
+use anyhow::Result;
+use regex::Regex;
+use serde::{Deserialize, Serialize};
+use std::collections::BTreeMap;
+use std::fs;
+use std::path::{Path, PathBuf};
+use std::process::Command;
+use std::sync::{Arc, Mutex};
+
+/// Default regression threshold in percent
+const DEFAULT_THRESHOLD_PERCENT: f64 = 5.0;
+
+/// A single benchmark measurement, normalized to nanoseconds
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct BenchmarkResult {
+    pub name: String,
+    /// Central estimate (criterion median estimate, libtest ns/iter, pytest mean)
+    pub mean_ns: f64,
+    /// Lower bound or minimum, when reported
+    pub low_ns: Option<f64>,
+    /// Upper bound or maximum, when reported
+    pub high_ns: Option<f64>,
+}
+
+/// A set of benchmark results as produced by `run_benchmarks`
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct BenchmarkSet {
+    pub framework: String,
+    pub benchmarks: Vec<BenchmarkResult>,
+}
+
+fn unit_to_ns(value: f64, unit: &str) -> f64 {
+    match unit {
+        "ps" => value / 1_000.0,
+        "ns" => value,
+        "us" | "µs" | "μs" => value * 1_000.0,
+        "ms" => value * 1_000_000.0,
+        "s" => value * 1_000_000_000.0,
+        _ => value,
+    }
+}
+
+/// Format a nanosecond duration with a readable unit
+pub fn format_ns(ns: f64) -> String {
+    if ns >= 1_000_000_000.0 {
+        format!("{:.3} s", ns / 1_000_000_000.0)
+    } else if ns >= 1_000_000.0 {
+        format!("{:.3} ms", ns / 1_000_000.0)
+    } else if ns >= 1_000.0 {
+        format!("{:.3} µs", ns / 1_000.0)
+    } else {
+        format!("{:.1} ns", ns)
+    }
+}
+
+/// Parse `cargo bench` output from libtest (`bench: N ns/iter`) or criterion (`time: [a b c]`)
+pub fn parse_cargo_bench(output: &str) -> Vec<BenchmarkResult> {
+    let libtest =
+        Regex::new(r"^test (\S+)\s+\.\.\. bench:\s+([\d,.]+) ns/iter \(\+/- ([\d,.]+)\)").unwrap();
+    let criterion =
+        Regex::new(r"^(.*?)\s*time:\s+\[([\d.]+) (\S+) ([\d.]+) (\S+) ([\d.]+) (\S+)\]").unwrap();
+
+    let mut results = Vec::new();
+    let mut previous_line = "";
+    for line in output.lines() {
+        if let Some(caps) = libtest.captures(line) {
+            let parse = |s: &str| s.replace(',', "").parse::<f64>().unwrap_or(0.0);
+            let mean = parse(&caps[2]);
+            let deviation = parse(&caps[3]);
+            results.push(BenchmarkResult {
+                name: caps[1].to_string(),
+                mean_ns: mean,
+                low_ns: Some(mean - deviation),
+                high_ns: Some(mean + deviation),
+            });
+        } else if let Some(caps) = criterion.captures(line) {
+            // Long benchmark names are printed on their own line before the timing line
+            let name = if caps[1].trim().is_empty() {
+                previous_line.trim()
+            } else {
+                caps[1].trim()
+            };
+            let value = |i: usize| caps[i].parse::<f64>().unwrap_or(0.0);
+            results.push(BenchmarkResult {
+                name: name.to_string(),
+                mean_ns: unit_to_ns(value(4), &caps[5]),
+                low_ns: Some(unit_to_ns(value(2), &caps[3])),
+                high_ns: Some(unit_to_ns(value(6), &caps[7])),
+            });
+        }
+        if !line.trim().is_empty() {
+            previous_line = line;
+        }
+    }
+    results
+}
+
+/// Parse a pytest-benchmark JSON report (`--benchmark-json`); times are in seconds
+pub fn parse_pytest_benchmark(report: &str) -> Result<Vec<BenchmarkResult>> {
+    let value: serde_json::Value = serde_json::from_str(report)?;
+    let benchmarks = value
+        .get("benchmarks")
+        .and_then(|b| b.as_array())
+        .ok_or_else(|| anyhow::anyhow!("pytest-benchmark report has no 'benchmarks' array"))?;
+
+    Ok(benchmarks
+        .iter()
+        .filter_map(|bench| {
+            let stats = bench.get("stats")?;
+            let seconds = |key: &str| stats.get(key).and_then(|v| v.as_f64());
+            Some(BenchmarkResult {
+                name: bench
+                    .get("fullname")
+                    .or_else(|| bench.get("name"))?
+                    .as_str()?
+                    .to_string(),
+                mean_ns: seconds("mean")? * 1e9,
+                low_ns: seconds("min").map(|s| s * 1e9),
+                high_ns: seconds("max").map(|s| s * 1e9),
+            })
+        })
+        .collect())
+}
+
+fn render_results(results: &[BenchmarkResult]) -> String {
+    let width = results.iter().map(|r| r.name.len()).max().unwrap_or(0);
+    results
+        .iter()
+        .map(|r| {
+            format!(
+                "{:<width$}  {}",
+                r.name,
+                format_ns(r.mean_ns),
+                width = width
+            )
+        })
+        .collect::<Vec<_>>()
+        .join("\n")
+}
+
+/// Tool for running benchmarks and collecting structured results
+pub struct RunBenchmarksTool {
+    name: String,
+}
+
+impl RunBenchmarksTool {
+    pub fn new() -> Self {
+        Self {
+            name: "run_benchmarks".to_string(),
+        }
+    }
+
+    fn detect_framework(dir: &Path) -> Option<&'static str> {
+        if dir.join("Cargo.toml").exists() {
+            Some("cargo")
+        } else if ["pytest.ini", "conftest.py", "pyproject.toml", "setup.cfg"]
+            .iter()
+            .any(|m| dir.join(m).exists())
+        {
+            Some("pytest")
+        } else {
+            None
+        }
+    }
+}
+
+impl Default for RunBenchmarksTool {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl Tool for RunBenchmarksTool {
+    fn name(&self) -> &str {
+        &self.name
+    }
+
+    fn description(&self) -> &str {
+        "Run benchmarks (cargo bench / criterion, pytest-benchmark) and return structured timings, optionally saving them for compare_benchmarks"
+    }
+
+    fn signature(&self) -> &str {
+        "run_benchmarks [path] [--package=<name>] [--framework=<cargo|pytest>] [--filter=<name>] [--save=<file.json>] [--command=<cmd>]"
+    }
+
+    fn category(&self) -> ToolCategory {
+        ToolCategory::Execution
+    }
+
+    fn danger_level(&self) -> DangerLevel {
+        DangerLevel::ExecutesCode
+    }
+
+    fn validate_args(&self, args: &ToolArgs) -> Result<(), ToolError> {
+        if let Some(framework) = args.get_named_arg("framework") {
+            if framework != "cargo" && framework != "pytest" {
+                return Err(ToolError::InvalidArgs {
+                    message: format!(
+                        "Unknown benchmark framework '{}'. Expected 'cargo' or 'pytest'",
+                        framework
+                    ),
+                });
+            }
+        }
+        Ok(())
+    }
+
+    fn accessed_paths(&self, args: &ToolArgs) -> Vec<PathBuf> {
+        // `save` names the file the results are written to
+        path_arguments(&self.named_args(args), |name| {
+            crate::llm::converter::is_path_param(name) || name == "save"
+        })
+    }
+
+    fn execute(&mut self, args: &ToolArgs, state: &Arc<Mutex<ToolState>>) -> Result<ToolResult> {
+        let dir = match args.get_named_arg("path").or_else(|| args.get_arg(0)) {
+            Some(path) => session_path(state, path),
+            None => {
+                let state_guard = state
+                    .lock()
+                    .map_err(|e| anyhow::anyhow!("Failed to lock state: {}", e))?;
+                state_guard.working_directory.clone()
+            }
+        };
+        if !dir.is_dir() {
+            return Ok(crate::utils::suggest::path_not_found(
+                format!("Directory {} not found", dir.display()),
+                &dir,
+            ));
+        }
+
+        // Cargo packages are benchmarked with `-p` from the workspace root, others in
+        // their own directory
+        let package = match args.get_named_arg("package") {
+            Some(package) => match resolve_package(&dir, package) {
+                Ok(resolved) => Some(resolved),
+                Err(result) => return Ok(result),
+            },
+            None => None,
+        };
+        let cargo_package = package
+            .as_ref()
+            .filter(|(_, member)| member.kind == WorkspaceKind::Cargo)
+            .map(|(_, member)| member.name.clone());
+        let dir = match &package {
+            Some((workspace, member)) => member.command_directory(&workspace.root),
+            None => dir,
+        };
+
+        let framework = match args
+            .get_named_arg("framework")
+            .map(|f| f.as_str())
+            .or_else(|| Self::detect_framework(&dir))
+        {
+            Some(framework) => framework,
+            None => {
+                return Ok(ToolResult::error(format!(
+                    "Could not detect a benchmark framework in {}. Pass --framework",
+                    dir.display()
+                )))
+            }
+        };
+        let filter = args.get_named_arg("filter");
+
+        // pytest-benchmark writes its machine-readable report to a file
+        let report_file = tempfile::NamedTempFile::new()?;
+        let command = match (args.get_named_arg("command"), framework) {
+            (Some(command), _) => command.clone(),
+            (None, "pytest") => {
+                let mut command = format!(
+                    "python -m pytest --benchmark-only --benchmark-json={}",
+                    shell_quote(&report_file.path().display().to_string())
+                );
+                if let Some(filter) = filter {
+                    command.push_str(&format!(" -k {}", shell_quote(filter)));
+                }
+                command
+            }
+            _ => {
+                let mut command = match &cargo_package {
+                    Some(name) => format!("cargo bench -p {}", name),
+                    None => "cargo bench".to_string(),
+                };
+                if let Some(filter) = filter {
+                    command.push_str(&format!(" -- {}", shell_quote(filter)));
+                }
+                command
+            }
+        };
+
+        if let Ok(mut state_guard) = state.lock() {
+            state_guard.push_history(format!("run_benchmarks: {}", command));
+        }
+
+        let output = Command::new("sh")
+            .arg("-c")
+            .arg(&command)
+            .current_dir(&dir)
+            .output()
+            .map_err(|e| anyhow::anyhow!("Failed to run '{}': {}", command, e))?;
+        let stdout = clean_output(&output.stdout, false);
+        let stderr = clean_output(&output.stderr, false);
+
+        let benchmarks = if framework == "pytest" {
+            let report = fs::read_to_string(report_file.path()).unwrap_or_default();
+            parse_pytest_benchmark(&report).unwrap_or_default()
+        } else {
+            parse_cargo_bench(&format!("{}\n{}", stdout, stderr))
+        };
+
+        if !output.status.success() && benchmarks.is_empty() {
+            let tail: Vec<&str> = stderr.lines().rev().take(20).collect();
+            return Ok(ToolResult::error(format!(
+                "Benchmark command failed ({}, exit code {}):\n{}",
+                command,
+                output.status.code().unwrap_or(-1),
+                tail.into_iter().rev().collect::<Vec<_>>().join("\n")
+            )));
+        }
+
+        let set = BenchmarkSet {
+            framework: framework.to_string(),
+            benchmarks,
+        };
+
+        let mut message = format!(
+            "{} benchmark(s) ({}):\n{}",
+            set.benchmarks.len(),
+            command,
+            render_results(&set.benchmarks)
+        );
+        if let Some(save) = args.get_named_arg("save") {
+            fs::write(
+                session_path(state, save),
+                serde_json::to_string_pretty(&set)?,
+            )?;
+            message.push_str(&format!("\nResults saved to {}", save));
+        }
+
+        Ok(ToolResult::success_with_data(
+            message,
+            serde_json::to_value(&set)?,
+        ))
+    }
+
+    fn get_parameters_schema(&self) -> serde_json::Value {
+        serde_json::json!({
+            "type": "object",
+            "properties": {
+                "path": {
+                    "type": "string",
+                    "description": "Project directory (defaults to the working directory)"
+                },
+                "package": {
+                    "type": "string",
+                    "description": "Workspace package (name or directory) to benchmark only; Cargo packages run as `cargo bench -p <name>` from the workspace root"
+                },
+                "framework": {
+                    "type": "string",
+                    "enum": ["cargo", "pytest"],
+                    "description": "Benchmark framework; auto-detected when omitted"
+                },
+                "filter": {
+                    "type": "string",
+                    "description": "Only run benchmarks matching this filter"
+                },
+                "save": {
+                    "type": "string",
+                    "description": "Write the results as JSON to this file for later comparison"
+                },
+                "command": {
+                    "type": "string",
+                    "description": "Custom benchmark command whose cargo bench style output is parsed"
+                }
+            },
+            "required": []
+        })
+    }
+}
+
+/// Tool for diffing two benchmark result sets and flagging regressions
+pub struct CompareBenchmarksTool {
+    name: String,
+}
+
+impl CompareBenchmarksTool {
+    pub fn new() -> Self {
+        Self {
+            name: "compare_benchmarks".to_string(),
+        }
+    }
+
+    /// Whether `source` is an inline JSON document rather than a file path
+    fn is_inline(source: &str) -> bool {
+        source.trim_start().starts_with('{')
+    }
+
+    /// Load a result set from a file path (relative to the session's working directory)
+    /// or an inline JSON document
+    fn load_set(source: &str, state: &Arc<Mutex<ToolState>>) -> Result<BenchmarkSet> {
+        let text = if Self::is_inline(source) {
+            source.to_string()
+        } else {
+            fs::read_to_string(session_path(state, source))
+                .map_err(|e| anyhow::anyhow!("Failed to read {}: {}", source, e))?
+        };
+        let value: serde_json::Value = serde_json::from_str(&text)?;
+        if value
+            .get("benchmarks")
+            .and_then(|b| b.get(0))
+            .and_then(|b| b.get("stats"))
+            .is_some()
+        {
+            // Raw pytest-benchmark report
+            return Ok(BenchmarkSet {
+                framework: "pytest".to_string(),
+                benchmarks: parse_pytest_benchmark(&text)?,
+            });
+        }
+        Ok(serde_json::from_value(value)?)
+    }
+}
+
+impl Default for CompareBenchmarksTool {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl Tool for CompareBenchmarksTool {
+    fn name(&self) -> &str {
+        &self.name
+    }
+
+    fn description(&self) -> &str {
+        "Compare two benchmark result sets and flag regressions beyond a percentage threshold"
+    }
+
+    fn signature(&self) -> &str {
+        "compare_benchmarks <baseline> <current> [--threshold=<percent>]"
+    }
+
+    fn category(&self) -> ToolCategory {
+        ToolCategory::Execution
+    }
+
+    fn danger_level(&self) -> DangerLevel {
+        DangerLevel::ReadOnly
+    }
+
+    fn validate_args(&self, args: &ToolArgs) -> Result<(), ToolError> {
+        let baseline = args.get_named_arg("baseline").or_else(|| args.get_arg(0));
+        let current = args.get_named_arg("current").or_else(|| args.get_arg(1));
+        if baseline.is_none() || current.is_none() {
+            return Err(ToolError::InvalidArgs {
+                message: "Usage: compare_benchmarks <baseline> <current> [--threshold=<percent>]"
+                    .to_string(),
+            });
+        }
+        Ok(())
+    }
+
+    fn accessed_paths(&self, args: &ToolArgs) -> Vec<PathBuf> {
+        ["baseline", "current"]
+            .iter()
+            .enumerate()
+            .filter_map(|(index, name)| args.get_named_arg(name).or_else(|| args.get_arg(index)))
+            .filter(|source| !Self::is_inline(source))
+            .map(PathBuf::from)
+            .collect()
+    }
+
+    fn execute(&mut self, args: &ToolArgs, state: &Arc<Mutex<ToolState>>) -> Result<ToolResult> {
+        let baseline_source = args
+            .get_named_arg("baseline")
+            .or_else(|| args.get_arg(0))
+            .ok_or_else(|| anyhow::anyhow!("Missing baseline parameter"))?;
+        let current_source = args
+            .get_named_arg("current")
+            .or_else(|| args.get_arg(1))
+            .ok_or_else(|| anyhow::anyhow!("Missing current parameter"))?;
+        let threshold = args
+            .get_named_arg("threshold")
+            .and_then(|t| t.trim_end_matches('%').parse::<f64>().ok())
+            .unwrap_or(DEFAULT_THRESHOLD_PERCENT);
+
+        let baseline = match Self::load_set(baseline_source, state) {
+            Ok(set) => set,
+            Err(e) => return Ok(ToolResult::error(format!("Invalid baseline: {}", e))),
+        };
+        let current = match Self::load_set(current_source, state) {
+            Ok(set) => set,
+            Err(e) => return Ok(ToolResult::error(format!("Invalid current results: {}", e))),
+        };
+
+        let before: BTreeMap<&str, &BenchmarkResult> = baseline
+            .benchmarks
+            .iter()
+            .map(|b| (b.name.as_str(), b))
+            .collect();
+        let after: BTreeMap<&str, &BenchmarkResult> = current
+            .benchmarks
+            .iter()
+            .map(|b| (b.name.as_str(), b))
+            .collect();
+
+        let mut comparisons = Vec::new();
+        let mut lines = Vec::new();
+        let mut regressions = Vec::new();
+        let mut improvements = Vec::new();
+        for (name, old) in &before {
+            let Some(new) = after.get(name) else {
+                continue;
+            };
+            let change = if old.mean_ns > 0.0 {
+                (new.mean_ns - old.mean_ns) / old.mean_ns * 100.0
+            } else {
+                0.0
+            };
+            let status = if change > threshold {
+                regressions.push(name.to_string());
+                "REGRESSION"
+            } else if change < -threshold {
+                improvements.push(name.to_string());
+                "improved"
+            } else {
+                "unchanged"
+            };
+            lines.push(format!(
+                "{} {}: {} -> {} ({:+.2}%)",
+                if status == "REGRESSION" { "!" } else { " " },
+                name,
+                format_ns(old.mean_ns),
+                format_ns(new.mean_ns),
+                change
+            ));
+            comparisons.push(serde_json::json!({
+                "name": name,
+                "baseline_ns": old.mean_ns,
+                "current_ns": new.mean_ns,
+                "change_percent": change,
+                "status": status
+            }));
+        }
+        let missing: Vec<&str> = before
+            .keys()
+            .filter(|k| !after.contains_key(*k))
+            .copied()
+            .collect();
+        let added: Vec<&str> = after
+            .keys()
+            .filter(|k| !before.contains_key(*k))
+            .copied()
+            .collect();
+
+        let mut message = format!(
+            "{} regression(s), {} improvement(s) beyond {}% across {} benchmark(s)",
+            regressions.len(),
+            improvements.len(),
+            threshold,
+            comparisons.len()
+        );
+        if !lines.is_empty() {
+            message.push('\n');
+            message.push_str(&lines.join("\n"));
+        }
+        if !missing.is_empty() {
+            message.push_str(&format!("\nMissing from current: {}", missing.join(", ")));
+        }
+        if !added.is_empty() {
+            message.push_str(&format!("\nNew in current: {}", added.join(", ")));
+        }
+
+        Ok(ToolResult::success_with_data(
+            message,
+            serde_json::json!({
+                "threshold_percent": threshold,
+                "regressed": !regressions.is_empty(),
+                "regressions": regressions,
+                "improvements": improvements,
+                "comparisons": comparisons,
+                "missing": missing,
+                "added": added
+            }),
+        ))
+    }
+
+    fn get_parameters_schema(&self) -> serde_json::Value {
+        serde_json::json!({
+            "type": "object",
+            "properties": {
+                "baseline": {
+                    "type": "string",
+                    "description": "Baseline results: a file saved by run_benchmarks, a pytest-benchmark JSON report, or inline JSON"
+                },
+                "current": {
+                    "type": "string",
+                    "description": "Current results in the same formats as baseline"
+                },
+                "threshold": {
+                    "type": "number",
+                    "description": "Slowdown in percent above which a benchmark is flagged as a regression (default 5)"
+                }
+            },
+            "required": ["baseline", "current"]
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parse_libtest_and_criterion() {
+        let output = "\
+test bench_parse ... bench:       1,234 ns/iter (+/- 56)
+fib 20                  time:   [26.029 us 26.251 us 26.505 us]
+group/a_very_long_benchmark_name_that_wraps
+                        time:   [1.2000 ms 1.3000 ms 1.4000 ms]
+";
+        let results = parse_cargo_bench(output);
+        assert_eq!(results.len(), 3);
+        assert_eq!(results[0].name, "bench_parse");
+        assert_eq!(results[0].mean_ns, 1234.0);
+        assert_eq!(results[1].name, "fib 20");
+        assert!((results[1].mean_ns - 26_251.0).abs() < 1e-6);
+        assert_eq!(
+            results[2].name,
+            "group/a_very_long_benchmark_name_that_wraps"
+        );
+        assert!((results[2].mean_ns - 1_300_000.0).abs() < 1e-6);
+    }
+
+    #[test]
+    fn test_parse_pytest_benchmark_report() {
+        let report = r#"{"benchmarks": [{"name": "test_sort", "fullname": "tests/test_b.py::test_sort", "stats": {"mean": 0.002, "min": 0.001, "max": 0.003}}]}"#;
+        let results = parse_pytest_benchmark(report).unwrap();
+        assert_eq!(results[0].name, "tests/test_b.py::test_sort");
+        assert!((results[0].mean_ns - 2_000_000.0).abs() < 1e-3);
+    }
+
+    #[test]
+    fn test_compare_flags_regressions() {
+        let baseline = r#"{"framework": "cargo", "benchmarks": [
+            {"name": "a", "mean_ns": 100.0, "low_ns": null, "high_ns": null},
+            {"name": "b", "mean_ns": 100.0, "low_ns": null, "high_ns": null},
+            {"name": "gone", "mean_ns": 5.0, "low_ns": null, "high_ns": null}]}"#;
+        let current = r#"{"framework": "cargo", "benchmarks": [
+            {"name": "a", "mean_ns": 120.0, "low_ns": null, "high_ns": null},
+            {"name": "b", "mean_ns": 101.0, "low_ns": null, "high_ns": null}]}"#;
+
+        let mut tool = CompareBenchmarksTool::new();
+        let state = Arc::new(Mutex::new(ToolState::new()));
+        let args = ToolArgs::from_args(&[baseline, current, "--threshold=10"]);
+        let result = tool.execute(&args, &state).unwrap();
+
+        assert!(result
+            .message
+            .starts_with("1 regression(s), 0 improvement(s)"));
+        let data = result.data.unwrap();
+        assert_eq!(data["regressions"][0], "a");
+        assert_eq!(data["missing"][0], "gone");
+        assert_eq!(data["regressed"], true);
+    }
+}
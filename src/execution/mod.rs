@@ -0,0 +1,866 @@
+//! Command execution tool for running shell commands
+//!
+//! This module provides the run_command tool that allows LLMs to execute shell commands
+//! in a controlled and safe manner, replacing direct bash command execution.
+
+use crate::config::{CommandPolicy, CONFIG_FILE};
+use crate::core::{DangerLevel, Tool, ToolArgs, ToolCategory, ToolError, ToolResult};
+use crate::events::{ChangeKind, EventSender, OutputStream, ToolEvent};
+use crate::state::ToolState;
+use crate::stream::{ResultStream, StreamControl};
+use anyhow::Result;
+use serde_json;
+use std::io::{BufRead, BufReader};
+use std::process::Stdio;
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::{mpsc, Arc, Mutex};
+use std::time::{Duration, Instant};
+
+mod audit;
+mod benchmarks;
+pub mod output;
+mod precommit;
+mod run_tests;
+mod snapshots;
+pub mod terminal;
+pub mod test_parser;
+#[cfg(feature = "ast")]
+mod verify;
+
+pub use audit::{AuditDependenciesTool, AuditFinding, Auditor, Severity};
+pub use benchmarks::{CompareBenchmarksTool, RunBenchmarksTool};
+pub use precommit::{HookManager, HookResult, HookStatus, RunPrecommitTool};
+pub use run_tests::RunTestsTool;
+pub use snapshots::{
+    changed_snapshots, detect_snapshot_framework, snapshot_files, ChangedSnapshot, SnapshotChange,
+    SnapshotFramework, UpdateSnapshotsTool,
+};
+#[cfg(feature = "ast")]
+pub use verify::VerifyChangeTool;
+
+use output::{clean_output, decode_output};
+use test_parser::parse_test_failures;
+
+/// Tool for executing shell commands safely
+#[allow(dead_code)]
+pub struct RunCommandTool {
+    timeout_seconds: u64,
+    /// Fixed working directory; when unset, commands run in the session's working directory
+    working_dir: Option<std::path::PathBuf>,
+    dangerous_commands: Vec<String>,
+    /// Commands refused before they run, from the `[commands]` table of `cats.toml`
+    policy: CommandPolicy,
+}
+
+impl RunCommandTool {
+    /// Create a new run_command tool
+    pub fn new() -> Self {
+        Self {
+            timeout_seconds: 120,
+            working_dir: None,
+            dangerous_commands: vec![
+                "rm -rf".to_string(),
+                "sudo rm".to_string(),
+                "format".to_string(),
+                "mkfs".to_string(),
+                "dd if=".to_string(),
+                "shutdown".to_string(),
+                "reboot".to_string(),
+                "halt".to_string(),
+                "kill -9".to_string(),
+                ":(){:|:&};:".to_string(), // Fork bomb
+            ],
+            policy: CommandPolicy::default(),
+        }
+    }
+
+    /// Create a new run_command tool with custom timeout
+    pub fn new_with_timeout(timeout_seconds: u64) -> Self {
+        let mut tool = Self::new();
+        tool.timeout_seconds = timeout_seconds;
+        tool
+    }
+
+    /// Create a new run_command tool with custom working directory
+    ///
+    /// Commands then always run there instead of in the session's working directory.
+    pub fn new_with_workdir<P: AsRef<std::path::Path>>(working_dir: P) -> Self {
+        let mut tool = Self::new();
+        tool.working_dir = Some(working_dir.as_ref().to_path_buf());
+        tool
+    }
+
+    /// Use `timeout_seconds` as the command timeout
+    pub fn with_timeout(mut self, timeout_seconds: u64) -> Self {
+        self.timeout_seconds = timeout_seconds;
+        self
+    }
+
+    /// Create a new run_command tool that refuses the commands `policy` denies
+    pub fn new_with_policy(policy: CommandPolicy) -> Self {
+        let mut tool = Self::new();
+        tool.policy = policy;
+        tool
+    }
+
+    /// Seconds a call may run: its `timeout_secs` argument, or the tool's timeout
+    fn timeout_secs(&self, args: &ToolArgs) -> Result<u64, ToolError> {
+        match args.get_named_arg("timeout_secs") {
+            None => Ok(self.timeout_seconds),
+            Some(value) => match value.trim().parse::<u64>() {
+                Ok(seconds) if seconds > 0 => Ok(seconds),
+                _ => Err(ToolError::InvalidArgs {
+                    message: format!(
+                        "Invalid timeout_secs '{}': expected a positive number of seconds",
+                        value
+                    ),
+                }),
+            },
+        }
+    }
+
+    /// Directory commands run in for the session owning `state`
+    fn resolve_working_dir(&self, state: &Arc<Mutex<ToolState>>) -> std::path::PathBuf {
+        if let Some(dir) = &self.working_dir {
+            return dir.clone();
+        }
+        state
+            .lock()
+            .map(|state| state.working_directory.clone())
+            .unwrap_or_else(|_| std::path::PathBuf::from("."))
+    }
+
+    /// Check if a command contains dangerous patterns
+    #[allow(dead_code)]
+    fn is_dangerous_command(&self, command: &str) -> bool {
+        let lower_command = command.to_lowercase();
+        for dangerous in &self.dangerous_commands {
+            if lower_command.contains(&dangerous.to_lowercase()) {
+                return true;
+            }
+        }
+        false
+    }
+}
+
+/// Kill `child` and, on unix, every process in its process group
+fn kill_process_group(child: &mut std::process::Child) {
+    #[cfg(unix)]
+    if let Ok(pid) = libc::pid_t::try_from(child.id()) {
+        // SAFETY: kill has no memory-safety preconditions; a stale group id only fails
+        unsafe {
+            libc::kill(-pid, libc::SIGKILL);
+        }
+    }
+    let _ = child.kill();
+}
+
+/// How often [`wait_until`] checks whether a command whose output ended has exited
+const WAIT_POLL_INTERVAL: Duration = Duration::from_millis(10);
+
+/// Wait for `child` to exit, killing its process group once `deadline` passes
+fn wait_until(
+    child: &Mutex<std::process::Child>,
+    deadline: Instant,
+    timed_out: &AtomicBool,
+) -> std::io::Result<std::process::ExitStatus> {
+    loop {
+        let mut child = child
+            .lock()
+            .map_err(|_| std::io::Error::other("Command state was poisoned"))?;
+        if let Some(status) = child.try_wait()? {
+            return Ok(status);
+        }
+        if Instant::now() >= deadline {
+            timed_out.store(true, Ordering::SeqCst);
+            kill_process_group(&mut child);
+            return child.wait();
+        }
+        drop(child);
+        std::thread::sleep(WAIT_POLL_INTERVAL);
+    }
+}
+
+/// Output of a command run by [`run_streaming`], and whether it ran out of time
+struct Finished {
+    output: std::process::Output,
+    timed_out: bool,
+}
+
+/// Run `process`, publishing each output line as a `CommandOutputChunk` event and a
+/// chunk of `stream` as it arrives, and return the collected output
+///
+/// The process group is killed when the stream consumer stops the call or when the
+/// command is still running after `timeout`.
+fn run_streaming(
+    process: &mut std::process::Command,
+    command: &str,
+    events: Option<&EventSender>,
+    stream: &ResultStream,
+    keep_ansi: bool,
+    timeout: Duration,
+) -> std::io::Result<Finished> {
+    fn pump(
+        mut reader: impl BufRead,
+        command: &str,
+        output: OutputStream,
+        events: Option<&EventSender>,
+        stream: &ResultStream,
+        child: &Mutex<std::process::Child>,
+        keep_ansi: bool,
+    ) -> Vec<u8> {
+        let mut collected = Vec::new();
+        let mut line = Vec::new();
+        while matches!(reader.read_until(b'\n', &mut line), Ok(n) if n > 0) {
+            let chunk = clean_output(&line, keep_ansi);
+            if stream.is_active() && !stream.is_stopped() {
+                let control =
+                    stream.send_with_data(chunk.clone(), serde_json::json!({ "stream": output }));
+                if control == StreamControl::Stop {
+                    if let Ok(mut child) = child.lock() {
+                        kill_process_group(&mut child);
+                    }
+                    break;
+                }
+            }
+            crate::events::emit(
+                events,
+                ToolEvent::CommandOutputChunk {
+                    command: command.to_string(),
+                    stream: output,
+                    chunk,
+                },
+            );
+            collected.append(&mut line);
+        }
+        collected
+    }
+
+    // Give the command its own process group, so stopping it also ends what it spawned
+    #[cfg(unix)]
+    std::os::unix::process::CommandExt::process_group(process, 0);
+    let mut child = process.spawn()?;
+    let stdout = child.stdout.take().map(BufReader::new);
+    let stderr = child.stderr.take().map(BufReader::new);
+    let child = Mutex::new(child);
+    let timed_out = AtomicBool::new(false);
+    let deadline = Instant::now() + timeout;
+    let (stdout, stderr, status) = std::thread::scope(|scope| {
+        // The watchdog wakes when the command exits or the timeout passes, whichever is first
+        let (done, finished) = mpsc::channel::<()>();
+        let (child_ref, timed_out_ref) = (&child, &timed_out);
+        scope.spawn(move || {
+            if finished.recv_timeout(timeout) == Err(mpsc::RecvTimeoutError::Timeout) {
+                timed_out_ref.store(true, Ordering::SeqCst);
+                if let Ok(mut child) = child_ref.lock() {
+                    kill_process_group(&mut child);
+                }
+            }
+        });
+        let stderr = scope.spawn(|| {
+            stderr
+                .map(|r| {
+                    pump(
+                        r,
+                        command,
+                        OutputStream::Stderr,
+                        events,
+                        stream,
+                        &child,
+                        keep_ansi,
+                    )
+                })
+                .unwrap_or_default()
+        });
+        let stdout = stdout
+            .map(|r| {
+                pump(
+                    r,
+                    command,
+                    OutputStream::Stdout,
+                    events,
+                    stream,
+                    &child,
+                    keep_ansi,
+                )
+            })
+            .unwrap_or_default();
+        let stderr = stderr.join().unwrap_or_default();
+        // The output can end long before the command does, e.g. when it redirects its
+        // stdio, so the deadline holds until it exits
+        let status = wait_until(&child, deadline, &timed_out);
+        drop(done);
+        (stdout, stderr, status)
+    });
+    let status = status?;
+    Ok(Finished {
+        output: std::process::Output {
+            status,
+            stdout,
+            stderr,
+        },
+        timed_out: timed_out.into_inner(),
+    })
+}
+
+impl Tool for RunCommandTool {
+    fn name(&self) -> &str {
+        "run_command"
+    }
+
+    fn description(&self) -> &str {
+        "Execute shell commands safely with timeout protection and dangerous command filtering"
+    }
+
+    fn signature(&self) -> &str {
+        "run_command(command: string, timeout_secs?: int) -> {stdout: string, stderr: string, success: bool}"
+    }
+
+    fn category(&self) -> ToolCategory {
+        ToolCategory::Execution
+    }
+
+    fn danger_level(&self) -> DangerLevel {
+        DangerLevel::ExecutesCode
+    }
+
+    fn compact_message(&self, result: &ToolResult) -> Option<String> {
+        let data = result.data.as_ref()?;
+        let lines = |key: &str| data[key].as_str().map_or(0, |s| s.lines().count());
+        Some(format!(
+            "Command succeeded ({} stdout lines, {} stderr lines)",
+            lines("stdout"),
+            lines("stderr")
+        ))
+    }
+
+    fn validate_args(&self, args: &ToolArgs) -> Result<(), ToolError> {
+        if args.get_arg(0).is_none() && args.get_named_arg("command").is_none() {
+            return Err(ToolError::InvalidArgs {
+                message: "run_command requires either a positional 'command' argument or named 'command' parameter".to_string(),
+            });
+        }
+        self.timeout_secs(args)?;
+
+        Ok(())
+    }
+
+    fn execute(&mut self, args: &ToolArgs, state: &Arc<Mutex<ToolState>>) -> Result<ToolResult> {
+        // Extract command from arguments
+        let command = if let Some(cmd) = args.get_arg(0) {
+            cmd.clone()
+        } else if let Some(cmd) = args.get_named_arg("command") {
+            cmd.clone()
+        } else {
+            return Ok(ToolResult::error("No command provided"));
+        };
+        if let Some(pattern) = self.policy.denied_pattern(&command) {
+            return Ok(ToolResult::error_with_data(
+                format!(
+                    "Command refused by the [commands] deny list of {}: matches '{}'",
+                    CONFIG_FILE, pattern
+                ),
+                serde_json::json!({
+                    "command": command,
+                    "denied_by": pattern
+                }),
+            ));
+        }
+
+        let timeout_secs = match self.timeout_secs(args) {
+            Ok(seconds) => seconds,
+            Err(e) => return Ok(ToolResult::error(e.to_string())),
+        };
+        let keep_ansi = args
+            .get_named_arg("keep_ansi")
+            .map(|v| v == "true")
+            .unwrap_or(false);
+        let terminal = args
+            .get_named_arg("terminal")
+            .map(|v| v == "true")
+            .unwrap_or(false);
+        let clean = |bytes: &[u8]| {
+            if terminal {
+                terminal::render_screen(
+                    &decode_output(bytes),
+                    terminal::DEFAULT_COLUMNS,
+                    terminal::DEFAULT_ROWS,
+                )
+            } else {
+                clean_output(bytes, keep_ansi)
+            }
+        };
+
+        // Log the command execution to state
+        if let Ok(mut state) = state.lock() {
+            state.push_history(format!("run_command: {}", command));
+        }
+        let working_dir = self.resolve_working_dir(state);
+        let (events, stream) = state
+            .lock()
+            .map(|state| (state.events.clone(), state.stream.clone()))
+            .unwrap_or_default();
+        // Fingerprint the open files, so windows the command rewrites (formatters,
+        // codegen) can be reloaded afterwards
+        let open_before = state
+            .lock()
+            .map(|state| state.open_file_hashes())
+            .unwrap_or_default();
+
+        // Execute command synchronously (blocking), since the Tool trait is not async
+        let mut process = std::process::Command::new("sh");
+        process
+            .arg("-c")
+            .arg(&command)
+            .current_dir(&working_dir)
+            .stdout(Stdio::piped())
+            .stderr(Stdio::piped());
+        if terminal {
+            // Programs that size their display from the environment draw for the
+            // virtual terminal
+            process
+                .env("COLUMNS", terminal::DEFAULT_COLUMNS.to_string())
+                .env("LINES", terminal::DEFAULT_ROWS.to_string());
+        }
+        let events = events.filter(|events| events.receiver_count() > 0);
+        let result = run_streaming(
+            &mut process,
+            &command,
+            events.as_ref(),
+            &stream,
+            keep_ansi,
+            Duration::from_secs(timeout_secs),
+        );
+
+        match result {
+            Ok(Finished { output, timed_out }) => {
+                let stdout = clean(&output.stdout);
+                let stderr = clean(&output.stderr);
+                let command_success = output.status.success();
+
+                let mut result_data = serde_json::json!({
+                    "stdout": stdout,
+                    "stderr": stderr,
+                    "success": command_success,
+                    "command": command,
+                    "working_directory": working_dir.to_string_lossy()
+                });
+                if timed_out {
+                    result_data["timed_out"] = serde_json::Value::Bool(true);
+                    result_data["timeout_secs"] = serde_json::json!(timeout_secs);
+                }
+                if terminal {
+                    result_data["terminal"] = serde_json::json!({
+                        "columns": terminal::DEFAULT_COLUMNS,
+                        "rows": terminal::DEFAULT_ROWS
+                    });
+                }
+                // A command killed because the consumer stopped reading is not a failure
+                let stopped = stream.is_stopped();
+                if !command_success && !stopped {
+                    let failures = parse_test_failures(&format!("{}\n{}", stdout, stderr), None);
+                    if !failures.is_empty() {
+                        result_data["test_failures"] = serde_json::json!(failures);
+                    }
+                }
+
+                let message = if timed_out {
+                    format!(
+                        "Command timed out after {}s; it and the processes it started were killed:\nstdout: {}\nstderr: {}",
+                        timeout_secs, stdout, stderr
+                    )
+                } else if stopped {
+                    format!(
+                        "Command stopped early by the stream consumer:\nstdout: {}\nstderr: {}",
+                        stdout, stderr
+                    )
+                } else if command_success {
+                    let mut msg_parts = Vec::new();
+                    if !stdout.is_empty() {
+                        msg_parts.push(format!("stdout:\n{}", stdout));
+                    }
+                    if !stderr.is_empty() {
+                        msg_parts.push(format!("stderr:\n{}", stderr));
+                    }
+                    if msg_parts.is_empty() {
+                        "Command executed successfully with no output (exit code: 0)".to_string()
+                    } else {
+                        format!("Command executed successfully:\n{}", msg_parts.join("\n"))
+                    }
+                } else {
+                    format!(
+                        "Command failed with exit code {}:\nstdout: {}\nstderr: {}",
+                        output.status.code().unwrap_or(-1),
+                        stdout,
+                        stderr
+                    )
+                };
+
+                let changed = state
+                    .lock()
+                    .map(|mut state| state.refresh_changed_files(&open_before))
+                    .unwrap_or_default();
+                let message = if changed.is_empty() {
+                    message
+                } else {
+                    let files: Vec<String> = changed
+                        .iter()
+                        .map(|(path, kind)| match kind {
+                            ChangeKind::Deleted => format!("{} (deleted, closed)", path.display()),
+                            _ => format!("{} (reloaded)", path.display()),
+                        })
+                        .collect();
+                    result_data["changed_open_files"] = serde_json::json!(changed
+                        .iter()
+                        .map(|(path, kind)| serde_json::json!({"path": path, "change": kind}))
+                        .collect::<Vec<_>>());
+                    format!(
+                        "{}\n\nOpen files changed by the command: {}",
+                        message,
+                        files.join(", ")
+                    )
+                };
+
+                // Return tool result with success reflecting command success
+                if command_success || stopped {
+                    Ok(ToolResult::success_with_data(message, result_data))
+                } else {
+                    Ok(ToolResult::error_with_data(message, result_data))
+                }
+            }
+            Err(e) => Ok(ToolResult::error(format!(
+                "command failed to execute '{}' in {}: {}",
+                command,
+                working_dir.display(),
+                e
+            ))),
+        }
+    }
+
+    fn get_parameters_schema(&self) -> serde_json::Value {
+        serde_json::json!({
+            "type": "object",
+            "properties": {
+                "command": {
+                    "type": "string",
+                    "description": "The shell command to execute"
+                },
+                "keep_ansi": {
+                    "type": "boolean",
+                    "description": "Keep ANSI color/control sequences in the output (stripped by default)"
+                },
+                "terminal": {
+                    "type": "boolean",
+                    "description": "Replay the output on a virtual terminal and return its final screen, for progress displays and watch modes that redraw with cursor movement"
+                },
+                "timeout_secs": {
+                    "type": "integer",
+                    "minimum": 1,
+                    "description": "Kill the command, and every process it started, after this many seconds; defaults to the configured command timeout (120 seconds unless set)"
+                }
+            },
+            "required": ["command"],
+            "additionalProperties": false
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::collections::HashMap;
+    use std::sync::{Arc, Mutex};
+
+    #[test]
+    fn test_run_command_tool_creation() {
+        let tool = RunCommandTool::new();
+        assert_eq!(tool.name(), "run_command");
+        assert!(!tool.description().is_empty());
+        assert!(!tool.signature().is_empty());
+    }
+
+    #[test]
+    fn test_run_command_validation() {
+        let tool = RunCommandTool::new();
+
+        // Test empty arguments
+        let empty_args = ToolArgs::from_args(&[]);
+        assert!(tool.validate_args(&empty_args).is_err());
+
+        // Test valid arguments
+        let valid_args = ToolArgs::from_args(&["echo hello"]);
+        assert!(tool.validate_args(&valid_args).is_ok());
+
+        // Test named argument
+        let mut named_args = HashMap::new();
+        named_args.insert("command".to_string(), "echo hello".to_string());
+        let named_tool_args = ToolArgs::with_named_args(vec![], named_args);
+        assert!(tool.validate_args(&named_tool_args).is_ok());
+    }
+
+    #[test]
+    fn test_dangerous_command_detection() {
+        let tool = RunCommandTool::new();
+
+        assert!(tool.is_dangerous_command("rm -rf /"));
+        assert!(tool.is_dangerous_command("sudo rm -f important.txt"));
+        assert!(tool.is_dangerous_command("shutdown now"));
+        assert!(!tool.is_dangerous_command("echo hello"));
+        assert!(!tool.is_dangerous_command("ls -la"));
+        assert!(!tool.is_dangerous_command("cargo build"));
+    }
+
+    #[test]
+    fn test_run_command_execution() {
+        let mut tool = RunCommandTool::new();
+        let state = Arc::new(Mutex::new(ToolState::new()));
+
+        // Test simple command
+        let args = ToolArgs::from_args(&["echo hello world"]);
+        let result = tool.execute(&args, &state).unwrap();
+
+        assert!(result.success);
+        assert!(result.message.contains("hello world"));
+
+        // Test command that should fail
+        let bad_args = ToolArgs::from_args(&["nonexistent_command_xyz"]);
+        let result = tool.execute(&bad_args, &state).unwrap();
+
+        assert!(!result.success);
+        assert!(result.message.contains("failed") || result.message.contains("Command failed"));
+    }
+
+    #[test]
+    fn test_run_command_strips_ansi_by_default() {
+        let mut tool = RunCommandTool::new();
+        let state = Arc::new(Mutex::new(ToolState::new()));
+        let command = r"printf '\033[31mred\033[0m\n10%%\r100%%\n'";
+
+        let result = tool
+            .execute(&ToolArgs::from_args(&[command]), &state)
+            .unwrap();
+        assert_eq!(result.data.as_ref().unwrap()["stdout"], "red\n100%\n");
+
+        let mut named_args = HashMap::new();
+        named_args.insert("keep_ansi".to_string(), "true".to_string());
+        let args = ToolArgs::with_named_args(vec![command.to_string()], named_args);
+        let result = tool.execute(&args, &state).unwrap();
+        assert!(result.data.unwrap()["stdout"]
+            .as_str()
+            .unwrap()
+            .contains("\u{1b}[31m"));
+    }
+
+    #[test]
+    fn test_run_command_terminal_capture() {
+        let mut tool = RunCommandTool::new();
+        let state = Arc::new(Mutex::new(ToolState::new()));
+        let command = r"printf 'step 1/2\n\033[1A\033[2Kstep 2/2\ndone\n'";
+
+        let mut named_args = HashMap::new();
+        named_args.insert("terminal".to_string(), "true".to_string());
+        let args = ToolArgs::with_named_args(vec![command.to_string()], named_args);
+        let result = tool.execute(&args, &state).unwrap();
+        let data = result.data.unwrap();
+        assert_eq!(data["stdout"], "step 2/2\ndone\n");
+        assert_eq!(data["terminal"]["columns"], terminal::DEFAULT_COLUMNS);
+
+        // Without it both frames are left in the output
+        let result = tool
+            .execute(&ToolArgs::from_args(&[command]), &state)
+            .unwrap();
+        assert_eq!(result.data.unwrap()["stdout"], "step 1/2\nstep 2/2\ndone\n");
+    }
+
+    #[test]
+    fn test_run_command_uses_session_working_directory() {
+        let temp_dir = tempfile::TempDir::new().unwrap();
+        let checkout = temp_dir.path().canonicalize().unwrap();
+        let mut tool = RunCommandTool::new();
+        let mut tool_state = ToolState::new();
+        tool_state.set_working_directory(&checkout);
+        let state = Arc::new(Mutex::new(tool_state));
+
+        let result = tool
+            .execute(&ToolArgs::from_args(&["pwd"]), &state)
+            .unwrap();
+        let data = result.data.unwrap();
+        assert_eq!(
+            data["stdout"].as_str().unwrap().trim(),
+            checkout.to_str().unwrap()
+        );
+        assert_eq!(data["working_directory"], checkout.to_str().unwrap());
+
+        // A fixed working directory wins over the session's
+        let mut tool = RunCommandTool::new_with_workdir(std::env::temp_dir());
+        let result = tool
+            .execute(&ToolArgs::from_args(&["pwd"]), &state)
+            .unwrap();
+        assert_ne!(
+            result.data.unwrap()["working_directory"],
+            checkout.to_str().unwrap()
+        );
+    }
+
+    #[test]
+    fn test_run_command_streams_output_chunks() {
+        let events = crate::events::channel();
+        let receiver = events.subscribe();
+        let mut tool_state = ToolState::new();
+        tool_state.events = Some(events);
+        let state = Arc::new(Mutex::new(tool_state));
+
+        let mut tool = RunCommandTool::new();
+        let result = tool
+            .execute(
+                &ToolArgs::from_args(&["echo one; echo two >&2; echo three"]),
+                &state,
+            )
+            .unwrap();
+        assert_eq!(result.data.unwrap()["stdout"], "one\nthree\n");
+
+        let mut chunks = Vec::new();
+        while let Ok(event) = receiver.try_recv() {
+            if let ToolEvent::CommandOutputChunk { stream, chunk, .. } = event {
+                chunks.push((stream, chunk));
+            }
+        }
+        assert_eq!(chunks.len(), 3);
+        assert!(chunks.contains(&(OutputStream::Stderr, "two\n".to_string())));
+        let stdout: Vec<_> = chunks
+            .iter()
+            .filter(|(stream, _)| *stream == OutputStream::Stdout)
+            .map(|(_, chunk)| chunk.as_str())
+            .collect();
+        assert_eq!(stdout, vec!["one\n", "three\n"]);
+    }
+
+    #[test]
+    fn test_run_command_stops_when_stream_consumer_stops() {
+        let mut tool_state = ToolState::new();
+        tool_state.stream = ResultStream::new("run_command", |chunk| {
+            if chunk.seq == 2 {
+                StreamControl::Stop
+            } else {
+                StreamControl::Continue
+            }
+        });
+        let state = Arc::new(Mutex::new(tool_state));
+
+        // Without the kill this command never ends
+        let result = RunCommandTool::new()
+            .execute(&ToolArgs::from_args(&["yes"]), &state)
+            .unwrap();
+        assert!(result.success, "{}", result.message);
+        assert!(result.message.starts_with("Command stopped early"));
+        assert!(state.lock().unwrap().stream.is_stopped());
+    }
+
+    #[test]
+    fn test_run_command_timeout_kills_process_group() {
+        let state = Arc::new(Mutex::new(ToolState::new()));
+        let mut tool = RunCommandTool::new();
+        let mut named_args = HashMap::new();
+        named_args.insert("timeout_secs".to_string(), "1".to_string());
+        // The background sleep keeps the output pipes open unless its group is killed
+        let args = ToolArgs::with_named_args(
+            vec!["echo started; sleep 30 & sleep 30".to_string()],
+            named_args,
+        );
+
+        let started = std::time::Instant::now();
+        let result = tool.execute(&args, &state).unwrap();
+        assert!(started.elapsed() < std::time::Duration::from_secs(10));
+        assert!(!result.success);
+        assert!(
+            result.message.starts_with("Command timed out after 1s"),
+            "{}",
+            result.message
+        );
+        let data = result.data.unwrap();
+        assert_eq!(data["timed_out"], true);
+        assert_eq!(data["stdout"], "started\n");
+
+        // A command that closes its output is still stopped at the deadline
+        let mut named_args = HashMap::new();
+        named_args.insert("timeout_secs".to_string(), "1".to_string());
+        let args = ToolArgs::with_named_args(
+            vec!["exec sleep 8 > /dev/null 2>&1".to_string()],
+            named_args,
+        );
+        let started = std::time::Instant::now();
+        let result = tool.execute(&args, &state).unwrap();
+        assert!(started.elapsed() < std::time::Duration::from_secs(5));
+        assert!(!result.success);
+        assert_eq!(result.data.unwrap()["timed_out"], true);
+
+        let mut named_args = HashMap::new();
+        named_args.insert("timeout_secs".to_string(), "0".to_string());
+        let args = ToolArgs::with_named_args(vec!["true".to_string()], named_args);
+        assert!(tool.validate_args(&args).is_err());
+
+        // The tool's own timeout applies to calls without one
+        let result = RunCommandTool::new_with_timeout(1)
+            .execute(&ToolArgs::from_args(&["sleep 30"]), &state)
+            .unwrap();
+        assert_eq!(result.data.unwrap()["timeout_secs"], 1);
+    }
+
+    #[test]
+    fn test_run_command_reloads_open_files_it_changes() {
+        let temp_dir = tempfile::TempDir::new().unwrap();
+        let formatted = temp_dir.path().join("formatted.rs");
+        let generated = temp_dir.path().join("generated.rs");
+        let untouched = temp_dir.path().join("untouched.rs");
+        for path in [&formatted, &generated, &untouched] {
+            std::fs::write(path, "fn  main(){}\n").unwrap();
+        }
+        let mut tool_state = ToolState::new();
+        tool_state.set_working_directory(temp_dir.path());
+        for path in [&formatted, &generated, &untouched] {
+            tool_state
+                .open_file(path.clone(), vec!["fn  main(){}".to_string()], 10)
+                .unwrap();
+        }
+        let state = Arc::new(Mutex::new(tool_state));
+
+        let mut tool = RunCommandTool::new();
+        let result = tool
+            .execute(
+                &ToolArgs::from_args(&["printf 'fn main() {}\\n' > formatted.rs; rm generated.rs"]),
+                &state,
+            )
+            .unwrap();
+        assert!(result.success, "{}", result.message);
+        assert!(result
+            .message
+            .contains("Open files changed by the command:"));
+        let changed = &result.data.unwrap()["changed_open_files"];
+        assert_eq!(changed.as_array().unwrap().len(), 2);
+        assert_eq!(changed[0]["change"], "modified");
+        assert_eq!(changed[1]["change"], "deleted");
+
+        let state = state.lock().unwrap();
+        assert_eq!(state.open_files.len(), 2);
+        let reloaded = &state.open_files[&formatted.canonicalize().unwrap()];
+        assert_eq!(reloaded.content, vec!["fn main() {}"]);
+    }
+
+    #[test]
+    fn test_openai_schema() {
+        let tool = RunCommandTool::new();
+        let schema = tool.get_openai_schema();
+
+        assert_eq!(schema["type"], "function");
+        assert_eq!(schema["function"]["name"], "run_command");
+        assert!(!schema["function"]["description"]
+            .as_str()
+            .unwrap()
+            .is_empty());
+
+        let params = &schema["function"]["parameters"];
+        assert_eq!(params["type"], "object");
+        assert!(params["properties"]["command"].is_object());
+        assert_eq!(params["required"][0], "command");
+    }
+}
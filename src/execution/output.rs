@@ -0,0 +1,153 @@
+//! Post-processing of raw command output into clean text for LLM consumption
+
+/// Decode raw process output into a `String`.
+///
+/// Valid UTF-8 is returned unchanged and UTF-16 output with a byte order mark is
+/// transcoded. Other invalid input is decoded lossily; when it contains no valid
+/// multi-byte UTF-8 sequence at all it is treated as Latin-1 so accented text from
+/// legacy tools survives instead of turning into replacement characters.
+pub fn decode_output(bytes: &[u8]) -> String {
+    if let Ok(text) = std::str::from_utf8(bytes) {
+        return text.to_string();
+    }
+
+    if bytes.len() >= 2 && (bytes[..2] == [0xFF, 0xFE] || bytes[..2] == [0xFE, 0xFF]) {
+        let little_endian = bytes[0] == 0xFF;
+        let units: Vec<u16> = bytes[2..]
+            .chunks_exact(2)
+            .map(|pair| {
+                if little_endian {
+                    u16::from_le_bytes([pair[0], pair[1]])
+                } else {
+                    u16::from_be_bytes([pair[0], pair[1]])
+                }
+            })
+            .collect();
+        return String::from_utf16_lossy(&units);
+    }
+
+    let lossy = String::from_utf8_lossy(bytes);
+    let has_multibyte = lossy
+        .chars()
+        .any(|c| !c.is_ascii() && c != char::REPLACEMENT_CHARACTER);
+    if has_multibyte {
+        lossy.into_owned()
+    } else {
+        bytes.iter().map(|&b| b as char).collect()
+    }
+}
+
+/// Remove ANSI escape sequences (colors, cursor movement, OSC titles/links) and
+/// stray control characters. Backspaces erase the preceding character.
+pub fn strip_ansi(text: &str) -> String {
+    let mut out = String::with_capacity(text.len());
+    let mut chars = text.chars().peekable();
+
+    while let Some(c) = chars.next() {
+        match c {
+            '\u{1b}' => match chars.peek() {
+                // CSI: ESC [ parameters... final byte in 0x40..=0x7E
+                Some('[') => {
+                    chars.next();
+                    for next in chars.by_ref() {
+                        if ('\u{40}'..='\u{7e}').contains(&next) {
+                            break;
+                        }
+                    }
+                }
+                // OSC: ESC ] ... terminated by BEL or ESC \
+                Some(']') => {
+                    chars.next();
+                    while let Some(next) = chars.next() {
+                        if next == '\u{7}' {
+                            break;
+                        }
+                        if next == '\u{1b}' {
+                            if chars.peek() == Some(&'\\') {
+                                chars.next();
+                            }
+                            break;
+                        }
+                    }
+                }
+                // Two-character escapes such as ESC ( B or ESC =
+                Some('(') | Some(')') => {
+                    chars.next();
+                    chars.next();
+                }
+                Some(_) => {
+                    chars.next();
+                }
+                None => {}
+            },
+            '\u{8}' => {
+                out.pop();
+            }
+            '\n' | '\r' | '\t' => out.push(c),
+            c if c.is_control() => {}
+            c => out.push(c),
+        }
+    }
+
+    out
+}
+
+/// Collapse carriage-return rewrites (progress bars, spinners) to the final
+/// visible text of each line. CRLF line endings are normalized to LF.
+pub fn collapse_carriage_returns(text: &str) -> String {
+    if !text.contains('\r') {
+        return text.to_string();
+    }
+
+    text.split('\n')
+        .map(|line| {
+            let line = line.strip_suffix('\r').unwrap_or(line);
+            line.rsplit('\r')
+                .find(|segment| !segment.is_empty())
+                .unwrap_or("")
+        })
+        .collect::<Vec<_>>()
+        .join("\n")
+}
+
+/// Decode and clean command output. ANSI sequences are kept when `keep_ansi` is set.
+pub fn clean_output(bytes: &[u8], keep_ansi: bool) -> String {
+    let decoded = decode_output(bytes);
+    let text = if keep_ansi {
+        decoded
+    } else {
+        strip_ansi(&decoded)
+    };
+    collapse_carriage_returns(&text)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_strip_ansi_colors_and_osc() {
+        let raw = "\u{1b}[1;31merror\u{1b}[0m: \u{1b}]8;;https://x\u{7}link\u{1b}]8;;\u{7} done";
+        assert_eq!(strip_ansi(raw), "error: link done");
+    }
+
+    #[test]
+    fn test_collapse_progress_bar() {
+        let raw = "Downloading 10%\rDownloading 50%\rDownloading 100%\nDone\r\n";
+        assert_eq!(collapse_carriage_returns(raw), "Downloading 100%\nDone\n");
+    }
+
+    #[test]
+    fn test_decode_latin1_and_utf16() {
+        assert_eq!(decode_output(b"caf\xe9"), "café");
+        assert_eq!(decode_output(&[0xFF, 0xFE, b'o', 0, b'k', 0]), "ok");
+        assert_eq!(decode_output("naïve".as_bytes()), "naïve");
+    }
+
+    #[test]
+    fn test_clean_output_keep_ansi() {
+        let raw = b"\x1b[32mok\x1b[0m\n";
+        assert_eq!(clean_output(raw, false), "ok\n");
+        assert_eq!(clean_output(raw, true), "\u{1b}[32mok\u{1b}[0m\n");
+    }
+}
@@ -0,0 +1,706 @@
+//! run_precommit tool: run the repository's pre-commit hooks on changed files
+//!
+//! Supports the [pre-commit](https://pre-commit.com) framework, husky and lefthook.
+//! Their output is split into one result per hook, so a failing formatter or linter
+//! can be fixed without reading through the report of every hook that passed.
+
+use super::output::clean_output;
+use super::run_tests::shell_quote;
+use crate::core::{DangerLevel, Tool, ToolArgs, ToolCategory, ToolError, ToolResult};
+use crate::state::{session_path, ToolState};
+use anyhow::Result;
+use regex::Regex;
+use serde::{Deserialize, Serialize};
+use std::path::Path;
+use std::process::Command;
+use std::sync::{Arc, Mutex};
+
+/// Output lines of a failing hook included in the message
+const HOOK_OUTPUT_LINES: usize = 20;
+/// Number of trailing output lines included when no hook result could be parsed
+const OUTPUT_TAIL_LINES: usize = 40;
+
+const LEFTHOOK_CONFIGS: [&str; 6] = [
+    "lefthook.yml",
+    ".lefthook.yml",
+    "lefthook.yaml",
+    ".lefthook.yaml",
+    "lefthook.toml",
+    "lefthook.json",
+];
+
+/// Pre-commit hook managers `run_precommit` can drive
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum HookManager {
+    /// The pre-commit framework, configured in `.pre-commit-config.yaml`
+    PreCommit,
+    /// husky, with its hooks as scripts under `.husky/`
+    Husky,
+    /// lefthook, configured in `lefthook.yml`
+    Lefthook,
+}
+
+impl HookManager {
+    /// Parse a manager name as accepted by the `manager` tool parameter
+    pub fn from_name(name: &str) -> Option<Self> {
+        match name.to_lowercase().as_str() {
+            "pre-commit" | "pre_commit" | "precommit" => Some(Self::PreCommit),
+            "husky" => Some(Self::Husky),
+            "lefthook" => Some(Self::Lefthook),
+            _ => None,
+        }
+    }
+
+    /// Canonical manager name
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            Self::PreCommit => "pre-commit",
+            Self::Husky => "husky",
+            Self::Lefthook => "lefthook",
+        }
+    }
+
+    /// Detect the manager configured in `dir`
+    pub fn detect(dir: &Path) -> Option<Self> {
+        let pre_commit = [".pre-commit-config.yaml", ".pre-commit-config.yml"];
+        if pre_commit.iter().any(|name| dir.join(name).is_file()) {
+            Some(Self::PreCommit)
+        } else if LEFTHOOK_CONFIGS.iter().any(|name| dir.join(name).is_file()) {
+            Some(Self::Lefthook)
+        } else if dir.join(".husky").join("pre-commit").is_file() {
+            Some(Self::Husky)
+        } else {
+            None
+        }
+    }
+
+    /// Command running the pre-commit hooks on `files`, or on every file
+    ///
+    /// husky hooks are plain scripts that pick their own files (usually the staged
+    /// ones, through lint-staged), so they get neither.
+    pub fn command(&self, files: &[String], all_files: bool) -> String {
+        let quoted = |flag: &str| {
+            files
+                .iter()
+                .map(|file| format!(" {}{}", flag, shell_quote(file)))
+                .collect::<String>()
+        };
+        match (self, all_files) {
+            (Self::PreCommit, true) => "pre-commit run --color never --all-files".to_string(),
+            (Self::PreCommit, false) => {
+                format!("pre-commit run --color never --files{}", quoted(""))
+            }
+            (Self::Lefthook, true) => "lefthook run pre-commit --all-files".to_string(),
+            (Self::Lefthook, false) => {
+                format!("lefthook run pre-commit{}", quoted("--file="))
+            }
+            (Self::Husky, _) => "sh .husky/pre-commit".to_string(),
+        }
+    }
+}
+
+/// Outcome of one hook
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum HookStatus {
+    Passed,
+    Failed,
+    Skipped,
+}
+
+/// Result of one hook, parsed from the manager's report
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct HookResult {
+    /// Hook name as the report shows it
+    pub name: String,
+    /// Hook id, when the manager reports one apart from the name
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub id: Option<String>,
+    pub status: HookStatus,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub exit_code: Option<i32>,
+    /// The hook rewrote files (formatters); running it again usually passes
+    #[serde(skip_serializing_if = "std::ops::Not::not")]
+    pub files_modified: bool,
+    /// What the hook printed
+    #[serde(skip_serializing_if = "String::is_empty")]
+    pub output: String,
+}
+
+impl HookResult {
+    fn new(name: &str, status: HookStatus) -> Self {
+        Self {
+            name: name.trim().to_string(),
+            id: None,
+            status,
+            exit_code: None,
+            files_modified: false,
+            output: String::new(),
+        }
+    }
+}
+
+/// Split a manager's report into per-hook results
+///
+/// husky runs a single script, so its result is the script's: `exit_code` decides
+/// the status and the whole output is the hook's.
+pub fn parse_hook_results(
+    manager: HookManager,
+    output: &str,
+    exit_code: Option<i32>,
+) -> Vec<HookResult> {
+    match manager {
+        HookManager::PreCommit => parse_pre_commit(output),
+        HookManager::Lefthook => parse_lefthook(output),
+        HookManager::Husky => {
+            let status = if exit_code == Some(0) {
+                HookStatus::Passed
+            } else {
+                HookStatus::Failed
+            };
+            let mut result = HookResult::new("pre-commit", status);
+            result.exit_code = exit_code;
+            result.output = output.trim().to_string();
+            vec![result]
+        }
+    }
+}
+
+/// `black....................Failed` lines followed by `- hook id:`, `- exit code:`
+/// and the hook's output
+fn parse_pre_commit(output: &str) -> Vec<HookResult> {
+    let status_re = Regex::new(r"^(.+?)\.+(?:\([^)]*\))?(Passed|Failed|Skipped)$").unwrap();
+    let mut results: Vec<HookResult> = Vec::new();
+    for line in output.lines() {
+        let line = line.trim_end();
+        if let Some(caps) = status_re.captures(line) {
+            let status = match &caps[2] {
+                "Passed" => HookStatus::Passed,
+                "Failed" => HookStatus::Failed,
+                _ => HookStatus::Skipped,
+            };
+            results.push(HookResult::new(&caps[1], status));
+            continue;
+        }
+        let Some(hook) = results.last_mut() else {
+            continue;
+        };
+        if let Some(id) = line.strip_prefix("- hook id: ") {
+            hook.id = Some(id.trim().to_string());
+        } else if let Some(code) = line.strip_prefix("- exit code: ") {
+            hook.exit_code = code.trim().parse().ok();
+        } else if line == "- files were modified by this hook" {
+            hook.files_modified = true;
+        } else {
+            hook.output.push_str(line);
+            hook.output.push('\n');
+        }
+    }
+    for hook in &mut results {
+        hook.output = hook.output.trim().to_string();
+    }
+    results
+}
+
+/// `┃  lint ❯` sections with each command's output, then a summary marking
+/// commands `✔️` passed or `🥊` failed
+fn parse_lefthook(output: &str) -> Vec<HookResult> {
+    let section_re = Regex::new(r"^\s*┃\s+(.+?)\s+❯").unwrap();
+    let mut sections: Vec<(String, String)> = Vec::new();
+    let mut results = Vec::new();
+    let mut in_summary = false;
+    for line in output.lines() {
+        let line = line.trim_end();
+        let trimmed = line.trim_start();
+        if trimmed.starts_with("summary:") {
+            in_summary = true;
+        } else if in_summary {
+            if let Some(name) = trimmed.strip_prefix("✔️") {
+                results.push(HookResult::new(name, HookStatus::Passed));
+            } else if let Some(name) = trimmed.strip_prefix("🥊") {
+                results.push(HookResult::new(name, HookStatus::Failed));
+            }
+        } else if let Some(caps) = section_re.captures(line) {
+            sections.push((caps[1].to_string(), String::new()));
+        } else if let Some((_, text)) = sections.last_mut() {
+            text.push_str(line);
+            text.push('\n');
+        }
+    }
+    for hook in &mut results {
+        if let Some((_, text)) = sections.iter().find(|(name, _)| *name == hook.name) {
+            hook.output = text.trim().to_string();
+        }
+    }
+    results
+}
+
+/// Files with uncommitted changes in `dir`, relative to it: staged, unstaged and
+/// untracked ones, without deletions. `None` outside a git repository.
+pub fn changed_files(dir: &Path) -> Option<Vec<String>> {
+    let git = |args: &[&str]| -> Option<Vec<String>> {
+        let output = Command::new("git")
+            .args(args)
+            .current_dir(dir)
+            .output()
+            .ok()?;
+        if !output.status.success() {
+            return None;
+        }
+        Some(
+            String::from_utf8_lossy(&output.stdout)
+                .split('\0')
+                .filter(|path| !path.is_empty())
+                .map(str::to_string)
+                .collect(),
+        )
+    };
+    let mut files = git(&["diff", "--name-only", "-z", "--relative", "--cached"])?;
+    files.extend(git(&["diff", "--name-only", "-z", "--relative"])?);
+    files.extend(git(&["ls-files", "-z", "--others", "--exclude-standard"])?);
+    files.sort();
+    files.dedup();
+    files.retain(|file| dir.join(file).is_file());
+    Some(files)
+}
+
+/// Parse the `files` parameter: a JSON array or a comma-separated list
+fn parse_file_list(value: &str) -> Vec<String> {
+    if let Ok(files) = serde_json::from_str::<Vec<String>>(value) {
+        return files;
+    }
+    value
+        .split(',')
+        .map(str::trim)
+        .filter(|file| !file.is_empty())
+        .map(str::to_string)
+        .collect()
+}
+
+/// Tool for running a repository's pre-commit hooks and reporting failing hooks
+pub struct RunPrecommitTool {
+    name: String,
+}
+
+impl RunPrecommitTool {
+    pub fn new() -> Self {
+        Self {
+            name: "run_precommit".to_string(),
+        }
+    }
+
+    fn format_failure(hook: &HookResult) -> String {
+        let mut notes = Vec::new();
+        if let Some(code) = hook.exit_code {
+            notes.push(format!("exit code {}", code));
+        }
+        if hook.files_modified {
+            notes.push("files modified".to_string());
+        }
+        let mut text = if notes.is_empty() {
+            format!("- {}", hook.name)
+        } else {
+            format!("- {} ({})", hook.name, notes.join(", "))
+        };
+        let lines: Vec<&str> = hook.output.lines().collect();
+        for line in lines.iter().take(HOOK_OUTPUT_LINES) {
+            text.push_str("\n    ");
+            text.push_str(line);
+        }
+        if lines.len() > HOOK_OUTPUT_LINES {
+            text.push_str(&format!(
+                "\n    ... {} more lines",
+                lines.len() - HOOK_OUTPUT_LINES
+            ));
+        }
+        text
+    }
+}
+
+impl Default for RunPrecommitTool {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl Tool for RunPrecommitTool {
+    fn name(&self) -> &str {
+        &self.name
+    }
+
+    fn description(&self) -> &str {
+        "Run the repository's pre-commit hooks (pre-commit, husky or lefthook, auto-detected) on the changed files and report each failing hook with its output"
+    }
+
+    fn signature(&self) -> &str {
+        "run_precommit [path] [--manager=<pre-commit|husky|lefthook>] [--files=<a,b>] [--all_files=true]"
+    }
+
+    fn category(&self) -> ToolCategory {
+        ToolCategory::Execution
+    }
+
+    fn danger_level(&self) -> DangerLevel {
+        DangerLevel::ExecutesCode
+    }
+
+    fn validate_args(&self, args: &ToolArgs) -> Result<(), ToolError> {
+        if let Some(manager) = args.get_named_arg("manager") {
+            if HookManager::from_name(manager).is_none() {
+                return Err(ToolError::InvalidArgs {
+                    message: format!(
+                        "Unknown hook manager '{}'. Expected one of: pre-commit, husky, lefthook",
+                        manager
+                    ),
+                });
+            }
+        }
+        Ok(())
+    }
+
+    fn execute(&mut self, args: &ToolArgs, state: &Arc<Mutex<ToolState>>) -> Result<ToolResult> {
+        let dir = match args.get_named_arg("path").or_else(|| args.get_arg(0)) {
+            Some(path) => session_path(state, path),
+            None => {
+                let state_guard = state
+                    .lock()
+                    .map_err(|e| anyhow::anyhow!("Failed to lock state: {}", e))?;
+                state_guard.working_directory.clone()
+            }
+        };
+        if !dir.is_dir() {
+            return Ok(crate::utils::suggest::path_not_found(
+                format!("Directory {} not found", dir.display()),
+                &dir,
+            ));
+        }
+
+        let manager = match args
+            .get_named_arg("manager")
+            .and_then(|m| HookManager::from_name(m))
+            .or_else(|| HookManager::detect(&dir))
+        {
+            Some(manager) => manager,
+            None => {
+                return Ok(ToolResult::error(format!(
+                    "No pre-commit configuration found in {} (looked for .pre-commit-config.yaml, lefthook.yml and .husky/pre-commit)",
+                    dir.display()
+                )))
+            }
+        };
+        let all_files = args.get_named_arg("all_files").map(|v| v.as_str()) == Some("true");
+        let files = match args.get_named_arg("files") {
+            Some(files) => parse_file_list(files),
+            None => changed_files(&dir).unwrap_or_default(),
+        };
+        if files.is_empty() && !all_files && manager != HookManager::Husky {
+            return Ok(ToolResult::success_with_data(
+                format!(
+                    "No changed files to check in {}. Pass --all_files=true to run the hooks on every file",
+                    dir.display()
+                ),
+                serde_json::json!({
+                    "manager": manager,
+                    "directory": dir.display().to_string(),
+                    "files": files,
+                    "passed": true,
+                    "hooks": [],
+                    "failures": [],
+                }),
+            ));
+        }
+        let command = manager.command(&files, all_files);
+
+        if let Ok(mut state_guard) = state.lock() {
+            state_guard.push_history(format!("run_precommit: {}", command));
+        }
+        // Formatting hooks rewrite files; open windows on them are reloaded afterwards
+        let open_before = state
+            .lock()
+            .map(|state| state.open_file_hashes())
+            .unwrap_or_default();
+
+        let output = match Command::new("sh")
+            .arg("-c")
+            .arg(&command)
+            .current_dir(&dir)
+            .env("NO_COLOR", "1")
+            .output()
+        {
+            Ok(output) => output,
+            Err(e) => {
+                return Ok(ToolResult::error(format!(
+                    "Failed to run '{}': {}",
+                    command, e
+                )))
+            }
+        };
+        let exit_code = output.status.code();
+        let combined = format!(
+            "{}\n{}",
+            clean_output(&output.stdout, false),
+            clean_output(&output.stderr, false)
+        );
+        if exit_code == Some(127) {
+            return Ok(ToolResult::error(format!(
+                "{} is not installed or not on PATH ({}):\n{}",
+                manager.as_str(),
+                command,
+                combined.trim()
+            )));
+        }
+
+        let hooks = parse_hook_results(manager, &combined, exit_code);
+        let failures: Vec<&HookResult> = hooks
+            .iter()
+            .filter(|hook| hook.status == HookStatus::Failed)
+            .collect();
+        let passed = output.status.success();
+        let changed = state
+            .lock()
+            .map(|mut state| state.refresh_changed_files(&open_before))
+            .unwrap_or_default();
+
+        let mut data = serde_json::json!({
+            "manager": manager,
+            "command": command,
+            "directory": dir.display().to_string(),
+            "files": files,
+            "exit_code": exit_code,
+            "passed": passed,
+            "hooks": hooks,
+            "failures": failures,
+        });
+        if !changed.is_empty() {
+            data["changed_open_files"] = serde_json::json!(changed
+                .iter()
+                .map(|(path, kind)| serde_json::json!({"path": path, "change": kind}))
+                .collect::<Vec<_>>());
+        }
+
+        if passed {
+            return Ok(ToolResult::success_with_data(
+                format!(
+                    "All {} pre-commit hooks passed ({})",
+                    manager.as_str(),
+                    command
+                ),
+                data,
+            ));
+        }
+
+        let message = if failures.is_empty() {
+            let lines: Vec<&str> = combined.trim_end().lines().collect();
+            let tail = &lines[lines.len().saturating_sub(OUTPUT_TAIL_LINES)..];
+            format!(
+                "Pre-commit hooks failed ({}, exit code {}) but no failing hook could be parsed. Last {} lines of output:\n{}",
+                command,
+                exit_code.unwrap_or(-1),
+                tail.len(),
+                tail.join("\n")
+            )
+        } else {
+            let mut message = format!(
+                "{} of {} pre-commit hook(s) failed ({}):\n{}",
+                failures.len(),
+                hooks.len(),
+                command,
+                failures
+                    .iter()
+                    .map(|hook| Self::format_failure(hook))
+                    .collect::<Vec<_>>()
+                    .join("\n")
+            );
+            if failures.iter().any(|hook| hook.files_modified) {
+                message.push_str("\n\nHooks that modified files usually pass when run again");
+            }
+            message
+        };
+
+        Ok(ToolResult::error_with_data(message, data))
+    }
+
+    fn get_parameters_schema(&self) -> serde_json::Value {
+        serde_json::json!({
+            "type": "object",
+            "properties": {
+                "path": {
+                    "type": "string",
+                    "description": "Repository directory to run the hooks in (defaults to the working directory)"
+                },
+                "manager": {
+                    "type": "string",
+                    "enum": ["pre-commit", "husky", "lefthook"],
+                    "description": "Hook manager; auto-detected from its configuration when omitted"
+                },
+                "files": {
+                    "type": ["string", "array"],
+                    "items": {"type": "string"},
+                    "description": "Files to check, relative to path (defaults to the files with uncommitted changes)"
+                },
+                "all_files": {
+                    "type": "boolean",
+                    "description": "Run the hooks on every file instead of the changed ones",
+                    "default": false
+                }
+            },
+            "required": []
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::fs;
+    use tempfile::TempDir;
+
+    #[test]
+    fn test_detect_and_command() {
+        let temp_dir = TempDir::new().unwrap();
+        assert_eq!(HookManager::detect(temp_dir.path()), None);
+
+        fs::create_dir(temp_dir.path().join(".husky")).unwrap();
+        fs::write(
+            temp_dir.path().join(".husky/pre-commit"),
+            "npx lint-staged\n",
+        )
+        .unwrap();
+        assert_eq!(
+            HookManager::detect(temp_dir.path()),
+            Some(HookManager::Husky)
+        );
+        fs::write(
+            temp_dir.path().join(".pre-commit-config.yaml"),
+            "repos: []\n",
+        )
+        .unwrap();
+        assert_eq!(
+            HookManager::detect(temp_dir.path()),
+            Some(HookManager::PreCommit)
+        );
+
+        let files = vec!["src/a.py".to_string(), "it's.py".to_string()];
+        assert_eq!(
+            HookManager::PreCommit.command(&files, false),
+            r"pre-commit run --color never --files 'src/a.py' 'it'\''s.py'"
+        );
+        assert_eq!(
+            HookManager::Lefthook.command(&files[..1], false),
+            "lefthook run pre-commit --file='src/a.py'"
+        );
+        assert_eq!(parse_file_list(r#"["a.py", "b.py"]"#), ["a.py", "b.py"]);
+        assert_eq!(parse_file_list("a.py, b.py"), ["a.py", "b.py"]);
+    }
+
+    #[test]
+    fn test_parse_pre_commit_output() {
+        let output = "\
+trim trailing whitespace.................................................Passed
+check yaml...........................................(no files to check)Skipped
+black....................................................................Failed
+- hook id: black
+- files were modified by this hook
+
+reformatted src/app.py
+
+All done! 1 file reformatted.
+
+flake8...................................................................Failed
+- hook id: flake8
+- exit code: 1
+
+src/app.py:3:1: F401 'os' imported but unused
+";
+        let hooks = parse_hook_results(HookManager::PreCommit, output, Some(1));
+        let statuses: Vec<HookStatus> = hooks.iter().map(|hook| hook.status).collect();
+        assert_eq!(
+            statuses,
+            [
+                HookStatus::Passed,
+                HookStatus::Skipped,
+                HookStatus::Failed,
+                HookStatus::Failed
+            ]
+        );
+        assert_eq!(hooks[0].name, "trim trailing whitespace");
+        assert!(hooks[2].files_modified);
+        assert_eq!(
+            hooks[2].output,
+            "reformatted src/app.py\n\nAll done! 1 file reformatted."
+        );
+        assert_eq!(hooks[3].id.as_deref(), Some("flake8"));
+        assert_eq!(hooks[3].exit_code, Some(1));
+        assert_eq!(
+            hooks[3].output,
+            "src/app.py:3:1: F401 'os' imported but unused"
+        );
+    }
+
+    #[test]
+    fn test_parse_lefthook_output() {
+        let output = "\
+╭──────────────────────────────────────╮
+│ 🥊 lefthook v1.5.0  hook: pre-commit │
+╰──────────────────────────────────────╯
+┃  eslint ❯
+
+src/app.js
+  3:7  error  'x' is assigned a value but never used  no-unused-vars
+
+┃  typecheck ❯
+
+────────────────────────────────────
+summary: (done in 1.20 seconds)
+✔️  typecheck
+🥊  eslint
+";
+        let hooks = parse_hook_results(HookManager::Lefthook, output, Some(1));
+        assert_eq!(hooks.len(), 2);
+        assert_eq!(hooks[0].name, "typecheck");
+        assert_eq!(hooks[0].status, HookStatus::Passed);
+        assert_eq!(hooks[1].name, "eslint");
+        assert_eq!(hooks[1].status, HookStatus::Failed);
+        assert_eq!(
+            hooks[1].output,
+            "src/app.js\n  3:7  error  'x' is assigned a value but never used  no-unused-vars"
+        );
+    }
+
+    #[test]
+    fn test_run_precommit_husky() {
+        let temp_dir = TempDir::new().unwrap();
+        fs::create_dir(temp_dir.path().join(".husky")).unwrap();
+        fs::write(
+            temp_dir.path().join(".husky/pre-commit"),
+            "echo 'src/app.ts: missing semicolon'\nexit 1\n",
+        )
+        .unwrap();
+
+        let mut tool = RunPrecommitTool::new();
+        let state = Arc::new(Mutex::new(ToolState::new()));
+        let args = ToolArgs::from_args(&[temp_dir.path().to_str().unwrap()]);
+        let result = tool.execute(&args, &state).unwrap();
+
+        assert!(!result.success);
+        assert!(result
+            .message
+            .contains("- pre-commit (exit code 1)\n    src/app.ts: missing semicolon"));
+        let data = result.data.unwrap();
+        assert_eq!(data["manager"], "husky");
+        assert_eq!(data["failures"][0]["status"], "failed");
+        assert_eq!(data["passed"], false);
+
+        // Nothing changed, nothing to check
+        fs::remove_dir_all(temp_dir.path().join(".husky")).unwrap();
+        fs::write(
+            temp_dir.path().join(".pre-commit-config.yaml"),
+            "repos: []\n",
+        )
+        .unwrap();
+        let result = tool.execute(&args, &state).unwrap();
+        assert!(result.success);
+        assert!(result.message.starts_with("No changed files to check"));
+    }
+}
@@ -0,0 +1,664 @@
+//! run_tests tool: detect the project's test runner, run it and report structured failures
+
+use super::output::clean_output;
+use super::test_parser::{parse_test_failures, TestFailure, TestFramework};
+use crate::core::{DangerLevel, Tool, ToolArgs, ToolCategory, ToolError, ToolResult};
+use crate::state::{session_path, ToolState};
+use crate::utils::workspace::{resolve_package, WorkspaceKind};
+use anyhow::Result;
+use serde::Serialize;
+use std::collections::BTreeSet;
+use std::fs;
+use std::path::Path;
+use std::process::Command;
+use std::sync::{Arc, Mutex};
+
+/// Number of trailing output lines included when no structured failure could be parsed
+const OUTPUT_TAIL_LINES: usize = 40;
+
+/// Most runs a single `repeat` call may ask for
+const MAX_REPEAT: usize = 50;
+
+/// Outcome of one run of the test command
+pub(super) struct TestRun {
+    pub exit_code: Option<i32>,
+    pub passed: bool,
+    pub failures: Vec<TestFailure>,
+    pub output: String,
+}
+
+/// A test that failed in at least one of several runs of the same command
+#[derive(Debug, Clone, Serialize)]
+struct RepeatedFailure {
+    /// The failure as reported by the first run it failed in
+    #[serde(flatten)]
+    failure: TestFailure,
+    failed_runs: usize,
+    /// Passed in some runs and failed in others
+    flaky: bool,
+}
+
+/// Tool for running a project's test suite and parsing its failures
+pub struct RunTestsTool {
+    name: String,
+}
+
+impl RunTestsTool {
+    pub fn new() -> Self {
+        Self {
+            name: "run_tests".to_string(),
+        }
+    }
+
+    /// Detect the test framework used by the project in `dir`
+    pub fn detect_framework(dir: &Path) -> Option<TestFramework> {
+        if dir.join("Cargo.toml").exists() {
+            return Some(TestFramework::Cargo);
+        }
+        if dir.join("go.mod").exists() {
+            return Some(TestFramework::GoTest);
+        }
+        if let Ok(package_json) = fs::read_to_string(dir.join("package.json")) {
+            if package_json.contains("jest") {
+                return Some(TestFramework::Jest);
+            }
+        }
+        let python_markers = [
+            "pytest.ini",
+            "conftest.py",
+            "pyproject.toml",
+            "setup.cfg",
+            "tox.ini",
+        ];
+        if python_markers.iter().any(|m| dir.join(m).exists()) {
+            return Some(TestFramework::Pytest);
+        }
+        None
+    }
+
+    /// Default command for a framework, optionally restricted by a test name filter
+    pub fn default_command(framework: TestFramework, filter: Option<&str>) -> String {
+        let filter = filter.map(shell_quote);
+        match (framework, filter) {
+            (TestFramework::Cargo, Some(f)) => format!("cargo test {}", f),
+            (TestFramework::Cargo, None) => "cargo test".to_string(),
+            (TestFramework::Pytest, Some(f)) => format!("python -m pytest -k {}", f),
+            (TestFramework::Pytest, None) => "python -m pytest".to_string(),
+            (TestFramework::Jest, Some(f)) => format!("npx jest --colors=false -t {}", f),
+            (TestFramework::Jest, None) => "npx jest --colors=false".to_string(),
+            (TestFramework::GoTest, Some(f)) => format!("go test ./... -run {}", f),
+            (TestFramework::GoTest, None) => "go test ./...".to_string(),
+        }
+    }
+
+    fn repeat(args: &ToolArgs) -> Result<usize, ToolError> {
+        match args.get_named_arg("repeat") {
+            None => Ok(1),
+            Some(value) => match value.parse::<usize>() {
+                Ok(n) if (1..=MAX_REPEAT).contains(&n) => Ok(n),
+                _ => Err(ToolError::InvalidArgs {
+                    message: format!(
+                        "Invalid repeat '{}': expected a number from 1 to {}",
+                        value, MAX_REPEAT
+                    ),
+                }),
+            },
+        }
+    }
+
+    pub(super) fn run_once(
+        command: &str,
+        dir: &Path,
+        framework: Option<TestFramework>,
+    ) -> std::io::Result<TestRun> {
+        let output = Command::new("sh")
+            .arg("-c")
+            .arg(command)
+            .current_dir(dir)
+            .output()?;
+        let stdout = clean_output(&output.stdout, false);
+        let stderr = clean_output(&output.stderr, false);
+        let combined = format!("{}\n{}", stdout, stderr);
+        Ok(TestRun {
+            exit_code: output.status.code(),
+            passed: output.status.success(),
+            failures: parse_test_failures(&combined, framework),
+            output: combined,
+        })
+    }
+
+    /// Failing tests across `runs`, in the order they first failed
+    fn repeated_failures(runs: &[TestRun]) -> Vec<RepeatedFailure> {
+        let mut repeated: Vec<RepeatedFailure> = Vec::new();
+        for run in runs {
+            let mut seen = BTreeSet::new();
+            for failure in &run.failures {
+                // A test reported twice in one run still failed in one run
+                if !seen.insert(failure.name.as_str()) {
+                    continue;
+                }
+                match repeated.iter_mut().find(|r| r.failure.name == failure.name) {
+                    Some(existing) => existing.failed_runs += 1,
+                    None => repeated.push(RepeatedFailure {
+                        failure: failure.clone(),
+                        failed_runs: 1,
+                        flaky: false,
+                    }),
+                }
+            }
+        }
+        for failure in &mut repeated {
+            failure.flaky = failure.failed_runs < runs.len();
+        }
+        repeated
+    }
+
+    pub(super) fn output_tail(output: &str) -> (usize, String) {
+        let lines: Vec<&str> = output.trim_end().lines().collect();
+        let tail = &lines[lines.len().saturating_sub(OUTPUT_TAIL_LINES)..];
+        (tail.len(), tail.join("\n"))
+    }
+
+    /// Report several runs of the same command, separating tests that failed every run
+    /// from flaky ones
+    fn report_repeated(
+        runs: &[TestRun],
+        command: &str,
+        dir: &Path,
+        framework: Option<TestFramework>,
+        package: Option<&str>,
+    ) -> ToolResult {
+        let failed_runs = runs.iter().filter(|run| !run.passed).count();
+        let failures = Self::repeated_failures(runs);
+        let (flaky, consistent): (Vec<_>, Vec<_>) = failures.iter().partition(|f| f.flaky);
+        let names = |failures: &[&RepeatedFailure]| {
+            failures
+                .iter()
+                .map(|f| f.failure.name.clone())
+                .collect::<Vec<_>>()
+        };
+        let last_failed = runs.iter().rev().find(|run| !run.passed);
+        let data = serde_json::json!({
+            "framework": framework.map(|f| f.as_str()),
+            "command": command,
+            "directory": dir.display().to_string(),
+            "package": package,
+            "exit_code": last_failed.unwrap_or(&runs[runs.len() - 1]).exit_code,
+            "passed": failed_runs == 0,
+            "runs": runs.len(),
+            "failed_runs": failed_runs,
+            "failures": failures,
+            "consistent": names(&consistent),
+            "flaky": names(&flaky),
+            "run_results": runs
+                .iter()
+                .map(|run| serde_json::json!({
+                    "passed": run.passed,
+                    "exit_code": run.exit_code,
+                    "failed_tests": run.failures.iter().map(|f| &f.name).collect::<Vec<_>>(),
+                }))
+                .collect::<Vec<_>>(),
+        });
+
+        if failed_runs == 0 {
+            return ToolResult::success_with_data(
+                format!(
+                    "All tests passed in {} of {} runs ({})",
+                    runs.len(),
+                    runs.len(),
+                    command
+                ),
+                data,
+            );
+        }
+
+        let mut message = format!(
+            "{} of {} runs failed ({})",
+            failed_runs,
+            runs.len(),
+            command
+        );
+        if !consistent.is_empty() {
+            let listed: Vec<TestFailure> = consistent.iter().map(|f| f.failure.clone()).collect();
+            message.push_str(&format!(
+                "\n\nFailed every run ({}):\n{}",
+                consistent.len(),
+                Self::format_failures(&listed)
+            ));
+        }
+        if !flaky.is_empty() {
+            let listed: Vec<TestFailure> = flaky
+                .iter()
+                .map(|f| TestFailure {
+                    name: format!(
+                        "{} [failed {}/{} runs]",
+                        f.failure.name,
+                        f.failed_runs,
+                        runs.len()
+                    ),
+                    ..f.failure.clone()
+                })
+                .collect();
+            message.push_str(&format!(
+                "\n\nFlaky, failed in some runs only ({}):\n{}",
+                flaky.len(),
+                Self::format_failures(&listed)
+            ));
+        }
+        let unparsed = runs
+            .iter()
+            .filter(|run| !run.passed && run.failures.is_empty())
+            .count();
+        if unparsed > 0 {
+            message.push_str(&format!(
+                "\n\n{} failing run(s) reported no parseable failures",
+                unparsed
+            ));
+            if failures.is_empty() {
+                if let Some(run) = last_failed {
+                    let (tail_len, tail) = Self::output_tail(&run.output);
+                    message.push_str(&format!(
+                        ". Last {} lines of the last failing run:\n{}",
+                        tail_len, tail
+                    ));
+                }
+            }
+        }
+        if consistent.is_empty() && failed_runs < runs.len() {
+            message.push_str(
+                "\n\nNo test failed every run: these failures are intermittent and may not be caused by the code under test",
+            );
+        }
+        ToolResult::error_with_data(message, data)
+    }
+
+    pub(super) fn format_failures(failures: &[TestFailure]) -> String {
+        failures
+            .iter()
+            .map(|failure| {
+                let location = match (&failure.file, failure.line) {
+                    (Some(file), Some(line)) => format!(" ({}:{})", file, line),
+                    (Some(file), None) => format!(" ({})", file),
+                    _ => String::new(),
+                };
+                let message = failure.message.lines().next().unwrap_or("");
+                if message.is_empty() {
+                    format!("- {}{}", failure.name, location)
+                } else {
+                    format!("- {}{}: {}", failure.name, location, message)
+                }
+            })
+            .collect::<Vec<_>>()
+            .join("\n")
+    }
+}
+
+impl Default for RunTestsTool {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+/// Quote a value for safe interpolation into a `sh -c` command line
+pub(super) fn shell_quote(value: &str) -> String {
+    format!("'{}'", value.replace('\'', r"'\''"))
+}
+
+impl Tool for RunTestsTool {
+    fn name(&self) -> &str {
+        &self.name
+    }
+
+    fn description(&self) -> &str {
+        "Run the project's tests (cargo, pytest, jest or go test, auto-detected) and report failing tests with file, line and assertion message. With repeat, runs the tests several times and separates tests that fail every run from flaky ones"
+    }
+
+    fn signature(&self) -> &str {
+        "run_tests [path] [--package=<name>] [--framework=<cargo|pytest|jest|go>] [--filter=<name>] [--command=<cmd>] [--repeat=<n>]"
+    }
+
+    fn category(&self) -> ToolCategory {
+        ToolCategory::Execution
+    }
+
+    fn danger_level(&self) -> DangerLevel {
+        DangerLevel::ExecutesCode
+    }
+
+    fn validate_args(&self, args: &ToolArgs) -> Result<(), ToolError> {
+        if let Some(framework) = args.get_named_arg("framework") {
+            if TestFramework::from_name(framework).is_none() {
+                return Err(ToolError::InvalidArgs {
+                    message: format!(
+                        "Unknown test framework '{}'. Expected one of: cargo, pytest, jest, go",
+                        framework
+                    ),
+                });
+            }
+        }
+        Self::repeat(args)?;
+        Ok(())
+    }
+
+    fn execute(&mut self, args: &ToolArgs, state: &Arc<Mutex<ToolState>>) -> Result<ToolResult> {
+        let dir = match args.get_named_arg("path").or_else(|| args.get_arg(0)) {
+            Some(path) => session_path(state, path),
+            None => {
+                let state_guard = state
+                    .lock()
+                    .map_err(|e| anyhow::anyhow!("Failed to lock state: {}", e))?;
+                state_guard.working_directory.clone()
+            }
+        };
+        if !dir.is_dir() {
+            return Ok(crate::utils::suggest::path_not_found(
+                format!("Directory {} not found", dir.display()),
+                &dir,
+            ));
+        }
+
+        // Cargo packages are tested with `-p` from the workspace root, others in their
+        // own directory
+        let package = match args.get_named_arg("package") {
+            Some(package) => match resolve_package(&dir, package) {
+                Ok(resolved) => Some(resolved),
+                Err(result) => return Ok(result),
+            },
+            None => None,
+        };
+        let cargo_package = package
+            .as_ref()
+            .filter(|(_, member)| member.kind == WorkspaceKind::Cargo)
+            .map(|(_, member)| member.name.clone());
+        let dir = match &package {
+            Some((workspace, member)) => member.command_directory(&workspace.root),
+            None => dir,
+        };
+
+        let framework = args
+            .get_named_arg("framework")
+            .and_then(|f| TestFramework::from_name(f))
+            .or_else(|| Self::detect_framework(&dir));
+        let filter = args.get_named_arg("filter").map(|f| f.as_str());
+
+        let command = match (args.get_named_arg("command"), framework) {
+            (Some(command), _) => command.clone(),
+            (None, Some(TestFramework::Cargo)) if cargo_package.is_some() => {
+                let mut command = format!("cargo test -p {}", cargo_package.unwrap_or_default());
+                if let Some(filter) = filter {
+                    command.push_str(&format!(" {}", shell_quote(filter)));
+                }
+                command
+            }
+            (None, Some(framework)) => Self::default_command(framework, filter),
+            (None, None) => {
+                return Ok(ToolResult::error(format!(
+                    "Could not detect a test framework in {}. Pass --framework or --command",
+                    dir.display()
+                )))
+            }
+        };
+
+        if let Ok(mut state_guard) = state.lock() {
+            state_guard.push_history(format!("run_tests: {}", command));
+        }
+
+        let repeat = Self::repeat(args)?;
+        let mut runs = Vec::with_capacity(repeat);
+        for _ in 0..repeat {
+            match Self::run_once(&command, &dir, framework) {
+                Ok(run) => runs.push(run),
+                Err(e) => {
+                    return Ok(ToolResult::error(format!(
+                        "Failed to run '{}': {}",
+                        command, e
+                    )))
+                }
+            }
+        }
+        if repeat > 1 {
+            return Ok(Self::report_repeated(
+                &runs,
+                &command,
+                &dir,
+                framework,
+                package.as_ref().map(|(_, member)| member.name.as_str()),
+            ));
+        }
+        let run = &runs[0];
+
+        let data = serde_json::json!({
+            "framework": framework.map(|f| f.as_str()),
+            "command": command,
+            "directory": dir.display().to_string(),
+            "package": package.as_ref().map(|(_, member)| &member.name),
+            "exit_code": run.exit_code,
+            "passed": run.passed,
+            "failures": run.failures,
+        });
+
+        if run.passed {
+            return Ok(ToolResult::success_with_data(
+                format!("All tests passed ({})", command),
+                data,
+            ));
+        }
+
+        let message = if run.failures.is_empty() {
+            let (tail_len, tail) = Self::output_tail(&run.output);
+            format!(
+                "Tests failed ({}, exit code {}) but no failures could be parsed. Last {} lines of output:\n{}",
+                command,
+                run.exit_code.unwrap_or(-1),
+                tail_len,
+                tail
+            )
+        } else {
+            format!(
+                "{} failing test(s) ({}):\n{}",
+                run.failures.len(),
+                command,
+                Self::format_failures(&run.failures)
+            )
+        };
+
+        Ok(ToolResult::error_with_data(message, data))
+    }
+
+    fn get_parameters_schema(&self) -> serde_json::Value {
+        serde_json::json!({
+            "type": "object",
+            "properties": {
+                "path": {
+                    "type": "string",
+                    "description": "Project directory to run tests in (defaults to the working directory)"
+                },
+                "package": {
+                    "type": "string",
+                    "description": "Workspace package (name or directory) to test only; Cargo packages run as `cargo test -p <name>` from the workspace root"
+                },
+                "framework": {
+                    "type": "string",
+                    "enum": ["cargo", "pytest", "jest", "go"],
+                    "description": "Test framework; auto-detected from project files when omitted"
+                },
+                "filter": {
+                    "type": "string",
+                    "description": "Only run tests whose name matches this filter"
+                },
+                "command": {
+                    "type": "string",
+                    "description": "Custom test command; its output is still parsed for failures"
+                },
+                "repeat": {
+                    "type": "integer",
+                    "minimum": 1,
+                    "maximum": MAX_REPEAT,
+                    "description": "Run the tests this many times and report each failing test's pass/fail count, to tell flaky tests from real regressions (default 1). Combine with filter to repeat a single test"
+                }
+            },
+            "required": []
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use tempfile::TempDir;
+
+    #[test]
+    fn test_detect_framework() {
+        let temp_dir = TempDir::new().unwrap();
+        assert_eq!(RunTestsTool::detect_framework(temp_dir.path()), None);
+
+        fs::write(temp_dir.path().join("pytest.ini"), "[pytest]\n").unwrap();
+        assert_eq!(
+            RunTestsTool::detect_framework(temp_dir.path()),
+            Some(TestFramework::Pytest)
+        );
+
+        fs::write(temp_dir.path().join("Cargo.toml"), "[package]\n").unwrap();
+        assert_eq!(
+            RunTestsTool::detect_framework(temp_dir.path()),
+            Some(TestFramework::Cargo)
+        );
+    }
+
+    #[test]
+    fn test_run_tests_parses_custom_command_output() {
+        let temp_dir = TempDir::new().unwrap();
+        fs::write(
+            temp_dir.path().join("out.txt"),
+            "--- FAIL: TestAdd (0.00s)\n    add_test.go:7: want 4\nFAIL\n",
+        )
+        .unwrap();
+
+        let mut tool = RunTestsTool::new();
+        let state = Arc::new(Mutex::new(ToolState::new()));
+        let args = ToolArgs::from_args(&[
+            temp_dir.path().to_str().unwrap(),
+            "--framework=go",
+            "--command=cat out.txt; exit 1",
+        ]);
+        let result = tool.execute(&args, &state).unwrap();
+
+        assert!(!result.success);
+        assert!(result.message.contains("- TestAdd (add_test.go:7): want 4"));
+        let data = result.data.unwrap();
+        assert_eq!(data["failures"][0]["line"], 7);
+        assert_eq!(data["passed"], false);
+    }
+
+    #[test]
+    fn test_run_tests_package() {
+        let temp_dir = TempDir::new().unwrap();
+        let root = temp_dir.path();
+        fs::write(
+            root.join("pyproject.toml"),
+            "[tool.uv.workspace]\nmembers = [\"libs/*\"]\n",
+        )
+        .unwrap();
+        fs::create_dir_all(root.join("libs/core")).unwrap();
+        fs::write(
+            root.join("libs/core/pyproject.toml"),
+            "[project]\nname = \"core\"\n",
+        )
+        .unwrap();
+
+        let mut tool = RunTestsTool::new();
+        let state = Arc::new(Mutex::new(ToolState::new()));
+        let args = ToolArgs::from_args(&[
+            root.to_str().unwrap(),
+            "--package=core",
+            "--command=test -f pyproject.toml",
+        ]);
+        let result = tool.execute(&args, &state).unwrap();
+        assert!(result.success, "{}", result.message);
+        let data = result.data.unwrap();
+        assert_eq!(data["package"], "core");
+        assert!(data["directory"].as_str().unwrap().ends_with("libs/core"));
+
+        let args = ToolArgs::from_args(&[root.to_str().unwrap(), "--package=missing"]);
+        let result = tool.execute(&args, &state).unwrap();
+        assert!(result.message.starts_with("Unknown package 'missing'"));
+    }
+
+    #[test]
+    fn test_run_tests_repeat_separates_flaky_tests() {
+        let temp_dir = TempDir::new().unwrap();
+        let root = temp_dir.path();
+        fs::write(
+            root.join("both.txt"),
+            "--- FAIL: TestAdd (0.00s)\n    add_test.go:7: want 4\n--- FAIL: TestSub (0.00s)\n    sub_test.go:3: want 0\nFAIL\n",
+        )
+        .unwrap();
+        fs::write(
+            root.join("sub.txt"),
+            "--- FAIL: TestSub (0.00s)\n    sub_test.go:3: want 0\nFAIL\n",
+        )
+        .unwrap();
+        // Odd runs fail TestAdd and TestSub, even runs only TestSub
+        let alternate = |even: &str| {
+            format!(
+                "--command=n=$(cat count 2>/dev/null || echo 0); echo $((n + 1)) > count; if [ $((n % 2)) -eq 0 ]; then cat both.txt; exit 1; fi; {}",
+                even
+            )
+        };
+
+        let mut tool = RunTestsTool::new();
+        let state = Arc::new(Mutex::new(ToolState::new()));
+        let command = alternate("cat sub.txt; exit 1");
+        let args = ToolArgs::from_args(&[
+            root.to_str().unwrap(),
+            "--framework=go",
+            &command,
+            "--repeat=3",
+        ]);
+        let result = tool.execute(&args, &state).unwrap();
+        assert!(!result.success);
+        assert!(
+            result.message.starts_with("3 of 3 runs failed"),
+            "{}",
+            result.message
+        );
+        assert!(result
+            .message
+            .contains("Failed every run (1):\n- TestSub (sub_test.go:3): want 0"));
+        assert!(result
+            .message
+            .contains("- TestAdd [failed 2/3 runs] (add_test.go:7): want 4"));
+        let data = result.data.unwrap();
+        assert_eq!(data["consistent"], serde_json::json!(["TestSub"]));
+        assert_eq!(data["flaky"], serde_json::json!(["TestAdd"]));
+        assert_eq!(data["failures"][0]["failed_runs"], 2);
+        assert_eq!(
+            data["run_results"][1]["failed_tests"],
+            serde_json::json!(["TestSub"])
+        );
+
+        fs::remove_file(root.join("count")).unwrap();
+        let command = alternate("exit 0");
+        let args = ToolArgs::from_args(&[
+            root.to_str().unwrap(),
+            "--framework=go",
+            &command,
+            "--repeat=2",
+        ]);
+        let result = tool.execute(&args, &state).unwrap();
+        assert!(result.message.starts_with("1 of 2 runs failed"));
+        assert!(result.message.contains("No test failed every run"));
+        assert_eq!(result.data.unwrap()["passed"], false);
+
+        let args = ToolArgs::from_args(&[root.to_str().unwrap(), "--repeat=0"]);
+        assert!(tool.validate_args(&args).is_err());
+    }
+
+    #[test]
+    fn test_shell_quote_filter() {
+        assert_eq!(
+            RunTestsTool::default_command(TestFramework::Cargo, Some("it's")),
+            r"cargo test 'it'\''s'"
+        );
+    }
+}
@@ -0,0 +1,507 @@
+//! update_snapshots tool: accept new snapshot output and report what changed
+//!
+//! Supports insta (`cargo insta test --accept`, or `INSTA_UPDATE=always cargo test` when
+//! cargo-insta is not installed) and jest (`jest -u`). Snapshot files are read before and
+//! after the run, so the report lists exactly the snapshots the update wrote, created or
+//! removed, with a diff of each.
+
+use super::output::clean_output;
+use super::run_tests::shell_quote;
+use crate::core::{DangerLevel, Tool, ToolArgs, ToolCategory, ToolError, ToolResult};
+use crate::editing::diff::DiffPreview;
+use crate::search::ConfigurableFilter;
+use crate::state::{session_path, ToolState};
+use anyhow::Result;
+use serde::Serialize;
+use std::collections::BTreeMap;
+use std::fs;
+use std::path::{Path, PathBuf};
+use std::process::Command;
+use std::sync::{Arc, Mutex};
+use walkdir::WalkDir;
+
+/// Number of trailing output lines included when the update command fails
+const OUTPUT_TAIL_LINES: usize = 40;
+
+/// Diff lines shown in the message across all files; the data holds every diff
+const MAX_MESSAGE_DIFF_LINES: usize = 400;
+
+/// A snapshot testing framework
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "snake_case")]
+pub enum SnapshotFramework {
+    Insta,
+    Jest,
+}
+
+impl SnapshotFramework {
+    /// Parse a framework name as accepted by the `framework` tool parameter
+    pub fn from_name(name: &str) -> Option<Self> {
+        match name.to_lowercase().as_str() {
+            "insta" | "rust" | "cargo" => Some(Self::Insta),
+            "jest" | "javascript" | "typescript" => Some(Self::Jest),
+            _ => None,
+        }
+    }
+
+    pub fn as_str(self) -> &'static str {
+        match self {
+            Self::Insta => "insta",
+            Self::Jest => "jest",
+        }
+    }
+
+    /// Whether `path` holds snapshots of this framework: snapshot files, pending insta
+    /// snapshots, and sources with inline snapshots
+    fn is_snapshot_file(self, path: &Path) -> bool {
+        let name = path
+            .file_name()
+            .map(|n| n.to_string_lossy())
+            .unwrap_or_default();
+        let parent = path
+            .parent()
+            .and_then(|p| p.file_name())
+            .map(|n| n.to_string_lossy())
+            .unwrap_or_default();
+        match self {
+            Self::Insta => {
+                (parent == "snapshots" && (name.ends_with(".snap") || name.ends_with(".snap.new")))
+                    || name.ends_with(".pending-snap")
+            }
+            Self::Jest => parent == "__snapshots__" && name.ends_with(".snap"),
+        }
+    }
+
+    /// Marker of an inline snapshot in a source file
+    fn inline_marker(self, path: &Path) -> Option<&'static str> {
+        let extension = path.extension()?.to_str()?;
+        match (self, extension) {
+            (Self::Insta, "rs") => Some("snapshot!("),
+            (Self::Jest, "js" | "jsx" | "ts" | "tsx" | "mjs" | "cjs") => {
+                Some("toMatchInlineSnapshot(")
+            }
+            _ => None,
+        }
+    }
+}
+
+/// Detect the snapshot framework used in `dir` from its manifests
+pub fn detect_snapshot_framework(dir: &Path) -> Option<SnapshotFramework> {
+    let mentions = |file: &str, needle: &str| {
+        fs::read_to_string(dir.join(file)).is_ok_and(|content| content.contains(needle))
+    };
+    if mentions("Cargo.toml", "insta") {
+        return Some(SnapshotFramework::Insta);
+    }
+    if mentions("package.json", "jest") {
+        return Some(SnapshotFramework::Jest);
+    }
+    // Workspace members declare insta in their own manifests; their snapshots show it
+    if dir.join("Cargo.toml").exists() && !snapshot_files(dir, SnapshotFramework::Insta).is_empty()
+    {
+        return Some(SnapshotFramework::Insta);
+    }
+    None
+}
+
+/// Every snapshot file of `framework` under `dir`, with its content
+pub fn snapshot_files(dir: &Path, framework: SnapshotFramework) -> BTreeMap<PathBuf, String> {
+    let filter = ConfigurableFilter::new(None);
+    WalkDir::new(dir)
+        .follow_links(false)
+        .into_iter()
+        .filter_entry(|e| {
+            e.path() == dir
+                || !e.file_type().is_dir()
+                || filter.should_include_path(e.path().strip_prefix(dir).unwrap_or(e.path()))
+        })
+        .filter_map(|entry| entry.ok())
+        .filter(|entry| entry.file_type().is_file())
+        .filter_map(|entry| {
+            let path = entry.path();
+            let snapshot = framework.is_snapshot_file(path);
+            if !snapshot && framework.inline_marker(path).is_none() {
+                return None;
+            }
+            let content = fs::read_to_string(path).ok()?;
+            let inline = framework
+                .inline_marker(path)
+                .is_some_and(|marker| content.contains(marker));
+            (snapshot || inline).then(|| (path.to_path_buf(), content))
+        })
+        .collect()
+}
+
+/// How a snapshot file changed during the update
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "snake_case")]
+pub enum SnapshotChange {
+    Added,
+    Updated,
+    Removed,
+}
+
+impl SnapshotChange {
+    fn code(self) -> char {
+        match self {
+            Self::Added => 'A',
+            Self::Updated => 'M',
+            Self::Removed => 'D',
+        }
+    }
+}
+
+/// A snapshot file written, created or removed by the update
+#[derive(Debug, Clone, Serialize)]
+pub struct ChangedSnapshot {
+    pub path: PathBuf,
+    pub change: SnapshotChange,
+    pub diff: DiffPreview,
+}
+
+/// Compare snapshot files before and after an update; paths are made relative to `dir`
+pub fn changed_snapshots(
+    dir: &Path,
+    before: &BTreeMap<PathBuf, String>,
+    after: &BTreeMap<PathBuf, String>,
+) -> Vec<ChangedSnapshot> {
+    let mut paths: Vec<&PathBuf> = before.keys().chain(after.keys()).collect();
+    paths.sort();
+    paths.dedup();
+    paths
+        .into_iter()
+        .filter_map(|path| {
+            let old = before.get(path);
+            let new = after.get(path);
+            let change = match (old, new) {
+                (None, Some(_)) => SnapshotChange::Added,
+                (Some(_), None) => SnapshotChange::Removed,
+                (Some(old), Some(new)) if old != new => SnapshotChange::Updated,
+                _ => return None,
+            };
+            let relative = path.strip_prefix(dir).unwrap_or(path);
+            let diff = DiffPreview::new(
+                old.map_or("", String::as_str),
+                new.map_or("", String::as_str),
+                &relative.to_string_lossy(),
+            );
+            Some(ChangedSnapshot {
+                path: relative.to_path_buf(),
+                change,
+                diff,
+            })
+        })
+        .collect()
+}
+
+/// Tool for accepting updated snapshots and reporting the snapshot files that changed
+pub struct UpdateSnapshotsTool {
+    name: String,
+}
+
+impl UpdateSnapshotsTool {
+    pub fn new() -> Self {
+        Self {
+            name: "update_snapshots".to_string(),
+        }
+    }
+
+    /// Command accepting new snapshots, optionally only for tests matching `filter`
+    pub fn default_command(framework: SnapshotFramework, filter: Option<&str>) -> String {
+        let filter = filter.map(shell_quote);
+        match framework {
+            SnapshotFramework::Insta => {
+                let cargo_insta = Command::new("cargo")
+                    .args(["insta", "--version"])
+                    .output()
+                    .is_ok_and(|output| output.status.success());
+                match (cargo_insta, filter) {
+                    (true, Some(f)) => format!("cargo insta test --accept -- {}", f),
+                    (true, None) => "cargo insta test --accept".to_string(),
+                    (false, Some(f)) => format!("INSTA_UPDATE=always cargo test {}", f),
+                    (false, None) => "INSTA_UPDATE=always cargo test".to_string(),
+                }
+            }
+            SnapshotFramework::Jest => match filter {
+                Some(f) => format!("npx jest --ci=false -u --colors=false -t {}", f),
+                None => "npx jest --ci=false -u --colors=false".to_string(),
+            },
+        }
+    }
+}
+
+impl Default for UpdateSnapshotsTool {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl Tool for UpdateSnapshotsTool {
+    fn name(&self) -> &str {
+        &self.name
+    }
+
+    fn description(&self) -> &str {
+        "Accept updated snapshots (insta or jest, auto-detected) by running the framework's update command, then report which snapshot files were updated, added or removed with a diff of each"
+    }
+
+    fn signature(&self) -> &str {
+        "update_snapshots [path] [--framework=<insta|jest>] [--filter=<name>] [--command=<cmd>]"
+    }
+
+    fn category(&self) -> ToolCategory {
+        ToolCategory::Execution
+    }
+
+    fn danger_level(&self) -> DangerLevel {
+        DangerLevel::ExecutesCode
+    }
+
+    fn validate_args(&self, args: &ToolArgs) -> Result<(), ToolError> {
+        if let Some(framework) = args.get_named_arg("framework") {
+            if SnapshotFramework::from_name(framework).is_none() {
+                return Err(ToolError::InvalidArgs {
+                    message: format!(
+                        "Unknown snapshot framework '{}'. Expected one of: insta, jest",
+                        framework
+                    ),
+                });
+            }
+        }
+        Ok(())
+    }
+
+    fn execute(&mut self, args: &ToolArgs, state: &Arc<Mutex<ToolState>>) -> Result<ToolResult> {
+        let dir = match args.get_named_arg("path").or_else(|| args.get_arg(0)) {
+            Some(path) => session_path(state, path),
+            None => {
+                let state_guard = state
+                    .lock()
+                    .map_err(|e| anyhow::anyhow!("Failed to lock state: {}", e))?;
+                state_guard.working_directory.clone()
+            }
+        };
+        if !dir.is_dir() {
+            return Ok(crate::utils::suggest::path_not_found(
+                format!("Directory {} not found", dir.display()),
+                &dir,
+            ));
+        }
+
+        let framework = match args
+            .get_named_arg("framework")
+            .and_then(|f| SnapshotFramework::from_name(f))
+            .or_else(|| detect_snapshot_framework(&dir))
+        {
+            Some(framework) => framework,
+            None => {
+                return Ok(ToolResult::error(format!(
+                    "Could not detect insta or jest in {}. Pass --framework",
+                    dir.display()
+                )))
+            }
+        };
+        let filter = args.get_named_arg("filter").map(|f| f.as_str());
+        let command = match args.get_named_arg("command") {
+            Some(command) => command.clone(),
+            None => Self::default_command(framework, filter),
+        };
+
+        if let Ok(mut state_guard) = state.lock() {
+            state_guard.push_history(format!("update_snapshots: {}", command));
+        }
+
+        let before = snapshot_files(&dir, framework);
+        let output = match Command::new("sh")
+            .arg("-c")
+            .arg(&command)
+            .current_dir(&dir)
+            .output()
+        {
+            Ok(output) => output,
+            Err(e) => {
+                return Ok(ToolResult::error(format!(
+                    "Failed to run '{}': {}",
+                    command, e
+                )))
+            }
+        };
+        let after = snapshot_files(&dir, framework);
+        let changed = changed_snapshots(&dir, &before, &after);
+
+        let data = serde_json::json!({
+            "framework": framework,
+            "command": command,
+            "directory": dir.display().to_string(),
+            "exit_code": output.status.code(),
+            "changed": changed,
+        });
+
+        let mut message = match changed.len() {
+            0 => format!("No snapshot files changed ({})", command),
+            n => format!("{} snapshot file(s) changed ({}):", n, command),
+        };
+        for snapshot in &changed {
+            message.push_str(&format!(
+                "\n  {} {} (+{} -{})",
+                snapshot.change.code(),
+                snapshot.path.display(),
+                snapshot.diff.additions,
+                snapshot.diff.deletions
+            ));
+        }
+        let mut shown = 0;
+        for snapshot in &changed {
+            let lines = snapshot.diff.patch.lines().count();
+            if shown + lines > MAX_MESSAGE_DIFF_LINES {
+                message.push_str("\n\n... more diffs in the result data");
+                break;
+            }
+            message.push_str(&format!("\n\n{}", snapshot.diff.patch.trim_end()));
+            shown += lines;
+        }
+
+        if output.status.success() {
+            return Ok(ToolResult::success_with_data(message, data));
+        }
+        // Tests failing for reasons other than snapshots still fail the update
+        let stdout = clean_output(&output.stdout, false);
+        let stderr = clean_output(&output.stderr, false);
+        let combined = format!("{}\n{}", stdout, stderr);
+        let lines: Vec<&str> = combined.trim_end().lines().collect();
+        let tail = &lines[lines.len().saturating_sub(OUTPUT_TAIL_LINES)..];
+        Ok(ToolResult::error_with_data(
+            format!(
+                "Snapshot update command failed (exit code {}). {}\n\nLast {} lines of output:\n{}",
+                output.status.code().unwrap_or(-1),
+                message,
+                tail.len(),
+                tail.join("\n")
+            ),
+            data,
+        ))
+    }
+
+    fn get_parameters_schema(&self) -> serde_json::Value {
+        serde_json::json!({
+            "type": "object",
+            "properties": {
+                "path": {
+                    "type": "string",
+                    "description": "Project directory (defaults to the working directory)"
+                },
+                "framework": {
+                    "type": "string",
+                    "enum": ["insta", "jest"],
+                    "description": "Snapshot framework; auto-detected from Cargo.toml or package.json when omitted"
+                },
+                "filter": {
+                    "type": "string",
+                    "description": "Only update snapshots of tests whose name matches this filter"
+                },
+                "command": {
+                    "type": "string",
+                    "description": "Custom update command; snapshot changes are still reported"
+                }
+            },
+            "required": []
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use tempfile::TempDir;
+
+    #[test]
+    fn test_detect_and_collect_snapshots() {
+        let temp_dir = TempDir::new().unwrap();
+        let dir = temp_dir.path();
+        assert_eq!(detect_snapshot_framework(dir), None);
+
+        fs::write(
+            dir.join("package.json"),
+            r#"{"devDependencies":{"jest":"29"}}"#,
+        )
+        .unwrap();
+        assert_eq!(
+            detect_snapshot_framework(dir),
+            Some(SnapshotFramework::Jest)
+        );
+        fs::create_dir_all(dir.join("src/__snapshots__")).unwrap();
+        fs::write(
+            dir.join("src/__snapshots__/a.test.js.snap"),
+            "exports[`a`] = `1`;\n",
+        )
+        .unwrap();
+        fs::write(
+            dir.join("src/a.test.js"),
+            "expect(a).toMatchInlineSnapshot(`1`);\n",
+        )
+        .unwrap();
+        fs::write(dir.join("src/b.test.js"), "expect(b).toBe(1);\n").unwrap();
+        let files: Vec<PathBuf> = snapshot_files(dir, SnapshotFramework::Jest)
+            .into_keys()
+            .map(|p| p.strip_prefix(dir).unwrap().to_path_buf())
+            .collect();
+        assert_eq!(
+            files,
+            vec![
+                PathBuf::from("src/__snapshots__/a.test.js.snap"),
+                PathBuf::from("src/a.test.js")
+            ]
+        );
+
+        fs::write(
+            dir.join("Cargo.toml"),
+            "[dev-dependencies]\ninsta = \"1\"\n",
+        )
+        .unwrap();
+        assert_eq!(
+            detect_snapshot_framework(dir),
+            Some(SnapshotFramework::Insta)
+        );
+    }
+
+    #[test]
+    fn test_update_snapshots_reports_changed_files() {
+        let temp_dir = TempDir::new().unwrap();
+        let dir = temp_dir.path();
+        fs::create_dir_all(dir.join("tests/snapshots")).unwrap();
+        fs::write(
+            dir.join("tests/snapshots/t__a.snap"),
+            "---\nsource: tests/t.rs\n---\nold\n",
+        )
+        .unwrap();
+        fs::write(dir.join("tests/snapshots/t__b.snap.new"), "pending\n").unwrap();
+        fs::write(dir.join("tests/snapshots/t__c.snap"), "same\n").unwrap();
+
+        let mut tool = UpdateSnapshotsTool::new();
+        let state = Arc::new(Mutex::new(ToolState::new()));
+        let command = "--command=printf -- '---\\nsource: tests/t.rs\\n---\\nnew\\n' > tests/snapshots/t__a.snap && mv tests/snapshots/t__b.snap.new tests/snapshots/t__b.snap";
+        let args = ToolArgs::from_args(&[dir.to_str().unwrap(), "--framework=insta", command]);
+        let result = tool.execute(&args, &state).unwrap();
+        assert!(result.success, "{}", result.message);
+        assert!(result
+            .message
+            .contains("M tests/snapshots/t__a.snap (+1 -1)"));
+        assert!(result
+            .message
+            .contains("A tests/snapshots/t__b.snap (+1 -0)"));
+        assert!(result.message.contains("D tests/snapshots/t__b.snap.new"));
+        assert!(result.message.contains("-old\n+new"));
+        let data = result.data.unwrap();
+        assert_eq!(data["changed"].as_array().unwrap().len(), 3);
+        assert_eq!(data["changed"][0]["change"], "updated");
+
+        let args = ToolArgs::from_args(&[
+            dir.to_str().unwrap(),
+            "--framework=insta",
+            "--command=exit 2",
+        ]);
+        let result = tool.execute(&args, &state).unwrap();
+        assert!(!result.success);
+        assert!(result.message.contains("No snapshot files changed"));
+    }
+}
@@ -0,0 +1,328 @@
+//! Rendering of cursor-addressed output to the text a terminal would show
+//!
+//! Progress UIs and watch modes redraw the screen with cursor movement and erase
+//! sequences. Stripping the escapes leaves every intermediate frame interleaved, so
+//! [`render_screen`] replays the output on a virtual terminal instead and returns what
+//! it shows at the end: the lines that scrolled off the top, then the final screen.
+//!
+//! Clearing the whole screen (`ESC [2J`) also drops the lines that scrolled off
+//! before it, since programs clear to redraw. Colors and other attributes are
+//! ignored, and every character is one column wide.
+
+/// Columns of the virtual terminal `run_command` renders on
+pub const DEFAULT_COLUMNS: usize = 120;
+/// Rows of the virtual terminal `run_command` renders on
+pub const DEFAULT_ROWS: usize = 40;
+
+/// The text a `columns` x `rows` terminal shows after printing `text`, one line per
+/// row with trailing blanks removed
+pub fn render_screen(text: &str, columns: usize, rows: usize) -> String {
+    let mut terminal = Terminal::new(columns.max(1), rows.max(1));
+    terminal.feed(text);
+    terminal.render()
+}
+
+type Grid = Vec<Vec<char>>;
+
+struct Terminal {
+    columns: usize,
+    rows: usize,
+    scrollback: Grid,
+    screen: Grid,
+    row: usize,
+    col: usize,
+    saved: (usize, usize),
+    /// A character was printed in the last column; the next one starts a new line
+    wrap_pending: bool,
+    /// Scrollback, screen and cursor of the main screen while the alternate one shows
+    main: Option<(Grid, Grid, usize, usize)>,
+}
+
+impl Terminal {
+    fn new(columns: usize, rows: usize) -> Self {
+        Self {
+            columns,
+            rows,
+            scrollback: Vec::new(),
+            screen: vec![vec![' '; columns]; rows],
+            row: 0,
+            col: 0,
+            saved: (0, 0),
+            wrap_pending: false,
+            main: None,
+        }
+    }
+
+    fn blank_row(&self) -> Vec<char> {
+        vec![' '; self.columns]
+    }
+
+    fn feed(&mut self, text: &str) {
+        let mut chars = text.chars().peekable();
+        while let Some(c) = chars.next() {
+            match c {
+                '\u{1b}' => match chars.next() {
+                    Some('[') => {
+                        let mut params = String::new();
+                        for next in chars.by_ref() {
+                            if ('\u{40}'..='\u{7e}').contains(&next) {
+                                self.csi(&params, next);
+                                break;
+                            }
+                            params.push(next);
+                        }
+                    }
+                    // OSC: ESC ] ... terminated by BEL or ESC \
+                    Some(']') => {
+                        while let Some(next) = chars.next() {
+                            if next == '\u{7}' {
+                                break;
+                            }
+                            if next == '\u{1b}' {
+                                if chars.peek() == Some(&'\\') {
+                                    chars.next();
+                                }
+                                break;
+                            }
+                        }
+                    }
+                    // Character set selection such as ESC ( B
+                    Some('(') | Some(')') => {
+                        chars.next();
+                    }
+                    Some('7') => self.saved = (self.row, self.col),
+                    Some('8') => self.move_to(self.saved.0, self.saved.1),
+                    Some('D') => self.line_feed(),
+                    Some('E') => {
+                        self.line_feed();
+                        self.col = 0;
+                    }
+                    Some('M') => self.reverse_index(),
+                    Some('c') => *self = Terminal::new(self.columns, self.rows),
+                    _ => {}
+                },
+                '\n' => {
+                    // Output to a terminal has its newlines translated to CR LF
+                    self.line_feed();
+                    self.col = 0;
+                }
+                '\r' => self.move_to(self.row, 0),
+                '\u{8}' => self.move_to(self.row, self.col.saturating_sub(1)),
+                '\t' => self.move_to(self.row, (self.col / 8 + 1) * 8),
+                c if c.is_control() => {}
+                c => self.print(c),
+            }
+        }
+    }
+
+    fn print(&mut self, c: char) {
+        if self.wrap_pending {
+            self.line_feed();
+            self.col = 0;
+        }
+        self.screen[self.row][self.col] = c;
+        if self.col + 1 == self.columns {
+            self.wrap_pending = true;
+        } else {
+            self.col += 1;
+        }
+    }
+
+    /// Move the cursor, clamped to the screen
+    fn move_to(&mut self, row: usize, col: usize) {
+        self.row = row.min(self.rows - 1);
+        self.col = col.min(self.columns - 1);
+        self.wrap_pending = false;
+    }
+
+    fn line_feed(&mut self) {
+        self.wrap_pending = false;
+        if self.row + 1 == self.rows {
+            self.scroll_up(1);
+        } else {
+            self.row += 1;
+        }
+    }
+
+    fn reverse_index(&mut self) {
+        self.wrap_pending = false;
+        if self.row == 0 {
+            self.scroll_down(1);
+        } else {
+            self.row -= 1;
+        }
+    }
+
+    fn scroll_up(&mut self, lines: usize) {
+        for _ in 0..lines.min(self.rows) {
+            let top = self.screen.remove(0);
+            // The alternate screen has no scrollback
+            if self.main.is_none() {
+                self.scrollback.push(top);
+            }
+            self.screen.push(self.blank_row());
+        }
+    }
+
+    fn scroll_down(&mut self, lines: usize) {
+        for _ in 0..lines.min(self.rows) {
+            self.screen.pop();
+            self.screen.insert(0, self.blank_row());
+        }
+    }
+
+    fn clear_row(&mut self, row: usize, columns: std::ops::Range<usize>) {
+        for cell in &mut self.screen[row][columns] {
+            *cell = ' ';
+        }
+    }
+
+    fn csi(&mut self, params: &str, action: char) {
+        let private = params.starts_with('?');
+        let numbers: Vec<usize> = params
+            .trim_start_matches(['?', '>', '='])
+            .split(';')
+            .map(|param| param.trim_end_matches(|c: char| !c.is_ascii_digit()))
+            .map(|param| param.parse().unwrap_or(0))
+            .collect();
+        let param = |index: usize| numbers.get(index).copied().unwrap_or(0);
+        // Movement counts and positions treat a missing or zero parameter as 1
+        let count = |index: usize| param(index).max(1);
+        let (row, col) = (self.row, self.col);
+
+        match action {
+            'A' => self.move_to(row.saturating_sub(count(0)), col),
+            'B' | 'e' => self.move_to(row + count(0), col),
+            'C' | 'a' => self.move_to(row, col + count(0)),
+            'D' => self.move_to(row, col.saturating_sub(count(0))),
+            'E' => self.move_to(row + count(0), 0),
+            'F' => self.move_to(row.saturating_sub(count(0)), 0),
+            'G' | '`' => self.move_to(row, count(0) - 1),
+            'd' => self.move_to(count(0) - 1, col),
+            'H' | 'f' => self.move_to(count(0) - 1, count(1) - 1),
+            'J' => match param(0) {
+                0 => {
+                    self.clear_row(row, col..self.columns);
+                    for below in row + 1..self.rows {
+                        self.clear_row(below, 0..self.columns);
+                    }
+                }
+                1 => {
+                    for above in 0..row {
+                        self.clear_row(above, 0..self.columns);
+                    }
+                    self.clear_row(row, 0..col + 1);
+                }
+                _ => {
+                    self.screen = vec![self.blank_row(); self.rows];
+                    self.scrollback.clear();
+                }
+            },
+            'K' => match param(0) {
+                0 => self.clear_row(row, col..self.columns),
+                1 => self.clear_row(row, 0..col + 1),
+                _ => self.clear_row(row, 0..self.columns),
+            },
+            'L' => {
+                for _ in 0..count(0).min(self.rows - row) {
+                    self.screen.pop();
+                    self.screen.insert(row, self.blank_row());
+                }
+            }
+            'M' => {
+                for _ in 0..count(0).min(self.rows - row) {
+                    self.screen.remove(row);
+                    self.screen.push(self.blank_row());
+                }
+            }
+            '@' => {
+                let line = &mut self.screen[row];
+                for _ in 0..count(0).min(self.columns - col) {
+                    line.pop();
+                    line.insert(col, ' ');
+                }
+            }
+            'P' => {
+                let line = &mut self.screen[row];
+                for _ in 0..count(0).min(self.columns - col) {
+                    line.remove(col);
+                    line.push(' ');
+                }
+            }
+            'X' => self.clear_row(row, col..(col + count(0)).min(self.columns)),
+            'S' => self.scroll_up(count(0)),
+            'T' => self.scroll_down(count(0)),
+            's' => self.saved = (row, col),
+            'u' => self.move_to(self.saved.0, self.saved.1),
+            'h' if private && matches!(param(0), 47 | 1047 | 1049) && self.main.is_none() => {
+                let blank = vec![self.blank_row(); self.rows];
+                let screen = std::mem::replace(&mut self.screen, blank);
+                let scrollback = std::mem::take(&mut self.scrollback);
+                self.main = Some((scrollback, screen, row, col));
+                self.move_to(0, 0);
+            }
+            'l' if private && matches!(param(0), 47 | 1047 | 1049) => {
+                if let Some((scrollback, screen, row, col)) = self.main.take() {
+                    self.scrollback = scrollback;
+                    self.screen = screen;
+                    self.move_to(row, col);
+                }
+            }
+            // Colors, modes and reports do not change the text
+            _ => {}
+        }
+    }
+
+    fn render(&self) -> String {
+        let mut lines: Vec<String> = self
+            .scrollback
+            .iter()
+            .chain(&self.screen)
+            .map(|line| line.iter().collect::<String>().trim_end().to_string())
+            .collect();
+        while lines.last().is_some_and(|line| line.is_empty()) {
+            lines.pop();
+        }
+        if lines.is_empty() {
+            return String::new();
+        }
+        lines.join("\n") + "\n"
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_render_screen() {
+        // A progress display redrawing two lines in place
+        let progress = "Building\n\
+            crate a: 10%\ncrate b: 0%\n\x1b[2A\x1b[Kcrate a: 100%\n\x1b[Kcrate b: 50%\n\
+            \x1b[1A\x1b[2Kcrate b: done\nFinished\n";
+        assert_eq!(
+            render_screen(progress, 80, 24),
+            "Building\ncrate a: 100%\ncrate b: done\nFinished\n"
+        );
+
+        // A watch loop clearing the screen between frames keeps the last frame
+        let watch = "\x1b[H\x1b[2Jtick 1\n\x1b[H\x1b[2Jtick 2\nok\n";
+        assert_eq!(render_screen(watch, 80, 24), "tick 2\nok\n");
+
+        // Absolute positioning, overwriting and erasing characters
+        let positioned = "\x1b[3;5Hx\x1b[1;1Habcdef\x1b[1;3H\x1b[2PZ";
+        assert_eq!(render_screen(positioned, 80, 24), "abZf\n\n    x\n");
+
+        // Lines scrolled off the top are kept; long lines wrap
+        assert_eq!(
+            render_screen("1\n2\n3\n4\nabcdefgh", 6, 2),
+            "1\n2\n3\n4\nabcdef\ngh\n"
+        );
+
+        // The alternate screen disappears when the program leaves it
+        let tui = "before\n\x1b[?1049h\x1b[1;1Hmenu\x1b[?1049lafter\n";
+        assert_eq!(render_screen(tui, 80, 24), "before\nafter\n");
+
+        assert_eq!(render_screen("\x1b[31m\x1b]0;title\x07\x1b[0m", 80, 24), "");
+    }
+}
@@ -0,0 +1,419 @@
+//! Structured parsing of test runner output
+//!
+//! Recognizes failures reported by `cargo test`, `pytest`, `jest` and `go test`
+//! so agents can work from test names and locations instead of raw logs.
+
+use regex::Regex;
+use serde::{Deserialize, Serialize};
+
+/// Test frameworks whose output can be parsed
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum TestFramework {
+    Cargo,
+    Pytest,
+    Jest,
+    GoTest,
+}
+
+impl TestFramework {
+    /// Parse a framework name as accepted by the `framework` tool parameter
+    pub fn from_name(name: &str) -> Option<Self> {
+        match name.to_lowercase().as_str() {
+            "cargo" | "rust" | "cargo_test" => Some(Self::Cargo),
+            "pytest" | "python" => Some(Self::Pytest),
+            "jest" | "javascript" | "typescript" => Some(Self::Jest),
+            "go" | "go_test" | "gotest" => Some(Self::GoTest),
+            _ => None,
+        }
+    }
+
+    /// Canonical framework name
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            Self::Cargo => "cargo",
+            Self::Pytest => "pytest",
+            Self::Jest => "jest",
+            Self::GoTest => "go",
+        }
+    }
+
+    /// Guess the framework that produced `output`
+    pub fn detect_from_output(output: &str) -> Option<Self> {
+        if output.contains("test result:")
+            || output.contains("running ") && output.contains(" tests")
+        {
+            Some(Self::Cargo)
+        } else if output.contains("short test summary info")
+            || output.contains("test session starts")
+        {
+            Some(Self::Pytest)
+        } else if output.contains("--- FAIL:") || output.contains("=== RUN") {
+            Some(Self::GoTest)
+        } else if output.contains("Tests:")
+            && (output.contains("●") || output.contains("PASS ") || output.contains("FAIL "))
+        {
+            Some(Self::Jest)
+        } else {
+            None
+        }
+    }
+}
+
+/// A single failing test extracted from runner output
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct TestFailure {
+    /// Fully qualified test name as reported by the runner
+    pub name: String,
+    /// Source file of the failing assertion, when reported
+    pub file: Option<String>,
+    /// Line of the failing assertion, when reported
+    pub line: Option<usize>,
+    /// Assertion or panic message
+    pub message: String,
+}
+
+/// Parse failing tests from runner output. When `framework` is `None` it is
+/// detected from the output itself.
+pub fn parse_test_failures(output: &str, framework: Option<TestFramework>) -> Vec<TestFailure> {
+    match framework.or_else(|| TestFramework::detect_from_output(output)) {
+        Some(TestFramework::Cargo) => parse_cargo(output),
+        Some(TestFramework::Pytest) => parse_pytest(output),
+        Some(TestFramework::Jest) => parse_jest(output),
+        Some(TestFramework::GoTest) => parse_go(output),
+        None => Vec::new(),
+    }
+}
+
+fn parse_cargo(output: &str) -> Vec<TestFailure> {
+    let header = Regex::new(r"^---- (\S+) stdout ----$").unwrap();
+    // Rust >= 1.73: "panicked at src/lib.rs:10:5:" followed by the message
+    let panic_new = Regex::new(r"^thread '[^']*' panicked at ([^:\s]+):(\d+):\d+:$").unwrap();
+    // Older toolchains: "panicked at 'message', src/lib.rs:10:5"
+    let panic_old =
+        Regex::new(r"^thread '[^']*' panicked at '(.*)', ([^:\s]+):(\d+):\d+$").unwrap();
+
+    let mut failures = Vec::new();
+    let lines: Vec<&str> = output.lines().collect();
+    let mut i = 0;
+    while i < lines.len() {
+        let Some(caps) = header.captures(lines[i]) else {
+            i += 1;
+            continue;
+        };
+        let mut failure = TestFailure {
+            name: caps[1].to_string(),
+            file: None,
+            line: None,
+            message: String::new(),
+        };
+        i += 1;
+        let mut message_lines = Vec::new();
+        let mut in_message = false;
+        while i < lines.len() && !header.is_match(lines[i]) && lines[i] != "failures:" {
+            let line = lines[i];
+            if let Some(caps) = panic_new.captures(line) {
+                failure.file = Some(caps[1].to_string());
+                failure.line = caps[2].parse().ok();
+                in_message = true;
+            } else if let Some(caps) = panic_old.captures(line) {
+                message_lines.push(caps[1].to_string());
+                failure.file = Some(caps[2].to_string());
+                failure.line = caps[3].parse().ok();
+            } else if line.starts_with("note: ") || line.starts_with("stack backtrace:") {
+                in_message = false;
+            } else if in_message && !line.trim().is_empty() {
+                message_lines.push(line.trim_end().to_string());
+            }
+            i += 1;
+        }
+        failure.message = message_lines.join("\n");
+        failures.push(failure);
+    }
+
+    // Failures without captured stdout (e.g. `should_panic` tests) are only listed
+    // in the trailing "failures:" summary.
+    let summary_entry = Regex::new(r"^    (\S+)$").unwrap();
+    if let Some(summary_start) = lines.iter().rposition(|line| *line == "failures:") {
+        for line in &lines[summary_start + 1..] {
+            if line.starts_with("test result:") {
+                break;
+            }
+            if let Some(caps) = summary_entry.captures(line) {
+                let name = caps[1].to_string();
+                if !failures.iter().any(|f| f.name == name) {
+                    failures.push(TestFailure {
+                        name,
+                        file: None,
+                        line: None,
+                        message: String::new(),
+                    });
+                }
+            }
+        }
+    }
+
+    failures
+}
+
+fn parse_pytest(output: &str) -> Vec<TestFailure> {
+    let summary = Regex::new(r"^(?:FAILED|ERROR) (\S+?)(?: - (.*))?$").unwrap();
+    let location = Regex::new(r"^(\S+\.py):(\d+): (.*)$").unwrap();
+    let lines: Vec<&str> = output.lines().collect();
+
+    let mut failures = Vec::new();
+    for line in &lines {
+        let Some(caps) = summary.captures(line) else {
+            continue;
+        };
+        let name = caps[1].to_string();
+        let file_part = name.split("::").next().unwrap_or(&name).to_string();
+        let test_fn = name
+            .rsplit("::")
+            .next()
+            .unwrap_or(&name)
+            .split('[')
+            .next()
+            .unwrap_or("")
+            .to_string();
+        let mut failure = TestFailure {
+            name: name.clone(),
+            file: Some(file_part.clone()),
+            line: None,
+            message: caps
+                .get(2)
+                .map(|m| m.as_str().to_string())
+                .unwrap_or_default(),
+        };
+
+        // Find the detailed section "____ test_fn ____" and its "file.py:NN: Error" line
+        let section_start = lines.iter().position(|l| {
+            l.starts_with('_') && l.trim_matches(|c| c == '_' || c == ' ').ends_with(&test_fn)
+        });
+        if let Some(start) = section_start {
+            for detail in lines.iter().skip(start + 1) {
+                if detail.starts_with("____") || detail.starts_with("====") {
+                    break;
+                }
+                if let Some(loc) = location.captures(detail) {
+                    failure.file = Some(loc[1].to_string());
+                    failure.line = loc[2].parse().ok();
+                    if failure.message.is_empty() {
+                        failure.message = loc[3].to_string();
+                    }
+                }
+                if failure.message.is_empty() && detail.starts_with("E ") {
+                    failure.message = detail[1..].trim().to_string();
+                }
+            }
+        }
+        failures.push(failure);
+    }
+    failures
+}
+
+fn parse_jest(output: &str) -> Vec<TestFailure> {
+    let frame = Regex::new(r"at .*?\(?([^\s()]+\.[cm]?[jt]sx?):(\d+):\d+\)?").unwrap();
+    let lines: Vec<&str> = output.lines().collect();
+
+    let mut failures = Vec::new();
+    let mut i = 0;
+    while i < lines.len() {
+        let trimmed = lines[i].trim_start();
+        let Some(name) = trimmed.strip_prefix("● ") else {
+            i += 1;
+            continue;
+        };
+        if name.starts_with("Console") {
+            i += 1;
+            continue;
+        }
+        let mut failure = TestFailure {
+            name: name.trim().to_string(),
+            file: None,
+            line: None,
+            message: String::new(),
+        };
+        let mut message_lines: Vec<String> = Vec::new();
+        i += 1;
+        while i < lines.len() && !lines[i].trim_start().starts_with("● ") {
+            let line = lines[i].trim();
+            if let Some(caps) = frame.captures(line) {
+                if failure.file.is_none() && !caps[1].contains("node_modules") {
+                    failure.file = Some(caps[1].to_string());
+                    failure.line = caps[2].parse().ok();
+                }
+            } else if !line.is_empty()
+                && failure.file.is_none()
+                && !line.starts_with('>')
+                && !line.chars().next().is_some_and(|c| c.is_ascii_digit())
+                && !line.starts_with('|')
+                && !line.starts_with('^')
+            {
+                message_lines.push(line.to_string());
+            }
+            i += 1;
+        }
+        failure.message = message_lines.join("\n");
+        failures.push(failure);
+    }
+    failures
+}
+
+fn parse_go(output: &str) -> Vec<TestFailure> {
+    let run = Regex::new(r"^=== (?:RUN|CONT)\s+(\S+)").unwrap();
+    let header = Regex::new(r"^\s*--- FAIL: (\S+)").unwrap();
+    let location = Regex::new(r"^\s+([\w./-]+\.go):(\d+): (.*)$").unwrap();
+
+    // Log lines belong to the most recently started test; with -v they are
+    // printed before the "--- FAIL" header, without -v right after it.
+    let mut logs: std::collections::HashMap<String, Vec<(String, usize, String)>> =
+        std::collections::HashMap::new();
+    let mut current: Option<String> = None;
+    let mut failures = Vec::new();
+
+    for line in output.lines() {
+        if let Some(caps) = run.captures(line) {
+            current = Some(caps[1].to_string());
+        } else if let Some(caps) = header.captures(line) {
+            current = Some(caps[1].to_string());
+            failures.push(caps[1].to_string());
+        } else if let Some(caps) = location.captures(line) {
+            if let Some(name) = &current {
+                logs.entry(name.clone()).or_default().push((
+                    caps[1].to_string(),
+                    caps[2].parse().unwrap_or(0),
+                    caps[3].to_string(),
+                ));
+            }
+        } else if !line.starts_with(' ') && !line.starts_with('\t') {
+            current = None;
+        }
+    }
+
+    failures
+        .into_iter()
+        .map(|name| {
+            let entries = logs.remove(&name).unwrap_or_default();
+            TestFailure {
+                file: entries.first().map(|e| e.0.clone()),
+                line: entries.first().map(|e| e.1),
+                message: entries
+                    .iter()
+                    .map(|e| e.2.as_str())
+                    .collect::<Vec<_>>()
+                    .join("\n"),
+                name,
+            }
+        })
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parse_cargo_failures() {
+        let output = "\
+running 2 tests
+test tests::ok ... ok
+test tests::adds ... FAILED
+
+failures:
+
+---- tests::adds stdout ----
+
+thread 'tests::adds' panicked at src/lib.rs:12:9:
+assertion `left == right` failed
+  left: 3
+ right: 4
+note: run with `RUST_BACKTRACE=1` environment variable to display a backtrace
+
+
+failures:
+    tests::adds
+
+test result: FAILED. 1 passed; 1 failed; 0 ignored; 0 measured; 0 filtered out
+";
+        let failures = parse_test_failures(output, None);
+        assert_eq!(failures.len(), 1);
+        assert_eq!(failures[0].name, "tests::adds");
+        assert_eq!(failures[0].file.as_deref(), Some("src/lib.rs"));
+        assert_eq!(failures[0].line, Some(12));
+        assert!(failures[0].message.contains("left: 3"));
+    }
+
+    #[test]
+    fn test_parse_pytest_failures() {
+        let output = "\
+============================= test session starts ==============================
+___________________________________ test_add ___________________________________
+
+    def test_add():
+>       assert add(1, 2) == 4
+E       assert 3 == 4
+
+tests/test_math.py:5: AssertionError
+=========================== short test summary info ============================
+FAILED tests/test_math.py::test_add - assert 3 == 4
+";
+        let failures = parse_test_failures(output, None);
+        assert_eq!(failures.len(), 1);
+        assert_eq!(failures[0].name, "tests/test_math.py::test_add");
+        assert_eq!(failures[0].file.as_deref(), Some("tests/test_math.py"));
+        assert_eq!(failures[0].line, Some(5));
+        assert_eq!(failures[0].message, "assert 3 == 4");
+    }
+
+    #[test]
+    fn test_parse_jest_failures() {
+        let output = "\
+FAIL src/sum.test.js
+  ● math › adds numbers
+
+    expect(received).toBe(expected) // Object.is equality
+
+    Expected: 4
+    Received: 3
+
+      3 | test('adds numbers', () => {
+    > 4 |   expect(sum(1, 2)).toBe(4);
+        |                     ^
+
+      at Object.<anonymous> (src/sum.test.js:4:21)
+
+Tests:       1 failed, 1 total
+";
+        let failures = parse_test_failures(output, None);
+        assert_eq!(failures.len(), 1);
+        assert_eq!(failures[0].name, "math › adds numbers");
+        assert_eq!(failures[0].file.as_deref(), Some("src/sum.test.js"));
+        assert_eq!(failures[0].line, Some(4));
+        assert!(failures[0].message.contains("Expected: 4"));
+    }
+
+    #[test]
+    fn test_parse_go_failures() {
+        let output = "\
+=== RUN   TestAdd
+    math_test.go:10: expected 4, got 3
+--- FAIL: TestAdd (0.00s)
+FAIL
+FAIL\texample.com/math\t0.002s
+";
+        let non_verbose = "\
+--- FAIL: TestAdd (0.00s)
+    math_test.go:10: expected 4, got 3
+FAIL
+";
+        for text in [output, non_verbose] {
+            let failures = parse_test_failures(text, None);
+            assert_eq!(failures.len(), 1);
+            assert_eq!(failures[0].name, "TestAdd");
+            assert_eq!(failures[0].file.as_deref(), Some("math_test.go"));
+            assert_eq!(failures[0].line, Some(10));
+            assert_eq!(failures[0].message, "expected 4, got 3");
+        }
+    }
+}
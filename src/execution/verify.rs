@@ -0,0 +1,777 @@
+//! verify_change tool: check an edit right after making it
+//!
+//! Runs a short pipeline over the files of the last edit, or the given paths:
+//!
+//! 1. `syntax`: every file parses with its tree-sitter grammar
+//! 2. `check`: `cargo check -p <package>` for the packages owning edited Rust files, or
+//!    the commands of the `[verify]` table of `cats.toml`
+//! 3. `tests`: the tests `test_map` links to the edited files, run by their framework
+//!
+//! A step runs only when the ones before it passed. The verdict is attached to the edit
+//! in the session history ([`ToolState::record_verification`]), so `_state` tells
+//! verified edits from unverified ones.
+
+use super::run_tests::{shell_quote, RunTestsTool, TestRun};
+use super::test_parser::TestFramework;
+use crate::ast::test_map::test_map;
+use crate::ast::{self, Language};
+use crate::config::{Config, VerifyConfig};
+use crate::core::{DangerLevel, Tool, ToolArgs, ToolCategory, ToolError, ToolResult};
+use crate::diagnostics::{parse_diagnostics, Severity};
+use crate::editing::refactor::CrateLayout;
+use crate::state::{canonical_path, ToolState, Verification};
+use crate::utils::workspace::{Workspace, WorkspaceKind};
+use anyhow::Result;
+use serde::Serialize;
+use std::collections::{BTreeMap, BTreeSet};
+use std::fs;
+use std::path::{Path, PathBuf};
+use std::sync::{Arc, Mutex};
+
+/// Errors or failing tests listed per failed step
+const MAX_DETAILS: usize = 10;
+
+/// Output lines kept when a failed command reports nothing that could be parsed
+const TAIL_LINES: usize = 20;
+
+/// A stage of the pipeline, in the order they run
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Serialize)]
+#[serde(rename_all = "snake_case")]
+enum VerifyStep {
+    Syntax,
+    Check,
+    Tests,
+}
+
+impl VerifyStep {
+    const ALL: [VerifyStep; 3] = [Self::Syntax, Self::Check, Self::Tests];
+
+    fn from_name(name: &str) -> Option<Self> {
+        match name.trim().to_lowercase().as_str() {
+            "syntax" => Some(Self::Syntax),
+            "check" | "build" | "compile" => Some(Self::Check),
+            "tests" | "test" => Some(Self::Tests),
+            _ => None,
+        }
+    }
+
+    fn as_str(&self) -> &'static str {
+        match self {
+            Self::Syntax => "syntax",
+            Self::Check => "check",
+            Self::Tests => "tests",
+        }
+    }
+
+    /// Steps named in `names`, in pipeline order
+    fn parse_list<'a>(names: impl IntoIterator<Item = &'a str>) -> Result<Vec<Self>, String> {
+        let mut steps = BTreeSet::new();
+        for name in names.into_iter().filter(|n| !n.trim().is_empty()) {
+            match Self::from_name(name) {
+                Some(step) => steps.insert(step),
+                None => {
+                    return Err(format!(
+                        "Unknown verification step '{}'. Expected syntax, check or tests",
+                        name.trim()
+                    ))
+                }
+            };
+        }
+        Ok(steps.into_iter().collect())
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "snake_case")]
+enum StepStatus {
+    Passed,
+    Failed,
+    Skipped,
+}
+
+impl StepStatus {
+    fn as_str(&self) -> &'static str {
+        match self {
+            Self::Passed => "passed",
+            Self::Failed => "failed",
+            Self::Skipped => "skipped",
+        }
+    }
+}
+
+/// What one step did
+#[derive(Debug, Clone, Serialize)]
+struct StepOutcome {
+    step: VerifyStep,
+    status: StepStatus,
+    /// Commands the step ran
+    commands: Vec<String>,
+    /// Errors or failing tests, or why the step was skipped
+    details: Vec<String>,
+}
+
+impl StepOutcome {
+    fn skipped(step: VerifyStep, reason: impl Into<String>) -> Self {
+        Self {
+            step,
+            status: StepStatus::Skipped,
+            commands: Vec::new(),
+            details: vec![reason.into()],
+        }
+    }
+
+    fn finished(step: VerifyStep, commands: Vec<String>, mut details: Vec<String>) -> Self {
+        let status = if details.is_empty() {
+            StepStatus::Passed
+        } else {
+            StepStatus::Failed
+        };
+        if details.len() > MAX_DETAILS {
+            let more = details.len() - MAX_DETAILS;
+            details.truncate(MAX_DETAILS);
+            details.push(format!("... and {} more", more));
+        }
+        Self {
+            step,
+            status,
+            commands,
+            details,
+        }
+    }
+}
+
+/// A command and the directory it runs in
+#[derive(Debug, Clone, PartialEq, Eq, PartialOrd, Ord)]
+struct Job {
+    command: String,
+    dir: PathBuf,
+}
+
+impl Job {
+    fn run(&self, framework: Option<TestFramework>) -> std::result::Result<TestRun, String> {
+        RunTestsTool::run_once(&self.command, &self.dir, framework)
+            .map_err(|e| format!("Failed to run '{}': {}", self.command, e))
+    }
+}
+
+/// The last lines of a failed command's output, as one detail
+fn output_tail(run: &TestRun) -> String {
+    let lines: Vec<&str> = run.output.trim_end().lines().collect();
+    let tail = &lines[lines.len().saturating_sub(TAIL_LINES)..];
+    format!(
+        "exit code {}; last {} lines of output:\n{}",
+        run.exit_code.unwrap_or(-1),
+        tail.len(),
+        tail.join("\n")
+    )
+}
+
+/// The nearest directory above `path` holding one of `markers`, else `fallback`
+fn project_dir(path: &Path, markers: &[&str], fallback: &Path) -> PathBuf {
+    path.ancestors()
+        .skip(1)
+        .find(|dir| markers.iter().any(|marker| dir.join(marker).exists()))
+        .map_or_else(|| fallback.to_path_buf(), Path::to_path_buf)
+}
+
+/// Where Cargo commands for `path` run, and the `-p` package they select in a workspace
+fn cargo_scope(path: &Path) -> Option<(PathBuf, Option<String>)> {
+    let workspace = path.parent().and_then(Workspace::detect);
+    if let Some(workspace) = &workspace {
+        let member = workspace
+            .member_for(path)
+            .filter(|member| member.kind == WorkspaceKind::Cargo);
+        if let Some(member) = member {
+            return Some((workspace.root.clone(), Some(member.name.clone())));
+        }
+    }
+    CrateLayout::find(path).map(|layout| (layout.root, None))
+}
+
+/// The Rust tests of one crate to run
+#[derive(Debug, Default)]
+struct CrateTests {
+    /// Run the whole suite, for edits of the crate root or shared test helpers
+    whole: bool,
+    /// Module paths (`a::b::`) whose inline tests to run
+    filters: BTreeSet<String>,
+    /// Integration test targets
+    targets: BTreeSet<String>,
+}
+
+fn cargo_command(subcommand: &str, package: &Option<String>) -> String {
+    match package {
+        Some(package) => format!("cargo {} -p {}", subcommand, package),
+        None => format!("cargo {}", subcommand),
+    }
+}
+
+/// Tool that checks the files of an edit: syntax, compilation and their tests
+pub struct VerifyChangeTool {
+    name: String,
+}
+
+impl VerifyChangeTool {
+    pub fn new() -> Self {
+        Self {
+            name: "verify_change".to_string(),
+        }
+    }
+
+    /// Paths from the positional arguments and the `paths` list
+    fn paths(args: &ToolArgs) -> Vec<String> {
+        let mut paths: Vec<String> = args.args.clone();
+        if let Some(list) = args.get_named_arg("paths") {
+            match serde_json::from_str::<Vec<String>>(list) {
+                Ok(list) => paths.extend(list),
+                Err(_) => paths.extend(
+                    list.split(',')
+                        .map(str::trim)
+                        .filter(|path| !path.is_empty())
+                        .map(str::to_string),
+                ),
+            }
+        }
+        paths
+    }
+
+    fn syntax(files: &[PathBuf]) -> StepOutcome {
+        let mut checked = 0;
+        let mut details = Vec::new();
+        for path in files {
+            let Some(language) = Language::from_path(path) else {
+                continue;
+            };
+            let Ok(source) = fs::read_to_string(path) else {
+                continue;
+            };
+            checked += 1;
+            details.extend(
+                ast::syntax_errors(language, &source)
+                    .into_iter()
+                    .map(|error| {
+                        format!(
+                            "{}:{}:{}: {}",
+                            path.display(),
+                            error.line,
+                            error.column,
+                            error.message
+                        )
+                    }),
+            );
+        }
+        if checked == 0 {
+            return StepOutcome::skipped(
+                VerifyStep::Syntax,
+                "No edited file is in a language with a syntax check",
+            );
+        }
+        StepOutcome::finished(VerifyStep::Syntax, Vec::new(), details)
+    }
+
+    /// Check commands for `files`: the configured command for their extension, else
+    /// `cargo check` of the package owning a Rust file
+    fn check_jobs(files: &[PathBuf], config: &VerifyConfig, root: &Path) -> Vec<Job> {
+        let mut jobs = BTreeSet::new();
+        for path in files {
+            let extension = path.extension().and_then(|e| e.to_str()).unwrap_or("");
+            if let Some(command) = config.check.get(extension) {
+                let package = path
+                    .parent()
+                    .and_then(Workspace::detect)
+                    .and_then(|workspace| workspace.member_for(path).map(|m| m.name.clone()))
+                    .unwrap_or_default();
+                jobs.insert(Job {
+                    command: command
+                        .replace("{path}", &path.to_string_lossy())
+                        .replace("{package}", &package),
+                    dir: root.to_path_buf(),
+                });
+            } else if extension == "rs" {
+                if let Some((dir, package)) = cargo_scope(path) {
+                    jobs.insert(Job {
+                        command: format!(
+                            "{} --message-format=json",
+                            cargo_command("check", &package)
+                        ),
+                        dir,
+                    });
+                }
+            }
+        }
+        jobs.into_iter().collect()
+    }
+
+    fn check(files: &[PathBuf], config: &VerifyConfig, root: &Path) -> StepOutcome {
+        let jobs = Self::check_jobs(files, config, root);
+        if jobs.is_empty() {
+            return StepOutcome::skipped(
+                VerifyStep::Check,
+                "No check command for the edited files; add one to the [verify] table of cats.toml",
+            );
+        }
+        let mut details = Vec::new();
+        for job in &jobs {
+            let run = match job.run(None) {
+                Ok(run) => run,
+                Err(e) => {
+                    details.push(e);
+                    continue;
+                }
+            };
+            if run.passed {
+                continue;
+            }
+            let errors: Vec<String> = parse_diagnostics(&run.output)
+                .into_iter()
+                .filter(|d| d.severity == Severity::Error)
+                .map(|d| {
+                    let code = d.code.map(|c| format!("[{}]", c)).unwrap_or_default();
+                    format!(
+                        "{}:{}:{}: error{}: {}",
+                        d.file, d.line, d.column, code, d.message
+                    )
+                })
+                .collect();
+            if errors.is_empty() {
+                details.push(format!("{}: {}", job.command, output_tail(&run)));
+            } else {
+                details.extend(errors);
+            }
+        }
+        let commands = jobs.into_iter().map(|job| job.command).collect();
+        StepOutcome::finished(VerifyStep::Check, commands, details)
+    }
+
+    /// Test commands covering `files`: the tests linked to them by `test_map`, the
+    /// edited test files themselves and the inline tests of edited Rust modules
+    fn test_jobs(files: &[PathBuf], root: &Path) -> Vec<(Job, TestFramework)> {
+        let map = test_map(root);
+        let edited: BTreeSet<PathBuf> = files.iter().cloned().collect();
+        let mut tests: BTreeSet<PathBuf> = map
+            .tests
+            .iter()
+            .filter(|path| edited.contains(&canonical_path(path)))
+            .cloned()
+            .collect();
+        let mut inline = Vec::new();
+        for source in &map.sources {
+            if !edited.contains(&canonical_path(&source.path)) {
+                continue;
+            }
+            tests.extend(source.tests.iter().map(|link| link.path.clone()));
+            if source.inline {
+                inline.push(source.path.clone());
+            }
+        }
+
+        let mut crates: BTreeMap<(PathBuf, Option<String>), CrateTests> = BTreeMap::new();
+        for path in &inline {
+            let (Some(scope), Some(layout)) = (cargo_scope(path), CrateLayout::find(path)) else {
+                continue;
+            };
+            let entry = crates.entry(scope).or_default();
+            match layout.module_segments(path) {
+                Some(segments) if !segments.is_empty() => {
+                    entry.filters.insert(format!("{}::", segments.join("::")));
+                }
+                _ => entry.whole = true,
+            }
+        }
+        let mut by_dir: BTreeMap<(TestFramework, PathBuf), BTreeSet<String>> = BTreeMap::new();
+        for path in &tests {
+            let Some(language) = Language::from_path(path) else {
+                continue;
+            };
+            match language {
+                Language::Rust => {
+                    let Some(scope) = cargo_scope(path) else {
+                        continue;
+                    };
+                    let entry = crates.entry(scope).or_default();
+                    let in_tests_dir = path
+                        .parent()
+                        .and_then(|dir| dir.file_name())
+                        .is_some_and(|name| name == "tests");
+                    match path.file_stem() {
+                        Some(stem) if in_tests_dir => {
+                            entry.targets.insert(stem.to_string_lossy().into_owned());
+                        }
+                        // Helpers shared by integration tests belong to all of them
+                        _ => entry.whole = true,
+                    }
+                }
+                Language::Python => {
+                    let markers = ["pyproject.toml", "setup.cfg", "pytest.ini", "tox.ini"];
+                    let dir = project_dir(path, &markers, root);
+                    let relative = path.strip_prefix(&dir).unwrap_or(path);
+                    by_dir
+                        .entry((TestFramework::Pytest, dir.clone()))
+                        .or_default()
+                        .insert(shell_quote(&relative.to_string_lossy()));
+                }
+                Language::JavaScript | Language::TypeScript | Language::Tsx => {
+                    let dir = project_dir(path, &["package.json"], root);
+                    let relative = path.strip_prefix(&dir).unwrap_or(path);
+                    by_dir
+                        .entry((TestFramework::Jest, dir.clone()))
+                        .or_default()
+                        .insert(shell_quote(&relative.to_string_lossy()));
+                }
+                Language::Go => {
+                    let dir = project_dir(path, &["go.mod"], root);
+                    let package = path
+                        .parent()
+                        .and_then(|parent| parent.strip_prefix(&dir).ok())
+                        .map(|relative| format!("./{}", relative.to_string_lossy()))
+                        .unwrap_or_else(|| "./".to_string());
+                    by_dir
+                        .entry((TestFramework::GoTest, dir.clone()))
+                        .or_default()
+                        .insert(shell_quote(package.trim_end_matches('/')));
+                }
+            }
+        }
+
+        for ((dir, package), tests) in crates {
+            let test = cargo_command("test", &package);
+            let commands = by_dir.entry((TestFramework::Cargo, dir)).or_default();
+            if tests.whole {
+                commands.insert(test);
+                continue;
+            }
+            if !tests.filters.is_empty() {
+                let filters: Vec<String> = tests.filters.iter().map(|f| shell_quote(f)).collect();
+                commands.insert(format!("{} -- {}", test, filters.join(" ")));
+            }
+            if !tests.targets.is_empty() {
+                let targets: Vec<String> = tests
+                    .targets
+                    .iter()
+                    .map(|t| format!("--test {}", shell_quote(t)))
+                    .collect();
+                commands.insert(format!("{} {}", test, targets.join(" ")));
+            }
+        }
+
+        let mut result = Vec::new();
+        for ((framework, dir), arguments) in by_dir {
+            let arguments: Vec<String> = arguments.into_iter().collect();
+            let commands = match framework {
+                TestFramework::Cargo => arguments,
+                TestFramework::Pytest => {
+                    vec![format!("python -m pytest {}", arguments.join(" "))]
+                }
+                TestFramework::Jest => {
+                    vec![format!("npx jest --colors=false {}", arguments.join(" "))]
+                }
+                TestFramework::GoTest => vec![format!("go test {}", arguments.join(" "))],
+            };
+            for command in commands {
+                let job = Job {
+                    command,
+                    dir: dir.clone(),
+                };
+                result.push((job, framework));
+            }
+        }
+        result
+    }
+
+    fn tests(files: &[PathBuf], root: &Path) -> StepOutcome {
+        let jobs = Self::test_jobs(files, root);
+        if jobs.is_empty() {
+            return StepOutcome::skipped(
+                VerifyStep::Tests,
+                "No tests found for the edited files (see test_map)",
+            );
+        }
+        let mut details = Vec::new();
+        for (job, framework) in &jobs {
+            let run = match job.run(Some(*framework)) {
+                Ok(run) => run,
+                Err(e) => {
+                    details.push(e);
+                    continue;
+                }
+            };
+            if run.passed {
+                continue;
+            }
+            if run.failures.is_empty() {
+                details.push(format!("{}: {}", job.command, output_tail(&run)));
+            } else {
+                details.extend(
+                    RunTestsTool::format_failures(&run.failures)
+                        .lines()
+                        .map(|line| line.trim_start_matches("- ").to_string()),
+                );
+            }
+        }
+        let commands = jobs.into_iter().map(|(job, _)| job.command).collect();
+        StepOutcome::finished(VerifyStep::Tests, commands, details)
+    }
+}
+
+impl Default for VerifyChangeTool {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl Tool for VerifyChangeTool {
+    fn name(&self) -> &str {
+        &self.name
+    }
+
+    fn description(&self) -> &str {
+        "Check the files of the last edit (or the given paths): tree-sitter syntax check, cargo check of the affected package, then the tests linked to them by test_map. Records a pass/fail verdict on the edit in the session history"
+    }
+
+    fn signature(&self) -> &str {
+        "verify_change [<path>...] [--paths=<a,b>] [--steps=<syntax,check,tests>]"
+    }
+
+    fn category(&self) -> ToolCategory {
+        ToolCategory::Execution
+    }
+
+    fn danger_level(&self) -> DangerLevel {
+        DangerLevel::ExecutesCode
+    }
+
+    // Attaches its verdict to the latest edit, which other calls of a batch change
+    fn is_parallel_safe(&self) -> bool {
+        false
+    }
+
+    fn validate_args(&self, args: &ToolArgs) -> Result<(), ToolError> {
+        if let Some(steps) = args.get_named_arg("steps") {
+            VerifyStep::parse_list(steps.split(','))
+                .map_err(|message| ToolError::InvalidArgs { message })?;
+        }
+        Ok(())
+    }
+
+    fn execute(&mut self, args: &ToolArgs, state: &Arc<Mutex<ToolState>>) -> Result<ToolResult> {
+        let (working_directory, last_edit) = {
+            let mut state_guard = state
+                .lock()
+                .map_err(|e| anyhow::anyhow!("Failed to lock state: {}", e))?;
+            if !state_guard.pending_edits.is_empty() {
+                state_guard.push_history("edit".to_string());
+            }
+            let last_edit = state_guard.last_edit().map(|snapshot| {
+                let paths: Vec<PathBuf> = snapshot.edits.iter().map(|e| e.path.clone()).collect();
+                (snapshot.operation.clone(), paths)
+            });
+            (state_guard.working_directory.clone(), last_edit)
+        };
+
+        let requested = Self::paths(args);
+        let files: Vec<PathBuf> = if requested.is_empty() {
+            match &last_edit {
+                Some((_, paths)) => paths.clone(),
+                None => {
+                    return Ok(ToolResult::error(
+                        "No edit to verify. Pass paths to check files changed outside the editing tools",
+                    ))
+                }
+            }
+        } else {
+            let mut files = Vec::new();
+            for path in &requested {
+                let resolved = working_directory.join(path);
+                if !resolved.exists() {
+                    return Ok(crate::utils::suggest::path_not_found(
+                        format!("File {} not found", path),
+                        &resolved,
+                    ));
+                }
+                files.push(canonical_path(&resolved));
+            }
+            files
+        };
+
+        let config = match Config::load(&working_directory) {
+            Ok(config) => config.verify,
+            Err(e) => return Ok(ToolResult::error(format!("{:#}", e))),
+        };
+        let steps = match args.get_named_arg("steps") {
+            Some(steps) => VerifyStep::parse_list(steps.split(',')),
+            None if !config.steps.is_empty() => {
+                VerifyStep::parse_list(config.steps.iter().map(String::as_str))
+            }
+            None => Ok(VerifyStep::ALL.to_vec()),
+        };
+        let steps = match steps {
+            Ok(steps) => steps,
+            Err(message) => return Ok(ToolResult::error(message)),
+        };
+
+        let root = canonical_path(&working_directory);
+        let mut outcomes: Vec<StepOutcome> = Vec::new();
+        for step in steps {
+            if outcomes.iter().any(|o| o.status == StepStatus::Failed) {
+                outcomes.push(StepOutcome::skipped(step, "An earlier step failed"));
+                continue;
+            }
+            outcomes.push(match step {
+                VerifyStep::Syntax => Self::syntax(&files),
+                VerifyStep::Check => Self::check(&files, &config, &root),
+                VerifyStep::Tests => Self::tests(&files, &root),
+            });
+        }
+
+        let passed = outcomes.iter().all(|o| o.status != StepStatus::Failed);
+        let summary = outcomes
+            .iter()
+            .map(|o| format!("{}: {}", o.step.as_str(), o.status.as_str()))
+            .collect::<Vec<_>>()
+            .join("\n");
+        let operation = state.lock().ok().and_then(|mut state_guard| {
+            state_guard.record_verification(
+                &files,
+                Verification {
+                    passed,
+                    summary: summary.clone(),
+                },
+            )
+        });
+
+        let subject = match &operation {
+            Some(operation) => format!("{} file(s) edited by {}", files.len(), operation),
+            None => format!("{} file(s)", files.len()),
+        };
+        let mut message = if passed {
+            format!("Verified {}: passed", subject)
+        } else {
+            format!("Verification of {} failed", subject)
+        };
+        for outcome in &outcomes {
+            message.push_str(&format!(
+                "\n{}: {}",
+                outcome.step.as_str(),
+                outcome.status.as_str()
+            ));
+            if !outcome.commands.is_empty() {
+                message.push_str(&format!(" ({})", outcome.commands.join("; ")));
+            }
+            for detail in &outcome.details {
+                message.push_str(&format!("\n  {}", detail.replace('\n', "\n  ")));
+            }
+        }
+
+        let data = serde_json::json!({
+            "passed": passed,
+            "files": files,
+            "operation": operation,
+            "steps": outcomes,
+        });
+        if passed {
+            Ok(ToolResult::success_with_data(message, data))
+        } else {
+            Ok(ToolResult::error_with_data(message, data))
+        }
+    }
+
+    fn get_parameters_schema(&self) -> serde_json::Value {
+        serde_json::json!({
+            "type": "object",
+            "properties": {
+                "paths": {
+                    "type": "array",
+                    "items": {"type": "string"},
+                    "description": "Files to verify; defaults to the files of the last edit"
+                },
+                "steps": {
+                    "type": "string",
+                    "description": "Comma-separated steps to run, from syntax, check and tests (default: the [verify] steps of cats.toml, else all). A step runs only if the earlier ones passed"
+                }
+            },
+            "required": []
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use tempfile::TempDir;
+
+    fn verify(state: &Arc<Mutex<ToolState>>, args: &[&str]) -> (ToolResult, serde_json::Value) {
+        let mut tool = VerifyChangeTool::new();
+        let result = tool.execute(&ToolArgs::from_args(args), state).unwrap();
+        let data = result.data.clone().unwrap_or_default();
+        (result, data)
+    }
+
+    #[test]
+    fn test_verify_change_records_verdict_on_last_edit() {
+        let temp_dir = TempDir::new().unwrap();
+        let root = temp_dir.path().canonicalize().unwrap();
+        let file = root.join("app.py");
+        fs::write(&file, "def f():\n    return 1\n").unwrap();
+
+        let state = Arc::new(Mutex::new(ToolState::new()));
+        state.lock().unwrap().set_working_directory(&root);
+        let (result, _) = verify(&state, &[]);
+        assert!(result.message.starts_with("No edit to verify"));
+
+        {
+            let mut guard = state.lock().unwrap();
+            guard.record_edit(&file, Some(String::new()), Some("def f(:\n".to_string()));
+            guard.push_history("create_file".to_string());
+        }
+        fs::write(&file, "def f(:\n").unwrap();
+        let (result, data) = verify(&state, &[]);
+        assert!(!result.success);
+        assert!(result
+            .message
+            .starts_with("Verification of 1 file(s) edited by create_file failed"));
+        assert!(result.message.contains("app.py:1:"), "{}", result.message);
+        assert_eq!(data["steps"][1]["status"], "skipped");
+
+        let guard = state.lock().unwrap();
+        let verification = guard.last_edit().unwrap().verification.clone().unwrap();
+        assert!(!verification.passed);
+        assert!(guard
+            .get_summary()
+            .contains("(1 file(s), verification failed)"));
+    }
+
+    #[test]
+    fn test_verify_change_runs_linked_tests() {
+        let temp_dir = TempDir::new().unwrap();
+        let root = temp_dir.path().canonicalize().unwrap();
+        fs::write(root.join("pytest.ini"), "[pytest]\n").unwrap();
+        fs::write(root.join("calc.py"), "def add(a, b):\n    return a + b\n").unwrap();
+        fs::write(
+            root.join("test_calc.py"),
+            "from calc import add\n\ndef test_add():\n    assert add(1, 1) == 2\n",
+        )
+        .unwrap();
+        fs::write(
+            root.join("cats.toml"),
+            "[verify]\ncheck = { py = \"test -f {path}\" }\n",
+        )
+        .unwrap();
+
+        let state = Arc::new(Mutex::new(ToolState::new()));
+        state.lock().unwrap().set_working_directory(&root);
+        let jobs = VerifyChangeTool::test_jobs(&[root.join("calc.py")], &root);
+        assert_eq!(jobs.len(), 1);
+        assert_eq!(jobs[0].0.command, "python -m pytest 'test_calc.py'");
+
+        let (result, data) = verify(&state, &["calc.py", "--steps=syntax,check"]);
+        assert!(result.success, "{}", result.message);
+        assert_eq!(
+            data["steps"][1]["commands"][0],
+            format!("test -f {}", root.join("calc.py").display())
+        );
+        assert_eq!(data["operation"], serde_json::Value::Null);
+
+        let args = ToolArgs::from_args(&["--steps=lint"]);
+        assert!(VerifyChangeTool::new().validate_args(&args).is_err());
+    }
+}
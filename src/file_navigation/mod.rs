@@ -2,11 +2,17 @@
 //!
 //! Provides windowed file viewing, line navigation, and file creation
 
-use crate::core::{Tool, ToolArgs, ToolError, ToolResult};
-use crate::state::ToolState;
-use anyhow::Result;
-use std::fs;
-use std::path::PathBuf;
+use crate::core::{
+    path_arguments, DangerLevel, Tool, ToolArgs, ToolCategory, ToolError, ToolResult,
+};
+use crate::editing::writer;
+use crate::messages::{MessageKey, MessageStyle};
+use crate::profiling;
+use crate::state::{session_path, NumberedLines, ToolState};
+use crate::utils::suggest::path_not_found;
+use anyhow::{Context, Result};
+use std::fmt::{self, Write as _};
+use std::path::{Path, PathBuf};
 use std::sync::{Arc, Mutex};
 
 /// Default window size used by other windowing utilities
@@ -15,6 +21,21 @@ pub const DEFAULT_WINDOW_SIZE: usize = 100;
 /// Default window size specifically for the "open" tool (configurable via simpaticoder.toml)
 pub const OPEN_TOOL_DEFAULT_WINDOW_SIZE: usize = 1000;
 
+/// Read an inclusive, 1-based range of lines from a file.
+///
+/// The range is clamped to the file; each returned entry is `(line_number, text)`.
+pub fn read_range(path: &Path, start: usize, end: usize) -> std::io::Result<Vec<(usize, String)>> {
+    let content = profiling::read_to_string(path)?;
+    let start = start.max(1);
+    Ok(content
+        .lines()
+        .enumerate()
+        .skip(start - 1)
+        .take(end.saturating_sub(start) + 1)
+        .map(|(i, line)| (i + 1, line.to_string()))
+        .collect())
+}
+
 /// Windowed file representation following SWE-agent pattern
 #[derive(Debug, Clone)]
 pub struct WindowedFile {
@@ -27,7 +48,7 @@ pub struct WindowedFile {
 impl WindowedFile {
     /// Create a new windowed file
     pub fn new(path: PathBuf, window_size: Option<usize>) -> Result<Self, ToolError> {
-        let content = fs::read_to_string(&path).map_err(|_| ToolError::FileNotFound {
+        let content = profiling::read_to_string(&path).map_err(|_| ToolError::FileNotFound {
             path: path.to_string_lossy().to_string(),
         })?;
 
@@ -53,39 +74,50 @@ impl WindowedFile {
 
     /// Get current window content with line numbers
     pub fn get_window_display(&self) -> String {
+        self.window_display_with(&MessageStyle::standard())
+    }
+
+    /// Current window content with line numbers, its rules rendered with `style`
+    pub fn window_display_with(&self, style: &MessageStyle) -> String {
+        let (start, end) = self.window_bounds();
+        let lines = NumberedLines::new(&self.content[start..end], start + 1);
+        let mut result = String::with_capacity(lines.estimated_len() + 320);
+        let _ = self.write_window(&mut result, style);
+        result
+    }
+
+    /// Line indices of the current window, clamped to the content
+    fn window_bounds(&self) -> (usize, usize) {
         let end = std::cmp::min(self.window_start + self.window_size, self.content.len());
-        let window_content = &self.content[self.window_start..end];
-
-        let mut result = String::new();
-        result.push_str(&format!(
-            "File: {} ({} lines)\n",
-            self.path.display(),
-            self.content.len()
-        ));
-        result.push_str(&format!(
-            "Lines {}-{} of {}:\n",
-            self.window_start + 1,
-            end,
-            self.content.len()
-        ));
-        result.push_str(&"-".repeat(80));
-        result.push('\n');
-
-        for (i, line) in window_content.iter().enumerate() {
-            result.push_str(&format!("{:4} | {}\n", self.window_start + i + 1, line));
+        (self.window_start.min(end), end)
+    }
+
+    fn write_window(&self, f: &mut impl fmt::Write, style: &MessageStyle) -> fmt::Result {
+        let (start, end) = self.window_bounds();
+        let total = self.content.len();
+        let rule = style.render_line(MessageKey::WindowRule, &[]);
+        writeln!(f, "File: {} ({} lines)", self.path.display(), total)?;
+        writeln!(f, "Lines {}-{} of {}:", start + 1, end, total)?;
+        f.write_str(&rule)?;
+        let lines = NumberedLines::new(&self.content[start..end], start + 1);
+        if !lines.is_empty() {
+            writeln!(f, "{}", lines)?;
         }
+        f.write_str(&rule)?;
 
-        result.push_str(&"-".repeat(80));
-        result.push('\n');
-
-        if self.window_start > 0 {
-            result.push_str("(Use scroll_up to see previous lines)\n");
+        if start > 0 {
+            writeln!(f, "(Use scroll_up to see previous lines)")?;
         }
-        if end < self.content.len() {
-            result.push_str("(Use scroll_down to see more lines)\n");
+        if end < total {
+            writeln!(f, "(Use scroll_down to see more lines)")?;
         }
+        Ok(())
+    }
+}
 
-        result
+impl fmt::Display for WindowedFile {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        self.write_window(f, &MessageStyle::standard())
     }
 }
 
@@ -135,6 +167,14 @@ impl Tool for OpenTool {
         r#"open "<path>" [<line_number>]"#
     }
 
+    fn category(&self) -> ToolCategory {
+        ToolCategory::Navigation
+    }
+
+    fn danger_level(&self) -> DangerLevel {
+        DangerLevel::ReadOnly
+    }
+
     fn validate_args(&self, args: &ToolArgs) -> Result<(), ToolError> {
         if args.is_empty() {
             return Err(ToolError::InvalidArgs {
@@ -146,11 +186,14 @@ impl Tool for OpenTool {
 
     fn execute(&mut self, args: &ToolArgs, state: &Arc<Mutex<ToolState>>) -> Result<ToolResult> {
         let path = args.get_arg(0).unwrap();
-        let path_buf = PathBuf::from(path);
+        let path_buf = session_path(state, path);
 
         // Check if path exists
         if !path_buf.exists() {
-            return Ok(ToolResult::error(format!("File not found: {}", path)));
+            return Ok(path_not_found(
+                format!("File not found: {}", path),
+                &path_buf,
+            ));
         }
 
         // Get line number if provided
@@ -161,46 +204,63 @@ impl Tool for OpenTool {
             let mut state_guard = state
                 .lock()
                 .map_err(|e| anyhow::anyhow!("Failed to lock state: {}", e))?;
-            if let Some(current_file) = &state_guard.current_file {
-                if current_file == &path_buf {
-                    // File is already open
-                    if let Some(line_num) = line_number {
-                        // Move current open file to requested line -- do mutations in an inner scope to avoid holding
-                        // a mutable borrow while also calling state_guard.push_history later.
-                        let mut moved_window: Option<Vec<String>> = None;
-                        let mut total_lines: Option<usize> = None;
-
-                        if let Some(file_state) = state_guard.get_current_file_state_mut() {
-                            file_state.goto_line(line_num);
-                            total_lines = Some(file_state.total_lines());
-                            moved_window = Some(file_state.get_window_with_line_numbers());
-                        }
+            if state_guard.is_current_file(&path_buf) {
+                // File is already open
+                if let Some(line_num) = line_number {
+                    // Move current open file to requested line -- do mutations in an inner scope to avoid holding
+                    // a mutable borrow while also calling state_guard.push_history later.
+                    let mut moved_window: Option<Vec<String>> = None;
+                    let mut total_lines: Option<usize> = None;
+
+                    if let Some(file_state) = state_guard.get_current_file_state_mut() {
+                        file_state.goto_line(line_num);
+                        total_lines = Some(file_state.total_lines());
+                        moved_window = Some(file_state.get_window_with_line_numbers());
+                    }
 
-                        // If requested line is beyond EOF, return a warning similar to open-path behavior
-                        if let Some(tl) = total_lines {
-                            if line_num > tl {
-                                return Ok(ToolResult::success_with_data(
-                                    format!("File {} is already open (moved to line {} of {}). Warning: Line number {} is greater than total lines.",
-                                        path, line_num, tl, line_num),
-                                    serde_json::json!({
-                                        "path": path,
-                                        "already_open": true,
-                                        "total_lines": tl,
-                                        "current_window": moved_window.unwrap_or_default()
-                                    })
-                                ));
-                            }
+                    // If requested line is beyond EOF, return a warning similar to open-path behavior
+                    if let Some(tl) = total_lines {
+                        if line_num > tl {
+                            return Ok(ToolResult::success_with_data(
+                                format!("File {} is already open (moved to line {} of {}). Warning: Line number {} is greater than total lines.",
+                                    path, line_num, tl, line_num),
+                                serde_json::json!({
+                                    "path": path,
+                                    "already_open": true,
+                                    "total_lines": tl,
+                                    "current_window": moved_window.unwrap_or_default()
+                                })
+                            ));
                         }
+                    }
 
-                        // Safe to push history now; mutable borrow from get_current_file_state_mut() has ended
-                        state_guard.push_history(format!("Moved to line {} in {}", line_num, path));
-
-                        let current_window = moved_window.unwrap_or_default();
+                    // Safe to push history now; mutable borrow from get_current_file_state_mut() has ended
+                    state_guard.push_history(format!("Moved to line {} in {}", line_num, path));
+
+                    let current_window = moved_window.unwrap_or_default();
+                    let result_message = format!(
+                        "File {} is already open (moved to line {}):\n\n{}",
+                        path,
+                        line_num,
+                        current_window.join("\n")
+                    );
+
+                    return Ok(ToolResult::success_with_data(
+                        result_message,
+                        serde_json::json!({
+                            "path": path,
+                            "already_open": true,
+                            "total_lines": total_lines.unwrap_or(0),
+                            "window_content": current_window
+                        }),
+                    ));
+                } else {
+                    if let Some(file_state) = state_guard.get_current_file_state() {
+                        let current_window = file_state.get_window_with_line_numbers();
                         let result_message = format!(
-                            "File {} is already open (moved to line {}):\n\n{}",
+                            "File {} is already open (showing current window):\n\n{}",
                             path,
-                            line_num,
-                            current_window.join("\n")
+                            file_state.window_display()
                         );
 
                         return Ok(ToolResult::success_with_data(
@@ -208,37 +268,17 @@ impl Tool for OpenTool {
                             serde_json::json!({
                                 "path": path,
                                 "already_open": true,
-                                "total_lines": total_lines.unwrap_or(0),
+                                "total_lines": file_state.total_lines(),
                                 "window_content": current_window
                             }),
                         ));
-                    } else {
-                        if let Some(file_state) = state_guard.get_current_file_state() {
-                            let current_window = file_state.get_window_with_line_numbers();
-                            let result_message = format!(
-                                "File {} is already open (showing current window):\n\n{}",
-                                path,
-                                current_window.join("\n")
-                            );
-
-                            return Ok(ToolResult::success_with_data(
-                                result_message,
-                                serde_json::json!({
-                                    "path": path,
-                                    "already_open": true,
-                                    "total_lines": file_state.total_lines(),
-                                    "window_content": current_window
-                                }),
-                            ));
-                        }
                     }
                 }
             }
         }
 
         // Read file content
-        let content = fs::read_to_string(&path_buf)
-            .map_err(|e| anyhow::anyhow!("Failed to read file: {}", e))?;
+        let content = profiling::read_to_string(&path_buf).context("Failed to read file")?;
         let lines: Vec<String> = content.lines().map(|s| s.to_string()).collect();
 
         // Get line number if provided
@@ -249,9 +289,7 @@ impl Tool for OpenTool {
             .default_window_size
             .unwrap_or(OPEN_TOOL_DEFAULT_WINDOW_SIZE);
 
-        // Create windowed file with the configured window size
-        let windowed_file =
-            WindowedFile::from_content(path_buf.clone(), lines.clone(), Some(open_window));
+        let total_lines = lines.len();
 
         // Update state
         {
@@ -281,30 +319,25 @@ impl Tool for OpenTool {
             state_guard.push_history(format!("Opened file: {}", path));
         }
 
-        // Get the current window content for immediate display
+        // Render the current window straight into the message
         let state_guard = state
             .lock()
             .map_err(|e| anyhow::anyhow!("Failed to lock state: {}", e))?;
-        let current_window = if let Some(file_state) = state_guard.get_current_file_state() {
-            file_state.get_window_with_line_numbers()
-        } else {
-            vec![]
-        };
+        let mut result_message = format!("Opened file: {} ({} lines)\n\n", path, total_lines);
+        let mut current_window = Vec::new();
+        if let Some(file_state) = state_guard.get_current_file_state() {
+            let window = file_state.window_display();
+            result_message.reserve(window.estimated_len());
+            let _ = write!(result_message, "{}", window);
+            current_window = file_state.get_window_with_line_numbers();
+        }
         drop(state_guard);
 
-        // Create result message with file content
-        let result_message = format!(
-            "Opened file: {} ({} lines)\n\n{}",
-            path,
-            windowed_file.content.len(),
-            current_window.join("\n")
-        );
-
         Ok(ToolResult::success_with_data(
             result_message,
             serde_json::json!({
                 "path": path,
-                "total_lines": windowed_file.content.len(),
+                "total_lines": total_lines,
                 "window_content": current_window
             }),
         ))
@@ -354,6 +387,14 @@ impl Tool for GotoTool {
         "goto <line_number>"
     }
 
+    fn category(&self) -> ToolCategory {
+        ToolCategory::Navigation
+    }
+
+    fn danger_level(&self) -> DangerLevel {
+        DangerLevel::ReadOnly
+    }
+
     fn validate_args(&self, args: &ToolArgs) -> Result<(), ToolError> {
         if args.is_empty() {
             return Err(ToolError::InvalidArgs {
@@ -404,6 +445,12 @@ impl Tool for GotoTool {
 
             file_state.goto_line(line_number);
             let display = file_state.get_window_with_line_numbers();
+            let result_message = format!(
+                "Moved to line {} in {}\n\n{}",
+                line_number,
+                current_file.display(),
+                file_state.window_display()
+            );
 
             state_guard.push_history(format!(
                 "Moved to line {} in {}",
@@ -411,13 +458,6 @@ impl Tool for GotoTool {
                 current_file.display()
             ));
 
-            let result_message = format!(
-                "Moved to line {} in {}\n\n{}",
-                line_number,
-                current_file.display(),
-                display.join("\n")
-            );
-
             Ok(ToolResult::success_with_data(
                 result_message,
                 serde_json::json!({
@@ -478,6 +518,19 @@ impl Tool for ScrollTool {
         &self.name
     }
 
+    fn category(&self) -> ToolCategory {
+        ToolCategory::Navigation
+    }
+
+    fn danger_level(&self) -> DangerLevel {
+        DangerLevel::ReadOnly
+    }
+
+    // Every call moves the window further
+    fn is_idempotent(&self) -> bool {
+        false
+    }
+
     fn validate_args(&self, _args: &ToolArgs) -> Result<(), ToolError> {
         Ok(()) // Scroll tools take no arguments
     }
@@ -510,23 +563,22 @@ impl Tool for ScrollTool {
 
             let new_start = file_state.window_start;
             let display = file_state.get_window_with_line_numbers();
-
             let direction = if self.up { "up" } else { "down" };
-            state_guard.push_history(format!(
-                "Scrolled {} in {}",
-                direction,
-                current_file.display()
-            ));
-
             let result_message = format!(
                 "Scrolled {} from line {} to line {} in {}\n\n{}",
                 direction,
                 old_start + 1,
                 new_start + 1,
                 current_file.display(),
-                display.join("\n")
+                file_state.window_display()
             );
 
+            state_guard.push_history(format!(
+                "Scrolled {} in {}",
+                direction,
+                current_file.display()
+            ));
+
             Ok(ToolResult::success_with_data(
                 result_message,
                 serde_json::json!({
@@ -577,6 +629,14 @@ impl Tool for CreateTool {
         "create <filename>"
     }
 
+    fn category(&self) -> ToolCategory {
+        ToolCategory::Editing
+    }
+
+    fn danger_level(&self) -> DangerLevel {
+        DangerLevel::ModifiesFiles
+    }
+
     fn validate_args(&self, args: &ToolArgs) -> Result<(), ToolError> {
         if args.is_empty() {
             return Err(ToolError::InvalidArgs {
@@ -586,9 +646,13 @@ impl Tool for CreateTool {
         Ok(())
     }
 
+    fn accessed_paths(&self, args: &ToolArgs) -> Vec<PathBuf> {
+        path_arguments(&self.named_args(args), |name| name == "filename")
+    }
+
     fn execute(&mut self, args: &ToolArgs, state: &Arc<Mutex<ToolState>>) -> Result<ToolResult> {
         let filename = args.get_arg(0).unwrap();
-        let path_buf = PathBuf::from(filename);
+        let path_buf = session_path(state, filename);
 
         // Check if file already exists
         if path_buf.exists() {
@@ -599,23 +663,22 @@ impl Tool for CreateTool {
         }
 
         // Create the file
-        fs::write(&path_buf, "").map_err(|e| anyhow::anyhow!("Failed to create file: {}", e))?;
-
-        // Open the new file
-        let content = vec![]; // Empty file
+        writer::write_file(state, &path_buf, "")
+            .map_err(|e| anyhow::anyhow!("Failed to create file: {}", e))?;
 
         // Update state
-        {
+        let style = {
             let mut state_guard = state
                 .lock()
                 .map_err(|e| anyhow::anyhow!("Failed to lock state: {}", e))?;
-            state_guard.open_file(path_buf.clone(), content.clone(), DEFAULT_WINDOW_SIZE)?;
+            state_guard.open_file(path_buf.clone(), Vec::new(), DEFAULT_WINDOW_SIZE)?;
             state_guard.push_history(format!("Created file: {}", filename));
-        }
+            state_guard.message_style.clone()
+        };
 
         // Create windowed file for display
-        let windowed_file = WindowedFile::from_content(path_buf, content, None);
-        let display = windowed_file.get_window_display();
+        let windowed_file = WindowedFile::from_content(path_buf, Vec::new(), None);
+        let display = windowed_file.window_display_with(&style);
 
         Ok(ToolResult::success_with_data(
             format!("Created and opened file: {}", filename),
@@ -644,6 +707,7 @@ impl Tool for CreateTool {
 #[cfg(test)]
 mod tests {
     use super::*;
+    use std::fs;
     use std::io::Write;
     use tempfile::TempDir;
 
@@ -654,6 +718,16 @@ mod tests {
         file_path
     }
 
+    #[test]
+    fn test_read_range_clamps() {
+        let temp_dir = TempDir::new().unwrap();
+        let file_path = create_test_file(&temp_dir, "range.txt", "a\nb\nc");
+
+        let lines = read_range(&file_path, 2, 10).unwrap();
+        assert_eq!(lines, vec![(2, "b".to_string()), (3, "c".to_string())]);
+        assert_eq!(read_range(&file_path, 0, 1).unwrap().len(), 1);
+    }
+
     #[test]
     fn test_windowed_file_creation() {
         let temp_dir = TempDir::new().unwrap();
@@ -664,6 +738,14 @@ mod tests {
         assert_eq!(windowed_file.content.len(), 5);
         assert_eq!(windowed_file.window_size, 3);
         assert_eq!(windowed_file.window_start, 0);
+        assert_eq!(
+            windowed_file.get_window_display(),
+            format!(
+                "File: {} (5 lines)\nLines 1-3 of 5:\n{rule}\n   1 | line1\n   2 | line2\n   3 | line3\n{rule}\n(Use scroll_down to see more lines)\n",
+                windowed_file.path.display(),
+                rule = "-".repeat(80)
+            )
+        );
     }
 
     #[test]
@@ -685,6 +767,43 @@ mod tests {
         assert_eq!(state_guard.open_files.len(), 1);
     }
 
+    #[test]
+    fn test_relative_and_absolute_paths_share_one_open_file() {
+        let temp_dir = TempDir::new().unwrap();
+        let file_path = create_test_file(&temp_dir, "test.txt", "line1\nline2\nline3");
+        // The same file, spelled relative to the process directory
+        let cwd = std::env::current_dir().unwrap();
+        let mut relative = PathBuf::new();
+        for _ in cwd.components().skip(1) {
+            relative.push("..");
+        }
+        relative.push(file_path.strip_prefix("/").unwrap());
+        let relative = relative.to_str().unwrap();
+
+        let state = Arc::new(Mutex::new(ToolState::new()));
+        let mut open = OpenTool::new();
+        open.execute(&ToolArgs::from_args(&[file_path.to_str().unwrap()]), &state)
+            .unwrap();
+        let reopened = open
+            .execute(&ToolArgs::from_args(&[relative]), &state)
+            .unwrap();
+        assert_eq!(reopened.data.unwrap()["already_open"], true);
+
+        // An edit through the relative spelling refreshes the open file
+        let mut replace = crate::editing::ReplaceTextTool::new();
+        let result = replace
+            .execute(
+                &ToolArgs::from_args(&[relative, "line2", "changed"]),
+                &state,
+            )
+            .unwrap();
+        assert!(result.success, "{}", result.message);
+        let state_guard = state.lock().unwrap();
+        assert_eq!(state_guard.open_files.len(), 1);
+        let content = &state_guard.get_current_file_state().unwrap().content;
+        assert_eq!(content[1], "changed");
+    }
+
     #[test]
     fn test_goto_tool() {
         let mut tool = GotoTool::new();
@@ -762,17 +881,18 @@ mod tests {
     #[test]
     fn test_create_tool() {
         let temp_dir = TempDir::new().unwrap();
-        std::env::set_current_dir(temp_dir.path()).unwrap();
+        // Changing the process directory would race with tests that use relative paths
+        let new_file = temp_dir.path().join("new_file.txt");
 
         let mut tool = CreateTool::new();
         let state = Arc::new(Mutex::new(ToolState::new()));
-        let args = ToolArgs::from_args(&["new_file.txt"]);
+        let args = ToolArgs::from_args(&[new_file.to_str().unwrap()]);
 
         let result = tool.execute(&args, &state).unwrap();
         assert!(result.success);
 
         // Verify file was created
-        assert!(PathBuf::from("new_file.txt").exists());
+        assert!(new_file.exists());
 
         // Verify state was updated
         let state_guard = state.lock().unwrap();
@@ -0,0 +1,931 @@
+//! Issue and pull request tools for GitHub and GitLab (`net` feature)
+//!
+//! `fetch_issue` and `fetch_pr` pull a task's description and review context straight
+//! from the forge: title, body, labels and, for pull requests, the changed files with
+//! their line counts. The repository comes from the `[forge]` table of `cats.toml`, a
+//! `repository` argument or the `origin` remote of the working directory, and the token
+//! from the environment variable `token_env` names (`GITHUB_TOKEN` or `GITLAB_TOKEN` by
+//! default). Public repositories work without one. Both tools only read.
+//!
+//! `create_pr` closes the loop the other way: it commits what the agent changed, pushes
+//! the branch to `origin` and opens a pull request titled and described from the
+//! summary passed to `submit`. It needs a token.
+
+pub mod client;
+
+use crate::config::ForgeConfig;
+use crate::core::{DangerLevel, Tool, ToolArgs, ToolCategory, ToolError, ToolResult};
+use crate::state::ToolState;
+use crate::vcs;
+use anyhow::Result;
+use client::ForgeClient;
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+use std::sync::{Arc, Mutex};
+
+/// Code hosts the forge tools can read from
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum ForgeProvider {
+    Github,
+    Gitlab,
+}
+
+impl ForgeProvider {
+    /// Parse a provider name as accepted by `[forge] provider`
+    pub fn from_name(name: &str) -> Option<Self> {
+        match name.to_lowercase().as_str() {
+            "github" => Some(Self::Github),
+            "gitlab" => Some(Self::Gitlab),
+            _ => None,
+        }
+    }
+
+    /// Canonical provider name
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            Self::Github => "github",
+            Self::Gitlab => "gitlab",
+        }
+    }
+
+    /// API of the public service
+    pub fn default_api_url(&self) -> &'static str {
+        match self {
+            Self::Github => "https://api.github.com",
+            Self::Gitlab => "https://gitlab.com/api/v4",
+        }
+    }
+
+    /// API of the instance at `host`, which may be self-hosted
+    pub fn api_url_for_host(&self, host: &str) -> String {
+        match (self, host) {
+            (Self::Github, "github.com") | (Self::Gitlab, "gitlab.com") => {
+                self.default_api_url().to_string()
+            }
+            (Self::Github, host) => format!("https://{}/api/v3", host),
+            (Self::Gitlab, host) => format!("https://{}/api/v4", host),
+        }
+    }
+
+    /// Environment variable read for the token when `[forge] token_env` is unset
+    pub fn default_token_env(&self) -> &'static str {
+        match self {
+            Self::Github => "GITHUB_TOKEN",
+            Self::Gitlab => "GITLAB_TOKEN",
+        }
+    }
+}
+
+/// An issue and its description
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct Issue {
+    pub number: u64,
+    pub title: String,
+    pub body: String,
+    /// `open` or `closed`
+    pub state: String,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub author: Option<String>,
+    pub labels: Vec<String>,
+    pub url: String,
+    /// Number of comments
+    pub comments: u64,
+    /// GitHub lists pull requests as issues too
+    #[serde(skip_serializing_if = "std::ops::Not::not")]
+    pub is_pull_request: bool,
+}
+
+/// A file changed by a pull request
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct ChangedFile {
+    pub path: String,
+    /// Path before a rename
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub previous_path: Option<String>,
+    /// `added`, `modified`, `removed` or `renamed`
+    pub status: String,
+    pub additions: u64,
+    pub deletions: u64,
+}
+
+/// A pull request (merge request on GitLab) and a summary of its diff
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct PullRequest {
+    pub number: u64,
+    pub title: String,
+    pub body: String,
+    /// `open`, `closed` or `merged`
+    pub state: String,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub author: Option<String>,
+    pub labels: Vec<String>,
+    pub url: String,
+    /// Branch the change goes into
+    pub base: String,
+    /// Branch the change comes from
+    pub head: String,
+    pub files: Vec<ChangedFile>,
+    pub additions: u64,
+    pub deletions: u64,
+}
+
+fn text(value: &Value, pointer: &str) -> String {
+    value
+        .pointer(pointer)
+        .and_then(Value::as_str)
+        .unwrap_or_default()
+        .to_string()
+}
+
+fn number(value: &Value, pointer: &str) -> u64 {
+    value
+        .pointer(pointer)
+        .and_then(Value::as_u64)
+        .unwrap_or_default()
+}
+
+/// Label names, from GitHub's label objects or GitLab's plain strings
+fn labels(value: &Value) -> Vec<String> {
+    value
+        .get("labels")
+        .and_then(Value::as_array)
+        .into_iter()
+        .flatten()
+        .filter_map(|label| {
+            label
+                .as_str()
+                .or_else(|| label.get("name").and_then(Value::as_str))
+        })
+        .map(str::to_string)
+        .collect()
+}
+
+/// An issue from `GET /repos/{repo}/issues/{number}`
+pub fn parse_github_issue(issue: &Value) -> Issue {
+    Issue {
+        number: number(issue, "/number"),
+        title: text(issue, "/title"),
+        body: text(issue, "/body"),
+        state: text(issue, "/state"),
+        author: issue
+            .pointer("/user/login")
+            .and_then(Value::as_str)
+            .map(str::to_string),
+        labels: labels(issue),
+        url: text(issue, "/html_url"),
+        comments: number(issue, "/comments"),
+        is_pull_request: issue.get("pull_request").is_some(),
+    }
+}
+
+/// A pull request from `GET /repos/{repo}/pulls/{number}` and its `/files` listing
+pub fn parse_github_pull(pull: &Value, files: &[Value]) -> PullRequest {
+    let merged = pull.get("merged").and_then(Value::as_bool) == Some(true)
+        || pull
+            .get("merged_at")
+            .is_some_and(|merged| !merged.is_null());
+    let files: Vec<ChangedFile> = files
+        .iter()
+        .map(|file| ChangedFile {
+            path: text(file, "/filename"),
+            previous_path: file
+                .get("previous_filename")
+                .and_then(Value::as_str)
+                .map(str::to_string),
+            status: text(file, "/status"),
+            additions: number(file, "/additions"),
+            deletions: number(file, "/deletions"),
+        })
+        .collect();
+    PullRequest {
+        number: number(pull, "/number"),
+        title: text(pull, "/title"),
+        body: text(pull, "/body"),
+        state: if merged {
+            "merged".to_string()
+        } else {
+            text(pull, "/state")
+        },
+        author: pull
+            .pointer("/user/login")
+            .and_then(Value::as_str)
+            .map(str::to_string),
+        labels: labels(pull),
+        url: text(pull, "/html_url"),
+        base: text(pull, "/base/ref"),
+        head: text(pull, "/head/ref"),
+        additions: files.iter().map(|file| file.additions).sum(),
+        deletions: files.iter().map(|file| file.deletions).sum(),
+        files,
+    }
+}
+
+/// An issue from `GET /projects/{id}/issues/{iid}`
+pub fn parse_gitlab_issue(issue: &Value) -> Issue {
+    Issue {
+        number: number(issue, "/iid"),
+        title: text(issue, "/title"),
+        body: text(issue, "/description"),
+        // GitLab says `opened`
+        state: text(issue, "/state").replace("opened", "open"),
+        author: issue
+            .pointer("/author/username")
+            .and_then(Value::as_str)
+            .map(str::to_string),
+        labels: labels(issue),
+        url: text(issue, "/web_url"),
+        comments: number(issue, "/user_notes_count"),
+        is_pull_request: false,
+    }
+}
+
+/// A merge request from `GET /projects/{id}/merge_requests/{iid}/changes`, with line
+/// counts taken from each file's diff
+pub fn parse_gitlab_merge_request(merge_request: &Value) -> PullRequest {
+    let files: Vec<ChangedFile> = merge_request
+        .get("changes")
+        .and_then(Value::as_array)
+        .into_iter()
+        .flatten()
+        .map(|change| {
+            let flag = |key: &str| change.get(key).and_then(Value::as_bool) == Some(true);
+            let path = text(change, "/new_path");
+            let old_path = text(change, "/old_path");
+            let status = if flag("new_file") {
+                "added"
+            } else if flag("deleted_file") {
+                "removed"
+            } else if flag("renamed_file") {
+                "renamed"
+            } else {
+                "modified"
+            };
+            let diff = text(change, "/diff");
+            let count = |marker: char| {
+                diff.lines()
+                    .filter(|line| line.starts_with(marker))
+                    .filter(|line| !line.starts_with("+++") && !line.starts_with("---"))
+                    .count() as u64
+            };
+            ChangedFile {
+                previous_path: (old_path != path && !old_path.is_empty()).then_some(old_path),
+                path,
+                status: status.to_string(),
+                additions: count('+'),
+                deletions: count('-'),
+            }
+        })
+        .collect();
+    PullRequest {
+        number: number(merge_request, "/iid"),
+        title: text(merge_request, "/title"),
+        body: text(merge_request, "/description"),
+        state: text(merge_request, "/state").replace("opened", "open"),
+        author: merge_request
+            .pointer("/author/username")
+            .and_then(Value::as_str)
+            .map(str::to_string),
+        labels: labels(merge_request),
+        url: text(merge_request, "/web_url"),
+        base: text(merge_request, "/target_branch"),
+        head: text(merge_request, "/source_branch"),
+        additions: files.iter().map(|file| file.additions).sum(),
+        deletions: files.iter().map(|file| file.deletions).sum(),
+        files,
+    }
+}
+
+/// The number in `123`, `#123`, `!123` or an issue or pull request URL
+pub fn parse_number(id: &str) -> Option<u64> {
+    let id = id.trim().trim_end_matches('/');
+    let last = id.rsplit('/').next().unwrap_or(id);
+    last.trim_start_matches(['#', '!']).parse().ok()
+}
+
+/// Shared argument handling of the two tools
+fn validate_number(name: &str, args: &ToolArgs) -> Result<(), ToolError> {
+    let id = args.get_named_arg("id").or_else(|| args.get_arg(0));
+    match id {
+        Some(id) if parse_number(id).is_some() => Ok(()),
+        Some(id) => Err(ToolError::InvalidArgs {
+            message: format!("'{}' is not an issue or pull request number", id),
+        }),
+        None => Err(ToolError::InvalidArgs {
+            message: format!("Usage: {} <id> [--repository=<owner/name>]", name),
+        }),
+    }
+}
+
+/// The client for a call: the configured forge, with `repository` overridden by the
+/// argument of the same name
+fn client_for(
+    config: &ForgeConfig,
+    args: &ToolArgs,
+    state: &Arc<Mutex<ToolState>>,
+) -> Result<ForgeClient> {
+    let dir = state
+        .lock()
+        .map_err(|e| anyhow::anyhow!("Failed to lock state: {}", e))?
+        .working_directory
+        .clone();
+    let mut config = config.clone();
+    if let Some(repository) = args.get_named_arg("repository") {
+        config.repository = Some(repository.clone());
+    }
+    ForgeClient::from_config(&config, &dir)
+}
+
+fn id_arg(args: &ToolArgs) -> u64 {
+    args.get_named_arg("id")
+        .or_else(|| args.get_arg(0))
+        .and_then(|id| parse_number(id))
+        .unwrap_or_default()
+}
+
+fn parameters_schema(kind: &str) -> Value {
+    serde_json::json!({
+        "type": "object",
+        "properties": {
+            "id": {
+                "type": ["integer", "string"],
+                "description": format!("{} number, e.g. 123 or #123, or its URL", kind)
+            },
+            "repository": {
+                "type": "string",
+                "description": "owner/name of the repository (defaults to [forge] in cats.toml or the origin remote)"
+            }
+        },
+        "required": ["id"]
+    })
+}
+
+/// Tool reading an issue's title, description and labels
+pub struct FetchIssueTool {
+    name: String,
+    config: ForgeConfig,
+}
+
+impl FetchIssueTool {
+    pub fn new(config: ForgeConfig) -> Self {
+        Self {
+            name: "fetch_issue".to_string(),
+            config,
+        }
+    }
+}
+
+impl Default for FetchIssueTool {
+    fn default() -> Self {
+        Self::new(ForgeConfig::default())
+    }
+}
+
+impl Tool for FetchIssueTool {
+    fn name(&self) -> &str {
+        &self.name
+    }
+
+    fn description(&self) -> &str {
+        "Fetch a GitHub or GitLab issue: title, description, labels, state and author"
+    }
+
+    fn signature(&self) -> &str {
+        "fetch_issue <id> [--repository=<owner/name>]"
+    }
+
+    fn category(&self) -> ToolCategory {
+        ToolCategory::Vcs
+    }
+
+    fn danger_level(&self) -> DangerLevel {
+        DangerLevel::ReadOnly
+    }
+
+    fn validate_args(&self, args: &ToolArgs) -> Result<(), ToolError> {
+        validate_number(&self.name, args)
+    }
+
+    fn execute(&mut self, args: &ToolArgs, state: &Arc<Mutex<ToolState>>) -> Result<ToolResult> {
+        let number = id_arg(args);
+        let fetched = client_for(&self.config, args, state)
+            .and_then(|client| Ok((client.issue(number)?, client)));
+        let (issue, client) = match fetched {
+            Ok(fetched) => fetched,
+            Err(e) => return Ok(ToolResult::error(format!("{:#}", e))),
+        };
+
+        let mut message = format!(
+            "Issue #{} in {} ({}{}): {}\n",
+            issue.number,
+            client.repository,
+            issue.state,
+            issue
+                .author
+                .as_ref()
+                .map(|author| format!(", by {}", author))
+                .unwrap_or_default(),
+            issue.title
+        );
+        if issue.is_pull_request {
+            message.push_str("This issue is a pull request; fetch_pr shows its changes\n");
+        }
+        if !issue.labels.is_empty() {
+            message.push_str(&format!("Labels: {}\n", issue.labels.join(", ")));
+        }
+        message.push_str(&format!(
+            "URL: {}\nComments: {}\n\n{}",
+            issue.url,
+            issue.comments,
+            if issue.body.trim().is_empty() {
+                "(no description)"
+            } else {
+                issue.body.trim()
+            }
+        ));
+
+        let mut data = serde_json::to_value(&issue)?;
+        data["provider"] = serde_json::json!(client.provider);
+        data["repository"] = serde_json::json!(client.repository);
+        Ok(ToolResult::success_with_data(message, data))
+    }
+
+    fn get_parameters_schema(&self) -> Value {
+        parameters_schema("Issue")
+    }
+}
+
+/// Tool reading a pull request's description and a summary of its diff
+pub struct FetchPrTool {
+    name: String,
+    config: ForgeConfig,
+}
+
+impl FetchPrTool {
+    pub fn new(config: ForgeConfig) -> Self {
+        Self {
+            name: "fetch_pr".to_string(),
+            config,
+        }
+    }
+
+    fn format_file(file: &ChangedFile) -> String {
+        let status = match file.status.as_str() {
+            "added" => "A",
+            "removed" => "D",
+            "renamed" => "R",
+            _ => "M",
+        };
+        let path = match &file.previous_path {
+            Some(previous) => format!("{} -> {}", previous, file.path),
+            None => file.path.clone(),
+        };
+        format!(
+            "  {} {} (+{} -{})",
+            status, path, file.additions, file.deletions
+        )
+    }
+}
+
+impl Default for FetchPrTool {
+    fn default() -> Self {
+        Self::new(ForgeConfig::default())
+    }
+}
+
+impl Tool for FetchPrTool {
+    fn name(&self) -> &str {
+        &self.name
+    }
+
+    fn description(&self) -> &str {
+        "Fetch a GitHub pull request or GitLab merge request: title, description, labels, branches and the changed files with line counts"
+    }
+
+    fn signature(&self) -> &str {
+        "fetch_pr <id> [--repository=<owner/name>]"
+    }
+
+    fn category(&self) -> ToolCategory {
+        ToolCategory::Vcs
+    }
+
+    fn danger_level(&self) -> DangerLevel {
+        DangerLevel::ReadOnly
+    }
+
+    fn validate_args(&self, args: &ToolArgs) -> Result<(), ToolError> {
+        validate_number(&self.name, args)
+    }
+
+    fn execute(&mut self, args: &ToolArgs, state: &Arc<Mutex<ToolState>>) -> Result<ToolResult> {
+        let number = id_arg(args);
+        let fetched = client_for(&self.config, args, state)
+            .and_then(|client| Ok((client.pull_request(number)?, client)));
+        let (pull, client) = match fetched {
+            Ok(fetched) => fetched,
+            Err(e) => return Ok(ToolResult::error(format!("{:#}", e))),
+        };
+
+        let kind = match client.provider {
+            ForgeProvider::Github => "Pull request",
+            ForgeProvider::Gitlab => "Merge request",
+        };
+        let mut message = format!(
+            "{} #{} in {} ({}{}): {}\n{} <- {}\n",
+            kind,
+            pull.number,
+            client.repository,
+            pull.state,
+            pull.author
+                .as_ref()
+                .map(|author| format!(", by {}", author))
+                .unwrap_or_default(),
+            pull.title,
+            pull.base,
+            pull.head
+        );
+        if !pull.labels.is_empty() {
+            message.push_str(&format!("Labels: {}\n", pull.labels.join(", ")));
+        }
+        message.push_str(&format!(
+            "URL: {}\n\n{}\n\nChanged files ({}, +{} -{}):\n{}",
+            pull.url,
+            if pull.body.trim().is_empty() {
+                "(no description)"
+            } else {
+                pull.body.trim()
+            },
+            pull.files.len(),
+            pull.additions,
+            pull.deletions,
+            pull.files
+                .iter()
+                .map(Self::format_file)
+                .collect::<Vec<_>>()
+                .join("\n")
+        ));
+
+        let mut data = serde_json::to_value(&pull)?;
+        data["provider"] = serde_json::json!(client.provider);
+        data["repository"] = serde_json::json!(client.repository);
+        Ok(ToolResult::success_with_data(message, data))
+    }
+
+    fn get_parameters_schema(&self) -> Value {
+        parameters_schema("Pull request")
+    }
+}
+
+/// Branch name for a change titled `title`: `cats/` and the title's first words
+pub fn branch_name(title: &str) -> String {
+    let mut slug = String::new();
+    for word in title
+        .split(|c: char| !c.is_ascii_alphanumeric())
+        .filter(|word| !word.is_empty())
+    {
+        if slug.len() + word.len() > 40 {
+            break;
+        }
+        if !slug.is_empty() {
+            slug.push('-');
+        }
+        slug.push_str(&word.to_ascii_lowercase());
+    }
+    if slug.is_empty() {
+        slug.push_str("change");
+    }
+    format!("cats/{}", slug)
+}
+
+/// Tool committing the session's changes, pushing them and opening a pull request
+pub struct CreatePrTool {
+    name: String,
+    config: ForgeConfig,
+}
+
+impl CreatePrTool {
+    pub fn new(config: ForgeConfig) -> Self {
+        Self {
+            name: "create_pr".to_string(),
+            config,
+        }
+    }
+
+    fn create(&self, args: &ToolArgs, state: &Arc<Mutex<ToolState>>) -> Result<(String, Value)> {
+        let client = client_for(&self.config, args, state)?;
+        if !client.has_token() {
+            anyhow::bail!(
+                "create_pr needs an API token; set {} (or [forge] token_env in cats.toml)",
+                self.config
+                    .token_env
+                    .as_deref()
+                    .unwrap_or(client.provider.default_token_env())
+            );
+        }
+        let dir = state
+            .lock()
+            .map_err(|e| anyhow::anyhow!("Failed to lock state: {}", e))?
+            .working_directory
+            .clone();
+        let root = vcs::repo_root(&dir)?;
+        let base = match args.get_named_arg("base") {
+            Some(base) => base.clone(),
+            None => client.default_branch()?,
+        };
+
+        // Everything since the branch left the base, committed or not
+        let uncommitted = vcs::has_uncommitted_changes(&root)?;
+        if uncommitted {
+            vcs::stage_all(&root, None)?;
+        }
+        let fork_point = vcs::git(&root, &["merge-base", "HEAD", &format!("origin/{}", base)])
+            .or_else(|_| vcs::git(&root, &["rev-parse", "HEAD"]))?;
+        let files: Vec<String> =
+            vcs::git(&root, &["diff", "--cached", "--name-only", &fork_point])?
+                .lines()
+                .map(str::to_string)
+                .collect();
+        if files.is_empty() {
+            anyhow::bail!("Nothing to propose: no changes since {}", base);
+        }
+        let (generated_title, generated_body) = {
+            let state_guard = state
+                .lock()
+                .map_err(|e| anyhow::anyhow!("Failed to lock state: {}", e))?;
+            vcs::describe_change(&state_guard, &files)
+        };
+        let title = args
+            .get_named_arg("title")
+            .cloned()
+            .unwrap_or(generated_title);
+        let body = args
+            .get_named_arg("body")
+            .cloned()
+            .unwrap_or(generated_body);
+
+        // Work on the base branch or a detached HEAD moves to a branch of its own
+        let current = vcs::git(&root, &["symbolic-ref", "--quiet", "--short", "HEAD"]).ok();
+        let branch = match (args.get_named_arg("branch"), current) {
+            (Some(branch), current) => {
+                if current.as_deref() != Some(branch.as_str()) {
+                    vcs::git(&root, &["switch", "-c", branch])?;
+                }
+                branch.clone()
+            }
+            (None, Some(current)) if current != base => current,
+            (None, _) => {
+                let branch = branch_name(&title);
+                vcs::git(&root, &["switch", "-c", &branch])?;
+                branch
+            }
+        };
+        if uncommitted {
+            vcs::git_with(
+                &root,
+                &["commit", "-q", "-F", "-"],
+                &[],
+                Some(&format!("{}\n\n{}\n", title, body)),
+            )?;
+        }
+        vcs::git(&root, &["push", "-q", "-u", "origin", &branch])?;
+
+        let draft = args
+            .get_named_arg("draft")
+            .is_some_and(|draft| draft != "false");
+        let (number, url) = client.create_pull_request(&title, &body, &branch, &base, draft)?;
+        if let Ok(mut state_guard) = state.lock() {
+            state_guard.push_history(format!("create_pr: {} -> {}", branch, base));
+        }
+
+        let message = format!(
+            "Opened {}#{} in {}: {}\n{} <- {}{}\nURL: {}",
+            if draft { "draft " } else { "" },
+            number,
+            client.repository,
+            title,
+            base,
+            branch,
+            if uncommitted {
+                " (uncommitted changes committed)"
+            } else {
+                ""
+            },
+            url
+        );
+        let data = serde_json::json!({
+            "provider": client.provider,
+            "repository": client.repository,
+            "number": number,
+            "url": url,
+            "title": title,
+            "body": body,
+            "head": branch,
+            "base": base,
+            "draft": draft,
+            "committed": uncommitted,
+            "files": files,
+        });
+        Ok((message, data))
+    }
+}
+
+impl Default for CreatePrTool {
+    fn default() -> Self {
+        Self::new(ForgeConfig::default())
+    }
+}
+
+impl Tool for CreatePrTool {
+    fn name(&self) -> &str {
+        &self.name
+    }
+
+    fn description(&self) -> &str {
+        "Commit uncommitted changes, push the branch and open a GitHub pull request or GitLab merge request titled and described from the submit summary"
+    }
+
+    fn signature(&self) -> &str {
+        "create_pr [--base=<branch>] [--branch=<name>] [--title=<text>] [--body=<text>] [--draft] [--repository=<owner/name>]"
+    }
+
+    fn category(&self) -> ToolCategory {
+        ToolCategory::Vcs
+    }
+
+    // Commits run the repository's hooks and the push publishes the change
+    fn danger_level(&self) -> DangerLevel {
+        DangerLevel::ExecutesCode
+    }
+
+    fn is_parallel_safe(&self) -> bool {
+        false
+    }
+
+    fn validate_args(&self, _args: &ToolArgs) -> Result<(), ToolError> {
+        Ok(())
+    }
+
+    fn execute(&mut self, args: &ToolArgs, state: &Arc<Mutex<ToolState>>) -> Result<ToolResult> {
+        match self.create(args, state) {
+            Ok((message, data)) => Ok(ToolResult::success_with_data(message, data)),
+            Err(e) => Ok(ToolResult::error(format!("{:#}", e))),
+        }
+    }
+
+    fn get_parameters_schema(&self) -> Value {
+        serde_json::json!({
+            "type": "object",
+            "properties": {
+                "base": {
+                    "type": "string",
+                    "description": "Branch to merge into (defaults to the repository's default branch)"
+                },
+                "branch": {
+                    "type": "string",
+                    "description": "Branch to push (defaults to the current one, or cats/<title> when on the base branch)"
+                },
+                "title": {
+                    "type": "string",
+                    "description": "Title (defaults to the first line of the submit summary)"
+                },
+                "body": {
+                    "type": "string",
+                    "description": "Description (defaults to the submit summary, plan and changed files)"
+                },
+                "draft": {
+                    "type": "boolean",
+                    "description": "Open as a draft"
+                },
+                "repository": {
+                    "type": "string",
+                    "description": "owner/name of the repository (defaults to [forge] in cats.toml or the origin remote)"
+                }
+            },
+            "required": []
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parse_github_responses() {
+        let issue = serde_json::json!({
+            "number": 42,
+            "title": "Panic on empty input",
+            "body": "Steps to reproduce...",
+            "state": "open",
+            "user": {"login": "alice"},
+            "labels": [{"name": "bug"}, {"name": "good first issue"}],
+            "html_url": "https://github.com/acme/widgets/issues/42",
+            "comments": 3
+        });
+        let issue = parse_github_issue(&issue);
+        assert_eq!(issue.number, 42);
+        assert_eq!(issue.labels, ["bug", "good first issue"]);
+        assert_eq!(issue.author.as_deref(), Some("alice"));
+        assert!(!issue.is_pull_request);
+
+        let pull = serde_json::json!({
+            "number": 7,
+            "title": "Handle empty input",
+            "body": null,
+            "state": "closed",
+            "merged_at": "2024-05-01T10:00:00Z",
+            "user": {"login": "bob"},
+            "labels": [],
+            "html_url": "https://github.com/acme/widgets/pull/7",
+            "base": {"ref": "main"},
+            "head": {"ref": "fix-empty"}
+        });
+        let files = [
+            serde_json::json!({"filename": "src/lib.rs", "status": "modified", "additions": 10, "deletions": 2}),
+            serde_json::json!({"filename": "src/input.rs", "previous_filename": "src/read.rs", "status": "renamed", "additions": 0, "deletions": 1}),
+        ];
+        let pull = parse_github_pull(&pull, &files);
+        assert_eq!(pull.state, "merged");
+        assert_eq!(pull.body, "");
+        assert_eq!(
+            (pull.base.as_str(), pull.head.as_str()),
+            ("main", "fix-empty")
+        );
+        assert_eq!((pull.additions, pull.deletions), (10, 3));
+        assert_eq!(
+            FetchPrTool::format_file(&pull.files[1]),
+            "  R src/read.rs -> src/input.rs (+0 -1)"
+        );
+    }
+
+    #[test]
+    fn test_parse_gitlab_responses() {
+        let merge_request = serde_json::json!({
+            "iid": 12,
+            "title": "Add retries",
+            "description": "Closes #3",
+            "state": "opened",
+            "author": {"username": "carol"},
+            "labels": ["backend"],
+            "web_url": "https://gitlab.com/acme/widgets/-/merge_requests/12",
+            "target_branch": "main",
+            "source_branch": "retries",
+            "changes": [
+                {"old_path": "src/net.rs", "new_path": "src/net.rs", "new_file": false,
+                 "deleted_file": false, "renamed_file": false,
+                 "diff": "--- a/src/net.rs\n+++ b/src/net.rs\n@@ -1,2 +1,3 @@\n-old\n+new\n+more\n ctx\n"},
+                {"old_path": "docs/retry.md", "new_path": "docs/retry.md", "new_file": true,
+                 "deleted_file": false, "renamed_file": false, "diff": "+# Retries\n"}
+            ]
+        });
+        let pull = parse_gitlab_merge_request(&merge_request);
+        assert_eq!(pull.state, "open");
+        assert_eq!(pull.labels, ["backend"]);
+        assert_eq!(pull.files[0].additions, 2);
+        assert_eq!(pull.files[0].deletions, 1);
+        assert_eq!(pull.files[1].status, "added");
+        assert_eq!(pull.files[0].previous_path, None);
+
+        let issue = parse_gitlab_issue(&serde_json::json!({
+            "iid": 3, "title": "Flaky requests", "description": "", "state": "closed",
+            "labels": ["bug"], "web_url": "https://gitlab.com/acme/widgets/-/issues/3",
+            "user_notes_count": 1
+        }));
+        assert_eq!((issue.number, issue.comments), (3, 1));
+        assert_eq!(issue.author, None);
+    }
+
+    #[test]
+    fn test_issue_number_arguments() {
+        assert_eq!(parse_number("123"), Some(123));
+        assert_eq!(parse_number("#123"), Some(123));
+        assert_eq!(parse_number("!45"), Some(45));
+        assert_eq!(
+            parse_number("https://github.com/acme/widgets/pull/7/"),
+            Some(7)
+        );
+        assert_eq!(parse_number("latest"), None);
+
+        let tool = FetchIssueTool::default();
+        assert!(tool.validate_args(&ToolArgs::from_args(&["#5"])).is_ok());
+        assert!(matches!(
+            tool.validate_args(&ToolArgs::from_args(&["five"])),
+            Err(ToolError::InvalidArgs { .. })
+        ));
+        assert!(tool.validate_args(&ToolArgs::from_args(&[])).is_err());
+    }
+
+    #[test]
+    fn test_branch_name() {
+        assert_eq!(
+            branch_name("Fix panic on empty input (#42)"),
+            "cats/fix-panic-on-empty-input-42"
+        );
+        assert_eq!(
+            branch_name("Handle every remaining edge case of the configuration parser"),
+            "cats/handle-every-remaining-edge-case-of-the"
+        );
+        assert_eq!(branch_name("..."), "cats/change");
+    }
+}
@@ -25,103 +25,710 @@
 //! # }
 //! ```
 
+#[cfg(feature = "ast")]
+pub mod ast;
+pub mod builder;
+pub mod config;
 pub mod core;
+#[cfg(feature = "data")]
+pub mod data;
+pub mod diagnostics;
 pub mod editing;
+pub mod events;
 pub mod execution;
 pub mod file_navigation;
+#[cfg(feature = "net")]
+pub mod forge;
 pub mod linting;
 pub mod llm;
+#[cfg(feature = "lsp")]
+pub mod lsp;
+pub mod messages;
+pub mod profiling;
+pub mod schema;
 pub mod search;
 pub mod state;
+pub mod stream;
+pub mod testing;
 pub mod utils;
+pub mod vcs;
 
 // Re-export main types
-pub use core::{Tool, ToolArgs, ToolRegistry, ToolResult};
+#[cfg(feature = "ast")]
+pub use ast::{
+    GetDocsTool, GotoDefinitionTool, GotoSymbolTool, InspectTypeTool, ListSymbolsTool,
+    ShowFunctionTool, TestMapTool,
+};
+pub use builder::RegistryBuilder;
+pub use config::{Config, EditingConfig, ForgeConfig, ShowConfigTool, VerifyConfig};
+pub use core::{
+    Attachment, AttachmentContent, BatchOutcome, DangerLevel, SessionHandle, Tool, ToolArgs,
+    ToolCatalog, ToolCategory, ToolDescriptor, ToolRegistry, ToolResult, WorkspaceGuard,
+    DEFAULT_SESSION,
+};
+#[cfg(feature = "data")]
+pub use data::QueryDataTool;
+#[cfg(feature = "ast")]
+pub use diagnostics::LocalizeTool;
+pub use diagnostics::{AnalyzeLogTool, ExplainErrorsTool, TraceToSourceTool};
 pub use editing::{
-    CopyPathTool, CreateDirectoryTool, CreateFileTool, DeleteFunctionTool, DeleteLineTool,
-    DeletePathTool, DeleteTextTool, InsertTextTool, MovePathTool, OverwriteFileTool,
-    ReplaceTextTool,
+    ApplyPatchTool, CopyPathTool, CreateDirectoryTool, CreateFileTool, CreateMigrationTool,
+    CreateSymlinkTool, DeleteLineTool, DeletePathTool, DeleteTextTool, EnvDiffTool, EnvSyncTool,
+    FormatJsonTool, InsertTextTool, MovePathTool, OverwriteFileTool, PurgeTrashTool,
+    RenameModuleTool, ReplaceTextTool, SetPermissionsTool, TouchTool,
+};
+#[cfg(feature = "ast")]
+pub use editing::{DeleteFunctionTool, UpdateCallersTool};
+pub use events::{EventReceiver, ToolEvent};
+#[cfg(feature = "ast")]
+pub use execution::VerifyChangeTool;
+pub use execution::{
+    AuditDependenciesTool, CompareBenchmarksTool, RunBenchmarksTool, RunCommandTool,
+    RunPrecommitTool, RunTestsTool, UpdateSnapshotsTool,
 };
-pub use execution::RunCommandTool;
 pub use file_navigation::{CreateTool, GotoTool, OpenTool, ScrollTool, WindowedFile};
+#[cfg(feature = "net")]
+pub use forge::{CreatePrTool, FetchIssueTool, FetchPrTool};
 pub use llm::{
-    assistant::{generate_assistant_content, ToolCallInfo},
+    assistant::{generate_assistant_content, generate_result_summary, Narrator, ToolCallInfo},
+    context::append_edit_window,
     converter::json_to_tool_args,
     executor::{
-        execute_tool_calls, execute_tool_calls_structured, ExecutionCallback, NoOpCallback,
-        ToolCallRequest, ToolExecutionResult,
+        execute_json_line, execute_tool_calls, execute_tool_calls_structured,
+        execute_tool_calls_structured_with_options, execute_tool_calls_with_options,
+        ExecutionCallback, ExecutionOptions, NoOpCallback, ToolCallRequest, ToolExecutionResult,
     },
-    result_handler::{handle_large_result, ResultHandlerConfig},
+    result_handler::{format_observation, handle_large_result, ResultHandlerConfig},
 };
-pub use search::{FindFileTool, SearchDirTool, SearchFileTool};
-pub use state::{StateTool, ToolState};
-pub use utils::{ClassifyTaskTool, CountTokensTool, FilemapTool, SubmitTool};
+#[cfg(feature = "lsp")]
+pub use lsp::{LspDefinitionTool, LspDiagnosticsTool, LspHoverTool, LspPool};
+pub use messages::{MessageKey, MessageStyle, Verbosity};
+pub use schema::SchemaViolation;
+pub use search::{FindFileTool, QueryJsonTool, SearchDirTool, SearchFileTool};
+pub use state::{
+    AnnotateHistoryTool, CreateScratchDirTool, RedoTool, StateTool, ToolState, UndoTool,
+};
+pub use stream::{ResultChunk, ResultStream, StreamControl};
+#[cfg(feature = "yaml")]
+pub use utils::CiInfoTool;
+pub use utils::{
+    ClassifyTaskTool, CountTokensTool, DependencyReportTool, DescribeToolTool, FilemapTool,
+    ListTasksTool, ReadDocTool, RepoBriefTool, SubmitTool, WorkspaceMembersTool,
+};
+#[cfg(feature = "git")]
+pub use vcs::{CodeOwnersTool, ExportPatchTool, GitCommitTool, GitDiffTool, GitStatusTool};
 
 /// Initialize the tool registry with all available tools (backward-compatible)
+///
+/// Shorthand for `RegistryBuilder::new().build()`; see [`RegistryBuilder`] for the
+/// available settings.
 pub fn create_tool_registry() -> ToolRegistry {
-    create_tool_registry_with_open_window_size(None)
+    RegistryBuilder::new().build()
+}
+
+/// Initialize a tool registry holding only the tools in `categories`
+///
+/// Useful for restricted profiles, e.g. `&[ToolCategory::Navigation, ToolCategory::Search]`
+/// for a read-only reviewer agent.
+pub fn create_tool_registry_with_categories(categories: &[ToolCategory]) -> ToolRegistry {
+    RegistryBuilder::new().categories(categories).build()
+}
+
+/// Initialize the tool registry with a workspace configuration, usually from `cats.toml`
+///
+/// Search filters and protected paths apply to every session, the window size to
+/// `open` and the command deny list to `run_command`; `show_config` reports `config`.
+pub fn create_tool_registry_with_config(config: Config) -> ToolRegistry {
+    RegistryBuilder::new().config(config).build()
 }
 
 /// Initialize the tool registry with a configurable default window size for the "open" tool
 pub fn create_tool_registry_with_open_window_size(open_window_size: Option<usize>) -> ToolRegistry {
-    let mut registry = ToolRegistry::new();
-
-    // Command execution tool (NEW - replaces direct bash)
-    registry.register(Box::new(execution::RunCommandTool::new()));
-
-    // File navigation tools
-    registry.register(Box::new(OpenTool::new_with_open_window_size(
-        open_window_size,
-    )));
-    registry.register(Box::new(GotoTool::new()));
-    registry.register(Box::new(ScrollTool::new("scroll_up", true)));
-    registry.register(Box::new(ScrollTool::new("scroll_down", false)));
-    registry.register(Box::new(CreateTool::new()));
-
-    // Search tools
-    registry.register(Box::new(FindFileTool::new()));
-    registry.register(Box::new(SearchFileTool::new()));
-    registry.register(Box::new(SearchDirTool::new()));
-
-    // Editing tools - New specialized tools
-    registry.register(Box::new(CreateFileTool::new()));
-    registry.register(Box::new(ReplaceTextTool::new()));
-    registry.register(Box::new(InsertTextTool::new()));
-    registry.register(Box::new(DeleteTextTool::new()));
-    registry.register(Box::new(DeleteLineTool::new()));
-    registry.register(Box::new(OverwriteFileTool::new()));
-    registry.register(Box::new(
-        editing::specialized_tools::DeleteFunctionTool::new(),
-    ));
-
-    // File management tools
-    registry.register(Box::new(DeletePathTool::new()));
-    registry.register(Box::new(MovePathTool::new()));
-    registry.register(Box::new(CopyPathTool::new()));
-    registry.register(Box::new(CreateDirectoryTool::new()));
-
-    // State management
-    registry.register(Box::new(StateTool::new()));
-
-    // Utility tools
-    registry.register(Box::new(CountTokensTool::new()));
-    registry.register(Box::new(FilemapTool::new()));
-    registry.register(Box::new(SubmitTool::new()));
-    registry.register(Box::new(ClassifyTaskTool::new()));
-
-    registry
+    match open_window_size {
+        Some(lines) => RegistryBuilder::new().open_window_size(lines).build(),
+        None => RegistryBuilder::new().build(),
+    }
 }
 
 #[cfg(test)]
 mod tests {
     use super::*;
 
+    #[test]
+    fn test_registry_by_category() {
+        let registry = create_tool_registry();
+        let groups = registry.tools_by_category();
+        assert!(groups[&ToolCategory::Navigation].contains(&"open".to_string()));
+        assert!(groups[&ToolCategory::Editing].contains(&"replace_text".to_string()));
+        assert!(groups[&ToolCategory::Search].contains(&"search_dir".to_string()));
+        assert!(groups[&ToolCategory::Execution].contains(&"run_command".to_string()));
+        assert!(groups[&ToolCategory::Utility].contains(&"_state".to_string()));
+        assert_eq!(
+            groups.values().map(Vec::len).sum::<usize>(),
+            registry.list_tools().len()
+        );
+
+        let read_only =
+            create_tool_registry_with_categories(&[ToolCategory::Navigation, ToolCategory::Search]);
+        let names = read_only.list_tools();
+        assert!(names.contains(&"goto".to_string()));
+        assert!(names.contains(&"find_file".to_string()));
+        assert!(!names.contains(&"replace_text".to_string()));
+        assert!(!names.contains(&"run_command".to_string()));
+        assert_eq!(read_only.catalog().names().len(), names.len());
+        assert_eq!(
+            registry.catalog().get("open").map(|d| d.category),
+            Some(ToolCategory::Navigation)
+        );
+        assert_eq!(
+            "Editing".parse::<ToolCategory>().ok(),
+            Some(ToolCategory::Editing)
+        );
+        assert!("shell".parse::<ToolCategory>().is_err());
+    }
+
+    #[test]
+    fn test_registry_danger_levels() {
+        let registry = create_tool_registry();
+        let level = |name: &str| registry.get_tool(name).map(|t| t.danger_level());
+        assert_eq!(level("search_dir"), Some(DangerLevel::ReadOnly));
+        assert_eq!(level("replace_text"), Some(DangerLevel::ModifiesFiles));
+        assert_eq!(level("delete_path"), Some(DangerLevel::Destructive));
+        assert_eq!(level("run_command"), Some(DangerLevel::ExecutesCode));
+        assert!(DangerLevel::ReadOnly < DangerLevel::Destructive);
+        assert_eq!(
+            registry
+                .catalog()
+                .get("purge_trash")
+                .map(|d| d.danger_level),
+            Some(DangerLevel::Destructive)
+        );
+
+        let mut read_only = create_tool_registry();
+        read_only.retain_danger_at_most(DangerLevel::ReadOnly);
+        let names = read_only.list_tools();
+        assert!(names.contains(&"open".to_string()));
+        assert!(!names.contains(&"create_file".to_string()));
+        assert!(!names.contains(&"run_tests".to_string()));
+        assert_eq!(
+            "executes_code".parse::<DangerLevel>().ok(),
+            Some(DangerLevel::ExecutesCode)
+        );
+    }
+
+    #[test]
+    fn test_registry_message_style() {
+        let temp = tempfile::TempDir::new().unwrap();
+        std::fs::write(temp.path().join("a.txt"), "needle\n").unwrap();
+        let dir = temp.path().to_string_lossy().to_string();
+        let args = ToolArgs::from_args(&["needle", &dir]);
+
+        let mut registry = create_tool_registry();
+        let standard = registry.execute_tool("search_dir", &args).unwrap();
+        assert!(standard.message.contains("📁 "));
+
+        registry.set_message_style(
+            MessageStyle::terse().with_template(MessageKey::SearchSummary, "{count} hit(s):"),
+        );
+        let terse = registry.execute_tool("search_dir", &args).unwrap();
+        assert!(terse.message.starts_with("1 hit(s):"), "{}", terse.message);
+        assert!(!terse.message.contains("📁"));
+        assert_eq!(terse.data, standard.data);
+
+        // Sessions created later inherit the style
+        let session = registry
+            .session("other")
+            .execute_tool("filemap", &ToolArgs::from_args(&[&dir]))
+            .unwrap();
+        assert!(!session.message.contains("📄"), "{}", session.message);
+    }
+
+    #[test]
+    fn test_annotations_in_submit_summary() {
+        let mut registry = create_tool_registry();
+        registry.annotate_history("step 1: reproduce");
+        registry
+            .execute_tool(
+                "annotate_history",
+                &ToolArgs::from_args(&["step 2: write tests"]),
+            )
+            .unwrap();
+        let submit = registry
+            .execute_tool("submit", &ToolArgs::from_args(&[]))
+            .unwrap();
+        assert!(submit
+            .message
+            .ends_with("Plan:\n  - step 1: reproduce\n  - step 2: write tests"));
+        assert_eq!(submit.data.unwrap()["annotations"][0], "step 1: reproduce");
+
+        // Sessions keep their own plan
+        let mut session = registry.session("other");
+        session.annotate_history("only here");
+        let state = session
+            .execute_tool("_state", &ToolArgs::from_args(&[]))
+            .unwrap();
+        assert_eq!(
+            state.data.unwrap()["annotations"],
+            serde_json::json!(["only here"])
+        );
+    }
+
+    #[test]
+    fn test_registry_with_config() {
+        let temp = tempfile::TempDir::new().unwrap();
+        std::fs::create_dir(temp.path().join("vendor")).unwrap();
+        std::fs::write(temp.path().join("vendor/lib.rs"), "needle\n").unwrap();
+        std::fs::write(temp.path().join("main.rs"), "needle\n").unwrap();
+        std::fs::write(temp.path().join("Cargo.lock"), "lock\n").unwrap();
+        let config = Config::from_toml_str(
+            r#"
+            [search_filtering]
+            exclude_dirs = ["vendor"]
+
+            [protection]
+            paths = ["Cargo.lock"]
+
+            [commands]
+            deny = ["git push"]
+            "#,
+        )
+        .unwrap();
+        let mut registry = create_tool_registry_with_config(config);
+        registry.session("other").set_working_directory(temp.path());
+        let mut session = registry.session("other");
+
+        let dir = temp.path().to_string_lossy().to_string();
+        let search = session
+            .execute_tool("search_dir", &ToolArgs::from_args(&["needle", &dir]))
+            .unwrap();
+        assert_eq!(search.data.unwrap()["total_matches"], 1);
+
+        let lock = temp.path().join("Cargo.lock").to_string_lossy().to_string();
+        let delete = session
+            .execute_tool("delete_path", &ToolArgs::from_args(&[&lock]))
+            .unwrap();
+        assert!(!delete.success, "{}", delete.message);
+
+        let push = session
+            .execute_tool("run_command", &ToolArgs::from_args(&["git push origin"]))
+            .unwrap();
+        assert!(!push.success);
+        assert_eq!(push.data.unwrap()["denied_by"], "git push");
+
+        let shown = session
+            .execute_tool("show_config", &ToolArgs::from_args(&[]))
+            .unwrap();
+        assert!(
+            shown.message.contains("deny = [\"git push\"]"),
+            "{}",
+            shown.message
+        );
+    }
+
+    #[test]
+    fn test_compact_verbosity() {
+        let temp = tempfile::TempDir::new().unwrap();
+        let file = temp.path().join("a.txt");
+        std::fs::write(&file, "needle\nhay\nneedle\n").unwrap();
+        let path = file.to_string_lossy().to_string();
+        let mut registry = create_tool_registry();
+
+        // Per call: the window is left out of the message but kept in the data
+        let args = ToolArgs::with_named_args(
+            vec![path.clone()],
+            std::collections::HashMap::from([(
+                core::VERBOSITY_ARG.to_string(),
+                "compact".to_string(),
+            )]),
+        );
+        let open = registry.execute_tool("open", &args).unwrap();
+        assert_eq!(open.message, format!("Opened file: {} (3 lines)", path));
+        let window = &open.data.unwrap()["window_content"];
+        assert_eq!(window.as_array().map(Vec::len), Some(3));
+
+        // Registry-wide, with tool-specific summaries
+        registry.set_verbosity(Verbosity::Compact);
+        let dir = temp.path().to_string_lossy().to_string();
+        let search = registry
+            .execute_tool("search_dir", &ToolArgs::from_args(&["needle", &dir]))
+            .unwrap();
+        assert_eq!(
+            search.message,
+            format!("2 matches in 1 files\n{}: 1, 3", path)
+        );
+        let missing = registry
+            .execute_tool("goto", &ToolArgs::from_args(&["99"]))
+            .unwrap();
+        assert!(!missing.success);
+        assert!(missing.message.contains("beyond the end of the file"));
+
+        let invalid = ToolArgs::with_named_args(
+            vec![path],
+            std::collections::HashMap::from([(
+                core::VERBOSITY_ARG.to_string(),
+                "loud".to_string(),
+            )]),
+        );
+        assert!(matches!(
+            registry.execute_tool("open", &invalid),
+            Err(core::ToolError::InvalidArgs { .. })
+        ));
+    }
+
+    #[test]
+    fn test_streaming_results() {
+        let temp = tempfile::TempDir::new().unwrap();
+        let src = temp.path().join("src");
+        std::fs::create_dir(&src).unwrap();
+        for name in ["a.txt", "b.txt", "c.txt"] {
+            std::fs::write(src.join(name), "needle\n").unwrap();
+        }
+        let mut registry = create_tool_registry();
+        registry.session("s").set_working_directory(temp.path());
+        let dir = src.to_string_lossy().to_string();
+        let in_session =
+            |args: Vec<String>, mut named: std::collections::HashMap<String, String>| {
+                named.insert(core::SESSION_ARG.to_string(), "s".to_string());
+                ToolArgs::with_named_args(args, named)
+            };
+        let search = in_session(vec!["needle".to_string(), dir.clone()], Default::default());
+
+        // Every file's matches arrive as a chunk before the consolidated result
+        let (on_chunk, chunks) = stream::channel();
+        let result = registry
+            .execute_tool_streaming("search_dir", &search, on_chunk)
+            .unwrap();
+        let chunks: Vec<ResultChunk> = chunks.try_iter().collect();
+        assert_eq!(chunks.len(), 3);
+        assert_eq!(chunks[0].seq, 0);
+        assert_eq!(chunks[0].tool, "search_dir");
+        assert_eq!(chunks[0].data.as_ref().unwrap()["match_count"], 1);
+        let data = result.data.unwrap();
+        assert_eq!(data["total_matches"], 3);
+        assert_eq!(data["streamed"]["chunks"], 3);
+        assert_eq!(data["streamed"]["stopped"], false);
+
+        // The consumer ends the call early and gets what was found so far
+        let stopped = registry
+            .execute_tool_streaming("search_dir", &search, |_| StreamControl::Stop)
+            .unwrap();
+        assert!(
+            stopped.message.contains("stopped early"),
+            "{}",
+            stopped.message
+        );
+        let data = stopped.data.unwrap();
+        assert_eq!(data["total_matches"], 1);
+        assert_eq!(data["streamed"]["stopped"], true);
+
+        let copy = in_session(
+            vec![],
+            std::collections::HashMap::from([
+                ("source".to_string(), dir.clone()),
+                (
+                    "destination".to_string(),
+                    temp.path().join("copy").to_string_lossy().to_string(),
+                ),
+            ]),
+        );
+        let copied = registry
+            .execute_tool_streaming("copy_path", &copy, |chunk| {
+                if chunk.seq == 1 {
+                    StreamControl::Stop
+                } else {
+                    StreamControl::Continue
+                }
+            })
+            .unwrap();
+        assert!(copied.success, "{}", copied.message);
+        assert_eq!(
+            std::fs::read_dir(temp.path().join("copy")).unwrap().count(),
+            2
+        );
+
+        let (on_chunk, chunks) = stream::channel();
+        let command = in_session(
+            vec!["printf 'one\\ntwo\\n'".to_string()],
+            Default::default(),
+        );
+        let output = registry
+            .execute_tool_streaming("run_command", &command, on_chunk)
+            .unwrap();
+        assert!(output.success, "{}", output.message);
+        let lines: Vec<String> = chunks.try_iter().map(|c| c.content).collect();
+        assert_eq!(lines, ["one\n", "two\n"]);
+
+        // Without a consumer nothing is streamed
+        let plain = registry.execute_tool("search_dir", &search).unwrap();
+        assert!(plain.data.unwrap().get("streamed").is_none());
+    }
+
+    #[test]
+    fn test_repeated_call_warning() {
+        let temp = tempfile::TempDir::new().unwrap();
+        let file = temp.path().join("a.txt");
+        std::fs::write(&file, "one\n").unwrap();
+        let path = file.to_string_lossy().to_string();
+        let mut registry = create_tool_registry();
+        registry.session("s").set_working_directory(temp.path());
+        let mut session = registry.session("s");
+        let open = ToolArgs::from_args(&[&path]);
+
+        for _ in 0..2 {
+            let result = session.execute_tool("open", &open).unwrap();
+            assert!(!result.message.contains("Warning"), "{}", result.message);
+        }
+        let third = session.execute_tool("open", &open).unwrap();
+        assert!(third.success);
+        assert!(
+            third.message.contains("open was called 3 times"),
+            "{}",
+            third.message
+        );
+        assert_eq!(third.data.unwrap()["repeated_call"]["count"], 3);
+
+        // A change to the files starts over
+        let edit = ToolArgs::with_named_args(
+            vec![],
+            std::collections::HashMap::from([
+                ("path".to_string(), path.clone()),
+                ("old_text".to_string(), "one".to_string()),
+                ("new_text".to_string(), "two".to_string()),
+            ]),
+        );
+        let edited = session.execute_tool("replace_text", &edit).unwrap();
+        assert!(edited.success, "{}", edited.message);
+        let reopened = session.execute_tool("open", &open).unwrap();
+        assert!(reopened.data.unwrap().get("repeated_call").is_none());
+
+        registry.set_repeat_threshold(None);
+        let mut session = registry.session("s");
+        for _ in 0..3 {
+            let result = session.execute_tool("open", &open).unwrap();
+            assert!(result.data.unwrap().get("repeated_call").is_none());
+        }
+    }
+
+    #[test]
+    fn test_resolved_args() {
+        let temp = tempfile::TempDir::new().unwrap();
+        std::fs::write(temp.path().join("a.txt"), "needle\n\n\n").unwrap();
+        let dir = temp.path().canonicalize().unwrap();
+        let mut registry = create_tool_registry();
+
+        // Positional and JSON forms of a call resolve to the same arguments
+        let positional = registry
+            .execute_tool(
+                "search_dir",
+                &ToolArgs::from_args(&["needle", &format!("{}/./", temp.path().display())]),
+            )
+            .unwrap();
+        let line = serde_json::json!({
+            "tool": "search_dir",
+            "args": {"search_term": "needle", "dir": temp.path()}
+        });
+        let json = execute_json_line(&mut registry, &line.to_string());
+        let expected = serde_json::json!({"search_term": "needle", "dir": dir});
+        assert_eq!(positional.data.unwrap()["resolved_args"], expected);
+        assert_eq!(json["data"]["resolved_args"], expected);
+
+        let path = temp.path().join("a.txt").to_string_lossy().to_string();
+        registry
+            .execute_tool("open", &ToolArgs::from_args(&[&path]))
+            .unwrap();
+        let goto = registry
+            .execute_tool("goto", &ToolArgs::from_args(&["3"]))
+            .unwrap();
+        assert_eq!(
+            goto.data.unwrap()["resolved_args"],
+            serde_json::json!({"line_number": 3})
+        );
+    }
+
+    #[test]
+    fn test_filemap_diagram_attachment() {
+        use base64::Engine;
+
+        // Hidden directories are filtered out of filemaps, so no `.tmp` prefix
+        let temp = tempfile::Builder::new()
+            .prefix("filemap")
+            .tempdir()
+            .unwrap();
+        std::fs::create_dir(temp.path().join("src")).unwrap();
+        std::fs::write(temp.path().join("src").join("lib.rs"), "").unwrap();
+        let dir = temp.path().to_string_lossy().to_string();
+        let mut registry = create_tool_registry();
+
+        let request = serde_json::json!({
+            "tool": "filemap",
+            "args": {"file_path": dir, "diagram": "svg"}
+        });
+        let response = execute_json_line(&mut registry, &request.to_string());
+        assert_eq!(response["success"], true, "{}", response);
+        let attachment = &response["attachments"][0];
+        assert_eq!(attachment["mime_type"], "image/svg+xml");
+        let svg = base64::engine::general_purpose::STANDARD
+            .decode(attachment["data"].as_str().unwrap())
+            .unwrap();
+        let svg = String::from_utf8(svg).unwrap();
+        assert!(svg.contains(">src/</text>") && svg.contains(">lib.rs</text>"));
+
+        // Without the argument there is nothing attached
+        let request = serde_json::json!({"tool": "filemap", "args": {"file_path": dir}});
+        let response = execute_json_line(&mut registry, &request.to_string());
+        assert!(response.get("attachments").is_none());
+
+        let request = serde_json::json!({
+            "tool": "filemap",
+            "args": {"file_path": dir, "diagram": "png"}
+        });
+        let response = execute_json_line(&mut registry, &request.to_string());
+        assert_eq!(response["error"]["kind"], "invalid_args");
+
+        let from_path = Attachment::from_path("image/png", "graph", "out/graph.png");
+        let json = serde_json::to_value(&from_path).unwrap();
+        assert_eq!(
+            json,
+            serde_json::json!({
+                "mime_type": "image/png",
+                "description": "graph",
+                "path": "out/graph.png"
+            })
+        );
+        assert_eq!(
+            serde_json::from_value::<Attachment>(json).unwrap(),
+            from_path
+        );
+    }
+
+    #[test]
+    fn test_strict_args() {
+        let temp = tempfile::TempDir::new().unwrap();
+        let file = temp.path().join("a.txt");
+        std::fs::write(&file, "one\n").unwrap();
+        let path = file.to_string_lossy().to_string();
+        let named = |pairs: &[(&str, &str)]| {
+            ToolArgs::with_named_args(
+                vec![],
+                pairs
+                    .iter()
+                    .map(|(k, v)| (k.to_string(), v.to_string()))
+                    .collect(),
+            )
+        };
+        let misspelled = named(&[("path", &path), ("old_text", "one"), ("new_txt", "two")]);
+
+        // Without strict mode the unknown key reaches the tool
+        let mut registry = create_tool_registry();
+        let lenient = registry.execute_tool("replace_text", &misspelled);
+        if let Err(error) = lenient {
+            assert!(!error.to_string().contains("new_txt"), "{}", error);
+        }
+
+        registry.set_strict_args(true);
+        let error = registry
+            .execute_tool("replace_text", &misspelled)
+            .unwrap_err();
+        assert_eq!(error.kind(), "invalid_args");
+        let message = error.to_string();
+        assert!(
+            message.contains("missing required parameter 'new_text'"),
+            "{}",
+            message
+        );
+        assert!(
+            message.contains("unknown parameter 'new_txt'; did you mean 'new_text'?"),
+            "{}",
+            message
+        );
+        assert_eq!(std::fs::read_to_string(&file).unwrap(), "one\n");
+
+        let error = registry
+            .execute_tool(
+                "replace_text",
+                &named(&[
+                    ("path", &path),
+                    ("old_text", "one"),
+                    ("new_text", "two"),
+                    ("occurrence", "first"),
+                ]),
+            )
+            .unwrap_err();
+        assert!(
+            error
+                .to_string()
+                .contains("/occurrence: expected integer, got string 'first'"),
+            "{}",
+            error
+        );
+
+        let error = registry
+            .execute_tool("open", &ToolArgs::from_args(&[&path, "1", "extra"]))
+            .unwrap_err();
+        assert!(
+            error
+                .to_string()
+                .contains("unexpected positional argument 3 ('extra')"),
+            "{}",
+            error
+        );
+
+        let valid = named(&[("path", &path), ("old_text", "one"), ("new_text", "two")]);
+        let result = registry.execute_tool("replace_text", &valid).unwrap();
+        assert!(result.success, "{}", result.message);
+        assert_eq!(std::fs::read_to_string(&file).unwrap(), "two\n");
+    }
+
+    #[test]
+    fn test_validate_against_schema() {
+        let registry = create_tool_registry();
+        let valid = serde_json::json!({
+            "path": "a.txt",
+            "old_text": "one",
+            "new_text": "two",
+            "session": "agent-1"
+        });
+        assert!(registry
+            .validate_against_schema("replace_text", &valid)
+            .unwrap()
+            .is_empty());
+
+        let invalid = serde_json::json!({"path": "a.txt", "old_text": "one", "new_txt": "two", "occurrence": "2"});
+        let violations = registry
+            .validate_against_schema("replace_text", &invalid)
+            .unwrap();
+        assert_eq!(violations.len(), 3, "{:?}", violations);
+        let report = schema::violation_report("replace_text", &violations);
+        assert!(report.starts_with("replace_text arguments do not match its schema:\n  - "));
+        assert!(report.contains("did you mean 'new_text'?"), "{}", report);
+        assert!(report.contains("/occurrence: expected integer, got string '2'"));
+
+        let error = registry
+            .validate_against_schema("replace_txt", &valid)
+            .unwrap_err();
+        assert_eq!(error.kind(), "tool_not_found");
+
+        let exported = registry.export_schemas();
+        assert_eq!(exported["$schema"], schema::SCHEMA_DIALECT);
+        let defs = exported["$defs"].as_object().unwrap();
+        assert_eq!(defs.len(), registry.list_tools().len());
+        assert_eq!(defs["replace_text"]["required"][2], "new_text");
+        assert!(defs["replace_text"]["description"].is_string());
+    }
+
     #[test]
     fn test_registry_creation() {
         let registry = create_tool_registry();
 
-        // Test that all expected tools are registered
+        // Test that all expected tools are registered, listed in name order
         let tool_names = registry.list_tools();
+        assert!(tool_names.windows(2).all(|w| w[0] < w[1]));
+        let schema_names: Vec<String> = registry
+            .get_all_schemas()
+            .iter()
+            .filter_map(|s| s["function"]["name"].as_str().map(String::from))
+            .collect();
+        assert_eq!(schema_names, tool_names);
 
         // File navigation tools
         assert!(tool_names.contains(&"open".to_string()));
@@ -132,11 +739,49 @@ mod tests {
 
         // Command execution tool
         assert!(tool_names.contains(&"run_command".to_string()));
+        assert!(tool_names.contains(&"run_tests".to_string()));
+        assert!(tool_names.contains(&"run_benchmarks".to_string()));
+        assert!(tool_names.contains(&"compare_benchmarks".to_string()));
+        assert!(tool_names.contains(&"run_precommit".to_string()));
+        assert!(tool_names.contains(&"audit_dependencies".to_string()));
+        assert!(tool_names.contains(&"explain_errors".to_string()));
+        assert!(tool_names.contains(&"analyze_log".to_string()));
+        assert!(tool_names.contains(&"trace_to_source".to_string()));
+        assert!(tool_names.contains(&"undo".to_string()));
+        assert!(tool_names.contains(&"redo".to_string()));
+        #[cfg(feature = "lsp")]
+        {
+            assert!(tool_names.contains(&"lsp_diagnostics".to_string()));
+            assert!(tool_names.contains(&"lsp_hover".to_string()));
+            assert!(tool_names.contains(&"lsp_definition".to_string()));
+        }
+        #[cfg(feature = "net")]
+        {
+            assert!(tool_names.contains(&"fetch_issue".to_string()));
+            assert!(tool_names.contains(&"fetch_pr".to_string()));
+            assert!(tool_names.contains(&"create_pr".to_string()));
+        }
+        #[cfg(feature = "data")]
+        assert!(tool_names.contains(&"query_data".to_string()));
+        #[cfg(feature = "ast")]
+        {
+            assert!(tool_names.contains(&"localize".to_string()));
+            assert!(tool_names.contains(&"goto_definition".to_string()));
+            assert!(tool_names.contains(&"inspect_type".to_string()));
+            assert!(tool_names.contains(&"get_docs".to_string()));
+            assert!(tool_names.contains(&"update_callers".to_string()));
+            assert!(tool_names.contains(&"test_map".to_string()));
+            assert!(tool_names.contains(&"verify_change".to_string()));
+            assert!(tool_names.contains(&"list_symbols".to_string()));
+            assert!(tool_names.contains(&"goto_symbol".to_string()));
+            assert!(tool_names.contains(&"show_function".to_string()));
+        }
 
         // Search tools
         assert!(tool_names.contains(&"find_file".to_string()));
         assert!(tool_names.contains(&"search_file".to_string()));
         assert!(tool_names.contains(&"search_dir".to_string()));
+        assert!(tool_names.contains(&"query_json".to_string()));
 
         // Editing tools - New specialized tools
         assert!(tool_names.contains(&"create_file".to_string()));
@@ -148,15 +793,45 @@ mod tests {
 
         // File management tools
         assert!(tool_names.contains(&"delete_path".to_string()));
+        assert!(tool_names.contains(&"purge_trash".to_string()));
         assert!(tool_names.contains(&"move_path".to_string()));
         assert!(tool_names.contains(&"copy_path".to_string()));
+        assert!(tool_names.contains(&"rename_module".to_string()));
         assert!(tool_names.contains(&"create_directory".to_string()));
+        assert!(tool_names.contains(&"set_permissions".to_string()));
+        assert!(tool_names.contains(&"touch".to_string()));
+        assert!(tool_names.contains(&"env_diff".to_string()));
+        assert!(tool_names.contains(&"env_sync".to_string()));
+        assert!(tool_names.contains(&"create_migration".to_string()));
+        assert!(tool_names.contains(&"format_json".to_string()));
+        assert!(tool_names.contains(&"apply_patch".to_string()));
+        assert!(tool_names.contains(&"update_snapshots".to_string()));
+        assert!(tool_names.contains(&"create_symlink".to_string()));
 
         // State and utility tools
         assert!(tool_names.contains(&"_state".to_string()));
+        assert!(tool_names.contains(&"annotate_history".to_string()));
+        assert!(tool_names.contains(&"show_config".to_string()));
+        assert!(tool_names.contains(&"create_scratch_dir".to_string()));
         assert!(tool_names.contains(&"count_tokens".to_string()));
         assert!(tool_names.contains(&"filemap".to_string()));
+        #[cfg(feature = "yaml")]
+        assert!(tool_names.contains(&"ci_info".to_string()));
+        assert!(tool_names.contains(&"workspace_members".to_string()));
+        assert!(tool_names.contains(&"list_tasks".to_string()));
+        assert!(tool_names.contains(&"dependency_report".to_string()));
+        assert!(tool_names.contains(&"repo_brief".to_string()));
+        assert!(tool_names.contains(&"read_doc".to_string()));
+        #[cfg(feature = "git")]
+        {
+            assert!(tool_names.contains(&"export_patch".to_string()));
+            assert!(tool_names.contains(&"code_owners".to_string()));
+            assert!(tool_names.contains(&"git_status".to_string()));
+            assert!(tool_names.contains(&"git_diff".to_string()));
+            assert!(tool_names.contains(&"git_commit".to_string()));
+        }
         assert!(tool_names.contains(&"submit".to_string()));
         assert!(tool_names.contains(&"classify_task".to_string()));
+        assert!(tool_names.contains(&"describe_tool".to_string()));
     }
 }
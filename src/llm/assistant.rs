@@ -1,9 +1,24 @@
 //! Assistant content generation for tool calls
 //!
 //! Generates human-friendly descriptions of tool calls, similar to
-//! professional AI assistants (Zed, VS Code Copilot).
+//! professional AI assistants (Zed, VS Code Copilot): what a call is about to do
+//! ("I'll search for `TODO` in `src`.") and what it did ("Edited src/lib.rs (replaced 1
+//! occurrence at line 42)").
+//!
+//! A [`Narrator`] renders both from templates keyed by tool name: `<tool>` for the
+//! call, `<tool>.done` for a successful result and `<tool>.failed` for a failed one.
+//! Placeholders in braces are filled from the call's arguments and, for results, from
+//! the result's `data` (arrays count their items), plus `{tool}`, `{summary}` (first
+//! line of the message) and `{error}`. Text in square brackets is detail: it is left
+//! out at [`Verbosity::Compact`] or when one of its placeholders has no value. A
+//! template missing a placeholder outside brackets falls back to the generic `_call`,
+//! `_done` or `_failed` template. Override any template to change the wording or
+//! language; `{{`, `}}`, `[[` and `]]` are literal characters.
 
+use crate::core::ToolResult;
+use crate::messages::Verbosity;
 use serde_json::Value;
+use std::collections::HashMap;
 
 /// Simple tool call representation for assistant content generation
 #[derive(Debug, Clone)]
@@ -19,12 +34,312 @@ impl ToolCallInfo {
             arguments: arguments.into(),
         }
     }
+
+    /// The arguments as a JSON object; empty when they are not one
+    fn arguments_object(&self) -> serde_json::Map<String, Value> {
+        match serde_json::from_str::<Value>(&self.arguments) {
+            Ok(Value::Object(object)) => object,
+            _ => serde_json::Map::new(),
+        }
+    }
+}
+
+/// Longest placeholder value shown, in characters; commands and search terms can be long
+const MAX_VALUE_CHARS: usize = 60;
+
+/// Built-in template for `key`
+fn builtin_template(key: &str) -> Option<&'static str> {
+    let template = match key {
+        // Fallbacks and multi-call summaries
+        "_call" => "I'll use the {tool} tool to help with your request.",
+        "_done" => "Ran {tool}[: {summary}]",
+        "_failed" => "{tool} failed[: {error}]",
+        "_pair" => "I'll help you with that by using {first} and {second}.",
+        "_many" => "I'll work on this using multiple tools: {tools}.",
+
+        // Navigation
+        "open" | "read_file" => "I'll read the file[ at `{path}`].",
+        "open.done" | "read_file.done" => "Opened {path}[ ({total_lines} lines)]",
+        "goto" => "I'll move to line {line_number}.",
+        "goto.done" => "Moved to line {line_number}",
+        "scroll_down" => "I'll scroll down.",
+        "scroll_down.done" => "Scrolled down",
+        "scroll_up" => "I'll scroll up.",
+        "scroll_up.done" => "Scrolled up",
+        "goto_definition" => "I'll find the definition of `{symbol}`.",
+        "goto_definition.done" => "Found the definition of {symbol}",
+        "inspect_type" => "I'll inspect the type `{type_name}`[ in `{path}`].",
+        "inspect_type.done" => "Inspected {type_name}",
+        "lsp_definition" => "I'll look up the definition of the symbol at {path}:{line}.",
+        "lsp_definition.done" => "Looked up the definition of the symbol at {path}:{line}",
+        "lsp_hover" => "I'll look up the symbol at {path}:{line}.",
+        "lsp_hover.done" => "Looked up the symbol at {path}:{line}",
+
+        // Editing
+        "copy_path" => "I'll copy `{source}` to `{destination}`.",
+        "copy_path.done" => "Copied {source} to {destination}",
+        "create" => "I'll create `{filename}`.",
+        "create.done" => "Created {path}",
+        "create_directory" => "I'll create the directory[ `{path}`].",
+        "create_directory.done" => "Created directory {path}",
+        "create_file" => "I'll create `{path}`.",
+        "create_file.done" => "Created {path}[ ({lines_created} lines)]",
+        "create_symlink" => "I'll link `{link_path}` to `{target}`.",
+        "create_symlink.done" => "Linked {link_path} to {target}",
+        "delete_function" => "I'll delete the function `{function_name}` from `{path}`.",
+        "delete_function.done" => {
+            "Deleted {function_name} from {path}[ (lines {start_line}-{end_line})]"
+        }
+        "delete_line" => "I'll delete lines {start_line}-{end_line} of `{path}`.",
+        "delete_line.done" => "Deleted lines {start_line}-{end_line} of {path}",
+        "delete_path" => "I'll delete `{path}`.",
+        "delete_path.done" => "Deleted {path}[ (moved to {trash_path})]",
+        "delete_text" => "I'll delete text from `{path}`.",
+        "delete_text.done" => "Edited {path}[ (deleted 1 occurrence at line {line})]",
+        "insert_text" => "I'll insert text into `{path}`[ at line {line_number}].",
+        "insert_text.done" => {
+            "Edited {path}[ (inserted {lines_added} lines at line {line_number})]"
+        }
+        "move_path" => "I'll move `{source}` to `{destination}`.",
+        "move_path.done" => "Moved {source} to {destination}",
+        "overwrite_file" | "write_file" => "I'll write content to `{path}`.",
+        "overwrite_file.done" | "write_file.done" => "Rewrote {path}[ ({lines_new} lines)]",
+        "purge_trash" => "I'll empty the trash.",
+        "purge_trash.done" => "Emptied the trash[ ({purged} sessions, {bytes} bytes)]",
+        "rename_module" => "I'll move the module `{old_path}` to `{new_path}`.",
+        "rename_module.done" => {
+            "Moved module {old_path} to {new_path}[ ({files_touched} files updated)]"
+        }
+        "replace_text" => "I'll edit `{path}`.",
+        "replace_text.done" => "Edited {path}[ (replaced 1 occurrence at line {line})]",
+        "set_permissions" => "I'll change the permissions of `{path}`.",
+        "set_permissions.done" => "Changed permissions of {path}[ from {old_mode} to {new_mode}]",
+        "touch" => "I'll touch `{path}`.",
+        "touch.done" => "Touched {path}",
+        "update_callers" => "I'll find the callers of `{function}`.",
+        "update_callers.done" => "Found {call_sites} calls of {function}",
+
+        // Search
+        "find_file" => "I'll look for files matching `{file_name}`[ in `{dir}`].",
+        "find_file.done" => "Found {count} files matching {pattern}",
+        "search_dir" => "I'll search for `{search_term}`[ in `{dir}`].",
+        "search_dir.done" => {
+            "Found {total_matches} matches for `{search_term}`[ in {file_count} files]"
+        }
+        "search_file" => "I'll search[ `{file}`] for `{search_term}`.",
+        "search_file.done" => "Found {count} matches for `{search_term}`[ in {file}]",
+
+        // Execution
+        "run_command" | "execute_command" => "I'll execute the command[: `{command}`]",
+        "run_command.done" | "execute_command.done" => "Ran `{command}`",
+        "run_command.failed" | "execute_command.failed" => "`{command}` failed[: {error}]",
+        "run_tests" => "I'll run the tests[ matching `{filter}`].",
+        "run_tests.done" => "All tests passed[ ({command})]",
+        "run_tests.failed" => "{failures} tests failed[ ({command})]",
+        "run_benchmarks" => "I'll run the benchmarks[ matching `{filter}`].",
+        "run_benchmarks.done" => "Ran the benchmarks",
+        "compare_benchmarks" => "I'll compare the benchmark results.",
+        "compare_benchmarks.done" => {
+            "Compared benchmarks[: {regressions} regressions, {improvements} improvements]"
+        }
+
+        // Utilities
+        "_state" => "I'll check the current state.",
+        "_state.done" => "Checked the current state",
+        "annotate_history" => "I'll note: {text}",
+        "annotate_history.done" => "Noted: {text}",
+        "classify_task" => "I'll analyze and classify this task to determine the best approach.",
+        "classify_task.done" => "Classified the task as {task_type}",
+        "count_tokens" => "I'll count the tokens[ in `{file_path}`].",
+        "count_tokens.done" => "Counted {tokens} tokens[ in {path}]",
+        "create_scratch_dir" => "I'll create a scratch directory.",
+        "create_scratch_dir.done" => "Created scratch directory {path}",
+        "describe_tool" => "I'll look up how to use `{tool_name}`.",
+        "describe_tool.done" => "Described {tool_name}",
+        "explain_errors" => "I'll group the errors[ from `{command}`].",
+        "explain_errors.done" => "Found {errors} errors and {warnings} warnings",
+        "filemap" => "I'll map the structure of `{file_path}`.",
+        "filemap.done" => "Mapped {file_path}",
+        "lsp_diagnostics" => "I'll get the diagnostics for `{path}`.",
+        "lsp_diagnostics.done" => "Got {diagnostics} diagnostics for {path}",
+        "show_config" => "I'll show the configuration in effect.",
+        "show_config.done" => "Showed the configuration",
+        "submit" => "Task completed successfully.",
+        "submit.done" => "Submitted the work",
+        _ => return None,
+    };
+    Some(template)
+}
+
+/// Renders narration of tool calls and their results
+#[derive(Debug, Clone, Default)]
+pub struct Narrator {
+    verbosity: Verbosity,
+    overrides: HashMap<String, String>,
+}
+
+impl Narrator {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Leave out bracketed details at [`Verbosity::Compact`]
+    pub fn with_verbosity(mut self, verbosity: Verbosity) -> Self {
+        self.verbosity = verbosity;
+        self
+    }
+
+    /// Replace the template for `key`, e.g. `open`, `replace_text.done` or `_failed`
+    pub fn with_template(mut self, key: impl Into<String>, template: impl Into<String>) -> Self {
+        self.overrides.insert(key.into(), template.into());
+        self
+    }
+
+    /// The template used for `key`, if any
+    pub fn template(&self, key: &str) -> Option<&str> {
+        self.overrides
+            .get(key)
+            .map(String::as_str)
+            .or_else(|| builtin_template(key))
+    }
+
+    /// What a batch of calls is about to do
+    pub fn describe_calls(&self, tool_calls: &[ToolCallInfo]) -> String {
+        let names: Vec<&str> = tool_calls.iter().map(|call| call.name.as_str()).collect();
+        let joined = names.join(", ");
+        let lookup = |name: &str| match name {
+            "first" => names.first().map(|name| name.to_string()),
+            "second" => names.get(1).map(|name| name.to_string()),
+            "tools" => Some(joined.clone()),
+            _ => None,
+        };
+        match tool_calls {
+            [] => String::new(), // Empty content for tool-only responses
+            [call] => self.describe_call(call),
+            [_, _] => self.render_first(&["_pair"], &lookup),
+            _ => self.render_first(&["_many"], &lookup),
+        }
+    }
+
+    /// What one call is about to do
+    pub fn describe_call(&self, call: &ToolCallInfo) -> String {
+        let args = call.arguments_object();
+        let lookup = |name: &str| match name {
+            "tool" => Some(call.name.clone()),
+            _ => args.get(name).and_then(value_text),
+        };
+        self.render_first(&[call.name.as_str(), "_call"], &lookup)
+    }
+
+    /// What a call did, from its result
+    pub fn describe_result(&self, call: &ToolCallInfo, result: &ToolResult) -> String {
+        let args = call.arguments_object();
+        let first_line = result.message.lines().next().unwrap_or("").trim();
+        let lookup = |name: &str| match name {
+            "tool" => Some(call.name.clone()),
+            "summary" | "error" => (!first_line.is_empty()).then(|| truncate(first_line)),
+            _ => result
+                .data
+                .as_ref()
+                .and_then(|data| data.get(name))
+                .and_then(value_text)
+                .or_else(|| args.get(name).and_then(value_text)),
+        };
+        let (key, fallback) = if result.success {
+            (format!("{}.done", call.name), "_done")
+        } else {
+            (format!("{}.failed", call.name), "_failed")
+        };
+        self.render_first(&[key.as_str(), fallback], &lookup)
+    }
+
+    /// The first of `keys` whose template renders
+    fn render_first(&self, keys: &[&str], lookup: &dyn Fn(&str) -> Option<String>) -> String {
+        let details = self.verbosity == Verbosity::Normal;
+        keys.iter()
+            .filter_map(|key| self.template(key))
+            .find_map(|template| render(template, lookup, details))
+            .unwrap_or_default()
+    }
+}
+
+/// Render `template`, or `None` if a placeholder outside brackets has no value
+fn render(
+    template: &str,
+    lookup: &dyn Fn(&str) -> Option<String>,
+    details: bool,
+) -> Option<String> {
+    let mut out = String::with_capacity(template.len() + 32);
+    // While inside brackets: the section so far, or `None` once a placeholder is missing
+    let mut section: Option<Option<String>> = None;
+    let mut chars = template.char_indices().peekable();
+    while let Some((index, c)) = chars.next() {
+        let text = match c {
+            '{' | '}' | '[' | ']' if chars.peek().map(|&(_, next)| next) == Some(c) => {
+                chars.next();
+                Some(c.to_string())
+            }
+            '[' => {
+                section = Some(Some(String::new()));
+                continue;
+            }
+            ']' => {
+                if let Some(Some(text)) = section.take() {
+                    if details {
+                        out.push_str(&text);
+                    }
+                }
+                continue;
+            }
+            '{' => match template[index..].find('}') {
+                Some(end) => {
+                    while chars.next_if(|&(next, _)| next < index + end).is_some() {}
+                    chars.next();
+                    lookup(&template[index + 1..index + end])
+                }
+                None => Some(c.to_string()),
+            },
+            c => Some(c.to_string()),
+        };
+        match (&mut section, text) {
+            (Some(Some(pending)), Some(text)) => pending.push_str(&text),
+            (Some(pending), None) => *pending = None,
+            (Some(None), Some(_)) => {}
+            (None, Some(text)) => out.push_str(&text),
+            (None, None) => return None,
+        }
+    }
+    Some(out)
+}
+
+/// A JSON value as placeholder text: arrays count their items; empty strings, nulls and
+/// objects have no text
+fn value_text(value: &Value) -> Option<String> {
+    match value {
+        Value::String(text) => {
+            let line = text.lines().next().unwrap_or("").trim();
+            (!line.is_empty()).then(|| truncate(line))
+        }
+        Value::Number(number) => Some(number.to_string()),
+        Value::Bool(flag) => Some(flag.to_string()),
+        Value::Array(items) => Some(items.len().to_string()),
+        Value::Null | Value::Object(_) => None,
+    }
+}
+
+fn truncate(text: &str) -> String {
+    if text.chars().count() <= MAX_VALUE_CHARS {
+        return text.to_string();
+    }
+    let kept: String = text.chars().take(MAX_VALUE_CHARS - 3).collect();
+    format!("{}...", kept)
 }
 
 /// Generate meaningful assistant content based on actual tool calls
 ///
 /// This replaces generic "I'll execute the requested tools." with specific descriptions
-/// like professional AI assistants do.
+/// like professional AI assistants do. Use a [`Narrator`] to change the verbosity or
+/// wording.
 ///
 /// # Arguments
 /// * `tool_calls` - Slice of tool call information
@@ -32,74 +347,13 @@ impl ToolCallInfo {
 /// # Returns
 /// * `String` - Human-friendly description of what the tools will do
 pub fn generate_assistant_content(tool_calls: &[ToolCallInfo]) -> String {
-    if tool_calls.is_empty() {
-        return String::new(); // Empty content for tool-only responses
-    }
+    Narrator::new().describe_calls(tool_calls)
+}
 
-    match tool_calls.len() {
-        1 => {
-            let tool_call = &tool_calls[0];
-            match tool_call.name.as_str() {
-                "classify_task" => {
-                    "I'll analyze and classify this task to determine the best approach."
-                        .to_string()
-                }
-                "read_file" | "open" => {
-                    if let Ok(args) = serde_json::from_str::<Value>(&tool_call.arguments) {
-                        if let Some(path) = args.get("path").and_then(|p| p.as_str()) {
-                            format!("I'll read the file at `{}`.", path)
-                        } else {
-                            "I'll read the specified file.".to_string()
-                        }
-                    } else {
-                        "I'll read the file for you.".to_string()
-                    }
-                }
-                "write_file" | "create_file" | "overwrite_file" => {
-                    if let Ok(args) = serde_json::from_str::<Value>(&tool_call.arguments) {
-                        if let Some(path) = args.get("path").and_then(|p| p.as_str()) {
-                            format!("I'll write content to `{}`.", path)
-                        } else {
-                            "I'll write to the specified file.".to_string()
-                        }
-                    } else {
-                        "I'll write the file for you.".to_string()
-                    }
-                }
-                "create_directory" => "I'll create the directory structure you need.".to_string(),
-                "run_command" | "execute_command" => {
-                    if let Ok(args) = serde_json::from_str::<Value>(&tool_call.arguments) {
-                        if let Some(command) = args.get("command").and_then(|c| c.as_str()) {
-                            format!("I'll execute the command: `{}`", command)
-                        } else {
-                            "I'll execute the specified command.".to_string()
-                        }
-                    } else {
-                        "I'll execute the command for you.".to_string()
-                    }
-                }
-                "submit" => "Task completed successfully.".to_string(),
-                _ => format!(
-                    "I'll use the {} tool to help with your request.",
-                    tool_call.name
-                ),
-            }
-        }
-        2 => {
-            let tool_names: Vec<&str> = tool_calls.iter().map(|tc| tc.name.as_str()).collect();
-            format!(
-                "I'll help you with that by using {} and {}.",
-                tool_names[0], tool_names[1]
-            )
-        }
-        _ => {
-            let tool_names: Vec<&str> = tool_calls.iter().map(|tc| tc.name.as_str()).collect();
-            format!(
-                "I'll work on this using multiple tools: {}.",
-                tool_names.join(", ")
-            )
-        }
-    }
+/// Summarize what a tool call did, e.g. "Edited src/lib.rs (replaced 1 occurrence at
+/// line 42)", for chat UIs
+pub fn generate_result_summary(tool_call: &ToolCallInfo, result: &ToolResult) -> String {
+    Narrator::new().describe_result(tool_call, result)
 }
 
 #[cfg(test)]
@@ -138,4 +392,64 @@ mod tests {
         let content = generate_assistant_content(&[]);
         assert_eq!(content, "");
     }
+
+    #[test]
+    fn test_narrate_calls_and_results() {
+        let search = ToolCallInfo::new("search_dir", r#"{"search_term": "TODO", "dir": "src"}"#);
+        assert_eq!(
+            generate_assistant_content(std::slice::from_ref(&search)),
+            "I'll search for `TODO` in `src`."
+        );
+        let without_dir = ToolCallInfo::new("search_dir", r#"{"search_term": "TODO"}"#);
+        assert_eq!(
+            generate_assistant_content(&[without_dir]),
+            "I'll search for `TODO`."
+        );
+        // A required placeholder without a value falls back to the generic sentence
+        assert_eq!(
+            generate_assistant_content(&[ToolCallInfo::new("copy_path", "not json")]),
+            "I'll use the copy_path tool to help with your request."
+        );
+
+        let edit = ToolCallInfo::new("replace_text", r#"{"path": "src/lib.rs"}"#);
+        let edited = ToolResult::success_with_data(
+            "Successfully replaced occurrence 1 in src/lib.rs",
+            serde_json::json!({"path": "src/lib.rs", "occurrence": 1, "line": 42}),
+        );
+        assert_eq!(
+            generate_result_summary(&edit, &edited),
+            "Edited src/lib.rs (replaced 1 occurrence at line 42)"
+        );
+        let compact = Narrator::new().with_verbosity(Verbosity::Compact);
+        assert_eq!(compact.describe_result(&edit, &edited), "Edited src/lib.rs");
+
+        let tests = ToolCallInfo::new("run_tests", "{}");
+        let failed = ToolResult::error_with_data(
+            "2 tests failed",
+            serde_json::json!({"command": "cargo test", "failures": [{}, {}]}),
+        );
+        assert_eq!(
+            generate_result_summary(&tests, &failed),
+            "2 tests failed (cargo test)"
+        );
+        assert_eq!(
+            generate_result_summary(
+                &ToolCallInfo::new("my_tool", "{}"),
+                &ToolResult::error("Boom\ndetails")
+            ),
+            "my_tool failed: Boom"
+        );
+
+        let german = Narrator::new()
+            .with_template("search_dir", "Ich suche nach `{search_term}`[ in `{dir}`].")
+            .with_template("_done", "{tool} [[fertig]]");
+        assert_eq!(
+            german.describe_call(&search),
+            "Ich suche nach `TODO` in `src`."
+        );
+        assert_eq!(
+            german.describe_result(&ToolCallInfo::new("x", "{}"), &ToolResult::success("")),
+            "x [fertig]"
+        );
+    }
 }
@@ -0,0 +1,140 @@
+//! Conversation-context helpers
+//!
+//! Editing tools report where they changed a file but not what it looks like
+//! afterwards, so models tend to follow every edit with an `open` or `goto` just to
+//! check it. [`append_edit_window`] saves that round trip by appending the lines
+//! around the edit to the result.
+
+use crate::core::{Tool, ToolCategory, ToolResult};
+use crate::state::{NumberedLines, ToolState};
+use serde_json::Value;
+use std::path::Path;
+use std::sync::{Arc, Mutex};
+
+/// Lines shown above and below an edit by default
+pub const DEFAULT_EDIT_CONTEXT_LINES: usize = 5;
+
+/// The line (1-based) an editing result reports its change at
+///
+/// Reads `line`, `line_number` or `start_line` from the result data, or the first
+/// `applied_at` location.
+pub fn edit_line(data: &Value) -> Option<usize> {
+    ["line", "line_number", "start_line"]
+        .iter()
+        .find_map(|key| data.get(key).and_then(Value::as_u64))
+        .or_else(|| data.pointer("/applied_at/0/line_start")?.as_u64())
+        .map(|line| line as usize)
+}
+
+/// Append the lines around the edit `result` reports to its message
+///
+/// Only successful results of editing tools that changed the current file of `state`
+/// at a known line get a window; previews and dry runs do not. The window covers
+/// `context_lines` above and below the edited line and is also reported as
+/// `edit_window` (`start_line`, `end_line`) in the result data. Returns whether a
+/// window was added.
+pub fn append_edit_window(
+    tool: &dyn Tool,
+    state: &Arc<Mutex<ToolState>>,
+    result: &mut ToolResult,
+    context_lines: usize,
+) -> bool {
+    if !result.success || tool.category() != ToolCategory::Editing {
+        return false;
+    }
+    let Some(data) = &result.data else {
+        return false;
+    };
+    let unchanged = ["preview", "dry_run"]
+        .iter()
+        .any(|key| data.get(key).and_then(Value::as_bool) == Some(true));
+    if unchanged {
+        return false;
+    }
+    let (Some(path), Some(line)) = (data.get("path").and_then(Value::as_str), edit_line(data))
+    else {
+        return false;
+    };
+    let Ok(state) = state.lock() else {
+        return false;
+    };
+    if !state.is_current_file(Path::new(path)) {
+        return false;
+    }
+    let Some(file) = state.get_current_file_state() else {
+        return false;
+    };
+
+    let total = file.content.len();
+    let line = line.clamp(1, total.max(1));
+    let start = line.saturating_sub(context_lines + 1);
+    let end = (line + context_lines).min(total);
+    let window = NumberedLines::new(&file.content[start..end], start + 1);
+    if window.is_empty() {
+        return false;
+    }
+    result.message.push_str(&format!(
+        "\n\nLines {}-{} of {} ({} lines) after the edit:\n{}",
+        start + 1,
+        end,
+        path,
+        total,
+        window
+    ));
+    result.insert_data(
+        "edit_window",
+        serde_json::json!({"start_line": start + 1, "end_line": end}),
+    );
+    true
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::core::ToolArgs;
+    use crate::editing::specialized_tools::ReplaceTextTool;
+
+    #[test]
+    fn test_append_edit_window() {
+        let temp_dir = tempfile::TempDir::new().unwrap();
+        let path = temp_dir.path().join("notes.txt");
+        let lines: Vec<String> = (1..=20).map(|i| format!("line {}", i)).collect();
+        std::fs::write(&path, lines.join("\n")).unwrap();
+        let state = Arc::new(Mutex::new(ToolState::new()));
+        let mut tool = ReplaceTextTool::new();
+        let named = [
+            ("path", path.to_string_lossy().to_string()),
+            ("old_text", "line 10".to_string()),
+            ("new_text", "line ten".to_string()),
+        ];
+        let mut args = ToolArgs::with_named_args(
+            Vec::new(),
+            named.map(|(key, value)| (key.to_string(), value)).into(),
+        );
+
+        // Not the current file: nothing to refresh
+        let mut result = tool.execute(&args, &state).unwrap();
+        assert!(!append_edit_window(&tool, &state, &mut result, 2));
+
+        state
+            .lock()
+            .unwrap()
+            .open_file(path.clone(), lines, 100)
+            .unwrap();
+        args.named_args
+            .insert("old_text".to_string(), "line 12".to_string());
+        let mut result = tool.execute(&args, &state).unwrap();
+        assert!(append_edit_window(&tool, &state, &mut result, 2));
+        assert!(
+            result.message.ends_with(
+                "after the edit:\n  10 | line ten\n  11 | line 11\n  12 | line ten\n  13 | line 13\n  14 | line 14"
+            ),
+            "{}",
+            result.message
+        );
+        assert_eq!(
+            result.data.unwrap()["edit_window"],
+            serde_json::json!({"start_line": 10, "end_line": 14})
+        );
+    }
+}
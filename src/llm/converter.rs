@@ -65,6 +65,7 @@ pub fn json_to_tool_args(tool_name: &str, args: Value) -> Result<ToolArgs> {
                 if let Some(dir) = obj.get("dir").and_then(|v| v.as_str()) {
                     positional_args.push(dir.to_string());
                 }
+                forward_named(obj, &["package"], &mut named_args);
             }
             "edit" => {
                 // For edit tool, preserve all arguments as named arguments to support complex modes
@@ -105,11 +106,17 @@ pub fn json_to_tool_args(tool_name: &str, args: Value) -> Result<ToolArgs> {
                 if let Some(command) = obj.get("command").and_then(|v| v.as_str()) {
                     positional_args.push(command.to_string());
                 }
+                forward_named(
+                    obj,
+                    &["keep_ansi", "terminal", "timeout_secs"],
+                    &mut named_args,
+                );
             }
             "filemap" => {
                 if let Some(file_path) = obj.get("file_path").and_then(|v| v.as_str()) {
                     positional_args.push(file_path.to_string());
                 }
+                forward_named(obj, &["diagram"], &mut named_args);
             }
             // File Creation Tools
             "create_file" => {
@@ -119,6 +126,14 @@ pub fn json_to_tool_args(tool_name: &str, args: Value) -> Result<ToolArgs> {
                 if let Some(content) = obj.get("content").and_then(|v| v.as_str()) {
                     positional_args.push(content.to_string());
                 }
+                forward_raw_content(obj, &mut named_args);
+                if obj.contains_key("register_module") {
+                    forward_named(
+                        obj,
+                        &["path", "content", "register_module"],
+                        &mut named_args,
+                    );
+                }
             }
             // File Editing Tools
             "replace_text" => {
@@ -134,6 +149,7 @@ pub fn json_to_tool_args(tool_name: &str, args: Value) -> Result<ToolArgs> {
                 if let Some(occurrence) = obj.get("occurrence").and_then(|v| v.as_u64()) {
                     positional_args.push(occurrence.to_string());
                 }
+                forward_raw_content(obj, &mut named_args);
             }
             "delete_function" => {
                 if let Some(file_name) = obj
@@ -179,6 +195,15 @@ pub fn json_to_tool_args(tool_name: &str, args: Value) -> Result<ToolArgs> {
                 if let Some(content) = obj.get("content").and_then(|v| v.as_str()) {
                     positional_args.push(content.to_string());
                 }
+                forward_raw_content(obj, &mut named_args);
+                // Named arguments take precedence, so the override needs everything named
+                if obj.contains_key("allow_protected") {
+                    forward_named(
+                        obj,
+                        &["path", "content", "allow_protected"],
+                        &mut named_args,
+                    );
+                }
             }
             "delete_line" => {
                 if let Some(path) = obj.get("path").and_then(|v| v.as_str()) {
@@ -196,8 +221,20 @@ pub fn json_to_tool_args(tool_name: &str, args: Value) -> Result<ToolArgs> {
                 if let Some(path) = obj.get("path").and_then(|v| v.as_str()) {
                     positional_args.push(path.to_string());
                 }
-                if let Some(recursive) = obj.get("recursive").and_then(|v| v.as_bool()) {
-                    positional_args.push(recursive.to_string());
+                let recursive = obj.get("recursive").and_then(|v| v.as_bool());
+                let permanent = obj.get("permanent").and_then(|v| v.as_bool());
+                if recursive.is_some() || permanent.is_some() {
+                    positional_args.push(recursive.unwrap_or(false).to_string());
+                }
+                if let Some(permanent) = permanent {
+                    positional_args.push(permanent.to_string());
+                }
+                if obj.contains_key("allow_protected") {
+                    forward_named(
+                        obj,
+                        &["path", "recursive", "permanent", "allow_protected"],
+                        &mut named_args,
+                    );
                 }
             }
             "copy_path" => {
@@ -239,6 +276,7 @@ pub fn json_to_tool_args(tool_name: &str, args: Value) -> Result<ToolArgs> {
                     positional_args.push(dest.to_string());
                     named_args.insert("destination".to_string(), dest.to_string());
                 }
+                forward_named(obj, &["allow_protected"], &mut named_args);
             }
             // File System Interaction Tools
             "list_directory" => {
@@ -268,12 +306,15 @@ pub fn json_to_tool_args(tool_name: &str, args: Value) -> Result<ToolArgs> {
                 }
             }
             "submit" => {
-                // No arguments needed for submit
+                forward_named(obj, &["summary"], &mut named_args);
             }
             "classify_task" => {
                 debug!("🔍 DEBUG convert_json_to_tool_args for classify_task:");
-                debug!("   Input JSON: {}", serde_json::to_string(&args).unwrap_or_default());
-                
+                debug!(
+                    "   Input JSON: {}",
+                    serde_json::to_string(&args).unwrap_or_default()
+                );
+
                 if let Some(task_type) = obj.get("task_type").and_then(|v| v.as_str()) {
                     debug!("   Found task_type: '{}'", task_type);
                     positional_args.push(task_type.to_string());
@@ -297,16 +338,225 @@ pub fn json_to_tool_args(tool_name: &str, args: Value) -> Result<ToolArgs> {
                 }
             }
         }
+        // The registry routes the call by session and verbosity and strips them again
+        forward_named(
+            obj,
+            &[crate::core::SESSION_ARG, crate::core::VERBOSITY_ARG],
+            &mut named_args,
+        );
     }
 
     Ok(ToolArgs::with_named_args(positional_args, named_args))
 }
 
+/// Copy optional parameters that tools read as named arguments alongside positional ones
+fn forward_named(
+    obj: &serde_json::Map<String, Value>,
+    keys: &[&str],
+    named_args: &mut HashMap<String, String>,
+) {
+    for key in keys {
+        match obj.get(*key) {
+            Some(Value::String(s)) => {
+                named_args.insert(key.to_string(), s.clone());
+            }
+            Some(Value::Null) | None => {}
+            Some(other) => {
+                named_args.insert(key.to_string(), other.to_string());
+            }
+        }
+    }
+}
+
+/// Pass the whole call through as `json` when `content` is an array of lines or
+/// encoded, since neither survives conversion to plain string arguments, or when it
+/// asks for `validate_syntax`, `allow_suspicious` or `allow_shrink`, which the
+/// positional form has no place for
+fn forward_raw_content(
+    obj: &serde_json::Map<String, Value>,
+    named_args: &mut HashMap<String, String>,
+) {
+    if matches!(obj.get("content"), Some(Value::Array(_)))
+        || obj.contains_key("encoding")
+        || obj.contains_key("validate_syntax")
+        || obj.contains_key("allow_suspicious")
+        || obj.contains_key("allow_shrink")
+    {
+        named_args.insert("json".to_string(), Value::Object(obj.clone()).to_string());
+    }
+}
+
+/// Longest string argument kept whole in resolved arguments; longer values (file
+/// contents, mostly) are cut and marked with their size
+pub const RESOLVED_ARG_MAX_BYTES: usize = 500;
+
+/// Arguments whose values are paths and are canonicalized in resolved arguments.
+/// Symlink targets are left alone because they are relative to the link.
+pub(crate) fn is_path_param(name: &str) -> bool {
+    matches!(
+        name,
+        "path" | "file" | "file_path" | "dir" | "source" | "destination" | "root"
+    ) || name.ends_with("_path")
+}
+
+/// The arguments a tool received, as one JSON object keyed by its schema's parameters
+///
+/// The reverse of [`json_to_tool_args`]: positional arguments are named in the order
+/// their parameters appear in `signature`, a `json` argument is merged in and named
+/// arguments override both. String values are converted to the schema's types,
+/// path parameters are canonicalized like tools resolve them, and parameters the call
+/// left out are filled in from schema defaults. Positional arguments without a matching
+/// parameter are kept as `arg<index>`.
+pub fn resolve_args(signature: &str, schema: &Value, args: &ToolArgs) -> Value {
+    let mut resolved = named_args(signature, schema, args);
+    for (key, value) in resolved.iter_mut() {
+        if let Value::String(text) = value {
+            if is_path_param(key) && !text.is_empty() {
+                *text = crate::state::canonical_path(std::path::Path::new(text))
+                    .to_string_lossy()
+                    .to_string();
+            } else if text.len() > RESOLVED_ARG_MAX_BYTES {
+                let mut cut = RESOLVED_ARG_MAX_BYTES;
+                while !text.is_char_boundary(cut) {
+                    cut -= 1;
+                }
+                *text = format!("{}... ({} bytes)", &text[..cut], text.len());
+            }
+        }
+    }
+    if let Some(properties) = schema.get("properties").and_then(Value::as_object) {
+        for (key, property) in properties {
+            if let Some(default) = property.get("default") {
+                resolved
+                    .entry(key.clone())
+                    .or_insert_with(|| default.clone());
+            }
+        }
+    }
+    Value::Object(resolved)
+}
+
+/// The arguments a call passed, keyed by parameter name and converted to the schema's
+/// types, as [`resolve_args`] names them but with paths as given and without defaults
+pub fn named_args(
+    signature: &str,
+    schema: &Value,
+    args: &ToolArgs,
+) -> serde_json::Map<String, Value> {
+    let empty = serde_json::Map::new();
+    let properties = schema
+        .get("properties")
+        .and_then(Value::as_object)
+        .unwrap_or(&empty);
+
+    // Parameter names in the order the signature mentions them, ignoring case and
+    // underscores so `<TypeName>` names `type_name`
+    let squash = |name: &str| name.replace('_', "").to_lowercase();
+    let mut order: Vec<&str> = Vec::new();
+    for word in signature.split(|c: char| !(c.is_alphanumeric() || c == '_')) {
+        let word = squash(word);
+        if let Some(name) = properties.keys().find(|name| squash(name) == word) {
+            if !order.contains(&name.as_str()) {
+                order.push(name);
+            }
+        }
+    }
+
+    let mut resolved = serde_json::Map::new();
+    for (index, value) in args.args.iter().enumerate() {
+        let key = order
+            .get(index)
+            .map_or_else(|| format!("arg{}", index), |name| name.to_string());
+        resolved.insert(key, Value::String(value.clone()));
+    }
+    if let Some(Ok(Value::Object(json))) = args
+        .get_named_arg("json")
+        .map(|json| serde_json::from_str::<Value>(json))
+    {
+        resolved.extend(json);
+    }
+    for (key, value) in &args.named_args {
+        if key != "json" {
+            resolved.insert(key.clone(), Value::String(value.clone()));
+        }
+    }
+
+    for (key, value) in resolved.iter_mut() {
+        if let Value::String(text) = value {
+            let kind = properties
+                .get(key)
+                .and_then(|p| p.get("type"))
+                .and_then(Value::as_str);
+            *value = typed_value(text, kind);
+        }
+    }
+    resolved
+}
+
+/// `text` as a value of the JSON schema type `kind`, or as a string if it does not parse
+fn typed_value(text: &str, kind: Option<&str>) -> Value {
+    let parsed = match kind {
+        Some("integer") => text.trim().parse::<i64>().ok().map(Value::from),
+        Some("number") => text.trim().parse::<f64>().ok().map(Value::from),
+        Some("boolean") => text.trim().parse::<bool>().ok().map(Value::from),
+        Some("array") | Some("object") => serde_json::from_str(text).ok(),
+        _ => None,
+    };
+    parsed.unwrap_or_else(|| Value::String(text.to_string()))
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
     use serde_json::json;
 
+    #[test]
+    fn test_resolve_args() {
+        let schema = json!({
+            "type": "object",
+            "properties": {
+                "path": {"type": "string"},
+                "line_number": {"type": "integer"},
+                "recursive": {"type": "boolean", "default": true},
+                "content": {"type": "string"}
+            }
+        });
+        let signature = "tool <path> [<line_number>]";
+
+        let positional = ToolArgs::from_args(&["./Cargo.toml", "7", "extra"]);
+        let resolved = resolve_args(signature, &schema, &positional);
+        let cwd = std::env::current_dir().unwrap();
+        assert_eq!(
+            resolved,
+            json!({
+                "path": cwd.join("Cargo.toml").to_string_lossy(),
+                "line_number": 7,
+                "arg2": "extra",
+                "recursive": true
+            })
+        );
+
+        // Named arguments override `json`, which overrides positional ones
+        let named = ToolArgs::with_named_args(
+            vec!["a.txt".to_string()],
+            HashMap::from([
+                (
+                    "json".to_string(),
+                    json!({"line_number": 1, "recursive": false}).to_string(),
+                ),
+                ("line_number".to_string(), "two".to_string()),
+                ("content".to_string(), "x".repeat(600)),
+            ]),
+        );
+        let resolved = resolve_args(signature, &schema, &named);
+        assert_eq!(resolved["line_number"], "two");
+        assert_eq!(resolved["recursive"], false);
+        assert!(resolved["path"].as_str().unwrap().ends_with("a.txt"));
+        let content = resolved["content"].as_str().unwrap();
+        assert!(content.ends_with("... (600 bytes)"));
+        assert!(content.len() < 600);
+    }
+
     #[test]
     fn test_json_to_tool_args_simple() {
         let args = json!({
@@ -3,10 +3,12 @@
 //! Provides utilities to execute tool calls with proper error handling,
 //! result processing, and optional callbacks for logging and custom behavior.
 
-use crate::ToolRegistry;
+use crate::core::{CALL_ID_ARG, DEFAULT_SESSION, SESSION_ARG};
+use crate::{Attachment, ToolArgs, ToolRegistry};
 use anyhow::Result;
 use serde_json::Value;
 
+use super::context::append_edit_window;
 use super::converter::json_to_tool_args;
 use super::result_handler::{handle_large_result, ResultHandlerConfig};
 
@@ -21,6 +23,39 @@ pub struct ToolExecutionResult {
     pub content: String,
     /// Whether the execution succeeded
     pub success: bool,
+    /// The call never ran because an earlier call failed and
+    /// [`ExecutionOptions::continue_on_error`] is off
+    pub skipped: bool,
+    /// Time spent running the tool, in milliseconds; 0 for calls that never ran
+    pub duration_ms: u64,
+    /// The batch had an identical call and this result is shared between them. Only
+    /// the first of them ran; the others have a `duration_ms` of 0.
+    pub deduplicated: bool,
+    /// Images and other non-text artifacts of the result
+    pub attachments: Vec<Attachment>,
+}
+
+impl ToolExecutionResult {
+    fn not_run(tool_call: &ToolCallRequest, content: String, skipped: bool) -> Self {
+        Self {
+            tool_call_id: tool_call.id.clone(),
+            tool_name: tool_call.name.clone(),
+            content,
+            success: false,
+            skipped,
+            duration_ms: 0,
+            deduplicated: false,
+            attachments: Vec::new(),
+        }
+    }
+
+    fn skipped(tool_call: &ToolCallRequest) -> Self {
+        Self::not_run(
+            tool_call,
+            format!("Skipped {}: an earlier tool call failed", tool_call.name),
+            true,
+        )
+    }
 }
 
 /// Simple tool call representation for execution
@@ -69,6 +104,63 @@ impl ExecutionCallback for NoOpCallback {
     fn on_compact_log(&mut self, _compact_json: &str) {}
 }
 
+/// How a batch of tool calls is executed
+#[derive(Debug, Clone)]
+pub struct ExecutionOptions {
+    /// Most calls running at once. Above 1, consecutive parallel-safe calls (searches
+    /// and other read-only lookups, see `Tool::is_parallel_safe`) run concurrently;
+    /// edits, commands and navigation still run one at a time, in order.
+    pub max_parallel: usize,
+    /// Whether the calls after a failed one still run (the default). When off, they
+    /// come back as skipped failures, e.g. so an edit never runs after the search it
+    /// depends on failed.
+    pub continue_on_error: bool,
+    /// Whether an exact duplicate of an earlier call (same tool and arguments) shares
+    /// that call's result instead of running again (the default). Only calls of
+    /// idempotent tools (see `Tool::is_idempotent`) with nothing but parallel-safe
+    /// calls between them are merged, so a search repeated after an edit still runs.
+    pub deduplicate: bool,
+    /// Lines of context shown above and below an edit of the open file, appended to
+    /// the editing tool's result (see [`append_edit_window`]); `None` (the default)
+    /// appends nothing
+    pub edit_window: Option<usize>,
+}
+
+impl Default for ExecutionOptions {
+    fn default() -> Self {
+        Self {
+            max_parallel: 1,
+            continue_on_error: true,
+            deduplicate: true,
+            edit_window: None,
+        }
+    }
+}
+
+impl ExecutionOptions {
+    pub fn with_max_parallel(mut self, max_parallel: usize) -> Self {
+        self.max_parallel = max_parallel.max(1);
+        self
+    }
+
+    pub fn with_continue_on_error(mut self, continue_on_error: bool) -> Self {
+        self.continue_on_error = continue_on_error;
+        self
+    }
+
+    pub fn with_deduplicate(mut self, deduplicate: bool) -> Self {
+        self.deduplicate = deduplicate;
+        self
+    }
+
+    /// Show the model `context_lines` around each edit of the open file, e.g.
+    /// [`DEFAULT_EDIT_CONTEXT_LINES`](super::context::DEFAULT_EDIT_CONTEXT_LINES)
+    pub fn with_edit_window(mut self, context_lines: usize) -> Self {
+        self.edit_window = Some(context_lines);
+        self
+    }
+}
+
 /// Execute tool calls and return combined result string
 ///
 /// # Arguments
@@ -85,60 +177,37 @@ pub fn execute_tool_calls(
     result_config: &ResultHandlerConfig,
     callback: &mut dyn ExecutionCallback,
 ) -> Result<String> {
-    let mut results = Vec::new();
-
-    for tool_call in tool_calls {
-        let tool_name = &tool_call.name;
-        let tool_args_str = &tool_call.arguments;
-
-        // Log the compact tool call JSON
-        let compact_json = serde_json::json!({
-            "name": tool_name,
-            "arguments": serde_json::from_str::<Value>(tool_args_str).unwrap_or(serde_json::json!({}))
-        });
-        if let Ok(compact_str) = serde_json::to_string(&compact_json) {
-            callback.on_compact_log(&compact_str);
-        }
-
-        callback.on_tool_start(tool_name, tool_args_str);
-
-        let args: Value = match serde_json::from_str(tool_args_str) {
-            Ok(args) => args,
-            Err(e) => {
-                let error_msg = format!("Failed to parse tool arguments for {}: {}", tool_name, e);
-                callback.on_tool_complete(tool_name, tool_args_str, &error_msg, false);
-                results.push(format!("Tool: {}\nError: {}", tool_name, error_msg));
-                continue;
-            }
-        };
-
-        // Convert JSON args to ToolArgs format
-        let tool_args = match json_to_tool_args(tool_name, args) {
-            Ok(args) => args,
-            Err(e) => {
-                let error_msg = format!("Failed to convert arguments for {}: {}", tool_name, e);
-                callback.on_tool_complete(tool_name, tool_args_str, &error_msg, false);
-                results.push(format!("Tool: {}\nError: {}", tool_name, error_msg));
-                continue;
-            }
-        };
-
-        match registry.execute_tool(tool_name, &tool_args) {
-            Ok(result) => {
-                callback.on_tool_complete(tool_name, tool_args_str, &result.message, true);
-
-                // Apply large result handling
-                let processed_result = handle_large_result(tool_name, &result.message, result_config);
-                results.push(format!("Tool: {}\nResult: {}", tool_name, processed_result));
-            }
-            Err(e) => {
-                let error_msg = format!("Tool execution failed for {}: {}", tool_name, e);
-                callback.on_tool_complete(tool_name, tool_args_str, &error_msg, false);
-                results.push(format!("Tool: {}\nError: {}", tool_name, error_msg));
-            }
-        }
-    }
+    execute_tool_calls_with_options(
+        registry,
+        tool_calls,
+        result_config,
+        &ExecutionOptions::default(),
+        callback,
+    )
+}
 
+/// [`execute_tool_calls`] with [`ExecutionOptions`]
+pub fn execute_tool_calls_with_options(
+    registry: &mut ToolRegistry,
+    tool_calls: Vec<ToolCallRequest>,
+    result_config: &ResultHandlerConfig,
+    options: &ExecutionOptions,
+    callback: &mut dyn ExecutionCallback,
+) -> Result<String> {
+    let results = execute_tool_calls_structured_with_options(
+        registry,
+        tool_calls,
+        result_config,
+        options,
+        callback,
+    )?;
+    let results: Vec<String> = results
+        .iter()
+        .map(|result| {
+            let label = if result.success { "Result" } else { "Error" };
+            format!("Tool: {}\n{}: {}", result.tool_name, label, result.content)
+        })
+        .collect();
     Ok(results.join("\n\n"))
 }
 
@@ -161,12 +230,134 @@ pub fn execute_tool_calls_structured(
     result_config: &ResultHandlerConfig,
     callback: &mut dyn ExecutionCallback,
 ) -> Result<Vec<ToolExecutionResult>> {
-    let mut results = Vec::new();
+    execute_tool_calls_structured_with_options(
+        registry,
+        tool_calls,
+        result_config,
+        &ExecutionOptions::default(),
+        callback,
+    )
+}
+
+/// [`execute_tool_calls_structured`] with [`ExecutionOptions`]
+///
+/// When calls run in parallel, `on_tool_start` is called for every call of the batch
+/// before any runs and `on_tool_complete` for each afterwards, in call order. Calls
+/// skipped after a failure get no callbacks, unless `on_tool_start` was already made.
+/// Duplicate calls sharing an earlier call's result get no callbacks either.
+pub fn execute_tool_calls_structured_with_options(
+    registry: &mut ToolRegistry,
+    tool_calls: Vec<ToolCallRequest>,
+    result_config: &ResultHandlerConfig,
+    options: &ExecutionOptions,
+    callback: &mut dyn ExecutionCallback,
+) -> Result<Vec<ToolExecutionResult>> {
+    let originals = if options.deduplicate {
+        find_duplicates(registry, &tool_calls)
+    } else {
+        vec![None; tool_calls.len()]
+    };
+    let unique: Vec<ToolCallRequest> = tool_calls
+        .iter()
+        .zip(&originals)
+        .filter(|(_, original)| original.is_none())
+        .map(|(tool_call, _)| tool_call.clone())
+        .collect();
+    let mut unique_results =
+        execute_unique(registry, unique, result_config, options, callback).into_iter();
+
+    let mut results: Vec<ToolExecutionResult> = Vec::with_capacity(tool_calls.len());
+    for (tool_call, original) in tool_calls.iter().zip(originals) {
+        match original {
+            None => results.push(unique_results.next().expect("one result per unique call")),
+            Some(original) => {
+                results[original].deduplicated = true;
+                let mut shared = results[original].clone();
+                shared.tool_call_id = tool_call.id.clone();
+                shared.duration_ms = 0;
+                results.push(shared);
+            }
+        }
+    }
+    Ok(results)
+}
+
+/// For each call, the earlier call whose result it can share, if any
+fn find_duplicates(registry: &ToolRegistry, tool_calls: &[ToolCallRequest]) -> Vec<Option<usize>> {
+    // Calls that ran since the last call that may have changed what the next sees
+    let mut seen: Vec<(usize, &str, Value)> = Vec::new();
+    let mut originals = Vec::with_capacity(tool_calls.len());
+    for (index, tool_call) in tool_calls.iter().enumerate() {
+        let tool = registry.get_tool(&tool_call.name);
+        let args = serde_json::from_str::<Value>(&tool_call.arguments).ok();
+        let idempotent = tool.is_some_and(|tool| tool.is_idempotent());
+        let original = match &args {
+            Some(args) if idempotent => seen
+                .iter()
+                .find(|(_, name, seen_args)| *name == tool_call.name && seen_args == args)
+                .map(|(original, _, _)| *original),
+            _ => None,
+        };
+        originals.push(original);
+        if original.is_some() {
+            continue;
+        }
+        if !tool.is_some_and(|tool| tool.is_parallel_safe()) {
+            seen.clear();
+        }
+        if let (Some(args), true) = (args, idempotent) {
+            seen.push((index, &tool_call.name, args));
+        }
+    }
+    originals
+}
+
+/// Run `tool_calls` in order, in the batches `options` allows
+fn execute_unique(
+    registry: &mut ToolRegistry,
+    tool_calls: Vec<ToolCallRequest>,
+    result_config: &ResultHandlerConfig,
+    options: &ExecutionOptions,
+    callback: &mut dyn ExecutionCallback,
+) -> Vec<ToolExecutionResult> {
+    let mut results: Vec<ToolExecutionResult> = Vec::with_capacity(tool_calls.len());
+    // One call at a time keeps each call's start and completion together
+    let batch_size = if options.max_parallel > 1 {
+        tool_calls.len().max(1)
+    } else {
+        1
+    };
+    for batch in tool_calls.chunks(batch_size) {
+        if !options.continue_on_error && results.iter().any(|result| !result.success) {
+            results.extend(batch.iter().map(ToolExecutionResult::skipped));
+            continue;
+        }
+        execute_batch(
+            registry,
+            batch,
+            result_config,
+            options,
+            callback,
+            &mut results,
+        );
+    }
+    results
+}
 
-    for tool_call in tool_calls {
+/// Parse, run and report `batch`, appending its results in call order
+fn execute_batch(
+    registry: &mut ToolRegistry,
+    batch: &[ToolCallRequest],
+    result_config: &ResultHandlerConfig,
+    options: &ExecutionOptions,
+    callback: &mut dyn ExecutionCallback,
+    results: &mut Vec<ToolExecutionResult>,
+) {
+    let stop_on_failure = !options.continue_on_error;
+    let mut prepared = Vec::with_capacity(batch.len());
+    for tool_call in batch {
         let tool_name = &tool_call.name;
         let tool_args_str = &tool_call.arguments;
-        let tool_call_id = tool_call.id.clone();
 
         // Log the compact tool call JSON
         let compact_json = serde_json::json!({
@@ -179,64 +370,196 @@ pub fn execute_tool_calls_structured(
 
         callback.on_tool_start(tool_name, tool_args_str);
 
-        let args: Value = match serde_json::from_str(tool_args_str) {
-            Ok(args) => args,
-            Err(e) => {
-                let error_msg = format!("Failed to parse tool arguments for {}: {}", tool_name, e);
-                callback.on_tool_complete(tool_name, tool_args_str, &error_msg, false);
-                results.push(ToolExecutionResult {
-                    tool_call_id,
-                    tool_name: tool_name.clone(),
-                    content: error_msg,
-                    success: false,
-                });
-                continue;
-            }
-        };
+        prepared.push(
+            serde_json::from_str::<Value>(tool_args_str)
+                .map_err(|e| format!("Failed to parse tool arguments for {}: {}", tool_name, e))
+                .and_then(|args| {
+                    // Convert JSON args to ToolArgs format
+                    json_to_tool_args(tool_name, args).map_err(|e| {
+                        format!("Failed to convert arguments for {}: {}", tool_name, e)
+                    })
+                })
+                .map(|mut args| {
+                    // Provenance records attribute changes to the model's call id
+                    if !tool_call.id.is_empty() {
+                        args.named_args
+                            .insert(CALL_ID_ARG.to_string(), tool_call.id.clone());
+                    }
+                    args
+                }),
+        );
+    }
 
-        // Convert JSON args to ToolArgs format
-        let tool_args = match json_to_tool_args(tool_name, args) {
-            Ok(args) => args,
-            Err(e) => {
-                let error_msg = format!("Failed to convert arguments for {}: {}", tool_name, e);
-                callback.on_tool_complete(tool_name, tool_args_str, &error_msg, false);
-                results.push(ToolExecutionResult {
-                    tool_call_id,
+    // Nothing after a call with bad arguments runs when stopping on failure
+    let runnable = if stop_on_failure {
+        prepared.iter().take_while(|args| args.is_ok()).count()
+    } else {
+        prepared.len()
+    };
+    let calls: Vec<(String, ToolArgs)> = batch
+        .iter()
+        .zip(&prepared[..runnable])
+        .filter_map(|(tool_call, args)| Some((tool_call.name.clone(), args.as_ref().ok()?.clone())))
+        .collect();
+    let mut outcomes = registry
+        .execute_batch(&calls, options.max_parallel, stop_on_failure)
+        .into_iter();
+
+    let mut failed = false;
+    for (tool_call, args) in batch.iter().zip(prepared) {
+        let tool_name = &tool_call.name;
+        let outcome = match args {
+            _ if failed && stop_on_failure => None,
+            Err(error_msg) => Some(Err(error_msg)),
+            Ok(args) => outcomes.next().flatten().map(|outcome| Ok((args, outcome))),
+        };
+        let execution = match outcome {
+            None => {
+                let skipped = ToolExecutionResult::skipped(tool_call);
+                callback.on_tool_complete(tool_name, &tool_call.arguments, &skipped.content, false);
+                skipped
+            }
+            Some(Err(error_msg)) => {
+                callback.on_tool_complete(tool_name, &tool_call.arguments, &error_msg, false);
+                ToolExecutionResult::not_run(tool_call, error_msg, false)
+            }
+            Some(Ok((args, outcome))) => {
+                let (content, success, attachments) = match outcome.result {
+                    Ok(mut result) => {
+                        if let Some(context_lines) = options.edit_window {
+                            let session = args
+                                .get_named_arg(SESSION_ARG)
+                                .map_or(DEFAULT_SESSION, String::as_str);
+                            let state = registry.session(session).get_state();
+                            if let Some(tool) = registry.get_tool(tool_name) {
+                                append_edit_window(tool, &state, &mut result, context_lines);
+                            }
+                        }
+
+                        callback.on_tool_complete(
+                            tool_name,
+                            &tool_call.arguments,
+                            &result.message,
+                            result.success,
+                        );
+
+                        // Apply large result handling
+                        (
+                            handle_large_result(tool_name, &result.message, result_config),
+                            result.success,
+                            result.attachments,
+                        )
+                    }
+                    Err(e) => {
+                        let error_msg = format!("Tool execution failed for {}: {}", tool_name, e);
+                        callback.on_tool_complete(
+                            tool_name,
+                            &tool_call.arguments,
+                            &error_msg,
+                            false,
+                        );
+                        (error_msg, false, Vec::new())
+                    }
+                };
+                ToolExecutionResult {
+                    tool_call_id: tool_call.id.clone(),
                     tool_name: tool_name.clone(),
-                    content: error_msg,
-                    success: false,
-                });
-                continue;
+                    content,
+                    success,
+                    skipped: false,
+                    duration_ms: outcome.duration.as_millis() as u64,
+                    deduplicated: false,
+                    attachments,
+                }
             }
         };
+        failed |= !execution.success;
+        results.push(execution);
+    }
+}
 
-        match registry.execute_tool(tool_name, &tool_args) {
-            Ok(result) => {
-                callback.on_tool_complete(tool_name, tool_args_str, &result.message, true);
+/// Execute a single JSON-encoded tool invocation and return a JSON response
+///
+/// The request has the form `{"tool": "...", "args": {...}, "id": ..., "session": "..."}`;
+/// `args`, `id` and `session` are optional. The optional `id` is echoed back so callers
+/// can correlate responses, and `session` selects the registry session the call runs in. Failures are reported in-band as
+/// `{"success": false, "error": {...}}` so one bad line never ends a session.
+pub fn execute_json_line(registry: &mut ToolRegistry, line: &str) -> Value {
+    let request: Value = match serde_json::from_str(line) {
+        Ok(request) => request,
+        Err(e) => {
+            return serde_json::json!({
+                "success": false,
+                "error": {"kind": "json", "message": format!("Invalid request: {}", e)}
+            })
+        }
+    };
+    let id = request.get("id").cloned().unwrap_or(Value::Null);
+
+    let tool_name = match request.get("tool").and_then(|t| t.as_str()) {
+        Some(name) => name.to_string(),
+        None => {
+            return serde_json::json!({
+                "id": id,
+                "success": false,
+                "error": {"kind": "invalid_args", "message": "Request is missing the \"tool\" field"}
+            })
+        }
+    };
+    let args = request
+        .get("args")
+        .cloned()
+        .unwrap_or_else(|| serde_json::json!({}));
+
+    let mut tool_args = match json_to_tool_args(&tool_name, args) {
+        Ok(tool_args) => tool_args,
+        Err(e) => {
+            return serde_json::json!({
+                "id": id,
+                "tool": tool_name,
+                "success": false,
+                "error": {"kind": "invalid_args", "message": e.to_string()}
+            })
+        }
+    };
 
-                // Apply large result handling
-                let processed_result = handle_large_result(tool_name, &result.message, result_config);
-                results.push(ToolExecutionResult {
-                    tool_call_id,
-                    tool_name: tool_name.clone(),
-                    content: processed_result,
-                    success: true,
-                });
-            }
-            Err(e) => {
-                let error_msg = format!("Tool execution failed for {}: {}", tool_name, e);
-                callback.on_tool_complete(tool_name, tool_args_str, &error_msg, false);
-                results.push(ToolExecutionResult {
-                    tool_call_id,
-                    tool_name: tool_name.clone(),
-                    content: error_msg,
-                    success: false,
-                });
+    if let Some(session) = request.get("session").and_then(|s| s.as_str()) {
+        tool_args
+            .named_args
+            .insert(crate::core::SESSION_ARG.to_string(), session.to_string());
+    }
+    let call_id = match &id {
+        Value::String(id) => Some(id.clone()),
+        Value::Number(id) => Some(id.to_string()),
+        _ => None,
+    };
+    if let Some(call_id) = call_id {
+        tool_args
+            .named_args
+            .insert(CALL_ID_ARG.to_string(), call_id);
+    }
+
+    match registry.execute_tool(&tool_name, &tool_args) {
+        Ok(result) => {
+            let mut response = serde_json::json!({
+                "id": id,
+                "tool": tool_name,
+                "success": result.success,
+                "message": result.message,
+                "data": result.data
+            });
+            if !result.attachments.is_empty() {
+                response["attachments"] = serde_json::json!(result.attachments);
             }
+            response
         }
+        Err(e) => serde_json::json!({
+            "id": id,
+            "tool": tool_name,
+            "success": false,
+            "error": e.to_json()
+        }),
     }
-
-    Ok(results)
 }
 
 #[cfg(test)]
@@ -275,11 +598,7 @@ mod tests {
         let config = ResultHandlerConfig::default();
         let mut callback = TestCallback::new();
 
-        let tool_calls = vec![ToolCallRequest::new(
-            "call_1",
-            "_state",
-            "{}",
-        )];
+        let tool_calls = vec![ToolCallRequest::new("call_1", "_state", "{}")];
 
         let result = execute_tool_calls(&mut registry, tool_calls, &config, &mut callback);
         assert!(result.is_ok());
@@ -295,11 +614,7 @@ mod tests {
         let config = ResultHandlerConfig::default();
         let mut callback = NoOpCallback;
 
-        let tool_calls = vec![ToolCallRequest::new(
-            "call_1",
-            "_state",
-            "{}",
-        )];
+        let tool_calls = vec![ToolCallRequest::new("call_1", "_state", "{}")];
 
         let results =
             execute_tool_calls_structured(&mut registry, tool_calls, &config, &mut callback);
@@ -312,17 +627,309 @@ mod tests {
         assert!(results[0].success);
     }
 
+    #[test]
+    fn test_execute_json_line_keeps_state_between_calls() {
+        let temp_dir = tempfile::TempDir::new().unwrap();
+        let file = temp_dir.path().join("notes.txt");
+        std::fs::write(&file, "alpha\nbeta\n").unwrap();
+        let mut registry = create_tool_registry();
+
+        let open = serde_json::json!({"id": 1, "tool": "open", "args": {"path": file}});
+        let response = execute_json_line(&mut registry, &open.to_string());
+        assert_eq!(response["id"], 1);
+        assert_eq!(response["success"], true);
+
+        let goto = r#"{"tool": "goto", "args": {"line_number": 2}}"#;
+        let response = execute_json_line(&mut registry, goto);
+        assert_eq!(response["success"], true, "{}", response);
+
+        let response = execute_json_line(&mut registry, r#"{"tool": "opn"}"#);
+        assert_eq!(response["error"]["kind"], "tool_not_found");
+
+        let response = execute_json_line(&mut registry, "not json");
+        assert_eq!(response["error"]["kind"], "json");
+    }
+
+    #[test]
+    fn test_execute_json_line_sessions_are_isolated() {
+        let temp_dir = tempfile::TempDir::new().unwrap();
+        let file = temp_dir.path().join("notes.txt");
+        std::fs::write(&file, "alpha\nbeta\n").unwrap();
+        let mut registry = create_tool_registry();
+
+        let open = serde_json::json!({"tool": "open", "args": {"path": file}, "session": "a"});
+        let response = execute_json_line(&mut registry, &open.to_string());
+        assert_eq!(response["success"], true, "{}", response);
+
+        let goto = r#"{"tool": "goto", "args": {"line_number": 2}, "session": "a"}"#;
+        let response = execute_json_line(&mut registry, goto);
+        assert_eq!(response["success"], true, "{}", response);
+
+        // Neither another session nor the default one has the file open
+        let goto = r#"{"tool": "goto", "args": {"line_number": 2, "session": "b"}}"#;
+        assert_ne!(execute_json_line(&mut registry, goto)["success"], true);
+        let goto = r#"{"tool": "goto", "args": {"line_number": 2}}"#;
+        assert_ne!(execute_json_line(&mut registry, goto)["success"], true);
+    }
+
+    #[test]
+    fn test_execute_tool_calls_in_parallel_keeps_order() {
+        let temp_dir = tempfile::TempDir::new().unwrap();
+        let file = temp_dir.path().join("notes.txt");
+        std::fs::write(&file, "alpha\nbeta\n").unwrap();
+        let dir = temp_dir.path().to_string_lossy().to_string();
+        let mut registry = create_tool_registry();
+        let mut callback = TestCallback::new();
+
+        let search = |id: &str, term: &str| {
+            ToolCallRequest::new(
+                id,
+                "search_dir",
+                serde_json::json!({"search_term": term, "dir": dir}).to_string(),
+            )
+        };
+        let tool_calls = vec![
+            search("call_1", "alpha"),
+            ToolCallRequest::new(
+                "call_2",
+                "find_file",
+                serde_json::json!({"file_name": "*.txt", "dir": dir}).to_string(),
+            ),
+            search("call_3", "gamma"),
+            ToolCallRequest::new(
+                "call_4",
+                "replace_text",
+                serde_json::json!({"path": file, "old_text": "beta", "new_text": "gamma"})
+                    .to_string(),
+            ),
+            search("call_5", "gamma"),
+            ToolCallRequest::new("call_6", "search_dir", "not json"),
+        ];
+
+        let options = ExecutionOptions::default().with_max_parallel(4);
+        let results = execute_tool_calls_structured_with_options(
+            &mut registry,
+            tool_calls,
+            &ResultHandlerConfig::default(),
+            &options,
+            &mut callback,
+        )
+        .unwrap();
+
+        let ids: Vec<&str> = results.iter().map(|r| r.tool_call_id.as_str()).collect();
+        assert_eq!(
+            ids,
+            ["call_1", "call_2", "call_3", "call_4", "call_5", "call_6"]
+        );
+        assert!(results[0].content.contains("Found 1 matches"));
+        assert!(results[1].content.contains("notes.txt"));
+        // The search before the edit does not see it; the one after does
+        assert!(
+            results[2].content.contains("No matches"),
+            "{}",
+            results[2].content
+        );
+        assert!(results[3].success, "{}", results[3].content);
+        assert!(
+            results[4].content.contains("Found 1 matches"),
+            "{}",
+            results[4].content
+        );
+        assert!(!results[5].success);
+        assert_eq!(
+            callback
+                .logs
+                .iter()
+                .filter(|log| log.starts_with("COMPLETE"))
+                .count(),
+            6
+        );
+    }
+
+    #[test]
+    fn test_execute_tool_calls_stop_on_error() {
+        let temp_dir = tempfile::TempDir::new().unwrap();
+        std::fs::write(temp_dir.path().join("notes.txt"), "alpha\n").unwrap();
+        let dir = temp_dir.path().to_string_lossy().to_string();
+        let mut registry = create_tool_registry();
+        let search = |id: &str| {
+            ToolCallRequest::new(
+                id,
+                "search_dir",
+                serde_json::json!({"search_term": "alpha", "dir": dir}).to_string(),
+            )
+        };
+        let tool_calls = vec![
+            search("call_1"),
+            ToolCallRequest::new("call_2", "no_such_tool", "{}"),
+            search("call_3"),
+        ];
+        let run = |registry: &mut ToolRegistry, options: &ExecutionOptions| {
+            let mut callback = TestCallback::new();
+            let results = execute_tool_calls_structured_with_options(
+                registry,
+                tool_calls.clone(),
+                &ResultHandlerConfig::default(),
+                options,
+                &mut callback,
+            )
+            .unwrap();
+            (results, callback.logs)
+        };
+
+        let (results, _) = run(&mut registry, &ExecutionOptions::default());
+        let flags: Vec<(bool, bool)> = results.iter().map(|r| (r.success, r.skipped)).collect();
+        assert_eq!(flags, [(true, false), (false, false), (true, false)]);
+
+        let options = ExecutionOptions::default().with_continue_on_error(false);
+        let (results, logs) = run(&mut registry, &options);
+        let flags: Vec<(bool, bool)> = results.iter().map(|r| (r.success, r.skipped)).collect();
+        assert_eq!(flags, [(true, false), (false, false), (false, true)]);
+        assert_eq!(results[2].tool_call_id, "call_3");
+        assert!(results[2].content.contains("an earlier tool call failed"));
+        assert_eq!(results[2].duration_ms, 0);
+        // The skipped call was never started
+        assert_eq!(
+            logs.iter().filter(|log| log.starts_with("START")).count(),
+            2
+        );
+
+        // In parallel every call was started, so every call is completed
+        let (results, logs) = run(&mut registry, &options.with_max_parallel(4));
+        assert!(results[2].skipped);
+        assert_eq!(
+            logs.iter()
+                .filter(|log| log.starts_with("COMPLETE"))
+                .count(),
+            3
+        );
+    }
+
+    #[test]
+    fn test_execute_tool_calls_deduplicates() {
+        let temp_dir = tempfile::TempDir::new().unwrap();
+        let file = temp_dir.path().join("notes.txt");
+        std::fs::write(&file, "alpha\n").unwrap();
+        let dir = temp_dir.path().to_string_lossy().to_string();
+        let mut registry = create_tool_registry();
+        let call =
+            |id: &str, name: &str, args: Value| ToolCallRequest::new(id, name, args.to_string());
+        let search = serde_json::json!({"search_term": "alpha", "dir": dir});
+        let tool_calls = vec![
+            call("call_1", "search_dir", search.clone()),
+            call(
+                "call_2",
+                "search_dir",
+                serde_json::json!({"dir": dir, "search_term": "alpha"}),
+            ),
+            call("call_3", "open", serde_json::json!({"path": file})),
+            call("call_4", "open", serde_json::json!({"path": file})),
+            call("call_5", "scroll_down", serde_json::json!({})),
+            call("call_6", "scroll_down", serde_json::json!({})),
+            call("call_7", "search_dir", search.clone()),
+            call(
+                "call_8",
+                "replace_text",
+                serde_json::json!({"path": file, "old_text": "alpha", "new_text": "beta"}),
+            ),
+            call("call_9", "search_dir", search),
+        ];
+        let run = |registry: &mut ToolRegistry, options: &ExecutionOptions| {
+            let mut callback = TestCallback::new();
+            let results = execute_tool_calls_structured_with_options(
+                registry,
+                tool_calls.clone(),
+                &ResultHandlerConfig::default(),
+                options,
+                &mut callback,
+            )
+            .unwrap();
+            (results, callback.logs)
+        };
+
+        let (results, logs) = run(&mut registry, &ExecutionOptions::default());
+        let ids: Vec<&str> = results.iter().map(|r| r.tool_call_id.as_str()).collect();
+        assert_eq!(
+            ids,
+            [
+                "call_1", "call_2", "call_3", "call_4", "call_5", "call_6", "call_7", "call_8",
+                "call_9"
+            ]
+        );
+        let deduplicated: Vec<bool> = results.iter().map(|r| r.deduplicated).collect();
+        // Opening a file ends the first run of searches; scrolling always runs
+        assert_eq!(
+            deduplicated,
+            [true, true, true, true, false, false, false, false, false]
+        );
+        assert_eq!(results[1].content, results[0].content);
+        assert_eq!(results[1].duration_ms, 0);
+        assert!(results[6].content.contains("Found 1 matches"));
+        assert!(
+            results[8].content.contains("No matches"),
+            "{}",
+            results[8].content
+        );
+        assert_eq!(
+            logs.iter().filter(|log| log.starts_with("START")).count(),
+            7
+        );
+
+        std::fs::write(&file, "alpha\n").unwrap();
+        let options = ExecutionOptions::default().with_deduplicate(false);
+        let (results, logs) = run(&mut registry, &options);
+        assert!(results.iter().all(|r| !r.deduplicated));
+        assert_eq!(
+            logs.iter().filter(|log| log.starts_with("START")).count(),
+            9
+        );
+    }
+
+    #[test]
+    fn test_execute_tool_calls_with_edit_window() {
+        let temp_dir = tempfile::TempDir::new().unwrap();
+        let file = temp_dir.path().join("notes.txt");
+        std::fs::write(&file, "alpha\nbeta\ngamma\ndelta\n").unwrap();
+        let mut registry = create_tool_registry();
+        let tool_calls = vec![
+            ToolCallRequest::new(
+                "call_1",
+                "open",
+                serde_json::json!({"path": file}).to_string(),
+            ),
+            ToolCallRequest::new(
+                "call_2",
+                "replace_text",
+                serde_json::json!({"path": file, "old_text": "gamma", "new_text": "GAMMA"})
+                    .to_string(),
+            ),
+        ];
+
+        let options = ExecutionOptions::default().with_edit_window(1);
+        let results = execute_tool_calls_structured_with_options(
+            &mut registry,
+            tool_calls,
+            &ResultHandlerConfig::default(),
+            &options,
+            &mut NoOpCallback,
+        )
+        .unwrap();
+        assert!(
+            results[1]
+                .content
+                .ends_with("after the edit:\n   2 | beta\n   3 | GAMMA\n   4 | delta"),
+            "{}",
+            results[1].content
+        );
+    }
+
     #[test]
     fn test_execute_tool_calls_invalid_json() {
         let mut registry = create_tool_registry();
         let config = ResultHandlerConfig::default();
         let mut callback = NoOpCallback;
 
-        let tool_calls = vec![ToolCallRequest::new(
-            "call_1",
-            "_state",
-            "invalid json",
-        )];
+        let tool_calls = vec![ToolCallRequest::new("call_1", "_state", "invalid json")];
 
         let results =
             execute_tool_calls_structured(&mut registry, tool_calls, &config, &mut callback);
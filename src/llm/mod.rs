@@ -7,16 +7,24 @@
 //!
 //! - **JSON Conversion**: Convert LLM function call JSON to CATS ToolArgs
 //! - **Tool Execution**: Execute tools with proper logging and error handling
-//! - **Result Handling**: Handle large tool results with truncation
-//! - **Assistant Content**: Generate human-friendly descriptions of tool calls
+//! - **Result Handling**: Handle large tool results with truncation, and fit a turn's
+//!   results into one token budget
+//! - **Assistant Content**: Generate human-friendly descriptions of tool calls and
+//!   their results
+//! - **Edit Context**: Show the model the lines around each edit of the open file
 
 pub mod assistant;
+pub mod context;
 pub mod converter;
 pub mod executor;
 pub mod result_handler;
 
 // Re-export main types
-pub use assistant::generate_assistant_content;
+pub use assistant::{generate_assistant_content, generate_result_summary, Narrator};
+pub use context::append_edit_window;
 pub use converter::json_to_tool_args;
-pub use executor::{execute_tool_calls, execute_tool_calls_structured, ToolExecutionResult};
-pub use result_handler::handle_large_result;
+pub use executor::{
+    execute_tool_calls, execute_tool_calls_structured, execute_tool_calls_structured_with_options,
+    execute_tool_calls_with_options, ExecutionOptions, ToolExecutionResult,
+};
+pub use result_handler::{format_observation, handle_large_result};
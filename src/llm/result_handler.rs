@@ -1,7 +1,10 @@
 //! Large tool result handling for LLM context limits
 //!
 //! Provides utilities to handle tool results that exceed size limits,
-//! either through truncation or replacement with warnings.
+//! either through truncation or replacement with warnings, and to fit all
+//! results of a turn into one token budget.
+
+use super::executor::ToolExecutionResult;
 
 /// Configuration for result handling
 #[derive(Debug, Clone)]
@@ -71,6 +74,91 @@ pub fn handle_large_result(
     }
 }
 
+/// Rough token count of `text`: one token per four characters, the usual rule of
+/// thumb for English and code. Good enough to budget without a tokenizer.
+pub fn estimate_tokens(text: &str) -> usize {
+    text.chars().count().div_ceil(4)
+}
+
+/// Format all results of a turn as one observation of at most about `token_budget`
+/// tokens (see [`estimate_tokens`])
+///
+/// Every result keeps its header line (tool, call ID, outcome); the budget left after
+/// the headers is shared between the contents. Failed results are served first, then
+/// the rest from smallest to largest, and no result gets more than an even share of
+/// what is left, so small results and errors come through whole while the largest
+/// results are cut. A cut content keeps its beginning and end around a note saying
+/// how much was left out. Results stay in call order.
+pub fn format_observation(results: &[ToolExecutionResult], token_budget: usize) -> String {
+    let headers: Vec<String> = results
+        .iter()
+        .map(|result| {
+            let outcome = if result.skipped {
+                "Skipped"
+            } else if result.success {
+                "Result"
+            } else {
+                "Error"
+            };
+            format!(
+                "Tool: {} ({})\n{}: ",
+                result.tool_name, result.tool_call_id, outcome
+            )
+        })
+        .collect();
+    let needs: Vec<usize> = results
+        .iter()
+        .map(|result| estimate_tokens(&result.content))
+        .collect();
+    // The blank lines between results cost a token each
+    let fixed: usize = headers
+        .iter()
+        .map(|header| estimate_tokens(header) + 1)
+        .sum();
+    let mut left = token_budget.saturating_sub(fixed);
+
+    let mut order: Vec<usize> = (0..results.len()).collect();
+    order.sort_by_key(|&index| (results[index].success, needs[index]));
+    let mut allowed = vec![0; results.len()];
+    for (served, &index) in order.iter().enumerate() {
+        let share = left / (order.len() - served);
+        allowed[index] = needs[index].min(share);
+        left -= allowed[index];
+    }
+
+    let parts: Vec<String> = results
+        .iter()
+        .zip(headers)
+        .enumerate()
+        .map(|(index, (result, header))| {
+            header + &fit_tokens(&result.content, needs[index], allowed[index])
+        })
+        .collect();
+    parts.join("\n\n")
+}
+
+/// `text`, which is `tokens` tokens, cut to about `allowed` tokens
+fn fit_tokens(text: &str, tokens: usize, allowed: usize) -> String {
+    if tokens <= allowed {
+        return text.to_string();
+    }
+    let note = format!(
+        "[... {} of {} tokens omitted ...]",
+        tokens - allowed,
+        tokens
+    );
+    let Some(keep) = allowed.checked_sub(estimate_tokens(&note) + 1) else {
+        return format!("[{} tokens omitted]", tokens);
+    };
+    // Keep more of the beginning, but also the end, where errors and totals tend to be
+    let chars: Vec<char> = text.chars().collect();
+    let head = keep * 4 * 2 / 3;
+    let tail = keep * 4 - head;
+    let head: String = chars[..head].iter().collect();
+    let tail: String = chars[chars.len() - tail..].iter().collect();
+    format!("{}\n{}\n{}", head, note, tail)
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
@@ -100,6 +188,45 @@ mod tests {
         assert!(processed.len() < large_result.len() + 500); // Accounting for warning message
     }
 
+    #[test]
+    fn test_format_observation() {
+        let result = |id: &str, content: String, success: bool| ToolExecutionResult {
+            tool_call_id: id.to_string(),
+            tool_name: "tool".to_string(),
+            content,
+            success,
+            skipped: false,
+            duration_ms: 0,
+            deduplicated: false,
+            attachments: Vec::new(),
+        };
+        let results = [
+            result("call_1", "a".repeat(4000), true),
+            result("call_2", "small".to_string(), true),
+            result("call_3", format!("{}tail", "e".repeat(996)), false),
+            result("call_4", "b".repeat(4000), true),
+        ];
+
+        let observation = format_observation(&results, 1200);
+        assert!(estimate_tokens(&observation) <= 1200);
+        let parts: Vec<&str> = observation.split("\n\nTool: ").collect();
+        assert_eq!(parts.len(), 4);
+        assert!(parts[0].starts_with("Tool: tool (call_1)\nResult: aaa"));
+        assert!(parts[1].ends_with("Result: small"));
+        // The error fits whole; the two large results share what is left
+        assert!(parts[2].starts_with("tool (call_3)\nError: eee"));
+        assert!(parts[2].ends_with("tail"));
+        assert!(!parts[2].contains("omitted"));
+        assert!(parts[0].contains("tokens omitted ...]"));
+        assert!(parts[3].contains("tokens omitted ...]"));
+        assert!(parts[0].len().abs_diff(parts[3].len()) < 8);
+
+        let everything = format_observation(&results, 10_000);
+        assert!(!everything.contains("omitted"));
+        let nothing = format_observation(&results, 0);
+        assert!(nothing.contains("Result: [1000 tokens omitted]"));
+    }
+
     #[test]
     fn test_handle_large_result_large_without_truncation() {
         let config = ResultHandlerConfig {
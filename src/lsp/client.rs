@@ -0,0 +1,432 @@
+//! Minimal synchronous Language Server Protocol client
+//!
+//! Speaks JSON-RPC over the server's stdin/stdout with `Content-Length` framing. A
+//! reader thread collects responses and `textDocument/publishDiagnostics`
+//! notifications and answers the few requests servers send to their clients, so
+//! callers can block on a response with a timeout.
+
+use anyhow::{anyhow, Result};
+use serde_json::{json, Value};
+use std::collections::HashMap;
+use std::io::{BufRead, BufReader, Write};
+use std::path::{Path, PathBuf};
+use std::process::{Child, ChildStdin, Command, Stdio};
+use std::sync::{Arc, Condvar, Mutex};
+use std::time::{Duration, Instant};
+
+/// Read one framed message, or `None` at end of stream
+pub fn read_message(reader: &mut impl BufRead) -> Result<Option<Value>> {
+    let mut length = None;
+    loop {
+        let mut header = String::new();
+        if reader.read_line(&mut header)? == 0 {
+            return Ok(None);
+        }
+        let header = header.trim_end();
+        if header.is_empty() {
+            break;
+        }
+        if let Some((name, value)) = header.split_once(':') {
+            if name.eq_ignore_ascii_case("content-length") {
+                length = Some(value.trim().parse::<usize>()?);
+            }
+        }
+    }
+    let length = length.ok_or_else(|| anyhow!("LSP message without Content-Length"))?;
+    let mut body = vec![0; length];
+    reader.read_exact(&mut body)?;
+    Ok(Some(serde_json::from_slice(&body)?))
+}
+
+/// Write `message` with its `Content-Length` header
+pub fn write_message(writer: &mut impl Write, message: &Value) -> Result<()> {
+    let body = serde_json::to_string(message)?;
+    write!(writer, "Content-Length: {}\r\n\r\n{}", body.len(), body)?;
+    writer.flush()?;
+    Ok(())
+}
+
+/// `file://` URI of an absolute path
+pub fn path_to_uri(path: &Path) -> String {
+    let path = path.to_string_lossy().replace('\\', "/");
+    let mut uri = String::from("file://");
+    if !path.starts_with('/') {
+        // Windows drive paths become file:///C:/...
+        uri.push('/');
+    }
+    for byte in path.bytes() {
+        match byte {
+            b'A'..=b'Z' | b'a'..=b'z' | b'0'..=b'9' | b'-' | b'.' | b'_' | b'~' | b'/' | b':' => {
+                uri.push(byte as char)
+            }
+            _ => uri.push_str(&format!("%{:02X}", byte)),
+        }
+    }
+    uri
+}
+
+/// Path of a `file://` URI, or `None` for other schemes
+pub fn uri_to_path(uri: &str) -> Option<PathBuf> {
+    let encoded = uri.strip_prefix("file://")?;
+    let bytes = encoded.as_bytes();
+    let mut decoded = Vec::with_capacity(bytes.len());
+    let mut i = 0;
+    while i < bytes.len() {
+        if bytes[i] == b'%' && i + 2 < bytes.len() {
+            let hex = std::str::from_utf8(&bytes[i + 1..i + 3]).ok();
+            if let Some(byte) = hex.and_then(|hex| u8::from_str_radix(hex, 16).ok()) {
+                decoded.push(byte);
+                i += 3;
+                continue;
+            }
+        }
+        decoded.push(bytes[i]);
+        i += 1;
+    }
+    let path = String::from_utf8(decoded).ok()?;
+    // file:///C:/x -> C:/x
+    let path = match path.as_bytes() {
+        [b'/', _, b':', ..] => path[1..].to_string(),
+        _ => path,
+    };
+    Some(PathBuf::from(path))
+}
+
+/// Diagnostics published for one document
+#[derive(Debug, Clone, Default)]
+struct PublishedDiagnostics {
+    /// Incremented on every publish so waiters can tell fresh results from old ones
+    generation: u64,
+    diagnostics: Vec<Value>,
+}
+
+#[derive(Debug, Default)]
+struct Inbox {
+    responses: HashMap<u64, Value>,
+    diagnostics: HashMap<String, PublishedDiagnostics>,
+    closed: bool,
+}
+
+type Shared = Arc<(Mutex<Inbox>, Condvar)>;
+
+/// An open document: its version and the text last sent to the server
+struct OpenDocument {
+    version: i64,
+    text: String,
+}
+
+/// A running language server
+pub struct LspClient {
+    child: Child,
+    stdin: Arc<Mutex<ChildStdin>>,
+    inbox: Shared,
+    next_id: u64,
+    documents: HashMap<String, OpenDocument>,
+    timeout: Duration,
+}
+
+impl LspClient {
+    /// Start `command` in `root` and complete the initialize handshake
+    pub fn start(command: &str, args: &[String], root: &Path, timeout: Duration) -> Result<Self> {
+        let mut child = Command::new(command)
+            .args(args)
+            .current_dir(root)
+            .stdin(Stdio::piped())
+            .stdout(Stdio::piped())
+            .stderr(Stdio::null())
+            .spawn()
+            .map_err(|e| anyhow!("Failed to start language server '{}': {}", command, e))?;
+
+        let stdin = Arc::new(Mutex::new(
+            child
+                .stdin
+                .take()
+                .ok_or_else(|| anyhow!("Language server stdin unavailable"))?,
+        ));
+        let stdout = child
+            .stdout
+            .take()
+            .ok_or_else(|| anyhow!("Language server stdout unavailable"))?;
+        let inbox: Shared = Arc::new((Mutex::new(Inbox::default()), Condvar::new()));
+        spawn_reader(
+            BufReader::new(stdout),
+            Arc::clone(&inbox),
+            Arc::clone(&stdin),
+        );
+
+        let mut client = Self {
+            child,
+            stdin,
+            inbox,
+            next_id: 0,
+            documents: HashMap::new(),
+            timeout,
+        };
+        let root_uri = path_to_uri(root);
+        client.request(
+            "initialize",
+            json!({
+                "processId": std::process::id(),
+                "rootUri": root_uri,
+                "workspaceFolders": [{"uri": root_uri, "name": "workspace"}],
+                "capabilities": {
+                    "textDocument": {
+                        "synchronization": {"didSave": false},
+                        "publishDiagnostics": {"relatedInformation": false},
+                        "hover": {"contentFormat": ["markdown", "plaintext"]},
+                        "definition": {"linkSupport": true}
+                    },
+                    "workspace": {"configuration": true, "workspaceFolders": true}
+                }
+            }),
+        )?;
+        client.notify("initialized", json!({}))?;
+        Ok(client)
+    }
+
+    /// Whether the server process is still running
+    pub fn is_alive(&mut self) -> bool {
+        matches!(self.child.try_wait(), Ok(None))
+    }
+
+    fn send(&self, message: Value) -> Result<()> {
+        let mut stdin = self
+            .stdin
+            .lock()
+            .map_err(|e| anyhow!("Failed to lock language server stdin: {}", e))?;
+        write_message(&mut *stdin, &message)
+    }
+
+    /// Send a notification
+    pub fn notify(&self, method: &str, params: Value) -> Result<()> {
+        self.send(json!({"jsonrpc": "2.0", "method": method, "params": params}))
+    }
+
+    /// Send a request and wait for its result
+    pub fn request(&mut self, method: &str, params: Value) -> Result<Value> {
+        self.next_id += 1;
+        let id = self.next_id;
+        self.send(json!({"jsonrpc": "2.0", "id": id, "method": method, "params": params}))?;
+
+        let (lock, ready) = &*self.inbox;
+        let deadline = Instant::now() + self.timeout;
+        let mut inbox = lock
+            .lock()
+            .map_err(|e| anyhow!("Failed to lock LSP inbox: {}", e))?;
+        loop {
+            if let Some(response) = inbox.responses.remove(&id) {
+                if let Some(error) = response.get("error") {
+                    return Err(anyhow!(
+                        "{} failed: {}",
+                        method,
+                        error["message"].as_str().unwrap_or("unknown error")
+                    ));
+                }
+                return Ok(response.get("result").cloned().unwrap_or(Value::Null));
+            }
+            if inbox.closed {
+                return Err(anyhow!("Language server exited during {}", method));
+            }
+            let now = Instant::now();
+            if now >= deadline {
+                return Err(anyhow!(
+                    "{} timed out after {}s",
+                    method,
+                    self.timeout.as_secs()
+                ));
+            }
+            inbox = ready
+                .wait_timeout(inbox, deadline - now)
+                .map_err(|e| anyhow!("Failed to wait for LSP response: {}", e))?
+                .0;
+        }
+    }
+
+    /// Open `path` in the server, or send its current text if it changed since
+    ///
+    /// Returns the document URI.
+    pub fn sync_document(&mut self, path: &Path, language_id: &str) -> Result<String> {
+        let uri = path_to_uri(path);
+        let text = std::fs::read_to_string(path)
+            .map_err(|e| anyhow!("Failed to read {}: {}", path.display(), e))?;
+        match self.documents.get_mut(&uri) {
+            Some(document) if document.text == text => {}
+            Some(document) => {
+                document.version += 1;
+                document.text = text.clone();
+                let version = document.version;
+                self.notify(
+                    "textDocument/didChange",
+                    json!({
+                        "textDocument": {"uri": uri, "version": version},
+                        "contentChanges": [{"text": text}]
+                    }),
+                )?;
+            }
+            None => {
+                self.notify(
+                    "textDocument/didOpen",
+                    json!({
+                        "textDocument": {
+                            "uri": uri,
+                            "languageId": language_id,
+                            "version": 1,
+                            "text": text
+                        }
+                    }),
+                )?;
+                self.documents
+                    .insert(uri.clone(), OpenDocument { version: 1, text });
+            }
+        }
+        Ok(uri)
+    }
+
+    /// Number of diagnostic publishes seen so far for `uri`
+    pub fn diagnostics_generation(&self, uri: &str) -> u64 {
+        self.inbox
+            .0
+            .lock()
+            .ok()
+            .and_then(|inbox| inbox.diagnostics.get(uri).map(|d| d.generation))
+            .unwrap_or(0)
+    }
+
+    /// Diagnostics for `uri` published after `generation`, waiting up to `wait`
+    ///
+    /// Falls back to the last published diagnostics (or none) when the server stays
+    /// quiet, with the flag telling whether the result is fresh.
+    pub fn wait_for_diagnostics(
+        &self,
+        uri: &str,
+        generation: u64,
+        wait: Duration,
+    ) -> Result<(Vec<Value>, bool)> {
+        let (lock, ready) = &*self.inbox;
+        let deadline = Instant::now() + wait;
+        let mut inbox = lock
+            .lock()
+            .map_err(|e| anyhow!("Failed to lock LSP inbox: {}", e))?;
+        loop {
+            let published = inbox.diagnostics.get(uri);
+            if let Some(published) = published.filter(|p| p.generation > generation) {
+                return Ok((published.diagnostics.clone(), true));
+            }
+            let now = Instant::now();
+            if inbox.closed || now >= deadline {
+                let last = published.map(|p| p.diagnostics.clone()).unwrap_or_default();
+                return Ok((last, false));
+            }
+            inbox = ready
+                .wait_timeout(inbox, deadline - now)
+                .map_err(|e| anyhow!("Failed to wait for diagnostics: {}", e))?
+                .0;
+        }
+    }
+}
+
+impl Drop for LspClient {
+    fn drop(&mut self) {
+        // Best effort: ask politely, then make sure the process is gone
+        let _ = self.send(json!({"jsonrpc": "2.0", "id": 0, "method": "shutdown"}));
+        let _ = self.notify("exit", Value::Null);
+        let _ = self.child.kill();
+        let _ = self.child.wait();
+    }
+}
+
+/// Collect messages from the server until its stdout closes
+fn spawn_reader(
+    mut reader: BufReader<std::process::ChildStdout>,
+    inbox: Shared,
+    stdin: Arc<Mutex<ChildStdin>>,
+) {
+    std::thread::spawn(move || {
+        let (lock, ready) = &*inbox;
+        while let Ok(Some(message)) = read_message(&mut reader) {
+            let method = message.get("method").and_then(|m| m.as_str());
+            let id = message.get("id").cloned();
+            match (method, id) {
+                // A request from the server; reply so it does not stall
+                (Some(method), Some(id)) => {
+                    let result = match method {
+                        "workspace/configuration" => {
+                            let items = message["params"]["items"]
+                                .as_array()
+                                .map(|items| items.len())
+                                .unwrap_or(0);
+                            Value::Array(vec![Value::Null; items])
+                        }
+                        _ => Value::Null,
+                    };
+                    if let Ok(mut stdin) = stdin.lock() {
+                        let _ = write_message(
+                            &mut *stdin,
+                            &json!({"jsonrpc": "2.0", "id": id, "result": result}),
+                        );
+                    }
+                }
+                (Some("textDocument/publishDiagnostics"), None) => {
+                    let params = &message["params"];
+                    if let (Some(uri), Ok(mut inbox)) = (params["uri"].as_str(), lock.lock()) {
+                        let entry = inbox.diagnostics.entry(uri.to_string()).or_default();
+                        entry.generation += 1;
+                        entry.diagnostics = params["diagnostics"]
+                            .as_array()
+                            .cloned()
+                            .unwrap_or_default();
+                        ready.notify_all();
+                    }
+                }
+                (None, Some(id)) => {
+                    if let (Some(id), Ok(mut inbox)) = (id.as_u64(), lock.lock()) {
+                        inbox.responses.insert(id, message);
+                        ready.notify_all();
+                    }
+                }
+                _ => {}
+            }
+        }
+        if let Ok(mut inbox) = lock.lock() {
+            inbox.closed = true;
+            ready.notify_all();
+        }
+    });
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::io::Cursor;
+
+    #[test]
+    fn test_message_framing_round_trip() {
+        let mut buffer = Vec::new();
+        write_message(&mut buffer, &json!({"id": 1, "result": "é"})).unwrap();
+        write_message(&mut buffer, &json!({"method": "exit"})).unwrap();
+
+        let mut reader = Cursor::new(buffer);
+        assert_eq!(
+            read_message(&mut reader).unwrap(),
+            Some(json!({"id": 1, "result": "é"}))
+        );
+        assert_eq!(
+            read_message(&mut reader).unwrap(),
+            Some(json!({"method": "exit"}))
+        );
+        assert_eq!(read_message(&mut reader).unwrap(), None);
+    }
+
+    #[test]
+    fn test_uri_round_trip() {
+        let path = Path::new("/work/my crate/src/lib.rs");
+        let uri = path_to_uri(path);
+        assert_eq!(uri, "file:///work/my%20crate/src/lib.rs");
+        assert_eq!(uri_to_path(&uri).unwrap(), path);
+        assert_eq!(
+            uri_to_path("file:///C:/work/a.rs").unwrap(),
+            PathBuf::from("C:/work/a.rs")
+        );
+        assert!(uri_to_path("untitled:1").is_none());
+    }
+}
@@ -0,0 +1,793 @@
+//! Language server integration
+//!
+//! `lsp_diagnostics`, `lsp_hover` and `lsp_definition` ask a language server
+//! (rust-analyzer, pyright or typescript-language-server, chosen by file extension)
+//! for compiler-grade information without a full build. Servers are optional: one is
+//! started for the session's working directory the first time a file of its language
+//! is queried, shared by the three tools through an [`LspPool`], and shut down when the
+//! registry is dropped. Lines and columns are 1-based; columns count characters.
+
+pub mod client;
+
+use crate::core::{DangerLevel, Tool, ToolArgs, ToolCategory, ToolError, ToolResult};
+use crate::state::ToolState;
+use anyhow::Result;
+use client::{path_to_uri, uri_to_path, LspClient};
+use serde_json::{json, Value};
+use std::collections::HashMap;
+use std::path::{Path, PathBuf};
+use std::sync::{Arc, Mutex};
+use std::time::Duration;
+
+/// Seconds to wait for a server response
+pub const REQUEST_TIMEOUT_SECS: u64 = 60;
+
+/// Default seconds `lsp_diagnostics` waits for fresh diagnostics
+pub const DIAGNOSTICS_WAIT_SECS: u64 = 10;
+
+/// How to start the language server for a set of file extensions
+#[derive(Debug, Clone)]
+pub struct LspServerConfig {
+    pub command: String,
+    pub args: Vec<String>,
+    /// File extensions handled by the server, mapped to their LSP language ids
+    pub languages: Vec<(String, String)>,
+}
+
+impl LspServerConfig {
+    fn new(command: &str, args: &[&str], languages: &[(&str, &str)]) -> Self {
+        Self {
+            command: command.to_string(),
+            args: args.iter().map(|a| a.to_string()).collect(),
+            languages: languages
+                .iter()
+                .map(|(ext, id)| (ext.to_string(), id.to_string()))
+                .collect(),
+        }
+    }
+
+    /// rust-analyzer, pyright and typescript-language-server
+    pub fn defaults() -> Vec<Self> {
+        vec![
+            Self::new("rust-analyzer", &[], &[("rs", "rust")]),
+            Self::new(
+                "pyright-langserver",
+                &["--stdio"],
+                &[("py", "python"), ("pyi", "python")],
+            ),
+            Self::new(
+                "typescript-language-server",
+                &["--stdio"],
+                &[
+                    ("ts", "typescript"),
+                    ("tsx", "typescriptreact"),
+                    ("js", "javascript"),
+                    ("jsx", "javascriptreact"),
+                    ("mjs", "javascript"),
+                    ("cjs", "javascript"),
+                ],
+            ),
+        ]
+    }
+
+    /// LSP language id of `path` if this server handles it
+    fn language_id(&self, path: &Path) -> Option<&str> {
+        let ext = path.extension()?.to_str()?;
+        self.languages
+            .iter()
+            .find(|(e, _)| e == ext)
+            .map(|(_, id)| id.as_str())
+    }
+}
+
+/// Running language servers shared by the LSP tools, keyed by workspace root and command
+#[derive(Clone)]
+pub struct LspPool {
+    servers: Arc<Vec<LspServerConfig>>,
+    clients: Arc<Mutex<HashMap<(PathBuf, String), LspClient>>>,
+    timeout: Duration,
+}
+
+impl LspPool {
+    /// A pool using the default servers
+    pub fn new() -> Self {
+        Self::with_servers(LspServerConfig::defaults())
+    }
+
+    /// A pool using `servers`, earlier entries winning for shared extensions
+    pub fn with_servers(servers: Vec<LspServerConfig>) -> Self {
+        Self {
+            servers: Arc::new(servers),
+            clients: Arc::new(Mutex::new(HashMap::new())),
+            timeout: Duration::from_secs(REQUEST_TIMEOUT_SECS),
+        }
+    }
+
+    /// Run `f` with the server for `path` in the workspace `root`, starting it if needed
+    fn with_client<R>(
+        &self,
+        root: &Path,
+        path: &Path,
+        f: impl FnOnce(&mut LspClient, &str) -> Result<R>,
+    ) -> Result<R, String> {
+        let (config, language_id) = self
+            .servers
+            .iter()
+            .find_map(|s| s.language_id(path).map(|id| (s, id.to_string())))
+            .ok_or_else(|| {
+                format!(
+                    "No language server configured for {} (supported extensions: {})",
+                    path.display(),
+                    self.servers
+                        .iter()
+                        .flat_map(|s| s.languages.iter().map(|(ext, _)| format!(".{}", ext)))
+                        .collect::<Vec<_>>()
+                        .join(", ")
+                )
+            })?;
+
+        let mut clients = self
+            .clients
+            .lock()
+            .map_err(|e| format!("Failed to lock language servers: {}", e))?;
+        let key = (root.to_path_buf(), config.command.clone());
+        if !clients.get_mut(&key).is_some_and(|c| c.is_alive()) {
+            let client = LspClient::start(&config.command, &config.args, root, self.timeout)
+                .map_err(|e| {
+                    format!(
+                        "{}. Install '{}' and make sure it is on PATH to use the LSP tools.",
+                        e, config.command
+                    )
+                })?;
+            clients.insert(key.clone(), client);
+        }
+        let client = clients
+            .get_mut(&key)
+            .ok_or_else(|| "Language server unavailable".to_string())?;
+        f(client, &language_id).map_err(|e| e.to_string())
+    }
+}
+
+impl Default for LspPool {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+/// Parse json, named or positional (`names` in order) parameters
+fn parse_params(
+    args: &ToolArgs,
+    names: &[&str],
+) -> Result<serde_json::Map<String, Value>, ToolError> {
+    let value = if let Some(json_str) = args.get_named_arg("json") {
+        serde_json::from_str(json_str).map_err(ToolError::Json)?
+    } else if !args.named_args.is_empty() {
+        serde_json::to_value(&args.named_args).map_err(ToolError::Json)?
+    } else {
+        Value::Object(
+            names
+                .iter()
+                .zip(args.args.iter())
+                .map(|(name, arg)| (name.to_string(), Value::String(arg.clone())))
+                .collect(),
+        )
+    };
+    match value {
+        Value::Object(obj) if obj.contains_key("path") => Ok(obj),
+        _ => Err(ToolError::InvalidArgs {
+            message: "Missing required parameter: path".to_string(),
+        }),
+    }
+}
+
+/// A positive integer parameter given as a number or a string
+fn number_param(obj: &serde_json::Map<String, Value>, key: &str) -> Option<u64> {
+    match obj.get(key)? {
+        Value::Number(n) => n.as_u64(),
+        Value::String(s) => s.trim().parse().ok(),
+        _ => None,
+    }
+}
+
+/// The file path and workspace root of a call
+fn resolve_path(
+    obj: &serde_json::Map<String, Value>,
+    state: &Arc<Mutex<ToolState>>,
+) -> Result<(PathBuf, PathBuf)> {
+    let root = state
+        .lock()
+        .map_err(|e| anyhow::anyhow!("Failed to lock state: {}", e))?
+        .working_directory
+        .clone();
+    let path = PathBuf::from(obj.get("path").and_then(|p| p.as_str()).unwrap_or(""));
+    let path = if path.is_absolute() {
+        path
+    } else {
+        root.join(path)
+    };
+    Ok((path, root))
+}
+
+/// The `line`/`column` parameters, or an error result naming the missing one
+fn position_params(obj: &serde_json::Map<String, Value>) -> Result<(u64, u64), ToolResult> {
+    let line = number_param(obj, "line").filter(|l| *l > 0);
+    let column = number_param(obj, "column").filter(|c| *c > 0);
+    match (line, column) {
+        (Some(line), Some(column)) => Ok((line, column)),
+        _ => Err(ToolResult::error(
+            "line and column must be positive 1-based numbers",
+        )),
+    }
+}
+
+/// LSP position (0-based line, UTF-16 character) of a 1-based line and character column
+pub fn to_lsp_position(text: &str, line: u64, column: u64) -> Value {
+    let character: usize = text
+        .lines()
+        .nth(line.saturating_sub(1) as usize)
+        .map(|l| {
+            l.chars()
+                .take(column.saturating_sub(1) as usize)
+                .map(char::len_utf16)
+                .sum()
+        })
+        .unwrap_or(column.saturating_sub(1) as usize);
+    json!({"line": line.saturating_sub(1), "character": character})
+}
+
+/// 1-based line and character column of an LSP position in `text`
+pub fn from_lsp_position(text: &str, position: &Value) -> (u64, u64) {
+    let line = position["line"].as_u64().unwrap_or(0);
+    let character = position["character"].as_u64().unwrap_or(0) as usize;
+    let column = match text.lines().nth(line as usize) {
+        Some(l) => {
+            let mut units = 0;
+            let mut chars = 0;
+            for c in l.chars() {
+                if units >= character {
+                    break;
+                }
+                units += c.len_utf16();
+                chars += 1;
+            }
+            chars
+        }
+        None => character,
+    };
+    (line + 1, column as u64 + 1)
+}
+
+/// Plain text of hover `contents` (MarkupContent, MarkedString or an array of them)
+pub fn hover_text(contents: &Value) -> String {
+    match contents {
+        Value::String(s) => s.clone(),
+        Value::Array(items) => items
+            .iter()
+            .map(hover_text)
+            .filter(|s| !s.is_empty())
+            .collect::<Vec<_>>()
+            .join("\n\n"),
+        Value::Object(obj) => match (obj.get("language"), obj.get("value")) {
+            (Some(language), Some(value)) => format!(
+                "```{}\n{}\n```",
+                language.as_str().unwrap_or(""),
+                value.as_str().unwrap_or("")
+            ),
+            (None, Some(value)) => value.as_str().unwrap_or("").to_string(),
+            _ => String::new(),
+        },
+        _ => String::new(),
+    }
+}
+
+/// `(uri, range)` pairs of a definition result (Location, Location[] or LocationLink[])
+pub fn definition_locations(result: &Value) -> Vec<(String, Value)> {
+    let items = match result {
+        Value::Array(items) => items.clone(),
+        Value::Null => Vec::new(),
+        single => vec![single.clone()],
+    };
+    items
+        .iter()
+        .filter_map(|item| {
+            let uri = item
+                .get("targetUri")
+                .or_else(|| item.get("uri"))?
+                .as_str()?
+                .to_string();
+            let range = item
+                .get("targetSelectionRange")
+                .or_else(|| item.get("range"))?
+                .clone();
+            Some((uri, range))
+        })
+        .collect()
+}
+
+/// Severity name of an LSP diagnostic severity number
+fn severity_name(severity: &Value) -> &'static str {
+    match severity.as_u64() {
+        Some(1) => "error",
+        Some(2) => "warning",
+        Some(3) => "info",
+        Some(4) => "hint",
+        _ => "error",
+    }
+}
+
+/// Tool reporting a language server's diagnostics for a file
+pub struct LspDiagnosticsTool {
+    name: String,
+    pool: LspPool,
+}
+
+impl LspDiagnosticsTool {
+    pub fn new(pool: LspPool) -> Self {
+        Self {
+            name: "lsp_diagnostics".to_string(),
+            pool,
+        }
+    }
+}
+
+impl Default for LspDiagnosticsTool {
+    fn default() -> Self {
+        Self::new(LspPool::new())
+    }
+}
+
+impl Tool for LspDiagnosticsTool {
+    fn name(&self) -> &str {
+        &self.name
+    }
+
+    fn description(&self) -> &str {
+        "Get errors and warnings for a file from its language server (rust-analyzer, pyright, typescript-language-server) without running a build"
+    }
+
+    fn signature(&self) -> &str {
+        "lsp_diagnostics(path: str, wait_secs?: int)"
+    }
+
+    fn category(&self) -> ToolCategory {
+        ToolCategory::Utility
+    }
+
+    fn danger_level(&self) -> DangerLevel {
+        // Language servers run build scripts and procedural macros
+        DangerLevel::ExecutesCode
+    }
+
+    fn validate_args(&self, args: &ToolArgs) -> Result<(), ToolError> {
+        parse_params(args, &["path", "wait_secs"]).map(|_| ())
+    }
+
+    fn execute(&mut self, args: &ToolArgs, state: &Arc<Mutex<ToolState>>) -> Result<ToolResult> {
+        let obj = parse_params(args, &["path", "wait_secs"])?;
+        let (path, root) = resolve_path(&obj, state)?;
+        if !path.is_file() {
+            return Ok(crate::utils::suggest::path_not_found(
+                format!("File not found: {}", path.display()),
+                &path,
+            ));
+        }
+        let wait =
+            Duration::from_secs(number_param(&obj, "wait_secs").unwrap_or(DIAGNOSTICS_WAIT_SECS));
+
+        let outcome = self.pool.with_client(&root, &path, |client, language_id| {
+            let generation = client.diagnostics_generation(&path_to_uri(&path));
+            let uri = client.sync_document(&path, language_id)?;
+            client.wait_for_diagnostics(&uri, generation, wait)
+        });
+        let (diagnostics, fresh) = match outcome {
+            Ok(outcome) => outcome,
+            Err(message) => return Ok(ToolResult::error(message)),
+        };
+
+        let text = std::fs::read_to_string(&path).unwrap_or_default();
+        let diagnostics: Vec<Value> = diagnostics
+            .iter()
+            .map(|d| {
+                let (line, column) = from_lsp_position(&text, &d["range"]["start"]);
+                let (end_line, end_column) = from_lsp_position(&text, &d["range"]["end"]);
+                json!({
+                    "line": line,
+                    "column": column,
+                    "end_line": end_line,
+                    "end_column": end_column,
+                    "severity": severity_name(&d["severity"]),
+                    "message": d["message"].as_str().unwrap_or(""),
+                    "source": d.get("source").cloned().unwrap_or(Value::Null),
+                    "code": d.get("code").cloned().unwrap_or(Value::Null),
+                })
+            })
+            .collect();
+
+        let mut message = format!("{} diagnostic(s) in {}", diagnostics.len(), path.display());
+        if !fresh {
+            message.push_str(&format!(
+                " (the server published nothing new within {}s; results may be stale or still indexing)",
+                wait.as_secs()
+            ));
+        }
+        for d in &diagnostics {
+            message.push_str(&format!(
+                "\n  {}:{} {}: {}",
+                d["line"],
+                d["column"],
+                d["severity"].as_str().unwrap_or(""),
+                d["message"].as_str().unwrap_or("")
+            ));
+        }
+
+        Ok(ToolResult::success_with_data(
+            message,
+            json!({
+                "path": path.to_string_lossy(),
+                "diagnostics": diagnostics,
+                "fresh": fresh
+            }),
+        ))
+    }
+
+    fn get_parameters_schema(&self) -> Value {
+        json!({
+            "type": "object",
+            "properties": {
+                "path": {
+                    "type": "string",
+                    "description": "File to check"
+                },
+                "wait_secs": {
+                    "type": "integer",
+                    "description": "Seconds to wait for the server to publish diagnostics",
+                    "default": DIAGNOSTICS_WAIT_SECS
+                }
+            },
+            "required": ["path"]
+        })
+    }
+}
+
+/// Tool returning a language server's hover information at a position
+pub struct LspHoverTool {
+    name: String,
+    pool: LspPool,
+}
+
+impl LspHoverTool {
+    pub fn new(pool: LspPool) -> Self {
+        Self {
+            name: "lsp_hover".to_string(),
+            pool,
+        }
+    }
+}
+
+impl Default for LspHoverTool {
+    fn default() -> Self {
+        Self::new(LspPool::new())
+    }
+}
+
+impl Tool for LspHoverTool {
+    fn name(&self) -> &str {
+        &self.name
+    }
+
+    fn description(&self) -> &str {
+        "Get the type, signature and documentation of the symbol at a position from the language server"
+    }
+
+    fn signature(&self) -> &str {
+        "lsp_hover(path: str, line: int, column: int)"
+    }
+
+    fn category(&self) -> ToolCategory {
+        ToolCategory::Navigation
+    }
+
+    fn danger_level(&self) -> DangerLevel {
+        // Language servers run build scripts and procedural macros
+        DangerLevel::ExecutesCode
+    }
+
+    fn validate_args(&self, args: &ToolArgs) -> Result<(), ToolError> {
+        parse_params(args, &["path", "line", "column"]).map(|_| ())
+    }
+
+    fn execute(&mut self, args: &ToolArgs, state: &Arc<Mutex<ToolState>>) -> Result<ToolResult> {
+        let obj = parse_params(args, &["path", "line", "column"])?;
+        let (path, root) = resolve_path(&obj, state)?;
+        let (line, column) = match position_params(&obj) {
+            Ok(position) => position,
+            Err(error) => return Ok(error),
+        };
+        let text = match std::fs::read_to_string(&path) {
+            Ok(text) => text,
+            Err(_) => {
+                return Ok(crate::utils::suggest::path_not_found(
+                    format!("File not found: {}", path.display()),
+                    &path,
+                ))
+            }
+        };
+
+        let outcome = self.pool.with_client(&root, &path, |client, language_id| {
+            let uri = client.sync_document(&path, language_id)?;
+            client.request(
+                "textDocument/hover",
+                json!({
+                    "textDocument": {"uri": uri},
+                    "position": to_lsp_position(&text, line, column)
+                }),
+            )
+        });
+        let hover = match outcome {
+            Ok(hover) => hover,
+            Err(message) => return Ok(ToolResult::error(message)),
+        };
+
+        let contents = hover_text(&hover["contents"]);
+        if contents.trim().is_empty() {
+            return Ok(ToolResult::success_with_data(
+                format!(
+                    "No hover information at {}:{}:{}",
+                    path.display(),
+                    line,
+                    column
+                ),
+                json!({"path": path.to_string_lossy(), "line": line, "column": column, "contents": null}),
+            ));
+        }
+        Ok(ToolResult::success_with_data(
+            contents.clone(),
+            json!({
+                "path": path.to_string_lossy(),
+                "line": line,
+                "column": column,
+                "contents": contents
+            }),
+        ))
+    }
+
+    fn get_parameters_schema(&self) -> Value {
+        json!({
+            "type": "object",
+            "properties": {
+                "path": {
+                    "type": "string",
+                    "description": "File containing the symbol"
+                },
+                "line": {
+                    "type": "integer",
+                    "description": "1-based line number"
+                },
+                "column": {
+                    "type": "integer",
+                    "description": "1-based column (in characters)"
+                }
+            },
+            "required": ["path", "line", "column"]
+        })
+    }
+}
+
+/// Tool resolving the definition of the symbol at a position through the language server
+pub struct LspDefinitionTool {
+    name: String,
+    pool: LspPool,
+}
+
+impl LspDefinitionTool {
+    pub fn new(pool: LspPool) -> Self {
+        Self {
+            name: "lsp_definition".to_string(),
+            pool,
+        }
+    }
+}
+
+impl Default for LspDefinitionTool {
+    fn default() -> Self {
+        Self::new(LspPool::new())
+    }
+}
+
+impl Tool for LspDefinitionTool {
+    fn name(&self) -> &str {
+        &self.name
+    }
+
+    fn description(&self) -> &str {
+        "Find where the symbol at a position is defined, using the language server"
+    }
+
+    fn signature(&self) -> &str {
+        "lsp_definition(path: str, line: int, column: int)"
+    }
+
+    fn category(&self) -> ToolCategory {
+        ToolCategory::Navigation
+    }
+
+    fn danger_level(&self) -> DangerLevel {
+        // Language servers run build scripts and procedural macros
+        DangerLevel::ExecutesCode
+    }
+
+    fn validate_args(&self, args: &ToolArgs) -> Result<(), ToolError> {
+        parse_params(args, &["path", "line", "column"]).map(|_| ())
+    }
+
+    fn execute(&mut self, args: &ToolArgs, state: &Arc<Mutex<ToolState>>) -> Result<ToolResult> {
+        let obj = parse_params(args, &["path", "line", "column"])?;
+        let (path, root) = resolve_path(&obj, state)?;
+        let (line, column) = match position_params(&obj) {
+            Ok(position) => position,
+            Err(error) => return Ok(error),
+        };
+        let text = match std::fs::read_to_string(&path) {
+            Ok(text) => text,
+            Err(_) => {
+                return Ok(crate::utils::suggest::path_not_found(
+                    format!("File not found: {}", path.display()),
+                    &path,
+                ))
+            }
+        };
+
+        let outcome = self.pool.with_client(&root, &path, |client, language_id| {
+            let uri = client.sync_document(&path, language_id)?;
+            client.request(
+                "textDocument/definition",
+                json!({
+                    "textDocument": {"uri": uri},
+                    "position": to_lsp_position(&text, line, column)
+                }),
+            )
+        });
+        let result = match outcome {
+            Ok(result) => result,
+            Err(message) => return Ok(ToolResult::error(message)),
+        };
+
+        let mut lines = Vec::new();
+        let definitions: Vec<Value> = definition_locations(&result)
+            .into_iter()
+            .map(|(uri, range)| {
+                let target = uri_to_path(&uri);
+                let target_text = target
+                    .as_ref()
+                    .and_then(|t| std::fs::read_to_string(t).ok())
+                    .unwrap_or_default();
+                let (def_line, def_column) = from_lsp_position(&target_text, &range["start"]);
+                let preview = target_text
+                    .lines()
+                    .nth(def_line as usize - 1)
+                    .unwrap_or("")
+                    .trim()
+                    .to_string();
+                let display = target
+                    .as_ref()
+                    .map(|t| t.display().to_string())
+                    .unwrap_or_else(|| uri.clone());
+                lines.push(format!(
+                    "  {}:{}:{}: {}",
+                    display, def_line, def_column, preview
+                ));
+                json!({
+                    "path": display,
+                    "line": def_line,
+                    "column": def_column,
+                    "preview": preview
+                })
+            })
+            .collect();
+
+        if definitions.is_empty() {
+            return Ok(ToolResult::success_with_data(
+                format!(
+                    "No definition found for the symbol at {}:{}:{}",
+                    path.display(),
+                    line,
+                    column
+                ),
+                json!({"definitions": []}),
+            ));
+        }
+        Ok(ToolResult::success_with_data(
+            format!(
+                "{} definition(s) for the symbol at {}:{}:{}:\n{}",
+                definitions.len(),
+                path.display(),
+                line,
+                column,
+                lines.join("\n")
+            ),
+            json!({"definitions": definitions}),
+        ))
+    }
+
+    fn get_parameters_schema(&self) -> Value {
+        json!({
+            "type": "object",
+            "properties": {
+                "path": {
+                    "type": "string",
+                    "description": "File containing the symbol"
+                },
+                "line": {
+                    "type": "integer",
+                    "description": "1-based line number"
+                },
+                "column": {
+                    "type": "integer",
+                    "description": "1-based column (in characters)"
+                }
+            },
+            "required": ["path", "line", "column"]
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use tempfile::TempDir;
+
+    #[test]
+    fn test_position_conversion_counts_utf16_units() {
+        let text = "fn main() {\n    let s = \"é😀\"; s\n}\n";
+        // The `s` after the string literal is the 19th character of line 2
+        let position = to_lsp_position(text, 2, 19);
+        assert_eq!(position, json!({"line": 1, "character": 19}));
+        assert_eq!(from_lsp_position(text, &position), (2, 19));
+    }
+
+    #[test]
+    fn test_response_parsing() {
+        let hover = json!([{"language": "rust", "value": "fn main()"}, "Entry point"]);
+        assert_eq!(hover_text(&hover), "```rust\nfn main()\n```\n\nEntry point");
+        assert_eq!(
+            hover_text(&json!({"kind": "markdown", "value": "**x**"})),
+            "**x**"
+        );
+
+        let range =
+            json!({"start": {"line": 0, "character": 3}, "end": {"line": 0, "character": 7}});
+        let links = json!([{"targetUri": "file:///a.rs", "targetRange": range, "targetSelectionRange": range}]);
+        assert_eq!(
+            definition_locations(&links),
+            vec![("file:///a.rs".to_string(), range.clone())]
+        );
+        let location = json!({"uri": "file:///b.rs", "range": range});
+        assert_eq!(definition_locations(&location).len(), 1);
+        assert!(definition_locations(&Value::Null).is_empty());
+    }
+
+    #[test]
+    fn test_missing_server_is_reported() {
+        let temp_dir = TempDir::new().unwrap();
+        let file = temp_dir.path().join("main.rs");
+        std::fs::write(&file, "fn main() {}\n").unwrap();
+        let pool = LspPool::with_servers(vec![LspServerConfig::new(
+            "cats-no-such-language-server",
+            &[],
+            &[("rs", "rust")],
+        )]);
+        let state = Arc::new(Mutex::new(ToolState::new()));
+
+        let mut tool = LspDiagnosticsTool::new(pool);
+        let result = tool
+            .execute(&ToolArgs::from_args(&[file.to_str().unwrap()]), &state)
+            .unwrap();
+        assert!(!result.success);
+        assert!(result.message.contains("cats-no-such-language-server"));
+
+        let result = tool
+            .execute(&ToolArgs::from_args(&["notes.txt"]), &state)
+            .unwrap();
+        assert!(!result.success);
+    }
+}
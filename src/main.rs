@@ -1,53 +1,347 @@
 //! Main binary for CATS CLI
 
-use clap::{Arg, Command};
-use cats::{create_tool_registry, ToolArgs};
-use std::collections::HashMap;
+use cats::config::{Config, CONFIG_FILE};
+use cats::core::{ToolCategory, ToolDescriptor, ToolError};
+use cats::messages::{MessageStyle, Verbosity};
+use cats::profiling::{init_chrome_trace, FlushGuard};
+use cats::state::journal::{recover, RecoverOptions, JOURNAL_DIR};
+use cats::{execute_json_line, RegistryBuilder, ToolArgs, ToolRegistry};
+use clap::{Arg, ArgAction, Command};
+use clap_complete::Shell;
+use std::io::{BufRead, Write};
+use std::path::PathBuf;
 
-#[tokio::main]
-async fn main() -> anyhow::Result<()> {
-    // Create registry to get available tools dynamically
-    let registry = create_tool_registry();
-    
-    // Collect tool information (name, description) with static lifetime
-    let tool_info: Vec<(&'static str, &'static str)> = registry
-        .list_tools()
-        .into_iter()
-        .filter_map(|name| {
-            registry.get_tool(&name).map(|tool| {
-                let name_static: &'static str = Box::leak(name.into_boxed_str());
-                let desc_static: &'static str = Box::leak(tool.description().to_string().into_boxed_str());
-                (name_static, desc_static)
-            })
-        })
+/// Descriptors of the registered tools, grouped by category and sorted by name
+fn tool_descriptors(registry: &ToolRegistry) -> Vec<ToolDescriptor> {
+    let catalog = registry.catalog();
+    let mut descriptors: Vec<ToolDescriptor> = catalog
+        .names()
+        .iter()
+        .filter_map(|name| catalog.get(name))
         .collect();
-    
+    descriptors.sort_by_key(|d| d.category);
+    descriptors
+}
+
+/// Build the clap command with one subcommand per registered tool
+fn build_cli(registry: &ToolRegistry) -> Command {
     let mut app = Command::new("cats")
         .version("0.1.1")
         .about("Coding Agent ToolS - A comprehensive toolkit for building AI-powered coding agents")
         .subcommand_required(true)
-        .arg_required_else_help(true);
+        .arg_required_else_help(true)
+        .arg(
+            Arg::new("json")
+                .long("json")
+                .global(true)
+                .action(ArgAction::SetTrue)
+                .help("Print results as JSON on stdout and errors as JSON objects on stderr"),
+        )
+        .arg(
+            Arg::new("terse")
+                .long("terse")
+                .global(true)
+                .action(ArgAction::SetTrue)
+                .help("Render tool messages without decorative emoji and separator rules"),
+        )
+        .arg(
+            Arg::new("compact")
+                .long("compact")
+                .global(true)
+                .action(ArgAction::SetTrue)
+                .help("Shorten successful tool messages to counts and key line numbers"),
+        )
+        .arg(
+            Arg::new("trace-output")
+                .long("trace-output")
+                .global(true)
+                .value_name("FILE")
+                .value_parser(clap::value_parser!(PathBuf))
+                .help("Write tool execution spans to FILE in Chrome trace format (chrome://tracing, Perfetto)"),
+        )
+        .subcommand(
+            Command::new(COMPLETIONS_COMMAND)
+                .about("Generate shell completions for the cats CLI")
+                .arg(
+                    Arg::new("shell")
+                        .help("Target shell")
+                        .required(true)
+                        .value_parser(clap::value_parser!(Shell)),
+                ),
+        );
+
+    app = app.subcommand(
+        Command::new(EXEC_COMMAND)
+            .about("Execute JSONL tool requests ({\"tool\": ..., \"args\": {...}}) with a persistent session")
+            .arg(
+                Arg::new("stdin")
+                    .long("stdin")
+                    .required(true)
+                    .action(ArgAction::SetTrue)
+                    .help("Read one JSON request per line from stdin and write one JSON response per line to stdout"),
+            ),
+    );
 
-    // Dynamically add subcommands from collected tool info
-    for (tool_name, description) in &tool_info {
+    app = app.subcommand(
+        Command::new(LIST_TOOLS_COMMAND)
+            .about("List the available tools by category with their signatures and descriptions")
+            .arg(
+                Arg::new("category")
+                    .long("category")
+                    .value_name("CATEGORY")
+                    .value_parser(clap::value_parser!(ToolCategoryArg))
+                    .help("Only list tools in this category (navigation, editing, search, execution, vcs, utility)"),
+            ),
+    );
+
+    app = app.subcommand(
+        Command::new(RECOVER_COMMAND)
+            .about("Inspect the operation journal after a crash and optionally roll back incomplete batches")
+            .arg(
+                Arg::new("dir")
+                    .long("dir")
+                    .value_name("DIR")
+                    .value_parser(clap::value_parser!(PathBuf))
+                    .help("Journal directory (defaults to .cats/journal)"),
+            )
+            .arg(
+                Arg::new("rollback")
+                    .long("rollback")
+                    .action(ArgAction::SetTrue)
+                    .help("Roll back the operations of incomplete batches"),
+            )
+            .arg(
+                Arg::new("force")
+                    .long("force")
+                    .action(ArgAction::SetTrue)
+                    .help("Roll back files even if they changed after the journaled operation"),
+            )
+            .arg(
+                Arg::new("prune")
+                    .long("prune")
+                    .action(ArgAction::SetTrue)
+                    .help("Delete journals whose batches are all complete or rolled back"),
+            ),
+    );
+
+    // One subcommand per tool, in category order so help lists related tools together;
+    // clap owns the name and description strings
+    for descriptor in tool_descriptors(registry) {
         app = app.subcommand(
-            Command::new(*tool_name)
-                .about(*description)
+            Command::new(descriptor.name)
+                .about(format!("[{}] {}", descriptor.category, descriptor.description))
+                .arg(
+                    Arg::new("arg")
+                        .long("arg")
+                        .action(ArgAction::Append)
+                        .value_name("KEY=VALUE")
+                        .help("Named argument (repeatable); `--arg json=<object>` passes raw JSON"),
+                )
                 .arg(
                     Arg::new("args")
-                        .help("Tool arguments")
+                        .help("Tool arguments; `key=value` tokens naming a tool parameter are passed as named arguments")
                         .num_args(0..)
                         .value_name("ARGS"),
                 ),
         );
     }
 
-    let matches = app.get_matches();
+    app
+}
 
-    // Create registry for execution
-    let mut registry = create_tool_registry();
+/// Human-readable tool list: a heading per category, then each tool name and danger
+/// level followed by its indented signature and description
+fn render_tool_list(descriptors: &[ToolDescriptor]) -> String {
+    let mut out = String::new();
+    let mut category = None;
+    for descriptor in descriptors {
+        if category != Some(descriptor.category) {
+            if category.is_some() {
+                out.push('\n');
+            }
+            category = Some(descriptor.category);
+            out.push_str(&format!("{}:\n", descriptor.category));
+        }
+        out.push_str(&format!(
+            "  {} ({})\n      {}\n      {}\n",
+            descriptor.name, descriptor.danger_level, descriptor.signature, descriptor.description
+        ));
+    }
+    out
+}
+
+/// `--category` value, parsed with the library's category names
+#[derive(Debug, Clone, Copy)]
+struct ToolCategoryArg(ToolCategory);
+
+impl std::str::FromStr for ToolCategoryArg {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        s.parse()
+            .map(ToolCategoryArg)
+            .map_err(|e: ToolError| e.to_string())
+    }
+}
+
+/// Parameter names declared in a tool's JSON schema
+fn parameter_names(schema: &serde_json::Value) -> Vec<String> {
+    schema
+        .get("properties")
+        .and_then(|p| p.as_object())
+        .map(|p| p.keys().cloned().collect())
+        .unwrap_or_default()
+}
+
+/// Name of the built-in subcommand generating shell completions
+const COMPLETIONS_COMMAND: &str = "completions";
+
+/// Name of the built-in subcommand executing JSONL requests from stdin
+const EXEC_COMMAND: &str = "exec";
+
+/// Name of the built-in subcommand listing the available tools
+const LIST_TOOLS_COMMAND: &str = "list-tools";
+
+/// Name of the built-in subcommand inspecting and rolling back the operation journal
+const RECOVER_COMMAND: &str = "recover";
+
+/// Exit code used when a tool runs but reports failure
+const TOOL_FAILURE_EXIT_CODE: i32 = 1;
+
+/// Flush any pending trace output, then exit the process with `code`
+fn exit(code: i32, trace_guard: Option<FlushGuard>) -> ! {
+    drop(trace_guard);
+    std::process::exit(code)
+}
+
+#[tokio::main]
+async fn main() -> anyhow::Result<()> {
+    // One registry both describes the subcommands and executes the selected tool
+    let config = match Config::load(&std::env::current_dir()?) {
+        Ok(config) => Some(config),
+        Err(e) => {
+            eprintln!("Warning: ignoring {}: {:#}", CONFIG_FILE, e);
+            None
+        }
+    };
+    // The CLI keeps the journal `cats recover` works from unless cats.toml turns it off
+    let journal = config
+        .as_ref()
+        .and_then(|config| config.editing.journal)
+        .unwrap_or(true);
+    let mut builder = RegistryBuilder::new().journal(journal);
+    if let Some(config) = config {
+        builder = builder.config(config);
+    }
+    // CATS_* variables override cats.toml, e.g. in containers
+    let mut registry = match builder.env() {
+        Ok(builder) => builder.build(),
+        Err(e) => {
+            eprintln!("Error: {}", e);
+            std::process::exit(e.exit_code());
+        }
+    };
+    let app = build_cli(&registry);
+
+    let json_requested = std::env::args().any(|arg| arg == "--json");
+    let matches = match app.try_get_matches() {
+        Ok(matches) => matches,
+        Err(e) if e.kind() == clap::error::ErrorKind::InvalidSubcommand => {
+            // Report unknown tools the same way the registry does
+            let name = std::env::args()
+                .skip(1)
+                .find(|arg| !arg.starts_with('-'))
+                .unwrap_or_default();
+            let error = ToolError::ToolNotFound {
+                suggestions: registry.suggest_tool_names(&name),
+                name,
+            };
+            if json_requested {
+                eprintln!("{}", error.to_json());
+            } else {
+                eprintln!("Error: {}", error);
+            }
+            std::process::exit(error.exit_code());
+        }
+        Err(e) => {
+            if json_requested && e.use_stderr() {
+                eprintln!(
+                    "{}",
+                    serde_json::json!({
+                        "code": e.exit_code(),
+                        "kind": "usage",
+                        "message": e.kind().to_string(),
+                        "suggestions": []
+                    })
+                );
+                std::process::exit(e.exit_code());
+            }
+            e.exit();
+        }
+    };
+    let json_output = matches.get_flag("json");
+    if matches.get_flag("terse") {
+        registry.set_message_style(MessageStyle::terse());
+    }
+    if matches.get_flag("compact") {
+        registry.set_verbosity(Verbosity::Compact);
+    }
+
+    // Profile tool execution into a Chrome trace when requested
+    let trace_guard = match matches.get_one::<PathBuf>("trace-output") {
+        Some(path) => Some(init_chrome_trace(path)?),
+        None => None,
+    };
 
     match matches.subcommand() {
+        Some((COMPLETIONS_COMMAND, sub_matches)) => {
+            let shell = *sub_matches
+                .get_one::<Shell>("shell")
+                .expect("shell is a required argument");
+            let mut app = build_cli(&registry);
+            clap_complete::generate(shell, &mut app, "cats", &mut std::io::stdout());
+        }
+        Some((LIST_TOOLS_COMMAND, sub_matches)) => {
+            let mut descriptors = tool_descriptors(&registry);
+            if let Some(ToolCategoryArg(category)) = sub_matches.get_one("category").copied() {
+                descriptors.retain(|d| d.category == category);
+            }
+            if json_output {
+                println!("{}", serde_json::to_string(&descriptors)?);
+            } else {
+                print!("{}", render_tool_list(&descriptors));
+            }
+        }
+        Some((EXEC_COMMAND, _)) => {
+            let stdin = std::io::stdin();
+            let mut stdout = std::io::stdout();
+            for line in stdin.lock().lines() {
+                let line = line?;
+                if line.trim().is_empty() {
+                    continue;
+                }
+                let response = execute_json_line(&mut registry, &line);
+                writeln!(stdout, "{}", response)?;
+                stdout.flush()?;
+            }
+        }
+        Some((RECOVER_COMMAND, sub_matches)) => {
+            let journal_dir = sub_matches
+                .get_one::<PathBuf>("dir")
+                .cloned()
+                .unwrap_or_else(|| PathBuf::from(JOURNAL_DIR));
+            let options = RecoverOptions {
+                rollback: sub_matches.get_flag("rollback"),
+                force: sub_matches.get_flag("force"),
+                prune: sub_matches.get_flag("prune"),
+            };
+            let report = recover(&journal_dir, options)?;
+            if json_output {
+                println!("{}", serde_json::to_string(&report)?);
+            } else {
+                println!("{}", report.render());
+            }
+        }
         Some((tool_name, sub_matches)) => {
             let args: Vec<String> = sub_matches
                 .get_many::<String>("args")
@@ -55,24 +349,82 @@ async fn main() -> anyhow::Result<()> {
                 .cloned()
                 .collect();
 
-            let tool_args = ToolArgs::with_named_args(args, HashMap::new());
+            let named_keys = registry
+                .get_tool(tool_name)
+                .map(|tool| parameter_names(&tool.get_parameters_schema()))
+                .unwrap_or_default();
+            let named_keys: Vec<&str> = named_keys.iter().map(|k| k.as_str()).collect();
+            let mut tool_args = ToolArgs::from_cli_tokens(&args, &named_keys);
+
+            for pair in sub_matches.get_many::<String>("arg").unwrap_or_default() {
+                match pair.split_once('=') {
+                    Some((key, value)) => {
+                        tool_args
+                            .named_args
+                            .insert(key.to_string(), value.to_string());
+                    }
+                    None => {
+                        let error = ToolError::InvalidArgs {
+                            message: format!("--arg expects KEY=VALUE, got '{}'", pair),
+                        };
+                        if json_output {
+                            eprintln!("{}", error.to_json());
+                        } else {
+                            eprintln!("Error: {}", error);
+                        }
+                        exit(error.exit_code(), trace_guard);
+                    }
+                }
+            }
 
             match registry.execute_tool(tool_name, &tool_args) {
                 Ok(result) => {
-                    println!("{}", result.message);
+                    if json_output {
+                        println!("{}", serde_json::to_string(&result)?);
+                    } else {
+                        println!("{}", result.message);
+                    }
                     if !result.success {
-                        std::process::exit(1);
+                        // A missing path exits like `ToolError::FileNotFound`
+                        let failure = result.failure();
+                        let (code, kind) = failure
+                            .as_ref()
+                            .map_or((TOOL_FAILURE_EXIT_CODE, "tool_failed"), |e| {
+                                (e.exit_code(), e.kind())
+                            });
+                        if json_output {
+                            let suggestions = result
+                                .data
+                                .as_ref()
+                                .and_then(|d| d.get("suggestions"))
+                                .cloned()
+                                .unwrap_or_else(|| serde_json::json!([]));
+                            eprintln!(
+                                "{}",
+                                serde_json::json!({
+                                    "code": code,
+                                    "kind": kind,
+                                    "message": result.message,
+                                    "suggestions": suggestions
+                                })
+                            );
+                        }
+                        exit(code, trace_guard);
                     }
                 }
                 Err(e) => {
-                    eprintln!("Error: {}", e);
-                    std::process::exit(1);
+                    if json_output {
+                        eprintln!("{}", e.to_json());
+                    } else {
+                        eprintln!("Error: {}", e);
+                    }
+                    exit(e.exit_code(), trace_guard);
                 }
             }
         }
         None => {
             eprintln!("No tool specified");
-            std::process::exit(1);
+            exit(1, trace_guard);
         }
     }
 
@@ -0,0 +1,312 @@
+//! User-facing message templates
+//!
+//! Decorated and repeated parts of tool messages (headings, listings, warnings) are
+//! rendered from templates looked up by [`MessageKey`], so embedders can change their
+//! tone or language, or drop decoration to save tokens, without patching tools. A
+//! [`MessageStyle`] picks the built-in standard or terse templates and can override
+//! any of them; set it for every session with `ToolRegistry::set_message_style`.
+//!
+//! Templates name their placeholders in braces (`{path}`); `{{` and `}}` are literal
+//! braces, and a placeholder the message does not provide is left as written.
+
+use crate::core::ToolError;
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+use std::fmt::{self, Write as _};
+
+/// A templated message, listing the placeholders it is rendered with
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum MessageKey {
+    /// `search_dir` result heading: `{count}`, `{term}`, `{dir}`, `{files}`
+    SearchSummary,
+    /// `search_dir` heading for one file: `{path}`, `{count}`
+    SearchFileHeading,
+    /// `search_dir` without matches: `{term}`, `{dir}`
+    SearchNoMatches,
+    /// `filemap` root directory: `{path}`
+    FilemapRoot,
+    /// `filemap` directory entry: `{indent}`, `{name}`
+    FilemapDirectory,
+    /// `filemap` file entry: `{indent}`, `{name}`, `{size}`
+    FilemapFile,
+    /// `filemap` heading of a single file: `{path}`, `{lines}`
+    FileSummary,
+    /// Rule above and below a rendered window; an empty rule is omitted
+    WindowRule,
+    /// A warning appended to a result: `{warning}`
+    Warning,
+    /// A written file that did not read back as written: `{path}`
+    ReadBackMismatch,
+    /// A call repeated with the same arguments: `{tool}`, `{count}`, `{hint}`
+    RepeatedCall,
+}
+
+impl MessageKey {
+    /// All keys, in declaration order
+    pub const ALL: [MessageKey; 11] = [
+        MessageKey::SearchSummary,
+        MessageKey::SearchFileHeading,
+        MessageKey::SearchNoMatches,
+        MessageKey::FilemapRoot,
+        MessageKey::FilemapDirectory,
+        MessageKey::FilemapFile,
+        MessageKey::FileSummary,
+        MessageKey::WindowRule,
+        MessageKey::Warning,
+        MessageKey::ReadBackMismatch,
+        MessageKey::RepeatedCall,
+    ];
+
+    pub fn as_str(self) -> &'static str {
+        match self {
+            MessageKey::SearchSummary => "search_summary",
+            MessageKey::SearchFileHeading => "search_file_heading",
+            MessageKey::SearchNoMatches => "search_no_matches",
+            MessageKey::FilemapRoot => "filemap_root",
+            MessageKey::FilemapDirectory => "filemap_directory",
+            MessageKey::FilemapFile => "filemap_file",
+            MessageKey::FileSummary => "file_summary",
+            MessageKey::WindowRule => "window_rule",
+            MessageKey::Warning => "warning",
+            MessageKey::ReadBackMismatch => "read_back_mismatch",
+            MessageKey::RepeatedCall => "repeated_call",
+        }
+    }
+
+    /// Built-in template of the standard style
+    pub fn standard_template(self) -> &'static str {
+        match self {
+            MessageKey::SearchSummary => {
+                "Found {count} matches for \"{term}\" in {dir} across {files} files:"
+            }
+            MessageKey::SearchFileHeading => "📁 {path} ({count} matches):",
+            MessageKey::SearchNoMatches => "No matches found for \"{term}\" in {dir}",
+            MessageKey::FilemapRoot => "📁 {path}",
+            MessageKey::FilemapDirectory => "{indent}📁 {name}/",
+            MessageKey::FilemapFile => "{indent}📄 {name} ({size} bytes)",
+            MessageKey::FileSummary => "📄 {path} ({lines} lines)",
+            MessageKey::WindowRule => {
+                "--------------------------------------------------------------------------------"
+            }
+            MessageKey::Warning => "Warning: {warning}",
+            MessageKey::ReadBackMismatch => {
+                "{path} did not read back with the content that was written; another process, the filesystem or an encoding conversion may have changed it"
+            }
+            MessageKey::RepeatedCall => {
+                "{tool} was called {count} times with the same arguments and no file changed in between. {hint}"
+            }
+        }
+    }
+
+    /// Built-in template of the terse style: no emoji or separator rules
+    pub fn terse_template(self) -> &'static str {
+        match self {
+            MessageKey::SearchSummary => "{count} matches for \"{term}\" in {files} files:",
+            MessageKey::SearchFileHeading => "{path} ({count}):",
+            MessageKey::SearchNoMatches => "No matches for \"{term}\"",
+            MessageKey::FilemapRoot => "{path}",
+            MessageKey::FilemapDirectory => "{indent}{name}/",
+            MessageKey::FilemapFile => "{indent}{name} ({size}b)",
+            MessageKey::FileSummary => "{path} ({lines} lines)",
+            MessageKey::WindowRule => "",
+            MessageKey::Warning => "Warning: {warning}",
+            MessageKey::ReadBackMismatch => "{path} did not read back as written",
+            MessageKey::RepeatedCall => "{tool} repeated {count} times unchanged. {hint}",
+        }
+    }
+}
+
+impl fmt::Display for MessageKey {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(self.as_str())
+    }
+}
+
+impl std::str::FromStr for MessageKey {
+    type Err = ToolError;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        Self::ALL
+            .into_iter()
+            .find(|key| key.as_str().eq_ignore_ascii_case(s))
+            .ok_or_else(|| ToolError::InvalidArgs {
+                message: format!(
+                    "Unknown message key '{}'; expected one of: {}",
+                    s,
+                    Self::ALL.map(|k| k.as_str()).join(", ")
+                ),
+            })
+    }
+}
+
+/// How much a successful result's message says
+///
+/// Compact messages keep counts and key line numbers and drop banners and rendered
+/// windows; the result's `data` is the same at every verbosity, and error messages are
+/// never shortened.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Hash, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum Verbosity {
+    #[default]
+    Normal,
+    Compact,
+}
+
+impl Verbosity {
+    pub const ALL: [Verbosity; 2] = [Verbosity::Normal, Verbosity::Compact];
+
+    pub fn as_str(self) -> &'static str {
+        match self {
+            Verbosity::Normal => "normal",
+            Verbosity::Compact => "compact",
+        }
+    }
+}
+
+impl fmt::Display for Verbosity {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(self.as_str())
+    }
+}
+
+impl std::str::FromStr for Verbosity {
+    type Err = ToolError;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        Self::ALL
+            .into_iter()
+            .find(|verbosity| verbosity.as_str().eq_ignore_ascii_case(s))
+            .ok_or_else(|| ToolError::InvalidArgs {
+                message: format!(
+                    "Unknown verbosity '{}'; expected one of: {}",
+                    s,
+                    Self::ALL.map(|v| v.as_str()).join(", ")
+                ),
+            })
+    }
+}
+
+/// How tools phrase templated messages: standard or terse, plus overrides
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct MessageStyle {
+    terse: bool,
+    overrides: HashMap<MessageKey, String>,
+}
+
+impl MessageStyle {
+    /// The standard style, with emoji and separator rules
+    pub fn standard() -> Self {
+        Self::default()
+    }
+
+    /// A style without decorative emoji and separators, to save tokens
+    pub fn terse() -> Self {
+        Self {
+            terse: true,
+            ..Self::default()
+        }
+    }
+
+    pub fn is_terse(&self) -> bool {
+        self.terse
+    }
+
+    /// Replace the template for `key`
+    pub fn with_template(mut self, key: MessageKey, template: impl Into<String>) -> Self {
+        self.overrides.insert(key, template.into());
+        self
+    }
+
+    /// The template used for `key`
+    pub fn template(&self, key: MessageKey) -> &str {
+        match self.overrides.get(&key) {
+            Some(template) => template,
+            None if self.terse => key.terse_template(),
+            None => key.standard_template(),
+        }
+    }
+
+    /// Render `key` with named placeholder values
+    pub fn render(&self, key: MessageKey, values: &[(&str, &dyn fmt::Display)]) -> String {
+        let template = self.template(key);
+        let mut out = String::with_capacity(template.len() + 32);
+        let mut rest = template;
+        while let Some(pos) = rest.find(['{', '}']) {
+            out.push_str(&rest[..pos]);
+            let tail = &rest[pos..];
+            if tail.starts_with("{{") || tail.starts_with("}}") {
+                out.push_str(&tail[..1]);
+                rest = &tail[2..];
+                continue;
+            }
+            let placeholder = tail
+                .strip_prefix('{')
+                .and_then(|t| t.find('}').map(|end| &t[..end]));
+            match placeholder.and_then(|name| values.iter().find(|(n, _)| *n == name)) {
+                Some((name, value)) => {
+                    let _ = write!(out, "{}", value);
+                    rest = &tail[name.len() + 2..];
+                }
+                None => {
+                    out.push_str(&tail[..1]);
+                    rest = &tail[1..];
+                }
+            }
+        }
+        out.push_str(rest);
+        out
+    }
+
+    /// Render `key` followed by a newline, or nothing if it renders empty
+    pub fn render_line(&self, key: MessageKey, values: &[(&str, &dyn fmt::Display)]) -> String {
+        let mut line = self.render(key, values);
+        if !line.is_empty() {
+            line.push('\n');
+        }
+        line
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_render_placeholders_and_escapes() {
+        let style = MessageStyle::standard();
+        assert_eq!(
+            style.render(
+                MessageKey::FileSummary,
+                &[("path", &"src/lib.rs"), ("lines", &12)]
+            ),
+            "📄 src/lib.rs (12 lines)"
+        );
+
+        let style = style.with_template(MessageKey::Warning, "Attention {{{warning}}} {unknown} {");
+        assert_eq!(
+            style.render(MessageKey::Warning, &[("warning", &"x")]),
+            "Attention {x} {unknown} {"
+        );
+    }
+
+    #[test]
+    fn test_terse_style_and_overrides() {
+        let terse = MessageStyle::terse();
+        assert!(terse.is_terse());
+        assert!(MessageKey::ALL
+            .iter()
+            .all(|key| !terse.template(*key).contains(['📁', '📄', '-'])));
+        assert_eq!(terse.render_line(MessageKey::WindowRule, &[]), "");
+
+        let german = terse.with_template(MessageKey::SearchNoMatches, "Keine Treffer für {term}");
+        assert_eq!(
+            german.render(MessageKey::SearchNoMatches, &[("term", &"foo")]),
+            "Keine Treffer für foo"
+        );
+        assert_eq!(
+            "Search_Summary".parse::<MessageKey>().unwrap(),
+            MessageKey::SearchSummary
+        );
+        assert!("nope".parse::<MessageKey>().is_err());
+    }
+}
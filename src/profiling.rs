@@ -0,0 +1,219 @@
+//! Profiling support for tool execution
+//!
+//! Every `ToolRegistry::execute_tool` call runs inside a `tool` tracing span carrying
+//! the tool name, argument size, duration and the bytes the tool read and wrote.
+//! File I/O performed through [`read_to_string`] and [`write`] is counted per thread
+//! so the registry can attach it to the span. With the `chrome-trace` feature,
+//! `init_chrome_trace` installs a subscriber writing these spans in Chrome trace format
+//! (viewable in `chrome://tracing` or Perfetto), which is what the CLI's
+//! `--trace-output` uses.
+
+use std::cell::Cell;
+use std::fs;
+use std::io;
+use std::path::Path;
+
+#[cfg(feature = "chrome-trace")]
+pub use chrome::{chrome_trace_subscriber, init_chrome_trace, FlushGuard};
+
+/// Name of the span wrapping each tool execution
+pub const TOOL_SPAN: &str = "tool";
+
+/// Bytes read and written through the counted I/O helpers
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct IoCounters {
+    pub bytes_read: u64,
+    pub bytes_written: u64,
+}
+
+impl IoCounters {
+    /// Counters accumulated since an earlier snapshot
+    pub fn since(&self, earlier: IoCounters) -> IoCounters {
+        IoCounters {
+            bytes_read: self.bytes_read.saturating_sub(earlier.bytes_read),
+            bytes_written: self.bytes_written.saturating_sub(earlier.bytes_written),
+        }
+    }
+}
+
+thread_local! {
+    static IO_COUNTERS: Cell<IoCounters> = Cell::new(IoCounters::default());
+}
+
+/// Snapshot of the current thread's I/O counters
+pub fn io_counters() -> IoCounters {
+    IO_COUNTERS.with(|counters| counters.get())
+}
+
+/// Record `bytes` read on the current thread
+pub fn record_read(bytes: usize) {
+    IO_COUNTERS.with(|counters| {
+        let mut current = counters.get();
+        current.bytes_read += bytes as u64;
+        counters.set(current);
+    });
+}
+
+/// Record `bytes` written on the current thread
+pub fn record_written(bytes: usize) {
+    IO_COUNTERS.with(|counters| {
+        let mut current = counters.get();
+        current.bytes_written += bytes as u64;
+        counters.set(current);
+    });
+}
+
+/// `fs::read_to_string` that counts the bytes read
+pub fn read_to_string<P: AsRef<Path>>(path: P) -> io::Result<String> {
+    let content = fs::read_to_string(path)?;
+    record_read(content.len());
+    Ok(content)
+}
+
+/// `fs::write` that counts the bytes written
+pub fn write<P: AsRef<Path>, C: AsRef<[u8]>>(path: P, contents: C) -> io::Result<()> {
+    let contents = contents.as_ref();
+    fs::write(path, contents)?;
+    record_written(contents.len());
+    Ok(())
+}
+
+/// Chrome trace output, behind the `chrome-trace` feature
+#[cfg(feature = "chrome-trace")]
+mod chrome {
+    use super::TOOL_SPAN;
+    use anyhow::Result;
+    use std::path::Path;
+    use tracing::span;
+    use tracing_chrome::{ChromeLayerBuilder, EventOrSpan};
+    use tracing_subscriber::layer::{Context, Layer};
+    use tracing_subscriber::prelude::*;
+    use tracing_subscriber::registry::LookupSpan;
+
+    pub use tracing_chrome::FlushGuard;
+
+    /// Tool name captured from the `tool` field of a tool span
+    struct ToolName(String);
+
+    /// Layer storing each tool span's tool name so trace entries can be named after it
+    struct ToolNameLayer;
+
+    impl<S> Layer<S> for ToolNameLayer
+    where
+        S: tracing::Subscriber + for<'a> LookupSpan<'a>,
+    {
+        fn on_new_span(&self, attrs: &span::Attributes<'_>, id: &span::Id, ctx: Context<'_, S>) {
+            if attrs.metadata().name() != TOOL_SPAN {
+                return;
+            }
+            let mut visitor = ToolNameVisitor(None);
+            attrs.record(&mut visitor);
+            if let (Some(name), Some(span)) = (visitor.0, ctx.span(id)) {
+                span.extensions_mut().insert(ToolName(name));
+            }
+        }
+    }
+
+    struct ToolNameVisitor(Option<String>);
+
+    impl tracing::field::Visit for ToolNameVisitor {
+        fn record_str(&mut self, field: &tracing::field::Field, value: &str) {
+            if field.name() == "tool" {
+                self.0 = Some(value.to_string());
+            }
+        }
+
+        fn record_debug(&mut self, field: &tracing::field::Field, value: &dyn std::fmt::Debug) {
+            if field.name() == "tool" {
+                self.0 = Some(format!("{:?}", value).trim_matches('"').to_string());
+            }
+        }
+    }
+
+    /// Build a subscriber that writes tool spans to `path` in Chrome trace format
+    ///
+    /// Spans are named after the tool they execute and carry their recorded fields as
+    /// trace arguments. Keep the returned guard alive until profiling should end; the
+    /// trace is flushed when it is dropped.
+    pub fn chrome_trace_subscriber<P: AsRef<Path>>(
+        path: P,
+    ) -> (impl tracing::Subscriber + Send + Sync, FlushGuard) {
+        let (chrome_layer, guard) = ChromeLayerBuilder::new()
+            .file(path.as_ref())
+            .include_args(true)
+            .name_fn(Box::new(|event_or_span| match event_or_span {
+                EventOrSpan::Event(event) => event.metadata().name().into(),
+                EventOrSpan::Span(span) => span
+                    .extensions()
+                    .get::<ToolName>()
+                    .map(|name| name.0.clone())
+                    .unwrap_or_else(|| span.name().into()),
+            }))
+            .build();
+        let subscriber = tracing_subscriber::registry()
+            .with(ToolNameLayer)
+            .with(chrome_layer);
+        (subscriber, guard)
+    }
+
+    /// Install a global subscriber writing a Chrome trace to `path`
+    pub fn init_chrome_trace<P: AsRef<Path>>(path: P) -> Result<FlushGuard> {
+        let (subscriber, guard) = chrome_trace_subscriber(path);
+        tracing::subscriber::set_global_default(subscriber)
+            .map_err(|e| anyhow::anyhow!("Failed to install trace subscriber: {}", e))?;
+        Ok(guard)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use tempfile::TempDir;
+
+    #[test]
+    fn test_io_counters_track_reads_and_writes() {
+        let temp_dir = TempDir::new().unwrap();
+        let path = temp_dir.path().join("a.txt");
+        let before = io_counters();
+
+        write(&path, "hello").unwrap();
+        assert_eq!(read_to_string(&path).unwrap(), "hello");
+
+        let delta = io_counters().since(before);
+        assert_eq!(delta.bytes_written, 5);
+        assert_eq!(delta.bytes_read, 5);
+    }
+
+    #[test]
+    #[cfg(feature = "chrome-trace")]
+    fn test_chrome_trace_names_spans_after_tools() {
+        use crate::{create_tool_registry, ToolArgs};
+
+        let temp_dir = TempDir::new().unwrap();
+        let trace_path = temp_dir.path().join("trace.json");
+        let file_path = temp_dir.path().join("sample.txt");
+        fs::write(&file_path, "line 1\nline 2\n").unwrap();
+
+        let (subscriber, guard) = chrome_trace_subscriber(&trace_path);
+        tracing::subscriber::with_default(subscriber, || {
+            let mut registry = create_tool_registry();
+            registry
+                .execute_tool("open", &ToolArgs::from_args(&[file_path.to_str().unwrap()]))
+                .unwrap();
+        });
+        drop(guard);
+
+        let trace: serde_json::Value =
+            serde_json::from_str(&fs::read_to_string(&trace_path).unwrap()).unwrap();
+        let entry = trace
+            .as_array()
+            .unwrap()
+            .iter()
+            .find(|e| e["name"] == "open" && e["ph"] == "E")
+            .expect("tool span in trace");
+        // tracing-chrome records span fields as strings
+        assert_eq!(entry["args"]["success"], "true");
+        assert_eq!(entry["args"]["bytes_read"], "14");
+        assert!(entry["args"].get("duration_us").is_some());
+    }
+}
@@ -0,0 +1,278 @@
+//! Validation of tool arguments against their JSON schemas
+//!
+//! Covers the part of JSON Schema that tool schemas use: `type` (one type or a list),
+//! `properties`, `required`, `additionalProperties`, `enum`, `minimum`, `maximum` and
+//! `items`. It is stricter than JSON Schema about objects: a property the schema does
+//! not list is a violation unless `additionalProperties` allows it, because an unknown
+//! argument is almost always a misspelled one.
+//!
+//! Embedders can check model-produced arguments before running anything with
+//! `ToolRegistry::validate_against_schema`, turn the violations into a correction
+//! prompt with [`violation_report`], and hand every tool schema to their own validator
+//! with `ToolRegistry::export_schemas`.
+
+use crate::utils::suggest::{closest_matches, MAX_SUGGESTIONS};
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+use std::fmt;
+
+/// JSON Schema dialect declared by `ToolRegistry::export_schemas`
+pub const SCHEMA_DIALECT: &str = "https://json-schema.org/draft/2020-12/schema";
+
+/// One way a value does not match its schema
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct SchemaViolation {
+    /// Path of the offending value, e.g. `/line_number` or `/content/2`; empty for
+    /// the value itself
+    pub path: String,
+    pub message: String,
+}
+
+impl fmt::Display for SchemaViolation {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        if self.path.is_empty() {
+            f.write_str(&self.message)
+        } else {
+            write!(f, "{}: {}", self.path, self.message)
+        }
+    }
+}
+
+/// Every violation of `schema` by `value`, in document order
+pub fn validate(schema: &Value, value: &Value) -> Vec<SchemaViolation> {
+    let mut violations = Vec::new();
+    validate_at(schema, value, "", &mut violations);
+    violations
+}
+
+/// The violations of a call to `tool`, one per line, e.g. to ask the model for a
+/// corrected call
+pub fn violation_report(tool: &str, violations: &[SchemaViolation]) -> String {
+    let lines: Vec<String> = violations
+        .iter()
+        .map(|violation| format!("  - {}", violation))
+        .collect();
+    format!(
+        "{} arguments do not match its schema:\n{}",
+        tool,
+        lines.join("\n")
+    )
+}
+
+fn validate_at(schema: &Value, value: &Value, path: &str, out: &mut Vec<SchemaViolation>) {
+    let mut violation = |message: String| {
+        out.push(SchemaViolation {
+            path: path.to_string(),
+            message,
+        })
+    };
+
+    let types: Vec<&str> = match schema.get("type") {
+        Some(Value::String(kind)) => vec![kind.as_str()],
+        Some(Value::Array(kinds)) => kinds.iter().filter_map(Value::as_str).collect(),
+        _ => Vec::new(),
+    };
+    if !types.is_empty() && !types.iter().any(|kind| has_type(value, kind)) {
+        violation(format!(
+            "expected {}, got {}",
+            types.join(" or "),
+            describe(value)
+        ));
+        return;
+    }
+
+    if let Some(allowed) = schema.get("enum").and_then(Value::as_array) {
+        if !allowed.contains(value) {
+            let allowed: Vec<String> = allowed.iter().map(display_value).collect();
+            violation(format!(
+                "{} is not one of: {}",
+                display_value(value),
+                allowed.join(", ")
+            ));
+        }
+    }
+    if let Some(number) = value.as_f64() {
+        if let Some(minimum) = schema.get("minimum").and_then(Value::as_f64) {
+            if number < minimum {
+                violation(format!("{} is less than the minimum {}", value, minimum));
+            }
+        }
+        if let Some(maximum) = schema.get("maximum").and_then(Value::as_f64) {
+            if number > maximum {
+                violation(format!("{} is greater than the maximum {}", value, maximum));
+            }
+        }
+    }
+
+    match value {
+        Value::Object(object) => validate_object(schema, object, path, out),
+        Value::Array(items) => {
+            if let Some(item_schema) = schema.get("items") {
+                for (index, item) in items.iter().enumerate() {
+                    validate_at(item_schema, item, &format!("{}/{}", path, index), out);
+                }
+            }
+        }
+        _ => {}
+    }
+}
+
+fn validate_object(
+    schema: &Value,
+    object: &serde_json::Map<String, Value>,
+    path: &str,
+    out: &mut Vec<SchemaViolation>,
+) {
+    let empty = serde_json::Map::new();
+    let properties = schema
+        .get("properties")
+        .and_then(Value::as_object)
+        .unwrap_or(&empty);
+
+    if let Some(required) = schema.get("required").and_then(Value::as_array) {
+        for name in required.iter().filter_map(Value::as_str) {
+            if !object.contains_key(name) {
+                out.push(SchemaViolation {
+                    path: path.to_string(),
+                    message: format!("missing required parameter '{}'", name),
+                });
+            }
+        }
+    }
+
+    for (name, value) in object {
+        let child = format!("{}/{}", path, name);
+        match (properties.get(name), schema.get("additionalProperties")) {
+            (Some(property), _) => validate_at(property, value, &child, out),
+            (None, Some(Value::Bool(true))) => {}
+            (None, Some(additional @ Value::Object(_))) => {
+                validate_at(additional, value, &child, out)
+            }
+            (None, _) => {
+                let suggestions =
+                    closest_matches(name, properties.keys().map(String::as_str), MAX_SUGGESTIONS);
+                let mut expected: Vec<&str> = properties.keys().map(String::as_str).collect();
+                expected.sort_unstable();
+                let message = match suggestions.first() {
+                    Some(closest) => {
+                        format!("unknown parameter '{}'; did you mean '{}'?", name, closest)
+                    }
+                    None if expected.is_empty() => {
+                        format!("unknown parameter '{}'; the tool takes no parameters", name)
+                    }
+                    None => format!(
+                        "unknown parameter '{}'; expected one of: {}",
+                        name,
+                        expected.join(", ")
+                    ),
+                };
+                out.push(SchemaViolation {
+                    path: path.to_string(),
+                    message,
+                });
+            }
+        }
+    }
+}
+
+fn has_type(value: &Value, kind: &str) -> bool {
+    match kind {
+        "string" => value.is_string(),
+        "integer" => {
+            value.is_i64() || value.is_u64() || value.as_f64().is_some_and(|n| n.fract() == 0.0)
+        }
+        "number" => value.is_number(),
+        "boolean" => value.is_boolean(),
+        "array" => value.is_array(),
+        "object" => value.is_object(),
+        "null" => value.is_null(),
+        // Unknown type names constrain nothing
+        _ => true,
+    }
+}
+
+/// Type and value of `value` for a type mismatch message
+fn describe(value: &Value) -> String {
+    let kind = match value {
+        Value::Null => return "null".to_string(),
+        Value::Bool(_) => "boolean",
+        Value::Number(_) => "number",
+        Value::String(_) => "string",
+        Value::Array(_) => return "array".to_string(),
+        Value::Object(_) => return "object".to_string(),
+    };
+    format!("{} {}", kind, display_value(value))
+}
+
+/// `value` for a message: strings quoted and shortened, everything else as JSON
+fn display_value(value: &Value) -> String {
+    match value {
+        Value::String(text) if text.chars().count() > 40 => {
+            format!("'{}...'", text.chars().take(40).collect::<String>())
+        }
+        Value::String(text) => format!("'{}'", text),
+        other => other.to_string(),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    #[test]
+    fn test_validate() {
+        let schema = json!({
+            "type": "object",
+            "properties": {
+                "path": {"type": "string"},
+                "new_text": {"type": "string"},
+                "occurrence": {"type": "integer", "minimum": 1},
+                "mode": {"type": "string", "enum": ["replace", "insert"]},
+                "content": {"type": ["string", "array"], "items": {"type": "string"}}
+            },
+            "required": ["path", "new_text"]
+        });
+
+        assert!(validate(&schema, &json!({"path": "a", "new_text": "b"})).is_empty());
+        assert!(validate(
+            &schema,
+            &json!({"path": "a", "new_text": "b", "content": ["x"]})
+        )
+        .is_empty());
+
+        let violations = validate(
+            &schema,
+            &json!({
+                "path": "a",
+                "new_txt": "b",
+                "occurrence": 0,
+                "mode": "append",
+                "content": ["x", 2]
+            }),
+        );
+        let messages: Vec<String> = violations.iter().map(ToString::to_string).collect();
+        assert_eq!(
+            messages,
+            [
+                "missing required parameter 'new_text'",
+                "/content/1: expected string, got number 2",
+                "/mode: 'append' is not one of: 'replace', 'insert'",
+                "unknown parameter 'new_txt'; did you mean 'new_text'?",
+                "/occurrence: 0 is less than the minimum 1",
+            ]
+        );
+
+        let loose = json!({"type": "object", "properties": {}, "additionalProperties": true});
+        assert!(validate(&loose, &json!({"anything": 1})).is_empty());
+        let none = json!({"type": "object", "properties": {}});
+        assert_eq!(
+            validate(&none, &json!({"x": 1}))[0].message,
+            "unknown parameter 'x'; the tool takes no parameters"
+        );
+        assert_eq!(
+            validate(&schema, &json!("a"))[0].message,
+            "expected object, got string 'a'"
+        );
+    }
+}
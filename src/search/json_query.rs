@@ -0,0 +1,829 @@
+//! Path queries over JSON files
+//!
+//! `query_json` pulls the values at a path out of a JSON file so that looking up one
+//! field of a large file does not mean reading all of it. Expressions accept both the
+//! JSONPath and the jq spelling of the common operations:
+//!
+//! - `.name`, `["name"]` or `['name']`: a member of an object
+//! - `[2]`, `[-1]`: an array element, negative indexes counting from the end
+//! - `[1:3]`, `[:2]`: a slice of an array
+//! - `[*]`, `[]` or `.*`: every element or member
+//! - `..name`, `..*`: searching all descendants
+//! - `[?(@.price < 10)]`, `[?(@.tags)]`: elements passing a comparison or having a field
+//! - a trailing `| length`, `| keys` or `| type`
+//!
+//! A leading `$` is optional, so `$.items[*].id` and `.items[].id` are the same query.
+
+use crate::core::{DangerLevel, Tool, ToolArgs, ToolCategory, ToolError, ToolResult};
+use crate::state::ToolState;
+use crate::utils::suggest::path_not_found;
+use anyhow::Result;
+use serde::Serialize;
+use serde_json::Value;
+use std::cmp::Ordering;
+use std::fs::File;
+use std::io::BufReader;
+use std::path::PathBuf;
+use std::sync::{Arc, Mutex};
+
+/// Matches shown when no `--limit` is given
+const DEFAULT_LIMIT: usize = 50;
+/// Largest accepted `--limit`
+const MAX_LIMIT: usize = 1000;
+/// Longest value, in characters, shown for one match
+const MAX_VALUE_CHARS: usize = 500;
+
+/// One step of a path expression
+#[derive(Debug, Clone, PartialEq)]
+pub enum Segment {
+    Key(String),
+    Index(i64),
+    Slice(Option<i64>, Option<i64>),
+    Wildcard,
+    /// The current values and all their descendants
+    Descendants,
+    Filter(Filter),
+}
+
+/// Comparison in a filter
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum CompareOp {
+    Eq,
+    Ne,
+    Lt,
+    Le,
+    Gt,
+    Ge,
+}
+
+/// `[?(@.path op value)]`, or `[?(@.path)]` keeping elements where the path exists
+#[derive(Debug, Clone, PartialEq)]
+pub struct Filter {
+    pub path: Vec<Segment>,
+    pub comparison: Option<(CompareOp, Value)>,
+}
+
+/// Function applied to each match after the path
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Function {
+    Length,
+    Keys,
+    Type,
+}
+
+/// A parsed expression
+#[derive(Debug, Clone, PartialEq)]
+pub struct JsonQuery {
+    pub segments: Vec<Segment>,
+    pub function: Option<Function>,
+}
+
+/// A value found by a query, with the JSONPath leading to it
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct JsonMatch {
+    pub path: String,
+    pub value: Value,
+}
+
+struct Parser<'a> {
+    chars: Vec<char>,
+    pos: usize,
+    source: &'a str,
+}
+
+impl Parser<'_> {
+    fn peek(&self) -> Option<char> {
+        self.chars.get(self.pos).copied()
+    }
+
+    fn eat(&mut self, c: char) -> bool {
+        if self.peek() == Some(c) {
+            self.pos += 1;
+            true
+        } else {
+            false
+        }
+    }
+
+    fn skip_whitespace(&mut self) {
+        while self.peek().is_some_and(char::is_whitespace) {
+            self.pos += 1;
+        }
+    }
+
+    fn error(&self, message: &str) -> String {
+        format!(
+            "{} at position {} of `{}`",
+            message,
+            self.pos + 1,
+            self.source
+        )
+    }
+
+    fn identifier(&mut self) -> Option<String> {
+        let start = self.pos;
+        while self
+            .peek()
+            .is_some_and(|c| c.is_alphanumeric() || c == '_' || c == '-')
+        {
+            self.pos += 1;
+        }
+        (self.pos > start).then(|| self.chars[start..self.pos].iter().collect())
+    }
+
+    fn quoted(&mut self) -> Result<String, String> {
+        let quote = self.peek().ok_or_else(|| self.error("Expected a string"))?;
+        self.pos += 1;
+        let mut text = String::new();
+        loop {
+            match self.peek() {
+                None => return Err(self.error("Unterminated string")),
+                Some(c) if c == quote => {
+                    self.pos += 1;
+                    return Ok(text);
+                }
+                Some('\\') => {
+                    self.pos += 1;
+                    let escaped = self
+                        .peek()
+                        .ok_or_else(|| self.error("Unterminated string"))?;
+                    text.push(match escaped {
+                        'n' => '\n',
+                        't' => '\t',
+                        other => other,
+                    });
+                    self.pos += 1;
+                }
+                Some(c) => {
+                    text.push(c);
+                    self.pos += 1;
+                }
+            }
+        }
+    }
+
+    fn integer(&mut self) -> Option<i64> {
+        let start = self.pos;
+        self.eat('-');
+        while self.peek().is_some_and(|c| c.is_ascii_digit()) {
+            self.pos += 1;
+        }
+        let text: String = self.chars[start..self.pos].iter().collect();
+        match text.parse() {
+            Ok(n) => Some(n),
+            Err(_) => {
+                self.pos = start;
+                None
+            }
+        }
+    }
+
+    /// Segments up to the end of the path, a `|`, or (inside a filter) a comparison
+    fn segments(&mut self, in_filter: bool) -> Result<Vec<Segment>, String> {
+        let mut segments = Vec::new();
+        loop {
+            self.skip_whitespace();
+            match self.peek() {
+                Some('.') if self.chars.get(self.pos + 1) == Some(&'.') => {
+                    self.pos += 2;
+                    segments.push(Segment::Descendants);
+                    if self.eat('*') {
+                        segments.push(Segment::Wildcard);
+                    } else if let Some(key) = self.identifier() {
+                        segments.push(Segment::Key(key));
+                    }
+                }
+                Some('.') => {
+                    self.pos += 1;
+                    if self.eat('*') {
+                        segments.push(Segment::Wildcard);
+                    } else if matches!(self.peek(), Some('"' | '\'')) {
+                        segments.push(Segment::Key(self.quoted()?));
+                    } else if let Some(key) = self.identifier() {
+                        segments.push(Segment::Key(key));
+                    }
+                }
+                Some('[') => {
+                    self.pos += 1;
+                    segments.push(self.bracket()?);
+                }
+                _ => return Ok(segments),
+            }
+            if in_filter
+                && segments
+                    .iter()
+                    .any(|s| !matches!(s, Segment::Key(_) | Segment::Index(_)))
+            {
+                return Err(self.error("Filters can only test member names and indexes"));
+            }
+        }
+    }
+
+    fn bracket(&mut self) -> Result<Segment, String> {
+        self.skip_whitespace();
+        let segment = match self.peek() {
+            Some(']') => Segment::Wildcard,
+            Some('*') => {
+                self.pos += 1;
+                Segment::Wildcard
+            }
+            Some('"' | '\'') => Segment::Key(self.quoted()?),
+            Some('?') => {
+                self.pos += 1;
+                self.skip_whitespace();
+                let parenthesized = self.eat('(');
+                let filter = self.filter()?;
+                self.skip_whitespace();
+                if parenthesized && !self.eat(')') {
+                    return Err(self.error("Expected `)` closing the filter"));
+                }
+                Segment::Filter(filter)
+            }
+            _ => {
+                let start = self.integer();
+                self.skip_whitespace();
+                if self.eat(':') {
+                    self.skip_whitespace();
+                    Segment::Slice(start, self.integer())
+                } else {
+                    Segment::Index(start.ok_or_else(|| {
+                        self.error("Expected an index, slice, `*`, string or filter")
+                    })?)
+                }
+            }
+        };
+        self.skip_whitespace();
+        if !self.eat(']') {
+            return Err(self.error("Expected `]`"));
+        }
+        Ok(segment)
+    }
+
+    fn filter(&mut self) -> Result<Filter, String> {
+        if !self.eat('@') {
+            return Err(self.error("Filters start with `@`, e.g. [?(@.price < 10)]"));
+        }
+        let path = self.segments(true)?;
+        self.skip_whitespace();
+        let op = [
+            ("==", CompareOp::Eq),
+            ("!=", CompareOp::Ne),
+            ("<=", CompareOp::Le),
+            (">=", CompareOp::Ge),
+            ("<", CompareOp::Lt),
+            (">", CompareOp::Gt),
+        ]
+        .into_iter()
+        .find(|(token, _)| {
+            token
+                .chars()
+                .enumerate()
+                .all(|(i, c)| self.chars.get(self.pos + i) == Some(&c))
+        });
+        let Some((token, op)) = op else {
+            return Ok(Filter {
+                path,
+                comparison: None,
+            });
+        };
+        self.pos += token.len();
+        self.skip_whitespace();
+        Ok(Filter {
+            path,
+            comparison: Some((op, self.literal()?)),
+        })
+    }
+
+    fn literal(&mut self) -> Result<Value, String> {
+        if matches!(self.peek(), Some('"' | '\'')) {
+            return Ok(Value::String(self.quoted()?));
+        }
+        let start = self.pos;
+        while self
+            .peek()
+            .is_some_and(|c| c.is_alphanumeric() || matches!(c, '-' | '+' | '.'))
+        {
+            self.pos += 1;
+        }
+        let text: String = self.chars[start..self.pos].iter().collect();
+        serde_json::from_str(&text)
+            .map_err(|_| self.error("Expected a string, number, true, false or null"))
+    }
+}
+
+impl JsonQuery {
+    /// Parse a JSONPath or jq-style expression
+    pub fn parse(expression: &str) -> Result<Self, String> {
+        let mut parser = Parser {
+            chars: expression.chars().collect(),
+            pos: 0,
+            source: expression,
+        };
+        parser.skip_whitespace();
+        parser.eat('$');
+        let segments = parser.segments(false)?;
+        parser.skip_whitespace();
+        let function = if parser.eat('|') {
+            parser.skip_whitespace();
+            let name = parser.identifier().unwrap_or_default();
+            Some(match name.as_str() {
+                "length" => Function::Length,
+                "keys" => Function::Keys,
+                "type" => Function::Type,
+                _ => {
+                    return Err(parser.error(&format!(
+                        "Unknown function '{}' (expected length, keys or type)",
+                        name
+                    )))
+                }
+            })
+        } else {
+            None
+        };
+        parser.skip_whitespace();
+        if parser.peek().is_some() {
+            return Err(parser.error("Unexpected character"));
+        }
+        Ok(Self { segments, function })
+    }
+
+    /// Every value the expression selects in `root`, in document order
+    pub fn evaluate(&self, root: &Value) -> Vec<JsonMatch> {
+        let mut current = vec![("$".to_string(), root)];
+        for segment in &self.segments {
+            current = current
+                .into_iter()
+                .flat_map(|(path, value)| step(segment, path, value))
+                .collect();
+        }
+        current
+            .into_iter()
+            .map(|(path, value)| JsonMatch {
+                path,
+                value: match self.function {
+                    None => value.clone(),
+                    Some(function) => apply(function, value),
+                },
+            })
+            .collect()
+    }
+}
+
+fn key_path(path: &str, key: &str) -> String {
+    let plain = key
+        .chars()
+        .next()
+        .is_some_and(|c| c.is_alphabetic() || c == '_')
+        && key.chars().all(|c| c.is_alphanumeric() || c == '_');
+    if plain {
+        format!("{}.{}", path, key)
+    } else {
+        format!("{}[{}]", path, Value::String(key.to_string()))
+    }
+}
+
+fn resolve_index(index: i64, len: usize) -> Option<usize> {
+    let index = if index < 0 { len as i64 + index } else { index };
+    (0..len as i64).contains(&index).then_some(index as usize)
+}
+
+fn children<'a>(path: &str, value: &'a Value) -> Vec<(String, &'a Value)> {
+    match value {
+        Value::Object(map) => map.iter().map(|(k, v)| (key_path(path, k), v)).collect(),
+        Value::Array(items) => items
+            .iter()
+            .enumerate()
+            .map(|(i, v)| (format!("{}[{}]", path, i), v))
+            .collect(),
+        _ => Vec::new(),
+    }
+}
+
+fn step<'a>(segment: &Segment, path: String, value: &'a Value) -> Vec<(String, &'a Value)> {
+    match segment {
+        Segment::Key(key) => value
+            .get(key)
+            .map(|child| (key_path(&path, key), child))
+            .into_iter()
+            .collect(),
+        Segment::Index(index) => value
+            .as_array()
+            .and_then(|items| {
+                let i = resolve_index(*index, items.len())?;
+                Some((format!("{}[{}]", path, i), &items[i]))
+            })
+            .into_iter()
+            .collect(),
+        Segment::Slice(start, end) => {
+            let Some(items) = value.as_array() else {
+                return Vec::new();
+            };
+            let len = items.len() as i64;
+            let clamp = |bound: i64| (if bound < 0 { len + bound } else { bound }).clamp(0, len);
+            let (start, end) = (clamp(start.unwrap_or(0)), clamp(end.unwrap_or(len)));
+            (start..end.max(start))
+                .map(|i| (format!("{}[{}]", path, i), &items[i as usize]))
+                .collect()
+        }
+        Segment::Wildcard => children(&path, value),
+        Segment::Descendants => {
+            let mut found = Vec::new();
+            let mut stack = vec![(path, value)];
+            while let Some((path, value)) = stack.pop() {
+                let mut nested = children(&path, value);
+                nested.reverse();
+                found.push((path, value));
+                stack.extend(nested);
+            }
+            found
+        }
+        Segment::Filter(filter) => children(&path, value)
+            .into_iter()
+            .filter(|(_, child)| filter.matches(child))
+            .collect(),
+    }
+}
+
+impl Filter {
+    fn matches(&self, value: &Value) -> bool {
+        let mut current = Some(value);
+        for segment in &self.path {
+            current = current.and_then(|value| match segment {
+                Segment::Key(key) => value.get(key),
+                Segment::Index(index) => value
+                    .as_array()
+                    .and_then(|items| items.get(resolve_index(*index, items.len())?)),
+                _ => None,
+            });
+        }
+        let Some(actual) = current else {
+            return false;
+        };
+        let Some((op, expected)) = &self.comparison else {
+            return true;
+        };
+        let ordering = match (actual, expected) {
+            (Value::Number(a), Value::Number(b)) => a
+                .as_f64()
+                .zip(b.as_f64())
+                .and_then(|(a, b)| a.partial_cmp(&b)),
+            (Value::String(a), Value::String(b)) => Some(a.cmp(b)),
+            (a, b) => (a == b).then_some(Ordering::Equal),
+        };
+        match op {
+            CompareOp::Eq => ordering == Some(Ordering::Equal),
+            CompareOp::Ne => ordering != Some(Ordering::Equal),
+            CompareOp::Lt => ordering == Some(Ordering::Less),
+            CompareOp::Le => matches!(ordering, Some(Ordering::Less | Ordering::Equal)),
+            CompareOp::Gt => ordering == Some(Ordering::Greater),
+            CompareOp::Ge => matches!(ordering, Some(Ordering::Greater | Ordering::Equal)),
+        }
+    }
+}
+
+fn apply(function: Function, value: &Value) -> Value {
+    match function {
+        Function::Length => match value {
+            Value::Array(items) => items.len().into(),
+            Value::Object(map) => map.len().into(),
+            Value::String(s) => s.chars().count().into(),
+            Value::Number(n) => n.as_f64().map_or(Value::Null, |n| n.abs().into()),
+            Value::Null => 0.into(),
+            Value::Bool(_) => Value::Null,
+        },
+        Function::Keys => match value {
+            Value::Object(map) => {
+                let mut keys: Vec<&String> = map.keys().collect();
+                keys.sort();
+                keys.into_iter().cloned().map(Value::String).collect()
+            }
+            Value::Array(items) => (0..items.len()).map(Value::from).collect(),
+            _ => Value::Null,
+        },
+        Function::Type => match value {
+            Value::Null => "null",
+            Value::Bool(_) => "boolean",
+            Value::Number(_) => "number",
+            Value::String(_) => "string",
+            Value::Array(_) => "array",
+            Value::Object(_) => "object",
+        }
+        .into(),
+    }
+}
+
+/// `value` as compact JSON, cut to `MAX_VALUE_CHARS` with a note on what was left out
+fn preview(value: &Value) -> (String, bool) {
+    let text = value.to_string();
+    let total = text.chars().count();
+    if total <= MAX_VALUE_CHARS {
+        return (text, false);
+    }
+    let outline = match value {
+        Value::Object(map) => format!("object with {} keys", map.len()),
+        Value::Array(items) => format!("array of {} items", items.len()),
+        _ => "value".to_string(),
+    };
+    let kept: String = text.chars().take(MAX_VALUE_CHARS).collect();
+    (
+        format!(
+            "{}... ({}, {} chars; query a narrower path)",
+            kept, outline, total
+        ),
+        true,
+    )
+}
+
+/// Tool extracting values from JSON files by path
+pub struct QueryJsonTool {
+    name: String,
+}
+
+impl QueryJsonTool {
+    pub fn new() -> Self {
+        Self {
+            name: "query_json".to_string(),
+        }
+    }
+
+    fn limit(args: &ToolArgs) -> Result<usize, ToolError> {
+        match args.get_named_arg("limit") {
+            None => Ok(DEFAULT_LIMIT),
+            Some(limit) => match limit.parse::<usize>() {
+                Ok(limit) if (1..=MAX_LIMIT).contains(&limit) => Ok(limit),
+                _ => Err(ToolError::InvalidArgs {
+                    message: format!(
+                        "Invalid limit '{}': expected a number from 1 to {}",
+                        limit, MAX_LIMIT
+                    ),
+                }),
+            },
+        }
+    }
+
+    /// The file and expression, named or positional
+    fn file_and_expression(args: &ToolArgs) -> (Option<&String>, Option<&String>) {
+        let file = args
+            .get_named_arg("file")
+            .or_else(|| args.get_named_arg("path"));
+        let expression = args.get_named_arg("expression");
+        match (file, expression) {
+            (Some(file), expression) => (Some(file), expression.or_else(|| args.get_arg(0))),
+            (None, Some(expression)) => (args.get_arg(0), Some(expression)),
+            (None, None) => (args.get_arg(0), args.get_arg(1)),
+        }
+    }
+}
+
+impl Default for QueryJsonTool {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl Tool for QueryJsonTool {
+    fn name(&self) -> &str {
+        &self.name
+    }
+
+    fn description(&self) -> &str {
+        "Extract values from a JSON file with a JSONPath or jq-style path (e.g. $.items[?(@.price < 10)].name, .dependencies | keys) instead of reading the whole file"
+    }
+
+    fn signature(&self) -> &str {
+        "query_json <file> <expression> [--limit=N]"
+    }
+
+    fn category(&self) -> ToolCategory {
+        ToolCategory::Search
+    }
+
+    fn danger_level(&self) -> DangerLevel {
+        DangerLevel::ReadOnly
+    }
+
+    fn validate_args(&self, args: &ToolArgs) -> Result<(), ToolError> {
+        let (file, expression) = Self::file_and_expression(args);
+        if file.is_none() {
+            return Err(ToolError::InvalidArgs {
+                message: "Missing required argument: file".to_string(),
+            });
+        }
+        let Some(expression) = expression else {
+            return Err(ToolError::InvalidArgs {
+                message: "Missing required argument: expression".to_string(),
+            });
+        };
+        JsonQuery::parse(expression).map_err(|message| ToolError::InvalidArgs { message })?;
+        Self::limit(args).map(|_| ())
+    }
+
+    fn execute(&mut self, args: &ToolArgs, state: &Arc<Mutex<ToolState>>) -> Result<ToolResult> {
+        let (file, expression) = Self::file_and_expression(args);
+        let (Some(file), Some(expression)) = (file, expression) else {
+            return Ok(ToolResult::error("Usage: query_json <file> <expression>"));
+        };
+        let query = match JsonQuery::parse(expression) {
+            Ok(query) => query,
+            Err(e) => return Ok(ToolResult::error(e)),
+        };
+        let limit = Self::limit(args).map_err(|e| anyhow::anyhow!("{}", e))?;
+        let path: PathBuf = state
+            .lock()
+            .map_err(|e| anyhow::anyhow!("Failed to lock state: {}", e))?
+            .working_directory
+            .join(file);
+        if !path.is_file() {
+            return Ok(path_not_found(
+                format!("File {} not found", path.display()),
+                &path,
+            ));
+        }
+        let root: Value = match serde_json::from_reader(BufReader::new(File::open(&path)?)) {
+            Ok(root) => root,
+            Err(e) => {
+                return Ok(ToolResult::error(format!(
+                    "{} is not valid JSON: {}",
+                    path.display(),
+                    e
+                )))
+            }
+        };
+
+        let matches = query.evaluate(&root);
+        let total = matches.len();
+        let mut lines = Vec::new();
+        let mut shown = Vec::new();
+        for found in matches.into_iter().take(limit) {
+            let (text, truncated) = preview(&found.value);
+            lines.push(format!("{} = {}", found.path, text));
+            shown.push(if truncated {
+                serde_json::json!({"path": found.path, "preview": text, "truncated": true})
+            } else {
+                serde_json::json!({"path": found.path, "value": found.value})
+            });
+        }
+        let message = if total == 0 {
+            let top_level = match &root {
+                Value::Object(map) => format!(
+                    "; top-level keys: {}",
+                    map.keys().take(30).cloned().collect::<Vec<_>>().join(", ")
+                ),
+                Value::Array(items) => {
+                    format!("; the document is an array of {} items", items.len())
+                }
+                _ => String::new(),
+            };
+            format!("No match for `{}` in {}{}", expression, file, top_level)
+        } else if shown.len() < total {
+            format!(
+                "{} matches for `{}` in {}, showing the first {}:\n{}",
+                total,
+                expression,
+                file,
+                shown.len(),
+                lines.join("\n")
+            )
+        } else {
+            format!(
+                "{} match(es) for `{}` in {}:\n{}",
+                total,
+                expression,
+                file,
+                lines.join("\n")
+            )
+        };
+        Ok(ToolResult::success_with_data(
+            message,
+            serde_json::json!({
+                "file": path.display().to_string(),
+                "expression": expression,
+                "total": total,
+                "matches": shown,
+            }),
+        ))
+    }
+
+    fn get_parameters_schema(&self) -> serde_json::Value {
+        serde_json::json!({
+            "type": "object",
+            "properties": {
+                "file": {
+                    "type": "string",
+                    "description": "JSON file to query"
+                },
+                "expression": {
+                    "type": "string",
+                    "description": "JSONPath or jq-style path: .a.b, [0], [-1], [1:3], [*], ..name, [?(@.x == 'y')], optionally followed by | length, | keys or | type"
+                },
+                "limit": {
+                    "type": "integer",
+                    "description": "Matches to return (default 50, at most 1000); the total is always reported"
+                }
+            },
+            "required": ["file", "expression"]
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use tempfile::TempDir;
+
+    fn query(expression: &str, root: &Value) -> Vec<(String, Value)> {
+        JsonQuery::parse(expression)
+            .unwrap()
+            .evaluate(root)
+            .into_iter()
+            .map(|m| (m.path, m.value))
+            .collect()
+    }
+
+    #[test]
+    fn test_jsonpath_and_jq_spellings() {
+        let root = serde_json::json!({
+            "items": [
+                {"name": "pen", "price": 2, "tags": ["office"]},
+                {"name": "lamp", "price": 30},
+                {"name": "ink", "price": 8.5, "tags": []}
+            ],
+            "meta": {"total count": 3}
+        });
+
+        let names = vec![
+            ("$.items[0].name".to_string(), serde_json::json!("pen")),
+            ("$.items[1].name".to_string(), serde_json::json!("lamp")),
+            ("$.items[2].name".to_string(), serde_json::json!("ink")),
+        ];
+        assert_eq!(query("$.items[*].name", &root), names);
+        assert_eq!(query(".items[].name", &root), names);
+        assert_eq!(query("$..name", &root), names);
+        assert_eq!(
+            query("$.items[-1].price", &root)[0].1,
+            serde_json::json!(8.5)
+        );
+        assert_eq!(query(".items[1:].name", &root).len(), 2);
+        assert_eq!(
+            query("$.items[?(@.price < 10)].name", &root)
+                .into_iter()
+                .map(|(_, v)| v)
+                .collect::<Vec<_>>(),
+            vec![serde_json::json!("pen"), serde_json::json!("ink")]
+        );
+        assert_eq!(
+            query("$.items[?(@.name == 'lamp')]", &root)[0].0,
+            "$.items[1]"
+        );
+        assert_eq!(query("$.items[?(@.tags)].name", &root).len(), 2);
+        assert_eq!(
+            query("$.meta[\"total count\"]", &root),
+            vec![("$.meta[\"total count\"]".to_string(), serde_json::json!(3))]
+        );
+        assert_eq!(query(".items | length", &root)[0].1, serde_json::json!(3));
+        assert_eq!(
+            query(". | keys", &root)[0].1,
+            serde_json::json!(["items", "meta"])
+        );
+        assert!(query("$.missing.path", &root).is_empty());
+
+        assert!(JsonQuery::parse("$.items[").is_err());
+        assert!(JsonQuery::parse(".a | sum").is_err());
+    }
+
+    #[test]
+    fn test_query_json_tool_bounds_output() {
+        let temp_dir = TempDir::new().unwrap();
+        let rows: Vec<Value> = (0..100)
+            .map(|i| serde_json::json!({"id": i, "blob": "x".repeat(1000)}))
+            .collect();
+        std::fs::write(
+            temp_dir.path().join("big.json"),
+            serde_json::to_string(&serde_json::json!({"rows": rows})).unwrap(),
+        )
+        .unwrap();
+        let mut tool_state = ToolState::new();
+        tool_state.working_directory = temp_dir.path().to_path_buf();
+        let state = Arc::new(Mutex::new(tool_state));
+        let mut tool = QueryJsonTool::new();
+
+        let args = ToolArgs::from_args(&["big.json", "$.rows[*].id", "--limit=3"]);
+        tool.validate_args(&args).unwrap();
+        let result = tool.execute(&args, &state).unwrap();
+        assert!(result.success, "{}", result.message);
+        assert!(result.message.starts_with("100 matches"));
+        assert!(result.message.contains("$.rows[2].id = 2"));
+        assert_eq!(result.data.unwrap()["matches"].as_array().unwrap().len(), 3);
+
+        let result = tool
+            .execute(&ToolArgs::from_args(&["big.json", "$.rows[0]"]), &state)
+            .unwrap();
+        assert!(result.message.contains("object with 2 keys"));
+        assert!(result.message.len() < 1000);
+
+        let result = tool
+            .execute(&ToolArgs::from_args(&["big.json", ".nope"]), &state)
+            .unwrap();
+        assert!(result.message.contains("top-level keys: rows"));
+    }
+}
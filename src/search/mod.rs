@@ -1,21 +1,50 @@
 //! Search tools for file discovery and content search
-
-use crate::core::{Tool, ToolArgs, ToolError, ToolResult};
-use crate::state::ToolState;
+//!
+//! Results are deterministic: directories are walked in file-name order, so the
+//! result caps keep the same entries on every run. `find_file` lists paths sorted,
+//! and `search_dir` lists files by match count (descending), then by path.
+
+use crate::core::{DangerLevel, Tool, ToolArgs, ToolCategory, ToolError, ToolResult};
+use crate::messages::MessageKey;
+use crate::profiling;
+use crate::state::{session_path, ToolState};
+use crate::stream::{ResultStream, StreamControl};
+use crate::utils::safe_regex;
+use crate::utils::suggest::path_not_found;
+use crate::utils::workspace::resolve_package;
 use anyhow::Result;
 use regex::Regex;
-use std::collections::HashMap;
-use std::fs;
+use std::collections::BTreeMap;
+use std::fmt::Write as _;
 use std::path::{Path, PathBuf};
 use std::sync::{Arc, Mutex};
 use walkdir::WalkDir;
 
 pub mod filtering;
-pub use filtering::ConfigurableFilter;
+pub mod json_query;
+pub use filtering::{ConfigurableFilter, SearchFilteringConfig};
+pub use json_query::QueryJsonTool;
 
 /// Maximum number of search results to return
 const MAX_SEARCH_RESULTS: usize = 1000;
 
+/// Files listed in a compact `search_dir` message
+const COMPACT_FILES: usize = 20;
+
+/// Distinct line numbers of serialized matches, e.g. `3, 7, 12`
+fn line_numbers(matches: &[serde_json::Value]) -> String {
+    let mut lines: Vec<u64> = matches
+        .iter()
+        .filter_map(|m| m["line_number"].as_u64())
+        .collect();
+    lines.dedup();
+    lines
+        .iter()
+        .map(|l| l.to_string())
+        .collect::<Vec<_>>()
+        .join(", ")
+}
+
 /// Search match result
 #[derive(Debug, Clone)]
 pub struct SearchMatch {
@@ -80,7 +109,7 @@ impl FindFileTool {
         }
 
         regex_pattern.push('$');
-        Regex::new(&regex_pattern).map_err(ToolError::from)
+        safe_regex::compile(&regex_pattern, false)
     }
 }
 
@@ -97,6 +126,14 @@ impl Tool for FindFileTool {
         "find_file <file_name> [<dir>]"
     }
 
+    fn category(&self) -> ToolCategory {
+        ToolCategory::Search
+    }
+
+    fn danger_level(&self) -> DangerLevel {
+        DangerLevel::ReadOnly
+    }
+
     fn validate_args(&self, args: &ToolArgs) -> Result<(), ToolError> {
         if args.is_empty() {
             return Err(ToolError::InvalidArgs {
@@ -106,18 +143,19 @@ impl Tool for FindFileTool {
         Ok(())
     }
 
-    fn execute(&mut self, args: &ToolArgs, _state: &Arc<Mutex<ToolState>>) -> Result<ToolResult> {
+    fn execute(&mut self, args: &ToolArgs, state: &Arc<Mutex<ToolState>>) -> Result<ToolResult> {
         let pattern = args.get_arg(0).unwrap();
         let default_dir = "./".to_string();
         let search_dir = args.get_arg(1).unwrap_or(&default_dir);
-        let search_path = Path::new(search_dir);
+        let search_path = session_path(state, search_dir);
+        let search_path = search_path.as_path();
 
         // Check if directory exists
         if !search_path.exists() {
-            return Ok(ToolResult::error(format!(
-                "Directory {} not found",
-                search_dir
-            )));
+            return Ok(path_not_found(
+                format!("Directory {} not found", search_dir),
+                search_path,
+            ));
         }
 
         if !search_path.is_dir() {
@@ -132,10 +170,14 @@ impl Tool for FindFileTool {
 
         // Search for matching files
         let mut matches = Vec::new();
-        let filter = ConfigurableFilter::new(None);
+        let filter = state
+            .lock()
+            .map_err(|e| anyhow::anyhow!("Failed to lock state: {}", e))?
+            .search_filter();
         let walker = WalkDir::new(search_path)
             .max_depth(100) // Reasonable depth limit
             .follow_links(false)
+            .sort_by_file_name()
             .into_iter()
             // Only apply filtering to directories (control descent). Always allow files through
             // Always allow the search root to avoid pruning the entire search tree when it
@@ -248,16 +290,17 @@ impl SearchFileTool {
         search_term: &str,
         file_path: &Path,
     ) -> Result<Vec<SearchMatch>, ToolError> {
-        let content = fs::read_to_string(file_path).map_err(|_| ToolError::FileNotFound {
-            path: file_path.to_string_lossy().to_string(),
-        })?;
+        let content =
+            profiling::read_to_string(file_path).map_err(|_| ToolError::FileNotFound {
+                path: file_path.to_string_lossy().to_string(),
+            })?;
 
         let mut matches = Vec::new();
         // Treat user input as a literal string by default to avoid parse errors
         // when the caller provides unescaped regex metacharacters. If callers
         // want real regex behavior in future, add an explicit flag.
         let escaped = regex::escape(search_term);
-        let regex = Regex::new(&escaped).map_err(ToolError::from)?;
+        let regex = safe_regex::compile(&escaped, false)?;
 
         for (line_num, line) in content.lines().enumerate() {
             for mat in regex.find_iter(line) {
@@ -293,6 +336,25 @@ impl Tool for SearchFileTool {
         "search_file <search_term> [<file>]"
     }
 
+    fn category(&self) -> ToolCategory {
+        ToolCategory::Search
+    }
+
+    fn danger_level(&self) -> DangerLevel {
+        DangerLevel::ReadOnly
+    }
+
+    fn compact_message(&self, result: &ToolResult) -> Option<String> {
+        let data = result.data.as_ref()?;
+        let matches = data["matches"].as_array()?;
+        Some(format!(
+            "{} matches in {} at lines {}",
+            matches.len(),
+            data["file"].as_str()?,
+            line_numbers(matches)
+        ))
+    }
+
     fn validate_args(&self, args: &ToolArgs) -> Result<(), ToolError> {
         if args.is_empty() {
             return Err(ToolError::InvalidArgs {
@@ -306,7 +368,7 @@ impl Tool for SearchFileTool {
         let search_term = args.get_arg(0).unwrap();
 
         let target_file = if let Some(file_arg) = args.get_arg(1) {
-            PathBuf::from(file_arg)
+            session_path(state, file_arg)
         } else {
             // Use current open file
             let state_guard = state
@@ -320,10 +382,10 @@ impl Tool for SearchFileTool {
 
         // Check if file exists
         if !target_file.exists() {
-            return Ok(ToolResult::error(format!(
-                "File not found: {}",
-                target_file.display()
-            )));
+            return Ok(path_not_found(
+                format!("File not found: {}", target_file.display()),
+                &target_file,
+            ));
         }
 
         // Search in the file
@@ -412,17 +474,19 @@ impl SearchDirTool {
         &self,
         search_term: &str,
         dir_path: &Path,
-    ) -> Result<HashMap<PathBuf, Vec<SearchMatch>>, ToolError> {
+        filter: &ConfigurableFilter,
+        stream: &ResultStream,
+    ) -> Result<BTreeMap<PathBuf, Vec<SearchMatch>>, ToolError> {
         // Treat user input as a literal string by default to avoid parse errors
         let escaped = regex::escape(search_term);
-        let regex = Regex::new(&escaped).map_err(ToolError::from)?;
-        let mut all_matches = HashMap::new();
+        let regex = safe_regex::compile(&escaped, false)?;
+        let mut all_matches = BTreeMap::new();
         let mut total_matches = 0;
 
-        let filter = ConfigurableFilter::new(None);
         let walker = WalkDir::new(dir_path)
             .max_depth(100)
             .follow_links(false)
+            .sort_by_file_name()
             .into_iter()
             // Only apply filtering to directories (control descent). Always allow files through
             // Always allow the search root to avoid pruning the entire search tree when it
@@ -454,7 +518,7 @@ impl SearchDirTool {
                         }
 
                         // Try to read file content
-                        if let Ok(content) = fs::read_to_string(path) {
+                        if let Ok(content) = profiling::read_to_string(path) {
                             let mut file_matches = Vec::new();
 
                             for (line_num, line) in content.lines().enumerate() {
@@ -479,7 +543,16 @@ impl SearchDirTool {
                             }
 
                             if !file_matches.is_empty() {
+                                // Stream each file's matches as soon as it is searched
+                                let control = if stream.is_active() {
+                                    stream_file_matches(stream, path, &file_matches)
+                                } else {
+                                    StreamControl::Continue
+                                };
                                 all_matches.insert(path.to_path_buf(), file_matches);
+                                if control == StreamControl::Stop {
+                                    break;
+                                }
                             }
                         }
                     }
@@ -492,6 +565,36 @@ impl SearchDirTool {
     }
 }
 
+/// Send the matches of one file as a chunk of a streaming `search_dir`
+fn stream_file_matches(
+    stream: &ResultStream,
+    path: &Path,
+    matches: &[SearchMatch],
+) -> StreamControl {
+    let mut content = format!("{} ({} matches)", path.display(), matches.len());
+    for m in matches {
+        let _ = write!(
+            content,
+            "\n  Line {}: {}",
+            m.line_number,
+            m.line_content.trim()
+        );
+    }
+    stream.send_with_data(
+        content,
+        serde_json::json!({
+            "file": path.to_string_lossy(),
+            "match_count": matches.len(),
+            "matches": matches.iter().map(|m| serde_json::json!({
+                "line_number": m.line_number,
+                "line_content": m.line_content,
+                "match_start": m.match_start,
+                "match_end": m.match_end
+            })).collect::<Vec<_>>()
+        }),
+    )
+}
+
 impl Tool for SearchDirTool {
     fn name(&self) -> &str {
         &self.name
@@ -502,7 +605,38 @@ impl Tool for SearchDirTool {
     }
 
     fn signature(&self) -> &str {
-        "search_dir <search_term> [<dir>]"
+        "search_dir <search_term> [<dir>] [--package=<name>]"
+    }
+
+    fn category(&self) -> ToolCategory {
+        ToolCategory::Search
+    }
+
+    fn danger_level(&self) -> DangerLevel {
+        DangerLevel::ReadOnly
+    }
+
+    fn compact_message(&self, result: &ToolResult) -> Option<String> {
+        let data = result.data.as_ref()?;
+        let files = data["files"].as_array()?;
+        let mut message = format!("{} matches in {} files", data["total_matches"], files.len());
+        for file in files.iter().take(COMPACT_FILES) {
+            let matches = file["matches"].as_array()?;
+            let _ = write!(
+                message,
+                "\n{}: {}",
+                file["file"].as_str()?,
+                line_numbers(matches)
+            );
+            let unlisted = file["match_count"].as_u64()? as usize - matches.len();
+            if unlisted > 0 {
+                let _ = write!(message, " (+{} more)", unlisted);
+            }
+        }
+        if files.len() > COMPACT_FILES {
+            let _ = write!(message, "\n... {} more files", files.len() - COMPACT_FILES);
+        }
+        Some(message)
     }
 
     fn validate_args(&self, args: &ToolArgs) -> Result<(), ToolError> {
@@ -514,18 +648,46 @@ impl Tool for SearchDirTool {
         Ok(())
     }
 
-    fn execute(&mut self, args: &ToolArgs, _state: &Arc<Mutex<ToolState>>) -> Result<ToolResult> {
+    fn execute(&mut self, args: &ToolArgs, state: &Arc<Mutex<ToolState>>) -> Result<ToolResult> {
         let search_term = args.get_arg(0).unwrap();
         let default_dir = "./".to_string();
-        let search_dir = args.get_arg(1).unwrap_or(&default_dir);
-        let search_path = Path::new(search_dir);
+        let (style, filter, stream, working_dir) = {
+            let mut state = state
+                .lock()
+                .map_err(|e| anyhow::anyhow!("Failed to lock state: {}", e))?;
+            (
+                state.message_style.clone(),
+                state.search_filter(),
+                state.stream.clone(),
+                state.working_directory.clone(),
+            )
+        };
+        // A package scopes the search to its directory; `dir` is then relative to it
+        let search_dir = match args.get_named_arg("package") {
+            Some(package) => match resolve_package(&working_dir, package) {
+                Ok((workspace, member)) => {
+                    let base = member.directory(&workspace.root);
+                    match args.get_arg(1) {
+                        Some(dir) => base.join(dir),
+                        None => base,
+                    }
+                    .display()
+                    .to_string()
+                }
+                Err(result) => return Ok(result),
+            },
+            None => args.get_arg(1).unwrap_or(&default_dir).clone(),
+        };
+        let search_dir = &search_dir;
+        let search_path = session_path(state, search_dir);
+        let search_path = search_path.as_path();
 
         // Check if directory exists
         if !search_path.exists() {
-            return Ok(ToolResult::error(format!(
-                "Directory {} not found",
-                search_dir
-            )));
+            return Ok(path_not_found(
+                format!("Directory {} not found", search_dir),
+                search_path,
+            ));
         }
 
         if !search_path.is_dir() {
@@ -536,13 +698,12 @@ impl Tool for SearchDirTool {
         }
 
         // Search in directory
-        let matches = self.search_in_directory(search_term, search_path)?;
+        let matches = self.search_in_directory(search_term, search_path, &filter, &stream)?;
 
         if matches.is_empty() {
-            return Ok(ToolResult::success(format!(
-                "No matches found for \"{}\" in {}",
-                search_term,
-                search_path.display()
+            return Ok(ToolResult::success(style.render(
+                MessageKey::SearchNoMatches,
+                &[("term", search_term), ("dir", &search_path.display())],
             )));
         }
 
@@ -550,24 +711,29 @@ impl Tool for SearchDirTool {
         let total_matches: usize = matches.values().map(|v| v.len()).sum();
 
         // Format results
-        let mut result_text = format!(
-            "Found {} matches for \"{}\" in {} across {} files:\n\n",
-            total_matches,
-            search_term,
-            search_path.display(),
-            matches.len()
+        let mut result_text = style.render(
+            MessageKey::SearchSummary,
+            &[
+                ("count", &total_matches),
+                ("term", search_term),
+                ("dir", &search_path.display()),
+                ("files", &matches.len()),
+            ],
         );
+        result_text.push_str("\n\n");
 
-        // Sort files by number of matches (descending)
+        // Sort files by number of matches (descending), then by path
         let mut file_matches: Vec<_> = matches.iter().collect();
-        file_matches.sort_by(|a, b| b.1.len().cmp(&a.1.len()));
+        file_matches.sort_by(|a, b| b.1.len().cmp(&a.1.len()).then_with(|| a.0.cmp(b.0)));
 
         for (file_path, file_matches) in file_matches.iter().take(20) {
             // Limit to top 20 files
-            result_text.push_str(&format!(
-                "📁 {} ({} matches):\n",
-                file_path.display(),
-                file_matches.len()
+            result_text.push_str(&style.render_line(
+                MessageKey::SearchFileHeading,
+                &[
+                    ("path", &file_path.display()),
+                    ("count", &file_matches.len()),
+                ],
             ));
 
             for (i, m) in file_matches.iter().take(5).enumerate() {
@@ -596,6 +762,9 @@ impl Tool for SearchDirTool {
                 matches.len() - 20
             ));
         }
+        if stream.is_stopped() {
+            result_text.push_str("Search stopped early by the stream consumer\n");
+        }
 
         // Prepare structured data
         let mut files_data = Vec::new();
@@ -638,6 +807,10 @@ impl Tool for SearchDirTool {
                     "type": "string",
                     "description": "The directory to search in (if not provided, searches in the current directory)",
                     "default": "./"
+                },
+                "package": {
+                    "type": "string",
+                    "description": "Workspace package (name or directory) to search in; dir is then relative to the package"
                 }
             },
             "required": ["search_term"]
@@ -648,6 +821,7 @@ impl Tool for SearchDirTool {
 #[cfg(test)]
 mod tests {
     use super::*;
+    use std::fs;
     use std::io::Write;
     use tempfile::TempDir;
 
@@ -720,6 +894,39 @@ mod tests {
         assert!(result.message.contains("2 files"));
     }
 
+    #[test]
+    fn test_search_dir_package() {
+        let temp_dir = TempDir::new().unwrap();
+        create_test_file(
+            &temp_dir,
+            "package.json",
+            r#"{"workspaces": ["packages/*"]}"#,
+        );
+        create_test_file(&temp_dir, "packages/api/package.json", r#"{"name": "api"}"#);
+        create_test_file(&temp_dir, "packages/api/src/index.ts", "const needle = 1;");
+        create_test_file(&temp_dir, "packages/web/src/index.ts", "const needle = 2;");
+
+        let mut tool = SearchDirTool::new();
+        let mut tool_state = ToolState::new();
+        tool_state.set_working_directory(temp_dir.path());
+        let state = Arc::new(Mutex::new(tool_state));
+
+        let args = ToolArgs::from_args(&["needle", "src", "--package=api"]);
+        let result = tool.execute(&args, &state).unwrap();
+        assert!(result.success, "{}", result.message);
+        let data = result.data.unwrap();
+        assert_eq!(data["total_matches"], 1);
+        assert!(data["search_dir"]
+            .as_str()
+            .unwrap()
+            .ends_with("packages/api/src"));
+
+        let args = ToolArgs::from_args(&["needle", "--package=ap"]);
+        let result = tool.execute(&args, &state).unwrap();
+        assert!(!result.success);
+        assert!(result.message.ends_with("Did you mean: api?"));
+    }
+
     #[test]
     fn test_glob_to_regex() {
         let regex = FindFileTool::glob_to_regex("*.rs").unwrap();
@@ -771,4 +978,34 @@ mod tests {
         assert!(result.message.contains("file.txt"));
         assert!(!result.message.contains("binary.exe"));
     }
+
+    #[test]
+    fn test_search_dir_orders_deterministically() {
+        let temp_dir = TempDir::new().unwrap();
+        create_test_file(&temp_dir, "c.txt", "needle");
+        create_test_file(&temp_dir, "a.txt", "needle");
+        create_test_file(&temp_dir, "sub/b.txt", "needle\nneedle");
+        create_test_file(&temp_dir, "b.txt", "needle");
+
+        let mut tool = SearchDirTool::new();
+        let state = Arc::new(Mutex::new(ToolState::new()));
+        let args = ToolArgs::from_args(&["needle", temp_dir.path().to_str().unwrap()]);
+        let first = tool.execute(&args, &state).unwrap();
+        let second = tool.execute(&args, &state).unwrap();
+        assert_eq!(first.message, second.message);
+        assert_eq!(first.data, second.data);
+
+        // Most matches first, ties broken by path
+        let order: Vec<usize> = ["sub/b.txt", "a.txt", "b.txt", "c.txt"]
+            .iter()
+            .map(|name| {
+                let path = temp_dir.path().join(name);
+                first
+                    .message
+                    .find(&format!("📁 {} ", path.display()))
+                    .unwrap()
+            })
+            .collect();
+        assert!(order.windows(2).all(|w| w[0] < w[1]), "{}", first.message);
+    }
 }
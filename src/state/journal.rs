@@ -0,0 +1,677 @@
+//! Crash-safe journal of mutating file operations
+//!
+//! Every mutating operation performed through `editing::writer` is appended to
+//! `.cats/journal/<session>.jsonl` before it touches the disk (an `intent` record with
+//! before/after hashes, the previous content and a unified patch) and again once it has
+//! completed (`applied`). Each tool execution forms a batch that is committed when the
+//! tool returns, so after a crash [`recover`] can tell which batches were cut short and
+//! optionally roll them back.
+
+use crate::editing::diff::unified_diff;
+use serde::{Deserialize, Serialize};
+use sha2::{Digest, Sha256};
+use std::collections::BTreeMap;
+use std::fs::{self, OpenOptions};
+use std::io::{self, Write};
+use std::path::{Path, PathBuf};
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::time::{SystemTime, UNIX_EPOCH};
+
+/// Journal directory relative to the working directory
+pub const JOURNAL_DIR: &str = ".cats/journal";
+
+/// Distinguishes journals of several sessions started by one process in the same second
+static SESSION_COUNTER: AtomicU64 = AtomicU64::new(0);
+
+/// Kind of mutating operation
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum OperationKind {
+    Write,
+    Delete,
+    DeleteDir,
+    Move,
+    Copy,
+    CreateDir,
+    Symlink,
+}
+
+impl OperationKind {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            OperationKind::Write => "write",
+            OperationKind::Delete => "delete",
+            OperationKind::DeleteDir => "delete_dir",
+            OperationKind::Move => "move",
+            OperationKind::Copy => "copy",
+            OperationKind::CreateDir => "create_dir",
+            OperationKind::Symlink => "symlink",
+        }
+    }
+}
+
+/// A mutating operation as recorded before it is performed
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct Operation {
+    pub kind: OperationKind,
+    pub path: PathBuf,
+    /// Destination of moves and copies, or the target of a symlink
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub to: Option<PathBuf>,
+    /// SHA-256 of the file before the operation (`None` if it did not exist)
+    pub before_hash: Option<String>,
+    /// SHA-256 of the file after the operation (`None` if it will not exist)
+    pub after_hash: Option<String>,
+    /// Previous text content, kept so the operation can be rolled back
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub before: Option<String>,
+    /// Unified diff of the change for text files
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub patch: Option<String>,
+}
+
+/// SHA-256 of `bytes` as lowercase hex
+pub fn hash_bytes(bytes: &[u8]) -> String {
+    format!("{:x}", Sha256::digest(bytes))
+}
+
+/// SHA-256 of the file at `path`, or `None` if it cannot be read
+pub fn hash_file(path: &Path) -> Option<String> {
+    fs::read(path).ok().map(|bytes| hash_bytes(&bytes))
+}
+
+fn absolute(path: &Path) -> PathBuf {
+    std::path::absolute(path).unwrap_or_else(|_| path.to_path_buf())
+}
+
+impl Operation {
+    fn new(kind: OperationKind, path: &Path) -> Self {
+        Self {
+            kind,
+            path: absolute(path),
+            to: None,
+            before_hash: None,
+            after_hash: None,
+            before: None,
+            patch: None,
+        }
+    }
+
+    /// Capture the current state of `path` before an operation changes it
+    fn with_before(mut self) -> Self {
+        if let Ok(bytes) = fs::read(&self.path) {
+            self.before_hash = Some(hash_bytes(&bytes));
+            self.before = String::from_utf8(bytes).ok();
+        }
+        self
+    }
+
+    /// Writing `contents` to `path` (creating or overwriting it)
+    pub fn write(path: &Path, contents: &[u8]) -> Self {
+        let mut op = Self::new(OperationKind::Write, path).with_before();
+        op.after_hash = Some(hash_bytes(contents));
+        if let Ok(after) = std::str::from_utf8(contents) {
+            let before = op.before.as_deref().unwrap_or("");
+            op.patch = Some(unified_diff(before, after, &path.to_string_lossy()));
+        }
+        op
+    }
+
+    /// Deleting the file at `path`
+    pub fn delete(path: &Path) -> Self {
+        Self::new(OperationKind::Delete, path).with_before()
+    }
+
+    /// Deleting the directory at `path`
+    pub fn delete_dir(path: &Path) -> Self {
+        Self::new(OperationKind::DeleteDir, path)
+    }
+
+    /// Moving `from` to `to`
+    pub fn rename(from: &Path, to: &Path) -> Self {
+        let mut op = Self::new(OperationKind::Move, from);
+        op.to = Some(absolute(to));
+        op.before_hash = hash_file(from);
+        op.after_hash = op.before_hash.clone();
+        op
+    }
+
+    /// Copying `from` to the new path `to`
+    pub fn copy(from: &Path, to: &Path) -> Self {
+        let mut op = Self::new(OperationKind::Copy, from);
+        op.to = Some(absolute(to));
+        op.after_hash = hash_file(from);
+        op
+    }
+
+    /// Creating the directory `path`
+    pub fn create_dir(path: &Path) -> Self {
+        Self::new(OperationKind::CreateDir, path)
+    }
+
+    /// Creating a symbolic link at `link` pointing to `target` (kept as given)
+    pub fn symlink(target: &Path, link: &Path) -> Self {
+        let mut op = Self::new(OperationKind::Symlink, link);
+        op.to = Some(target.to_path_buf());
+        op
+    }
+}
+
+/// Event stored in a journal record
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(tag = "event", rename_all = "snake_case")]
+pub enum JournalEvent {
+    BatchStart { label: String },
+    Intent { op: Operation },
+    Applied { intent: u64 },
+    Failed { intent: u64, error: String },
+    BatchCommit,
+    RolledBack,
+}
+
+/// One line of a journal file
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct JournalRecord {
+    pub seq: u64,
+    /// Seconds since the Unix epoch
+    pub timestamp: u64,
+    /// Sequence number of the batch's `batch_start` record
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub batch: Option<u64>,
+    #[serde(flatten)]
+    pub event: JournalEvent,
+}
+
+#[derive(Debug, Clone)]
+struct PendingBatch {
+    label: String,
+    /// Set once the `batch_start` record has been written
+    id: Option<u64>,
+}
+
+/// Append-only journal for one session
+#[derive(Debug, Clone)]
+pub struct Journal {
+    dir: PathBuf,
+    session: String,
+    next_seq: u64,
+    batch: Option<PendingBatch>,
+}
+
+fn unix_now() -> u64 {
+    SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .map(|d| d.as_secs())
+        .unwrap_or(0)
+}
+
+impl Journal {
+    /// Create a journal writing to `dir`; nothing is written until the first operation
+    pub fn new(dir: PathBuf) -> Self {
+        let session = format!(
+            "{}-{}-{}",
+            unix_now(),
+            std::process::id(),
+            SESSION_COUNTER.fetch_add(1, Ordering::Relaxed)
+        );
+        Self {
+            dir,
+            session,
+            next_seq: 0,
+            batch: None,
+        }
+    }
+
+    /// Directory holding the journal files
+    pub fn dir(&self) -> &Path {
+        &self.dir
+    }
+
+    /// Path of this session's journal file
+    pub fn path(&self) -> PathBuf {
+        self.dir.join(format!("{}.jsonl", self.session))
+    }
+
+    fn append(&mut self, batch: Option<u64>, event: JournalEvent) -> io::Result<u64> {
+        let record = JournalRecord {
+            seq: self.next_seq,
+            timestamp: unix_now(),
+            batch,
+            event,
+        };
+        fs::create_dir_all(&self.dir)?;
+        let mut file = OpenOptions::new()
+            .create(true)
+            .append(true)
+            .open(self.path())?;
+        writeln!(file, "{}", serde_json::to_string(&record)?)?;
+        // The record must be durable before the operation it describes starts
+        file.sync_data()?;
+        self.next_seq += 1;
+        Ok(record.seq)
+    }
+
+    /// Start a batch; its start record is written lazily with the first operation
+    pub fn begin_batch(&mut self, label: &str) {
+        self.batch = Some(PendingBatch {
+            label: label.to_string(),
+            id: None,
+        });
+    }
+
+    /// Commit the current batch, if it recorded any operation
+    pub fn commit_batch(&mut self) -> io::Result<()> {
+        if let Some(PendingBatch { id: Some(id), .. }) = self.batch.take() {
+            self.append(Some(id), JournalEvent::BatchCommit)?;
+        }
+        Ok(())
+    }
+
+    fn current_batch(&mut self) -> io::Result<Option<u64>> {
+        let label = match &self.batch {
+            Some(PendingBatch { id: Some(id), .. }) => return Ok(Some(*id)),
+            Some(PendingBatch { label, id: None }) => label.clone(),
+            None => return Ok(None),
+        };
+        let id = self.append(None, JournalEvent::BatchStart { label })?;
+        if let Some(batch) = self.batch.as_mut() {
+            batch.id = Some(id);
+        }
+        Ok(Some(id))
+    }
+
+    /// Record an operation about to be performed, returning its sequence number
+    pub fn record_intent(&mut self, op: &Operation) -> io::Result<u64> {
+        let batch = self.current_batch()?;
+        self.append(batch, JournalEvent::Intent { op: op.clone() })
+    }
+
+    /// Record that the operation `intent` completed
+    pub fn record_applied(&mut self, intent: u64) -> io::Result<()> {
+        let batch = self.batch.as_ref().and_then(|b| b.id);
+        self.append(batch, JournalEvent::Applied { intent })
+            .map(|_| ())
+    }
+
+    /// Record that the operation `intent` failed without completing
+    pub fn record_failed(&mut self, intent: u64, error: &str) -> io::Result<()> {
+        let batch = self.batch.as_ref().and_then(|b| b.id);
+        self.append(
+            batch,
+            JournalEvent::Failed {
+                intent,
+                error: error.to_string(),
+            },
+        )
+        .map(|_| ())
+    }
+}
+
+/// Outcome of a journaled operation as reconstructed from the journal
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "snake_case")]
+pub enum OperationStatus {
+    Applied,
+    Failed,
+    /// The intent was recorded but the process stopped before the outcome
+    Interrupted,
+}
+
+/// A recorded operation and what recovery did with it
+#[derive(Debug, Clone, Serialize)]
+pub struct OperationReport {
+    pub seq: u64,
+    pub op: Operation,
+    pub status: OperationStatus,
+    /// Result of rolling the operation back, when attempted
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub rollback: Option<String>,
+}
+
+/// A batch of operations from one tool execution
+#[derive(Debug, Clone, Serialize)]
+pub struct BatchReport {
+    pub session: String,
+    /// Sequence number of the batch start, `None` for operations outside a batch
+    pub batch: Option<u64>,
+    pub label: String,
+    pub complete: bool,
+    pub rolled_back: bool,
+    pub operations: Vec<OperationReport>,
+}
+
+/// Summary of all journals in a directory
+#[derive(Debug, Clone, Default, Serialize)]
+pub struct RecoveryReport {
+    pub journal_dir: PathBuf,
+    pub sessions: usize,
+    pub batches: Vec<BatchReport>,
+    /// Journal files removed because all of their batches were complete
+    pub pruned: Vec<PathBuf>,
+}
+
+impl RecoveryReport {
+    /// Batches cut short by a crash and not yet rolled back
+    pub fn incomplete(&self) -> impl Iterator<Item = &BatchReport> {
+        self.batches
+            .iter()
+            .filter(|b| !b.complete && !b.rolled_back)
+    }
+
+    /// Human-readable summary
+    pub fn render(&self) -> String {
+        let incomplete = self.incomplete().count();
+        let mut lines = vec![format!(
+            "Journal {}: {} session(s), {} batch(es), {} incomplete",
+            self.journal_dir.display(),
+            self.sessions,
+            self.batches.len(),
+            incomplete
+        )];
+        for batch in &self.batches {
+            let state = if batch.rolled_back {
+                "rolled back"
+            } else if batch.complete {
+                "complete"
+            } else {
+                "INCOMPLETE"
+            };
+            lines.push(format!(
+                "[{}] session {} batch {} ({}, {} operation(s))",
+                state,
+                batch.session,
+                batch
+                    .batch
+                    .map(|b| format!("#{}", b))
+                    .unwrap_or_else(|| "-".to_string()),
+                batch.label,
+                batch.operations.len()
+            ));
+            if batch.complete && !batch.rolled_back {
+                continue;
+            }
+            for report in &batch.operations {
+                let target = match &report.op.to {
+                    Some(to) => format!("{} -> {}", report.op.path.display(), to.display()),
+                    None => report.op.path.display().to_string(),
+                };
+                let status = match report.status {
+                    OperationStatus::Applied => "applied",
+                    OperationStatus::Failed => "failed",
+                    OperationStatus::Interrupted => "interrupted",
+                };
+                let mut line = format!(
+                    "  {:<11} {:<10} {}",
+                    status,
+                    report.op.kind.as_str(),
+                    target
+                );
+                if let Some(rollback) = &report.rollback {
+                    line.push_str(&format!(" [{}]", rollback));
+                }
+                lines.push(line);
+            }
+        }
+        for path in &self.pruned {
+            lines.push(format!("Pruned {}", path.display()));
+        }
+        lines.join("\n")
+    }
+}
+
+/// Options for [`recover`]
+#[derive(Debug, Clone, Copy, Default)]
+pub struct RecoverOptions {
+    /// Roll back the operations of incomplete batches
+    pub rollback: bool,
+    /// Roll back even when a file changed after the journaled operation
+    pub force: bool,
+    /// Delete journal files whose batches are all complete or rolled back
+    pub prune: bool,
+}
+
+fn read_records(path: &Path) -> io::Result<Vec<JournalRecord>> {
+    // A crash can leave a torn final line; everything before it is still valid
+    Ok(fs::read_to_string(path)?
+        .lines()
+        .filter_map(|line| serde_json::from_str(line).ok())
+        .collect())
+}
+
+fn build_batches(session: &str, records: &[JournalRecord]) -> Vec<BatchReport> {
+    let mut batches: BTreeMap<Option<u64>, BatchReport> = BTreeMap::new();
+    let mut outcomes: BTreeMap<u64, OperationStatus> = BTreeMap::new();
+    let new_batch = |batches: &mut BTreeMap<Option<u64>, BatchReport>, id: Option<u64>| {
+        batches.entry(id).or_insert_with(|| BatchReport {
+            session: session.to_string(),
+            batch: id,
+            label: "(no batch)".to_string(),
+            // Operations outside a batch have no commit to wait for
+            complete: id.is_none(),
+            rolled_back: false,
+            operations: Vec::new(),
+        });
+    };
+
+    for record in records {
+        match &record.event {
+            JournalEvent::BatchStart { label } => {
+                new_batch(&mut batches, Some(record.seq));
+                if let Some(batch) = batches.get_mut(&Some(record.seq)) {
+                    batch.label = label.clone();
+                }
+            }
+            JournalEvent::Intent { op } => {
+                new_batch(&mut batches, record.batch);
+                if let Some(batch) = batches.get_mut(&record.batch) {
+                    batch.operations.push(OperationReport {
+                        seq: record.seq,
+                        op: op.clone(),
+                        status: OperationStatus::Interrupted,
+                        rollback: None,
+                    });
+                }
+            }
+            JournalEvent::Applied { intent } => {
+                outcomes.insert(*intent, OperationStatus::Applied);
+            }
+            JournalEvent::Failed { intent, .. } => {
+                outcomes.insert(*intent, OperationStatus::Failed);
+            }
+            JournalEvent::BatchCommit => {
+                if let Some(batch) = batches.get_mut(&record.batch) {
+                    batch.complete = true;
+                }
+            }
+            JournalEvent::RolledBack => {
+                if let Some(batch) = batches.get_mut(&record.batch) {
+                    batch.rolled_back = true;
+                }
+            }
+        }
+    }
+
+    let mut batches: Vec<BatchReport> = batches.into_values().collect();
+    for batch in &mut batches {
+        for report in &mut batch.operations {
+            if let Some(status) = outcomes.get(&report.seq) {
+                report.status = *status;
+            }
+        }
+    }
+    batches
+}
+
+/// Undo one operation, returning a description of what happened
+fn rollback_operation(op: &Operation, force: bool) -> String {
+    let current_hash = hash_file(&op.path);
+    let result = match op.kind {
+        OperationKind::Write => {
+            if current_hash == op.before_hash {
+                return "unchanged".to_string();
+            }
+            if current_hash != op.after_hash && !force {
+                return "skipped: file changed since the operation (use --force)".to_string();
+            }
+            match (&op.before_hash, &op.before) {
+                (None, _) => fs::remove_file(&op.path).map(|_| "removed created file"),
+                (Some(_), Some(before)) => {
+                    fs::write(&op.path, before).map(|_| "restored previous content")
+                }
+                (Some(_), None) => return "skipped: previous content not recorded".to_string(),
+            }
+        }
+        OperationKind::Delete => match (&op.before, op.path.exists()) {
+            (_, true) => return "unchanged".to_string(),
+            (Some(before), false) => fs::write(&op.path, before).map(|_| "restored deleted file"),
+            (None, false) => return "skipped: previous content not recorded".to_string(),
+        },
+        OperationKind::DeleteDir => {
+            return "skipped: deleted directories cannot be restored".to_string()
+        }
+        OperationKind::Move => match &op.to {
+            Some(to) if to.exists() && !op.path.exists() => {
+                fs::rename(to, &op.path).map(|_| "moved back")
+            }
+            _ => return "unchanged".to_string(),
+        },
+        OperationKind::Copy => match &op.to {
+            Some(to) if to.is_dir() => fs::remove_dir_all(to).map(|_| "removed copy"),
+            Some(to) if to.exists() => {
+                if hash_file(to) != op.after_hash && !force {
+                    return "skipped: copy changed since the operation (use --force)".to_string();
+                }
+                fs::remove_file(to).map(|_| "removed copy")
+            }
+            _ => return "unchanged".to_string(),
+        },
+        OperationKind::CreateDir => {
+            if !op.path.is_dir() {
+                return "unchanged".to_string();
+            }
+            fs::remove_dir(&op.path).map(|_| "removed directory")
+        }
+        OperationKind::Symlink => match fs::symlink_metadata(&op.path) {
+            Ok(meta) if meta.file_type().is_symlink() => {
+                // Windows directory links are removed like directories
+                fs::remove_file(&op.path)
+                    .or_else(|_| fs::remove_dir(&op.path))
+                    .map(|_| "removed link")
+            }
+            _ => return "unchanged".to_string(),
+        },
+    };
+    match result {
+        Ok(message) => message.to_string(),
+        Err(e) => format!("failed: {}", e),
+    }
+}
+
+/// Inspect the journals in `journal_dir` and optionally roll back incomplete batches
+pub fn recover(journal_dir: &Path, options: RecoverOptions) -> io::Result<RecoveryReport> {
+    let mut report = RecoveryReport {
+        journal_dir: journal_dir.to_path_buf(),
+        ..Default::default()
+    };
+    if !journal_dir.is_dir() {
+        return Ok(report);
+    }
+
+    let mut files: Vec<PathBuf> = fs::read_dir(journal_dir)?
+        .filter_map(|entry| entry.ok().map(|e| e.path()))
+        .filter(|path| path.extension().and_then(|e| e.to_str()) == Some("jsonl"))
+        .collect();
+    files.sort();
+
+    for file in files {
+        let session = file
+            .file_stem()
+            .map(|s| s.to_string_lossy().to_string())
+            .unwrap_or_default();
+        let records = read_records(&file)?;
+        let mut batches = build_batches(&session, &records);
+        report.sessions += 1;
+
+        if options.rollback {
+            let first_seq = records.iter().map(|r| r.seq + 1).max().unwrap_or(0);
+            let incomplete = batches.iter_mut().filter(|b| !b.complete && !b.rolled_back);
+            for (seq, batch) in (first_seq..).zip(incomplete) {
+                for op in batch.operations.iter_mut().rev() {
+                    op.rollback = Some(match op.status {
+                        OperationStatus::Failed => "unchanged".to_string(),
+                        _ => rollback_operation(&op.op, options.force),
+                    });
+                }
+                let record = JournalRecord {
+                    seq,
+                    timestamp: unix_now(),
+                    batch: batch.batch,
+                    event: JournalEvent::RolledBack,
+                };
+                let mut journal = OpenOptions::new().append(true).open(&file)?;
+                writeln!(journal, "{}", serde_json::to_string(&record)?)?;
+                batch.rolled_back = true;
+            }
+        }
+
+        if options.prune && batches.iter().all(|b| b.complete || b.rolled_back) {
+            fs::remove_file(&file)?;
+            report.pruned.push(file.clone());
+        }
+        report.batches.extend(batches);
+    }
+
+    Ok(report)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use tempfile::TempDir;
+
+    #[test]
+    fn test_recover_rolls_back_incomplete_batch() {
+        let temp_dir = TempDir::new().unwrap();
+        let journal_dir = temp_dir.path().join(JOURNAL_DIR);
+        let edited = temp_dir.path().join("a.txt");
+        let created = temp_dir.path().join("b.txt");
+        fs::write(&edited, "old\n").unwrap();
+
+        let mut journal = Journal::new(journal_dir.clone());
+
+        // A committed batch is left alone
+        journal.begin_batch("create_file");
+        let op = Operation::write(&created, b"new file\n");
+        let intent = journal.record_intent(&op).unwrap();
+        fs::write(&created, "new file\n").unwrap();
+        journal.record_applied(intent).unwrap();
+        journal.commit_batch().unwrap();
+
+        // A batch interrupted after its first write
+        journal.begin_batch("replace_text");
+        let op = Operation::write(&edited, b"new\n");
+        assert!(op.patch.as_ref().unwrap().contains("-old\n+new\n"));
+        let intent = journal.record_intent(&op).unwrap();
+        fs::write(&edited, "new\n").unwrap();
+        journal.record_applied(intent).unwrap();
+
+        let report = recover(&journal_dir, RecoverOptions::default()).unwrap();
+        assert_eq!(report.batches.len(), 2);
+        assert_eq!(report.incomplete().count(), 1);
+        assert!(report.render().contains("[INCOMPLETE]"));
+
+        let options = RecoverOptions {
+            rollback: true,
+            prune: true,
+            ..Default::default()
+        };
+        let report = recover(&journal_dir, options).unwrap();
+        assert_eq!(fs::read_to_string(&edited).unwrap(), "old\n");
+        assert_eq!(fs::read_to_string(&created).unwrap(), "new file\n");
+        assert_eq!(
+            report.batches[1].operations[0].rollback.as_deref(),
+            Some("restored previous content")
+        );
+        assert_eq!(report.pruned.len(), 1);
+    }
+}
@@ -2,24 +2,147 @@
 //!
 //! Maintains context of currently open files, cursor positions, and session history
 
-use crate::core::{Tool, ToolArgs, ToolError, ToolResult};
+use crate::core::{DangerLevel, Tool, ToolArgs, ToolError, ToolResult};
+use crate::editing::artifacts::TouchedPath;
+use crate::editing::protection::ProtectedPaths;
+use crate::events::{ChangeKind, EventSender};
+use crate::messages::MessageStyle;
+use crate::search::{ConfigurableFilter, SearchFilteringConfig};
+use crate::stream::ResultStream;
 use anyhow::Result;
 use serde::{Deserialize, Serialize};
-use std::collections::HashMap;
-use std::path::PathBuf;
-use std::sync::{Arc, Mutex};
+use std::collections::BTreeMap;
+use std::fmt::{self, Write as _};
+use std::ops::Range;
+use std::path::{Path, PathBuf};
+use std::sync::{Arc, Mutex, PoisonError};
+
+pub mod journal;
+pub mod provenance;
+pub mod repetition;
+pub mod scratch;
+pub mod undo;
+
+use journal::{Journal, JOURNAL_DIR};
+use repetition::RecentCalls;
+pub use scratch::CreateScratchDirTool;
+use scratch::ScratchDir;
+pub use undo::{EditStep, FileEdit, RedoTool, UndoTool};
+
+/// `path` as an absolute path with symlinks, `.` and `..` resolved, so that every
+/// spelling of a file (`src/lib.rs`, `./src/lib.rs`, `/repo/src/lib.rs`) maps to one
+/// key. Relative paths resolve against the process directory; tools pass their path
+/// arguments through [`session_path`] first. A path that does not exist keeps its file
+/// name under its resolved parent.
+pub fn canonical_path(path: &Path) -> PathBuf {
+    if let Ok(canonical) = std::fs::canonicalize(path) {
+        return canonical;
+    }
+    let absolute = std::path::absolute(path).unwrap_or_else(|_| path.to_path_buf());
+    match (absolute.parent(), absolute.file_name()) {
+        (Some(parent), Some(name)) => match std::fs::canonicalize(parent) {
+            Ok(parent) => parent.join(name),
+            Err(_) => absolute,
+        },
+        _ => absolute,
+    }
+}
+
+/// [`ToolState::session_path`] of the session behind `state`
+pub fn session_path(state: &Arc<Mutex<ToolState>>, path: impl AsRef<Path>) -> PathBuf {
+    state
+        .lock()
+        .unwrap_or_else(PoisonError::into_inner)
+        .session_path(path)
+}
+
+/// Resolve `path` to an absolute path with `.`/`..` removed and existing symlinks followed
+pub fn resolve_path(path: &Path) -> PathBuf {
+    let absolute = std::path::absolute(path).unwrap_or_else(|_| path.to_path_buf());
+    let mut lexical = PathBuf::new();
+    for component in absolute.components() {
+        match component {
+            std::path::Component::CurDir => {}
+            std::path::Component::ParentDir => {
+                lexical.pop();
+            }
+            other => lexical.push(other),
+        }
+    }
+    // Follow links in the longest existing prefix so sandbox checks cannot be escaped
+    let mut existing = lexical.as_path();
+    let mut rest = Vec::new();
+    while !existing.exists() {
+        match (existing.parent(), existing.file_name()) {
+            (Some(parent), Some(name)) => {
+                rest.push(name.to_os_string());
+                existing = parent;
+            }
+            _ => return lexical,
+        }
+    }
+    let mut resolved = existing
+        .canonicalize()
+        .unwrap_or_else(|_| existing.to_path_buf());
+    resolved.extend(rest.iter().rev());
+    resolved
+}
 
 /// Represents the state of the tool system
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct ToolState {
-    /// Currently open files with their windowed views
-    pub open_files: HashMap<PathBuf, FileState>,
-    /// The currently active file
+    /// Currently open files with their windowed views, keyed by canonical path
+    pub open_files: BTreeMap<PathBuf, FileState>,
+    /// The currently active file, as a canonical path
     pub current_file: Option<PathBuf>,
     /// Session history for undo/redo
     pub history: Vec<StateSnapshot>,
+    /// Snapshots taken out of the history by `undo`, most recently undone last
+    #[serde(default)]
+    pub redo_stack: Vec<StateSnapshot>,
+    /// File edits made since the last snapshot, attached to the next one
+    #[serde(skip)]
+    pub pending_edits: Vec<FileEdit>,
+    /// What the agent said it did when it called `submit`; describes the change in
+    /// exported patches and pull requests
+    #[serde(default)]
+    pub submit_summary: Option<String>,
     /// Current working directory
     pub working_directory: PathBuf,
+    /// Journal of mutating operations, written under `.cats/journal/` of the working
+    /// directory; off unless turned on with [`ToolState::set_journaling`]
+    #[serde(skip)]
+    pub journal: Option<Journal>,
+    /// Whether file changes are attributed in `.cats/provenance.json`
+    #[serde(skip)]
+    pub provenance: bool,
+    /// Name of this session's trash directory, assigned on the first deletion
+    #[serde(skip)]
+    pub trash_session: Option<String>,
+    /// Protected path globs, loaded from `cats.toml` on first use
+    #[serde(skip)]
+    pub protected_paths: Option<ProtectedPaths>,
+    /// Scratch directories created this session, removed when the state is dropped
+    #[serde(skip)]
+    pub scratch_dirs: Vec<ScratchDir>,
+    /// Paths written during the current tool execution
+    #[serde(skip)]
+    pub touched_paths: Vec<TouchedPath>,
+    /// Event channel of the owning registry, for tools that publish progress
+    #[serde(skip)]
+    pub events: Option<EventSender>,
+    /// Templates for user-facing messages
+    #[serde(skip)]
+    pub message_style: MessageStyle,
+    /// Search filter rules, loaded from `cats.toml` on first use
+    #[serde(skip)]
+    pub search_filtering: Option<SearchFilteringConfig>,
+    /// Chunk stream of the running call; inactive unless the call is streaming
+    #[serde(skip)]
+    pub stream: ResultStream,
+    /// Recent calls, for spotting an agent that repeats itself
+    #[serde(skip)]
+    pub recent_calls: RecentCalls,
 }
 
 /// State of an individual file
@@ -49,22 +172,35 @@ impl FileState {
         }
     }
 
+    /// Line indices of the current window, clamped to the content
+    fn window_range(&self) -> Range<usize> {
+        let end = std::cmp::min(self.window_start + self.window_size, self.content.len());
+        self.window_start.min(end)..end
+    }
+
     /// Get the current window content
     pub fn get_window(&self) -> Vec<&String> {
-        let end = std::cmp::min(self.window_start + self.window_size, self.content.len());
-        self.content[self.window_start..end].iter().collect()
+        self.content[self.window_range()].iter().collect()
     }
 
     /// Get window with line numbers
     pub fn get_window_with_line_numbers(&self) -> Vec<String> {
-        let end = std::cmp::min(self.window_start + self.window_size, self.content.len());
-        self.content[self.window_start..end]
+        let range = self.window_range();
+        let first_line = range.start + 1;
+        self.content[range]
             .iter()
             .enumerate()
-            .map(|(i, line)| format!("{:4} | {}", self.window_start + i + 1, line))
+            .map(|(i, line)| format!("{:4} | {}", first_line + i, line))
             .collect()
     }
 
+    /// The current window with line numbers, for formatting into a larger message
+    /// without building the lines first
+    pub fn window_display(&self) -> NumberedLines<'_> {
+        let range = self.window_range();
+        NumberedLines::new(&self.content[range.clone()], range.start + 1)
+    }
+
     /// Move window to show specific line
     pub fn goto_line(&mut self, line_number: usize) {
         if line_number == 0 {
@@ -95,6 +231,15 @@ impl FileState {
         self.window_start = std::cmp::min(self.window_start + self.window_size, max_start);
     }
 
+    /// Replace the content with what is on disk now, keeping the window in bounds
+    pub fn reload(&mut self, content: Vec<String>) {
+        self.content = content;
+        let max_start = self.content.len().saturating_sub(self.window_size);
+        self.window_start = self.window_start.min(max_start);
+        self.modified = false;
+        self.last_modified = Some(std::time::SystemTime::now());
+    }
+
     /// Get total number of lines
     pub fn total_lines(&self) -> usize {
         self.content.len()
@@ -111,39 +256,209 @@ impl FileState {
     }
 }
 
+/// Lines displayed as `{number:4} | {line}`, one per output line
+///
+/// Formatting writes straight into the destination, so a window can be embedded in a
+/// tool message with a single allocation.
+#[derive(Debug, Clone, Copy)]
+pub struct NumberedLines<'a> {
+    lines: &'a [String],
+    first_line: usize,
+}
+
+impl<'a> NumberedLines<'a> {
+    /// `lines`, the first of which is line `first_line` (1-based)
+    pub fn new(lines: &'a [String], first_line: usize) -> Self {
+        Self { lines, first_line }
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.lines.is_empty()
+    }
+
+    /// Upper bound of the displayed length, for preallocating output buffers
+    pub fn estimated_len(&self) -> usize {
+        self.lines.iter().map(|line| line.len() + 8).sum()
+    }
+}
+
+impl fmt::Display for NumberedLines<'_> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        for (i, line) in self.lines.iter().enumerate() {
+            if i > 0 {
+                f.write_char('\n')?;
+            }
+            write!(f, "{:4} | {}", self.first_line + i, line)?;
+        }
+        Ok(())
+    }
+}
+
 /// Snapshot of the tool state for history tracking
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct StateSnapshot {
     pub timestamp: std::time::SystemTime,
     pub current_file: Option<PathBuf>,
     pub operation: String,
-    pub file_states: HashMap<PathBuf, FileState>,
+    pub file_states: BTreeMap<PathBuf, FileState>,
+    /// Marker added by the embedding agent rather than recorded by a tool
+    #[serde(default)]
+    pub annotation: bool,
+    /// Files the operation wrote or deleted, with their contents before and after
+    #[serde(default)]
+    pub edits: Vec<FileEdit>,
+    /// Verdict of `verify_change` on the operation's edits
+    #[serde(default)]
+    pub verification: Option<Verification>,
+}
+
+/// Outcome of checking an edit after it was made
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct Verification {
+    pub passed: bool,
+    /// One line per step, e.g. `syntax: passed`
+    pub summary: String,
 }
 
 impl ToolState {
     /// Create a new tool state
     pub fn new() -> Self {
+        let working_directory = std::env::current_dir().unwrap_or_else(|_| PathBuf::from("."));
         Self {
-            open_files: HashMap::new(),
+            open_files: BTreeMap::new(),
             current_file: None,
             history: Vec::new(),
-            working_directory: std::env::current_dir().unwrap_or_else(|_| PathBuf::from(".")),
+            redo_stack: Vec::new(),
+            pending_edits: Vec::new(),
+            submit_summary: None,
+            journal: None,
+            provenance: false,
+            trash_session: None,
+            protected_paths: None,
+            scratch_dirs: Vec::new(),
+            touched_paths: Vec::new(),
+            events: None,
+            message_style: MessageStyle::default(),
+            search_filtering: None,
+            stream: ResultStream::default(),
+            recent_calls: RecentCalls::default(),
+            working_directory,
+        }
+    }
+
+    /// Point the session at another workspace root
+    ///
+    /// Commands run in it and relative path arguments resolve against it from now on
+    /// ([`ToolState::session_path`]), and the journal (if enabled) moves to the new
+    /// workspace's `.cats/journal/`.
+    pub fn set_working_directory(&mut self, path: impl Into<PathBuf>) {
+        self.working_directory = path.into();
+        if self.journal.is_some() {
+            self.journal = Some(Journal::new(self.working_directory.join(JOURNAL_DIR)));
         }
     }
 
-    /// Open a file and add it to the state
+    /// `path`, a tool's path argument, below the working directory
+    ///
+    /// Absolute paths are kept, and so are relative ones while the working directory is
+    /// the process's, so results show paths as the caller spelled them.
+    pub fn session_path(&self, path: impl AsRef<Path>) -> PathBuf {
+        let path = path.as_ref();
+        if path.is_relative()
+            && std::env::current_dir().map_or(true, |cwd| cwd != self.working_directory)
+        {
+            self.working_directory.join(path)
+        } else {
+            path.to_path_buf()
+        }
+    }
+
+    /// Turn the journal `cats recover` works from on or off
+    ///
+    /// It is written to `.cats/journal/` of the working directory and keeps the previous
+    /// content of every file changed, so it is off by default.
+    pub fn set_journaling(&mut self, on: bool) {
+        if on != self.journal.is_some() {
+            self.journal = on.then(|| Journal::new(self.working_directory.join(JOURNAL_DIR)));
+        }
+    }
+
+    /// Fresh state for another session in the same workspace
+    ///
+    /// Shares the working directory, protected paths, search filters, message style, the
+    /// repeated-call threshold and whether journaling and provenance are on, but starts
+    /// with no open files, history, recent calls or scratch directories of its own.
+    pub fn new_session(&self) -> Self {
+        Self {
+            journal: self
+                .journal
+                .as_ref()
+                .map(|_| Journal::new(self.working_directory.join(JOURNAL_DIR))),
+            provenance: self.provenance,
+            protected_paths: self.protected_paths.clone(),
+            message_style: self.message_style.clone(),
+            search_filtering: self.search_filtering.clone(),
+            recent_calls: RecentCalls::with_threshold(self.recent_calls.threshold),
+            working_directory: self.working_directory.clone(),
+            ..Self::new()
+        }
+    }
+
+    /// Open a file and add it to the state, keyed by its canonical path
     pub fn open_file(
         &mut self,
         path: PathBuf,
         content: Vec<String>,
         window_size: usize,
     ) -> Result<()> {
+        let path = canonical_path(&path);
         let file_state = FileState::new(content, window_size);
         self.open_files.insert(path.clone(), file_state);
         self.current_file = Some(path);
         Ok(())
     }
 
+    /// Whether `path`, however it is spelled, is the current file
+    pub fn is_current_file(&self, path: &Path) -> bool {
+        self.current_file
+            .as_deref()
+            .is_some_and(|current| current == canonical_path(path))
+    }
+
+    /// Follow a file that moved from `from` to `to`, keeping its window
+    pub fn rename_file(&mut self, from: &Path, to: &Path) {
+        let from = canonical_path(from);
+        let to = canonical_path(to);
+        if let Some(file_state) = self.open_files.remove(&from) {
+            self.open_files.insert(to.clone(), file_state);
+        }
+        if self.current_file.as_ref() == Some(&from) {
+            self.current_file = Some(to);
+        }
+    }
+
+    /// Protected path globs for the working directory
+    pub fn protected_paths(&mut self) -> &ProtectedPaths {
+        let working_directory = &self.working_directory;
+        self.protected_paths
+            .get_or_insert_with(|| ProtectedPaths::load(working_directory))
+    }
+
+    /// Filter deciding which paths searches and listings walk into
+    ///
+    /// Uses the `[search_filtering]` table of `cats.toml` in the working directory,
+    /// falling back to `config/simpaticoder.toml` and the built-in rules.
+    pub fn search_filter(&mut self) -> ConfigurableFilter {
+        let working_directory = &self.working_directory;
+        let config = self.search_filtering.get_or_insert_with(|| {
+            crate::config::Config::load(working_directory)
+                .ok()
+                .and_then(|config| config.search_filtering)
+                .unwrap_or_else(|| ConfigurableFilter::new(None).config)
+        });
+        ConfigurableFilter::new(Some(config.clone()))
+    }
+
     /// Get the current file state
     pub fn get_current_file_state(&self) -> Option<&FileState> {
         self.current_file
@@ -158,9 +473,10 @@ impl ToolState {
     }
 
     /// Switch to a different open file
-    pub fn switch_to_file(&mut self, path: &PathBuf) -> Result<(), ToolError> {
-        if self.open_files.contains_key(path) {
-            self.current_file = Some(path.clone());
+    pub fn switch_to_file(&mut self, path: &Path) -> Result<(), ToolError> {
+        let canonical = canonical_path(path);
+        if self.open_files.contains_key(&canonical) {
+            self.current_file = Some(canonical);
             Ok(())
         } else {
             Err(ToolError::FileNotFound {
@@ -169,14 +485,53 @@ impl ToolState {
         }
     }
 
-    /// Close a file
-    pub fn close_file(&mut self, path: &PathBuf) {
-        self.open_files.remove(path);
-        if self.current_file.as_ref() == Some(path) {
+    /// Close a file; if it was current, the first remaining file by path becomes current
+    pub fn close_file(&mut self, path: &Path) {
+        let path = canonical_path(path);
+        self.open_files.remove(&path);
+        if self.current_file.as_ref() == Some(&path) {
             self.current_file = self.open_files.keys().next().cloned();
         }
     }
 
+    /// SHA-256 of every open file as it is on disk (`None` if it cannot be read), to
+    /// tell later which ones something outside the tools changed
+    pub fn open_file_hashes(&self) -> BTreeMap<PathBuf, Option<String>> {
+        self.open_files
+            .keys()
+            .map(|path| (path.clone(), journal::hash_file(path)))
+            .collect()
+    }
+
+    /// Reload the open files whose content changed since `before` was taken with
+    /// [`ToolState::open_file_hashes`], and close those that can no longer be read
+    ///
+    /// Returns each changed file with [`ChangeKind::Modified`] or [`ChangeKind::Deleted`].
+    pub fn refresh_changed_files(
+        &mut self,
+        before: &BTreeMap<PathBuf, Option<String>>,
+    ) -> Vec<(PathBuf, ChangeKind)> {
+        let mut changed = Vec::new();
+        for (path, hash) in before {
+            if journal::hash_file(path) == *hash {
+                continue;
+            }
+            match crate::profiling::read_to_string(path) {
+                Ok(content) => {
+                    if let Some(file_state) = self.open_files.get_mut(path) {
+                        file_state.reload(content.lines().map(String::from).collect());
+                        changed.push((path.clone(), ChangeKind::Modified));
+                    }
+                }
+                Err(_) => {
+                    self.close_file(path);
+                    changed.push((path.clone(), ChangeKind::Deleted));
+                }
+            }
+        }
+        changed
+    }
+
     /// Create a snapshot for history
     pub fn create_snapshot(&self, operation: String) -> StateSnapshot {
         StateSnapshot {
@@ -184,21 +539,84 @@ impl ToolState {
             current_file: self.current_file.clone(),
             operation,
             file_states: self.open_files.clone(),
+            annotation: false,
+            edits: Vec::new(),
+            verification: None,
         }
     }
 
     /// Add to history
     pub fn push_history(&mut self, operation: String) {
         let snapshot = self.create_snapshot(operation);
+        self.push_snapshot(snapshot);
+    }
+
+    /// Add a high-level marker from the embedding agent ("starting step 3: write
+    /// tests") to the history, so summaries show the plan next to the operations
+    pub fn annotate_history(&mut self, text: impl Into<String>) {
+        let mut snapshot = self.create_snapshot(text.into());
+        snapshot.annotation = true;
+        self.push_snapshot(snapshot);
+    }
+
+    /// Annotations in the history, oldest first
+    pub fn annotations(&self) -> Vec<&str> {
+        self.history
+            .iter()
+            .filter(|snapshot| snapshot.annotation)
+            .map(|snapshot| snapshot.operation.as_str())
+            .collect()
+    }
+
+    fn push_snapshot(&mut self, mut snapshot: StateSnapshot) {
+        snapshot.edits = std::mem::take(&mut self.pending_edits);
+        // A new edit forks the history: what was undone can no longer be redone
+        if !snapshot.edits.is_empty() {
+            self.redo_stack.clear();
+        }
         self.history.push(snapshot);
 
-        // Keep history size manageable
+        // Keep history size manageable, dropping tool operations before annotations
         const MAX_HISTORY: usize = 100;
         if self.history.len() > MAX_HISTORY {
-            self.history.remove(0);
+            let oldest = self
+                .history
+                .iter()
+                .position(|snapshot| !snapshot.annotation)
+                .unwrap_or(0);
+            self.history.remove(oldest);
         }
     }
 
+    /// Attach `verification` to the most recent operation that edited any of `paths`
+    /// (canonical), returning the operation's name
+    ///
+    /// Edits not yet in the history are pushed as their own operation first.
+    pub fn record_verification(
+        &mut self,
+        paths: &[PathBuf],
+        verification: Verification,
+    ) -> Option<String> {
+        if !self.pending_edits.is_empty() {
+            self.push_history("edit".to_string());
+        }
+        let snapshot = self
+            .history
+            .iter_mut()
+            .rev()
+            .find(|snapshot| snapshot.edits.iter().any(|edit| paths.contains(&edit.path)))?;
+        snapshot.verification = Some(verification);
+        Some(snapshot.operation.clone())
+    }
+
+    /// The most recent operation that edited files
+    pub fn last_edit(&self) -> Option<&StateSnapshot> {
+        self.history
+            .iter()
+            .rev()
+            .find(|snapshot| !snapshot.edits.is_empty())
+    }
+
     /// Get state summary for display
     pub fn get_summary(&self) -> String {
         let mut summary = String::new();
@@ -227,6 +645,28 @@ impl ToolState {
             summary.push_str("No file currently open\n");
         }
 
+        if let Some(edit) = self.last_edit() {
+            let verdict = match &edit.verification {
+                Some(v) if v.passed => "verified",
+                Some(_) => "verification failed",
+                None => "not verified",
+            };
+            summary.push_str(&format!(
+                "Last Edit: {} ({} file(s), {})\n",
+                edit.operation,
+                edit.edits.len(),
+                verdict
+            ));
+        }
+
+        let annotations = self.annotations();
+        if !annotations.is_empty() {
+            summary.push_str("\nPlan:\n");
+            for annotation in annotations {
+                summary.push_str(&format!("  - {}\n", annotation));
+            }
+        }
+
         if !self.open_files.is_empty() {
             summary.push_str("\nOpen Files:\n");
             for (path, file_state) in &self.open_files {
@@ -244,6 +684,13 @@ impl ToolState {
             }
         }
 
+        if !self.scratch_dirs.is_empty() {
+            summary.push_str("\nScratch Directories:\n");
+            for scratch in &self.scratch_dirs {
+                summary.push_str(&format!("  {}\n", scratch.path.display()));
+            }
+        }
+
         summary
     }
 }
@@ -280,6 +727,15 @@ impl Tool for StateTool {
         "_state"
     }
 
+    fn danger_level(&self) -> DangerLevel {
+        DangerLevel::ReadOnly
+    }
+
+    // Reports the session state, which other calls of a batch change
+    fn is_parallel_safe(&self) -> bool {
+        false
+    }
+
     fn validate_args(&self, _args: &ToolArgs) -> Result<(), ToolError> {
         Ok(()) // State tool takes no arguments
     }
@@ -294,10 +750,10 @@ impl Tool for StateTool {
         let mut full_response = format!("Current tool state:\n\n{}", summary);
 
         if let Some(file_state) = state.get_current_file_state() {
-            let window_content = file_state.get_window_with_line_numbers();
-            if !window_content.is_empty() {
-                full_response.push_str("\n\nCurrent window:\n");
-                full_response.push_str(&window_content.join("\n"));
+            let window = file_state.window_display();
+            if !window.is_empty() {
+                full_response.reserve(window.estimated_len() + 20);
+                let _ = write!(full_response, "\n\nCurrent window:\n{}", window);
             }
         }
 
@@ -308,7 +764,14 @@ impl Tool for StateTool {
                 "working_directory": state.working_directory,
                 "current_file": state.current_file,
                 "open_files": state.open_files.keys().collect::<Vec<_>>(),
-                "history_count": state.history.len()
+                "scratch_dirs": state.scratch_dirs.iter().map(|s| &s.path).collect::<Vec<_>>(),
+                "history_count": state.history.len(),
+                "last_edit": state.last_edit().map(|edit| serde_json::json!({
+                    "operation": edit.operation,
+                    "files": edit.edits.iter().map(|e| &e.path).collect::<Vec<_>>(),
+                    "verification": edit.verification,
+                })),
+                "annotations": state.annotations()
             }),
         ))
     }
@@ -322,6 +785,97 @@ impl Tool for StateTool {
     }
 }
 
+/// Tool that adds a plan marker to the session history
+pub struct AnnotateHistoryTool {
+    name: String,
+}
+
+impl AnnotateHistoryTool {
+    pub fn new() -> Self {
+        Self {
+            name: "annotate_history".to_string(),
+        }
+    }
+}
+
+impl Default for AnnotateHistoryTool {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl Tool for AnnotateHistoryTool {
+    fn name(&self) -> &str {
+        &self.name
+    }
+
+    fn description(&self) -> &str {
+        "Record a high-level marker (e.g. 'starting step 3: write tests') in the session history, shown by _state and submit"
+    }
+
+    fn signature(&self) -> &str {
+        "annotate_history <text>"
+    }
+
+    fn danger_level(&self) -> DangerLevel {
+        DangerLevel::ReadOnly
+    }
+
+    // Markers belong between the calls they separate
+    fn is_parallel_safe(&self) -> bool {
+        false
+    }
+
+    // Every call adds a marker
+    fn is_idempotent(&self) -> bool {
+        false
+    }
+
+    fn validate_args(&self, args: &ToolArgs) -> Result<(), ToolError> {
+        let text = args.get_named_arg("text").or_else(|| args.get_arg(0));
+        if text.is_none_or(|t| t.trim().is_empty()) {
+            return Err(ToolError::InvalidArgs {
+                message: "Usage: annotate_history <text>".to_string(),
+            });
+        }
+        Ok(())
+    }
+
+    fn execute(&mut self, args: &ToolArgs, state: &Arc<Mutex<ToolState>>) -> Result<ToolResult> {
+        let text = args
+            .get_named_arg("text")
+            .or_else(|| args.get_arg(0))
+            .ok_or_else(|| anyhow::anyhow!("Missing text parameter"))?
+            .trim()
+            .to_string();
+        let mut state = state
+            .lock()
+            .map_err(|e| anyhow::anyhow!("Failed to lock state: {}", e))?;
+        state.annotate_history(text.clone());
+
+        Ok(ToolResult::success_with_data(
+            format!("Noted: {}", text),
+            serde_json::json!({
+                "annotation": text,
+                "annotations": state.annotations()
+            }),
+        ))
+    }
+
+    fn get_parameters_schema(&self) -> serde_json::Value {
+        serde_json::json!({
+            "type": "object",
+            "properties": {
+                "text": {
+                    "type": "string",
+                    "description": "The marker to record, e.g. 'starting step 3: write tests'"
+                }
+            },
+            "required": ["text"]
+        })
+    }
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
@@ -341,6 +895,26 @@ mod tests {
         assert!(!file_state.modified);
     }
 
+    #[test]
+    fn test_window_display_matches_numbered_lines() {
+        let content: Vec<String> = (1..=10).map(|i| format!("line {}", i)).collect();
+        let mut file_state = FileState::new(content, 3);
+        file_state.goto_line(5);
+        assert_eq!(
+            file_state.window_display().to_string(),
+            file_state.get_window_with_line_numbers().join("\n")
+        );
+        assert_eq!(
+            file_state.window_display().to_string(),
+            "   4 | line 4\n   5 | line 5\n   6 | line 6"
+        );
+
+        // A window left past the end by a shrinking file renders empty instead of panicking
+        file_state.content.truncate(2);
+        assert!(file_state.window_display().is_empty());
+        assert!(file_state.get_window().is_empty());
+    }
+
     #[test]
     fn test_file_state_windowing() {
         let content: Vec<String> = (1..=10).map(|i| format!("line {}", i)).collect();
@@ -370,8 +944,9 @@ mod tests {
 
         // Test opening file
         state.open_file(path.clone(), content, 10).unwrap();
-        assert_eq!(state.current_file, Some(path.clone()));
-        assert!(state.open_files.contains_key(&path));
+        assert_eq!(state.current_file, Some(canonical_path(&path)));
+        assert!(state.is_current_file(&path));
+        assert!(state.open_files.contains_key(&canonical_path(&path)));
 
         // Test getting current file state
         let file_state = state.get_current_file_state().unwrap();
@@ -379,10 +954,53 @@ mod tests {
 
         // Test closing file
         state.close_file(&path);
-        assert!(!state.open_files.contains_key(&path));
+        assert!(state.open_files.is_empty());
         assert_eq!(state.current_file, None);
     }
 
+    #[test]
+    fn test_close_current_file_switches_to_first_by_path() {
+        let mut state = ToolState::new();
+        for name in ["c.txt", "a.txt", "b.txt"] {
+            state
+                .open_file(PathBuf::from(name), vec!["x".to_string()], 10)
+                .unwrap();
+        }
+        assert!(state.is_current_file(Path::new("b.txt")));
+
+        state.close_file(&PathBuf::from("b.txt"));
+        assert!(state.is_current_file(Path::new("a.txt")));
+        let summary = state.get_summary();
+        assert!(summary.find("a.txt - ").unwrap() < summary.find("c.txt - ").unwrap());
+    }
+
+    #[test]
+    fn test_open_files_keyed_by_canonical_path() {
+        let temp = tempfile::TempDir::new().unwrap();
+        std::fs::create_dir(temp.path().join("src")).unwrap();
+        let file = temp.path().join("src/lib.rs");
+        std::fs::write(&file, "fn main() {}\n").unwrap();
+        let dotted = temp.path().join("./src/../src/lib.rs");
+
+        let mut state = ToolState::new();
+        state
+            .open_file(file.clone(), vec!["a".to_string()], 10)
+            .unwrap();
+        state
+            .open_file(dotted.clone(), vec!["b".to_string()], 10)
+            .unwrap();
+        assert_eq!(state.open_files.len(), 1);
+        assert!(state.is_current_file(&file));
+        assert!(state.is_current_file(&dotted));
+
+        // A moved file keeps its window under the new path
+        let moved = temp.path().join("src/main.rs");
+        std::fs::rename(&file, &moved).unwrap();
+        state.rename_file(&dotted, &moved);
+        assert!(state.is_current_file(&moved));
+        assert_eq!(state.get_current_file_state().unwrap().content, vec!["b"]);
+    }
+
     #[test]
     fn test_state_tool_execution() {
         let mut tool = StateTool::new();
@@ -393,4 +1011,42 @@ mod tests {
         assert!(result.success);
         assert!(result.data.is_some());
     }
+
+    #[test]
+    fn test_annotations_in_summary_and_history() {
+        let state = Arc::new(Mutex::new(ToolState::new()));
+        let mut annotate = AnnotateHistoryTool::new();
+        assert!(annotate
+            .validate_args(&ToolArgs::from_args(&["  "]))
+            .is_err());
+        let result = annotate
+            .execute(
+                &ToolArgs::from_args(&["starting step 3: write tests"]),
+                &state,
+            )
+            .unwrap();
+        assert!(result.success);
+
+        // Annotations outlive the tool operations trimmed from a full history
+        {
+            let mut state = state.lock().unwrap();
+            for i in 0..150 {
+                state.push_history(format!("op {}", i));
+            }
+            state.annotate_history("step 4: fix lints");
+            assert_eq!(state.history.len(), 100);
+            assert_eq!(
+                state.annotations(),
+                vec!["starting step 3: write tests", "step 4: fix lints"]
+            );
+        }
+
+        let result = StateTool::new()
+            .execute(&ToolArgs::from_args(&[]), &state)
+            .unwrap();
+        assert!(result
+            .message
+            .contains("Plan:\n  - starting step 3: write tests\n  - step 4: fix lints"));
+        assert_eq!(result.data.unwrap()["annotations"][1], "step 4: fix lints");
+    }
 }
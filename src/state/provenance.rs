@@ -0,0 +1,323 @@
+//! Provenance of agent edits
+//!
+//! With provenance on (`ToolRegistry::set_provenance`, `[editing] provenance` in
+//! `cats.toml` or `CATS_PROVENANCE`), every tool call that changes files appends one
+//! record per file to `.cats/provenance.json` in the session's working directory. A
+//! record names the session, the tool, the tool call id and the time, and lists the
+//! regions of the file the call changed. Line numbers refer to the file as the call left
+//! it, identified by its SHA-256; tooling comparing against a later version of the file
+//! can tell from the hash that lines may have moved since.
+
+use super::journal::hash_file;
+use crate::editing::artifacts::TouchedPath;
+use crate::events::ChangeKind;
+use serde::{Deserialize, Serialize};
+use std::io;
+use std::path::Path;
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::sync::Mutex;
+use std::time::SystemTime;
+
+/// Provenance file relative to the working directory
+pub const PROVENANCE_FILE: &str = ".cats/provenance.json";
+
+/// Version of the file format
+const FORMAT_VERSION: u32 = 1;
+
+/// Numbers the calls that arrive without an id
+static CALL_COUNTER: AtomicU64 = AtomicU64::new(0);
+
+/// Serializes read-modify-write cycles of provenance files by sessions running at once
+static FILE_LOCK: Mutex<()> = Mutex::new(());
+
+/// A run of changed lines
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct Region {
+    /// 1-based first line of the run in the file after the change; for a pure
+    /// deletion, the line that now follows the removed ones
+    pub start: usize,
+    /// Lines added or rewritten, starting at `start`
+    pub lines: usize,
+    /// Lines of the previous version removed or rewritten
+    pub removed: usize,
+}
+
+/// Who changed one file, when and where
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct ProvenanceRecord {
+    /// Relative to the working directory when the file lies inside it
+    pub path: String,
+    pub change: ChangeKind,
+    /// Previous path of a renamed file
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub renamed_from: Option<String>,
+    pub session: String,
+    pub tool: String,
+    pub call_id: String,
+    /// RFC 3339, UTC
+    pub timestamp: String,
+    /// SHA-256 of the file after the call; absent for deletions
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub sha256: Option<String>,
+    /// Changed regions of text files; empty for binary files, renames and deletions
+    pub regions: Vec<Region>,
+}
+
+/// Contents of `.cats/provenance.json`
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct ProvenanceLog {
+    pub version: u32,
+    pub records: Vec<ProvenanceRecord>,
+}
+
+impl Default for ProvenanceLog {
+    fn default() -> Self {
+        Self {
+            version: FORMAT_VERSION,
+            records: Vec::new(),
+        }
+    }
+}
+
+impl ProvenanceLog {
+    /// The log of the workspace at `working_dir`; empty when there is none yet
+    pub fn load(working_dir: &Path) -> io::Result<Self> {
+        let path = working_dir.join(PROVENANCE_FILE);
+        match std::fs::read_to_string(&path) {
+            Ok(text) => serde_json::from_str(&text).map_err(|e| {
+                io::Error::new(
+                    io::ErrorKind::InvalidData,
+                    format!("{}: {}", path.display(), e),
+                )
+            }),
+            Err(e) if e.kind() == io::ErrorKind::NotFound => Ok(Self::default()),
+            Err(e) => Err(e),
+        }
+    }
+
+    /// Write the log, replacing the file in one rename
+    fn save(&self, working_dir: &Path) -> io::Result<()> {
+        let path = working_dir.join(PROVENANCE_FILE);
+        if let Some(parent) = path.parent() {
+            std::fs::create_dir_all(parent)?;
+        }
+        let partial = path.with_extension("json.tmp");
+        std::fs::write(&partial, serde_json::to_string_pretty(self)?)?;
+        std::fs::rename(&partial, &path)
+    }
+}
+
+/// The call that made a change
+#[derive(Debug, Clone)]
+pub struct CallInfo<'a> {
+    pub session: &'a str,
+    pub tool: &'a str,
+    /// Id the caller gave the call, e.g. the model's tool call id
+    pub call_id: Option<&'a str>,
+}
+
+/// Id for a call that arrived without one, unique within the process
+fn generated_call_id() -> String {
+    let millis = SystemTime::now()
+        .duration_since(SystemTime::UNIX_EPOCH)
+        .map_or(0, |elapsed| elapsed.as_millis());
+    format!(
+        "cats-{}-{}",
+        millis,
+        CALL_COUNTER.fetch_add(1, Ordering::Relaxed)
+    )
+}
+
+/// Runs of changed lines in a unified diff
+pub fn regions(patch: &str) -> Vec<Region> {
+    let mut regions = Vec::new();
+    let mut new_line = 0;
+    let mut run: Option<Region> = None;
+    for line in patch.lines() {
+        if let Some(header) = line.strip_prefix("@@ ") {
+            regions.extend(run.take());
+            // `-a,b +c,d @@`: the hunk's lines in the new file start at c
+            new_line = header
+                .split_whitespace()
+                .find_map(|range| range.strip_prefix('+'))
+                .and_then(|range| range.split(',').next())
+                .and_then(|start| start.parse::<usize>().ok())
+                .unwrap_or(1)
+                .max(1);
+            continue;
+        }
+        if new_line == 0 {
+            // File headers before the first hunk
+            continue;
+        }
+        match line.chars().next() {
+            Some('+') => {
+                run.get_or_insert(Region {
+                    start: new_line,
+                    lines: 0,
+                    removed: 0,
+                })
+                .lines += 1;
+                new_line += 1;
+            }
+            Some('-') => {
+                run.get_or_insert(Region {
+                    start: new_line,
+                    lines: 0,
+                    removed: 0,
+                })
+                .removed += 1;
+            }
+            Some('\\') => {}
+            _ => {
+                regions.extend(run.take());
+                new_line += 1;
+            }
+        }
+    }
+    regions.extend(run);
+    regions
+}
+
+/// Append a record per path in `touched` to the provenance file of `working_dir`
+///
+/// Returns the call id the records carry.
+pub fn record(
+    working_dir: &Path,
+    call: &CallInfo<'_>,
+    touched: &[TouchedPath],
+) -> io::Result<String> {
+    let call_id = call
+        .call_id
+        .map(str::to_string)
+        .unwrap_or_else(generated_call_id);
+    let timestamp = humantime::format_rfc3339_seconds(SystemTime::now()).to_string();
+    let relative = |path: &Path| {
+        path.strip_prefix(working_dir)
+            .unwrap_or(path)
+            .to_string_lossy()
+            .to_string()
+    };
+
+    let _guard = FILE_LOCK.lock().unwrap_or_else(|e| e.into_inner());
+    let mut log = ProvenanceLog::load(working_dir)?;
+    log.records.extend(touched.iter().map(|touched| {
+        let deleted = touched.kind == ChangeKind::Deleted;
+        ProvenanceRecord {
+            path: relative(&touched.path),
+            change: touched.kind,
+            renamed_from: touched.from.as_deref().map(relative),
+            session: call.session.to_string(),
+            tool: call.tool.to_string(),
+            call_id: call_id.clone(),
+            timestamp: timestamp.clone(),
+            sha256: if deleted {
+                None
+            } else {
+                hash_file(&touched.path)
+            },
+            regions: match &touched.diff {
+                Some(diff) if !deleted => regions(diff),
+                _ => Vec::new(),
+            },
+        }
+    }));
+    log.save(working_dir)?;
+    Ok(call_id)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::editing::diff::unified_diff;
+
+    #[test]
+    fn test_regions_of_a_diff() {
+        let old = "a\nb\nc\nd\ne\nf\ng\nh\ni\nj\n";
+        let new = "a\nB\nc\nd\ne\nf\ng\nh\nj\nk\nl\n";
+        assert_eq!(
+            regions(&unified_diff(old, new, "x.txt")),
+            vec![
+                Region {
+                    start: 2,
+                    lines: 1,
+                    removed: 1
+                },
+                Region {
+                    start: 9,
+                    lines: 0,
+                    removed: 1
+                },
+                Region {
+                    start: 10,
+                    lines: 2,
+                    removed: 0
+                },
+            ]
+        );
+        assert_eq!(
+            regions(&unified_diff("", "one\ntwo\n", "new.txt")),
+            vec![Region {
+                start: 1,
+                lines: 2,
+                removed: 0
+            }]
+        );
+    }
+
+    #[test]
+    fn test_record_appends_to_the_sidecar() {
+        let temp = tempfile::TempDir::new().unwrap();
+        let file = temp.path().join("src").join("lib.rs");
+        std::fs::create_dir_all(file.parent().unwrap()).unwrap();
+        std::fs::write(&file, "fn a() {}\nfn b() {}\n").unwrap();
+        let touched = TouchedPath {
+            diff: Some(unified_diff(
+                "fn a() {}\n",
+                "fn a() {}\nfn b() {}\n",
+                "lib.rs",
+            )),
+            ..TouchedPath::new(file.clone(), ChangeKind::Modified)
+        };
+        let call = |call_id| CallInfo {
+            session: "agent-1",
+            tool: "insert_text",
+            call_id,
+        };
+
+        assert_eq!(
+            record(
+                temp.path(),
+                &call(Some("call_7")),
+                std::slice::from_ref(&touched)
+            )
+            .unwrap(),
+            "call_7"
+        );
+        let generated = record(temp.path(), &call(None), &[touched]).unwrap();
+        assert!(generated.starts_with("cats-"));
+
+        let log = ProvenanceLog::load(temp.path()).unwrap();
+        assert_eq!(log.version, FORMAT_VERSION);
+        assert_eq!(log.records.len(), 2);
+        let first = &log.records[0];
+        assert_eq!(
+            first.path,
+            Path::new("src").join("lib.rs").to_string_lossy()
+        );
+        assert_eq!(
+            (first.session.as_str(), first.call_id.as_str()),
+            ("agent-1", "call_7")
+        );
+        assert_eq!(first.sha256, hash_file(&file));
+        assert_eq!(
+            first.regions,
+            vec![Region {
+                start: 2,
+                lines: 1,
+                removed: 0
+            }]
+        );
+        assert_eq!(log.records[1].call_id, generated);
+    }
+}
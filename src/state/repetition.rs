@@ -0,0 +1,136 @@
+//! Repeated-call detection
+//!
+//! Agents get stuck re-opening the same file or re-running the same failing command.
+//! The session remembers its last [`RECENT_CALLS`] calls. When the same tool is called
+//! with the same arguments [`DEFAULT_REPEAT_THRESHOLD`] times and no file changed in
+//! between, the registry adds a warning to the result with a hint at what to try
+//! instead. A call that modifies files forgets the remembered calls, because repeating
+//! a command after an edit is how an agent checks its work.
+
+use crate::core::ToolArgs;
+use std::collections::{BTreeMap, VecDeque};
+
+/// Number of recent calls remembered per session
+pub const RECENT_CALLS: usize = 10;
+
+/// Identical calls among the recent ones that trigger a warning, unless configured
+pub const DEFAULT_REPEAT_THRESHOLD: usize = 3;
+
+/// The recent calls of a session
+#[derive(Debug, Clone)]
+pub struct RecentCalls {
+    calls: VecDeque<String>,
+    /// Identical calls that trigger a warning; `None` turns detection off
+    pub threshold: Option<usize>,
+}
+
+impl Default for RecentCalls {
+    fn default() -> Self {
+        Self::with_threshold(Some(DEFAULT_REPEAT_THRESHOLD))
+    }
+}
+
+impl RecentCalls {
+    /// No remembered calls, warning at `threshold` identical ones
+    pub fn with_threshold(threshold: Option<usize>) -> Self {
+        Self {
+            calls: VecDeque::with_capacity(RECENT_CALLS),
+            threshold,
+        }
+    }
+
+    /// Remember a call and return how often it occurs among the recent calls, this one
+    /// included
+    pub fn record(&mut self, tool: &str, args: &ToolArgs) -> usize {
+        let fingerprint = fingerprint(tool, args);
+        if self.calls.len() == RECENT_CALLS {
+            self.calls.pop_front();
+        }
+        self.calls.push_back(fingerprint);
+        let last = self.calls.back().expect("just pushed");
+        self.calls.iter().filter(|call| *call == last).count()
+    }
+
+    /// Whether `count` identical calls should be reported
+    pub fn is_repetition(&self, count: usize) -> bool {
+        self.threshold.is_some_and(|threshold| count >= threshold)
+    }
+
+    /// Forget the remembered calls, e.g. after files changed
+    pub fn clear(&mut self) {
+        self.calls.clear();
+    }
+}
+
+/// Identity of a call: the tool and its arguments, with named arguments in key order
+fn fingerprint(tool: &str, args: &ToolArgs) -> String {
+    let named: BTreeMap<_, _> = args.named_args.iter().collect();
+    serde_json::json!([tool, args.args, named]).to_string()
+}
+
+/// What to try instead of calling `tool` again with the same arguments
+pub fn loop_hint(tool: &str) -> &'static str {
+    match tool {
+        "open" | "goto" | "scroll_up" | "scroll_down" => {
+            "The file is already open; use goto or scroll_down to move the window, or search_file to find the lines you need."
+        }
+        "run_command" | "run_tests" | "run_benchmarks" => {
+            "The command will give the same result until something changes; read its output, change the code or the command, or run explain_errors on the errors."
+        }
+        "search_dir" | "search_file" | "find_file" => {
+            "The search will find the same matches; try another term, a narrower or wider directory, or filemap to see the layout."
+        }
+        "replace_text" | "insert_text" | "delete_text" | "delete_line" | "overwrite_file" => {
+            "The edit is not taking effect; open the file to check its current content before editing again."
+        }
+        _ => "Repeating the call will not change the result; try different arguments or another tool.",
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::collections::HashMap;
+
+    #[test]
+    fn test_record_counts_identical_calls() {
+        let mut recent = RecentCalls::default();
+        let open = ToolArgs::from_args(&["a.rs"]);
+        assert_eq!(recent.record("open", &open), 1);
+        assert_eq!(recent.record("search_dir", &ToolArgs::from_args(&["x"])), 1);
+        assert_eq!(recent.record("open", &open), 2);
+        assert!(!recent.is_repetition(2));
+        assert_eq!(recent.record("open", &open), 3);
+        assert!(recent.is_repetition(3));
+
+        // Named arguments match regardless of their order
+        let named = |pairs: &[(&str, &str)]| {
+            let map: HashMap<String, String> = pairs
+                .iter()
+                .map(|(k, v)| (k.to_string(), v.to_string()))
+                .collect();
+            ToolArgs::with_named_args(vec![], map)
+        };
+        recent.record(
+            "copy_path",
+            &named(&[("source", "a"), ("destination", "b")]),
+        );
+        assert_eq!(
+            recent.record(
+                "copy_path",
+                &named(&[("destination", "b"), ("source", "a")])
+            ),
+            2
+        );
+
+        // Only the most recent calls count
+        for i in 0..RECENT_CALLS {
+            recent.record("goto", &ToolArgs::from_args(&[&i.to_string()]));
+        }
+        assert_eq!(recent.record("open", &open), 1);
+
+        recent.clear();
+        recent.threshold = None;
+        assert!(!recent.is_repetition(100));
+    }
+}
@@ -0,0 +1,165 @@
+//! Session-scoped scratch directories
+//!
+//! `create_scratch_dir` hands out temporary directories outside the repository for
+//! unpacking artifacts or staging generated files. They are owned by the session state
+//! and removed when the last handle to it is dropped, i.e. when the registry goes away.
+
+use super::ToolState;
+use crate::core::{DangerLevel, Tool, ToolArgs, ToolError, ToolResult};
+use anyhow::Result;
+use std::path::PathBuf;
+use std::sync::{Arc, Mutex};
+use std::time::SystemTime;
+use tempfile::TempDir;
+
+/// Prefix of every scratch directory name
+pub const SCRATCH_PREFIX: &str = "cats-scratch-";
+
+/// A scratch directory owned by the session
+#[derive(Debug, Clone)]
+pub struct ScratchDir {
+    /// Absolute path of the directory
+    pub path: PathBuf,
+    /// Optional label given when the directory was created
+    pub label: Option<String>,
+    pub created: SystemTime,
+    // Shared so cloned states keep the directory alive; removed on the last drop
+    _dir: Arc<TempDir>,
+}
+
+impl ScratchDir {
+    /// Create a new scratch directory under the system temp directory
+    pub fn create(label: Option<String>) -> std::io::Result<Self> {
+        let mut prefix = SCRATCH_PREFIX.to_string();
+        if let Some(label) = &label {
+            let sanitized: String = label
+                .chars()
+                .map(|c| if c.is_ascii_alphanumeric() { c } else { '-' })
+                .collect();
+            prefix.push_str(&sanitized);
+            prefix.push('-');
+        }
+        let dir = tempfile::Builder::new().prefix(&prefix).tempdir()?;
+        Ok(Self {
+            path: dir.path().to_path_buf(),
+            label,
+            created: SystemTime::now(),
+            _dir: Arc::new(dir),
+        })
+    }
+}
+
+/// Tool for creating a session-scoped scratch directory
+pub struct CreateScratchDirTool {
+    name: String,
+}
+
+impl CreateScratchDirTool {
+    pub fn new() -> Self {
+        Self {
+            name: "create_scratch_dir".to_string(),
+        }
+    }
+}
+
+impl Default for CreateScratchDirTool {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl Tool for CreateScratchDirTool {
+    fn name(&self) -> &str {
+        &self.name
+    }
+
+    fn description(&self) -> &str {
+        "Create a temporary directory outside the repository for this session; it is removed automatically when the session ends"
+    }
+
+    fn signature(&self) -> &str {
+        "create_scratch_dir(label?: str)"
+    }
+
+    fn danger_level(&self) -> DangerLevel {
+        DangerLevel::ModifiesFiles
+    }
+
+    fn validate_args(&self, _args: &ToolArgs) -> Result<(), ToolError> {
+        Ok(())
+    }
+
+    fn execute(&mut self, args: &ToolArgs, state: &Arc<Mutex<ToolState>>) -> Result<ToolResult> {
+        let label = args
+            .get_named_arg("label")
+            .or_else(|| args.get_arg(0))
+            .filter(|l| !l.is_empty())
+            .cloned();
+
+        let scratch = ScratchDir::create(label)
+            .map_err(|e| anyhow::anyhow!("Failed to create scratch directory: {}", e))?;
+        let path = scratch.path.clone();
+
+        let count = {
+            let mut state_guard = state
+                .lock()
+                .map_err(|e| anyhow::anyhow!("Failed to lock state: {}", e))?;
+            state_guard.scratch_dirs.push(scratch);
+            state_guard.push_history(format!("Created scratch directory: {}", path.display()));
+            state_guard.scratch_dirs.len()
+        };
+
+        Ok(ToolResult::success_with_data(
+            format!(
+                "Created scratch directory: {} (removed when the session ends)",
+                path.display()
+            ),
+            serde_json::json!({
+                "path": path.to_string_lossy(),
+                "scratch_dirs": count
+            }),
+        ))
+    }
+
+    fn get_parameters_schema(&self) -> serde_json::Value {
+        serde_json::json!({
+            "type": "object",
+            "properties": {
+                "label": {
+                    "type": "string",
+                    "description": "Short label included in the directory name"
+                }
+            },
+            "required": []
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_scratch_dir_removed_with_state() {
+        let state = Arc::new(Mutex::new(ToolState::new()));
+        let mut tool = CreateScratchDirTool::new();
+
+        let result = tool
+            .execute(&ToolArgs::from_args(&["--label=unpack"]), &state)
+            .unwrap();
+        assert!(result.success);
+        let path = PathBuf::from(result.data.unwrap()["path"].as_str().unwrap());
+        assert!(path.is_dir());
+        assert!(path
+            .file_name()
+            .unwrap()
+            .to_string_lossy()
+            .starts_with("cats-scratch-unpack-"));
+
+        let summary = state.lock().unwrap().get_summary();
+        assert!(summary.contains(&path.display().to_string()));
+
+        drop(state);
+        assert!(!path.exists());
+    }
+}
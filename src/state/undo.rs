@@ -0,0 +1,441 @@
+//! Undo and redo of file edits
+//!
+//! Every write or deletion made through `editing::writer` is recorded as a
+//! [`FileEdit`] holding the file's text before and after, and attached to the next
+//! [`StateSnapshot`](super::StateSnapshot) pushed to the history (the registry pushes
+//! one after any tool call that edited files without recording history itself).
+//! `undo` puts the files of the most recent snapshot with edits back to their earlier
+//! content on disk and in the open file views, and moves the snapshot to the redo stack;
+//! `redo` applies it again. Files that changed since the edit, for example through
+//! `run_command`, are left alone unless the call is forced.
+//!
+//! Only text content is covered: moves, copies, directories, binary files and writes of
+//! redacted files such as `.env` are not recorded and cannot be undone.
+
+use super::journal::{hash_bytes, hash_file};
+use super::{canonical_path, ToolState};
+use crate::core::{DangerLevel, Tool, ToolArgs, ToolCategory, ToolError, ToolResult};
+use crate::editing::writer::restore_file;
+use anyhow::{anyhow, bail, Result};
+use serde::{Deserialize, Serialize};
+use std::path::{Path, PathBuf};
+use std::sync::{Arc, Mutex};
+
+/// A file written or deleted by one operation
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct FileEdit {
+    /// Canonical path of the file
+    pub path: PathBuf,
+    /// Text before the operation, `None` if the file did not exist
+    pub before: Option<String>,
+    /// Text after the operation, `None` if it deleted the file
+    pub after: Option<String>,
+}
+
+impl FileEdit {
+    /// The content the file holds once the edit is applied (undo) or reverted (redo),
+    /// and the content to put back
+    fn sides(&self, undo: bool) -> (Option<&str>, Option<&str>) {
+        let (before, after) = (self.before.as_deref(), self.after.as_deref());
+        if undo {
+            (after, before)
+        } else {
+            (before, after)
+        }
+    }
+}
+
+/// An operation undone or redone
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+pub struct EditStep {
+    /// The operation as recorded in the history
+    pub operation: String,
+    /// Files put back, in the order they were restored
+    pub files: Vec<PathBuf>,
+}
+
+impl ToolState {
+    /// Record that `path` went from `before` to `after`
+    ///
+    /// Several edits of one file before the next snapshot merge into one that keeps the
+    /// earliest content and the latest.
+    pub fn record_edit(&mut self, path: &Path, before: Option<String>, after: Option<String>) {
+        let path = canonical_path(path);
+        match self.pending_edits.iter_mut().find(|edit| edit.path == path) {
+            Some(edit) => edit.after = after,
+            None => self.pending_edits.push(FileEdit {
+                path,
+                before,
+                after,
+            }),
+        }
+    }
+
+    /// Whether the history holds an edit that can be undone
+    pub fn can_undo(&self) -> bool {
+        !self.pending_edits.is_empty() || self.history.iter().any(|s| !s.edits.is_empty())
+    }
+
+    /// Whether an undone edit can be redone
+    pub fn can_redo(&self) -> bool {
+        !self.redo_stack.is_empty()
+    }
+
+    /// Revert the files of the most recent operation that edited any, on disk and in
+    /// the open file views
+    ///
+    /// Returns `None` when there is nothing to undo. Fails without changing anything if
+    /// a file no longer holds what the operation wrote, unless `force` is set.
+    pub fn undo_last_edit(&mut self, force: bool) -> Result<Option<EditStep>> {
+        if !self.pending_edits.is_empty() {
+            self.push_history("edit".to_string());
+        }
+        let Some(index) = self.history.iter().rposition(|s| !s.edits.is_empty()) else {
+            return Ok(None);
+        };
+        let snapshot = self.history.remove(index);
+        match self.apply_edits(&snapshot.edits, true, force) {
+            Ok(files) => {
+                let step = EditStep {
+                    operation: snapshot.operation.clone(),
+                    files,
+                };
+                self.redo_stack.push(snapshot);
+                Ok(Some(step))
+            }
+            Err(e) => {
+                self.history.insert(index, snapshot);
+                Err(e)
+            }
+        }
+    }
+
+    /// Apply the most recently undone operation again
+    ///
+    /// Returns `None` when there is nothing to redo. Fails without changing anything if
+    /// a file changed since it was undone, unless `force` is set.
+    pub fn redo_last_undo(&mut self, force: bool) -> Result<Option<EditStep>> {
+        let Some(snapshot) = self.redo_stack.pop() else {
+            return Ok(None);
+        };
+        match self.apply_edits(&snapshot.edits, false, force) {
+            Ok(files) => {
+                let step = EditStep {
+                    operation: snapshot.operation.clone(),
+                    files,
+                };
+                self.history.push(snapshot);
+                Ok(Some(step))
+            }
+            Err(e) => {
+                self.redo_stack.push(snapshot);
+                Err(e)
+            }
+        }
+    }
+
+    /// Put each file of `edits` back to its content before (`undo`) or after them
+    fn apply_edits(&mut self, edits: &[FileEdit], undo: bool, force: bool) -> Result<Vec<PathBuf>> {
+        if !force {
+            let changed: Vec<String> = edits
+                .iter()
+                .filter(|edit| {
+                    let (current, _) = edit.sides(undo);
+                    hash_file(&edit.path) != current.map(|text| hash_bytes(text.as_bytes()))
+                })
+                .map(|edit| edit.path.display().to_string())
+                .collect();
+            if !changed.is_empty() {
+                bail!(
+                    "Changed since the edit: {}. Pass force: true to overwrite",
+                    changed.join(", ")
+                );
+            }
+        }
+
+        let mut restored = Vec::new();
+        for edit in edits {
+            let (_, content) = edit.sides(undo);
+            if content.is_some() || edit.path.exists() {
+                restore_file(self, &edit.path, content).map_err(|e| {
+                    anyhow!(
+                        "Failed to restore {} after restoring {} other file(s): {}",
+                        edit.path.display(),
+                        restored.len(),
+                        e
+                    )
+                })?;
+            }
+            match content {
+                Some(content) => {
+                    if let Some(file_state) = self.open_files.get_mut(&edit.path) {
+                        file_state.reload(content.lines().map(String::from).collect());
+                    }
+                }
+                None => self.close_file(&edit.path),
+            }
+            restored.push(edit.path.clone());
+        }
+        Ok(restored)
+    }
+}
+
+/// Shared execution of `undo` and `redo`
+fn step(state: &Arc<Mutex<ToolState>>, args: &ToolArgs, undo: bool) -> Result<ToolResult> {
+    let force = args.get_named_arg("force").map(String::as_str) == Some("true");
+    let mut state_guard = state
+        .lock()
+        .map_err(|e| anyhow!("Failed to lock state: {}", e))?;
+    let outcome = if undo {
+        state_guard.undo_last_edit(force)
+    } else {
+        state_guard.redo_last_undo(force)
+    };
+    let step = match outcome {
+        Ok(Some(step)) => step,
+        Ok(None) if undo => return Ok(ToolResult::error("Nothing to undo")),
+        Ok(None) => return Ok(ToolResult::error("Nothing to redo")),
+        Err(e) => return Ok(ToolResult::error(format!("Cannot {}: {}", verb(undo), e))),
+    };
+
+    let mut message = format!(
+        "{} {} ({} file(s))",
+        if undo { "Undid" } else { "Redid" },
+        step.operation,
+        step.files.len()
+    );
+    for path in &step.files {
+        let shown = path
+            .strip_prefix(&state_guard.working_directory)
+            .unwrap_or(path);
+        message.push_str(&format!("\n  {}", shown.display()));
+    }
+    Ok(ToolResult::success_with_data(
+        message,
+        serde_json::json!({
+            "operation": step.operation,
+            "files": step.files,
+            "can_undo": state_guard.can_undo(),
+            "can_redo": state_guard.can_redo(),
+        }),
+    ))
+}
+
+fn verb(undo: bool) -> &'static str {
+    if undo {
+        "undo"
+    } else {
+        "redo"
+    }
+}
+
+fn schema(undo: bool) -> serde_json::Value {
+    serde_json::json!({
+        "type": "object",
+        "properties": {
+            "force": {
+                "type": "boolean",
+                "description": format!(
+                    "Also {} files that changed on disk since, overwriting those changes",
+                    verb(undo)
+                )
+            }
+        },
+        "required": []
+    })
+}
+
+/// Tool reverting the file edits of the most recent editing operation
+pub struct UndoTool {
+    name: String,
+}
+
+impl UndoTool {
+    pub fn new() -> Self {
+        Self {
+            name: "undo".to_string(),
+        }
+    }
+}
+
+impl Default for UndoTool {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl Tool for UndoTool {
+    fn name(&self) -> &str {
+        &self.name
+    }
+
+    fn description(&self) -> &str {
+        "Revert the most recent operation that changed files, restoring their previous content on disk and in open files (created files are deleted, deleted files restored). Repeat to go further back; refuses files changed since unless force: true"
+    }
+
+    fn signature(&self) -> &str {
+        "undo(force?: bool)"
+    }
+
+    fn category(&self) -> ToolCategory {
+        ToolCategory::Editing
+    }
+
+    fn danger_level(&self) -> DangerLevel {
+        DangerLevel::ModifiesFiles
+    }
+
+    fn validate_args(&self, _args: &ToolArgs) -> Result<(), ToolError> {
+        Ok(())
+    }
+
+    fn execute(&mut self, args: &ToolArgs, state: &Arc<Mutex<ToolState>>) -> Result<ToolResult> {
+        step(state, args, true)
+    }
+
+    fn get_parameters_schema(&self) -> serde_json::Value {
+        schema(true)
+    }
+}
+
+/// Tool applying the most recently undone operation again
+pub struct RedoTool {
+    name: String,
+}
+
+impl RedoTool {
+    pub fn new() -> Self {
+        Self {
+            name: "redo".to_string(),
+        }
+    }
+}
+
+impl Default for RedoTool {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl Tool for RedoTool {
+    fn name(&self) -> &str {
+        &self.name
+    }
+
+    fn description(&self) -> &str {
+        "Apply the most recently undone operation again. Available until another edit is made; refuses files changed since the undo unless force: true"
+    }
+
+    fn signature(&self) -> &str {
+        "redo(force?: bool)"
+    }
+
+    fn category(&self) -> ToolCategory {
+        ToolCategory::Editing
+    }
+
+    fn danger_level(&self) -> DangerLevel {
+        DangerLevel::ModifiesFiles
+    }
+
+    fn validate_args(&self, _args: &ToolArgs) -> Result<(), ToolError> {
+        Ok(())
+    }
+
+    fn execute(&mut self, args: &ToolArgs, state: &Arc<Mutex<ToolState>>) -> Result<ToolResult> {
+        step(state, args, false)
+    }
+
+    fn get_parameters_schema(&self) -> serde_json::Value {
+        schema(false)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::editing::writer;
+    use std::fs;
+    use tempfile::TempDir;
+
+    #[test]
+    fn test_undo_and_redo_restore_files() {
+        let temp_dir = TempDir::new().unwrap();
+        let (a, b) = (temp_dir.path().join("a.txt"), temp_dir.path().join("b.txt"));
+        fs::write(&a, "one\n").unwrap();
+        let state = Arc::new(Mutex::new(ToolState::new()));
+        state
+            .lock()
+            .unwrap()
+            .open_file(a.clone(), vec!["one".to_string()], 10)
+            .unwrap();
+
+        writer::write_file(&state, &a, "two\n").unwrap();
+        writer::write_file(&state, &a, "three\n").unwrap();
+        writer::write_file(&state, &b, "new\n").unwrap();
+        state
+            .lock()
+            .unwrap()
+            .push_history("edit a and b".to_string());
+        writer::remove_file(&state, &b).unwrap();
+        state.lock().unwrap().push_history("delete b".to_string());
+
+        let mut guard = state.lock().unwrap();
+        let step = guard.undo_last_edit(false).unwrap().unwrap();
+        assert_eq!(step.operation, "delete b");
+        assert_eq!(fs::read_to_string(&b).unwrap(), "new\n");
+        let step = guard.undo_last_edit(false).unwrap().unwrap();
+        assert_eq!(step.files.len(), 2);
+        assert_eq!(fs::read_to_string(&a).unwrap(), "one\n");
+        assert!(!b.exists());
+        assert_eq!(guard.get_current_file_state().unwrap().content, vec!["one"]);
+        assert!(guard.undo_last_edit(false).unwrap().is_none());
+
+        guard.redo_last_undo(false).unwrap().unwrap();
+        assert_eq!(fs::read_to_string(&a).unwrap(), "three\n");
+        assert_eq!(fs::read_to_string(&b).unwrap(), "new\n");
+        assert!(guard.can_redo());
+        drop(guard);
+
+        // A new edit drops what is left to redo
+        writer::write_file(&state, &a, "four\n").unwrap();
+        let mut guard = state.lock().unwrap();
+        guard.push_history("edit a".to_string());
+        assert!(!guard.can_redo());
+        assert!(guard.redo_last_undo(false).unwrap().is_none());
+    }
+
+    #[test]
+    fn test_undo_tool_refuses_changed_files_unless_forced() {
+        let temp_dir = TempDir::new().unwrap();
+        let path = temp_dir.path().join("a.txt");
+        fs::write(&path, "one\n").unwrap();
+        let state = Arc::new(Mutex::new(ToolState::new()));
+        writer::write_file(&state, &path, "two\n").unwrap();
+        fs::write(&path, "changed outside\n").unwrap();
+
+        let mut tool = UndoTool::new();
+        let result = tool.execute(&ToolArgs::from_args(&[]), &state).unwrap();
+        assert!(!result.success);
+        assert!(result.message.contains("Changed since the edit"));
+        assert_eq!(fs::read_to_string(&path).unwrap(), "changed outside\n");
+
+        let args = ToolArgs::with_named_args(
+            vec![],
+            [("force".to_string(), "true".to_string())]
+                .into_iter()
+                .collect(),
+        );
+        let result = tool.execute(&args, &state).unwrap();
+        assert!(result.success, "{}", result.message);
+        assert_eq!(fs::read_to_string(&path).unwrap(), "one\n");
+        let result = tool.execute(&args, &state).unwrap();
+        assert_eq!(result.message, "Nothing to undo");
+
+        let result = RedoTool::new()
+            .execute(&ToolArgs::from_args(&[]), &state)
+            .unwrap();
+        assert!(result.success, "{}", result.message);
+        assert_eq!(fs::read_to_string(&path).unwrap(), "two\n");
+    }
+}
@@ -0,0 +1,159 @@
+//! Streaming tool results
+//!
+//! Long-running tools can hand their output to the caller in chunks while they work,
+//! and still return one consolidated [`ToolResult`](crate::core::ToolResult) at the end.
+//! Run a call with `ToolRegistry::execute_tool_streaming` and a callback. The callback
+//! gets every [`ResultChunk`] as it is produced and answers [`StreamControl::Stop`] to
+//! end the call early. The tool then returns what it has so far. `search_dir` streams
+//! matches file by file, `run_command` streams output lines and `copy` streams copied
+//! files.
+//!
+//! A tool reaches the stream of the running call through `ToolState::stream`. Outside a
+//! streaming call that stream is inactive and sending to it does nothing.
+
+use serde::{Deserialize, Serialize};
+use std::sync::atomic::{AtomicBool, AtomicUsize, Ordering};
+use std::sync::{mpsc, Arc, Mutex};
+
+/// One piece of a tool's output, delivered while the tool runs
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct ResultChunk {
+    /// Tool producing the chunk
+    pub tool: String,
+    /// Position of the chunk in the call, starting at 0
+    pub seq: usize,
+    /// Text of the chunk, in the format of the final message
+    pub content: String,
+    /// Structured form of the chunk, when the tool has one
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub data: Option<serde_json::Value>,
+}
+
+/// Answer of a chunk callback
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum StreamControl {
+    /// Keep the tool running
+    Continue,
+    /// End the call early; the tool returns the output it has so far
+    Stop,
+}
+
+/// Callback receiving the chunks of a streaming call
+pub type ChunkCallback = Box<dyn FnMut(&ResultChunk) -> StreamControl + Send>;
+
+struct StreamInner {
+    tool: String,
+    callback: Mutex<ChunkCallback>,
+    sent: AtomicUsize,
+    stopped: AtomicBool,
+}
+
+/// Where a tool sends its chunks during one call
+///
+/// Cloning shares the stream, so a tool can hand it to worker threads. The default
+/// stream is inactive.
+#[derive(Clone, Default)]
+pub struct ResultStream {
+    inner: Option<Arc<StreamInner>>,
+}
+
+impl ResultStream {
+    /// A stream delivering the chunks of `tool` to `callback`
+    pub fn new(
+        tool: impl Into<String>,
+        callback: impl FnMut(&ResultChunk) -> StreamControl + Send + 'static,
+    ) -> Self {
+        Self {
+            inner: Some(Arc::new(StreamInner {
+                tool: tool.into(),
+                callback: Mutex::new(Box::new(callback)),
+                sent: AtomicUsize::new(0),
+                stopped: AtomicBool::new(false),
+            })),
+        }
+    }
+
+    /// Whether a consumer is listening, so tools can skip building chunks otherwise
+    pub fn is_active(&self) -> bool {
+        self.inner.is_some()
+    }
+
+    /// Whether the consumer asked the tool to stop
+    pub fn is_stopped(&self) -> bool {
+        self.inner
+            .as_ref()
+            .is_some_and(|inner| inner.stopped.load(Ordering::SeqCst))
+    }
+
+    /// Number of chunks delivered so far
+    pub fn chunks_sent(&self) -> usize {
+        self.inner
+            .as_ref()
+            .map_or(0, |inner| inner.sent.load(Ordering::SeqCst))
+    }
+
+    /// Send a text chunk
+    pub fn send(&self, content: impl Into<String>) -> StreamControl {
+        self.deliver(content.into(), None)
+    }
+
+    /// Send a chunk with a structured form
+    pub fn send_with_data(
+        &self,
+        content: impl Into<String>,
+        data: serde_json::Value,
+    ) -> StreamControl {
+        self.deliver(content.into(), Some(data))
+    }
+
+    fn deliver(&self, content: String, data: Option<serde_json::Value>) -> StreamControl {
+        let Some(inner) = &self.inner else {
+            return StreamControl::Continue;
+        };
+        if inner.stopped.load(Ordering::SeqCst) {
+            return StreamControl::Stop;
+        }
+        // A poisoned callback panicked on an earlier chunk; stop instead of retrying
+        let control = match inner.callback.lock() {
+            Ok(mut callback) => {
+                let chunk = ResultChunk {
+                    tool: inner.tool.clone(),
+                    seq: inner.sent.fetch_add(1, Ordering::SeqCst),
+                    content,
+                    data,
+                };
+                callback(&chunk)
+            }
+            Err(_) => StreamControl::Stop,
+        };
+        if control == StreamControl::Stop {
+            inner.stopped.store(true, Ordering::SeqCst);
+        }
+        control
+    }
+}
+
+impl std::fmt::Debug for ResultStream {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("ResultStream")
+            .field("active", &self.is_active())
+            .field("chunks_sent", &self.chunks_sent())
+            .field("stopped", &self.is_stopped())
+            .finish()
+    }
+}
+
+/// A chunk callback forwarding into a channel, for consumers on another thread
+///
+/// Dropping the receiver stops the tool at its next chunk.
+pub fn channel() -> (
+    impl FnMut(&ResultChunk) -> StreamControl + Send + 'static,
+    mpsc::Receiver<ResultChunk>,
+) {
+    let (sender, receiver) = mpsc::channel();
+    let callback = move |chunk: &ResultChunk| match sender.send(chunk.clone()) {
+        Ok(()) => StreamControl::Continue,
+        Err(_) => StreamControl::Stop,
+    };
+    (callback, receiver)
+}
@@ -0,0 +1,177 @@
+//! Synthetic source trees for benchmarks and search filter tuning
+//!
+//! [`generate_corpus`] writes a seeded tree of source files spread over nested
+//! directories, plus the kind of noise real repositories carry: build output and
+//! dependency directories (`target`, `node_modules`, `dist`), hidden directories and
+//! binary assets. The benches in `benches/` search and edit such trees; running
+//! `search_dir` over one with a candidate `SearchFilteringConfig` shows how much of
+//! the noise a filter keeps out and what it costs.
+
+use super::{FileOptions, Generator};
+use std::io;
+use std::path::{Path, PathBuf};
+
+/// Directories that default search filters exclude, used for noise files
+const NOISE_DIRS: &[&str] = &[
+    "target/debug",
+    "node_modules/dep/lib",
+    "dist",
+    ".git/objects",
+];
+
+const EXTENSIONS: &[&str] = &["rs", "py", "ts", "go", "md"];
+
+/// Shape of a generated corpus
+#[derive(Debug, Clone)]
+pub struct CorpusOptions {
+    /// Source files outside noise directories
+    pub source_files: usize,
+    /// Nesting depth of source directories
+    pub depth: usize,
+    /// Subdirectories per directory
+    pub fan_out: usize,
+    pub lines_per_file: usize,
+    /// Text files inside directories default filters exclude
+    pub noise_files: usize,
+    /// Binary files (`.png`, `.so`) among the source files
+    pub binary_files: usize,
+    /// Token planted in every `needle_every`-th source file, so searches have hits
+    pub needle: String,
+    pub needle_every: usize,
+    pub seed: u64,
+}
+
+impl Default for CorpusOptions {
+    fn default() -> Self {
+        Self {
+            source_files: 500,
+            depth: 3,
+            fan_out: 4,
+            lines_per_file: 80,
+            noise_files: 500,
+            binary_files: 20,
+            needle: "CATS_NEEDLE".to_string(),
+            needle_every: 25,
+            seed: 0,
+        }
+    }
+}
+
+/// What [`generate_corpus`] wrote
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct CorpusStats {
+    pub source_files: usize,
+    pub noise_files: usize,
+    pub binary_files: usize,
+    /// Source files containing the needle
+    pub needle_files: usize,
+    pub bytes: u64,
+}
+
+/// Directory of the `index`-th file: a path `depth` levels down, spreading files
+/// across `fan_out` subdirectories per level
+fn directory(index: usize, options: &CorpusOptions) -> PathBuf {
+    let fan_out = options.fan_out.max(1);
+    let mut dir = PathBuf::from("src");
+    let mut rest = index;
+    for level in 0..options.depth {
+        dir.push(format!("m{}_{}", level, rest % fan_out));
+        rest /= fan_out;
+    }
+    dir
+}
+
+/// Write a corpus under `root`, creating it if needed
+pub fn generate_corpus(root: &Path, options: &CorpusOptions) -> io::Result<CorpusStats> {
+    let mut generator = Generator::new(options.seed);
+    let file_options = FileOptions {
+        max_lines: options.lines_per_file,
+        ..FileOptions::default()
+    };
+    let mut stats = CorpusStats::default();
+    let write = |path: PathBuf, contents: &[u8], stats: &mut CorpusStats| {
+        if let Some(parent) = path.parent() {
+            std::fs::create_dir_all(parent)?;
+        }
+        stats.bytes += contents.len() as u64;
+        std::fs::write(path, contents)
+    };
+
+    for index in 0..options.source_files {
+        let extension = EXTENSIONS[index % EXTENSIONS.len()];
+        let path = root
+            .join(directory(index, options))
+            .join(format!("file{}.{}", index, extension));
+        let mut contents = generator.file(&file_options);
+        if options.needle_every > 0 && index % options.needle_every == 0 {
+            contents.push_str(&format!("// {}\n", options.needle));
+            stats.needle_files += 1;
+        }
+        write(path, contents.as_bytes(), &mut stats)?;
+        stats.source_files += 1;
+    }
+
+    for index in 0..options.noise_files {
+        let dir = NOISE_DIRS[index % NOISE_DIRS.len()];
+        // Noise mentions the needle too, so unfiltered searches report extra hits
+        let contents = format!("{}// {}\n", generator.file(&file_options), options.needle);
+        write(
+            root.join(dir).join(format!("noise{}.js", index)),
+            contents.as_bytes(),
+            &mut stats,
+        )?;
+        stats.noise_files += 1;
+    }
+
+    for index in 0..options.binary_files {
+        let extension = if index % 2 == 0 { "png" } else { "so" };
+        let mut contents = vec![0u8; 4096];
+        for (i, byte) in contents.iter_mut().enumerate() {
+            *byte = (i.wrapping_mul(31) ^ index) as u8;
+        }
+        write(
+            root.join(directory(index, options))
+                .join(format!("asset{}.{}", index, extension)),
+            &contents,
+            &mut stats,
+        )?;
+        stats.binary_files += 1;
+    }
+    Ok(stats)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::core::{Tool, ToolArgs};
+    use crate::search::SearchDirTool;
+    use crate::state::ToolState;
+    use std::sync::{Arc, Mutex};
+
+    #[test]
+    fn test_generate_corpus_with_noise() {
+        let temp = tempfile::TempDir::new().unwrap();
+        let options = CorpusOptions {
+            source_files: 50,
+            noise_files: 12,
+            binary_files: 4,
+            lines_per_file: 10,
+            needle_every: 10,
+            ..CorpusOptions::default()
+        };
+        let stats = generate_corpus(temp.path(), &options).unwrap();
+        assert_eq!(stats.source_files, 50);
+        assert_eq!(stats.needle_files, 5);
+        assert!(temp.path().join("node_modules/dep/lib/noise1.js").exists());
+
+        // Default filters keep the search to the planted source files
+        let state = Arc::new(Mutex::new(ToolState::new()));
+        let root = temp.path().to_string_lossy().to_string();
+        let result = SearchDirTool::new()
+            .execute(&ToolArgs::from_args(&["CATS_NEEDLE", &root]), &state)
+            .unwrap();
+        assert!(result.success, "{}", result.message);
+        let matches = result.data.unwrap()["total_matches"].as_u64();
+        assert_eq!(matches, Some(5));
+    }
+}
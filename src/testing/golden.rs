@@ -0,0 +1,317 @@
+//! Golden-file tests for scripted tool sessions
+//!
+//! A case is a directory holding a fixture tree, a script of tool calls and the tree
+//! expected after running the script:
+//!
+//! ```text
+//! my_case/
+//!   before/            fixture tree, copied to a temporary directory
+//!   script.jsonl       one `execute_json_line` request per line
+//!   after/             expected tree
+//!   transcript.jsonl   optional: expected `tool`, `success` and `message` per call
+//! ```
+//!
+//! Tools resolve paths against the process directory, so scripts refer to the copied
+//! fixture as `$ROOT` (e.g. `{"tool": "replace_text", "args": {"path": "$ROOT/a.rs",
+//! ...}}`); the same placeholder replaces the temporary path in the transcript. Blank
+//! lines and lines starting with `#` in the script are skipped. Only files are compared,
+//! and the `.cats` directory tools keep their own state in is ignored.
+//!
+//! Setting `CATS_UPDATE_GOLDEN=1` (or [`GoldenTest::update`]) rewrites `after/` and
+//! `transcript.jsonl` from the actual results instead of comparing.
+
+use crate::core::ToolRegistry;
+use crate::editing::diff::unified_diff;
+use crate::llm::executor::execute_json_line;
+use std::collections::BTreeMap;
+use std::fmt;
+use std::path::{Path, PathBuf};
+use walkdir::WalkDir;
+
+/// Placeholder for the fixture copy in scripts and transcripts
+pub const ROOT_PLACEHOLDER: &str = "$ROOT";
+
+/// Environment variable that switches golden tests to update mode
+pub const UPDATE_ENV: &str = "CATS_UPDATE_GOLDEN";
+
+const STATE_DIR: &str = ".cats";
+
+/// A difference between the expected and the actual result of a case
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum Difference {
+    /// An expected file was not produced
+    Missing(PathBuf),
+    /// A file was produced that is not expected
+    Unexpected(PathBuf),
+    /// A file differs; `diff` is a unified diff from expected to actual
+    Changed { path: PathBuf, diff: String },
+    /// The transcript differs; `diff` is a unified diff from expected to actual
+    Transcript { diff: String },
+}
+
+impl fmt::Display for Difference {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Difference::Missing(path) => write!(f, "missing file {}", path.display()),
+            Difference::Unexpected(path) => write!(f, "unexpected file {}", path.display()),
+            Difference::Changed { path, diff } => {
+                write!(f, "file {} differs:\n{}", path.display(), diff)
+            }
+            Difference::Transcript { diff } => write!(f, "transcript differs:\n{}", diff),
+        }
+    }
+}
+
+/// Why a golden case failed
+#[derive(Debug, thiserror::Error)]
+pub enum GoldenError {
+    #[error("{}: {source}", path.display())]
+    Io {
+        path: PathBuf,
+        source: std::io::Error,
+    },
+    #[error("{case}: {} difference(s) from the golden output\n{}", differences.len(), render(differences))]
+    Mismatch {
+        case: PathBuf,
+        differences: Vec<Difference>,
+    },
+}
+
+fn render(differences: &[Difference]) -> String {
+    differences
+        .iter()
+        .map(|d| d.to_string())
+        .collect::<Vec<_>>()
+        .join("\n")
+}
+
+fn io_error(path: &Path) -> impl FnOnce(std::io::Error) -> GoldenError + '_ {
+    move |source| GoldenError::Io {
+        path: path.to_path_buf(),
+        source,
+    }
+}
+
+/// A golden-file test case
+#[derive(Debug, Clone)]
+pub struct GoldenTest {
+    case: PathBuf,
+    update: bool,
+}
+
+impl GoldenTest {
+    /// The case in directory `case`; update mode follows `CATS_UPDATE_GOLDEN`
+    pub fn new(case: impl Into<PathBuf>) -> Self {
+        let update = std::env::var(UPDATE_ENV).is_ok_and(|v| !v.is_empty() && v != "0");
+        Self {
+            case: case.into(),
+            update,
+        }
+    }
+
+    /// Rewrite the golden outputs instead of comparing against them
+    pub fn update(mut self, update: bool) -> Self {
+        self.update = update;
+        self
+    }
+
+    /// Run the script with `registry` and compare the resulting tree and transcript
+    /// against the golden outputs
+    pub fn run(&self, registry: &mut ToolRegistry) -> Result<(), GoldenError> {
+        let workdir = tempfile::TempDir::new().map_err(io_error(&self.case))?;
+        let root = workdir.path().join("root");
+        copy_tree(&self.case.join("before"), &root)?;
+        let transcript = self.run_script(registry, &root)?;
+
+        let after = self.case.join("after");
+        let transcript_path = self.case.join("transcript.jsonl");
+        if self.update {
+            if after.exists() {
+                std::fs::remove_dir_all(&after).map_err(io_error(&after))?;
+            }
+            copy_tree(&root, &after)?;
+            return std::fs::write(&transcript_path, transcript)
+                .map_err(io_error(&transcript_path));
+        }
+
+        let mut differences = compare_trees(&read_tree(&after)?, &read_tree(&root)?);
+        if transcript_path.exists() {
+            let expected =
+                std::fs::read_to_string(&transcript_path).map_err(io_error(&transcript_path))?;
+            if expected != transcript {
+                differences.push(Difference::Transcript {
+                    diff: unified_diff(&expected, &transcript, "transcript.jsonl"),
+                });
+            }
+        }
+        if differences.is_empty() {
+            Ok(())
+        } else {
+            Err(GoldenError::Mismatch {
+                case: self.case.clone(),
+                differences,
+            })
+        }
+    }
+
+    /// Run every call of the script, returning the transcript
+    fn run_script(&self, registry: &mut ToolRegistry, root: &Path) -> Result<String, GoldenError> {
+        let script_path = self.case.join("script.jsonl");
+        let script = std::fs::read_to_string(&script_path).map_err(io_error(&script_path))?;
+        if let Ok(mut state) = registry.get_state().lock() {
+            state.set_working_directory(root);
+        }
+        // The placeholder is substituted inside JSON strings, so escape the path for JSON
+        let root_str = root.to_string_lossy().to_string();
+        let escaped = serde_json::to_string(&root_str).unwrap_or_default();
+        let escaped = escaped.trim_matches('"');
+
+        let mut transcript = String::new();
+        for line in script.lines() {
+            let line = line.trim();
+            if line.is_empty() || line.starts_with('#') {
+                continue;
+            }
+            let response = execute_json_line(registry, &line.replace(ROOT_PLACEHOLDER, escaped));
+            let entry = serde_json::json!({
+                "tool": response.get("tool"),
+                "success": response.get("success"),
+                "message": response
+                    .get("message")
+                    .or_else(|| response.pointer("/error/message")),
+            });
+            transcript.push_str(&entry.to_string().replace(escaped, ROOT_PLACEHOLDER));
+            transcript.push('\n');
+        }
+        Ok(transcript)
+    }
+}
+
+/// Run the golden case in `case` with `registry`, panicking with a diff report on failure
+pub fn assert_golden(case: impl Into<PathBuf>, registry: &mut ToolRegistry) {
+    if let Err(e) = GoldenTest::new(case).run(registry) {
+        panic!("{}", e);
+    }
+}
+
+/// Files under `root` by relative path, skipping the `.cats` state directory
+fn read_tree(root: &Path) -> Result<BTreeMap<PathBuf, Vec<u8>>, GoldenError> {
+    let mut files = BTreeMap::new();
+    if !root.exists() {
+        return Ok(files);
+    }
+    let walker = WalkDir::new(root)
+        .sort_by_file_name()
+        .into_iter()
+        .filter_entry(|e| e.depth() == 0 || e.file_name() != STATE_DIR);
+    for entry in walker {
+        let entry = entry.map_err(|e| GoldenError::Io {
+            path: root.to_path_buf(),
+            source: e.into(),
+        })?;
+        if entry.file_type().is_file() {
+            let relative = entry.path().strip_prefix(root).unwrap_or(entry.path());
+            let contents = std::fs::read(entry.path()).map_err(io_error(entry.path()))?;
+            files.insert(relative.to_path_buf(), contents);
+        }
+    }
+    Ok(files)
+}
+
+fn copy_tree(from: &Path, to: &Path) -> Result<(), GoldenError> {
+    std::fs::create_dir_all(to).map_err(io_error(to))?;
+    for (relative, contents) in read_tree(from)? {
+        let target = to.join(&relative);
+        if let Some(parent) = target.parent() {
+            std::fs::create_dir_all(parent).map_err(io_error(parent))?;
+        }
+        std::fs::write(&target, contents).map_err(io_error(&target))?;
+    }
+    Ok(())
+}
+
+fn compare_trees(
+    expected: &BTreeMap<PathBuf, Vec<u8>>,
+    actual: &BTreeMap<PathBuf, Vec<u8>>,
+) -> Vec<Difference> {
+    let mut differences = Vec::new();
+    for (path, want) in expected {
+        match actual.get(path) {
+            None => differences.push(Difference::Missing(path.clone())),
+            Some(got) if got != want => {
+                let diff = match (std::str::from_utf8(want), std::str::from_utf8(got)) {
+                    (Ok(want), Ok(got)) => unified_diff(want, got, &path.to_string_lossy()),
+                    _ => "binary files differ\n".to_string(),
+                };
+                differences.push(Difference::Changed {
+                    path: path.clone(),
+                    diff,
+                });
+            }
+            Some(_) => {}
+        }
+    }
+    for path in actual.keys().filter(|p| !expected.contains_key(*p)) {
+        differences.push(Difference::Unexpected(path.clone()));
+    }
+    differences
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::create_tool_registry;
+    use tempfile::TempDir;
+
+    fn write(path: &Path, contents: &str) {
+        std::fs::create_dir_all(path.parent().unwrap()).unwrap();
+        std::fs::write(path, contents).unwrap();
+    }
+
+    #[test]
+    fn test_golden_case_compares_and_updates() {
+        let temp = TempDir::new().unwrap();
+        let case = temp.path().join("rename_value");
+        write(&case.join("before/src/lib.rs"), "let value = 1;\n");
+        write(&case.join("before/README.md"), "untouched\n");
+        write(
+            &case.join("script.jsonl"),
+            "# rename the binding\n\
+             {\"tool\": \"replace_text\", \"args\": {\"path\": \"$ROOT/src/lib.rs\", \"old_text\": \"value\", \"new_text\": \"total\"}}\n",
+        );
+
+        // Update mode records the outputs, after which the case passes
+        let mut registry = create_tool_registry();
+        GoldenTest::new(&case)
+            .update(true)
+            .run(&mut registry)
+            .unwrap();
+        assert_eq!(
+            std::fs::read_to_string(case.join("after/src/lib.rs")).unwrap(),
+            "let total = 1;\n"
+        );
+        let transcript = std::fs::read_to_string(case.join("transcript.jsonl")).unwrap();
+        assert!(transcript.contains("\"success\":true"));
+        assert!(!transcript.contains(&temp.path().to_string_lossy().to_string()));
+        GoldenTest::new(&case)
+            .update(false)
+            .run(&mut create_tool_registry())
+            .unwrap();
+
+        // A wrong expectation is reported with a diff
+        write(&case.join("after/src/lib.rs"), "let sum = 1;\n");
+        write(&case.join("after/extra.txt"), "x\n");
+        std::fs::remove_file(case.join("after/README.md")).unwrap();
+        let err = GoldenTest::new(&case)
+            .update(false)
+            .run(&mut create_tool_registry())
+            .unwrap_err();
+        let GoldenError::Mismatch { differences, .. } = &err else {
+            panic!("expected a mismatch, got {}", err);
+        };
+        assert_eq!(differences.len(), 3);
+        assert!(err.to_string().contains("-let sum = 1;\n+let total = 1;"));
+        assert!(differences.contains(&Difference::Missing(PathBuf::from("extra.txt"))));
+        assert!(differences.contains(&Difference::Unexpected(PathBuf::from("README.md"))));
+    }
+}
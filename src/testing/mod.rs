@@ -0,0 +1,463 @@
+//! Test support for tool authors
+//!
+//! Seeded generators for random source files and text edits, invariant checks for
+//! editing tools, and [`check_edit_tool`], which runs a file-editing tool against many
+//! generated files and reports the first case that breaks an invariant together with
+//! the seed that reproduces it. [`golden`] runs scripted tool sessions against fixture
+//! trees and compares the result with golden outputs. With the `proptest` feature, [`strategies`] wraps the
+//! generators as proptest strategies; the `fuzz/` directory of the repository holds
+//! cargo-fuzz targets for the span and normalization layers.
+//!
+//! ```
+//! use cats::testing::{check_edit_tool, TextEdit};
+//! use cats::{ReplaceTextTool, ToolArgs};
+//! use std::path::Path;
+//!
+//! let mut tool = ReplaceTextTool::new();
+//! let args = |path: &Path, edit: &mut TextEdit| {
+//!     ToolArgs::from_args(&[&path.to_string_lossy(), &edit.old_text, &edit.new_text])
+//! };
+//! check_edit_tool(&mut tool, args, 20, 7).unwrap();
+//! ```
+
+pub mod corpus;
+pub mod golden;
+#[cfg(feature = "proptest")]
+pub mod strategies;
+
+pub use corpus::{generate_corpus, CorpusOptions};
+pub use golden::{assert_golden, GoldenTest};
+
+use crate::core::{Tool, ToolArgs};
+use crate::editing::span::Span;
+use crate::state::ToolState;
+use std::fmt;
+use std::path::Path;
+use std::sync::{Arc, Mutex};
+
+/// Line ending style of a file
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Eol {
+    Lf,
+    CrLf,
+}
+
+impl Eol {
+    pub fn as_str(self) -> &'static str {
+        match self {
+            Eol::Lf => "\n",
+            Eol::CrLf => "\r\n",
+        }
+    }
+
+    /// The line ending used by every line break of `text`, or `None` for text without
+    /// line breaks or with mixed endings
+    pub fn detect(text: &str) -> Option<Self> {
+        let breaks = text.matches('\n').count();
+        let crlf = text.matches("\r\n").count();
+        match (breaks, crlf) {
+            (0, _) => None,
+            (b, c) if b == c => Some(Eol::CrLf),
+            (_, 0) => Some(Eol::Lf),
+            _ => None,
+        }
+    }
+}
+
+/// Shape of generated files
+#[derive(Debug, Clone)]
+pub struct FileOptions {
+    pub max_lines: usize,
+    pub max_line_len: usize,
+    /// Mix accented, CJK and emoji characters into lines
+    pub unicode: bool,
+    /// Line ending to use; random per file when `None`
+    pub eol: Option<Eol>,
+    /// Whether the file ends with a line ending; random per file when `None`
+    pub trailing_newline: Option<bool>,
+}
+
+impl Default for FileOptions {
+    fn default() -> Self {
+        Self {
+            max_lines: 40,
+            max_line_len: 60,
+            unicode: true,
+            eol: None,
+            trailing_newline: None,
+        }
+    }
+}
+
+/// A replacement of `old_text`, which occurs exactly once in the file, by `new_text`
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct TextEdit {
+    pub old_text: String,
+    pub new_text: String,
+    /// Where `old_text` occurs in the original file
+    pub span: Span,
+}
+
+const ASCII: &[&str] = &[
+    "fn", "let", "x", "value", "_", "0", "42", " ", " ", "(", ")", "{", "}", ";", "=", ",", ".",
+    "\"", "'", "\\", "//", "->", "::", "<", ">", "+",
+];
+const UNICODE: &[&str] = &[
+    "é",
+    "ß",
+    "Ω",
+    "日本",
+    "語",
+    "🚀",
+    "✅",
+    "👩‍💻",
+    "\u{301}",
+    "İ",
+];
+const INDENTS: &[&str] = &["", "", "    ", "        ", "\t"];
+
+/// Seeded generator of files and edits; the same seed always yields the same values
+pub struct Generator {
+    rng: fastrand::Rng,
+    seed: u64,
+}
+
+impl Generator {
+    pub fn new(seed: u64) -> Self {
+        Self {
+            rng: fastrand::Rng::with_seed(seed),
+            seed,
+        }
+    }
+
+    /// The seed this generator was created with
+    pub fn seed(&self) -> u64 {
+        self.seed
+    }
+
+    /// A single line without a line ending
+    pub fn line(&mut self, options: &FileOptions) -> String {
+        let mut line = INDENTS[self.rng.usize(..INDENTS.len())].to_string();
+        let target = self.rng.usize(0..=options.max_line_len);
+        while line.chars().count() < target {
+            let pool = if options.unicode && self.rng.u8(..8) == 0 {
+                UNICODE
+            } else {
+                ASCII
+            };
+            line.push_str(pool[self.rng.usize(..pool.len())]);
+        }
+        // Occasional trailing whitespace, which editors like to strip
+        if self.rng.u8(..6) == 0 {
+            line.push_str(if self.rng.bool() { " " } else { "\t " });
+        }
+        line
+    }
+
+    /// A file of random lines
+    pub fn file(&mut self, options: &FileOptions) -> String {
+        let eol = options
+            .eol
+            .unwrap_or(if self.rng.bool() { Eol::Lf } else { Eol::CrLf });
+        let trailing = options
+            .trailing_newline
+            .unwrap_or_else(|| self.rng.u8(..4) != 0);
+        let count = self.rng.usize(1..=options.max_lines.max(1));
+        let lines: Vec<String> = (0..count).map(|_| self.line(options)).collect();
+        let mut file = lines.join(eol.as_str());
+        if trailing {
+            file.push_str(eol.as_str());
+        }
+        file
+    }
+
+    /// Text to insert, without line breaks
+    pub fn replacement(&mut self) -> String {
+        let options = FileOptions {
+            max_line_len: 12,
+            ..FileOptions::default()
+        };
+        self.line(&options).trim_start().to_string()
+    }
+
+    /// An edit replacing a substring of one line that occurs exactly once in `content`
+    ///
+    /// Returns `None` when no unique substring was found within a few attempts.
+    pub fn edit(&mut self, content: &str) -> Option<TextEdit> {
+        let lines: Vec<&str> = content.lines().filter(|l| !l.trim().is_empty()).collect();
+        if lines.is_empty() {
+            return None;
+        }
+        for _ in 0..20 {
+            let line = lines[self.rng.usize(..lines.len())].trim_end_matches('\r');
+            let chars: Vec<(usize, char)> = line.char_indices().collect();
+            if chars.is_empty() {
+                continue;
+            }
+            let first = self.rng.usize(..chars.len());
+            let last = self.rng.usize(first..chars.len());
+            let start = chars[first].0;
+            let end = chars[last].0 + chars[last].1.len_utf8();
+            let old_text = &line[start..end];
+            if old_text.trim().is_empty() || content.matches(old_text).count() != 1 {
+                continue;
+            }
+            let offset = content.find(old_text)?;
+            return Some(TextEdit {
+                old_text: old_text.to_string(),
+                new_text: self.replacement(),
+                span: Span::new(content, offset, offset + old_text.len())?,
+            });
+        }
+        None
+    }
+}
+
+/// An invariant an edit broke
+#[derive(Debug, Clone, PartialEq, Eq, thiserror::Error)]
+pub enum InvariantViolation {
+    #[error("text before the edited span changed")]
+    PrefixChanged,
+    #[error("text after the edited span changed")]
+    SuffixChanged,
+    #[error("edited span is {actual:?}, expected {expected:?}")]
+    WrongReplacement { expected: String, actual: String },
+    #[error("line endings changed from {before:?} to {after:?}")]
+    EolChanged {
+        before: Option<Eol>,
+        after: Option<Eol>,
+    },
+    #[error("applying twice gave {second:?} instead of {first:?}")]
+    NotIdempotent { first: String, second: String },
+}
+
+/// Check that `after` is `before` with exactly `edit` applied: the text outside the
+/// edited span is byte-for-byte unchanged and the span holds the new text
+pub fn check_edit(before: &str, after: &str, edit: &TextEdit) -> Result<(), InvariantViolation> {
+    let prefix = &before[..edit.span.start()];
+    let suffix = &before[edit.span.end()..];
+    if !after.starts_with(prefix) {
+        return Err(InvariantViolation::PrefixChanged);
+    }
+    if !after.ends_with(suffix) || after.len() < prefix.len() + suffix.len() {
+        return Err(InvariantViolation::SuffixChanged);
+    }
+    let actual = &after[prefix.len()..after.len() - suffix.len()];
+    if actual != edit.new_text {
+        return Err(InvariantViolation::WrongReplacement {
+            expected: edit.new_text.clone(),
+            actual: actual.to_string(),
+        });
+    }
+    Ok(())
+}
+
+/// Check that an edit kept the line ending style of a file
+pub fn check_eol_preserved(before: &str, after: &str) -> Result<(), InvariantViolation> {
+    let (old, new) = (Eol::detect(before), Eol::detect(after));
+    // Removing the only line break is not a style change
+    if old.is_some() && new.is_some() && old != new {
+        return Err(InvariantViolation::EolChanged {
+            before: old,
+            after: new,
+        });
+    }
+    Ok(())
+}
+
+/// Check that applying `f` to its own output changes nothing
+pub fn check_idempotent(
+    input: &str,
+    mut f: impl FnMut(&str) -> String,
+) -> Result<(), InvariantViolation> {
+    let first = f(input);
+    let second = f(&first);
+    if first != second {
+        return Err(InvariantViolation::NotIdempotent { first, second });
+    }
+    Ok(())
+}
+
+/// A generated case that failed, with everything needed to reproduce it
+#[derive(Debug, Clone)]
+pub struct CaseFailure {
+    /// Seed of the failing case; `Generator::new(seed)` regenerates its file and edit
+    pub seed: u64,
+    pub content: String,
+    pub edit: TextEdit,
+    pub reason: String,
+}
+
+impl fmt::Display for CaseFailure {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(
+            f,
+            "case with seed {} failed: {}\n  old_text: {:?}\n  new_text: {:?}\n  content: {:?}",
+            self.seed, self.reason, self.edit.old_text, self.edit.new_text, self.content
+        )
+    }
+}
+
+impl std::error::Error for CaseFailure {}
+
+/// Run `tool` on `cases` generated files, each with one generated edit, and check that
+/// only the edited span changed and the line endings were kept
+///
+/// `args` builds the tool arguments for the file path and edit, and may adjust the edit
+/// to what the tool is expected to do, e.g. clear `new_text` for a deletion. Case `i`
+/// uses seed `seed + i`, so a reported failure can be replayed on its own.
+pub fn check_edit_tool(
+    tool: &mut dyn Tool,
+    args: impl Fn(&Path, &mut TextEdit) -> ToolArgs,
+    cases: u64,
+    seed: u64,
+) -> Result<(), CaseFailure> {
+    let dir = tempfile::TempDir::new().expect("create temporary directory");
+    let path = dir.path().join("case.txt");
+    let options = FileOptions::default();
+
+    for case in 0..cases {
+        let case_seed = seed.wrapping_add(case);
+        let mut generator = Generator::new(case_seed);
+        let content = generator.file(&options);
+        let Some(mut edit) = generator.edit(&content) else {
+            continue;
+        };
+        let tool_args = args(&path, &mut edit);
+        let fail = |reason: String| CaseFailure {
+            seed: case_seed,
+            content: content.clone(),
+            edit: edit.clone(),
+            reason,
+        };
+
+        std::fs::write(&path, &content).map_err(|e| fail(e.to_string()))?;
+        let state = Arc::new(Mutex::new(ToolState::new()));
+        if let Ok(mut guard) = state.lock() {
+            guard.working_directory = dir.path().to_path_buf();
+        }
+        tool.validate_args(&tool_args)
+            .map_err(|e| fail(format!("invalid arguments: {}", e)))?;
+        let result = tool
+            .execute(&tool_args, &state)
+            .map_err(|e| fail(format!("tool error: {}", e)))?;
+        if !result.success {
+            return Err(fail(format!("tool failed: {}", result.message)));
+        }
+
+        let after = std::fs::read_to_string(&path).map_err(|e| fail(e.to_string()))?;
+        check_edit(&content, &after, &edit).map_err(|e| fail(e.to_string()))?;
+        check_eol_preserved(&content, &after).map_err(|e| fail(e.to_string()))?;
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::editing::{DeleteTextTool, NormalizationOptions, NormalizedText, ReplaceTextTool};
+
+    #[test]
+    fn test_generators_are_deterministic() {
+        let options = FileOptions::default();
+        let a = Generator::new(11).file(&options);
+        assert_eq!(a, Generator::new(11).file(&options));
+
+        let crlf = FileOptions {
+            eol: Some(Eol::CrLf),
+            trailing_newline: Some(true),
+            max_lines: 5,
+            ..FileOptions::default()
+        };
+        let mut generator = Generator::new(3);
+        let file = generator.file(&crlf);
+        assert!(file.ends_with("\r\n"));
+        assert_eq!(Eol::detect(&file), Some(Eol::CrLf));
+        if let Some(edit) = generator.edit(&file) {
+            assert_eq!(edit.span.slice(&file), edit.old_text);
+            assert_eq!(file.matches(&edit.old_text).count(), 1);
+        }
+    }
+
+    #[test]
+    fn test_invariant_checks() {
+        let before = "a = 1;\nb = 2;\n";
+        let edit = TextEdit {
+            old_text: "1".to_string(),
+            new_text: "10".to_string(),
+            span: Span::new(before, 4, 5).unwrap(),
+        };
+        assert_eq!(check_edit(before, "a = 10;\nb = 2;\n", &edit), Ok(()));
+        assert_eq!(
+            check_edit(before, "a = 10;\nb = 3;\n", &edit),
+            Err(InvariantViolation::SuffixChanged)
+        );
+        assert!(check_eol_preserved(before, "a = 10;\r\nb = 2;\r\n").is_err());
+        assert!(check_idempotent("x", |s| format!("{}!", s)).is_err());
+    }
+
+    #[test]
+    fn test_editing_tools_hold_invariants() {
+        let mut replace = ReplaceTextTool::new();
+        check_edit_tool(
+            &mut replace,
+            |path, edit| {
+                ToolArgs::from_args(&[&path.to_string_lossy(), &edit.old_text, &edit.new_text])
+            },
+            200,
+            0,
+        )
+        .unwrap_or_else(|failure| panic!("{}", failure));
+
+        let mut delete = DeleteTextTool::new();
+        check_edit_tool(
+            &mut delete,
+            |path, edit| {
+                edit.new_text.clear();
+                ToolArgs::from_args(&[&path.to_string_lossy(), &edit.old_text])
+            },
+            200,
+            1_000,
+        )
+        .unwrap_or_else(|failure| panic!("{}", failure));
+    }
+
+    #[test]
+    fn test_normalization_is_idempotent() {
+        let none = NormalizationOptions {
+            normalize_eol: false,
+            trim_lines: false,
+            normalize_whitespace: false,
+            ignore_case: false,
+        };
+        // Each pass on its own; trim_lines followed by normalize_whitespace is not
+        // idempotent because collapsing turns line breaks into trailing spaces
+        let passes = [
+            NormalizationOptions {
+                normalize_eol: true,
+                ..none.clone()
+            },
+            NormalizationOptions {
+                trim_lines: true,
+                ..none.clone()
+            },
+            NormalizationOptions {
+                normalize_whitespace: true,
+                ..none.clone()
+            },
+            NormalizationOptions {
+                ignore_case: true,
+                ..none.clone()
+            },
+        ];
+        for options in &passes {
+            for seed in 0..100 {
+                let file = Generator::new(seed).file(&FileOptions::default());
+                check_idempotent(&file, |text| {
+                    NormalizedText::new(text, options).into_string()
+                })
+                .unwrap_or_else(|e| panic!("{:?}, seed {}: {}", options, seed, e));
+            }
+        }
+    }
+}
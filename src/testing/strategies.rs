@@ -0,0 +1,38 @@
+//! proptest strategies built on the seeded [`Generator`]
+//!
+//! Shrinking works on the seed, so a failing case shrinks towards small seeds rather
+//! than smaller files; the reported seed still reproduces the case exactly.
+
+use super::{FileOptions, Generator, TextEdit};
+use proptest::prelude::*;
+
+/// Files shaped by `options`
+pub fn file(options: FileOptions) -> impl Strategy<Value = String> {
+    any::<u64>().prop_map(move |seed| Generator::new(seed).file(&options))
+}
+
+/// Files with default options, each paired with an edit of a unique substring
+pub fn file_with_edit() -> impl Strategy<Value = (String, TextEdit)> {
+    any::<u64>().prop_filter_map("no unique substring to edit", |seed| {
+        let mut generator = Generator::new(seed);
+        let content = generator.file(&FileOptions::default());
+        let edit = generator.edit(&content)?;
+        Some((content, edit))
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::testing::{check_edit, check_eol_preserved};
+
+    proptest! {
+        #[test]
+        fn test_span_replace_holds_invariants((content, edit) in file_with_edit()) {
+            let mut after = content.clone();
+            prop_assert!(edit.span.replace(&mut after, &edit.new_text));
+            prop_assert_eq!(check_edit(&content, &after, &edit), Ok(()));
+            prop_assert_eq!(check_eol_preserved(&content, &after), Ok(()));
+        }
+    }
+}
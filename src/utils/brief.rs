@@ -0,0 +1,797 @@
+//! repo_brief tool: a first look at an unfamiliar repository
+//!
+//! Combines what the project says about itself (manifest name and description, the
+//! opening of its README), what it is made of (languages by file count, top-level
+//! layout, entry points) and how it is worked on (test command, task runner tasks,
+//! workspace packages, CI jobs, direct dependencies) into one document kept under a
+//! token budget. Sections are added in that order and the ones that no longer fit are
+//! named at the end, so a small budget still gets the overview.
+
+#[cfg(feature = "yaml")]
+use super::ci_info::{find_ci_files, parse_ci_config};
+use super::dependencies::{find_lockfiles, parse_lockfile};
+use super::tasks::{find_task_files, parse_task_file};
+use super::workspace::Workspace;
+use crate::core::{DangerLevel, Tool, ToolArgs, ToolError, ToolResult};
+use crate::execution::RunTestsTool;
+use crate::llm::result_handler::estimate_tokens;
+use crate::search::ConfigurableFilter;
+use crate::state::ToolState;
+use crate::utils::suggest::path_not_found;
+use anyhow::Result;
+use serde::Serialize;
+use std::collections::BTreeMap;
+use std::fs;
+use std::path::{Path, PathBuf};
+use std::sync::{Arc, Mutex};
+use walkdir::WalkDir;
+
+/// Token budget of the brief by default
+const DEFAULT_MAX_TOKENS: usize = 1500;
+
+/// Bounds for `--max_tokens`
+const MIN_MAX_TOKENS: usize = 200;
+const MAX_MAX_TOKENS: usize = 8000;
+
+/// Tokens kept free for the notes on what did not fit
+const RESERVED_TOKENS: usize = 40;
+
+/// Files counted for the language and layout statistics
+const MAX_SCANNED_FILES: usize = 20_000;
+
+/// Characters of README text kept after its title
+const README_CHARS: usize = 800;
+
+/// Entries listed per list (languages, layout, tasks, packages, jobs, dependencies)
+const LISTED: usize = 12;
+
+/// Files that commonly start a program or library, checked in this order
+const ENTRY_POINTS: &[&str] = &[
+    "src/main.rs",
+    "src/lib.rs",
+    "main.go",
+    "cmd",
+    "__main__.py",
+    "main.py",
+    "app.py",
+    "manage.py",
+    "src/index.ts",
+    "src/index.js",
+    "src/main.ts",
+    "index.ts",
+    "index.js",
+    "server.js",
+    "src/main/java",
+    "Program.cs",
+];
+
+/// Source languages by extension; documentation and configuration are not counted
+fn language(path: &Path) -> Option<&'static str> {
+    Some(match path.extension()?.to_str()? {
+        "rs" => "Rust",
+        "py" | "pyi" => "Python",
+        "js" | "jsx" | "mjs" | "cjs" => "JavaScript",
+        "ts" | "tsx" | "mts" | "cts" => "TypeScript",
+        "go" => "Go",
+        "java" => "Java",
+        "kt" | "kts" => "Kotlin",
+        "scala" => "Scala",
+        "rb" => "Ruby",
+        "php" => "PHP",
+        "c" | "h" => "C",
+        "cc" | "cpp" | "cxx" | "hpp" | "hh" => "C++",
+        "cs" => "C#",
+        "swift" => "Swift",
+        "m" | "mm" => "Objective-C",
+        "ex" | "exs" => "Elixir",
+        "erl" => "Erlang",
+        "hs" => "Haskell",
+        "lua" => "Lua",
+        "dart" => "Dart",
+        "vue" => "Vue",
+        "svelte" => "Svelte",
+        "sh" | "bash" => "Shell",
+        "sql" => "SQL",
+        _ => return None,
+    })
+}
+
+/// What the project's manifest says about it
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize)]
+pub struct ProjectInfo {
+    pub name: Option<String>,
+    pub description: Option<String>,
+    /// Manifest the name was read from
+    pub manifest: Option<String>,
+    /// Dependencies declared in the manifest, for projects without a lockfile
+    pub dependencies: Vec<String>,
+}
+
+/// Name, description and declared dependencies from the first manifest in `root`
+pub fn project_info(root: &Path) -> ProjectInfo {
+    let read = |name: &str| fs::read_to_string(root.join(name)).ok();
+    let string = |value: Option<&toml::Value>| value.and_then(|v| v.as_str()).map(String::from);
+    if let Some(manifest) = read("Cargo.toml").and_then(|t| t.parse::<toml::Value>().ok()) {
+        let package = manifest.get("package");
+        return ProjectInfo {
+            name: string(package.and_then(|p| p.get("name"))),
+            description: string(package.and_then(|p| p.get("description"))),
+            manifest: Some("Cargo.toml".to_string()),
+            dependencies: toml_keys(manifest.get("dependencies")),
+        };
+    }
+    if let Some(manifest) =
+        read("package.json").and_then(|t| serde_json::from_str::<serde_json::Value>(&t).ok())
+    {
+        let text = |key: &str| manifest[key].as_str().map(String::from);
+        return ProjectInfo {
+            name: text("name"),
+            description: text("description"),
+            manifest: Some("package.json".to_string()),
+            dependencies: manifest["dependencies"]
+                .as_object()
+                .map(|deps| deps.keys().cloned().collect())
+                .unwrap_or_default(),
+        };
+    }
+    if let Some(manifest) = read("pyproject.toml").and_then(|t| t.parse::<toml::Value>().ok()) {
+        let project = manifest
+            .get("project")
+            .or_else(|| manifest.get("tool").and_then(|t| t.get("poetry")));
+        let dependencies = match project.and_then(|p| p.get("dependencies")) {
+            Some(toml::Value::Array(items)) => items
+                .iter()
+                .filter_map(|item| item.as_str())
+                .map(|spec| {
+                    let end = spec
+                        .find(|c: char| !(c.is_alphanumeric() || "-_.".contains(c)))
+                        .unwrap_or(spec.len());
+                    spec[..end].to_string()
+                })
+                .collect(),
+            table => toml_keys(table)
+                .into_iter()
+                .filter(|name| name != "python")
+                .collect(),
+        };
+        return ProjectInfo {
+            name: string(project.and_then(|p| p.get("name"))),
+            description: string(project.and_then(|p| p.get("description"))),
+            manifest: Some("pyproject.toml".to_string()),
+            dependencies,
+        };
+    }
+    if let Some(text) = read("go.mod") {
+        let module = text
+            .lines()
+            .find_map(|line| line.trim().strip_prefix("module "))
+            .map(|module| module.trim().to_string());
+        let dependencies = text
+            .lines()
+            .map(str::trim)
+            .filter(|line| !line.ends_with("// indirect"))
+            .filter_map(|line| {
+                let line = line.strip_prefix("require ").unwrap_or(line);
+                let (path, version) = line.split_once(' ')?;
+                (path.contains('.') && version.trim_start().starts_with('v'))
+                    .then(|| path.to_string())
+            })
+            .collect();
+        return ProjectInfo {
+            name: module,
+            description: None,
+            manifest: Some("go.mod".to_string()),
+            dependencies,
+        };
+    }
+    ProjectInfo::default()
+}
+
+fn toml_keys(table: Option<&toml::Value>) -> Vec<String> {
+    table
+        .and_then(|t| t.as_table())
+        .map(|t| t.keys().cloned().collect())
+        .unwrap_or_default()
+}
+
+/// The title and opening text of a README
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+pub struct ReadmeSummary {
+    pub path: String,
+    pub title: Option<String>,
+    pub summary: String,
+}
+
+/// Title and first paragraphs of the README in `root`, skipping badges, images and
+/// HTML
+pub fn readme_summary(root: &Path) -> Option<ReadmeSummary> {
+    let path = [
+        "README.md",
+        "README.rst",
+        "README.txt",
+        "README",
+        "readme.md",
+    ]
+    .iter()
+    .map(|name| root.join(name))
+    .find(|path| path.is_file())?;
+    let text = fs::read_to_string(&path).ok()?;
+    let lines: Vec<&str> = text.lines().collect();
+
+    let mut title = None;
+    let mut summary = String::new();
+    let mut i = 0;
+    while i < lines.len() && summary.chars().count() < README_CHARS {
+        let line = lines[i].trim();
+        i += 1;
+        let underline = lines
+            .get(i)
+            .is_some_and(|next| next.len() >= 3 && next.trim().chars().all(|c| "=-~".contains(c)));
+        let heading = line.starts_with('#') || (underline && !line.is_empty());
+        if heading {
+            if underline {
+                i += 1;
+            }
+            let text = line.trim_start_matches('#').trim().to_string();
+            if title.is_none() && summary.is_empty() {
+                title = Some(text);
+                continue;
+            }
+            if !summary.is_empty() {
+                break;
+            }
+            continue;
+        }
+        let noise = line.is_empty()
+            || line.starts_with("[![")
+            || line.starts_with("![")
+            || line.starts_with('<')
+            || line.starts_with(".. ")
+            || line.starts_with("```");
+        if noise {
+            if !summary.is_empty() && !summary.ends_with("\n\n") {
+                summary.push_str("\n\n");
+            }
+            continue;
+        }
+        if !summary.is_empty() && !summary.ends_with('\n') {
+            summary.push(' ');
+        }
+        summary.push_str(line);
+    }
+    let mut summary = summary.trim().to_string();
+    if summary.chars().count() > README_CHARS {
+        summary = summary.chars().take(README_CHARS).collect::<String>() + "...";
+    }
+    Some(ReadmeSummary {
+        path: path
+            .file_name()
+            .map(|name| name.to_string_lossy().to_string())
+            .unwrap_or_default(),
+        title,
+        summary,
+    })
+}
+
+/// Files per language and per top-level entry under `root`
+struct Census {
+    files: usize,
+    languages: Vec<(&'static str, usize)>,
+    /// Top-level entries with the number of files under each (`None` for files)
+    layout: Vec<(String, Option<usize>)>,
+    truncated: bool,
+}
+
+fn census(root: &Path, filter: &ConfigurableFilter) -> Census {
+    let mut languages: BTreeMap<&'static str, usize> = BTreeMap::new();
+    let mut layout: BTreeMap<String, Option<usize>> = BTreeMap::new();
+    let mut files = 0;
+    let mut truncated = false;
+    let walker = WalkDir::new(root)
+        .follow_links(false)
+        .sort_by_file_name()
+        .into_iter()
+        .filter_entry(|e| {
+            e.path() == root
+                || filter.should_include_path(e.path().strip_prefix(root).unwrap_or(e.path()))
+        })
+        .filter_map(|entry| entry.ok());
+    for entry in walker {
+        let Ok(relative) = entry.path().strip_prefix(root) else {
+            continue;
+        };
+        let Some(top) = relative.components().next() else {
+            continue;
+        };
+        let top = top.as_os_str().to_string_lossy().to_string();
+        if entry.depth() == 1 && entry.file_type().is_dir() {
+            layout.entry(format!("{}/", top)).or_insert(Some(0));
+            continue;
+        }
+        if !entry.file_type().is_file() {
+            continue;
+        }
+        if files == MAX_SCANNED_FILES {
+            truncated = true;
+            break;
+        }
+        files += 1;
+        if entry.depth() == 1 {
+            layout.insert(top, None);
+        } else if let Some(Some(count)) = layout.get_mut(&format!("{}/", top)) {
+            *count += 1;
+        }
+        if let Some(language) = language(entry.path()) {
+            *languages.entry(language).or_default() += 1;
+        }
+    }
+    let mut languages: Vec<_> = languages.into_iter().collect();
+    languages.sort_by(|a, b| b.1.cmp(&a.1).then(a.0.cmp(b.0)));
+    // Directories first, biggest first, then files by name
+    let mut layout: Vec<_> = layout.into_iter().collect();
+    layout.sort_by(|a, b| match (a.1, b.1) {
+        (Some(x), Some(y)) => y.cmp(&x).then(a.0.cmp(&b.0)),
+        (Some(_), None) => std::cmp::Ordering::Less,
+        (None, Some(_)) => std::cmp::Ordering::Greater,
+        (None, None) => a.0.cmp(&b.0),
+    });
+    Census {
+        files,
+        languages,
+        layout,
+        truncated,
+    }
+}
+
+/// `items` joined with commas, the first `LISTED` of them, then how many more
+fn listed<T: AsRef<str>>(items: &[T]) -> String {
+    let shown: Vec<&str> = items.iter().take(LISTED).map(AsRef::as_ref).collect();
+    let mut text = shown.join(", ");
+    if items.len() > LISTED {
+        text.push_str(&format!(" and {} more", items.len() - LISTED));
+    }
+    text
+}
+
+/// The jobs of every CI configuration under `root`, as `name (provider)`
+#[cfg(feature = "yaml")]
+fn ci_jobs(root: &Path) -> Vec<String> {
+    find_ci_files(root)
+        .into_iter()
+        .flat_map(|(provider, path)| {
+            let file = path
+                .strip_prefix(root)
+                .unwrap_or(&path)
+                .display()
+                .to_string();
+            fs::read_to_string(&path)
+                .ok()
+                .and_then(|text| parse_ci_config(provider, &file, &text).ok())
+                .unwrap_or_default()
+        })
+        .map(|job| format!("{} ({})", job.name, job.provider.as_str()))
+        .collect()
+}
+
+/// CI configurations are YAML, which needs the `yaml` feature
+#[cfg(not(feature = "yaml"))]
+fn ci_jobs(_root: &Path) -> Vec<String> {
+    Vec::new()
+}
+
+/// A titled block of the brief
+struct Section {
+    title: &'static str,
+    lines: Vec<String>,
+}
+
+/// Tool writing a bounded orientation document for a repository
+pub struct RepoBriefTool {
+    name: String,
+}
+
+impl RepoBriefTool {
+    pub fn new() -> Self {
+        Self {
+            name: "repo_brief".to_string(),
+        }
+    }
+
+    fn max_tokens(args: &ToolArgs) -> Result<usize, ToolError> {
+        match args.get_named_arg("max_tokens") {
+            None => Ok(DEFAULT_MAX_TOKENS),
+            Some(value) => value
+                .parse::<usize>()
+                .ok()
+                .filter(|n| (MIN_MAX_TOKENS..=MAX_MAX_TOKENS).contains(n))
+                .ok_or_else(|| ToolError::InvalidArgs {
+                    message: format!(
+                        "Invalid max_tokens '{}': expected a number from {} to {}",
+                        value, MIN_MAX_TOKENS, MAX_MAX_TOKENS
+                    ),
+                }),
+        }
+    }
+
+    /// The sections of the brief, most important first, and the data behind them
+    fn sections(root: &Path, filter: &ConfigurableFilter) -> (Vec<Section>, serde_json::Value) {
+        let info = project_info(root);
+        let readme = readme_summary(root);
+        let census = census(root, filter);
+
+        let mut overview = Vec::new();
+        if let Some(name) = &info.name {
+            let mut line = format!("Project: {}", name);
+            if let Some(manifest) = &info.manifest {
+                line.push_str(&format!(" ({})", manifest));
+            }
+            overview.push(line);
+        }
+        if let Some(description) = &info.description {
+            overview.push(description.clone());
+        }
+        let total: usize = census.languages.iter().map(|(_, n)| n).sum();
+        if total > 0 {
+            let languages: Vec<String> = census
+                .languages
+                .iter()
+                .map(|(language, n)| format!("{} {}%", language, n * 100 / total))
+                .collect();
+            overview.push(format!("Languages: {}", listed(&languages)));
+        }
+        overview.push(format!(
+            "Files: {}{}",
+            census.files,
+            if census.truncated { "+" } else { "" }
+        ));
+
+        let mut readme_lines = Vec::new();
+        if let Some(readme) = &readme {
+            if let Some(title) = &readme.title {
+                readme_lines.push(format!("{} ({})", title, readme.path));
+            }
+            readme_lines.extend(readme.summary.lines().map(String::from));
+        }
+
+        let layout: Vec<String> = census
+            .layout
+            .iter()
+            .map(|(name, count)| match count {
+                Some(n) => format!("{} ({} file(s))", name, n),
+                None => name.clone(),
+            })
+            .collect();
+        let mut layout_lines: Vec<String> = layout.iter().take(LISTED * 2).cloned().collect();
+        if layout.len() > LISTED * 2 {
+            layout_lines.push(format!("... and {} more", layout.len() - LISTED * 2));
+        }
+        let entry_points: Vec<&str> = ENTRY_POINTS
+            .iter()
+            .copied()
+            .filter(|entry| root.join(entry).exists())
+            .collect();
+        if !entry_points.is_empty() {
+            layout_lines.push(format!("Entry points: {}", entry_points.join(", ")));
+        }
+
+        let mut workflow = Vec::new();
+        let framework = RunTestsTool::detect_framework(root);
+        if let Some(framework) = framework {
+            workflow.push(format!(
+                "Tests: {}",
+                RunTestsTool::default_command(framework, None)
+            ));
+        }
+        let tasks: Vec<String> = find_task_files(root)
+            .into_iter()
+            .flat_map(|(runner, path)| {
+                let file = path
+                    .file_name()
+                    .map(|name| name.to_string_lossy().to_string())
+                    .unwrap_or_default();
+                parse_task_file(runner, &path, &file).unwrap_or_default()
+            })
+            .map(|task| task.invocation)
+            .collect();
+        if !tasks.is_empty() {
+            workflow.push(format!("Tasks: {}", listed(&tasks)));
+        }
+        let workspace = Workspace::detect(root);
+        if let Some(workspace) = &workspace {
+            let members: Vec<String> = workspace
+                .members
+                .iter()
+                .map(|member| format!("{} ({})", member.name, member.path))
+                .collect();
+            workflow.push(format!(
+                "Workspace packages ({}): {}",
+                members.len(),
+                listed(&members)
+            ));
+        }
+        let ci_jobs = ci_jobs(root);
+        if !ci_jobs.is_empty() {
+            workflow.push(format!("CI jobs: {}", listed(&ci_jobs)));
+        }
+
+        let mut dependencies = Vec::new();
+        let mut dependency_data = Vec::new();
+        for path in find_lockfiles(root) {
+            let Ok(lockfile) = parse_lockfile(&path) else {
+                continue;
+            };
+            let direct: Vec<String> = lockfile
+                .packages
+                .iter()
+                .filter(|p| p.direct && !p.local && !p.dev)
+                .map(|p| format!("{} {}", p.name, p.version))
+                .collect();
+            dependencies.push(format!(
+                "{} ({} direct, {} locked): {}",
+                lockfile.ecosystem.as_str(),
+                direct.len(),
+                lockfile.packages.iter().filter(|p| !p.local).count(),
+                listed(&direct)
+            ));
+            dependency_data.push(serde_json::json!({
+                "ecosystem": lockfile.ecosystem,
+                "direct": direct,
+            }));
+        }
+        if dependencies.is_empty() && !info.dependencies.is_empty() {
+            dependencies.push(format!(
+                "Declared in {}: {}",
+                info.manifest.as_deref().unwrap_or("the manifest"),
+                listed(&info.dependencies)
+            ));
+        }
+
+        let data = serde_json::json!({
+            "project": info,
+            "readme": readme,
+            "files": census.files,
+            "languages": census.languages.iter().map(|(language, files)| {
+                serde_json::json!({"language": language, "files": files})
+            }).collect::<Vec<_>>(),
+            "layout": layout,
+            "entry_points": entry_points,
+            "test_framework": framework,
+            "tasks": tasks,
+            "workspace": workspace,
+            "ci_jobs": ci_jobs,
+            "dependencies": dependency_data,
+        });
+        let sections = vec![
+            Section {
+                title: "Overview",
+                lines: overview,
+            },
+            Section {
+                title: "README",
+                lines: readme_lines,
+            },
+            Section {
+                title: "Layout",
+                lines: layout_lines,
+            },
+            Section {
+                title: "Build and test",
+                lines: workflow,
+            },
+            Section {
+                title: "Dependencies",
+                lines: dependencies,
+            },
+        ];
+        (sections, data)
+    }
+
+    /// The sections joined under `max_tokens`; sections that do not fit are cut short
+    /// or left out and named at the end
+    fn render(root: &Path, sections: &[Section], max_tokens: usize) -> String {
+        // Room kept for the notes about what was cut
+        let budget = max_tokens.saturating_sub(RESERVED_TOKENS);
+        let mut brief = format!("# {}\n", root.display());
+        let mut full = false;
+        let mut omitted = Vec::new();
+        for section in sections.iter().filter(|s| !s.lines.is_empty()) {
+            let heading = format!("\n## {}\n", section.title);
+            if full || estimate_tokens(&brief) + estimate_tokens(&heading) > budget {
+                full = true;
+                omitted.push(section.title);
+                continue;
+            }
+            brief.push_str(&heading);
+            for (i, line) in section.lines.iter().enumerate() {
+                let used = estimate_tokens(&brief);
+                if used + estimate_tokens(line) <= budget {
+                    brief.push_str(line);
+                    brief.push('\n');
+                    continue;
+                }
+                // Cut the line that crosses the budget rather than dropping it
+                let room = budget.saturating_sub(used) * 4;
+                let mut left = section.lines.len() - i;
+                if room >= 40 {
+                    brief.extend(line.chars().take(room - 3));
+                    brief.push_str("...\n");
+                    left -= 1;
+                }
+                if left > 0 {
+                    brief.push_str(&format!("... {} more line(s)\n", left));
+                }
+                full = true;
+                break;
+            }
+        }
+        if !omitted.is_empty() {
+            brief.push_str(&format!(
+                "\nLeft out to stay within {} tokens: {}\n",
+                max_tokens,
+                omitted.join(", ")
+            ));
+        }
+        brief.trim_end().to_string()
+    }
+}
+
+impl Default for RepoBriefTool {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl Tool for RepoBriefTool {
+    fn name(&self) -> &str {
+        &self.name
+    }
+
+    fn description(&self) -> &str {
+        "First look at a repository in one bounded document: project name and description, README opening, languages, top-level layout and entry points, test command, tasks, workspace packages, CI jobs and direct dependencies"
+    }
+
+    fn signature(&self) -> &str {
+        "repo_brief [<path>] [--max_tokens=N]"
+    }
+
+    fn danger_level(&self) -> DangerLevel {
+        DangerLevel::ReadOnly
+    }
+
+    fn validate_args(&self, args: &ToolArgs) -> Result<(), ToolError> {
+        Self::max_tokens(args).map(|_| ())
+    }
+
+    fn execute(&mut self, args: &ToolArgs, state: &Arc<Mutex<ToolState>>) -> Result<ToolResult> {
+        let max_tokens = Self::max_tokens(args).map_err(|e| anyhow::anyhow!("{}", e))?;
+        let (working_directory, filter) = {
+            let mut state_guard = state
+                .lock()
+                .map_err(|e| anyhow::anyhow!("Failed to lock state: {}", e))?;
+            (
+                state_guard.working_directory.clone(),
+                state_guard.search_filter(),
+            )
+        };
+        let root = match args.get_named_arg("path").or_else(|| args.get_arg(0)) {
+            Some(path) => working_directory.join(path),
+            None => working_directory,
+        };
+        if !root.is_dir() {
+            return Ok(path_not_found(
+                format!("Directory not found: {}", root.display()),
+                &root,
+            ));
+        }
+        let root: PathBuf = root.canonicalize().unwrap_or(root);
+
+        let (sections, mut data) = Self::sections(&root, &filter);
+        let brief = Self::render(&root, &sections, max_tokens);
+        data["tokens"] = serde_json::json!(estimate_tokens(&brief));
+        Ok(ToolResult::success_with_data(brief, data))
+    }
+
+    fn get_parameters_schema(&self) -> serde_json::Value {
+        serde_json::json!({
+            "type": "object",
+            "properties": {
+                "path": {
+                    "type": "string",
+                    "description": "Repository directory (default: the working directory)"
+                },
+                "max_tokens": {
+                    "type": "integer",
+                    "description": "Approximate size limit of the brief (default 1500, 200 to 8000)"
+                }
+            },
+            "required": []
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use tempfile::TempDir;
+
+    #[test]
+    fn test_readme_summary_skips_badges() {
+        let temp_dir = TempDir::new().unwrap();
+        fs::write(
+            temp_dir.path().join("README.md"),
+            "# widget\n\n[![CI](https://ci/badge.svg)](https://ci)\n\nA small library\nfor widgets.\n\nSecond paragraph.\n\n## Install\n\ncargo add widget\n",
+        )
+        .unwrap();
+        let readme = readme_summary(temp_dir.path()).unwrap();
+        assert_eq!(readme.title.as_deref(), Some("widget"));
+        assert_eq!(
+            readme.summary,
+            "A small library for widgets.\n\nSecond paragraph."
+        );
+    }
+
+    #[test]
+    fn test_repo_brief_combines_sources_within_budget() {
+        let temp_dir = TempDir::new().unwrap();
+        let root = temp_dir.path();
+        fs::create_dir_all(root.join("src")).unwrap();
+        fs::write(
+            root.join("Cargo.toml"),
+            "[package]\nname = \"widget\"\ndescription = \"Widgets for everyone\"\n\n[dependencies]\nserde = \"1\"\n",
+        )
+        .unwrap();
+        fs::write(root.join("src/main.rs"), "fn main() {}\n").unwrap();
+        fs::write(root.join("src/util.rs"), "pub fn f() {}\n").unwrap();
+        fs::write(root.join("build.py"), "print()\n").unwrap();
+        fs::write(
+            root.join("Makefile"),
+            "# Run the tests\ntest:\n\tcargo test\n",
+        )
+        .unwrap();
+        fs::write(
+            root.join("README.md"),
+            format!("# widget\n\n{}\n", "Widgets. ".repeat(80)),
+        )
+        .unwrap();
+
+        let mut tool_state = ToolState::new();
+        tool_state.working_directory = root.to_path_buf();
+        let state = Arc::new(Mutex::new(tool_state));
+        let mut tool = RepoBriefTool::new();
+        let result = tool.execute(&ToolArgs::from_args(&[]), &state).unwrap();
+        assert!(result.success, "{}", result.message);
+        for expected in [
+            "Project: widget (Cargo.toml)",
+            "Widgets for everyone",
+            "Languages: Rust 66%, Python 33%",
+            "src/ (2 file(s))",
+            "Entry points: src/main.rs",
+            "Tests: cargo test",
+            "Tasks: make test",
+            "Declared in Cargo.toml: serde",
+        ] {
+            assert!(result.message.contains(expected), "{}", result.message);
+        }
+
+        let args = ToolArgs::with_named_args(
+            vec![],
+            [("max_tokens".to_string(), "200".to_string())]
+                .into_iter()
+                .collect(),
+        );
+        let result = tool.execute(&args, &state).unwrap();
+        assert!(
+            estimate_tokens(&result.message) <= 200,
+            "{}",
+            result.message
+        );
+        assert!(result.message.contains("Project: widget"));
+        assert!(
+            result.message.contains(
+                "Left out to stay within 200 tokens: Layout, Build and test, Dependencies"
+            ),
+            "{}",
+            result.message
+        );
+    }
+}
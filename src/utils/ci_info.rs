@@ -0,0 +1,877 @@
+//! ci_info tool: summarize the CI configuration of a repository
+//!
+//! Reads GitHub Actions workflows, `.gitlab-ci.yml` and CircleCI's `config.yml` and
+//! lists every job with the events that trigger it and the commands it runs, so the
+//! checks a change has to pass can be reproduced locally.
+
+use crate::core::{DangerLevel, Tool, ToolArgs, ToolError, ToolResult};
+use crate::state::{session_path, ToolState};
+use anyhow::Result;
+use serde::{Deserialize, Serialize};
+use serde_yaml_ng::Value;
+use std::fs;
+use std::path::{Path, PathBuf};
+use std::sync::{Arc, Mutex};
+
+/// Top-level keys of `.gitlab-ci.yml` that are not jobs
+const GITLAB_KEYWORDS: [&str; 10] = [
+    "default",
+    "include",
+    "stages",
+    "variables",
+    "workflow",
+    "image",
+    "services",
+    "cache",
+    "before_script",
+    "after_script",
+];
+
+/// How deep `extends` chains of GitLab jobs are followed
+const MAX_EXTENDS_DEPTH: usize = 8;
+
+/// CI services whose configuration `ci_info` reads
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum CiProvider {
+    /// Workflows under `.github/workflows`
+    Github,
+    /// `.gitlab-ci.yml`
+    Gitlab,
+    /// `.circleci/config.yml`
+    Circleci,
+}
+
+impl CiProvider {
+    /// Canonical provider name
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            Self::Github => "github",
+            Self::Gitlab => "gitlab",
+            Self::Circleci => "circleci",
+        }
+    }
+}
+
+/// One CI job and what it runs
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct CiJob {
+    pub provider: CiProvider,
+    /// Configuration file, relative to the repository
+    pub file: String,
+    /// Workflow (GitHub, CircleCI) or stage (GitLab) the job belongs to
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub workflow: Option<String>,
+    pub name: String,
+    /// Events and filters that start the job, e.g. `push (branches: main)`
+    pub triggers: Vec<String>,
+    /// Condition on the job itself (`if:`)
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub condition: Option<String>,
+    /// Runner label or container image
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub environment: Option<String>,
+    /// Jobs that have to finish first
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub needs: Vec<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub matrix: Option<serde_json::Value>,
+    /// Actions, orbs and reusable workflows the job uses besides its commands
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub actions: Vec<String>,
+    /// Shell commands, in the order the job runs them
+    pub commands: Vec<String>,
+}
+
+impl CiJob {
+    fn new(provider: CiProvider, file: &str, name: &str) -> Self {
+        Self {
+            provider,
+            file: file.to_string(),
+            workflow: None,
+            name: name.to_string(),
+            triggers: Vec::new(),
+            condition: None,
+            environment: None,
+            needs: Vec::new(),
+            matrix: None,
+            actions: Vec::new(),
+            commands: Vec::new(),
+        }
+    }
+}
+
+/// The CI configuration files in `dir`, in the order they are reported
+pub fn find_ci_files(dir: &Path) -> Vec<(CiProvider, PathBuf)> {
+    let mut files = Vec::new();
+    if let Ok(entries) = fs::read_dir(dir.join(".github").join("workflows")) {
+        let mut workflows: Vec<PathBuf> = entries
+            .filter_map(|entry| entry.ok().map(|entry| entry.path()))
+            .filter(|path| {
+                path.is_file()
+                    && matches!(
+                        path.extension().and_then(|e| e.to_str()),
+                        Some("yml" | "yaml")
+                    )
+            })
+            .collect();
+        workflows.sort();
+        files.extend(workflows.into_iter().map(|path| (CiProvider::Github, path)));
+    }
+    for (provider, path) in [
+        (CiProvider::Gitlab, dir.join(".gitlab-ci.yml")),
+        (
+            CiProvider::Circleci,
+            dir.join(".circleci").join("config.yml"),
+        ),
+    ] {
+        if path.is_file() {
+            files.push((provider, path));
+        }
+    }
+    files
+}
+
+/// The jobs defined in one configuration file; `file` is the name they report
+pub fn parse_ci_config(provider: CiProvider, file: &str, text: &str) -> Result<Vec<CiJob>> {
+    let doc: Value = serde_yaml_ng::from_str(text)?;
+    Ok(match provider {
+        CiProvider::Github => parse_github(file, &doc),
+        CiProvider::Gitlab => parse_gitlab(file, &doc),
+        CiProvider::Circleci => parse_circleci(file, &doc),
+    })
+}
+
+/// A scalar as text
+fn scalar(value: &Value) -> Option<String> {
+    match value {
+        Value::String(s) => Some(s.clone()),
+        Value::Number(n) => Some(n.to_string()),
+        Value::Bool(b) => Some(b.to_string()),
+        _ => None,
+    }
+}
+
+/// A scalar or a sequence of scalars as a list
+fn strings(value: Option<&Value>) -> Vec<String> {
+    match value {
+        Some(Value::Sequence(items)) => items.iter().filter_map(scalar).collect(),
+        Some(value) => scalar(value).into_iter().collect(),
+        None => Vec::new(),
+    }
+}
+
+fn to_json(value: &Value) -> Option<serde_json::Value> {
+    serde_json::to_value(value).ok()
+}
+
+/// `push (branches: main; paths: src/**)` for each event of a workflow's `on:`
+fn github_triggers(on: Option<&Value>) -> Vec<String> {
+    let Some(Value::Mapping(events)) = on else {
+        return strings(on);
+    };
+    events
+        .iter()
+        .filter_map(|(event, filter)| {
+            let event = scalar(event)?;
+            let filters: Vec<String> = match filter {
+                Value::Mapping(filter) => filter
+                    .iter()
+                    .filter_map(|(key, value)| {
+                        let key = scalar(key)?;
+                        let values = strings(Some(value));
+                        Some(if values.is_empty() {
+                            key
+                        } else {
+                            format!("{}: {}", key, values.join(", "))
+                        })
+                    })
+                    .collect(),
+                // schedule: a list of crons
+                Value::Sequence(items) => items
+                    .iter()
+                    .filter_map(|item| item.get("cron").and_then(scalar))
+                    .collect(),
+                _ => Vec::new(),
+            };
+            Some(if filters.is_empty() {
+                event
+            } else {
+                format!("{} ({})", event, filters.join("; "))
+            })
+        })
+        .collect()
+}
+
+fn parse_github(file: &str, doc: &Value) -> Vec<CiJob> {
+    let workflow = doc.get("name").and_then(scalar).unwrap_or_else(|| {
+        Path::new(file)
+            .file_stem()
+            .map(|stem| stem.to_string_lossy().to_string())
+            .unwrap_or_default()
+    });
+    let triggers = github_triggers(doc.get("on"));
+    let default_dir = |job: &Value| {
+        job.get("defaults")
+            .or_else(|| doc.get("defaults"))
+            .and_then(|defaults| defaults.get("run")?.get("working-directory"))
+            .and_then(scalar)
+    };
+
+    let Some(Value::Mapping(jobs)) = doc.get("jobs") else {
+        return Vec::new();
+    };
+    let mut result = Vec::new();
+    for (id, job) in jobs {
+        let Some(id) = scalar(id) else { continue };
+        let name = job.get("name").and_then(scalar).unwrap_or(id);
+        let mut ci_job = CiJob::new(CiProvider::Github, file, &name);
+        ci_job.workflow = Some(workflow.clone());
+        ci_job.triggers = triggers.clone();
+        ci_job.condition = job.get("if").and_then(scalar);
+        let runs_on = strings(job.get("runs-on"));
+        ci_job.environment = (!runs_on.is_empty()).then(|| runs_on.join(", "));
+        ci_job.needs = strings(job.get("needs"));
+        ci_job.matrix = job
+            .get("strategy")
+            .and_then(|strategy| strategy.get("matrix"))
+            .and_then(to_json);
+        // A job calling a reusable workflow has no steps
+        ci_job.actions.extend(job.get("uses").and_then(scalar));
+
+        let steps = job.get("steps").and_then(Value::as_sequence);
+        for step in steps.into_iter().flatten() {
+            ci_job.actions.extend(step.get("uses").and_then(scalar));
+            let Some(run) = step.get("run").and_then(scalar) else {
+                continue;
+            };
+            let run = run.trim_end().to_string();
+            let dir = step
+                .get("working-directory")
+                .and_then(scalar)
+                .or_else(|| default_dir(job));
+            ci_job.commands.push(match dir {
+                Some(dir) => format!("cd {} && {}", dir, run),
+                None => run,
+            });
+        }
+        result.push(ci_job);
+    }
+    result
+}
+
+/// A key of a GitLab job, following `extends` and falling back to `default:` and
+/// the deprecated top-level defaults
+fn gitlab_field<'a>(doc: &'a Value, job: &'a Value, key: &str, depth: usize) -> Option<&'a Value> {
+    if let Some(value) = job.get(key) {
+        return Some(value);
+    }
+    if depth < MAX_EXTENDS_DEPTH {
+        // Later templates override earlier ones
+        for template in strings(job.get("extends")).iter().rev() {
+            if let Some(template) = doc.get(template.as_str()) {
+                if let Some(value) = gitlab_field(doc, template, key, depth + 1) {
+                    return Some(value);
+                }
+            }
+        }
+    }
+    if depth > 0 {
+        return None;
+    }
+    let inherited = [
+        "image",
+        "before_script",
+        "after_script",
+        "services",
+        "cache",
+    ];
+    if inherited.contains(&key) {
+        doc.get("default")
+            .and_then(|default| default.get(key))
+            .or_else(|| doc.get(key))
+    } else {
+        None
+    }
+}
+
+/// `if: $CI_COMMIT_BRANCH == "main"` for each rule, or the `only`/`except` lists
+fn gitlab_triggers(doc: &Value, job: &Value) -> Vec<String> {
+    if let Some(Value::Sequence(rules)) = gitlab_field(doc, job, "rules", 0) {
+        return rules
+            .iter()
+            .filter_map(|rule| {
+                let parts: Vec<String> = ["if", "changes", "exists", "when"]
+                    .iter()
+                    .filter_map(|key| {
+                        let values = strings(rule.get(*key));
+                        (!values.is_empty()).then(|| format!("{}: {}", key, values.join(", ")))
+                    })
+                    .collect();
+                (!parts.is_empty()).then(|| parts.join("; "))
+            })
+            .collect();
+    }
+    let mut triggers = Vec::new();
+    for key in ["only", "except"] {
+        let value = gitlab_field(doc, job, key, 0);
+        // `only: {refs: [...], changes: [...]}`
+        let values = match value {
+            Some(Value::Mapping(map)) => map
+                .iter()
+                .filter_map(|(kind, values)| {
+                    Some(format!(
+                        "{} {}",
+                        scalar(kind)?,
+                        strings(Some(values)).join(", ")
+                    ))
+                })
+                .collect(),
+            value => strings(value),
+        };
+        if !values.is_empty() {
+            triggers.push(format!("{}: {}", key, values.join(", ")));
+        }
+    }
+    triggers
+}
+
+fn parse_gitlab(file: &str, doc: &Value) -> Vec<CiJob> {
+    let Value::Mapping(entries) = doc else {
+        return Vec::new();
+    };
+    let mut result = Vec::new();
+    for (key, job) in entries {
+        let Some(name) = scalar(key) else { continue };
+        // Hidden keys are templates for `extends`
+        if name.starts_with('.') || GITLAB_KEYWORDS.contains(&name.as_str()) || !job.is_mapping() {
+            continue;
+        }
+        let field = |key: &str| gitlab_field(doc, job, key, 0);
+        let mut ci_job = CiJob::new(CiProvider::Gitlab, file, &name);
+        ci_job.workflow = Some(
+            field("stage")
+                .and_then(scalar)
+                .unwrap_or_else(|| "test".to_string()),
+        );
+        ci_job.triggers = gitlab_triggers(doc, job);
+        ci_job.environment = field("image").and_then(|image| match image {
+            Value::Mapping(_) => image.get("name").and_then(scalar),
+            image => scalar(image),
+        });
+        ci_job.needs = match field("needs") {
+            Some(Value::Sequence(needs)) => needs
+                .iter()
+                .filter_map(|need| scalar(need).or_else(|| need.get("job").and_then(scalar)))
+                .collect(),
+            _ => Vec::new(),
+        };
+        ci_job.matrix = field("parallel")
+            .and_then(|parallel| parallel.get("matrix"))
+            .and_then(to_json);
+        ci_job.actions = strings(field("trigger").and_then(|trigger| match trigger {
+            Value::Mapping(_) => trigger.get("include").or_else(|| trigger.get("project")),
+            trigger => Some(trigger),
+        }));
+        for script in ["before_script", "script", "after_script"] {
+            ci_job.commands.extend(
+                strings(field(script))
+                    .into_iter()
+                    .map(|command| command.trim_end().to_string()),
+            );
+        }
+        result.push(ci_job);
+    }
+    result
+}
+
+/// A CircleCI job's steps: `checkout`, `run: <command>`, `run: {command: ...}` and
+/// orb commands
+fn circleci_steps(job: &Value, ci_job: &mut CiJob) {
+    let steps = job.get("steps").and_then(Value::as_sequence);
+    for step in steps.into_iter().flatten() {
+        match step {
+            Value::String(step) => ci_job.actions.push(step.clone()),
+            Value::Mapping(map) => {
+                for (kind, body) in map {
+                    let Some(kind) = scalar(kind) else { continue };
+                    if kind != "run" {
+                        ci_job.actions.push(kind);
+                        continue;
+                    }
+                    let command = scalar(body).or_else(|| body.get("command").and_then(scalar));
+                    let Some(command) = command else { continue };
+                    let command = command.trim_end().to_string();
+                    ci_job
+                        .commands
+                        .push(match body.get("working_directory").and_then(scalar) {
+                            Some(dir) => format!("cd {} && {}", dir, command),
+                            None => command,
+                        });
+                }
+            }
+            _ => {}
+        }
+    }
+}
+
+fn parse_circleci(file: &str, doc: &Value) -> Vec<CiJob> {
+    let Some(Value::Mapping(jobs)) = doc.get("jobs") else {
+        return Vec::new();
+    };
+    let job = |name: &str| {
+        let definition = jobs.get(name);
+        let mut ci_job = CiJob::new(CiProvider::Circleci, file, name);
+        if let Some(definition) = definition {
+            ci_job.environment = definition
+                .get("docker")
+                .and_then(|docker| docker.get(0)?.get("image"))
+                .or_else(|| definition.get("machine")?.get("image"))
+                .or_else(|| definition.get("executor"))
+                .and_then(scalar);
+            circleci_steps(definition, &mut ci_job);
+        }
+        ci_job
+    };
+
+    let mut result = Vec::new();
+    let mut scheduled: Vec<String> = Vec::new();
+    if let Some(Value::Mapping(workflows)) = doc.get("workflows") {
+        for (workflow, definition) in workflows {
+            let Some(workflow) = scalar(workflow) else {
+                continue;
+            };
+            let Some(entries) = definition.get("jobs").and_then(Value::as_sequence) else {
+                continue;
+            };
+            let schedules: Vec<String> = definition
+                .get("triggers")
+                .and_then(Value::as_sequence)
+                .into_iter()
+                .flatten()
+                .filter_map(|trigger| trigger.get("schedule")?.get("cron").and_then(scalar))
+                .map(|cron| format!("schedule ({})", cron))
+                .collect();
+            for entry in entries {
+                // `- build` or `- build: {requires: [...], filters: {...}}`
+                let (name, options) = match entry {
+                    Value::Mapping(map) => match map.iter().next() {
+                        Some((name, options)) => (scalar(name), Some(options)),
+                        None => continue,
+                    },
+                    entry => (scalar(entry), None),
+                };
+                let Some(name) = name else { continue };
+                let mut ci_job = job(&name);
+                ci_job.workflow = Some(workflow.clone());
+                ci_job.triggers = if schedules.is_empty() {
+                    vec!["push".to_string()]
+                } else {
+                    schedules.clone()
+                };
+                if let Some(options) = options {
+                    ci_job.needs = strings(options.get("requires"));
+                    if let Some(Value::Mapping(filters)) = options.get("filters") {
+                        for (kind, filter) in filters {
+                            let Some(kind) = scalar(kind) else { continue };
+                            for rule in ["only", "ignore"] {
+                                let values = strings(filter.get(rule));
+                                if !values.is_empty() {
+                                    ci_job.triggers.push(format!(
+                                        "{} {}: {}",
+                                        kind,
+                                        rule,
+                                        values.join(", ")
+                                    ));
+                                }
+                            }
+                        }
+                    }
+                }
+                scheduled.push(name);
+                result.push(ci_job);
+            }
+        }
+    }
+    // Without workflows, CircleCI runs the `build` job on every push
+    for name in jobs.keys().filter_map(scalar) {
+        if !scheduled.contains(&name) {
+            let mut ci_job = job(&name);
+            if name == "build" && doc.get("workflows").is_none() {
+                ci_job.triggers.push("push".to_string());
+            }
+            result.push(ci_job);
+        }
+    }
+    result
+}
+
+/// Tool summarizing the CI jobs of a repository
+pub struct CiInfoTool {
+    name: String,
+}
+
+impl CiInfoTool {
+    pub fn new() -> Self {
+        Self {
+            name: "ci_info".to_string(),
+        }
+    }
+
+    fn format_job(job: &CiJob) -> String {
+        let title = match &job.workflow {
+            Some(workflow) => format!("- {} / {}", workflow, job.name),
+            None => format!("- {}", job.name),
+        };
+        let mut lines = vec![title];
+        if !job.triggers.is_empty() {
+            lines.push(format!("  on: {}", job.triggers.join("; ")));
+        }
+        if let Some(condition) = &job.condition {
+            lines.push(format!("  if: {}", condition));
+        }
+        if let Some(environment) = &job.environment {
+            lines.push(format!("  runs on: {}", environment));
+        }
+        if !job.needs.is_empty() {
+            lines.push(format!("  needs: {}", job.needs.join(", ")));
+        }
+        if let Some(matrix) = &job.matrix {
+            lines.push(format!("  matrix: {}", matrix));
+        }
+        if !job.actions.is_empty() {
+            lines.push(format!("  uses: {}", job.actions.join(", ")));
+        }
+        for command in &job.commands {
+            for (index, line) in command.lines().enumerate() {
+                let prefix = if index == 0 { "  $ " } else { "    " };
+                lines.push(format!("{}{}", prefix, line));
+            }
+        }
+        lines.join("\n")
+    }
+}
+
+impl Default for CiInfoTool {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl Tool for CiInfoTool {
+    fn name(&self) -> &str {
+        &self.name
+    }
+
+    fn description(&self) -> &str {
+        "Summarize the repository's CI configuration (GitHub Actions, GitLab CI, CircleCI): each job with its triggers, runner and the commands it runs, to reproduce the checks locally"
+    }
+
+    fn signature(&self) -> &str {
+        "ci_info [path] [--job=<name>]"
+    }
+
+    fn danger_level(&self) -> DangerLevel {
+        DangerLevel::ReadOnly
+    }
+
+    fn validate_args(&self, _args: &ToolArgs) -> Result<(), ToolError> {
+        Ok(())
+    }
+
+    fn execute(&mut self, args: &ToolArgs, state: &Arc<Mutex<ToolState>>) -> Result<ToolResult> {
+        let dir = match args.get_named_arg("path").or_else(|| args.get_arg(0)) {
+            Some(path) => session_path(state, path),
+            None => {
+                let state_guard = state
+                    .lock()
+                    .map_err(|e| anyhow::anyhow!("Failed to lock state: {}", e))?;
+                state_guard.working_directory.clone()
+            }
+        };
+        if !dir.is_dir() {
+            return Ok(crate::utils::suggest::path_not_found(
+                format!("Directory {} not found", dir.display()),
+                &dir,
+            ));
+        }
+
+        let files = find_ci_files(&dir);
+        if files.is_empty() {
+            return Ok(ToolResult::error(format!(
+                "No CI configuration found in {} (looked for .github/workflows, .gitlab-ci.yml and .circleci/config.yml)",
+                dir.display()
+            )));
+        }
+        let filter = args.get_named_arg("job").map(|job| job.to_lowercase());
+
+        let mut jobs = Vec::new();
+        let mut errors = Vec::new();
+        let mut sections = Vec::new();
+        for (provider, path) in &files {
+            let file = path
+                .strip_prefix(&dir)
+                .unwrap_or(path)
+                .display()
+                .to_string();
+            let parsed = fs::read_to_string(path)
+                .map_err(anyhow::Error::from)
+                .and_then(|text| parse_ci_config(*provider, &file, &text));
+            let mut file_jobs = match parsed {
+                Ok(file_jobs) => file_jobs,
+                Err(e) => {
+                    sections.push(format!(
+                        "{} ({}): could not parse: {}",
+                        file,
+                        provider.as_str(),
+                        e
+                    ));
+                    errors.push(serde_json::json!({"file": file, "error": e.to_string()}));
+                    continue;
+                }
+            };
+            if let Some(filter) = &filter {
+                file_jobs.retain(|job| {
+                    job.name.to_lowercase().contains(filter)
+                        || job
+                            .workflow
+                            .as_ref()
+                            .is_some_and(|w| w.to_lowercase().contains(filter))
+                });
+            }
+            if file_jobs.is_empty() {
+                continue;
+            }
+            let mut section = format!("{} ({})", file, provider.as_str());
+            for job in &file_jobs {
+                section.push('\n');
+                section.push_str(&Self::format_job(job));
+            }
+            sections.push(section);
+            jobs.extend(file_jobs);
+        }
+
+        let message = if jobs.is_empty() && filter.is_some() {
+            format!(
+                "No CI job matches '{}' in {} configuration file(s)",
+                args.get_named_arg("job").map(String::as_str).unwrap_or(""),
+                files.len()
+            )
+        } else {
+            format!(
+                "{} CI job(s) in {} configuration file(s):\n\n{}",
+                jobs.len(),
+                files.len(),
+                sections.join("\n\n")
+            )
+        };
+        Ok(ToolResult::success_with_data(
+            message,
+            serde_json::json!({
+                "directory": dir.display().to_string(),
+                "files": files
+                    .iter()
+                    .map(|(provider, path)| serde_json::json!({
+                        "provider": provider,
+                        "path": path.strip_prefix(&dir).unwrap_or(path),
+                    }))
+                    .collect::<Vec<_>>(),
+                "jobs": jobs,
+                "errors": errors,
+            }),
+        ))
+    }
+
+    fn get_parameters_schema(&self) -> serde_json::Value {
+        serde_json::json!({
+            "type": "object",
+            "properties": {
+                "path": {
+                    "type": "string",
+                    "description": "Repository directory (defaults to the working directory)"
+                },
+                "job": {
+                    "type": "string",
+                    "description": "Only show jobs whose name or workflow contains this text (case-insensitive)"
+                }
+            },
+            "required": []
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use tempfile::TempDir;
+
+    const WORKFLOW: &str = r#"
+name: CI
+on:
+  push:
+    branches: [main]
+  pull_request:
+  schedule:
+    - cron: "0 3 * * 1"
+jobs:
+  lint:
+    runs-on: ubuntu-latest
+    steps:
+      - uses: actions/checkout@v4
+      - run: cargo fmt --check
+  test:
+    name: Test
+    needs: lint
+    if: github.event_name != 'schedule'
+    runs-on: ${{ matrix.os }}
+    strategy:
+      matrix:
+        os: [ubuntu-latest, macos-latest]
+    defaults:
+      run:
+        working-directory: crates/core
+    steps:
+      - uses: actions/checkout@v4
+      - name: Test
+        run: |
+          cargo build
+          cargo test --workspace
+"#;
+
+    const GITLAB: &str = r#"
+stages: [build, test]
+default:
+  image: rust:1.80
+  before_script:
+    - rustc --version
+.cargo:
+  stage: test
+  script:
+    - cargo test
+unit:
+  extends: .cargo
+  rules:
+    - if: $CI_PIPELINE_SOURCE == "merge_request_event"
+docs:
+  stage: build
+  image: python:3.12
+  script: mkdocs build
+  only: [main]
+"#;
+
+    #[test]
+    fn test_parse_github_workflow() {
+        let jobs =
+            parse_ci_config(CiProvider::Github, ".github/workflows/ci.yml", WORKFLOW).unwrap();
+        assert_eq!(jobs.len(), 2);
+        assert_eq!(
+            jobs[0].triggers,
+            [
+                "push (branches: main)",
+                "pull_request",
+                "schedule (0 3 * * 1)"
+            ]
+        );
+        assert_eq!(jobs[0].commands, ["cargo fmt --check"]);
+        assert_eq!(jobs[0].actions, ["actions/checkout@v4"]);
+
+        let test = &jobs[1];
+        assert_eq!(test.name, "Test");
+        assert_eq!(test.workflow.as_deref(), Some("CI"));
+        assert_eq!(test.needs, ["lint"]);
+        assert_eq!(
+            test.condition.as_deref(),
+            Some("github.event_name != 'schedule'")
+        );
+        assert_eq!(
+            test.matrix,
+            Some(serde_json::json!({"os": ["ubuntu-latest", "macos-latest"]}))
+        );
+        assert_eq!(
+            test.commands,
+            ["cd crates/core && cargo build\ncargo test --workspace"]
+        );
+    }
+
+    #[test]
+    fn test_parse_gitlab_and_circleci() {
+        let jobs = parse_ci_config(CiProvider::Gitlab, ".gitlab-ci.yml", GITLAB).unwrap();
+        let names: Vec<&str> = jobs.iter().map(|job| job.name.as_str()).collect();
+        assert_eq!(names, ["unit", "docs"]);
+        assert_eq!(jobs[0].workflow.as_deref(), Some("test"));
+        assert_eq!(jobs[0].environment.as_deref(), Some("rust:1.80"));
+        assert_eq!(jobs[0].commands, ["rustc --version", "cargo test"]);
+        assert_eq!(
+            jobs[0].triggers,
+            [r#"if: $CI_PIPELINE_SOURCE == "merge_request_event""#]
+        );
+        assert_eq!(jobs[1].environment.as_deref(), Some("python:3.12"));
+        assert_eq!(jobs[1].commands, ["rustc --version", "mkdocs build"]);
+        assert_eq!(jobs[1].triggers, ["only: main"]);
+
+        let circleci = r#"
+version: 2.1
+jobs:
+  build:
+    docker:
+      - image: cimg/node:20.0
+    steps:
+      - checkout
+      - run: npm ci
+      - run:
+          name: Test
+          command: npm test
+workflows:
+  main:
+    jobs:
+      - build:
+          filters:
+            branches:
+              ignore: gh-pages
+"#;
+        let jobs = parse_ci_config(CiProvider::Circleci, ".circleci/config.yml", circleci).unwrap();
+        assert_eq!(jobs.len(), 1);
+        assert_eq!(jobs[0].workflow.as_deref(), Some("main"));
+        assert_eq!(jobs[0].environment.as_deref(), Some("cimg/node:20.0"));
+        assert_eq!(jobs[0].actions, ["checkout"]);
+        assert_eq!(jobs[0].commands, ["npm ci", "npm test"]);
+        assert_eq!(jobs[0].triggers, ["push", "branches ignore: gh-pages"]);
+    }
+
+    #[test]
+    fn test_ci_info_tool() {
+        let temp_dir = TempDir::new().unwrap();
+        let mut tool = CiInfoTool::new();
+        let state = Arc::new(Mutex::new(ToolState::new()));
+        let args = ToolArgs::from_args(&[temp_dir.path().to_str().unwrap()]);
+        assert!(!tool.execute(&args, &state).unwrap().success);
+
+        let workflows = temp_dir.path().join(".github").join("workflows");
+        fs::create_dir_all(&workflows).unwrap();
+        fs::write(workflows.join("ci.yml"), WORKFLOW).unwrap();
+        fs::write(workflows.join("broken.yaml"), "jobs: [").unwrap();
+        fs::write(temp_dir.path().join(".gitlab-ci.yml"), GITLAB).unwrap();
+
+        let result = tool.execute(&args, &state).unwrap();
+        assert!(result.success);
+        assert!(result
+            .message
+            .starts_with("4 CI job(s) in 3 configuration file(s)"));
+        assert!(result.message.contains(
+            ".github/workflows/ci.yml (github)\n- CI / lint\n  on: push (branches: main); pull_request; schedule (0 3 * * 1)\n  runs on: ubuntu-latest\n  uses: actions/checkout@v4\n  $ cargo fmt --check"
+        ));
+        assert!(result
+            .message
+            .contains("  $ cd crates/core && cargo build\n    cargo test --workspace"));
+        let data = result.data.unwrap();
+        assert_eq!(data["errors"][0]["file"], ".github/workflows/broken.yaml");
+        assert_eq!(data["jobs"][2]["provider"], "gitlab");
+
+        let mut args = args;
+        args.named_args
+            .insert("job".to_string(), "DOCS".to_string());
+        let data = tool.execute(&args, &state).unwrap().data.unwrap();
+        assert_eq!(data["jobs"].as_array().unwrap().len(), 1);
+        assert_eq!(data["jobs"][0]["name"], "docs");
+    }
+}
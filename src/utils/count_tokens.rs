@@ -1,8 +1,8 @@
-use crate::core::{Tool, ToolArgs, ToolError, ToolResult};
-use crate::state::ToolState;
-use anyhow::Result;
-use std::fs;
-use std::path::PathBuf;
+use crate::core::{DangerLevel, Tool, ToolArgs, ToolError, ToolResult};
+use crate::profiling;
+use crate::state::{session_path, ToolState};
+use crate::utils::suggest::path_not_found;
+use anyhow::{Context, Result};
 use std::sync::{Arc, Mutex};
 
 /// Tool to count tokens using tiktoken_rs cl100k_base encoding.
@@ -49,6 +49,10 @@ impl Tool for CountTokensTool {
         "count_tokens <file_path> OR count_tokens --content=<text>"
     }
 
+    fn danger_level(&self) -> DangerLevel {
+        DangerLevel::ReadOnly
+    }
+
     fn validate_args(&self, args: &ToolArgs) -> Result<(), ToolError> {
         if args.is_empty() {
             return Err(ToolError::InvalidArgs {
@@ -59,7 +63,7 @@ impl Tool for CountTokensTool {
         Ok(())
     }
 
-    fn execute(&mut self, args: &ToolArgs, _state: &Arc<Mutex<ToolState>>) -> Result<ToolResult> {
+    fn execute(&mut self, args: &ToolArgs, state: &Arc<Mutex<ToolState>>) -> Result<ToolResult> {
         // If named arg `content` present, use it. Otherwise, take first positional arg as file path.
         if let Some(content) = args.get_named_arg("content") {
             let count = Self::count_from_text(content);
@@ -70,14 +74,16 @@ impl Tool for CountTokensTool {
         }
 
         let path = args.get_arg(0).unwrap();
-        let path_buf = PathBuf::from(path);
+        let path_buf = session_path(state, path);
 
         if !path_buf.exists() {
-            return Ok(ToolResult::error(format!("Path not found: {}", path)));
+            return Ok(path_not_found(
+                format!("Path not found: {}", path),
+                &path_buf,
+            ));
         }
 
-        let content = fs::read_to_string(&path_buf)
-            .map_err(|e| anyhow::anyhow!("Failed to read file: {}", e))?;
+        let content = profiling::read_to_string(&path_buf).context("Failed to read file")?;
 
         let count = Self::count_from_text(&content);
 
@@ -0,0 +1,1042 @@
+//! dependency_report tool: what a project's lockfiles pull in
+//!
+//! Parses `Cargo.lock`, `package-lock.json` (lockfile versions 1 to 3) and
+//! `poetry.lock` into a dependency graph and reports the direct and transitive
+//! dependency counts, how much each direct dependency pulls in, packages locked at
+//! several versions and outdated entries. Offline, a package counts as outdated when
+//! the tree also holds a newer version of it; with the `net` feature and `registry`,
+//! the latest versions of the direct dependencies are looked up on crates.io, npm or
+//! PyPI.
+
+use crate::core::{DangerLevel, Tool, ToolArgs, ToolError, ToolResult};
+use crate::state::{session_path, ToolState};
+use anyhow::{Context, Result};
+use serde::Serialize;
+use std::cmp::Ordering;
+use std::collections::{BTreeMap, HashMap};
+use std::fs;
+use std::path::{Path, PathBuf};
+use std::sync::{Arc, Mutex};
+
+/// Lockfiles `dependency_report` reads, with the manifest naming their direct
+/// dependencies
+const LOCKFILES: [(&str, Ecosystem); 3] = [
+    ("Cargo.lock", Ecosystem::Cargo),
+    ("package-lock.json", Ecosystem::Npm),
+    ("poetry.lock", Ecosystem::Poetry),
+];
+
+/// Direct dependencies listed in the message, heaviest first
+const LISTED_DIRECT: usize = 15;
+
+/// Direct dependencies looked up on the registry at most per lockfile
+#[cfg(feature = "net")]
+const MAX_REGISTRY_LOOKUPS: usize = 50;
+
+/// Package ecosystem of a lockfile
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "snake_case")]
+pub enum Ecosystem {
+    Cargo,
+    Npm,
+    Poetry,
+}
+
+impl Ecosystem {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            Self::Cargo => "cargo",
+            Self::Npm => "npm",
+            Self::Poetry => "poetry",
+        }
+    }
+}
+
+/// One package of a lockfile
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+pub struct LockedPackage {
+    pub name: String,
+    pub version: String,
+    /// Where it comes from: a registry, git URL or resolved tarball
+    pub source: Option<String>,
+    /// Part of the project itself (workspace member, path dependency, the root)
+    pub local: bool,
+    /// Required by a local package
+    pub direct: bool,
+    /// Only needed for development
+    pub dev: bool,
+}
+
+/// A parsed lockfile: its packages and which package depends on which
+#[derive(Debug, Clone)]
+pub struct Lockfile {
+    pub ecosystem: Ecosystem,
+    pub packages: Vec<LockedPackage>,
+    /// Indices into `packages` each package depends on
+    pub dependencies: Vec<Vec<usize>>,
+}
+
+/// A package locked at more than one version
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+pub struct Duplicate {
+    pub name: String,
+    /// Oldest first
+    pub versions: Vec<String>,
+}
+
+/// A package behind a newer known version
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+pub struct Outdated {
+    pub name: String,
+    pub current: String,
+    pub latest: String,
+    /// `lockfile` when the newer version is elsewhere in the tree, `registry` when
+    /// it was looked up
+    pub basis: &'static str,
+}
+
+impl Lockfile {
+    fn new(
+        ecosystem: Ecosystem,
+        packages: Vec<LockedPackage>,
+        dependencies: Vec<Vec<usize>>,
+    ) -> Self {
+        let mut lockfile = Self {
+            ecosystem,
+            packages,
+            dependencies,
+        };
+        lockfile.mark_direct();
+        lockfile
+    }
+
+    /// Direct dependencies are what local packages require; without local packages,
+    /// the packages nothing else requires
+    fn mark_direct(&mut self) {
+        let has_local = self.packages.iter().any(|package| package.local);
+        let mut direct = vec![false; self.packages.len()];
+        if has_local {
+            for (index, package) in self.packages.iter().enumerate() {
+                if package.local {
+                    for &dependency in &self.dependencies[index] {
+                        direct[dependency] = true;
+                    }
+                }
+            }
+        } else {
+            direct = vec![true; self.packages.len()];
+            for dependencies in &self.dependencies {
+                for &dependency in dependencies {
+                    direct[dependency] = false;
+                }
+            }
+        }
+        for (package, direct) in self.packages.iter_mut().zip(direct) {
+            package.direct = direct && !package.local;
+        }
+    }
+
+    /// Packages that are not part of the project
+    pub fn external(&self) -> impl Iterator<Item = &LockedPackage> {
+        self.packages.iter().filter(|package| !package.local)
+    }
+
+    /// Number of external packages `index` pulls in, itself excluded
+    pub fn transitive_count(&self, index: usize) -> usize {
+        let mut seen = vec![false; self.packages.len()];
+        let mut stack = vec![index];
+        seen[index] = true;
+        let mut count = 0;
+        while let Some(current) = stack.pop() {
+            for &dependency in &self.dependencies[current] {
+                if !seen[dependency] {
+                    seen[dependency] = true;
+                    if !self.packages[dependency].local {
+                        count += 1;
+                    }
+                    stack.push(dependency);
+                }
+            }
+        }
+        count
+    }
+
+    /// Packages locked at several versions
+    pub fn duplicates(&self) -> Vec<Duplicate> {
+        let mut versions: BTreeMap<&str, Vec<&str>> = BTreeMap::new();
+        for package in self.external() {
+            let entry = versions.entry(&package.name).or_default();
+            if !entry.contains(&package.version.as_str()) {
+                entry.push(&package.version);
+            }
+        }
+        versions
+            .into_iter()
+            .filter(|(_, versions)| versions.len() > 1)
+            .map(|(name, mut versions)| {
+                versions.sort_by(|a, b| compare_versions(a, b));
+                Duplicate {
+                    name: name.to_string(),
+                    versions: versions.into_iter().map(str::to_string).collect(),
+                }
+            })
+            .collect()
+    }
+
+    /// Older versions of duplicated packages, measured against the newest in the tree
+    pub fn outdated_in_tree(&self) -> Vec<Outdated> {
+        self.duplicates()
+            .into_iter()
+            .flat_map(|duplicate| {
+                let latest = duplicate.versions.last().cloned().unwrap_or_default();
+                duplicate.versions[..duplicate.versions.len() - 1]
+                    .iter()
+                    .map(|current| Outdated {
+                        name: duplicate.name.clone(),
+                        current: current.clone(),
+                        latest: latest.clone(),
+                        basis: "lockfile",
+                    })
+                    .collect::<Vec<_>>()
+            })
+            .collect()
+    }
+}
+
+/// Compare dotted version numbers numerically, ignoring a leading `v`; a pre-release
+/// (`1.0.0-beta`) sorts before its release
+pub fn compare_versions(a: &str, b: &str) -> Ordering {
+    fn split(version: &str) -> (Vec<u64>, Option<&str>) {
+        let version = version.trim().trim_start_matches('v');
+        let (release, pre) = match version.split_once(['-', '+']) {
+            Some((release, pre)) => (release, Some(pre)),
+            None => (version, None),
+        };
+        let numbers = release
+            .split('.')
+            .map(|part| {
+                part.chars()
+                    .take_while(char::is_ascii_digit)
+                    .collect::<String>()
+                    .parse()
+                    .unwrap_or(0)
+            })
+            .collect();
+        (numbers, pre)
+    }
+    let (a_numbers, a_pre) = split(a);
+    let (b_numbers, b_pre) = split(b);
+    let length = a_numbers.len().max(b_numbers.len());
+    for i in 0..length {
+        let ordering = a_numbers
+            .get(i)
+            .unwrap_or(&0)
+            .cmp(b_numbers.get(i).unwrap_or(&0));
+        if ordering != Ordering::Equal {
+            return ordering;
+        }
+    }
+    match (a_pre, b_pre) {
+        (None, None) => Ordering::Equal,
+        (Some(_), None) => Ordering::Less,
+        (None, Some(_)) => Ordering::Greater,
+        (Some(a), Some(b)) => a.cmp(b),
+    }
+}
+
+/// Parse `Cargo.lock`; packages without a `source` are workspace members or path
+/// dependencies
+pub fn parse_cargo_lock(text: &str) -> Result<Lockfile> {
+    let lock: toml::Value = toml::from_str(text).context("Invalid Cargo.lock")?;
+    let entries = lock
+        .get("package")
+        .and_then(|packages| packages.as_array())
+        .cloned()
+        .unwrap_or_default();
+    let packages: Vec<LockedPackage> = entries
+        .iter()
+        .map(|entry| {
+            let field = |key: &str| entry.get(key).and_then(|value| value.as_str());
+            LockedPackage {
+                name: field("name").unwrap_or_default().to_string(),
+                version: field("version").unwrap_or_default().to_string(),
+                source: field("source").map(str::to_string),
+                local: field("source").is_none(),
+                direct: false,
+                dev: false,
+            }
+        })
+        .collect();
+
+    // Entries are `name`, `name version` or `name version (source)`
+    let dependencies = entries
+        .iter()
+        .map(|entry| {
+            let listed = entry
+                .get("dependencies")
+                .and_then(|dependencies| dependencies.as_array())
+                .cloned()
+                .unwrap_or_default();
+            listed
+                .iter()
+                .filter_map(|dependency| {
+                    let mut parts = dependency.as_str()?.split_whitespace();
+                    let name = parts.next()?;
+                    let version = parts.next();
+                    packages.iter().position(|package| {
+                        package.name == name
+                            && version.is_none_or(|version| package.version == version)
+                    })
+                })
+                .collect()
+        })
+        .collect();
+    Ok(Lockfile::new(Ecosystem::Cargo, packages, dependencies))
+}
+
+/// Names of the dependency maps of a `package.json` or lockfile entry
+fn npm_dependency_names(entry: &serde_json::Value, keys: &[&str]) -> Vec<String> {
+    keys.iter()
+        .filter_map(|key| entry.get(*key)?.as_object())
+        .flat_map(|map| map.keys().cloned())
+        .collect()
+}
+
+/// Parse `package-lock.json`
+pub fn parse_package_lock(text: &str) -> Result<Lockfile> {
+    let lock: serde_json::Value =
+        serde_json::from_str(text).context("Invalid package-lock.json")?;
+    let root_keys = [
+        "dependencies",
+        "devDependencies",
+        "optionalDependencies",
+        "peerDependencies",
+    ];
+    let package_keys = ["dependencies", "optionalDependencies", "peerDependencies"];
+
+    // Lockfile v2 and v3 list every installed path under `packages`
+    if let Some(entries) = lock
+        .get("packages")
+        .and_then(|packages| packages.as_object())
+    {
+        let paths: Vec<&String> = entries.keys().collect();
+        let index: HashMap<&str, usize> = paths
+            .iter()
+            .enumerate()
+            .map(|(i, path)| (path.as_str(), i))
+            .collect();
+        let packages = paths
+            .iter()
+            .map(|path| {
+                let entry = &entries[path.as_str()];
+                let installed = path.rsplit_once("node_modules/").map(|(_, name)| name);
+                LockedPackage {
+                    name: entry
+                        .get("name")
+                        .and_then(|name| name.as_str())
+                        .or(installed)
+                        .unwrap_or(path.as_str())
+                        .to_string(),
+                    version: entry
+                        .get("version")
+                        .and_then(|version| version.as_str())
+                        .unwrap_or_default()
+                        .to_string(),
+                    source: entry
+                        .get("resolved")
+                        .and_then(|resolved| resolved.as_str())
+                        .map(str::to_string),
+                    // The root, workspace directories and links to them
+                    local: installed.is_none()
+                        || entry.get("link").and_then(|link| link.as_bool()) == Some(true),
+                    direct: false,
+                    dev: entry.get("dev").and_then(|dev| dev.as_bool()) == Some(true),
+                }
+            })
+            .collect();
+        // Node resolves a name in the nearest enclosing node_modules
+        let resolve = |from: &str, name: &str| -> Option<usize> {
+            let mut base = from.to_string();
+            loop {
+                let candidate = if base.is_empty() {
+                    format!("node_modules/{}", name)
+                } else {
+                    format!("{}/node_modules/{}", base, name)
+                };
+                if let Some(&found) = index.get(candidate.as_str()) {
+                    return Some(found);
+                }
+                if base.is_empty() {
+                    return None;
+                }
+                base = match base.rfind("node_modules/") {
+                    Some(at) => base[..at].trim_end_matches('/').to_string(),
+                    None => String::new(),
+                };
+            }
+        };
+        let dependencies = paths
+            .iter()
+            .map(|path| {
+                let keys: &[&str] = if path.is_empty() {
+                    &root_keys
+                } else {
+                    &package_keys
+                };
+                npm_dependency_names(&entries[path.as_str()], keys)
+                    .iter()
+                    .filter_map(|name| resolve(path, name))
+                    .collect()
+            })
+            .collect();
+        return Ok(Lockfile::new(Ecosystem::Npm, packages, dependencies));
+    }
+
+    // Lockfile v1 nests `dependencies` with `requires`
+    fn flatten<'a>(
+        map: &'a serde_json::Map<String, serde_json::Value>,
+        out: &mut Vec<(&'a str, &'a serde_json::Value)>,
+    ) {
+        for (name, entry) in map {
+            out.push((name, entry));
+            if let Some(nested) = entry.get("dependencies").and_then(|d| d.as_object()) {
+                flatten(nested, out);
+            }
+        }
+    }
+    let mut flat = Vec::new();
+    if let Some(map) = lock.get("dependencies").and_then(|d| d.as_object()) {
+        flatten(map, &mut flat);
+    }
+    let packages: Vec<LockedPackage> = flat
+        .iter()
+        .map(|(name, entry)| LockedPackage {
+            name: name.to_string(),
+            version: entry
+                .get("version")
+                .and_then(|version| version.as_str())
+                .unwrap_or_default()
+                .to_string(),
+            source: entry
+                .get("resolved")
+                .and_then(|resolved| resolved.as_str())
+                .map(str::to_string),
+            local: false,
+            direct: false,
+            dev: entry.get("dev").and_then(|dev| dev.as_bool()) == Some(true),
+        })
+        .collect();
+    let dependencies = flat
+        .iter()
+        .map(|(_, entry)| {
+            npm_dependency_names(entry, &["requires"])
+                .iter()
+                .filter_map(|name| packages.iter().position(|package| &package.name == name))
+                .collect()
+        })
+        .collect();
+    Ok(Lockfile::new(Ecosystem::Npm, packages, dependencies))
+}
+
+/// PEP 503 normalized Python package name
+pub(crate) fn normalize_python_name(name: &str) -> String {
+    name.trim().to_lowercase().replace(['_', '.'], "-")
+}
+
+/// Direct dependency names a `pyproject.toml` declares, for Poetry and PEP 621
+fn pyproject_dependencies(text: &str) -> Vec<String> {
+    let Ok(pyproject) = toml::from_str::<toml::Value>(text) else {
+        return Vec::new();
+    };
+    let mut names = Vec::new();
+    let table_keys = |table: Option<&toml::Value>| -> Vec<String> {
+        table
+            .and_then(|table| table.as_table())
+            .map(|table| table.keys().cloned().collect())
+            .unwrap_or_default()
+    };
+    if let Some(poetry) = pyproject.get("tool").and_then(|tool| tool.get("poetry")) {
+        names.extend(table_keys(poetry.get("dependencies")));
+        names.extend(table_keys(poetry.get("dev-dependencies")));
+        if let Some(groups) = poetry.get("group").and_then(|groups| groups.as_table()) {
+            for group in groups.values() {
+                names.extend(table_keys(group.get("dependencies")));
+            }
+        }
+    }
+    // PEP 508 requirement strings: the name ends at the first version or extra marker
+    let requirement_name = |requirement: &toml::Value| {
+        requirement.as_str().and_then(|requirement| {
+            requirement
+                .split(|c: char| " <>=!~;[(".contains(c))
+                .next()
+                .map(str::to_string)
+        })
+    };
+    if let Some(project) = pyproject.get("project") {
+        if let Some(requirements) = project.get("dependencies").and_then(|d| d.as_array()) {
+            names.extend(requirements.iter().filter_map(requirement_name));
+        }
+        if let Some(extras) = project
+            .get("optional-dependencies")
+            .and_then(|d| d.as_table())
+        {
+            for requirements in extras.values().filter_map(|r| r.as_array()) {
+                names.extend(requirements.iter().filter_map(requirement_name));
+            }
+        }
+    }
+    names
+        .into_iter()
+        .filter(|name| name != "python")
+        .map(|name| normalize_python_name(&name))
+        .collect()
+}
+
+/// Parse `poetry.lock`; `pyproject` (the project's `pyproject.toml`) names the direct
+/// dependencies
+pub fn parse_poetry_lock(text: &str, pyproject: Option<&str>) -> Result<Lockfile> {
+    let lock: toml::Value = toml::from_str(text).context("Invalid poetry.lock")?;
+    let entries = lock
+        .get("package")
+        .and_then(|packages| packages.as_array())
+        .cloned()
+        .unwrap_or_default();
+    let mut packages: Vec<LockedPackage> = entries
+        .iter()
+        .map(|entry| {
+            let field = |key: &str| entry.get(key).and_then(|value| value.as_str());
+            // Poetry 1 records a `category`, Poetry 2 the `groups` needing the package
+            let groups: Vec<&str> = entry
+                .get("groups")
+                .and_then(|groups| groups.as_array())
+                .map(|groups| groups.iter().filter_map(|g| g.as_str()).collect())
+                .unwrap_or_default();
+            let dev =
+                field("category") == Some("dev") || !groups.is_empty() && !groups.contains(&"main");
+            LockedPackage {
+                name: normalize_python_name(field("name").unwrap_or_default()),
+                version: field("version").unwrap_or_default().to_string(),
+                source: entry
+                    .get("source")
+                    .and_then(|source| source.get("url"))
+                    .and_then(|url| url.as_str())
+                    .map(str::to_string),
+                local: false,
+                direct: false,
+                dev,
+            }
+        })
+        .collect();
+    let position = |packages: &[LockedPackage], name: &str| {
+        let name = normalize_python_name(name);
+        packages.iter().position(|package| package.name == name)
+    };
+    let mut dependencies: Vec<Vec<usize>> = entries
+        .iter()
+        .map(|entry| {
+            entry
+                .get("dependencies")
+                .and_then(|dependencies| dependencies.as_table())
+                .map(|table| {
+                    table
+                        .keys()
+                        .filter_map(|name| position(&packages, name))
+                        .collect()
+                })
+                .unwrap_or_default()
+        })
+        .collect();
+
+    // The project itself, requiring what pyproject.toml declares
+    let declared = pyproject.map(pyproject_dependencies).unwrap_or_default();
+    if !declared.is_empty() {
+        let direct = declared
+            .iter()
+            .filter_map(|name| position(&packages, name))
+            .collect();
+        packages.push(LockedPackage {
+            name: "(project)".to_string(),
+            version: String::new(),
+            source: None,
+            local: true,
+            direct: false,
+            dev: false,
+        });
+        dependencies.push(direct);
+    }
+    Ok(Lockfile::new(Ecosystem::Poetry, packages, dependencies))
+}
+
+/// Parse the lockfile at `path`, reading the manifest next to it when needed
+pub fn parse_lockfile(path: &Path) -> Result<Lockfile> {
+    let text =
+        fs::read_to_string(path).with_context(|| format!("Failed to read {}", path.display()))?;
+    match path.file_name().and_then(|name| name.to_str()) {
+        Some("Cargo.lock") => parse_cargo_lock(&text),
+        Some("package-lock.json") => parse_package_lock(&text),
+        Some("poetry.lock") => {
+            let pyproject = path
+                .parent()
+                .and_then(|dir| fs::read_to_string(dir.join("pyproject.toml")).ok());
+            parse_poetry_lock(&text, pyproject.as_deref())
+        }
+        _ => anyhow::bail!(
+            "{} is not a supported lockfile (Cargo.lock, package-lock.json or poetry.lock)",
+            path.display()
+        ),
+    }
+}
+
+/// The lockfiles in `dir`
+pub fn find_lockfiles(dir: &Path) -> Vec<PathBuf> {
+    LOCKFILES
+        .iter()
+        .map(|(name, _)| dir.join(name))
+        .filter(|path| path.is_file())
+        .collect()
+}
+
+/// Latest published version of `name` on the ecosystem's registry
+#[cfg(feature = "net")]
+pub fn latest_version(ecosystem: Ecosystem, name: &str) -> Result<String> {
+    let (url, pointer) = match ecosystem {
+        Ecosystem::Cargo => (
+            format!("https://crates.io/api/v1/crates/{}", name),
+            "/crate/max_stable_version",
+        ),
+        Ecosystem::Npm => (
+            format!(
+                "https://registry.npmjs.org/{}/latest",
+                name.replace('/', "%2F")
+            ),
+            "/version",
+        ),
+        Ecosystem::Poetry => (
+            format!("https://pypi.org/pypi/{}/json", name),
+            "/info/version",
+        ),
+    };
+    let response = ureq::get(&url)
+        .timeout(std::time::Duration::from_secs(
+            crate::forge::client::REQUEST_TIMEOUT_SECS,
+        ))
+        .set("User-Agent", concat!("cats/", env!("CARGO_PKG_VERSION")))
+        .call()
+        .map_err(|e| anyhow::anyhow!("Request to {} failed: {}", url, e))?;
+    let body: serde_json::Value = serde_json::from_str(&response.into_string()?)
+        .with_context(|| format!("Invalid JSON from {}", url))?;
+    body.pointer(pointer)
+        .and_then(|version| version.as_str())
+        .map(str::to_string)
+        .ok_or_else(|| anyhow::anyhow!("{} has no version for {}", url, name))
+}
+
+/// Tool summarizing the dependency trees of a project's lockfiles
+pub struct DependencyReportTool {
+    name: String,
+}
+
+impl DependencyReportTool {
+    pub fn new() -> Self {
+        Self {
+            name: "dependency_report".to_string(),
+        }
+    }
+
+    /// Latest versions of the direct dependencies, or why they were not looked up
+    fn registry_outdated(lockfile: &Lockfile) -> Result<Vec<Outdated>, String> {
+        #[cfg(feature = "net")]
+        {
+            let mut outdated = Vec::new();
+            let mut failures = 0;
+            for package in lockfile
+                .external()
+                .filter(|package| package.direct)
+                .take(MAX_REGISTRY_LOOKUPS)
+            {
+                match latest_version(lockfile.ecosystem, &package.name) {
+                    Ok(latest) => {
+                        if compare_versions(&package.version, &latest) == Ordering::Less {
+                            outdated.push(Outdated {
+                                name: package.name.clone(),
+                                current: package.version.clone(),
+                                latest,
+                                basis: "registry",
+                            });
+                        }
+                    }
+                    Err(e) => {
+                        tracing::debug!("Registry lookup failed: {:#}", e);
+                        failures += 1;
+                    }
+                }
+            }
+            if failures > 0 && outdated.is_empty() {
+                return Err(format!("{} registry lookup(s) failed", failures));
+            }
+            Ok(outdated)
+        }
+        #[cfg(not(feature = "net"))]
+        {
+            let _ = lockfile;
+            Err("registry lookups need the `net` feature".to_string())
+        }
+    }
+
+    fn report(lockfile: &Lockfile, file: &str, registry: bool) -> (String, serde_json::Value) {
+        let external: Vec<&LockedPackage> = lockfile.external().collect();
+        let mut direct: Vec<(usize, &LockedPackage, usize)> = lockfile
+            .packages
+            .iter()
+            .enumerate()
+            .filter(|(_, package)| package.direct)
+            .map(|(index, package)| (index, package, lockfile.transitive_count(index)))
+            .collect();
+        direct.sort_by(|a, b| b.2.cmp(&a.2).then_with(|| a.1.name.cmp(&b.1.name)));
+        let duplicates = lockfile.duplicates();
+        let mut outdated = lockfile.outdated_in_tree();
+        let mut registry_note = None;
+        if registry {
+            match Self::registry_outdated(lockfile) {
+                Ok(found) => outdated.extend(found),
+                Err(note) => registry_note = Some(note),
+            }
+        }
+
+        let mut message = format!(
+            "{} ({}): {} packages, {} direct, {} transitive\n",
+            file,
+            lockfile.ecosystem.as_str(),
+            external.len(),
+            direct.len(),
+            external.len() - direct.len()
+        );
+        if !direct.is_empty() {
+            message.push_str("Direct dependencies (packages each pulls in):\n");
+            for (_, package, transitive) in direct.iter().take(LISTED_DIRECT) {
+                message.push_str(&format!(
+                    "  {} {}{} - {}\n",
+                    package.name,
+                    package.version,
+                    if package.dev { " (dev)" } else { "" },
+                    transitive
+                ));
+            }
+            if direct.len() > LISTED_DIRECT {
+                message.push_str(&format!(
+                    "  ... and {} more\n",
+                    direct.len() - LISTED_DIRECT
+                ));
+            }
+        }
+        if !duplicates.is_empty() {
+            message.push_str(&format!("Duplicate versions ({}):\n", duplicates.len()));
+            for duplicate in &duplicates {
+                message.push_str(&format!(
+                    "  {}: {}\n",
+                    duplicate.name,
+                    duplicate.versions.join(", ")
+                ));
+            }
+        }
+        if !outdated.is_empty() {
+            message.push_str(&format!("Outdated ({}):\n", outdated.len()));
+            for entry in &outdated {
+                message.push_str(&format!(
+                    "  {} {} -> {} ({})\n",
+                    entry.name, entry.current, entry.latest, entry.basis
+                ));
+            }
+        }
+        if let Some(note) = &registry_note {
+            message.push_str(&format!("Registry not checked: {}\n", note));
+        }
+
+        let data = serde_json::json!({
+            "file": file,
+            "ecosystem": lockfile.ecosystem,
+            "total": external.len(),
+            "direct": direct.len(),
+            "transitive": external.len() - direct.len(),
+            "direct_dependencies": direct.iter().map(|(_, package, transitive)| serde_json::json!({
+                "name": package.name,
+                "version": package.version,
+                "dev": package.dev,
+                "transitive": transitive,
+            })).collect::<Vec<_>>(),
+            "duplicates": duplicates,
+            "outdated": outdated,
+            "registry_note": registry_note,
+        });
+        (message, data)
+    }
+}
+
+impl Default for DependencyReportTool {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl Tool for DependencyReportTool {
+    fn name(&self) -> &str {
+        &self.name
+    }
+
+    fn description(&self) -> &str {
+        "Report a project's dependencies from Cargo.lock, package-lock.json or poetry.lock: direct and transitive counts, what each direct dependency pulls in, duplicate versions and outdated packages (registry lookups with --registry and the net feature)"
+    }
+
+    fn signature(&self) -> &str {
+        "dependency_report [path] [--registry]"
+    }
+
+    fn danger_level(&self) -> DangerLevel {
+        DangerLevel::ReadOnly
+    }
+
+    fn validate_args(&self, _args: &ToolArgs) -> Result<(), ToolError> {
+        Ok(())
+    }
+
+    fn execute(&mut self, args: &ToolArgs, state: &Arc<Mutex<ToolState>>) -> Result<ToolResult> {
+        let path = match args.get_named_arg("path").or_else(|| args.get_arg(0)) {
+            Some(path) => session_path(state, path),
+            None => {
+                let state_guard = state
+                    .lock()
+                    .map_err(|e| anyhow::anyhow!("Failed to lock state: {}", e))?;
+                state_guard.working_directory.clone()
+            }
+        };
+        if !path.exists() {
+            return Ok(crate::utils::suggest::path_not_found(
+                format!("Path {} not found", path.display()),
+                &path,
+            ));
+        }
+        let lockfiles = if path.is_file() {
+            vec![path.clone()]
+        } else {
+            find_lockfiles(&path)
+        };
+        if lockfiles.is_empty() {
+            return Ok(ToolResult::error(format!(
+                "No lockfile in {} (looked for {})",
+                path.display(),
+                LOCKFILES
+                    .iter()
+                    .map(|(name, _)| *name)
+                    .collect::<Vec<_>>()
+                    .join(", ")
+            )));
+        }
+        let registry = args
+            .get_named_arg("registry")
+            .is_some_and(|registry| registry != "false");
+
+        let mut messages = Vec::new();
+        let mut reports = Vec::new();
+        let mut errors = Vec::new();
+        for lockfile_path in &lockfiles {
+            let file = lockfile_path
+                .file_name()
+                .map(|name| name.to_string_lossy().to_string())
+                .unwrap_or_default();
+            match parse_lockfile(lockfile_path) {
+                Ok(lockfile) => {
+                    let (message, data) = Self::report(&lockfile, &file, registry);
+                    messages.push(message);
+                    reports.push(data);
+                }
+                Err(e) => {
+                    messages.push(format!("{}: {:#}\n", file, e));
+                    errors.push(serde_json::json!({"file": file, "error": format!("{:#}", e)}));
+                }
+            }
+        }
+
+        let data = serde_json::json!({"lockfiles": reports, "errors": errors});
+        let message = messages.join("\n").trim_end().to_string();
+        if reports.is_empty() {
+            return Ok(ToolResult::error_with_data(message, data));
+        }
+        Ok(ToolResult::success_with_data(message, data))
+    }
+
+    fn get_parameters_schema(&self) -> serde_json::Value {
+        serde_json::json!({
+            "type": "object",
+            "properties": {
+                "path": {
+                    "type": "string",
+                    "description": "Project directory or lockfile (defaults to the working directory)"
+                },
+                "registry": {
+                    "type": "boolean",
+                    "description": "Look up the latest versions of direct dependencies on crates.io, npm or PyPI (needs the net feature)"
+                }
+            },
+            "required": []
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const CARGO_LOCK: &str = r#"
+version = 4
+
+[[package]]
+name = "app"
+version = "0.1.0"
+dependencies = ["serde", "syn 2.0.87", "helper"]
+
+[[package]]
+name = "helper"
+version = "0.1.0"
+dependencies = ["syn 1.0.109"]
+
+[[package]]
+name = "serde"
+version = "1.0.210"
+source = "registry+https://github.com/rust-lang/crates.io-index"
+dependencies = ["serde_derive"]
+
+[[package]]
+name = "serde_derive"
+version = "1.0.210"
+source = "registry+https://github.com/rust-lang/crates.io-index"
+dependencies = ["syn 2.0.87"]
+
+[[package]]
+name = "syn"
+version = "1.0.109"
+source = "registry+https://github.com/rust-lang/crates.io-index"
+
+[[package]]
+name = "syn"
+version = "2.0.87"
+source = "registry+https://github.com/rust-lang/crates.io-index"
+"#;
+
+    #[test]
+    fn test_cargo_lock() {
+        let lockfile = parse_cargo_lock(CARGO_LOCK).unwrap();
+        let direct: Vec<String> = lockfile
+            .packages
+            .iter()
+            .filter(|package| package.direct)
+            .map(|package| format!("{} {}", package.name, package.version))
+            .collect();
+        // helper is a path dependency, so its syn 1 is direct too
+        assert_eq!(direct, vec!["serde 1.0.210", "syn 1.0.109", "syn 2.0.87"]);
+        assert_eq!(lockfile.transitive_count(2), 2);
+        assert_eq!(
+            lockfile.duplicates(),
+            vec![Duplicate {
+                name: "syn".to_string(),
+                versions: vec!["1.0.109".to_string(), "2.0.87".to_string()],
+            }]
+        );
+        let outdated = lockfile.outdated_in_tree();
+        assert_eq!(
+            (outdated[0].current.as_str(), outdated[0].latest.as_str()),
+            ("1.0.109", "2.0.87")
+        );
+    }
+
+    #[test]
+    fn test_package_lock() {
+        let v3 = r#"{
+            "lockfileVersion": 3,
+            "packages": {
+                "": {"name": "web", "dependencies": {"a": "^1"}, "devDependencies": {"jest": "^29"}},
+                "node_modules/a": {"version": "1.2.0", "dependencies": {"b": "^2"}},
+                "node_modules/a/node_modules/b": {"version": "2.0.0"},
+                "node_modules/b": {"version": "1.0.0"},
+                "node_modules/jest": {"version": "29.7.0", "dev": true, "dependencies": {"b": "^1"}}
+            }
+        }"#;
+        let lockfile = parse_package_lock(v3).unwrap();
+        let a = lockfile
+            .packages
+            .iter()
+            .position(|p| p.name == "a")
+            .unwrap();
+        let nested_b = &lockfile.packages[lockfile.dependencies[a][0]];
+        assert_eq!(nested_b.version, "2.0.0");
+        let direct: Vec<&str> = lockfile
+            .packages
+            .iter()
+            .filter(|package| package.direct)
+            .map(|package| package.name.as_str())
+            .collect();
+        assert_eq!(direct, vec!["a", "jest"]);
+        assert!(lockfile.packages.iter().any(|p| p.name == "jest" && p.dev));
+        assert_eq!(lockfile.duplicates()[0].versions, vec!["1.0.0", "2.0.0"]);
+
+        let v1 = r#"{"lockfileVersion": 1, "dependencies": {
+            "a": {"version": "1.0.0", "requires": {"b": "^1"}},
+            "b": {"version": "1.1.0"}
+        }}"#;
+        let lockfile = parse_package_lock(v1).unwrap();
+        assert!(lockfile.packages[0].direct);
+        assert!(!lockfile.packages[1].direct);
+    }
+
+    #[test]
+    fn test_poetry_lock_and_tool() {
+        let temp_dir = tempfile::TempDir::new().unwrap();
+        fs::write(
+            temp_dir.path().join("poetry.lock"),
+            r#"
+[[package]]
+name = "Requests"
+version = "2.31.0"
+groups = ["main"]
+
+[package.dependencies]
+urllib3 = ">=1.21.1,<3"
+
+[[package]]
+name = "urllib3"
+version = "2.2.1"
+groups = ["main"]
+
+[[package]]
+name = "pytest"
+version = "8.0.0"
+category = "dev"
+"#,
+        )
+        .unwrap();
+        fs::write(
+            temp_dir.path().join("pyproject.toml"),
+            "[tool.poetry.dependencies]\npython = \"^3.11\"\nrequests = \"^2\"\n\n[tool.poetry.group.dev.dependencies]\npytest = \"^8\"\n",
+        )
+        .unwrap();
+
+        let mut tool = DependencyReportTool::new();
+        let state = Arc::new(Mutex::new(ToolState::new()));
+        let args = ToolArgs::from_args(&[temp_dir.path().to_str().unwrap()]);
+        let result = tool.execute(&args, &state).unwrap();
+        assert!(result.success, "{}", result.message);
+        assert!(result
+            .message
+            .starts_with("poetry.lock (poetry): 3 packages, 2 direct, 1 transitive\n"));
+        assert!(result
+            .message
+            .contains("  requests 2.31.0 - 1\n  pytest 8.0.0 (dev) - 0"));
+    }
+
+    #[test]
+    fn test_compare_versions() {
+        assert_eq!(compare_versions("1.10.0", "1.9.3"), Ordering::Greater);
+        assert_eq!(compare_versions("v2.0", "2.0.0"), Ordering::Equal);
+        assert_eq!(compare_versions("1.0.0-beta.1", "1.0.0"), Ordering::Less);
+    }
+}
@@ -0,0 +1,241 @@
+use crate::core::{
+    DangerLevel, Tool, ToolArgs, ToolCatalog, ToolDescriptor, ToolError, ToolResult,
+};
+use crate::state::ToolState;
+use crate::utils::suggest::{closest_matches, format_suggestions, MAX_SUGGESTIONS};
+use anyhow::Result;
+use serde_json::Value;
+use std::sync::{Arc, Mutex};
+
+/// Tool that describes any registered tool: schema, signature and a usage example
+pub struct DescribeToolTool {
+    name: String,
+    catalog: ToolCatalog,
+}
+
+impl DescribeToolTool {
+    pub fn new(catalog: ToolCatalog) -> Self {
+        Self {
+            name: "describe_tool".to_string(),
+            catalog,
+        }
+    }
+
+    /// Placeholder value for a schema property, used to build usage examples
+    fn example_value(name: &str, property: &Value) -> Value {
+        if let Some(first) = property
+            .get("enum")
+            .and_then(|e| e.as_array())
+            .and_then(|e| e.first())
+        {
+            return first.clone();
+        }
+        match property.get("type").and_then(|t| t.as_str()) {
+            Some("integer") | Some("number") => serde_json::json!(1),
+            Some("boolean") => serde_json::json!(true),
+            Some("array") => serde_json::json!([]),
+            Some("object") => serde_json::json!({}),
+            _ => Value::String(format!("<{}>", name)),
+        }
+    }
+
+    /// Build an example call using the required parameters of a tool
+    pub fn usage_example(descriptor: &ToolDescriptor) -> Value {
+        let mut arguments = serde_json::Map::new();
+        let properties = descriptor.parameters.get("properties");
+        if let Some(required) = descriptor
+            .parameters
+            .get("required")
+            .and_then(|r| r.as_array())
+        {
+            for key in required.iter().filter_map(|k| k.as_str()) {
+                let property = properties
+                    .and_then(|p| p.get(key))
+                    .cloned()
+                    .unwrap_or(Value::Null);
+                arguments.insert(key.to_string(), Self::example_value(key, &property));
+            }
+        }
+        serde_json::json!({
+            "name": descriptor.name,
+            "arguments": arguments
+        })
+    }
+
+    fn format_parameters(parameters: &Value) -> Vec<String> {
+        let required: Vec<&str> = parameters
+            .get("required")
+            .and_then(|r| r.as_array())
+            .map(|r| r.iter().filter_map(|k| k.as_str()).collect())
+            .unwrap_or_default();
+
+        let mut lines = Vec::new();
+        if let Some(properties) = parameters.get("properties").and_then(|p| p.as_object()) {
+            let mut keys: Vec<&String> = properties.keys().collect();
+            keys.sort_by_key(|k| (!required.contains(&k.as_str()), k.as_str()));
+            for key in keys {
+                let property = &properties[key];
+                let kind = property
+                    .get("type")
+                    .and_then(|t| t.as_str())
+                    .unwrap_or("any");
+                let description = property
+                    .get("description")
+                    .and_then(|d| d.as_str())
+                    .unwrap_or("");
+                let marker = if required.contains(&key.as_str()) {
+                    "required"
+                } else {
+                    "optional"
+                };
+                lines.push(format!(
+                    "  - {} ({}, {}): {}",
+                    key, kind, marker, description
+                ));
+            }
+        }
+        lines
+    }
+}
+
+impl Tool for DescribeToolTool {
+    fn name(&self) -> &str {
+        &self.name
+    }
+
+    fn description(&self) -> &str {
+        "Describe a registered tool: its description, signature, parameter schema and a usage example"
+    }
+
+    fn signature(&self) -> &str {
+        "describe_tool <tool_name>"
+    }
+
+    fn danger_level(&self) -> DangerLevel {
+        DangerLevel::ReadOnly
+    }
+
+    fn validate_args(&self, args: &ToolArgs) -> Result<(), ToolError> {
+        if args.get_arg(0).is_none() && args.get_named_arg("tool_name").is_none() {
+            return Err(ToolError::InvalidArgs {
+                message: "Usage: describe_tool <tool_name>".to_string(),
+            });
+        }
+        Ok(())
+    }
+
+    fn execute(&mut self, args: &ToolArgs, _state: &Arc<Mutex<ToolState>>) -> Result<ToolResult> {
+        let tool_name = args
+            .get_named_arg("tool_name")
+            .or_else(|| args.get_arg(0))
+            .ok_or_else(|| anyhow::anyhow!("Missing tool_name parameter"))?;
+
+        let descriptor = match self.catalog.get(tool_name) {
+            Some(descriptor) => descriptor,
+            None => {
+                let names = self.catalog.names();
+                let suggestions =
+                    closest_matches(tool_name, names.iter().map(|n| n.as_str()), MAX_SUGGESTIONS);
+                return Ok(ToolResult::error_with_data(
+                    format!(
+                        "Tool not found: {}{}",
+                        tool_name,
+                        format_suggestions(&suggestions)
+                    ),
+                    serde_json::json!({
+                        "suggestions": suggestions,
+                        "available_tools": names
+                    }),
+                ));
+            }
+        };
+
+        let example = Self::usage_example(&descriptor);
+        let mut message = format!(
+            "Tool: {}\nCategory: {}\nDanger level: {}\nDescription: {}\nSignature: {}\n",
+            descriptor.name,
+            descriptor.category,
+            descriptor.danger_level,
+            descriptor.description,
+            descriptor.signature
+        );
+        let parameters = Self::format_parameters(&descriptor.parameters);
+        if parameters.is_empty() {
+            message.push_str("Parameters: none\n");
+        } else {
+            message.push_str("Parameters:\n");
+            message.push_str(&parameters.join("\n"));
+            message.push('\n');
+        }
+        message.push_str(&format!("Example call: {}", example));
+
+        Ok(ToolResult::success_with_data(
+            message,
+            serde_json::json!({
+                "name": descriptor.name,
+                "category": descriptor.category,
+                "danger_level": descriptor.danger_level,
+                "description": descriptor.description,
+                "signature": descriptor.signature,
+                "parameters": descriptor.parameters,
+                "example": example
+            }),
+        ))
+    }
+
+    fn get_parameters_schema(&self) -> Value {
+        serde_json::json!({
+            "type": "object",
+            "properties": {
+                "tool_name": {
+                    "type": "string",
+                    "description": "Name of the registered tool to describe"
+                }
+            },
+            "required": ["tool_name"]
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::core::ToolRegistry;
+    use crate::utils::CountTokensTool;
+
+    fn registry_with_describe() -> ToolRegistry {
+        let mut registry = ToolRegistry::new();
+        registry.register(Box::new(CountTokensTool::new()));
+        registry.register(Box::new(DescribeToolTool::new(registry.catalog())));
+        registry
+    }
+
+    #[test]
+    fn test_describe_registered_tool() {
+        let mut registry = registry_with_describe();
+        let result = registry
+            .execute_tool("describe_tool", &ToolArgs::from_args(&["describe_tool"]))
+            .unwrap();
+
+        assert!(result.success);
+        assert!(result
+            .message
+            .contains("Signature: describe_tool <tool_name>"));
+        assert!(result.message.contains("tool_name (string, required)"));
+        let data = result.data.unwrap();
+        assert_eq!(data["example"]["arguments"]["tool_name"], "<tool_name>");
+        assert_eq!(data["category"], "utility");
+        assert_eq!(data["danger_level"], "read_only");
+    }
+
+    #[test]
+    fn test_describe_unknown_tool_suggests() {
+        let mut registry = registry_with_describe();
+        let result = registry
+            .execute_tool("describe_tool", &ToolArgs::from_args(&["count_token"]))
+            .unwrap();
+
+        assert!(!result.success);
+        assert!(result.message.contains("Did you mean: count_tokens?"));
+    }
+}
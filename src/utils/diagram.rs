@@ -0,0 +1,136 @@
+//! SVG rendering of tool output for multimodal models and UIs
+//!
+//! Diagrams are plain SVG text drawn with lines and monospace labels, so rendering
+//! needs no graphics dependencies. PNG output would need a rasterizer and is left to
+//! the embedder.
+
+use std::fmt::Write;
+
+const ROW_HEIGHT: usize = 20;
+const INDENT: usize = 20;
+const MARGIN: usize = 12;
+/// Approximate advance of one character of the 13px monospace labels
+const CHAR_WIDTH: usize = 8;
+
+/// One entry of a directory tree, in depth-first order
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct TreeNode {
+    /// 1 for the root's children, 2 for theirs, and so on
+    pub depth: usize,
+    pub name: String,
+    pub is_dir: bool,
+}
+
+/// A tree diagram of `nodes` under `root`, with a closing note when `more` entries
+/// were left out
+pub fn tree_svg(root: &str, nodes: &[TreeNode], more: usize) -> String {
+    let mut rows: Vec<(usize, String, bool)> = vec![(0, root.to_string(), true)];
+    rows.extend(nodes.iter().map(|node| {
+        let name = if node.is_dir {
+            format!("{}/", node.name)
+        } else {
+            node.name.clone()
+        };
+        (node.depth, name, node.is_dir)
+    }));
+    if more > 0 {
+        rows.push((1, format!("... and {} more items", more), false));
+    }
+
+    let x = |depth: usize| MARGIN + depth * INDENT;
+    let y = |row: usize| MARGIN + row * ROW_HEIGHT + ROW_HEIGHT / 2;
+    let width = rows
+        .iter()
+        .map(|(depth, name, _)| x(*depth) + 12 + name.chars().count() * CHAR_WIDTH)
+        .max()
+        .unwrap_or(0)
+        + MARGIN;
+    let height = rows.len() * ROW_HEIGHT + 2 * MARGIN;
+
+    let mut svg = format!(
+        "<svg xmlns=\"http://www.w3.org/2000/svg\" width=\"{width}\" height=\"{height}\" \
+         viewBox=\"0 0 {width} {height}\" font-family=\"monospace\" font-size=\"13\">\n\
+         <rect width=\"100%\" height=\"100%\" fill=\"#ffffff\"/>\n"
+    );
+    // Row of the latest entry at each depth, to connect children to their parent
+    let mut parents: Vec<usize> = Vec::new();
+    for (row, (depth, name, is_dir)) in rows.iter().enumerate() {
+        parents.truncate(*depth);
+        if let Some(&parent) = parents.last() {
+            let _ = writeln!(
+                svg,
+                "<path d=\"M{} {} V{} H{}\" fill=\"none\" stroke=\"#9ca3af\"/>",
+                x(depth - 1) + 4,
+                y(parent) + 6,
+                y(row),
+                x(*depth)
+            );
+        }
+        let (fill, weight) = if *is_dir {
+            ("#1d4ed8", "bold")
+        } else {
+            ("#111827", "normal")
+        };
+        let _ = writeln!(
+            svg,
+            "<text x=\"{}\" y=\"{}\" dominant-baseline=\"middle\" fill=\"{}\" font-weight=\"{}\">{}</text>",
+            x(*depth) + 2,
+            y(row),
+            fill,
+            weight,
+            escape(name)
+        );
+        parents.push(row);
+    }
+    svg.push_str("</svg>\n");
+    svg
+}
+
+/// `text` with the XML special characters escaped
+fn escape(text: &str) -> String {
+    let mut escaped = String::with_capacity(text.len());
+    for c in text.chars() {
+        match c {
+            '&' => escaped.push_str("&amp;"),
+            '<' => escaped.push_str("&lt;"),
+            '>' => escaped.push_str("&gt;"),
+            '"' => escaped.push_str("&quot;"),
+            c => escaped.push(c),
+        }
+    }
+    escaped
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_tree_svg() {
+        let node = |depth: usize, name: &str, is_dir: bool| TreeNode {
+            depth,
+            name: name.to_string(),
+            is_dir,
+        };
+        let svg = tree_svg(
+            "project",
+            &[
+                node(1, "src", true),
+                node(2, "a<b>.rs", false),
+                node(1, "README.md", false),
+            ],
+            2,
+        );
+
+        assert!(svg.starts_with("<svg xmlns=\"http://www.w3.org/2000/svg\""));
+        assert!(svg.trim_end().ends_with("</svg>"));
+        assert!(svg.contains(">src/</text>"));
+        assert!(svg.contains(">a&lt;b&gt;.rs</text>"));
+        assert!(svg.contains(">... and 2 more items</text>"));
+        // One connector per entry below the root
+        assert_eq!(svg.matches("<path ").count(), 4);
+        // a<b>.rs hangs off src (row 1), README.md off the root (row 0)
+        assert!(svg.contains("<path d=\"M36 48 V62 H52\""));
+        assert!(svg.contains("<path d=\"M16 28 V82 H32\""));
+    }
+}
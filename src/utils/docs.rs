@@ -0,0 +1,603 @@
+//! read_doc tool: one section of a Markdown or reStructuredText document
+//!
+//! Documents are split at their headings: ATX (`## Build`) and setext (underlined)
+//! headings in Markdown, underlined and over-and-underlined titles in
+//! reStructuredText, where levels follow the order in which adornment styles first
+//! appear. Headings inside fenced or literal code blocks are ignored. A section runs
+//! from its heading to the next heading of the same or a higher level, so it includes
+//! its subsections.
+
+use crate::core::{DangerLevel, Tool, ToolArgs, ToolError, ToolResult};
+use crate::state::ToolState;
+use crate::utils::suggest::{closest_matches, format_suggestions, path_not_found};
+use anyhow::Result;
+use serde::Serialize;
+use std::fmt::Write as _;
+use std::fs;
+use std::path::Path;
+use std::sync::{Arc, Mutex};
+
+/// Lines of a section returned before it is cut
+const MAX_SECTION_LINES: usize = 400;
+
+/// Headings listed in the table of contents
+const MAX_OUTLINE_HEADINGS: usize = 200;
+
+/// Separator between heading titles in a nested `section` such as `Install > From source`
+const SECTION_SEPARATOR: char = '>';
+
+/// Markup a document is parsed as
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "lowercase")]
+pub enum DocFormat {
+    Markdown,
+    Rst,
+}
+
+impl DocFormat {
+    /// `.rst` and `.rest` files are reStructuredText; anything else is read as Markdown
+    pub fn from_path(path: &Path) -> Self {
+        match path.extension().and_then(|e| e.to_str()) {
+            Some(ext) if ext.eq_ignore_ascii_case("rst") || ext.eq_ignore_ascii_case("rest") => {
+                Self::Rst
+            }
+            _ => Self::Markdown,
+        }
+    }
+}
+
+/// A heading and the extent of its section, with 1-based inclusive line numbers
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+pub struct Heading {
+    pub title: String,
+    /// 1 for the top level
+    pub level: usize,
+    /// Line of the heading text (of the overline for reStructuredText titles with one)
+    pub start_line: usize,
+    /// Last line before the next heading of the same or a higher level
+    pub end_line: usize,
+}
+
+/// A fence that opens a Markdown code block: the fence character and its length
+fn code_fence(line: &str) -> Option<(char, usize)> {
+    let trimmed = line.trim_start();
+    if line.len() - trimmed.len() > 3 {
+        return None;
+    }
+    let fence = trimmed.chars().next().filter(|c| *c == '`' || *c == '~')?;
+    let count = trimmed.chars().take_while(|c| *c == fence).count();
+    (count >= 3).then_some((fence, count))
+}
+
+/// An ATX heading: `#` to `######` followed by a space or the end of the line
+fn atx_heading(line: &str) -> Option<(usize, String)> {
+    let trimmed = line.trim_start();
+    if line.len() - trimmed.len() > 3 {
+        return None;
+    }
+    let level = trimmed.chars().take_while(|c| *c == '#').count();
+    let rest = &trimmed[level..];
+    if !(1..=6).contains(&level) || !(rest.is_empty() || rest.starts_with([' ', '\t'])) {
+        return None;
+    }
+    // A closing run of `#` is not part of the title
+    let title = rest.trim();
+    let title = match title.trim_end_matches('#') {
+        stripped if stripped.is_empty() || stripped.ends_with([' ', '\t']) => stripped.trim(),
+        _ => title,
+    };
+    Some((level, title.to_string()))
+}
+
+/// A reStructuredText adornment line: one punctuation character repeated
+fn adornment(line: &str) -> Option<char> {
+    let line = line.trim_end();
+    let c = line.chars().next().filter(|c| c.is_ascii_punctuation())?;
+    (line.chars().count() >= 2 && line.chars().all(|x| x == c)).then_some(c)
+}
+
+/// Close every open section at `end_line` down to `level`
+fn close_sections(headings: &mut [Heading], open: &mut Vec<usize>, level: usize, end_line: usize) {
+    while let Some(&index) = open.last() {
+        if headings[index].level < level {
+            break;
+        }
+        headings[index].end_line = end_line;
+        open.pop();
+    }
+}
+
+/// The headings of a Markdown document
+fn markdown_headings(lines: &[&str]) -> Vec<(usize, usize, String)> {
+    let mut found = Vec::new();
+    let mut fence: Option<(char, usize)> = None;
+    let mut paragraph_start: Option<usize> = None;
+    for (i, line) in lines.iter().enumerate() {
+        if let Some((c, count)) = fence {
+            if code_fence(line).is_some_and(|(x, n)| x == c && n >= count)
+                && line.trim().chars().all(|x| x == c)
+            {
+                fence = None;
+            }
+            continue;
+        }
+        if let Some(opened) = code_fence(line) {
+            fence = Some(opened);
+            paragraph_start = None;
+            continue;
+        }
+        if let Some((level, title)) = atx_heading(line) {
+            found.push((i, level, title));
+            paragraph_start = None;
+            continue;
+        }
+        let trimmed = line.trim();
+        if trimmed.is_empty() {
+            paragraph_start = None;
+            continue;
+        }
+        // Setext underline of the paragraph above it
+        if let Some(start) = paragraph_start {
+            let level = match adornment(trimmed) {
+                Some('=') => Some(1),
+                Some('-') => Some(2),
+                _ => None,
+            };
+            if let Some(level) = level.filter(|_| line.len() - line.trim_start().len() <= 3) {
+                let title = lines[start..i]
+                    .iter()
+                    .map(|l| l.trim())
+                    .collect::<Vec<_>>()
+                    .join(" ");
+                found.push((start, level, title));
+                paragraph_start = None;
+                continue;
+            }
+        }
+        // Indented lines, lists and quotes cannot start a setext heading
+        let starts_paragraph = !line.starts_with("    ")
+            && !line.starts_with('\t')
+            && !trimmed.starts_with(['>', '-', '*', '+', '|']);
+        if paragraph_start.is_none() && starts_paragraph {
+            paragraph_start = Some(i);
+        }
+    }
+    found
+}
+
+/// The headings of a reStructuredText document
+fn rst_headings(lines: &[&str]) -> Vec<(usize, usize, String)> {
+    let mut found = Vec::new();
+    // Adornment styles in order of appearance: character and whether it has an overline
+    let mut styles: Vec<(char, bool)> = Vec::new();
+    let mut level_of = |style: (char, bool)| match styles.iter().position(|s| *s == style) {
+        Some(index) => index + 1,
+        None => {
+            styles.push(style);
+            styles.len()
+        }
+    };
+    let mut literal_indent: Option<usize> = None;
+    let mut i = 0;
+    while i < lines.len() {
+        let line = lines[i];
+        let indent = line.len() - line.trim_start().len();
+        // Skip the indented block after a `::` paragraph or a directive
+        if let Some(block) = literal_indent {
+            if line.trim().is_empty() || indent > block {
+                i += 1;
+                continue;
+            }
+            literal_indent = None;
+        }
+        let trimmed = line.trim();
+        if trimmed.ends_with("::") || trimmed.starts_with(".. ") {
+            literal_indent = Some(indent);
+            i += 1;
+            continue;
+        }
+        let next = lines.get(i + 1).copied().unwrap_or("");
+        let title_fits = |title: &str, rule: &str| {
+            rule.trim_end().chars().count() >= title.trim().chars().count()
+        };
+        // Overline, title, underline
+        if let Some(c) = adornment(line) {
+            let under = lines.get(i + 2).copied().unwrap_or("");
+            if !next.trim().is_empty()
+                && adornment(next).is_none()
+                && adornment(under) == Some(c)
+                && title_fits(next, line)
+            {
+                found.push((i, level_of((c, true)), next.trim().to_string()));
+                i += 3;
+                continue;
+            }
+        }
+        // Title, underline
+        if !trimmed.is_empty() && indent == 0 && adornment(line).is_none() {
+            if let Some(c) = adornment(next).filter(|_| title_fits(line, next)) {
+                found.push((i, level_of((c, false)), trimmed.to_string()));
+                i += 2;
+                continue;
+            }
+        }
+        i += 1;
+    }
+    found
+}
+
+/// The headings of `text` with the extent of their sections
+pub fn outline(text: &str, format: DocFormat) -> Vec<Heading> {
+    let lines: Vec<&str> = text.lines().collect();
+    let found = match format {
+        DocFormat::Markdown => markdown_headings(&lines),
+        DocFormat::Rst => rst_headings(&lines),
+    };
+    let mut headings: Vec<Heading> = Vec::with_capacity(found.len());
+    let mut open: Vec<usize> = Vec::new();
+    for (line, level, title) in found {
+        close_sections(&mut headings, &mut open, level, line);
+        open.push(headings.len());
+        headings.push(Heading {
+            title,
+            level,
+            start_line: line + 1,
+            end_line: lines.len(),
+        });
+    }
+    close_sections(&mut headings, &mut open, 0, lines.len());
+    headings
+}
+
+/// Find the section named by `query`, case-insensitively: an exact title first, then
+/// the only title containing it. `A > B` looks for `B` within the section `A`.
+pub fn find_section<'a>(
+    headings: &'a [Heading],
+    query: &str,
+) -> Result<&'a Heading, Vec<&'a Heading>> {
+    let mut scope: Vec<&Heading> = headings.iter().collect();
+    let mut found = None;
+    for part in query
+        .split(SECTION_SEPARATOR)
+        .map(str::trim)
+        .filter(|p| !p.is_empty())
+    {
+        let part = part.trim_start_matches('#').trim().to_lowercase();
+        let exact: Vec<&Heading> = scope
+            .iter()
+            .copied()
+            .filter(|h| h.title.to_lowercase() == part)
+            .collect();
+        let matches = if exact.is_empty() {
+            scope
+                .iter()
+                .copied()
+                .filter(|h| h.title.to_lowercase().contains(&part))
+                .collect()
+        } else {
+            exact
+        };
+        let heading = match matches.as_slice() {
+            [] => return Err(Vec::new()),
+            [only] => *only,
+            _ => return Err(matches),
+        };
+        scope = headings
+            .iter()
+            .filter(|h| h.start_line > heading.start_line && h.end_line <= heading.end_line)
+            .collect();
+        found = Some(heading);
+    }
+    found.ok_or_else(Vec::new)
+}
+
+/// Tool returning one section of a document, or its table of contents
+pub struct ReadDocTool {
+    name: String,
+}
+
+impl ReadDocTool {
+    pub fn new() -> Self {
+        Self {
+            name: "read_doc".to_string(),
+        }
+    }
+
+    fn render_outline(headings: &[Heading]) -> String {
+        let mut message = String::new();
+        for heading in headings.iter().take(MAX_OUTLINE_HEADINGS) {
+            let _ = write!(
+                message,
+                "\n{}{} (lines {}-{})",
+                "  ".repeat(heading.level.saturating_sub(1)),
+                heading.title,
+                heading.start_line,
+                heading.end_line
+            );
+        }
+        if headings.len() > MAX_OUTLINE_HEADINGS {
+            let _ = write!(
+                message,
+                "\n... {} more headings",
+                headings.len() - MAX_OUTLINE_HEADINGS
+            );
+        }
+        message
+    }
+}
+
+impl Default for ReadDocTool {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl Tool for ReadDocTool {
+    fn name(&self) -> &str {
+        &self.name
+    }
+
+    fn description(&self) -> &str {
+        "Read one section of a Markdown or reStructuredText document by its heading (e.g. \"Build\" or \"Install > From source\"), including its subsections; without a section, list the document's headings with their line ranges"
+    }
+
+    fn signature(&self) -> &str {
+        "read_doc <path> [<section>]"
+    }
+
+    fn danger_level(&self) -> DangerLevel {
+        DangerLevel::ReadOnly
+    }
+
+    fn validate_args(&self, args: &ToolArgs) -> Result<(), ToolError> {
+        if args
+            .get_named_arg("path")
+            .or_else(|| args.get_arg(0))
+            .is_none()
+        {
+            return Err(ToolError::InvalidArgs {
+                message: "read_doc requires a document path".to_string(),
+            });
+        }
+        Ok(())
+    }
+
+    fn execute(&mut self, args: &ToolArgs, state: &Arc<Mutex<ToolState>>) -> Result<ToolResult> {
+        let (path_arg, section) = match args.get_named_arg("path") {
+            Some(path) => (
+                path,
+                args.get_named_arg("section").or_else(|| args.get_arg(0)),
+            ),
+            None => (
+                args.get_arg(0)
+                    .ok_or_else(|| anyhow::anyhow!("read_doc requires a document path"))?,
+                args.get_named_arg("section").or_else(|| args.get_arg(1)),
+            ),
+        };
+        let working_directory = state
+            .lock()
+            .map_err(|e| anyhow::anyhow!("Failed to lock state: {}", e))?
+            .working_directory
+            .clone();
+        let path = working_directory.join(path_arg);
+        if !path.is_file() {
+            return Ok(path_not_found(
+                format!("File not found: {}", path.display()),
+                &path,
+            ));
+        }
+        let text = match fs::read_to_string(&path) {
+            Ok(text) => text,
+            Err(e) => {
+                return Ok(ToolResult::error(format!(
+                    "Failed to read {}: {}",
+                    path.display(),
+                    e
+                )))
+            }
+        };
+        let format = DocFormat::from_path(&path);
+        let headings = outline(&text, format);
+
+        let Some(query) = section.map(|s| s.trim()).filter(|s| !s.is_empty()) else {
+            let message = if headings.is_empty() {
+                format!(
+                    "{} has no headings ({} lines)",
+                    path_arg,
+                    text.lines().count()
+                )
+            } else {
+                format!(
+                    "{} headings in {}:{}",
+                    headings.len(),
+                    path_arg,
+                    Self::render_outline(&headings)
+                )
+            };
+            return Ok(ToolResult::success_with_data(
+                message,
+                serde_json::json!({
+                    "path": path_arg,
+                    "format": format,
+                    "headings": headings,
+                }),
+            ));
+        };
+
+        let heading = match find_section(&headings, query) {
+            Ok(heading) => heading,
+            Err(ambiguous) => {
+                let message = if ambiguous.is_empty() {
+                    let suggestions =
+                        closest_matches(query, headings.iter().map(|h| h.title.as_str()), 3);
+                    format!(
+                        "No section '{}' in {}{}",
+                        query,
+                        path_arg,
+                        format_suggestions(&suggestions)
+                    )
+                } else {
+                    format!(
+                        "Section '{}' matches several headings in {}; use the full title or 'Parent > Child':{}",
+                        query,
+                        path_arg,
+                        Self::render_outline(&ambiguous.into_iter().cloned().collect::<Vec<_>>())
+                    )
+                };
+                return Ok(ToolResult::error_with_data(
+                    message,
+                    serde_json::json!({
+                        "path": path_arg,
+                        "headings": headings,
+                    }),
+                ));
+            }
+        };
+
+        let lines: Vec<&str> = text
+            .lines()
+            .skip(heading.start_line - 1)
+            .take(heading.end_line + 1 - heading.start_line)
+            .collect();
+        let truncated = lines.len() > MAX_SECTION_LINES;
+        let content = lines[..lines.len().min(MAX_SECTION_LINES)].join("\n");
+        let subsections: Vec<&Heading> = headings
+            .iter()
+            .filter(|h| h.start_line > heading.start_line && h.end_line <= heading.end_line)
+            .collect();
+        let mut message = format!(
+            "{} > {} (lines {}-{})\n\n{}",
+            path_arg, heading.title, heading.start_line, heading.end_line, content
+        );
+        if truncated {
+            let shown_to = heading.start_line + MAX_SECTION_LINES - 1;
+            let _ = write!(
+                message,
+                "\n\n... section cut at line {}; read a subsection or open the file at line {} for the rest",
+                shown_to,
+                shown_to + 1
+            );
+        }
+        Ok(ToolResult::success_with_data(
+            message,
+            serde_json::json!({
+                "path": path_arg,
+                "format": format,
+                "section": heading,
+                "subsections": subsections,
+                "content": content,
+                "truncated": truncated,
+            }),
+        ))
+    }
+
+    fn get_parameters_schema(&self) -> serde_json::Value {
+        serde_json::json!({
+            "type": "object",
+            "properties": {
+                "path": {
+                    "type": "string",
+                    "description": "Markdown (.md) or reStructuredText (.rst) file"
+                },
+                "section": {
+                    "type": "string",
+                    "description": "Heading of the section to return, matched case-insensitively (exact title first, then a unique partial match); 'Parent > Child' for nested headings. Omit for the table of contents"
+                }
+            },
+            "required": ["path"]
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use tempfile::TempDir;
+
+    #[test]
+    fn test_outline_markdown_and_rst() {
+        let markdown = "# Widget\n\nIntro\n\n## Build\n\n```sh\n# not a heading\ncargo build\n```\n\nTesting\n-------\n\ncargo test\n\n### Nightly ##\n\nextra\n\nUsage\n=====\n\n- item\n---\n";
+        let summary: Vec<_> = outline(markdown, DocFormat::Markdown)
+            .into_iter()
+            .map(|h| (h.title, h.level, h.start_line, h.end_line))
+            .collect();
+        assert_eq!(
+            summary,
+            vec![
+                ("Widget".to_string(), 1, 1, 20),
+                ("Build".to_string(), 2, 5, 11),
+                ("Testing".to_string(), 2, 12, 20),
+                ("Nightly".to_string(), 3, 17, 20),
+                ("Usage".to_string(), 1, 21, 25),
+            ]
+        );
+
+        let rst = "=======\nProject\n=======\n\nInstall\n-------\n\nExample::\n\n    Not\n    ---\n\nUsage\n-----\n\nDetails\n~~~~~~~\n";
+        let summary: Vec<_> = outline(rst, DocFormat::Rst)
+            .into_iter()
+            .map(|h| (h.title, h.level, h.start_line))
+            .collect();
+        assert_eq!(
+            summary,
+            vec![
+                ("Project".to_string(), 1, 1),
+                ("Install".to_string(), 2, 5),
+                ("Usage".to_string(), 2, 13),
+                ("Details".to_string(), 3, 16),
+            ]
+        );
+    }
+
+    #[test]
+    fn test_read_doc_section_and_outline() {
+        let temp_dir = TempDir::new().unwrap();
+        fs::write(
+            temp_dir.path().join("README.md"),
+            "# Widget\n\n## Install\n\n### From source\n\nmake\n\n## Development\n\n### From source\n\ncargo build\n\n## Testing\n\nRun `cargo test`.\n",
+        )
+        .unwrap();
+        let mut tool_state = ToolState::new();
+        tool_state.working_directory = temp_dir.path().to_path_buf();
+        let state = Arc::new(Mutex::new(tool_state));
+        let mut tool = ReadDocTool::new();
+
+        let result = tool
+            .execute(&ToolArgs::from_args(&["README.md"]), &state)
+            .unwrap();
+        assert!(result.success, "{}", result.message);
+        assert!(result.message.contains("\n  Testing (lines 15-17)"));
+
+        let result = tool
+            .execute(&ToolArgs::from_args(&["README.md", "testing"]), &state)
+            .unwrap();
+        assert!(result.success, "{}", result.message);
+        assert_eq!(
+            result.data.as_ref().unwrap()["content"],
+            "## Testing\n\nRun `cargo test`."
+        );
+
+        let result = tool
+            .execute(&ToolArgs::from_args(&["README.md", "From source"]), &state)
+            .unwrap();
+        assert!(!result.success);
+        assert!(result.message.contains("several headings"));
+        let result = tool
+            .execute(
+                &ToolArgs::from_args(&["README.md", "Development > From source"]),
+                &state,
+            )
+            .unwrap();
+        assert!(result.success, "{}", result.message);
+        assert_eq!(result.data.as_ref().unwrap()["section"]["start_line"], 11);
+
+        let result = tool
+            .execute(&ToolArgs::from_args(&["README.md", "Testng"]), &state)
+            .unwrap();
+        assert!(!result.success);
+        assert!(
+            result.message.contains("Did you mean: Testing?"),
+            "{}",
+            result.message
+        );
+    }
+}
@@ -1,18 +1,38 @@
 //! Utility tools for project analysis and task completion
 
-use crate::core::{Tool, ToolArgs, ToolError, ToolResult};
-use crate::state::ToolState;
-use anyhow::Result;
-use std::fs;
-use std::path::{Path, PathBuf};
+use crate::core::{DangerLevel, Tool, ToolArgs, ToolError, ToolResult};
+use crate::messages::{MessageKey, MessageStyle};
+use crate::profiling;
+use crate::state::{session_path, ToolState};
+use anyhow::{Context, Result};
+use std::path::Path;
 use std::sync::{Arc, Mutex};
 use walkdir::WalkDir;
 
 use crate::search::ConfigurableFilter;
 
+mod brief;
+#[cfg(feature = "yaml")]
+mod ci_info;
 mod count_tokens;
-
+pub mod dependencies;
+mod describe_tool;
+pub mod diagram;
+pub mod docs;
+pub mod safe_regex;
+pub mod suggest;
+mod tasks;
+pub mod workspace;
+
+pub use brief::RepoBriefTool;
+#[cfg(feature = "yaml")]
+pub use ci_info::{CiInfoTool, CiJob, CiProvider};
 pub use count_tokens::CountTokensTool;
+pub use dependencies::{DependencyReportTool, Ecosystem, Lockfile};
+pub use describe_tool::DescribeToolTool;
+pub use docs::ReadDocTool;
+pub use tasks::{ListTasksTool, Task, TaskRunner};
+pub use workspace::{Workspace, WorkspaceKind, WorkspaceMember, WorkspaceMembersTool};
 
 /// Tool for task classification
 pub struct ClassifyTaskTool {
@@ -40,6 +60,10 @@ impl Tool for ClassifyTaskTool {
         "classify_task <task_type>"
     }
 
+    fn danger_level(&self) -> DangerLevel {
+        DangerLevel::ReadOnly
+    }
+
     fn validate_args(&self, args: &ToolArgs) -> Result<(), ToolError> {
         if args.is_empty() {
             return Err(ToolError::InvalidArgs {
@@ -94,6 +118,9 @@ impl Tool for ClassifyTaskTool {
     }
 }
 
+/// Most entries a filemap lists
+const MAX_FILEMAP_ENTRIES: usize = 100;
+
 pub struct FilemapTool {
     name: String,
 }
@@ -173,56 +200,51 @@ impl FilemapTool {
     }
 
     /// Generate a tree-like directory structure
-    fn generate_tree(path: &Path, max_depth: usize) -> Result<String> {
+    fn generate_tree(
+        path: &Path,
+        max_depth: usize,
+        style: &MessageStyle,
+        filter: &ConfigurableFilter,
+    ) -> Result<String> {
         let mut result = String::new();
 
         if path.is_file() {
             // If it's a file, just show its content summary
-            return Self::show_file_content(path);
+            return Self::show_file_content(path, style);
         }
 
-        result.push_str(&format!("📁 {}\n", path.display()));
-
-        // Create a single ConfigurableFilter instance to avoid re-reading config per entry
-        let filter = ConfigurableFilter::new(None);
-
-        // Use `filter_entry` to prevent walking into ignored directories (e.g. `.git`, `target`).
-        // We still `filter_map` the iterator to ignore IO errors and then skip the root path itself.
-        let mut entries: Vec<_> = WalkDir::new(path)
-            .max_depth(max_depth)
-            .into_iter()
-            .filter_entry(|e| filter.should_include_path(e.path()))
-            .filter_map(|e| e.ok())
-            .filter(|e| e.path() != path)
-            .collect();
-
-        // Sort entries by full path so items that belong to the same directory
-        // are listed next to each other. This ensures files appear directly
-        // under their parent directory instead of printing all directories
-        // first which separates files from their directory context.
-        entries.sort_by(|a, b| a.path().cmp(b.path()));
+        result.push_str(&style.render_line(MessageKey::FilemapRoot, &[("path", &path.display())]));
 
+        let entries = Self::tree_entries(path, max_depth, filter);
         let mut file_count = 0;
         let mut dir_count = 0;
 
-        for entry in entries.iter().take(100) {
-            // Limit to first 100 entries
+        for entry in entries.iter().take(MAX_FILEMAP_ENTRIES) {
             let depth = entry.depth();
             let indent = "  ".repeat(depth);
             let name = entry.file_name().to_string_lossy();
 
             if entry.file_type().is_dir() {
-                result.push_str(&format!("{}📁 {}/\n", indent, name));
+                result.push_str(&style.render_line(
+                    MessageKey::FilemapDirectory,
+                    &[("indent", &indent), ("name", &name)],
+                ));
                 dir_count += 1;
             } else {
                 let size = entry.metadata().map(|m| m.len()).unwrap_or(0);
-                result.push_str(&format!("{}📄 {} ({} bytes)\n", indent, name, size));
+                result.push_str(&style.render_line(
+                    MessageKey::FilemapFile,
+                    &[("indent", &indent), ("name", &name), ("size", &size)],
+                ));
                 file_count += 1;
             }
         }
 
-        if entries.len() > 100 {
-            result.push_str(&format!("... and {} more items\n", entries.len() - 100));
+        if entries.len() > MAX_FILEMAP_ENTRIES {
+            result.push_str(&format!(
+                "... and {} more items\n",
+                entries.len() - MAX_FILEMAP_ENTRIES
+            ));
         }
 
         result.push_str(&format!(
@@ -233,20 +255,78 @@ impl FilemapTool {
         Ok(result)
     }
 
+    /// Entries under the directory `path`, down to `max_depth`, in path order
+    fn tree_entries(
+        path: &Path,
+        max_depth: usize,
+        filter: &ConfigurableFilter,
+    ) -> Vec<walkdir::DirEntry> {
+        // Use `filter_entry` to prevent walking into ignored directories (e.g. `.git`, `target`).
+        // We still `filter_map` the iterator to ignore IO errors and then skip the root path itself.
+        let mut entries: Vec<_> = WalkDir::new(path)
+            .max_depth(max_depth)
+            .sort_by_file_name()
+            .into_iter()
+            .filter_entry(|e| filter.should_include_path(e.path()))
+            .filter_map(|e| e.ok())
+            .filter(|e| e.path() != path)
+            .collect();
+
+        // Sort entries by full path so items that belong to the same directory
+        // are listed next to each other. This ensures files appear directly
+        // under their parent directory instead of printing all directories
+        // first which separates files from their directory context.
+        entries.sort_by(|a, b| a.path().cmp(b.path()));
+        entries
+    }
+
+    /// SVG diagram of the tree [`generate_tree`](Self::generate_tree) lists
+    fn tree_diagram(path: &Path, max_depth: usize, filter: &ConfigurableFilter) -> String {
+        let entries = Self::tree_entries(path, max_depth, filter);
+        let nodes: Vec<diagram::TreeNode> = entries
+            .iter()
+            .take(MAX_FILEMAP_ENTRIES)
+            .map(|entry| diagram::TreeNode {
+                depth: entry.depth(),
+                name: entry.file_name().to_string_lossy().to_string(),
+                is_dir: entry.file_type().is_dir(),
+            })
+            .collect();
+        diagram::tree_svg(
+            &path.display().to_string(),
+            &nodes,
+            entries.len().saturating_sub(MAX_FILEMAP_ENTRIES),
+        )
+    }
+
+    /// Heading line naming a file and its line count
+    fn file_summary(path: &Path, lines: &[&str], style: &MessageStyle) -> String {
+        style.render_line(
+            MessageKey::FileSummary,
+            &[("path", &path.display()), ("lines", &lines.len())],
+        )
+    }
+
     /// Show abbreviated file content (similar to SWE-agent's filemap for Python)
-    fn show_file_content(path: &Path) -> Result<String> {
-        let content =
-            fs::read_to_string(path).map_err(|e| anyhow::anyhow!("Failed to read file: {}", e))?;
+    fn show_file_content(path: &Path, style: &MessageStyle) -> Result<String> {
+        let content = profiling::read_to_string(path).context("Failed to read file")?;
 
         let lines: Vec<&str> = content.lines().collect();
-        let mut result = String::new();
 
-        result.push_str(&format!("📄 {} ({} lines)\n", path.display(), lines.len()));
+        // Languages with a grammar fold exactly the function bodies of the syntax tree
+        #[cfg(feature = "ast")]
+        if let Some(bodies) = crate::ast::Language::from_path(path)
+            .and_then(|language| crate::ast::symbols::elided_bodies(language, &content))
+        {
+            return Ok(Self::show_with_elisions(path, &lines, &bodies, style));
+        }
+
+        let mut result = Self::file_summary(path, &lines, style);
 
         // Check if it's a Python file for special handling
         if let Some(ext) = path.extension() {
             if ext == "py" {
-                return Self::show_python_file_content(path, &lines);
+                return Self::show_python_file_content(path, &lines, style);
             }
         }
 
@@ -293,10 +373,45 @@ impl FilemapTool {
         Ok(result)
     }
 
+    /// Numbered lines of a file with the 1-based, inclusive `elided` ranges folded
+    #[cfg(feature = "ast")]
+    fn show_with_elisions(
+        path: &Path,
+        lines: &[&str],
+        elided: &[(usize, usize)],
+        style: &MessageStyle,
+    ) -> String {
+        let mut result = Self::file_summary(path, lines, style);
+        let mut elided = elided.iter().peekable();
+        let mut line = 1;
+        while line <= lines.len() {
+            if let Some(&&(start, end)) = elided.peek() {
+                if start == line {
+                    result.push_str(&format!("     | ... eliding lines {}-{} ...\n", start, end));
+                    line = end + 1;
+                    elided.next();
+                    continue;
+                }
+            }
+            result.push_str(&format!("{:4} | {}\n", line, lines[line - 1]));
+            line += 1;
+
+            // Limit total output
+            if result.len() > 50000 {
+                result.push_str("... (output truncated) ...\n");
+                break;
+            }
+        }
+        result
+    }
+
     /// Show Python file content with function/class elision
-    fn show_python_file_content(path: &Path, lines: &[&str]) -> Result<String> {
-        let mut result = String::new();
-        result.push_str(&format!("📄 {} ({} lines)\n", path.display(), lines.len()));
+    fn show_python_file_content(
+        path: &Path,
+        lines: &[&str],
+        style: &MessageStyle,
+    ) -> Result<String> {
+        let mut result = Self::file_summary(path, lines, style);
 
         let mut current_line = 0;
         while current_line < lines.len() {
@@ -436,26 +551,57 @@ impl Tool for FilemapTool {
         "filemap <file_path>"
     }
 
+    fn danger_level(&self) -> DangerLevel {
+        DangerLevel::ReadOnly
+    }
+
     fn validate_args(&self, args: &ToolArgs) -> Result<(), ToolError> {
         if args.is_empty() {
             return Err(ToolError::InvalidArgs {
                 message: "Usage: filemap <file_path>".to_string(),
             });
         }
+        if let Some(format) = args.get_named_arg("diagram") {
+            if format != "svg" {
+                return Err(ToolError::InvalidArgs {
+                    message: format!("Unknown diagram format '{}'; expected 'svg'", format),
+                });
+            }
+        }
         Ok(())
     }
 
     fn execute(&mut self, args: &ToolArgs, state: &Arc<Mutex<ToolState>>) -> Result<ToolResult> {
         let file_path = args.get_arg(0).unwrap();
-        let path_buf = PathBuf::from(file_path);
+        let path_buf = session_path(state, file_path);
 
         // Check if path exists
         if !path_buf.exists() {
-            return Ok(ToolResult::error(format!("Path not found: {}", file_path)));
+            return Ok(suggest::path_not_found(
+                format!("Path not found: {}", file_path),
+                &path_buf,
+            ));
         }
 
+        let (style, filter) = {
+            let mut state = state
+                .lock()
+                .map_err(|e| anyhow::anyhow!("Failed to lock state: {}", e))?;
+            (state.message_style.clone(), state.search_filter())
+        };
+
         // Generate the filemap
-        let content = Self::generate_tree(&path_buf, 3)?; // Max depth of 3
+        let content = Self::generate_tree(&path_buf, 3, &style, &filter)?; // Max depth of 3
+        let diagram = match args.get_named_arg("diagram") {
+            None => None,
+            Some(_) if path_buf.is_dir() => Some(Self::tree_diagram(&path_buf, 3, &filter)),
+            Some(_) => {
+                return Ok(ToolResult::error(format!(
+                    "{} is a file; diagrams are only rendered for directories",
+                    file_path
+                )))
+            }
+        };
 
         // Update state
         {
@@ -465,14 +611,21 @@ impl Tool for FilemapTool {
             state_guard.push_history(format!("Generated filemap for: {}", file_path));
         }
 
-        Ok(ToolResult::success_with_data(
+        let mut result = ToolResult::success_with_data(
             content,
             serde_json::json!({
                 "path": file_path,
                 "type": if path_buf.is_file() { "file" } else { "directory" },
                 "action": "filemap"
             }),
-        ))
+        );
+        if let Some(svg) = diagram {
+            result.add_attachment(crate::core::Attachment::svg(
+                format!("Directory tree of {}", file_path),
+                svg,
+            ));
+        }
+        Ok(result)
     }
 
     fn get_parameters_schema(&self) -> serde_json::Value {
@@ -482,6 +635,11 @@ impl Tool for FilemapTool {
                 "file_path": {
                     "type": "string",
                     "description": "The path to the file or directory to map"
+                },
+                "diagram": {
+                    "type": "string",
+                    "enum": ["svg"],
+                    "description": "Also render a directory's tree as an image attachment"
                 }
             },
             "required": ["file_path"]
@@ -507,31 +665,63 @@ impl Tool for SubmitTool {
     }
 
     fn description(&self) -> &str {
-        "Submit your completed task or solution"
+        "Submit your completed task or solution, optionally with a summary of the change (first line a title) that export_patch and create_pr use to describe it"
     }
 
     fn signature(&self) -> &str {
-        "submit"
+        "submit [--summary=<text>]"
+    }
+
+    fn danger_level(&self) -> DangerLevel {
+        DangerLevel::ReadOnly
+    }
+
+    // Submits the work the calls before it did
+    fn is_parallel_safe(&self) -> bool {
+        false
     }
 
     fn validate_args(&self, _args: &ToolArgs) -> Result<(), ToolError> {
-        Ok(()) // Submit takes no arguments
+        Ok(()) // Submit only takes an optional summary
     }
 
-    fn execute(&mut self, _args: &ToolArgs, state: &Arc<Mutex<ToolState>>) -> Result<ToolResult> {
+    fn execute(&mut self, args: &ToolArgs, state: &Arc<Mutex<ToolState>>) -> Result<ToolResult> {
+        let summary = args
+            .get_named_arg("summary")
+            .or_else(|| args.get_arg(0))
+            .map(|summary| summary.trim().to_string())
+            .filter(|summary| !summary.is_empty());
         // Update state
-        {
+        let annotations: Vec<String> = {
             let mut state_guard = state
                 .lock()
                 .map_err(|e| anyhow::anyhow!("Failed to lock state: {}", e))?;
             state_guard.push_history("Task submitted".to_string());
+            if summary.is_some() {
+                state_guard.submit_summary = summary.clone();
+            }
+            state_guard
+                .annotations()
+                .into_iter()
+                .map(str::to_string)
+                .collect()
+        };
+
+        let mut message = "Task has been submitted successfully".to_string();
+        if !annotations.is_empty() {
+            message.push_str("\n\nPlan:");
+            for annotation in &annotations {
+                message.push_str(&format!("\n  - {}", annotation));
+            }
         }
 
         Ok(ToolResult::success_with_data(
-            "Task has been submitted successfully".to_string(),
+            message,
             serde_json::json!({
                 "action": "submit",
-                "status": "completed"
+                "status": "completed",
+                "annotations": annotations,
+                "summary": summary
             }),
         ))
     }
@@ -539,7 +729,12 @@ impl Tool for SubmitTool {
     fn get_parameters_schema(&self) -> serde_json::Value {
         serde_json::json!({
             "type": "object",
-            "properties": {},
+            "properties": {
+                "summary": {
+                    "type": "string",
+                    "description": "What the change does: a title line, then optional details"
+                }
+            },
             "required": []
         })
     }
@@ -0,0 +1,77 @@
+//! Compile user-supplied regular expressions with resource limits
+//!
+//! The `regex` crate matches in linear time, so a pattern cannot backtrack
+//! catastrophically; what it can do is compile into an enormous program (large counted
+//! repetitions of Unicode classes, deep nesting, huge alternations) that takes seconds
+//! and hundreds of megabytes to build and search. Patterns from tool arguments go
+//! through [`compile`], which caps the pattern length, nesting depth and compiled
+//! size, and reports a pattern over the limits as invalid arguments.
+
+use crate::core::ToolError;
+use regex::{Regex, RegexBuilder};
+
+/// Longest accepted pattern, in bytes
+pub const MAX_PATTERN_LEN: usize = 4096;
+
+/// Maximum size of the compiled program, in bytes
+pub const SIZE_LIMIT: usize = 1 << 20;
+
+/// Maximum size of the lazy DFA cache per search, in bytes
+pub const DFA_SIZE_LIMIT: usize = 2 << 20;
+
+/// Maximum nesting depth of groups and repetitions
+pub const NEST_LIMIT: u32 = 64;
+
+/// Compile a user-supplied `pattern` within the resource limits
+pub fn compile(pattern: &str, case_insensitive: bool) -> Result<Regex, ToolError> {
+    if pattern.len() > MAX_PATTERN_LEN {
+        return Err(ToolError::InvalidArgs {
+            message: format!(
+                "Regex pattern is {} bytes long; the limit is {} bytes",
+                pattern.len(),
+                MAX_PATTERN_LEN
+            ),
+        });
+    }
+    RegexBuilder::new(pattern)
+        .case_insensitive(case_insensitive)
+        .size_limit(SIZE_LIMIT)
+        .dfa_size_limit(DFA_SIZE_LIMIT)
+        .nest_limit(NEST_LIMIT)
+        .build()
+        .map_err(|e| match e {
+            regex::Error::CompiledTooBig(limit) => ToolError::InvalidArgs {
+                message: format!(
+                    "Regex pattern is too complex: its compiled form exceeds {} bytes. \
+                     Simplify it, e.g. avoid large counted repetitions such as \\w{{1000}} \
+                     or use a literal search",
+                    limit
+                ),
+            },
+            e => ToolError::Regex(e),
+        })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_compile_enforces_limits() {
+        assert!(compile(r"fn\s+\w+", false).unwrap().is_match("fn main"));
+        assert!(compile("HELLO", true).unwrap().is_match("hello"));
+
+        let too_complex = compile(r"(\w{100}){100}", false).unwrap_err();
+        assert!(matches!(too_complex, ToolError::InvalidArgs { .. }));
+        assert!(too_complex.to_string().contains("too complex"));
+
+        let too_long = compile(&"a".repeat(MAX_PATTERN_LEN + 1), false).unwrap_err();
+        assert!(too_long.to_string().contains("limit is 4096 bytes"));
+
+        let too_deep = format!("{}a{}", "(".repeat(100), ")".repeat(100));
+        assert!(matches!(
+            compile(&too_deep, false),
+            Err(ToolError::Regex(_))
+        ));
+    }
+}
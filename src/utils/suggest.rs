@@ -0,0 +1,145 @@
+//! "Did you mean" suggestions for mistyped tool names and paths
+
+use crate::core::ToolResult;
+use edit_distance::edit_distance;
+use std::fs;
+use std::path::Path;
+
+/// Maximum number of suggestions returned by the helpers in this module
+pub const MAX_SUGGESTIONS: usize = 3;
+
+/// Return up to `limit` candidates that are close to `target`, best first.
+///
+/// A candidate is considered close when its edit distance is within a third of
+/// the target length (at least 2), or when one string contains the other
+/// (for strings of three characters or more).
+pub fn closest_matches<'a, I>(target: &str, candidates: I, limit: usize) -> Vec<String>
+where
+    I: IntoIterator<Item = &'a str>,
+{
+    let target_lower = target.to_lowercase();
+    let max_distance = (target.chars().count() / 3).max(2);
+
+    let mut scored: Vec<(usize, &str)> = candidates
+        .into_iter()
+        .filter(|candidate| *candidate != target)
+        .filter_map(|candidate| {
+            let candidate_lower = candidate.to_lowercase();
+            let distance = edit_distance(&target_lower, &candidate_lower);
+            let contains = target_lower.chars().count() >= 3
+                && candidate_lower.chars().count() >= 3
+                && (candidate_lower.contains(&target_lower)
+                    || target_lower.contains(&candidate_lower));
+            if distance <= max_distance || contains {
+                Some((distance, candidate))
+            } else {
+                None
+            }
+        })
+        .collect();
+
+    scored.sort_by(|a, b| a.0.cmp(&b.0).then_with(|| a.1.cmp(b.1)));
+    scored.dedup_by(|a, b| a.1 == b.1);
+    scored
+        .into_iter()
+        .take(limit)
+        .map(|(_, candidate)| candidate.to_string())
+        .collect()
+}
+
+/// Suggest existing siblings of a nonexistent path (entries of its parent directory)
+pub fn similar_paths(path: &Path, limit: usize) -> Vec<String> {
+    let file_name = match path.file_name().and_then(|n| n.to_str()) {
+        Some(name) => name,
+        None => return Vec::new(),
+    };
+
+    let parent = match path.parent() {
+        Some(p) if !p.as_os_str().is_empty() => p,
+        _ => Path::new("."),
+    };
+
+    let entries = match fs::read_dir(parent) {
+        Ok(entries) => entries,
+        Err(_) => return Vec::new(),
+    };
+
+    let siblings: Vec<String> = entries
+        .filter_map(|entry| entry.ok())
+        .filter_map(|entry| entry.file_name().to_str().map(|s| s.to_string()))
+        .collect();
+
+    let in_current_dir = !matches!(path.parent(), Some(p) if !p.as_os_str().is_empty());
+    closest_matches(file_name, siblings.iter().map(|s| s.as_str()), limit)
+        .into_iter()
+        .map(|name| {
+            if in_current_dir {
+                name
+            } else {
+                parent.join(name).display().to_string()
+            }
+        })
+        .collect()
+}
+
+/// Render a suggestion list as a trailing hint, or an empty string when there is none
+pub fn format_suggestions(suggestions: &[String]) -> String {
+    if suggestions.is_empty() {
+        String::new()
+    } else {
+        format!(". Did you mean: {}?", suggestions.join(", "))
+    }
+}
+
+/// Build an error result for a missing path, including similarly named siblings
+///
+/// Its data marks it as `"error": "file_not_found"`, which [`ToolResult::failure`]
+/// reports as [`crate::core::ToolError::FileNotFound`].
+pub fn path_not_found(message: impl Into<String>, path: &Path) -> ToolResult {
+    let suggestions = similar_paths(path, MAX_SUGGESTIONS);
+    let message = format!("{}{}", message.into(), format_suggestions(&suggestions));
+    ToolResult::error_with_data(
+        message,
+        serde_json::json!({
+            "error": "file_not_found",
+            "path": path.display().to_string(),
+            "suggestions": suggestions
+        }),
+    )
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use tempfile::TempDir;
+
+    #[test]
+    fn test_closest_matches_orders_by_distance() {
+        let names = ["search_dir", "search_file", "find_file", "open"];
+        let matches = closest_matches("serch_file", names.iter().copied(), 3);
+        assert_eq!(matches.first().map(|s| s.as_str()), Some("search_file"));
+        assert!(!matches.contains(&"open".to_string()));
+    }
+
+    #[test]
+    fn test_closest_matches_ignores_unrelated() {
+        let names = ["create_file", "delete_line"];
+        assert!(closest_matches("zzz", names.iter().copied(), 3).is_empty());
+    }
+
+    #[test]
+    fn test_path_not_found_suggests_siblings() {
+        let temp_dir = TempDir::new().unwrap();
+        std::fs::write(temp_dir.path().join("config.rs"), "").unwrap();
+        std::fs::write(temp_dir.path().join("unrelated.txt"), "").unwrap();
+
+        let missing = temp_dir.path().join("confg.rs");
+        let result = path_not_found(format!("File not found: {}", missing.display()), &missing);
+
+        assert!(!result.success);
+        assert!(result.message.contains("Did you mean"));
+        assert!(result.message.contains("config.rs"));
+        let suggestions = result.data.unwrap()["suggestions"].clone();
+        assert_eq!(suggestions.as_array().unwrap().len(), 1);
+    }
+}
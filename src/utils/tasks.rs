@@ -0,0 +1,770 @@
+//! list_tasks tool: the workflows a project defines for its task runners
+//!
+//! Reads Makefile targets, justfile recipes, package.json scripts, tox environments and
+//! nox sessions and lists each task with the command invoking it and what it runs, so
+//! the project's canonical `make test` or `npm run lint` is used instead of a
+//! hand-written equivalent.
+
+use crate::core::{DangerLevel, Tool, ToolArgs, ToolError, ToolResult};
+use crate::state::{session_path, ToolState};
+use anyhow::{Context, Result};
+use regex::Regex;
+use serde::{Deserialize, Serialize};
+use std::fs;
+use std::path::{Path, PathBuf};
+use std::sync::{Arc, Mutex, OnceLock};
+
+/// Characters of a task's commands shown per line of the message
+const COMMAND_PREVIEW_CHARS: usize = 100;
+
+/// Task runners whose configuration `list_tasks` reads
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum TaskRunner {
+    /// Makefile targets
+    Make,
+    /// justfile recipes
+    Just,
+    /// `scripts` of package.json
+    Npm,
+    /// tox environments from tox.ini, setup.cfg or pyproject.toml
+    Tox,
+    /// nox sessions from noxfile.py
+    Nox,
+}
+
+impl TaskRunner {
+    /// Parse a runner name as accepted by the `runner` tool parameter
+    pub fn from_name(name: &str) -> Option<Self> {
+        match name.to_lowercase().as_str() {
+            "make" | "makefile" => Some(Self::Make),
+            "just" | "justfile" => Some(Self::Just),
+            "npm" | "yarn" | "pnpm" | "bun" | "package.json" | "scripts" => Some(Self::Npm),
+            "tox" => Some(Self::Tox),
+            "nox" | "noxfile" => Some(Self::Nox),
+            _ => None,
+        }
+    }
+
+    /// Canonical runner name
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            Self::Make => "make",
+            Self::Just => "just",
+            Self::Npm => "npm",
+            Self::Tox => "tox",
+            Self::Nox => "nox",
+        }
+    }
+}
+
+/// One task a runner can run
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct Task {
+    pub runner: TaskRunner,
+    /// Configuration file, relative to the project directory
+    pub file: String,
+    pub name: String,
+    /// Command running the task, e.g. `make test` or `pnpm run lint`
+    pub invocation: String,
+    /// What the task runs, one command per entry
+    pub commands: Vec<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub description: Option<String>,
+}
+
+/// The task runner configuration files in `dir`
+pub fn find_task_files(dir: &Path) -> Vec<(TaskRunner, PathBuf)> {
+    let candidates: [(TaskRunner, &[&str]); 5] = [
+        (TaskRunner::Make, &["GNUmakefile", "makefile", "Makefile"]),
+        (TaskRunner::Just, &["justfile", "Justfile", ".justfile"]),
+        (TaskRunner::Npm, &["package.json"]),
+        (TaskRunner::Tox, &["tox.ini", "setup.cfg", "pyproject.toml"]),
+        (TaskRunner::Nox, &["noxfile.py"]),
+    ];
+    let mut files = Vec::new();
+    for (runner, names) in candidates {
+        // Each runner reads the first of its files that exists
+        let found = names.iter().map(|name| dir.join(name)).find(|path| {
+            path.is_file()
+                && match (runner, path.file_name().and_then(|n| n.to_str())) {
+                    (TaskRunner::Tox, Some("setup.cfg")) => {
+                        fs::read_to_string(path).is_ok_and(|text| text.contains("[tox:tox]"))
+                    }
+                    (TaskRunner::Tox, Some("pyproject.toml")) => {
+                        fs::read_to_string(path).is_ok_and(|text| text.contains("[tool.tox"))
+                    }
+                    _ => true,
+                }
+        });
+        if let Some(path) = found {
+            files.push((runner, path));
+        }
+    }
+    files
+}
+
+/// Comment lines directly above `index`, without `#`
+fn comment_above(lines: &[&str], index: usize) -> Option<String> {
+    let mut comments = Vec::new();
+    for line in lines[..index].iter().rev() {
+        match line.trim_start().strip_prefix('#') {
+            Some(comment) => comments.push(comment.trim_start_matches('#').trim()),
+            None => break,
+        }
+    }
+    comments.reverse();
+    let text = comments.join(" ");
+    (!text.is_empty()).then_some(text)
+}
+
+/// Makefile targets with their recipes; a `## text` after the target is its
+/// description, as in the common `make help` idiom, otherwise the comment above it
+pub fn parse_makefile(text: &str, file: &str) -> Vec<Task> {
+    static TARGET: OnceLock<Regex> = OnceLock::new();
+    let target = TARGET
+        .get_or_init(|| Regex::new(r"^([A-Za-z0-9_][A-Za-z0-9_.\-/ ]*?)\s*::?(.*)$").unwrap());
+    let lines: Vec<&str> = text.lines().collect();
+    let mut tasks: Vec<Task> = Vec::new();
+    let mut current: Vec<usize> = Vec::new();
+    let mut in_define = false;
+    let mut continued = false;
+    for (index, line) in lines.iter().enumerate() {
+        if in_define {
+            in_define = line.trim() != "endef";
+            continue;
+        }
+        if let Some(recipe) = line.strip_prefix('\t') {
+            let recipe = recipe.trim();
+            for &task in &current {
+                if continued {
+                    if let Some(last) = tasks[task].commands.last_mut() {
+                        last.push(' ');
+                        last.push_str(recipe);
+                    }
+                } else if !recipe.is_empty() && !recipe.starts_with('#') {
+                    tasks[task].commands.push(recipe.to_string());
+                }
+            }
+            continued = recipe.ends_with('\\');
+            if continued {
+                for &task in &current {
+                    if let Some(last) = tasks[task].commands.last_mut() {
+                        *last = last.trim_end_matches('\\').trim_end().to_string();
+                    }
+                }
+            }
+            continue;
+        }
+        continued = false;
+        if line.trim_start().starts_with("define ") {
+            in_define = true;
+            continue;
+        }
+        if line.trim().is_empty() || line.starts_with('#') {
+            continue;
+        }
+        current.clear();
+        let Some(captures) = target.captures(line) else {
+            continue;
+        };
+        // `NAME := value` and `NAME ::= value` are variables
+        let rest = captures.get(2).map_or("", |m| m.as_str());
+        if rest.starts_with('=') {
+            continue;
+        }
+        let description = rest
+            .split_once("##")
+            .map(|(_, description)| description.trim().to_string())
+            .filter(|description| !description.is_empty())
+            .or_else(|| comment_above(&lines, index));
+        // An inline recipe after `;`
+        let inline = rest
+            .split_once(';')
+            .map(|(_, recipe)| recipe.split("##").next().unwrap_or_default().trim())
+            .filter(|recipe| !recipe.is_empty());
+        for name in captures[1].split_whitespace() {
+            if name.contains('%') || name.contains('$') {
+                continue;
+            }
+            if let Some(existing) = tasks.iter().position(|task| task.name == name) {
+                current.push(existing);
+                continue;
+            }
+            tasks.push(Task {
+                runner: TaskRunner::Make,
+                file: file.to_string(),
+                name: name.to_string(),
+                invocation: format!("make {}", name),
+                commands: inline
+                    .map(|recipe| vec![recipe.to_string()])
+                    .unwrap_or_default(),
+                description: description.clone(),
+            });
+            current.push(tasks.len() - 1);
+        }
+    }
+    // Build files and special targets (.PHONY, .DEFAULT_GOAL) are not tasks
+    tasks.retain(|task| !task.name.starts_with('.') && !task.name.contains('/'));
+    tasks
+}
+
+/// justfile recipes, except private ones (`_name` or `[private]`)
+pub fn parse_justfile(text: &str, file: &str) -> Vec<Task> {
+    static RECIPE: OnceLock<Regex> = OnceLock::new();
+    let recipe = RECIPE
+        .get_or_init(|| Regex::new(r"^@?([A-Za-z_][A-Za-z0-9_-]*)(?:\s+[^:]*)?:(.*)$").unwrap());
+    let lines: Vec<&str> = text.lines().collect();
+    let mut tasks: Vec<Task> = Vec::new();
+    // Whether indented lines belong to the last listed task
+    let mut in_recipe = false;
+    for (index, line) in lines.iter().enumerate() {
+        if line.starts_with([' ', '\t']) {
+            let command = line.trim().trim_start_matches(['@', '-']).trim();
+            if let Some(task) = tasks.last_mut().filter(|_| in_recipe) {
+                if !command.is_empty() && !command.starts_with('#') {
+                    task.commands.push(command.to_string());
+                }
+            }
+            continue;
+        }
+        in_recipe = false;
+        // `set`, `alias` and variables assign with `:=`
+        let Some(captures) = recipe.captures(line).filter(|c| !c[2].starts_with('=')) else {
+            continue;
+        };
+        let name = &captures[1];
+        // Attributes sit between the doc comment and the recipe
+        let mut above = index;
+        let mut private = name.starts_with('_');
+        while above > 0 && lines[above - 1].trim_start().starts_with('[') {
+            above -= 1;
+            private |= lines[above].contains("private");
+        }
+        if private {
+            continue;
+        }
+        in_recipe = true;
+        tasks.push(Task {
+            runner: TaskRunner::Just,
+            file: file.to_string(),
+            name: name.to_string(),
+            invocation: format!("just {}", name),
+            commands: Vec::new(),
+            description: comment_above(&lines, above),
+        });
+    }
+    tasks
+}
+
+/// Package manager running package.json scripts in `dir`, from its lockfile
+fn package_manager(dir: &Path, manifest: &serde_json::Value) -> &'static str {
+    let declared = manifest
+        .get("packageManager")
+        .and_then(|manager| manager.as_str())
+        .unwrap_or_default();
+    for manager in ["pnpm", "yarn", "bun", "npm"] {
+        if declared.starts_with(manager) {
+            return manager;
+        }
+    }
+    if dir.join("pnpm-lock.yaml").is_file() {
+        "pnpm"
+    } else if dir.join("yarn.lock").is_file() {
+        "yarn"
+    } else if dir.join("bun.lockb").is_file() || dir.join("bun.lock").is_file() {
+        "bun"
+    } else {
+        "npm"
+    }
+}
+
+/// `scripts` of a package.json, run with the package manager the project uses
+pub fn parse_package_scripts(text: &str, file: &str, dir: &Path) -> Result<Vec<Task>> {
+    let manifest: serde_json::Value = serde_json::from_str(text).context("Invalid JSON")?;
+    let manager = package_manager(dir, &manifest);
+    let Some(scripts) = manifest.get("scripts").and_then(|s| s.as_object()) else {
+        return Ok(Vec::new());
+    };
+    Ok(scripts
+        .iter()
+        .map(|(name, command)| Task {
+            runner: TaskRunner::Npm,
+            file: file.to_string(),
+            name: name.clone(),
+            invocation: match manager {
+                "yarn" => format!("yarn {}", name),
+                "pnpm" => format!("pnpm run {}", name),
+                other => format!("{} run {}", other, name),
+            },
+            commands: command
+                .as_str()
+                .map(|c| vec![c.to_string()])
+                .unwrap_or_default(),
+            description: None,
+        })
+        .collect())
+}
+
+/// Sections of an INI file as (name, key/value pairs), joining indented
+/// continuation lines to the value above them with newlines
+fn parse_ini(text: &str) -> Vec<(String, Vec<(String, String)>)> {
+    let mut sections: Vec<(String, Vec<(String, String)>)> = Vec::new();
+    for line in text.lines() {
+        let trimmed = line.trim();
+        if trimmed.is_empty() || trimmed.starts_with(['#', ';']) {
+            continue;
+        }
+        if trimmed.starts_with('[') && trimmed.ends_with(']') {
+            sections.push((trimmed[1..trimmed.len() - 1].trim().to_string(), Vec::new()));
+            continue;
+        }
+        let Some((_, entries)) = sections.last_mut() else {
+            continue;
+        };
+        if line.starts_with([' ', '\t']) {
+            if let Some((_, value)) = entries.last_mut() {
+                if !value.is_empty() {
+                    value.push('\n');
+                }
+                value.push_str(trimmed);
+                continue;
+            }
+        }
+        if let Some((key, value)) = trimmed.split_once(['=', ':']) {
+            entries.push((key.trim().to_string(), value.trim().to_string()));
+        }
+    }
+    sections
+}
+
+/// tox environments: `env_list`/`envlist` plus every `[testenv:name]` section, with
+/// their `commands`
+pub fn parse_tox(text: &str, file: &str) -> Vec<Task> {
+    // pyproject.toml keeps the INI configuration in `legacy_tox_ini`
+    let ini = if file.ends_with(".toml") {
+        toml::from_str::<toml::Value>(text)
+            .ok()
+            .and_then(|value| {
+                value
+                    .get("tool")?
+                    .get("tox")?
+                    .get("legacy_tox_ini")?
+                    .as_str()
+                    .map(str::to_string)
+            })
+            .unwrap_or_default()
+    } else {
+        text.to_string()
+    };
+    let sections = parse_ini(&ini);
+    let (main_section, env_prefix) = if file.ends_with("setup.cfg") {
+        ("tox:tox", "testenv:")
+    } else {
+        ("tox", "testenv:")
+    };
+    let get = |section: &str, key: &str| -> Option<String> {
+        sections
+            .iter()
+            .find(|(name, _)| name == section)
+            .and_then(|(_, entries)| entries.iter().find(|(k, _)| k == key))
+            .map(|(_, value)| value.clone())
+    };
+    let split_list = |value: &str| -> Vec<String> {
+        value
+            .split([',', '\n'])
+            .map(str::trim)
+            .filter(|name| !name.is_empty())
+            .map(str::to_string)
+            .collect()
+    };
+
+    let mut names: Vec<String> = get(main_section, "env_list")
+        .or_else(|| get(main_section, "envlist"))
+        .map(|list| split_list(&list))
+        .unwrap_or_default();
+    for (section, _) in &sections {
+        if let Some(name) = section.strip_prefix(env_prefix) {
+            if !names.iter().any(|existing| existing == name) {
+                names.push(name.to_string());
+            }
+        }
+    }
+    names
+        .into_iter()
+        .map(|name| {
+            let section = format!("{}{}", env_prefix, name);
+            let commands = get(&section, "commands")
+                .or_else(|| get("testenv", "commands"))
+                .map(|commands| {
+                    commands
+                        .lines()
+                        .map(str::trim)
+                        .filter(|c| !c.is_empty())
+                        .map(str::to_string)
+                        .collect()
+                })
+                .unwrap_or_default();
+            Task {
+                runner: TaskRunner::Tox,
+                file: file.to_string(),
+                invocation: format!("tox -e {}", name),
+                description: get(&section, "description"),
+                name,
+                commands,
+            }
+        })
+        .collect()
+}
+
+/// nox sessions: functions decorated with `@nox.session`, their docstrings and the
+/// commands they `session.run`
+pub fn parse_noxfile(text: &str, file: &str) -> Vec<Task> {
+    static RENAMED: OnceLock<Regex> = OnceLock::new();
+    static RUN: OnceLock<Regex> = OnceLock::new();
+    let renamed = RENAMED.get_or_init(|| Regex::new(r#"name\s*=\s*["']([^"']+)["']"#).unwrap());
+    let run = RUN.get_or_init(|| Regex::new(r"session\.run(?:_always)?\((.*)\)").unwrap());
+    let lines: Vec<&str> = text.lines().collect();
+    let mut tasks: Vec<Task> = Vec::new();
+    let mut index = 0;
+    while index < lines.len() {
+        let line = lines[index].trim();
+        index += 1;
+        if !line.starts_with("@nox.session") {
+            continue;
+        }
+        let name_override = renamed.captures(line).map(|c| c[1].to_string());
+        // Further decorators may sit between @nox.session and the function
+        while index < lines.len() && lines[index].trim().starts_with('@') {
+            index += 1;
+        }
+        let Some(function) = lines.get(index).and_then(|line| {
+            line.trim()
+                .strip_prefix("def ")
+                .and_then(|rest| rest.split('(').next())
+        }) else {
+            continue;
+        };
+        index += 1;
+        let mut task = Task {
+            runner: TaskRunner::Nox,
+            file: file.to_string(),
+            name: name_override.unwrap_or_else(|| function.to_string()),
+            invocation: String::new(),
+            commands: Vec::new(),
+            description: None,
+        };
+        task.invocation = format!("nox -s {}", task.name);
+        if let Some(doc) = lines.get(index).map(|line| line.trim()) {
+            if let Some(doc) = doc
+                .strip_prefix("\"\"\"")
+                .or_else(|| doc.strip_prefix("'''"))
+            {
+                let doc = doc
+                    .trim_end_matches("\"\"\"")
+                    .trim_end_matches("'''")
+                    .trim();
+                task.description = (!doc.is_empty()).then(|| doc.to_string());
+            }
+        }
+        // The body ends at the next line indented no deeper than the `def`
+        while index < lines.len()
+            && (lines[index].trim().is_empty() || lines[index].starts_with([' ', '\t']))
+        {
+            if let Some(captures) = run.captures(lines[index]) {
+                let command = captures[1]
+                    .split(',')
+                    .map(|arg| arg.trim().trim_matches(['"', '\'']))
+                    .filter(|arg| !arg.is_empty() && !arg.contains('='))
+                    .collect::<Vec<_>>()
+                    .join(" ");
+                if !command.is_empty() {
+                    task.commands.push(command);
+                }
+            }
+            index += 1;
+        }
+        tasks.push(task);
+    }
+    tasks
+}
+
+/// Parse one task runner configuration file
+pub fn parse_task_file(runner: TaskRunner, path: &Path, file: &str) -> Result<Vec<Task>> {
+    let text = fs::read_to_string(path)?;
+    let dir = path.parent().unwrap_or(Path::new("."));
+    Ok(match runner {
+        TaskRunner::Make => parse_makefile(&text, file),
+        TaskRunner::Just => parse_justfile(&text, file),
+        TaskRunner::Npm => parse_package_scripts(&text, file, dir)?,
+        TaskRunner::Tox => parse_tox(&text, file),
+        TaskRunner::Nox => parse_noxfile(&text, file),
+    })
+}
+
+/// Tool listing the tasks of a project's task runners
+pub struct ListTasksTool {
+    name: String,
+}
+
+impl ListTasksTool {
+    pub fn new() -> Self {
+        Self {
+            name: "list_tasks".to_string(),
+        }
+    }
+
+    fn format_task(task: &Task) -> String {
+        let mut line = format!("  {}", task.invocation);
+        if let Some(description) = &task.description {
+            line.push_str(&format!(" - {}", description));
+        }
+        if !task.commands.is_empty() {
+            let commands = task.commands.join(" && ");
+            let preview: String = commands.chars().take(COMMAND_PREVIEW_CHARS).collect();
+            let ellipsis = if preview.len() < commands.len() {
+                "..."
+            } else {
+                ""
+            };
+            line.push_str(&format!("\n      {}{}", preview, ellipsis));
+        }
+        line
+    }
+}
+
+impl Default for ListTasksTool {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl Tool for ListTasksTool {
+    fn name(&self) -> &str {
+        &self.name
+    }
+
+    fn description(&self) -> &str {
+        "List the project's tasks (Makefile targets, justfile recipes, package.json scripts, tox environments, nox sessions) with the command invoking each and what it runs, to use the project's own workflows"
+    }
+
+    fn signature(&self) -> &str {
+        "list_tasks [path] [--runner=<make|just|npm|tox|nox>] [--filter=<text>]"
+    }
+
+    fn danger_level(&self) -> DangerLevel {
+        DangerLevel::ReadOnly
+    }
+
+    fn validate_args(&self, args: &ToolArgs) -> Result<(), ToolError> {
+        if let Some(runner) = args.get_named_arg("runner") {
+            if TaskRunner::from_name(runner).is_none() {
+                return Err(ToolError::InvalidArgs {
+                    message: format!(
+                        "Unknown task runner '{}'. Expected one of: make, just, npm, tox, nox",
+                        runner
+                    ),
+                });
+            }
+        }
+        Ok(())
+    }
+
+    fn execute(&mut self, args: &ToolArgs, state: &Arc<Mutex<ToolState>>) -> Result<ToolResult> {
+        let dir = match args.get_named_arg("path").or_else(|| args.get_arg(0)) {
+            Some(path) => session_path(state, path),
+            None => {
+                let state_guard = state
+                    .lock()
+                    .map_err(|e| anyhow::anyhow!("Failed to lock state: {}", e))?;
+                state_guard.working_directory.clone()
+            }
+        };
+        if !dir.is_dir() {
+            return Ok(crate::utils::suggest::path_not_found(
+                format!("Directory {} not found", dir.display()),
+                &dir,
+            ));
+        }
+
+        let mut files = find_task_files(&dir);
+        if let Some(runner) = args
+            .get_named_arg("runner")
+            .and_then(|runner| TaskRunner::from_name(runner))
+        {
+            files.retain(|(file_runner, _)| *file_runner == runner);
+        }
+        if files.is_empty() {
+            return Ok(ToolResult::error(format!(
+                "No task runner configuration found in {} (looked for Makefile, justfile, package.json scripts, tox.ini and noxfile.py)",
+                dir.display()
+            )));
+        }
+        let filter = args.get_named_arg("filter").map(|f| f.to_lowercase());
+
+        let mut tasks = Vec::new();
+        let mut errors = Vec::new();
+        let mut sections = Vec::new();
+        for (runner, path) in &files {
+            let file = path
+                .strip_prefix(&dir)
+                .unwrap_or(path)
+                .display()
+                .to_string();
+            let mut file_tasks = match parse_task_file(*runner, path, &file) {
+                Ok(file_tasks) => file_tasks,
+                Err(e) => {
+                    sections.push(format!(
+                        "{} ({}): could not parse: {:#}",
+                        file,
+                        runner.as_str(),
+                        e
+                    ));
+                    errors.push(serde_json::json!({"file": file, "error": format!("{:#}", e)}));
+                    continue;
+                }
+            };
+            if let Some(filter) = &filter {
+                file_tasks.retain(|task| {
+                    task.name.to_lowercase().contains(filter)
+                        || task
+                            .description
+                            .as_ref()
+                            .is_some_and(|d| d.to_lowercase().contains(filter))
+                });
+            }
+            if file_tasks.is_empty() {
+                continue;
+            }
+            let mut section = format!("{} ({})", file, runner.as_str());
+            for task in &file_tasks {
+                section.push('\n');
+                section.push_str(&Self::format_task(task));
+            }
+            sections.push(section);
+            tasks.extend(file_tasks);
+        }
+
+        let message = if tasks.is_empty() && errors.is_empty() {
+            match args.get_named_arg("filter") {
+                Some(filter) => format!("No task matches '{}' in {} file(s)", filter, files.len()),
+                None => format!("No tasks defined in {} file(s)", files.len()),
+            }
+        } else {
+            format!(
+                "{} task(s) in {} file(s):\n\n{}",
+                tasks.len(),
+                files.len(),
+                sections.join("\n\n")
+            )
+        };
+        Ok(ToolResult::success_with_data(
+            message,
+            serde_json::json!({
+                "directory": dir.display().to_string(),
+                "files": files
+                    .iter()
+                    .map(|(runner, path)| serde_json::json!({
+                        "runner": runner,
+                        "path": path.display().to_string(),
+                    }))
+                    .collect::<Vec<_>>(),
+                "tasks": tasks,
+                "errors": errors,
+            }),
+        ))
+    }
+
+    fn get_parameters_schema(&self) -> serde_json::Value {
+        serde_json::json!({
+            "type": "object",
+            "properties": {
+                "path": {
+                    "type": "string",
+                    "description": "Project directory (defaults to the working directory)"
+                },
+                "runner": {
+                    "type": "string",
+                    "enum": ["make", "just", "npm", "tox", "nox"],
+                    "description": "Only list this runner's tasks"
+                },
+                "filter": {
+                    "type": "string",
+                    "description": "Only list tasks whose name or description contains this text (case-insensitive)"
+                }
+            },
+            "required": []
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use tempfile::TempDir;
+
+    #[test]
+    fn test_parse_makefile_and_justfile() {
+        let makefile = "VERSION := 1.0\n.PHONY: test lint\n\n# Run the tests\ntest:\n\tcargo test \\\n\t  --workspace\n\nlint fmt: ## Check style\n\tcargo clippy\n\nbuild/out.o: src/a.c\n\tcc -c $<\n\n%.o: %.c\n\tcc $<\nci: test lint ; echo done\n";
+        let tasks = parse_makefile(makefile, "Makefile");
+        let names: Vec<&str> = tasks.iter().map(|t| t.name.as_str()).collect();
+        assert_eq!(names, vec!["test", "lint", "fmt", "ci"]);
+        assert_eq!(tasks[0].commands, vec!["cargo test --workspace"]);
+        assert_eq!(tasks[0].description.as_deref(), Some("Run the tests"));
+        assert_eq!(tasks[1].description.as_deref(), Some("Check style"));
+        assert_eq!(tasks[2].commands, vec!["cargo clippy"]);
+        assert_eq!(tasks[3].commands, vec!["echo done"]);
+
+        let justfile = "set shell := [\"bash\", \"-c\"]\nalias t := test\n\n# Run all tests\ntest filter='':\n    cargo test {{filter}}\n\n[private]\nhelper:\n    echo hidden\n\n_setup:\n    echo hidden\n\n@release version: test\n    git tag {{version}}\n";
+        let tasks = parse_justfile(justfile, "justfile");
+        let names: Vec<&str> = tasks.iter().map(|t| t.name.as_str()).collect();
+        assert_eq!(names, vec!["test", "release"]);
+        assert_eq!(tasks[0].description.as_deref(), Some("Run all tests"));
+        assert_eq!(tasks[1].commands, vec!["git tag {{version}}"]);
+    }
+
+    #[test]
+    fn test_parse_tox_and_noxfile() {
+        let tox = "[tox]\nenvlist = py311, lint\n\n[testenv]\ncommands = pytest {posargs}\n\n[testenv:lint]\ndescription = Run linters\ncommands =\n    ruff check .\n    mypy src\n";
+        let tasks = parse_tox(tox, "tox.ini");
+        assert_eq!(tasks.len(), 2);
+        assert_eq!(tasks[0].invocation, "tox -e py311");
+        assert_eq!(tasks[0].commands, vec!["pytest {posargs}"]);
+        assert_eq!(tasks[1].commands, vec!["ruff check .", "mypy src"]);
+        assert_eq!(tasks[1].description.as_deref(), Some("Run linters"));
+
+        let noxfile = "import nox\n\n@nox.session(python=[\"3.11\"])\ndef tests(session):\n    \"\"\"Run the test suite.\"\"\"\n    session.install(\"-e\", \".\")\n    session.run(\"pytest\", \"-q\", env={\"X\": \"1\"})\n\n\n@nox.session(name=\"docs-build\")\n@nox.parametrize(\"x\", [1])\ndef docs(session, x):\n    session.run(\"sphinx-build\", \"docs\", \"out\")\n";
+        let tasks = parse_noxfile(noxfile, "noxfile.py");
+        assert_eq!(tasks.len(), 2);
+        assert_eq!(tasks[0].description.as_deref(), Some("Run the test suite."));
+        assert_eq!(tasks[0].commands, vec!["pytest -q"]);
+        assert_eq!(tasks[1].invocation, "nox -s docs-build");
+        assert_eq!(tasks[1].commands, vec!["sphinx-build docs out"]);
+    }
+
+    #[test]
+    fn test_list_tasks() {
+        let temp_dir = TempDir::new().unwrap();
+        let dir = temp_dir.path();
+        fs::write(
+            dir.join("package.json"),
+            r#"{"name": "web", "scripts": {"test": "vitest run", "lint": "eslint ."}}"#,
+        )
+        .unwrap();
+        fs::write(dir.join("pnpm-lock.yaml"), "lockfileVersion: '9.0'\n").unwrap();
+        fs::write(dir.join("Makefile"), "test:\n\tpnpm test\n").unwrap();
+
+        let mut tool = ListTasksTool::new();
+        let state = Arc::new(Mutex::new(ToolState::new()));
+        let args = ToolArgs::from_args(&[dir.to_str().unwrap(), "--filter=test"]);
+        let result = tool.execute(&args, &state).unwrap();
+        assert!(result.success, "{}", result.message);
+        assert_eq!(
+            result.message,
+            "2 task(s) in 2 file(s):\n\nMakefile (make)\n  make test\n      pnpm test\n\npackage.json (npm)\n  pnpm run test\n      vitest run"
+        );
+
+        let args = ToolArgs::from_args(&[dir.to_str().unwrap(), "--runner=tox"]);
+        assert!(!tool.execute(&args, &state).unwrap().success);
+    }
+}
@@ -0,0 +1,814 @@
+//! Monorepo package boundaries
+//!
+//! Detects Cargo workspaces, npm/yarn/pnpm workspaces and Python monorepos (uv
+//! workspaces, or several `pyproject.toml`/`setup.py` projects under one repository)
+//! and maps files to the package that owns them. Each [`WorkspaceMember`] carries the
+//! directory to search in and the commands that test and build only that package,
+//! which `search_dir`, `run_tests` and `run_benchmarks` use to scope a call to the
+//! package named by their `package` argument ([`resolve_package`]).
+
+use crate::core::{DangerLevel, Tool, ToolArgs, ToolError, ToolResult};
+use crate::state::ToolState;
+use crate::utils::suggest;
+use anyhow::Result;
+use serde::Serialize;
+use std::fs;
+use std::path::{Component, Path, PathBuf};
+use std::sync::{Arc, Mutex};
+use walkdir::WalkDir;
+
+/// How deep below the root a Python monorepo's projects are looked for
+const PYTHON_SCAN_DEPTH: usize = 3;
+
+/// Directories never holding workspace members
+const SKIPPED_DIRS: &[&str] = &[
+    "node_modules",
+    "target",
+    "venv",
+    "__pycache__",
+    "build",
+    "dist",
+];
+
+/// Package manager a workspace is declared for
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "snake_case")]
+pub enum WorkspaceKind {
+    Cargo,
+    Npm,
+    Yarn,
+    Pnpm,
+    Python,
+}
+
+impl WorkspaceKind {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            WorkspaceKind::Cargo => "cargo",
+            WorkspaceKind::Npm => "npm",
+            WorkspaceKind::Yarn => "yarn",
+            WorkspaceKind::Pnpm => "pnpm",
+            WorkspaceKind::Python => "python",
+        }
+    }
+}
+
+/// One package of a workspace
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+pub struct WorkspaceMember {
+    pub name: String,
+    pub kind: WorkspaceKind,
+    /// Directory relative to the workspace root, `/`-separated; `.` for the root
+    pub path: String,
+    /// Manifest declaring the package, relative to the workspace root
+    pub manifest: String,
+    /// Command running only this package's tests, from the workspace root
+    pub test_command: String,
+    /// Command building only this package, from the workspace root
+    pub build_command: String,
+}
+
+impl WorkspaceMember {
+    fn new(name: String, kind: WorkspaceKind, path: String, manifest: &str) -> Self {
+        let manifest = if path == "." {
+            manifest.to_string()
+        } else {
+            format!("{}/{}", path, manifest)
+        };
+        let (test_command, build_command) = match kind {
+            WorkspaceKind::Cargo => (
+                format!("cargo test -p {}", name),
+                format!("cargo build -p {}", name),
+            ),
+            WorkspaceKind::Npm => (
+                format!("npm test --workspace={}", name),
+                format!("npm run build --workspace={}", name),
+            ),
+            WorkspaceKind::Yarn => (
+                format!("yarn workspace {} test", name),
+                format!("yarn workspace {} build", name),
+            ),
+            WorkspaceKind::Pnpm => (
+                format!("pnpm --filter {} test", name),
+                format!("pnpm --filter {} build", name),
+            ),
+            WorkspaceKind::Python => (
+                format!("python -m pytest {}", path),
+                format!("python -m build {}", path),
+            ),
+        };
+        Self {
+            name,
+            kind,
+            path,
+            manifest,
+            test_command,
+            build_command,
+        }
+    }
+}
+
+impl WorkspaceMember {
+    /// The member's directory in the workspace rooted at `root`
+    pub fn directory(&self, root: &Path) -> PathBuf {
+        if self.path == "." {
+            root.to_path_buf()
+        } else {
+            root.join(&self.path)
+        }
+    }
+
+    /// Where package-scoped commands run: the workspace root for Cargo packages, which
+    /// are selected with `-p`, the member's directory otherwise
+    pub fn command_directory(&self, root: &Path) -> PathBuf {
+        match self.kind {
+            WorkspaceKind::Cargo => root.to_path_buf(),
+            _ => self.directory(root),
+        }
+    }
+}
+
+/// A detected workspace and its members
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+pub struct Workspace {
+    pub root: PathBuf,
+    pub kinds: Vec<WorkspaceKind>,
+    pub members: Vec<WorkspaceMember>,
+}
+
+impl Workspace {
+    /// The workspace `dir` is in: the nearest ancestor declaring a Cargo, npm, yarn,
+    /// pnpm or uv workspace, else the git repository when it holds several Python
+    /// projects
+    pub fn detect(dir: &Path) -> Option<Self> {
+        let dir = dir.canonicalize().unwrap_or_else(|_| dir.to_path_buf());
+        for ancestor in dir.ancestors() {
+            let workspace = Self::declared(ancestor);
+            if !workspace.members.is_empty() {
+                return Some(workspace);
+            }
+        }
+        let root = crate::vcs::repo_root(&dir).ok()?;
+        let root = root.canonicalize().unwrap_or(root);
+        let members = python_projects(&root);
+        (members.len() > 1).then(|| Self {
+            root,
+            kinds: vec![WorkspaceKind::Python],
+            members,
+        })
+    }
+
+    /// The workspaces declared in `root` itself
+    fn declared(root: &Path) -> Self {
+        let mut workspace = Self {
+            root: root.to_path_buf(),
+            kinds: Vec::new(),
+            members: Vec::new(),
+        };
+        let sources = [
+            (WorkspaceKind::Cargo, cargo_members(root)),
+            (javascript_kind(root), javascript_members(root)),
+            (WorkspaceKind::Python, uv_members(root)),
+        ];
+        for (kind, members) in sources {
+            if !members.is_empty() {
+                workspace.kinds.push(kind);
+                workspace.members.extend(members);
+            }
+        }
+        workspace
+    }
+
+    /// The member owning `path` (absolute, or relative to the workspace root): the one
+    /// with the deepest directory containing it
+    pub fn member_for(&self, path: &Path) -> Option<&WorkspaceMember> {
+        let relative = self.relative(path)?;
+        self.members
+            .iter()
+            .filter(|member| {
+                member.path == "."
+                    || relative == member.path
+                    || relative.starts_with(&format!("{}/", member.path))
+            })
+            .max_by_key(|member| {
+                if member.path == "." {
+                    0
+                } else {
+                    member.path.len()
+                }
+            })
+    }
+
+    /// The member named `name`, or whose directory is `name`
+    pub fn find(&self, name: &str) -> Option<&WorkspaceMember> {
+        let path = name.trim_start_matches("./").trim_end_matches('/');
+        self.members
+            .iter()
+            .find(|member| member.name == name)
+            .or_else(|| self.members.iter().find(|member| member.path == path))
+    }
+
+    /// `path` relative to the root with `/` separators
+    fn relative(&self, path: &Path) -> Option<String> {
+        let absolute = if path.is_absolute() {
+            path.canonicalize().unwrap_or_else(|_| path.to_path_buf())
+        } else {
+            self.root.join(path)
+        };
+        let relative = absolute.strip_prefix(&self.root).ok()?;
+        let mut parts: Vec<String> = Vec::new();
+        for component in relative.components() {
+            match component {
+                Component::Normal(part) => parts.push(part.to_string_lossy().to_string()),
+                Component::ParentDir => {
+                    // Above the root is outside the workspace
+                    parts.pop()?;
+                }
+                _ => {}
+            }
+        }
+        Some(parts.join("/"))
+    }
+}
+
+/// The member `package` names (by name or directory) in the workspace around `dir`
+///
+/// Fails with the result to return when there is no workspace or no such member,
+/// suggesting the closest member names.
+pub fn resolve_package(
+    dir: &Path,
+    package: &str,
+) -> std::result::Result<(Workspace, WorkspaceMember), ToolResult> {
+    let Some(workspace) = Workspace::detect(dir) else {
+        return Err(ToolResult::error(format!(
+            "No workspace found at or above {} to find package '{}' in",
+            dir.display(),
+            package
+        )));
+    };
+    if let Some(member) = workspace.find(package) {
+        let member = member.clone();
+        return Ok((workspace, member));
+    }
+    let names: Vec<&str> = workspace.members.iter().map(|m| m.name.as_str()).collect();
+    let suggestions =
+        suggest::closest_matches(package, names.iter().copied(), suggest::MAX_SUGGESTIONS);
+    Err(ToolResult::error_with_data(
+        format!(
+            "Unknown package '{}' in the workspace at {}{}",
+            package,
+            workspace.root.display(),
+            suggest::format_suggestions(&suggestions)
+        ),
+        serde_json::json!({
+            "package": package,
+            "members": names,
+            "suggestions": suggestions,
+        }),
+    ))
+}
+
+/// Directories matching workspace member globs, minus those matching an exclude glob
+/// or a `!`-prefixed include, that contain `manifest`
+fn expand_members(
+    root: &Path,
+    patterns: &[String],
+    excludes: &[String],
+    manifest: &str,
+) -> Vec<String> {
+    let mut excluded: Vec<String> = excludes.to_vec();
+    excluded.extend(
+        patterns
+            .iter()
+            .filter_map(|pattern| pattern.strip_prefix('!').map(str::to_string)),
+    );
+    let excluded: Vec<glob::Pattern> = excluded
+        .iter()
+        .filter_map(|pattern| glob::Pattern::new(normalize_pattern(pattern)).ok())
+        .collect();
+
+    let mut members = Vec::new();
+    for pattern in patterns.iter().filter(|pattern| !pattern.starts_with('!')) {
+        let full = root.join(normalize_pattern(pattern));
+        let Ok(paths) = glob::glob(&full.to_string_lossy()) else {
+            continue;
+        };
+        for path in paths.flatten() {
+            if !path.join(manifest).is_file() {
+                continue;
+            }
+            let Ok(relative) = path.strip_prefix(root) else {
+                continue;
+            };
+            let relative = relative
+                .components()
+                .map(|c| c.as_os_str().to_string_lossy().to_string())
+                .collect::<Vec<_>>()
+                .join("/");
+            let relative = if relative.is_empty() {
+                ".".to_string()
+            } else {
+                relative
+            };
+            if !excluded.iter().any(|exclude| exclude.matches(&relative))
+                && !members.contains(&relative)
+            {
+                members.push(relative);
+            }
+        }
+    }
+    members
+}
+
+fn normalize_pattern(pattern: &str) -> &str {
+    let pattern = pattern.trim_start_matches('!').trim_start_matches("./");
+    pattern.trim_end_matches('/')
+}
+
+fn read_toml(path: &Path) -> Option<toml::Value> {
+    toml::from_str(&fs::read_to_string(path).ok()?).ok()
+}
+
+fn string_list(value: Option<&toml::Value>) -> Vec<String> {
+    value
+        .and_then(|value| value.as_array())
+        .map(|items| {
+            items
+                .iter()
+                .filter_map(|item| item.as_str().map(str::to_string))
+                .collect()
+        })
+        .unwrap_or_default()
+}
+
+fn cargo_members(root: &Path) -> Vec<WorkspaceMember> {
+    let Some(manifest) = read_toml(&root.join("Cargo.toml")) else {
+        return Vec::new();
+    };
+    let Some(workspace) = manifest.get("workspace") else {
+        return Vec::new();
+    };
+    let mut paths = expand_members(
+        root,
+        &string_list(workspace.get("members")),
+        &string_list(workspace.get("exclude")),
+        "Cargo.toml",
+    );
+    // A root package is a member of its own workspace
+    if manifest.get("package").is_some() && !paths.iter().any(|path| path == ".") {
+        paths.insert(0, ".".to_string());
+    }
+    paths
+        .into_iter()
+        .filter_map(|path| {
+            let manifest = read_toml(&root.join(&path).join("Cargo.toml"))?;
+            let name = manifest.get("package")?.get("name")?.as_str()?.to_string();
+            Some(WorkspaceMember::new(
+                name,
+                WorkspaceKind::Cargo,
+                path,
+                "Cargo.toml",
+            ))
+        })
+        .collect()
+}
+
+/// pnpm when `pnpm-workspace.yaml` exists, yarn with a `yarn.lock`, npm otherwise
+fn javascript_kind(root: &Path) -> WorkspaceKind {
+    if root.join("pnpm-workspace.yaml").is_file() {
+        WorkspaceKind::Pnpm
+    } else if root.join("yarn.lock").is_file() {
+        WorkspaceKind::Yarn
+    } else {
+        WorkspaceKind::Npm
+    }
+}
+
+/// The `packages` patterns of a `pnpm-workspace.yaml`
+#[cfg(feature = "yaml")]
+fn pnpm_packages(text: &str) -> Vec<String> {
+    serde_yaml_ng::from_str::<serde_yaml_ng::Value>(text)
+        .ok()
+        .and_then(|config| {
+            config.get("packages")?.as_sequence().map(|packages| {
+                packages
+                    .iter()
+                    .filter_map(|package| package.as_str().map(str::to_string))
+                    .collect()
+            })
+        })
+        .unwrap_or_default()
+}
+
+/// Without the `yaml` feature pnpm workspaces are detected but list no packages
+#[cfg(not(feature = "yaml"))]
+fn pnpm_packages(_text: &str) -> Vec<String> {
+    Vec::new()
+}
+
+fn javascript_members(root: &Path) -> Vec<WorkspaceMember> {
+    let kind = javascript_kind(root);
+    let patterns: Vec<String> = if kind == WorkspaceKind::Pnpm {
+        fs::read_to_string(root.join("pnpm-workspace.yaml"))
+            .ok()
+            .map(|text| pnpm_packages(&text))
+            .unwrap_or_default()
+    } else {
+        let package = fs::read_to_string(root.join("package.json"))
+            .ok()
+            .and_then(|text| serde_json::from_str::<serde_json::Value>(&text).ok());
+        // `workspaces` is a list, or an object with a `packages` list (yarn)
+        let workspaces = package.as_ref().and_then(|package| {
+            let workspaces = package.get("workspaces")?;
+            workspaces
+                .get("packages")
+                .unwrap_or(workspaces)
+                .as_array()
+                .cloned()
+        });
+        workspaces
+            .unwrap_or_default()
+            .iter()
+            .filter_map(|pattern| pattern.as_str().map(str::to_string))
+            .collect()
+    };
+    expand_members(root, &patterns, &[], "package.json")
+        .into_iter()
+        .filter_map(|path| {
+            let text = fs::read_to_string(root.join(&path).join("package.json")).ok()?;
+            let package: serde_json::Value = serde_json::from_str(&text).ok()?;
+            let name = package.get("name")?.as_str()?.to_string();
+            Some(WorkspaceMember::new(name, kind, path, "package.json"))
+        })
+        .collect()
+}
+
+/// Name of the Python project in `dir`: `[project]` or `[tool.poetry]` of its
+/// `pyproject.toml`, else the directory name when it has a `setup.py`
+fn python_project(dir: &Path) -> Option<(String, &'static str)> {
+    if let Some(pyproject) = read_toml(&dir.join("pyproject.toml")) {
+        let name = pyproject
+            .get("project")
+            .and_then(|project| project.get("name"))
+            .or_else(|| pyproject.get("tool")?.get("poetry")?.get("name"))
+            .and_then(|name| name.as_str());
+        if let Some(name) = name {
+            return Some((name.to_string(), "pyproject.toml"));
+        }
+    }
+    if dir.join("setup.py").is_file() {
+        let name = dir.file_name()?.to_string_lossy().to_string();
+        return Some((name, "setup.py"));
+    }
+    None
+}
+
+fn uv_members(root: &Path) -> Vec<WorkspaceMember> {
+    let Some(pyproject) = read_toml(&root.join("pyproject.toml")) else {
+        return Vec::new();
+    };
+    let Some(workspace) = pyproject
+        .get("tool")
+        .and_then(|tool| tool.get("uv"))
+        .and_then(|uv| uv.get("workspace"))
+    else {
+        return Vec::new();
+    };
+    let mut paths = expand_members(
+        root,
+        &string_list(workspace.get("members")),
+        &string_list(workspace.get("exclude")),
+        "pyproject.toml",
+    );
+    if !paths.iter().any(|path| path == ".") {
+        paths.insert(0, ".".to_string());
+    }
+    paths
+        .into_iter()
+        .filter_map(|path| {
+            let (name, manifest) = python_project(&root.join(&path))?;
+            let mut member = WorkspaceMember::new(name, WorkspaceKind::Python, path, manifest);
+            member.test_command =
+                format!("uv run --package {} pytest {}", member.name, member.path);
+            member.build_command = format!("uv build --package {}", member.name);
+            Some(member)
+        })
+        .collect()
+}
+
+/// Every Python project at most [`PYTHON_SCAN_DEPTH`] levels below `root`
+fn python_projects(root: &Path) -> Vec<WorkspaceMember> {
+    WalkDir::new(root)
+        .max_depth(PYTHON_SCAN_DEPTH)
+        .sort_by_file_name()
+        .into_iter()
+        .filter_entry(|entry| {
+            let name = entry.file_name().to_string_lossy();
+            entry.depth() == 0
+                || entry.file_type().is_dir()
+                    && !name.starts_with('.')
+                    && !SKIPPED_DIRS.contains(&name.as_ref())
+        })
+        .flatten()
+        .filter_map(|entry| {
+            let (name, manifest) = python_project(entry.path())?;
+            let relative = entry.path().strip_prefix(root).ok()?;
+            let relative = relative
+                .components()
+                .map(|c| c.as_os_str().to_string_lossy().to_string())
+                .collect::<Vec<_>>()
+                .join("/");
+            let relative = if relative.is_empty() {
+                ".".to_string()
+            } else {
+                relative
+            };
+            Some(WorkspaceMember::new(
+                name,
+                WorkspaceKind::Python,
+                relative,
+                manifest,
+            ))
+        })
+        .collect()
+}
+
+/// Tool listing a monorepo's packages and the package owning given files
+pub struct WorkspaceMembersTool {
+    name: String,
+}
+
+impl WorkspaceMembersTool {
+    pub fn new() -> Self {
+        Self {
+            name: "workspace_members".to_string(),
+        }
+    }
+}
+
+impl Default for WorkspaceMembersTool {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl Tool for WorkspaceMembersTool {
+    fn name(&self) -> &str {
+        &self.name
+    }
+
+    fn description(&self) -> &str {
+        "List the packages of a Cargo, npm/yarn/pnpm or Python monorepo with their directories and package-scoped test and build commands, and map files to the package that owns them"
+    }
+
+    fn signature(&self) -> &str {
+        "workspace_members [<file>...]"
+    }
+
+    fn danger_level(&self) -> DangerLevel {
+        DangerLevel::ReadOnly
+    }
+
+    fn validate_args(&self, _args: &ToolArgs) -> Result<(), ToolError> {
+        Ok(())
+    }
+
+    fn execute(&mut self, args: &ToolArgs, state: &Arc<Mutex<ToolState>>) -> Result<ToolResult> {
+        let dir = state
+            .lock()
+            .map_err(|e| anyhow::anyhow!("Failed to lock state: {}", e))?
+            .working_directory
+            .clone();
+        let Some(workspace) = Workspace::detect(&dir) else {
+            return Ok(ToolResult::error(format!(
+                "No workspace found at or above {}: no Cargo [workspace], package.json workspaces, pnpm-workspace.yaml, uv workspace or several Python projects in one repository",
+                dir.display()
+            )));
+        };
+
+        let mut files: Vec<String> = args.args.clone();
+        if let Some(list) = args.get_named_arg("files") {
+            match serde_json::from_str::<Vec<String>>(list) {
+                Ok(list) => files.extend(list),
+                Err(_) => files.extend(list.split(',').map(|file| file.trim().to_string())),
+            }
+        }
+        let owners: Vec<serde_json::Value> = files
+            .iter()
+            .filter(|file| !file.is_empty())
+            .map(|file| {
+                let member = workspace.member_for(&dir.join(file));
+                serde_json::json!({
+                    "file": file,
+                    "member": member.map(|member| &member.name),
+                    "path": member.map(|member| &member.path),
+                })
+            })
+            .collect();
+
+        let kinds: Vec<&str> = workspace.kinds.iter().map(|kind| kind.as_str()).collect();
+        let mut message = format!(
+            "{} workspace at {} ({} members):\n",
+            kinds.join(" + "),
+            workspace.root.display(),
+            workspace.members.len()
+        );
+        for member in &workspace.members {
+            message.push_str(&format!(
+                "  {} ({}, {}) - test: {}\n",
+                member.name,
+                member.path,
+                member.kind.as_str(),
+                member.test_command
+            ));
+        }
+        if !owners.is_empty() {
+            message.push_str("\nFiles:\n");
+            for owner in &owners {
+                message.push_str(&format!(
+                    "  {} -> {}\n",
+                    owner["file"].as_str().unwrap_or_default(),
+                    owner["member"].as_str().unwrap_or("(outside every member)")
+                ));
+            }
+        }
+
+        Ok(ToolResult::success_with_data(
+            message.trim_end().to_string(),
+            serde_json::json!({
+                "root": workspace.root,
+                "kinds": workspace.kinds,
+                "members": workspace.members,
+                "files": owners,
+            }),
+        ))
+    }
+
+    fn get_parameters_schema(&self) -> serde_json::Value {
+        serde_json::json!({
+            "type": "object",
+            "properties": {
+                "files": {
+                    "type": "array",
+                    "items": {"type": "string"},
+                    "description": "Files to map to their owning package, relative to the working directory"
+                }
+            },
+            "required": []
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use tempfile::TempDir;
+
+    fn write(root: &Path, path: &str, content: &str) {
+        let path = root.join(path);
+        fs::create_dir_all(path.parent().unwrap()).unwrap();
+        fs::write(path, content).unwrap();
+    }
+
+    #[test]
+    #[cfg(feature = "yaml")]
+    fn test_cargo_and_pnpm_workspace() {
+        let temp_dir = TempDir::new().unwrap();
+        let root = temp_dir.path();
+        write(
+            root,
+            "Cargo.toml",
+            "[workspace]\nmembers = [\"crates/*\"]\nexclude = [\"crates/old\"]\n\n[package]\nname = \"app\"\n",
+        );
+        write(
+            root,
+            "crates/core/Cargo.toml",
+            "[package]\nname = \"app-core\"\n",
+        );
+        write(root, "crates/old/Cargo.toml", "[package]\nname = \"old\"\n");
+        write(root, "crates/notes/README.md", "");
+        write(
+            root,
+            "pnpm-workspace.yaml",
+            "packages:\n  - 'web/*'\n  - '!web/legacy'\n",
+        );
+        write(root, "web/ui/package.json", r#"{"name": "@app/ui"}"#);
+        write(root, "web/legacy/package.json", r#"{"name": "legacy"}"#);
+
+        let workspace = Workspace::detect(&root.join("crates/core")).unwrap();
+        assert_eq!(
+            workspace.kinds,
+            vec![WorkspaceKind::Cargo, WorkspaceKind::Pnpm]
+        );
+        let names: Vec<&str> = workspace.members.iter().map(|m| m.name.as_str()).collect();
+        assert_eq!(names, vec!["app", "app-core", "@app/ui"]);
+
+        let core = workspace
+            .member_for(Path::new("crates/core/src/lib.rs"))
+            .unwrap();
+        assert_eq!(core.test_command, "cargo test -p app-core");
+        assert_eq!(core.manifest, "crates/core/Cargo.toml");
+        assert_eq!(
+            workspace.member_for(Path::new("src/main.rs")).unwrap().name,
+            "app"
+        );
+        let ui = workspace.find("web/ui").unwrap();
+        assert_eq!(ui.test_command, "pnpm --filter @app/ui test");
+    }
+
+    #[test]
+    fn test_npm_workspaces_and_tool() {
+        let temp_dir = TempDir::new().unwrap();
+        let root = temp_dir.path();
+        write(
+            root,
+            "package.json",
+            r#"{"name": "root", "workspaces": {"packages": ["packages/*"]}}"#,
+        );
+        write(root, "yarn.lock", "");
+        write(root, "packages/api/package.json", r#"{"name": "api"}"#);
+        write(root, "packages/web/package.json", r#"{"name": "web"}"#);
+
+        let mut tool_state = ToolState::new();
+        tool_state.set_working_directory(root.join("packages"));
+        let state = Arc::new(Mutex::new(tool_state));
+        let args = ToolArgs::from_args(&["api/src/index.ts", "../README.md"]);
+        let result = WorkspaceMembersTool::new().execute(&args, &state).unwrap();
+        assert!(result.success, "{}", result.message);
+        assert!(result.message.starts_with("yarn workspace at"));
+        assert!(result
+            .message
+            .contains("api (packages/api, yarn) - test: yarn workspace api test"));
+        let data = result.data.unwrap();
+        assert_eq!(data["files"][0]["member"], "api");
+        assert_eq!(data["files"][1]["member"], serde_json::Value::Null);
+    }
+
+    #[test]
+    fn test_resolve_package() {
+        let temp_dir = TempDir::new().unwrap();
+        let root = temp_dir.path();
+        write(
+            root,
+            "Cargo.toml",
+            "[workspace]\nmembers = [\"crates/*\"]\n",
+        );
+        write(
+            root,
+            "crates/parser/Cargo.toml",
+            "[package]\nname = \"parser\"\n",
+        );
+
+        let (workspace, member) = resolve_package(root, "crates/parser/").unwrap();
+        assert_eq!(member.name, "parser");
+        assert_eq!(
+            member.directory(&workspace.root),
+            workspace.root.join("crates/parser")
+        );
+        let error = resolve_package(root, "parsr").unwrap_err();
+        assert_eq!(
+            error.message,
+            format!(
+                "Unknown package 'parsr' in the workspace at {}. Did you mean: parser?",
+                workspace.root.display()
+            )
+        );
+    }
+
+    #[test]
+    fn test_python_projects() {
+        let temp_dir = TempDir::new().unwrap();
+        let root = temp_dir.path();
+        write(
+            root,
+            "services/auth/pyproject.toml",
+            "[project]\nname = \"auth\"\n",
+        );
+        write(
+            root,
+            "libs/common/pyproject.toml",
+            "[tool.poetry]\nname = \"common\"\n",
+        );
+        write(root, "tools/legacy/setup.py", "");
+        write(
+            root,
+            ".venv/lib/pyproject.toml",
+            "[project]\nname = \"vendored\"\n",
+        );
+
+        let members = python_projects(root);
+        let names: Vec<(&str, &str)> = members
+            .iter()
+            .map(|m| (m.name.as_str(), m.path.as_str()))
+            .collect();
+        assert_eq!(
+            names,
+            vec![
+                ("common", "libs/common"),
+                ("auth", "services/auth"),
+                ("legacy", "tools/legacy")
+            ]
+        );
+        assert_eq!(members[1].test_command, "python -m pytest services/auth");
+    }
+}
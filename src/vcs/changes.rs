@@ -0,0 +1,1152 @@
+//! Working tree status, diffs and commits
+//!
+//! `git_status`, `git_diff` and `git_commit` let an agent inspect and checkpoint its
+//! work. Results carry the parsed form in `data` (files with their staged and
+//! unstaged status, diffs split into files and hunks) so nothing has to be scraped
+//! from git's text output. Paths are relative to the repository root, and the `.cats`
+//! metadata directory is left out everywhere.
+
+#[cfg(feature = "git")]
+use super::{describe_change, git_with, repo_root, stage_all};
+use super::{git, METADATA_PATHSPEC};
+#[cfg(feature = "git")]
+use crate::core::{DangerLevel, Tool, ToolArgs, ToolCategory, ToolError, ToolResult};
+#[cfg(feature = "git")]
+use crate::state::ToolState;
+use anyhow::Result;
+use serde::Serialize;
+#[cfg(feature = "git")]
+use std::fmt::Write as _;
+use std::path::Path;
+#[cfg(feature = "git")]
+use std::sync::{Arc, Mutex};
+
+/// Diff lines shown in a `git_diff` message and kept in its hunks
+#[cfg(feature = "git")]
+const MAX_DIFF_LINES: usize = 1000;
+
+/// Entries listed per group in a `git_status` message
+#[cfg(feature = "git")]
+const MAX_LISTED_ENTRIES: usize = 100;
+
+/// How a file changed
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "snake_case")]
+pub enum ChangeStatus {
+    Added,
+    Modified,
+    Deleted,
+    Renamed,
+    Copied,
+    TypeChanged,
+    Unmerged,
+    Untracked,
+}
+
+impl ChangeStatus {
+    /// Parse one column of git's `XY` status code; `.` and ` ` mean unchanged
+    pub fn from_code(code: char) -> Option<Self> {
+        match code {
+            'A' => Some(Self::Added),
+            'M' => Some(Self::Modified),
+            'D' => Some(Self::Deleted),
+            'R' => Some(Self::Renamed),
+            'C' => Some(Self::Copied),
+            'T' => Some(Self::TypeChanged),
+            'U' => Some(Self::Unmerged),
+            '?' => Some(Self::Untracked),
+            _ => None,
+        }
+    }
+
+    /// The one-letter code git uses
+    pub fn code(&self) -> char {
+        match self {
+            Self::Added => 'A',
+            Self::Modified => 'M',
+            Self::Deleted => 'D',
+            Self::Renamed => 'R',
+            Self::Copied => 'C',
+            Self::TypeChanged => 'T',
+            Self::Unmerged => 'U',
+            Self::Untracked => '?',
+        }
+    }
+}
+
+/// A changed path in the working tree or index
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+pub struct StatusEntry {
+    pub path: String,
+    /// Previous path of a renamed or copied file
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub from: Option<String>,
+    /// Change staged in the index
+    pub staged: Option<ChangeStatus>,
+    /// Change in the working tree not yet staged
+    pub unstaged: Option<ChangeStatus>,
+    /// Unresolved merge conflict
+    pub conflicted: bool,
+}
+
+/// Branch and changed files of a repository
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize)]
+pub struct Status {
+    /// Checked out branch, `None` when `HEAD` is detached
+    pub branch: Option<String>,
+    /// Commit `HEAD` points to, `None` before the first commit
+    pub head: Option<String>,
+    pub upstream: Option<String>,
+    pub ahead: usize,
+    pub behind: usize,
+    pub entries: Vec<StatusEntry>,
+}
+
+impl Status {
+    pub fn is_clean(&self) -> bool {
+        self.entries.is_empty()
+    }
+}
+
+/// Parse the output of `git status --porcelain=v2 --branch -z`
+pub fn parse_status(text: &str) -> Status {
+    let mut status = Status::default();
+    let mut fields = text.split('\0').filter(|f| !f.is_empty());
+    while let Some(record) = fields.next() {
+        if let Some(header) = record.strip_prefix("# ") {
+            let (key, value) = header.split_once(' ').unwrap_or((header, ""));
+            match key {
+                "branch.oid" if value != "(initial)" => status.head = Some(value.to_string()),
+                "branch.head" if value != "(detached)" => status.branch = Some(value.to_string()),
+                "branch.upstream" => status.upstream = Some(value.to_string()),
+                "branch.ab" => {
+                    for part in value.split_whitespace() {
+                        if let Some(n) = part.strip_prefix('+') {
+                            status.ahead = n.parse().unwrap_or(0);
+                        } else if let Some(n) = part.strip_prefix('-') {
+                            status.behind = n.parse().unwrap_or(0);
+                        }
+                    }
+                }
+                _ => {}
+            }
+            continue;
+        }
+        let kind = record.chars().next().unwrap_or(' ');
+        let entry = match kind {
+            // `1 XY sub mH mI mW hH hI path`, `2 ... Xscore path` then the original path
+            '1' | '2' | 'u' => {
+                let skip = match kind {
+                    '1' => 8,
+                    '2' => 9,
+                    _ => 10,
+                };
+                let parts: Vec<&str> = record.splitn(skip + 1, ' ').collect();
+                let Some(path) = parts.get(skip) else {
+                    continue;
+                };
+                let mut xy = parts.get(1).copied().unwrap_or("..").chars();
+                let (x, y) = (xy.next().unwrap_or('.'), xy.next().unwrap_or('.'));
+                let conflicted = kind == 'u';
+                StatusEntry {
+                    path: path.to_string(),
+                    from: (kind == '2').then(|| fields.next().unwrap_or("").to_string()),
+                    staged: if conflicted {
+                        None
+                    } else {
+                        ChangeStatus::from_code(x)
+                    },
+                    unstaged: if conflicted {
+                        Some(ChangeStatus::Unmerged)
+                    } else {
+                        ChangeStatus::from_code(y)
+                    },
+                    conflicted,
+                }
+            }
+            '?' => StatusEntry {
+                path: record[2..].to_string(),
+                from: None,
+                staged: None,
+                unstaged: Some(ChangeStatus::Untracked),
+                conflicted: false,
+            },
+            _ => continue,
+        };
+        status.entries.push(entry);
+    }
+    status
+}
+
+/// One hunk of a file diff
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+pub struct Hunk {
+    pub old_start: usize,
+    pub old_lines: usize,
+    pub new_start: usize,
+    pub new_lines: usize,
+    /// Function or section name git prints after the range
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub section: Option<String>,
+    /// Lines with their ` `, `+`, `-` or `\` prefix
+    pub lines: Vec<String>,
+}
+
+/// The changes to one file in a unified diff
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+pub struct FileDiff {
+    pub path: String,
+    /// Previous path of a renamed or copied file
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub from: Option<String>,
+    pub status: ChangeStatus,
+    pub binary: bool,
+    pub additions: usize,
+    pub deletions: usize,
+    pub hunks: Vec<Hunk>,
+}
+
+/// `a,b` of a hunk range, where a missing count means one line
+fn parse_range(range: &str) -> Option<(usize, usize)> {
+    let (start, count) = range.split_once(',').unwrap_or((range, "1"));
+    Some((start.parse().ok()?, count.parse().ok()?))
+}
+
+/// Parse a hunk header such as `@@ -1,3 +1,4 @@ fn main() {`
+fn parse_hunk_header(line: &str) -> Option<Hunk> {
+    let rest = line.strip_prefix("@@ -")?;
+    let (ranges, section) = rest.split_once(" @@").unwrap_or((rest, ""));
+    let (old, new) = ranges.split_once(" +")?;
+    let (old_start, old_lines) = parse_range(old)?;
+    let (new_start, new_lines) = parse_range(new)?;
+    let section = section.trim();
+    Some(Hunk {
+        old_start,
+        old_lines,
+        new_start,
+        new_lines,
+        section: (!section.is_empty()).then(|| section.to_string()),
+        lines: Vec::new(),
+    })
+}
+
+/// Strip the `a/` or `b/` prefix git puts on diff paths, and the timestamp `diff -u`
+/// appends after a tab
+fn diff_path(path: &str) -> String {
+    let path = path.split('\t').next().unwrap_or(path).trim_end();
+    path.strip_prefix("a/")
+        .or_else(|| path.strip_prefix("b/"))
+        .unwrap_or(path)
+        .to_string()
+}
+
+impl FileDiff {
+    fn new(path: String) -> Self {
+        Self {
+            path,
+            from: None,
+            status: ChangeStatus::Modified,
+            binary: false,
+            additions: 0,
+            deletions: 0,
+            hunks: Vec::new(),
+        }
+    }
+}
+
+/// Parse a unified diff into files and hunks
+///
+/// Besides `git diff` output this accepts plain `diff -u` output (files introduced by
+/// `---`/`+++` alone), hunk line counts that are off, which end a hunk at the first line
+/// that cannot belong to it, and bare `@@` headers without line numbers, whose counts
+/// are taken from their lines and whose start is left at 0.
+pub fn parse_diff(text: &str) -> Vec<FileDiff> {
+    let mut files: Vec<FileDiff> = Vec::new();
+    // Lines of the old and new side still expected in the current hunk
+    let (mut old_left, mut new_left) = (0usize, 0usize);
+    // Whether the current file's `+++` line was seen, so a `---` starts the next file
+    let mut header_done = true;
+    let lines: Vec<&str> = text.lines().collect();
+    for (i, &line) in lines.iter().enumerate() {
+        let in_hunk = old_left > 0 || new_left > 0;
+        let prefix = line.chars().next().unwrap_or(' ');
+        // A hunk without counts also ends where the next file's header starts
+        let next_file = old_left > usize::MAX / 2
+            && line.starts_with("--- ")
+            && lines
+                .get(i + 1)
+                .is_some_and(|next| next.starts_with("+++ "));
+        if in_hunk && !next_file && matches!(prefix, ' ' | '+' | '-' | '\\') {
+            if let Some(file) = files.last_mut() {
+                match prefix {
+                    '+' => {
+                        file.additions += 1;
+                        new_left = new_left.saturating_sub(1);
+                    }
+                    '-' => {
+                        file.deletions += 1;
+                        old_left = old_left.saturating_sub(1);
+                    }
+                    '\\' => {}
+                    _ => {
+                        old_left = old_left.saturating_sub(1);
+                        new_left = new_left.saturating_sub(1);
+                    }
+                }
+                if let Some(hunk) = file.hunks.last_mut() {
+                    hunk.lines.push(line.to_string());
+                }
+                continue;
+            }
+        }
+        (old_left, new_left) = (0, 0);
+        if let Some(rest) = line.strip_prefix("diff --git ") {
+            // Provisional paths; `---`/`+++` and rename lines are unambiguous
+            let (old, new) = rest.rsplit_once(" b/").unwrap_or((rest, rest));
+            let mut file = FileDiff::new(diff_path(new));
+            let old = diff_path(old);
+            if old != file.path {
+                file.from = Some(old);
+            }
+            files.push(file);
+            header_done = false;
+            continue;
+        }
+        if line.starts_with("--- ") && (header_done || files.is_empty()) {
+            files.push(FileDiff::new(String::new()));
+            header_done = false;
+        }
+        let Some(file) = files.last_mut() else {
+            continue;
+        };
+        if line.starts_with("@@") {
+            let hunk = parse_hunk_header(line).unwrap_or(Hunk {
+                old_start: 0,
+                old_lines: 0,
+                new_start: 0,
+                new_lines: 0,
+                section: None,
+                lines: Vec::new(),
+            });
+            // Without counts the hunk runs until a line that cannot belong to it
+            (old_left, new_left) = if hunk.old_start == 0 && hunk.new_start == 0 {
+                (usize::MAX, usize::MAX)
+            } else {
+                (hunk.old_lines, hunk.new_lines)
+            };
+            file.hunks.push(hunk);
+            header_done = true;
+        } else if line.starts_with("new file mode") {
+            file.status = ChangeStatus::Added;
+        } else if line.starts_with("deleted file mode") {
+            file.status = ChangeStatus::Deleted;
+        } else if let Some(from) = line.strip_prefix("rename from ") {
+            file.status = ChangeStatus::Renamed;
+            file.from = Some(from.to_string());
+        } else if let Some(to) = line.strip_prefix("rename to ") {
+            file.path = to.to_string();
+        } else if let Some(from) = line.strip_prefix("copy from ") {
+            file.status = ChangeStatus::Copied;
+            file.from = Some(from.to_string());
+        } else if let Some(to) = line.strip_prefix("copy to ") {
+            file.path = to.to_string();
+        } else if let Some(old) = line.strip_prefix("--- ") {
+            let old = diff_path(old);
+            if old == "/dev/null" {
+                file.status = ChangeStatus::Added;
+            } else if file.path.is_empty() {
+                file.path = old;
+            } else if file.status == ChangeStatus::Modified {
+                file.from = Some(old).filter(|from| *from != file.path);
+            }
+        } else if let Some(new) = line.strip_prefix("+++ ") {
+            let new = diff_path(new);
+            if new == "/dev/null" {
+                file.status = ChangeStatus::Deleted;
+            } else {
+                if file.status == ChangeStatus::Modified
+                    && file.path != new
+                    && !file.path.is_empty()
+                {
+                    file.from.get_or_insert_with(|| file.path.clone());
+                }
+                file.path = new;
+            }
+            header_done = true;
+        } else if line.starts_with("Binary files ") || line == "GIT binary patch" {
+            file.binary = true;
+        }
+    }
+    // Bare `@@` hunks get the counts of the lines they hold, without trailing blank lines
+    for hunk in files.iter_mut().flat_map(|file| file.hunks.iter_mut()) {
+        if hunk.old_start == 0 && hunk.new_start == 0 && hunk.old_lines == 0 && hunk.new_lines == 0
+        {
+            while hunk.lines.last().is_some_and(|l| l.is_empty()) {
+                hunk.lines.pop();
+            }
+            hunk.old_lines = hunk
+                .lines
+                .iter()
+                .filter(|l| !l.starts_with(['+', '\\']))
+                .count();
+            hunk.new_lines = hunk
+                .lines
+                .iter()
+                .filter(|l| !l.starts_with(['-', '\\']))
+                .count();
+        }
+    }
+    files.retain(|file| !file.path.is_empty());
+    files
+}
+
+/// The repository root, or the error result to return outside a repository
+#[cfg(feature = "git")]
+fn root_of(
+    state: &Arc<Mutex<ToolState>>,
+) -> Result<std::result::Result<std::path::PathBuf, ToolResult>> {
+    let dir = state
+        .lock()
+        .map_err(|e| anyhow::anyhow!("Failed to lock state: {}", e))?
+        .working_directory
+        .clone();
+    Ok(repo_root(&dir).map_err(|e| ToolResult::error(format!("{:#}", e))))
+}
+
+/// The status of the repository at `root`
+pub fn status(root: &Path) -> Result<Status> {
+    let text = git(
+        root,
+        &[
+            "status",
+            "--porcelain=v2",
+            "--branch",
+            "-z",
+            "--untracked-files=all",
+            "--",
+            ".",
+            METADATA_PATHSPEC,
+        ],
+    )?;
+    Ok(parse_status(&text))
+}
+
+/// Tool reporting the branch and the staged, unstaged and untracked files
+#[cfg(feature = "git")]
+pub struct GitStatusTool {
+    name: String,
+}
+
+#[cfg(feature = "git")]
+impl GitStatusTool {
+    pub fn new() -> Self {
+        Self {
+            name: "git_status".to_string(),
+        }
+    }
+
+    fn render(status: &Status) -> String {
+        let mut message = match &status.branch {
+            Some(branch) => format!("On branch {}", branch),
+            None => format!(
+                "HEAD detached at {}",
+                status
+                    .head
+                    .as_deref()
+                    .map_or("(no commit)", |h| &h[..h.len().min(12)])
+            ),
+        };
+        if let Some(upstream) = &status.upstream {
+            let _ = write!(
+                message,
+                " (tracking {}, ahead {}, behind {})",
+                upstream, status.ahead, status.behind
+            );
+        }
+        if status.head.is_none() {
+            message.push_str("\nNo commits yet");
+        }
+        if status.is_clean() {
+            message.push_str("\nWorking tree clean");
+            return message;
+        }
+
+        let groups: [(&str, Vec<(char, &StatusEntry)>); 4] = [
+            (
+                "Conflicted",
+                status
+                    .entries
+                    .iter()
+                    .filter(|e| e.conflicted)
+                    .map(|e| ('U', e))
+                    .collect(),
+            ),
+            (
+                "Staged",
+                status
+                    .entries
+                    .iter()
+                    .filter_map(|e| e.staged.map(|s| (s.code(), e)))
+                    .collect(),
+            ),
+            (
+                "Unstaged",
+                status
+                    .entries
+                    .iter()
+                    .filter(|e| !e.conflicted)
+                    .filter_map(|e| {
+                        e.unstaged
+                            .filter(|s| *s != ChangeStatus::Untracked)
+                            .map(|s| (s.code(), e))
+                    })
+                    .collect(),
+            ),
+            (
+                "Untracked",
+                status
+                    .entries
+                    .iter()
+                    .filter(|e| e.unstaged == Some(ChangeStatus::Untracked))
+                    .map(|e| ('?', e))
+                    .collect(),
+            ),
+        ];
+        for (title, entries) in groups.iter().filter(|(_, e)| !e.is_empty()) {
+            let _ = write!(message, "\n{} ({}):", title, entries.len());
+            for (code, entry) in entries.iter().take(MAX_LISTED_ENTRIES) {
+                match &entry.from {
+                    Some(from) => {
+                        let _ = write!(message, "\n  {} {} -> {}", code, from, entry.path);
+                    }
+                    None => {
+                        let _ = write!(message, "\n  {} {}", code, entry.path);
+                    }
+                }
+            }
+            if entries.len() > MAX_LISTED_ENTRIES {
+                let _ = write!(
+                    message,
+                    "\n  ... {} more",
+                    entries.len() - MAX_LISTED_ENTRIES
+                );
+            }
+        }
+        message
+    }
+}
+
+#[cfg(feature = "git")]
+impl Default for GitStatusTool {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+#[cfg(feature = "git")]
+impl Tool for GitStatusTool {
+    fn name(&self) -> &str {
+        &self.name
+    }
+
+    fn description(&self) -> &str {
+        "Show the current branch, how far it is ahead of or behind its upstream, and the conflicted, staged, unstaged and untracked files"
+    }
+
+    fn signature(&self) -> &str {
+        "git_status"
+    }
+
+    fn category(&self) -> ToolCategory {
+        ToolCategory::Vcs
+    }
+
+    fn danger_level(&self) -> DangerLevel {
+        DangerLevel::ReadOnly
+    }
+
+    fn validate_args(&self, _args: &ToolArgs) -> Result<(), ToolError> {
+        Ok(())
+    }
+
+    fn execute(&mut self, _args: &ToolArgs, state: &Arc<Mutex<ToolState>>) -> Result<ToolResult> {
+        let root = match root_of(state)? {
+            Ok(root) => root,
+            Err(result) => return Ok(result),
+        };
+        let status = match status(&root) {
+            Ok(status) => status,
+            Err(e) => return Ok(ToolResult::error(format!("{:#}", e))),
+        };
+        Ok(ToolResult::success_with_data(
+            Self::render(&status),
+            serde_json::json!({
+                "root": root,
+                "clean": status.is_clean(),
+                "branch": status.branch,
+                "head": status.head,
+                "upstream": status.upstream,
+                "ahead": status.ahead,
+                "behind": status.behind,
+                "entries": status.entries,
+            }),
+        ))
+    }
+
+    fn get_parameters_schema(&self) -> serde_json::Value {
+        serde_json::json!({
+            "type": "object",
+            "properties": {},
+            "required": []
+        })
+    }
+}
+
+/// Tool showing unstaged, staged or committed changes as a parsed unified diff
+#[cfg(feature = "git")]
+pub struct GitDiffTool {
+    name: String,
+}
+
+#[cfg(feature = "git")]
+impl GitDiffTool {
+    pub fn new() -> Self {
+        Self {
+            name: "git_diff".to_string(),
+        }
+    }
+
+    fn context(args: &ToolArgs) -> Result<usize, ToolError> {
+        match args.get_named_arg("context") {
+            None => Ok(3),
+            Some(value) => value.parse().map_err(|_| ToolError::InvalidArgs {
+                message: format!("Invalid context '{}': expected a number of lines", value),
+            }),
+        }
+    }
+}
+
+#[cfg(feature = "git")]
+impl Default for GitDiffTool {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+#[cfg(feature = "git")]
+impl Tool for GitDiffTool {
+    fn name(&self) -> &str {
+        &self.name
+    }
+
+    fn description(&self) -> &str {
+        "Show changes as a unified diff with per-file hunks: unstaged by default, staged with --staged, or the working tree against a revision with --rev; optionally limited to paths"
+    }
+
+    fn signature(&self) -> &str {
+        "git_diff [<path>...] [--staged] [--rev=<rev>] [--context=N]"
+    }
+
+    fn category(&self) -> ToolCategory {
+        ToolCategory::Vcs
+    }
+
+    fn danger_level(&self) -> DangerLevel {
+        DangerLevel::ReadOnly
+    }
+
+    fn validate_args(&self, args: &ToolArgs) -> Result<(), ToolError> {
+        if args.get_named_arg("staged").map(String::as_str) == Some("true")
+            && args.get_named_arg("rev").is_some()
+        {
+            return Err(ToolError::InvalidArgs {
+                message: "Pass either staged or rev, not both".to_string(),
+            });
+        }
+        Self::context(args).map(|_| ())
+    }
+
+    fn execute(&mut self, args: &ToolArgs, state: &Arc<Mutex<ToolState>>) -> Result<ToolResult> {
+        let staged = args.get_named_arg("staged").map(String::as_str) == Some("true");
+        let rev = args.get_named_arg("rev");
+        let context = format!(
+            "-U{}",
+            Self::context(args).map_err(|e| anyhow::anyhow!("{}", e))?
+        );
+        let dir = state
+            .lock()
+            .map_err(|e| anyhow::anyhow!("Failed to lock state: {}", e))?
+            .working_directory
+            .clone();
+        let root = match root_of(state)? {
+            Ok(root) => root,
+            Err(result) => return Ok(result),
+        };
+
+        let mut paths: Vec<&str> = args.args.iter().map(String::as_str).collect();
+        if let Some(path) = args.get_named_arg("path") {
+            paths.push(path);
+        }
+        let mut command = vec!["diff", "--no-color", "--no-ext-diff", "-M", &context];
+        if staged {
+            command.push("--cached");
+        }
+        if let Some(rev) = rev {
+            command.push(rev);
+        }
+        command.push("--");
+        if paths.is_empty() {
+            command.push(".");
+        } else {
+            command.extend(&paths);
+        }
+        command.push(METADATA_PATHSPEC);
+        // Pathspecs are relative to the working directory, output paths to the root
+        let text = match git(&dir, &command) {
+            Ok(text) => text,
+            Err(e) => return Ok(ToolResult::error(format!("{:#}", e))),
+        };
+        let mut files = parse_diff(&text);
+
+        // Untracked files never appear in a diff against the index; say so
+        let untracked: Vec<String> = if staged || rev.is_some() {
+            Vec::new()
+        } else {
+            let mut ls = vec![
+                "ls-files",
+                "--others",
+                "--exclude-standard",
+                "--full-name",
+                "--",
+            ];
+            if paths.is_empty() {
+                ls.push(".");
+            } else {
+                ls.extend(&paths);
+            }
+            ls.push(METADATA_PATHSPEC);
+            git(&dir, &ls)
+                .unwrap_or_default()
+                .lines()
+                .map(str::to_string)
+                .collect()
+        };
+
+        let what = match (staged, rev) {
+            (true, _) => "staged changes".to_string(),
+            (false, Some(rev)) => format!("changes since {}", rev),
+            (false, None) => "unstaged changes".to_string(),
+        };
+        let (additions, deletions) = files
+            .iter()
+            .fold((0, 0), |(a, d), f| (a + f.additions, d + f.deletions));
+        let mut message = if files.is_empty() {
+            format!("No {}", what)
+        } else {
+            format!(
+                "{} file(s) with {}, +{} -{}",
+                files.len(),
+                what,
+                additions,
+                deletions
+            )
+        };
+        if !untracked.is_empty() {
+            let _ = write!(
+                message,
+                "\n{} untracked file(s) not shown (stage them to diff): {}",
+                untracked.len(),
+                untracked.join(", ")
+            );
+        }
+
+        // Keep the first MAX_DIFF_LINES lines of hunks in both the message and the data
+        let mut budget = MAX_DIFF_LINES;
+        let mut truncated = false;
+        for file in &mut files {
+            let _ = write!(
+                message,
+                "\n\n{} {}",
+                file.status.code(),
+                match &file.from {
+                    Some(from) => format!("{} -> {}", from, file.path),
+                    None => file.path.clone(),
+                }
+            );
+            if file.binary {
+                message.push_str(" (binary)");
+            }
+            let mut kept = Vec::new();
+            for hunk in std::mem::take(&mut file.hunks) {
+                if hunk.lines.len() > budget {
+                    truncated = true;
+                    break;
+                }
+                budget -= hunk.lines.len();
+                let _ = write!(
+                    message,
+                    "\n@@ -{},{} +{},{} @@{}",
+                    hunk.old_start,
+                    hunk.old_lines,
+                    hunk.new_start,
+                    hunk.new_lines,
+                    hunk.section
+                        .as_deref()
+                        .map(|s| format!(" {}", s))
+                        .unwrap_or_default()
+                );
+                for line in &hunk.lines {
+                    message.push('\n');
+                    message.push_str(line);
+                }
+                kept.push(hunk);
+            }
+            file.hunks = kept;
+        }
+        if truncated {
+            let _ = write!(
+                message,
+                "\n\n... diff cut at {} lines; pass paths to see the rest",
+                MAX_DIFF_LINES
+            );
+        }
+
+        Ok(ToolResult::success_with_data(
+            message,
+            serde_json::json!({
+                "root": root,
+                "staged": staged,
+                "rev": rev,
+                "files": files,
+                "additions": additions,
+                "deletions": deletions,
+                "untracked": untracked,
+                "truncated": truncated,
+            }),
+        ))
+    }
+
+    fn get_parameters_schema(&self) -> serde_json::Value {
+        serde_json::json!({
+            "type": "object",
+            "properties": {
+                "path": {
+                    "type": "string",
+                    "description": "Limit the diff to this file or directory"
+                },
+                "staged": {
+                    "type": "boolean",
+                    "description": "Show what is staged for the next commit instead of unstaged changes"
+                },
+                "rev": {
+                    "type": "string",
+                    "description": "Compare the working tree with this commit, branch or tag"
+                },
+                "context": {
+                    "type": "integer",
+                    "description": "Context lines around each change (default 3)"
+                }
+            },
+            "required": []
+        })
+    }
+}
+
+/// Tool committing staged changes, optionally staging everything or given paths first
+#[cfg(feature = "git")]
+pub struct GitCommitTool {
+    name: String,
+}
+
+#[cfg(feature = "git")]
+impl GitCommitTool {
+    pub fn new() -> Self {
+        Self {
+            name: "git_commit".to_string(),
+        }
+    }
+
+    /// Paths from the `paths` list, as JSON or comma-separated
+    fn paths(args: &ToolArgs) -> Vec<String> {
+        let Some(list) = args.get_named_arg("paths") else {
+            return Vec::new();
+        };
+        match serde_json::from_str::<Vec<String>>(list) {
+            Ok(list) => list,
+            Err(_) => list
+                .split(',')
+                .map(str::trim)
+                .filter(|path| !path.is_empty())
+                .map(str::to_string)
+                .collect(),
+        }
+    }
+}
+
+#[cfg(feature = "git")]
+impl Default for GitCommitTool {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+#[cfg(feature = "git")]
+impl Tool for GitCommitTool {
+    fn name(&self) -> &str {
+        &self.name
+    }
+
+    fn description(&self) -> &str {
+        "Commit the staged changes. --all stages every change first (including new files), --paths stages only those; the message defaults to one generated from the submit summary and changed files"
+    }
+
+    fn signature(&self) -> &str {
+        "git_commit [<message>] [--all] [--paths=<a,b>]"
+    }
+
+    fn category(&self) -> ToolCategory {
+        ToolCategory::Vcs
+    }
+
+    fn danger_level(&self) -> DangerLevel {
+        DangerLevel::ModifiesFiles
+    }
+
+    fn validate_args(&self, args: &ToolArgs) -> Result<(), ToolError> {
+        let message = args.get_named_arg("message").or_else(|| args.get_arg(0));
+        if message.is_some_and(|message| message.trim().is_empty()) {
+            return Err(ToolError::InvalidArgs {
+                message: "The commit message is empty".to_string(),
+            });
+        }
+        Ok(())
+    }
+
+    fn execute(&mut self, args: &ToolArgs, state: &Arc<Mutex<ToolState>>) -> Result<ToolResult> {
+        let all = args.get_named_arg("all").map(String::as_str) == Some("true");
+        let paths = Self::paths(args);
+        let dir = state
+            .lock()
+            .map_err(|e| anyhow::anyhow!("Failed to lock state: {}", e))?
+            .working_directory
+            .clone();
+        let root = match root_of(state)? {
+            Ok(root) => root,
+            Err(result) => return Ok(result),
+        };
+
+        if all {
+            stage_all(&root, None)?;
+        }
+        if !paths.is_empty() {
+            let mut add = vec!["add", "-A", "--"];
+            add.extend(paths.iter().map(String::as_str));
+            if let Err(e) = git(&dir, &add) {
+                return Ok(ToolResult::error(format!("{:#}", e)));
+            }
+        }
+        let staged: Vec<String> = git(&root, &["diff", "--cached", "--name-only", "-z"])?
+            .split('\0')
+            .filter(|path| !path.is_empty())
+            .map(str::to_string)
+            .collect();
+        if staged.is_empty() {
+            return Ok(ToolResult::error(
+                "Nothing staged to commit. Pass --all to commit every change or --paths to choose files",
+            ));
+        }
+
+        let message = match args.get_named_arg("message").or_else(|| args.get_arg(0)) {
+            Some(message) => message.trim().to_string(),
+            None => {
+                let state_guard = state
+                    .lock()
+                    .map_err(|e| anyhow::anyhow!("Failed to lock state: {}", e))?;
+                let (title, body) = describe_change(&state_guard, &staged);
+                format!("{}\n\n{}", title, body)
+            }
+        };
+        if let Err(e) = git_with(&root, &["commit", "-q", "-F", "-"], &[], Some(&message)) {
+            return Ok(ToolResult::error(format!("{:#}", e)));
+        }
+        let commit = git(&root, &["rev-parse", "HEAD"])?;
+        let stat = git(&root, &["show", "--shortstat", "--format=", "HEAD"])?;
+        let title = message.lines().next().unwrap_or_default().to_string();
+        if let Ok(mut state_guard) = state.lock() {
+            state_guard.push_history(format!("git_commit: {}", title));
+        }
+
+        Ok(ToolResult::success_with_data(
+            format!(
+                "Committed {} {}\n{}\n{}",
+                &commit[..commit.len().min(12)],
+                title,
+                stat.trim(),
+                staged
+                    .iter()
+                    .map(|path| format!("  {}", path))
+                    .collect::<Vec<_>>()
+                    .join("\n")
+            ),
+            serde_json::json!({
+                "commit": commit,
+                "message": message,
+                "files": staged,
+                "stat": stat.trim(),
+            }),
+        ))
+    }
+
+    fn get_parameters_schema(&self) -> serde_json::Value {
+        serde_json::json!({
+            "type": "object",
+            "properties": {
+                "message": {
+                    "type": "string",
+                    "description": "Commit message; defaults to a title and body generated from the submit summary and the staged files"
+                },
+                "all": {
+                    "type": "boolean",
+                    "description": "Stage every change in the working tree, including new and deleted files, before committing"
+                },
+                "paths": {
+                    "type": "array",
+                    "items": {"type": "string"},
+                    "description": "Stage these files or directories before committing"
+                }
+            },
+            "required": []
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    #[cfg(feature = "git")]
+    use std::fs;
+
+    #[test]
+    fn test_parse_status_and_diff() {
+        let status = parse_status(
+            "# branch.oid 1234abcd\0# branch.head main\0# branch.upstream origin/main\0# branch.ab +2 -1\x001 .M N... 100644 100644 100644 aaa bbb src/lib.rs\x002 R. N... 100644 100644 100644 aaa bbb R100 new name.rs\0old.rs\0u UU N... 100644 100644 100644 100644 a b c conflict.rs\0? notes.txt\0",
+        );
+        assert_eq!(status.branch.as_deref(), Some("main"));
+        assert_eq!((status.ahead, status.behind), (2, 1));
+        let summary: Vec<_> = status
+            .entries
+            .iter()
+            .map(|e| (e.path.as_str(), e.from.as_deref(), e.staged, e.unstaged))
+            .collect();
+        assert_eq!(
+            summary,
+            vec![
+                ("src/lib.rs", None, None, Some(ChangeStatus::Modified)),
+                (
+                    "new name.rs",
+                    Some("old.rs"),
+                    Some(ChangeStatus::Renamed),
+                    None
+                ),
+                ("conflict.rs", None, None, Some(ChangeStatus::Unmerged)),
+                ("notes.txt", None, None, Some(ChangeStatus::Untracked)),
+            ]
+        );
+
+        let diff = "diff --git a/src/lib.rs b/src/lib.rs\nindex 1..2 100644\n--- a/src/lib.rs\n+++ b/src/lib.rs\n@@ -1,3 +1,3 @@ fn main() {\n a\n--- old\n+++ new\n c\ndiff --git a/img.png b/img.png\nnew file mode 100644\nBinary files /dev/null and b/img.png differ\ndiff --git a/a.rs b/b.rs\nsimilarity index 90%\nrename from a.rs\nrename to b.rs\n@@ -1 +1 @@\n-x\n+y\n";
+        let files = parse_diff(diff);
+        assert_eq!(files.len(), 3);
+        assert_eq!(files[0].path, "src/lib.rs");
+        assert_eq!(files[0].from, None);
+        assert_eq!((files[0].additions, files[0].deletions), (1, 1));
+        assert_eq!(files[0].hunks[0].section.as_deref(), Some("fn main() {"));
+        assert_eq!(
+            files[0].hunks[0].lines,
+            vec![" a", "--- old", "+++ new", " c"]
+        );
+        assert_eq!(files[1].status, ChangeStatus::Added);
+        assert!(files[1].binary);
+        assert_eq!(files[2].status, ChangeStatus::Renamed);
+        assert_eq!(
+            (files[2].from.as_deref(), files[2].path.as_str()),
+            (Some("a.rs"), "b.rs")
+        );
+        assert_eq!(files[2].hunks[0].lines, vec!["-x", "+y"]);
+
+        // `diff -u` output and hand-written hunks without line numbers
+        let diff = "--- lib.py\t2024-01-01 10:00:00\n+++ lib.py\t2024-01-02 10:00:00\n@@\n def f():\n-    return 1\n+    return 2\n\n--- /dev/null\n+++ b/new.py\n@@ -0,0 +1 @@\n+x = 1\n";
+        let files = parse_diff(diff);
+        assert_eq!(files.len(), 2);
+        assert_eq!(files[0].path, "lib.py");
+        assert_eq!(
+            files[0].hunks[0].lines,
+            vec![" def f():", "-    return 1", "+    return 2"]
+        );
+        assert_eq!(
+            (files[0].hunks[0].old_lines, files[0].hunks[0].new_lines),
+            (2, 2)
+        );
+        assert_eq!(
+            (files[1].path.as_str(), files[1].status),
+            ("new.py", ChangeStatus::Added)
+        );
+    }
+
+    #[test]
+    #[cfg(feature = "git")]
+    fn test_status_diff_and_commit_tools() {
+        let temp_dir = super::super::tests::init_repo();
+        let dir = temp_dir.path();
+        fs::write(dir.join("a.txt"), "one\ntwo\n").unwrap();
+        fs::write(dir.join("b.txt"), "new\n").unwrap();
+        fs::create_dir_all(dir.join(".cats/journal")).unwrap();
+        fs::write(dir.join(".cats/journal/x.jsonl"), "{}\n").unwrap();
+        let mut tool_state = ToolState::new();
+        tool_state.working_directory = dir.to_path_buf();
+        let state = Arc::new(Mutex::new(tool_state));
+        let no_args = ToolArgs::from_args(&[]);
+
+        let result = GitStatusTool::new().execute(&no_args, &state).unwrap();
+        assert!(result.success, "{}", result.message);
+        let data = result.data.as_ref().unwrap();
+        assert_eq!(data["branch"], "main");
+        assert_eq!(data["entries"].as_array().unwrap().len(), 2);
+        assert!(result.message.contains("Unstaged (1):\n  M a.txt"));
+        assert!(result.message.contains("Untracked (1):\n  ? b.txt"));
+
+        let result = GitDiffTool::new().execute(&no_args, &state).unwrap();
+        let data = result.data.as_ref().unwrap();
+        assert_eq!(data["files"][0]["path"], "a.txt");
+        assert_eq!(data["files"][0]["hunks"][0]["lines"][1], "+two");
+        assert_eq!(data["untracked"][0], "b.txt");
+
+        let result = GitCommitTool::new()
+            .execute(&ToolArgs::from_args(&["Update a"]), &state)
+            .unwrap();
+        assert!(!result.success);
+        assert!(result.message.contains("Nothing staged"));
+
+        let args = ToolArgs::with_named_args(
+            vec!["Update a and add b".to_string()],
+            [("all".to_string(), "true".to_string())]
+                .into_iter()
+                .collect(),
+        );
+        let result = GitCommitTool::new().execute(&args, &state).unwrap();
+        assert!(result.success, "{}", result.message);
+        assert_eq!(
+            result.data.as_ref().unwrap()["files"],
+            serde_json::json!(["a.txt", "b.txt"])
+        );
+        assert_eq!(
+            git(dir, &["log", "-1", "--format=%s"]).unwrap(),
+            "Update a and add b"
+        );
+        let result = GitStatusTool::new().execute(&no_args, &state).unwrap();
+        assert!(
+            result.message.contains("Working tree clean"),
+            "{}",
+            result.message
+        );
+    }
+}
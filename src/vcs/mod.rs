@@ -0,0 +1,516 @@
+//! Version control tools
+//!
+//! `export_patch` turns the agent's work into a reviewable patch series without
+//! touching the branch, the index or the working tree: uncommitted changes are
+//! snapshotted into a commit object on top of `HEAD` through a temporary index, and
+//! the commits since the base are written out with `git format-patch`. The snapshot's
+//! message comes from [`describe_change`], which reads the summary passed to `submit`.
+//!
+//! `code_owners` answers who reviews a path, from the repository's CODEOWNERS file.
+//!
+//! `git_status`, `git_diff` and `git_commit` inspect and commit the working tree.
+//!
+//! The tools need the `git` feature. The helpers running git, the diff and status
+//! parsers and CODEOWNERS lookup are always built: `apply_patch`, `create_pr`,
+//! `localize` and workspace discovery use them.
+
+#[cfg(feature = "git")]
+use crate::core::{DangerLevel, Tool, ToolArgs, ToolCategory, ToolError, ToolResult};
+use crate::state::ToolState;
+use anyhow::{bail, Context, Result};
+use std::io::Write;
+use std::path::{Path, PathBuf};
+use std::process::{Command, Stdio};
+#[cfg(feature = "git")]
+use std::sync::{Arc, Mutex};
+
+mod changes;
+mod owners;
+pub use changes::{
+    parse_diff, parse_status, status, ChangeStatus, FileDiff, Hunk, Status, StatusEntry,
+};
+#[cfg(feature = "git")]
+pub use changes::{GitCommitTool, GitDiffTool, GitStatusTool};
+#[cfg(feature = "git")]
+pub use owners::CodeOwnersTool;
+pub use owners::{CodeOwners, OwnerRule, Ownership, CODEOWNERS_LOCATIONS};
+
+/// Longest generated title, in characters
+pub const MAX_TITLE_CHARS: usize = 72;
+
+/// Files listed in a generated body
+const MAX_LISTED_FILES: usize = 50;
+
+/// Workspace metadata cats writes (journal, trash) that never belongs in a change
+const METADATA_PATHSPEC: &str = ":(exclude).cats";
+
+/// Run git in `dir` and return its trimmed stdout, or fail with its stderr
+pub(crate) fn git(dir: &Path, args: &[&str]) -> Result<String> {
+    git_with(dir, args, &[], None)
+}
+
+/// [`git`] with extra environment variables and text fed to stdin
+pub(crate) fn git_with(
+    dir: &Path,
+    args: &[&str],
+    envs: &[(&str, &Path)],
+    stdin: Option<&str>,
+) -> Result<String> {
+    let mut command = Command::new("git");
+    command
+        .args(args)
+        .current_dir(dir)
+        .stdin(if stdin.is_some() {
+            Stdio::piped()
+        } else {
+            Stdio::null()
+        })
+        .stdout(Stdio::piped())
+        .stderr(Stdio::piped());
+    for (key, value) in envs {
+        command.env(key, value);
+    }
+    let mut child = command
+        .spawn()
+        .with_context(|| format!("Failed to run git {}", args.join(" ")))?;
+    if let (Some(text), Some(mut input)) = (stdin, child.stdin.take()) {
+        input.write_all(text.as_bytes())?;
+    }
+    let output = child.wait_with_output()?;
+    if !output.status.success() {
+        bail!(
+            "git {} failed: {}",
+            args.join(" "),
+            String::from_utf8_lossy(&output.stderr).trim()
+        );
+    }
+    Ok(String::from_utf8_lossy(&output.stdout)
+        .trim_end()
+        .to_string())
+}
+
+/// Root of the git repository `dir` is in
+pub(crate) fn repo_root(dir: &Path) -> Result<PathBuf> {
+    let root = git(dir, &["rev-parse", "--show-toplevel"])
+        .with_context(|| format!("{} is not inside a git repository", dir.display()))?;
+    Ok(PathBuf::from(root))
+}
+
+/// Whether the working tree differs from `HEAD`, ignoring cats' own metadata
+#[cfg(any(feature = "git", feature = "net"))]
+pub(crate) fn has_uncommitted_changes(root: &Path) -> Result<bool> {
+    let status = git(
+        root,
+        &[
+            "status",
+            "--porcelain",
+            "--untracked-files=all",
+            "--",
+            ".",
+            METADATA_PATHSPEC,
+        ],
+    )?;
+    Ok(!status.is_empty())
+}
+
+/// Stage every change in the working tree into the index at `index`, or the
+/// repository's own index when `None`
+#[cfg(any(feature = "git", feature = "net"))]
+pub(crate) fn stage_all(root: &Path, index: Option<&Path>) -> Result<()> {
+    let envs: Vec<(&str, &Path)> = index
+        .map(|path| ("GIT_INDEX_FILE", path))
+        .into_iter()
+        .collect();
+    if index.is_some() {
+        git_with(root, &["read-tree", "HEAD"], &envs, None)?;
+    }
+    git_with(
+        root,
+        &["add", "-A", "--", ".", METADATA_PATHSPEC],
+        &envs,
+        None,
+    )?;
+    Ok(())
+}
+
+/// Title and body describing a change to `files`
+///
+/// The first line of the session's submit summary is the title and the rest opens the
+/// body; without a summary the title names the changed files. The plan annotations and
+/// the changed files follow in the body.
+pub fn describe_change(state: &ToolState, files: &[String]) -> (String, String) {
+    let summary = state.submit_summary.as_deref().unwrap_or("").trim();
+    let (first, rest) = summary.split_once('\n').unwrap_or((summary, ""));
+    let title = if first.trim().is_empty() {
+        match files {
+            [] => "Update files".to_string(),
+            [file] => format!("Update {}", file),
+            files => format!("Update {} files", files.len()),
+        }
+    } else {
+        first.trim().to_string()
+    };
+    let title = if title.chars().count() > MAX_TITLE_CHARS {
+        let cut: String = title.chars().take(MAX_TITLE_CHARS - 3).collect();
+        format!("{}...", cut.trim_end())
+    } else {
+        title
+    };
+
+    let mut sections = Vec::new();
+    if !rest.trim().is_empty() {
+        sections.push(rest.trim().to_string());
+    }
+    let annotations = state.annotations();
+    if !annotations.is_empty() {
+        sections.push(format!(
+            "Plan:\n{}",
+            annotations
+                .iter()
+                .map(|annotation| format!("- {}", annotation))
+                .collect::<Vec<_>>()
+                .join("\n")
+        ));
+    }
+    if !files.is_empty() {
+        let mut listed: Vec<String> = files
+            .iter()
+            .take(MAX_LISTED_FILES)
+            .map(|file| format!("- {}", file))
+            .collect();
+        if files.len() > MAX_LISTED_FILES {
+            listed.push(format!("- ... and {} more", files.len() - MAX_LISTED_FILES));
+        }
+        sections.push(format!("Files changed:\n{}", listed.join("\n")));
+    }
+    (title, sections.join("\n\n"))
+}
+
+/// Tool writing the session's changes as a `git format-patch` series
+#[cfg(feature = "git")]
+pub struct ExportPatchTool {
+    name: String,
+}
+
+#[cfg(feature = "git")]
+impl ExportPatchTool {
+    pub fn new() -> Self {
+        Self {
+            name: "export_patch".to_string(),
+        }
+    }
+
+    /// The commit the default base resolves to: where `HEAD` forked from its upstream,
+    /// or `HEAD` itself, so only uncommitted changes are exported
+    fn default_base(root: &Path) -> Result<String> {
+        match git(root, &["merge-base", "HEAD", "@{upstream}"]) {
+            Ok(base) => Ok(base),
+            Err(_) => git(root, &["rev-parse", "HEAD"]),
+        }
+    }
+}
+
+#[cfg(feature = "git")]
+impl Default for ExportPatchTool {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+#[cfg(feature = "git")]
+impl Tool for ExportPatchTool {
+    fn name(&self) -> &str {
+        &self.name
+    }
+
+    fn description(&self) -> &str {
+        "Export the commits since a base plus any uncommitted changes as a git format-patch series, titled from the submit summary, without changing the branch or the index"
+    }
+
+    fn signature(&self) -> &str {
+        "export_patch [--base=<rev>] [--output=<file>] [--title=<text>] [--body=<text>]"
+    }
+
+    fn category(&self) -> ToolCategory {
+        ToolCategory::Vcs
+    }
+
+    fn danger_level(&self) -> DangerLevel {
+        DangerLevel::ModifiesFiles
+    }
+
+    fn validate_args(&self, _args: &ToolArgs) -> Result<(), ToolError> {
+        Ok(())
+    }
+
+    fn accessed_paths(&self, args: &ToolArgs) -> Vec<PathBuf> {
+        args.get_named_arg("output")
+            .map(PathBuf::from)
+            .into_iter()
+            .collect()
+    }
+
+    fn execute(&mut self, args: &ToolArgs, state: &Arc<Mutex<ToolState>>) -> Result<ToolResult> {
+        let dir = state
+            .lock()
+            .map_err(|e| anyhow::anyhow!("Failed to lock state: {}", e))?
+            .working_directory
+            .clone();
+        let root = match repo_root(&dir) {
+            Ok(root) => root,
+            Err(e) => return Ok(ToolResult::error(format!("{:#}", e))),
+        };
+        let head = match git(&root, &["rev-parse", "--verify", "HEAD"]) {
+            Ok(head) => head,
+            Err(_) => {
+                return Ok(ToolResult::error(
+                    "export_patch needs at least one commit to diff against".to_string(),
+                ))
+            }
+        };
+        let base = match args.get_named_arg("base") {
+            Some(base) => git(
+                &root,
+                &["rev-parse", "--verify", &format!("{}^{{commit}}", base)],
+            ),
+            None => Self::default_base(&root),
+        };
+        let base = match base {
+            Ok(base) => base,
+            Err(e) => return Ok(ToolResult::error(format!("{:#}", e))),
+        };
+
+        // Snapshot uncommitted work into a commit object nothing points to
+        let uncommitted = has_uncommitted_changes(&root)?;
+        let mut tip = head.clone();
+        let snapshot_files = if uncommitted {
+            let index_dir = tempfile::tempdir()?;
+            let index = index_dir.path().join("index");
+            stage_all(&root, Some(&index))?;
+            let tree = git_with(&root, &["write-tree"], &[("GIT_INDEX_FILE", &index)], None)?;
+            let files = git(&root, &["diff", "--name-only", &head, &tree])?;
+            Some((tree, files))
+        } else {
+            None
+        };
+
+        let files_since_base: Vec<String> = {
+            let mut files: Vec<String> = git(&root, &["diff", "--name-only", &base, &head])?
+                .lines()
+                .map(str::to_string)
+                .collect();
+            if let Some((_, snapshot)) = &snapshot_files {
+                files.extend(snapshot.lines().map(str::to_string));
+            }
+            files.sort();
+            files.dedup();
+            files
+        };
+        let (generated_title, generated_body) = {
+            let state_guard = state
+                .lock()
+                .map_err(|e| anyhow::anyhow!("Failed to lock state: {}", e))?;
+            describe_change(&state_guard, &files_since_base)
+        };
+        let title = args
+            .get_named_arg("title")
+            .cloned()
+            .unwrap_or(generated_title);
+        let body = args
+            .get_named_arg("body")
+            .cloned()
+            .unwrap_or(generated_body);
+
+        if let Some((tree, _)) = &snapshot_files {
+            let message = format!("{}\n\n{}\n", title, body);
+            tip = git_with(
+                &root,
+                &["commit-tree", tree, "-p", &head, "-F", "-"],
+                &[],
+                Some(&message),
+            )?;
+        }
+        if tip == base {
+            return Ok(ToolResult::success_with_data(
+                format!(
+                    "Nothing to export: no changes since {}",
+                    &base[..base.len().min(12)]
+                ),
+                serde_json::json!({"base": base, "head": head, "commits": 0}),
+            ));
+        }
+
+        let range = format!("{}..{}", base, tip);
+        let patch = git(&root, &["format-patch", "--stdout", &range])?;
+        let commits = git(&root, &["rev-list", "--count", &range])?
+            .parse::<usize>()
+            .unwrap_or(0);
+        let stat = git(&root, &["diff", "--shortstat", &base, &tip])?;
+
+        let output = args.get_named_arg("output").map(|output| {
+            let path = PathBuf::from(output);
+            if path.is_absolute() {
+                path
+            } else {
+                dir.join(path)
+            }
+        });
+        if let Some(output) = &output {
+            if let Some(parent) = output.parent() {
+                std::fs::create_dir_all(parent)?;
+            }
+            std::fs::write(output, format!("{}\n", patch))?;
+        }
+        if let Ok(mut state_guard) = state.lock() {
+            state_guard.push_history(format!("export_patch: {}", range));
+        }
+
+        let header = format!(
+            "Exported {} commit(s) ({}{}){}\nTitle: {}",
+            commits,
+            stat.trim(),
+            if uncommitted {
+                ", including uncommitted changes"
+            } else {
+                ""
+            },
+            output
+                .as_ref()
+                .map(|output| format!(" to {}", output.display()))
+                .unwrap_or_default(),
+            title
+        );
+        let message = if output.is_some() {
+            header
+        } else {
+            format!("{}\n\n{}", header, patch)
+        };
+        Ok(ToolResult::success_with_data(
+            message,
+            serde_json::json!({
+                "base": base,
+                "head": head,
+                "tip": tip,
+                "commits": commits,
+                "uncommitted": uncommitted,
+                "title": title,
+                "body": body,
+                "files": files_since_base,
+                "output": output,
+                "patch": patch,
+            }),
+        ))
+    }
+
+    fn get_parameters_schema(&self) -> serde_json::Value {
+        serde_json::json!({
+            "type": "object",
+            "properties": {
+                "base": {
+                    "type": "string",
+                    "description": "Revision the series starts after (defaults to where the branch forked from its upstream, or HEAD)"
+                },
+                "output": {
+                    "type": "string",
+                    "description": "File to write the series to; the patch is returned in the result when omitted"
+                },
+                "title": {
+                    "type": "string",
+                    "description": "Subject of the commit made from uncommitted changes (defaults to the submit summary)"
+                },
+                "body": {
+                    "type": "string",
+                    "description": "Message body of that commit (defaults to the submit summary, plan and changed files)"
+                }
+            },
+            "required": []
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    #[cfg(feature = "git")]
+    use std::fs;
+
+    /// A repository with one commit of `a.txt`
+    #[cfg(feature = "git")]
+    pub(crate) fn init_repo() -> tempfile::TempDir {
+        let temp_dir = tempfile::TempDir::new().unwrap();
+        let dir = temp_dir.path();
+        git(dir, &["init", "-q", "-b", "main"]).unwrap();
+        git(dir, &["config", "user.name", "Test"]).unwrap();
+        git(dir, &["config", "user.email", "test@example.com"]).unwrap();
+        fs::write(dir.join("a.txt"), "one\n").unwrap();
+        git(dir, &["add", "a.txt"]).unwrap();
+        git(dir, &["commit", "-q", "-m", "Initial commit"]).unwrap();
+        temp_dir
+    }
+
+    #[test]
+    fn test_describe_change() {
+        let mut state = ToolState::new();
+        let files = vec!["src/lib.rs".to_string()];
+        assert_eq!(
+            describe_change(&state, &files),
+            (
+                "Update src/lib.rs".to_string(),
+                "Files changed:\n- src/lib.rs".to_string()
+            )
+        );
+
+        state.annotate_history("reproduce the panic");
+        state.submit_summary =
+            Some("Fix panic on empty input\n\nReturn an error instead.".to_string());
+        let (title, body) = describe_change(&state, &files);
+        assert_eq!(title, "Fix panic on empty input");
+        assert_eq!(
+            body,
+            "Return an error instead.\n\nPlan:\n- reproduce the panic\n\nFiles changed:\n- src/lib.rs"
+        );
+
+        state.submit_summary = Some("x".repeat(100));
+        assert_eq!(
+            describe_change(&state, &[]).0.chars().count(),
+            MAX_TITLE_CHARS
+        );
+    }
+
+    #[test]
+    #[cfg(feature = "git")]
+    fn test_export_patch() {
+        let repo = init_repo();
+        let dir = repo.path();
+        let mut tool_state = ToolState::new();
+        tool_state.set_working_directory(dir);
+        tool_state.submit_summary = Some("Add b.txt and extend a.txt".to_string());
+        let state = Arc::new(Mutex::new(tool_state));
+        let mut tool = ExportPatchTool::new();
+
+        let result = tool.execute(&ToolArgs::from_args(&[]), &state).unwrap();
+        assert!(result.message.starts_with("Nothing to export"));
+
+        fs::write(dir.join("a.txt"), "one\ntwo\n").unwrap();
+        fs::write(dir.join("b.txt"), "new\n").unwrap();
+        fs::create_dir_all(dir.join(".cats/journal")).unwrap();
+        fs::write(dir.join(".cats/journal/log"), "{}\n").unwrap();
+        let args = ToolArgs::from_args(&["--output=out/change.patch"]);
+        let result = tool.execute(&args, &state).unwrap();
+        assert!(result.success, "{}", result.message);
+        assert!(result.message.starts_with(
+            "Exported 1 commit(s) (2 files changed, 2 insertions(+), including uncommitted changes) to"
+        ));
+        let patch = fs::read_to_string(dir.join("out/change.patch")).unwrap();
+        assert!(patch.contains("Subject: [PATCH] Add b.txt and extend a.txt"));
+        assert!(patch.contains("+two"));
+        assert!(!patch.contains(".cats"));
+        let data = result.data.unwrap();
+        assert_eq!(data["files"], serde_json::json!(["a.txt", "b.txt"]));
+
+        // The branch, index and working tree are as they were
+        assert_eq!(git(dir, &["rev-list", "--count", "HEAD"]).unwrap(), "1");
+        assert_eq!(git(dir, &["diff", "--cached", "--name-only"]).unwrap(), "");
+        assert_eq!(fs::read_to_string(dir.join("a.txt")).unwrap(), "one\ntwo\n");
+    }
+}
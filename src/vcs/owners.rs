@@ -0,0 +1,597 @@
+//! CODEOWNERS parsing and lookup
+//!
+//! Reads the file GitHub and GitLab look for (`.github/CODEOWNERS`, `CODEOWNERS`,
+//! `docs/CODEOWNERS` or `.gitlab/CODEOWNERS`) and resolves the owners of a path with
+//! their rules: the last matching rule wins, a pattern without a `/` before its end
+//! matches at any depth, a leading `/` anchors it to the root, and a pattern naming a
+//! directory owns everything below it unless its last segment is a wildcard (`docs/*`
+//! owns `docs/a.md` but not `docs/api/b.md`). GitLab sections (`[Name]`, `^[Name]`,
+//! with default owners after the header) are each resolved on their own and their
+//! owners combined.
+
+#[cfg(feature = "git")]
+use super::repo_root;
+#[cfg(feature = "git")]
+use crate::core::{DangerLevel, Tool, ToolArgs, ToolCategory, ToolError, ToolResult};
+#[cfg(feature = "git")]
+use crate::state::ToolState;
+use anyhow::{Context, Result};
+use glob::{MatchOptions, Pattern};
+use serde::Serialize;
+#[cfg(feature = "git")]
+use std::collections::BTreeMap;
+#[cfg(feature = "git")]
+use std::path::Component;
+use std::path::{Path, PathBuf};
+#[cfg(feature = "git")]
+use std::sync::{Arc, Mutex};
+
+/// Where CODEOWNERS is looked for, in the order GitHub and GitLab check
+pub const CODEOWNERS_LOCATIONS: &[&str] = &[
+    ".github/CODEOWNERS",
+    "CODEOWNERS",
+    "docs/CODEOWNERS",
+    ".gitlab/CODEOWNERS",
+];
+
+/// One rule of a CODEOWNERS file
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+pub struct OwnerRule {
+    pub pattern: String,
+    /// Owners named on the line, or the section's default owners; empty when the
+    /// rule explicitly leaves paths unowned
+    pub owners: Vec<String>,
+    /// 1-based line in the file
+    pub line: usize,
+    /// GitLab section the rule is in
+    pub section: Option<String>,
+    #[serde(skip)]
+    glob: Option<Pattern>,
+    #[serde(skip)]
+    dir_only: bool,
+    #[serde(skip)]
+    owns_children: bool,
+}
+
+impl OwnerRule {
+    fn new(pattern: &str, owners: Vec<String>, line: usize, section: Option<String>) -> Self {
+        let dir_only = pattern.ends_with('/');
+        let trimmed = pattern.trim_end_matches('/');
+        let glob = if trimmed.is_empty() {
+            // `/` on its own is the whole repository
+            "**".to_string()
+        } else if trimmed.trim_start_matches('/').contains('/') || trimmed.starts_with('/') {
+            trimmed.trim_start_matches('/').to_string()
+        } else {
+            format!("**/{}", trimmed)
+        };
+        let last = glob.rsplit('/').next().unwrap_or(&glob);
+        let owns_children = !last.contains(['*', '?', '[']) || last == "**";
+        Self {
+            pattern: pattern.to_string(),
+            owners,
+            line,
+            section,
+            glob: Pattern::new(&glob).ok(),
+            dir_only,
+            owns_children,
+        }
+    }
+
+    /// Whether the rule covers `path` (relative to the repository root, `/`-separated)
+    pub fn matches(&self, path: &str, is_dir: bool) -> bool {
+        let Some(glob) = &self.glob else {
+            return false;
+        };
+        let options = MatchOptions {
+            case_sensitive: true,
+            require_literal_separator: true,
+            require_literal_leading_dot: false,
+        };
+        let path = path.trim_matches('/');
+        if (is_dir || !self.dir_only) && glob.matches_with(path, options) {
+            return true;
+        }
+        // A rule naming a directory owns what is inside it
+        self.owns_children
+            && path
+                .match_indices('/')
+                .any(|(i, _)| glob.matches_with(&path[..i], options))
+    }
+}
+
+/// The owners of one path
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+pub struct Ownership {
+    pub path: String,
+    /// Owners from every matching rule, without duplicates
+    pub owners: Vec<String>,
+    /// The deciding rule of each section that has one
+    pub rules: Vec<OwnerRule>,
+}
+
+/// A parsed CODEOWNERS file
+#[derive(Debug, Clone, Default)]
+pub struct CodeOwners {
+    /// The file, relative to the repository root
+    pub file: PathBuf,
+    pub rules: Vec<OwnerRule>,
+    /// Lines that are neither comments, rules nor section headers
+    pub invalid_lines: Vec<usize>,
+}
+
+impl CodeOwners {
+    /// Parse CODEOWNERS text
+    pub fn parse(text: &str) -> Self {
+        let mut owners = Self::default();
+        let mut section: Option<(String, Vec<String>)> = None;
+        for (index, line) in text.lines().enumerate() {
+            let line_number = index + 1;
+            let tokens = split_tokens(line);
+            let Some(first) = tokens.first() else {
+                continue;
+            };
+            if let Some((name, defaults)) = parse_section(first, &tokens[1..]) {
+                section = Some((name, defaults));
+                continue;
+            }
+            let mut rule_owners: Vec<String> = tokens[1..].to_vec();
+            if rule_owners.iter().any(|owner| !is_owner(owner)) {
+                owners.invalid_lines.push(line_number);
+                continue;
+            }
+            if rule_owners.is_empty() {
+                if let Some((_, defaults)) = &section {
+                    rule_owners = defaults.clone();
+                }
+            }
+            owners.rules.push(OwnerRule::new(
+                first,
+                rule_owners,
+                line_number,
+                section.as_ref().map(|(name, _)| name.clone()),
+            ));
+        }
+        owners
+    }
+
+    /// The CODEOWNERS file of the repository at `root`, if it has one
+    pub fn load(root: &Path) -> Result<Option<Self>> {
+        for location in CODEOWNERS_LOCATIONS {
+            let path = root.join(location);
+            if path.is_file() {
+                let text = std::fs::read_to_string(&path)
+                    .with_context(|| format!("Failed to read {}", path.display()))?;
+                let mut owners = Self::parse(&text);
+                owners.file = PathBuf::from(location);
+                return Ok(Some(owners));
+            }
+        }
+        Ok(None)
+    }
+
+    /// Owners of `path`, relative to the repository root
+    pub fn owners_of(&self, path: &str, is_dir: bool) -> Ownership {
+        // The last matching rule of each section decides
+        let mut deciding: Vec<&OwnerRule> = Vec::new();
+        for rule in self.rules.iter().rev() {
+            if deciding.iter().any(|chosen| chosen.section == rule.section) {
+                continue;
+            }
+            if rule.matches(path, is_dir) {
+                deciding.push(rule);
+            }
+        }
+        deciding.sort_by_key(|rule| rule.line);
+
+        let mut owners: Vec<String> = Vec::new();
+        for owner in deciding.iter().flat_map(|rule| &rule.owners) {
+            if !owners.contains(owner) {
+                owners.push(owner.clone());
+            }
+        }
+        Ownership {
+            path: path.to_string(),
+            owners,
+            rules: deciding.into_iter().cloned().collect(),
+        }
+    }
+}
+
+/// Whitespace-separated tokens of a line up to a comment; `\ ` and `\#` escape
+fn split_tokens(line: &str) -> Vec<String> {
+    let mut tokens = Vec::new();
+    let mut current = String::new();
+    let mut chars = line.chars();
+    while let Some(c) = chars.next() {
+        match c {
+            '\\' => match chars.next() {
+                Some(next @ (' ' | '#' | '\\')) => current.push(next),
+                Some(next) => {
+                    current.push('\\');
+                    current.push(next);
+                }
+                None => current.push('\\'),
+            },
+            '#' if current.is_empty() => break,
+            c if c.is_whitespace() => {
+                if !current.is_empty() {
+                    tokens.push(std::mem::take(&mut current));
+                }
+            }
+            c => current.push(c),
+        }
+    }
+    if !current.is_empty() {
+        tokens.push(current);
+    }
+    tokens
+}
+
+/// A GitLab section header: `[Name]`, `^[Name]` or `[Name][2]`, then default owners
+fn parse_section(first: &str, rest: &[String]) -> Option<(String, Vec<String>)> {
+    let header = first.strip_prefix('^').unwrap_or(first);
+    let name = header.strip_prefix('[')?;
+    let end = name.find(']')?;
+    let approvals = &name[end + 1..];
+    let approvals_valid = approvals.is_empty()
+        || approvals
+            .strip_prefix('[')
+            .and_then(|count| count.strip_suffix(']'))
+            .is_some_and(|count| count.parse::<u32>().is_ok());
+    if !approvals_valid || !rest.iter().all(|owner| is_owner(owner)) {
+        return None;
+    }
+    Some((name[..end].to_string(), rest.to_vec()))
+}
+
+/// `@user`, `@org/team`, `@@role` or an email address
+fn is_owner(token: &str) -> bool {
+    token.starts_with('@') && token.len() > 1
+        || token
+            .split_once('@')
+            .is_some_and(|(user, domain)| !user.is_empty() && domain.contains('.'))
+}
+
+/// Tool reporting the owners CODEOWNERS assigns to paths
+#[cfg(feature = "git")]
+pub struct CodeOwnersTool {
+    name: String,
+}
+
+#[cfg(feature = "git")]
+impl CodeOwnersTool {
+    pub fn new() -> Self {
+        Self {
+            name: "code_owners".to_string(),
+        }
+    }
+
+    /// Paths from the positional arguments, `path` and the `paths` list
+    fn paths(args: &ToolArgs) -> Vec<String> {
+        let mut paths: Vec<String> = args.args.clone();
+        if let Some(path) = args.get_named_arg("path") {
+            paths.push(path.clone());
+        }
+        if let Some(list) = args.get_named_arg("paths") {
+            match serde_json::from_str::<Vec<String>>(list) {
+                Ok(list) => paths.extend(list),
+                Err(_) => paths.extend(
+                    list.split(',')
+                        .map(str::trim)
+                        .filter(|path| !path.is_empty())
+                        .map(str::to_string),
+                ),
+            }
+        }
+        paths
+    }
+
+    /// `path` relative to `root` with `/` separators, or `None` outside it
+    fn relative_to(root: &Path, path: &Path) -> Option<String> {
+        // Paths that no longer exist, e.g. deleted in a change, are resolved lexically
+        let absolute = match path.canonicalize() {
+            Ok(absolute) => absolute,
+            Err(_) => {
+                let mut normalized = PathBuf::new();
+                for component in std::path::absolute(path).ok()?.components() {
+                    match component {
+                        Component::ParentDir => {
+                            normalized.pop();
+                        }
+                        Component::CurDir => {}
+                        component => normalized.push(component),
+                    }
+                }
+                normalized
+            }
+        };
+        let relative = absolute.strip_prefix(root).ok()?;
+        let parts: Vec<String> = relative
+            .components()
+            .filter_map(|c| match c {
+                Component::Normal(part) => Some(part.to_string_lossy().to_string()),
+                _ => None,
+            })
+            .collect();
+        Some(parts.join("/"))
+    }
+
+    fn describe(ownership: &Ownership) -> String {
+        let rules = ownership
+            .rules
+            .iter()
+            .map(|rule| match &rule.section {
+                Some(section) => format!("line {} [{}]: {}", rule.line, section, rule.pattern),
+                None => format!("line {}: {}", rule.line, rule.pattern),
+            })
+            .collect::<Vec<_>>()
+            .join("; ");
+        match (ownership.rules.is_empty(), ownership.owners.is_empty()) {
+            (true, _) => format!("{}: no matching rule", ownership.path),
+            (false, true) => format!("{}: unowned ({})", ownership.path, rules),
+            (false, false) => format!(
+                "{}: {} ({})",
+                ownership.path,
+                ownership.owners.join(" "),
+                rules
+            ),
+        }
+    }
+}
+
+#[cfg(feature = "git")]
+impl Default for CodeOwnersTool {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+#[cfg(feature = "git")]
+impl Tool for CodeOwnersTool {
+    fn name(&self) -> &str {
+        &self.name
+    }
+
+    fn description(&self) -> &str {
+        "Report the teams and users CODEOWNERS assigns to files or directories, with the rule that decides, for routing reviews and scoping changes"
+    }
+
+    fn signature(&self) -> &str {
+        "code_owners <path>... [--paths=<list>]"
+    }
+
+    fn category(&self) -> ToolCategory {
+        ToolCategory::Vcs
+    }
+
+    fn danger_level(&self) -> DangerLevel {
+        DangerLevel::ReadOnly
+    }
+
+    fn validate_args(&self, args: &ToolArgs) -> Result<(), ToolError> {
+        if Self::paths(args).is_empty() {
+            return Err(ToolError::InvalidArgs {
+                message: "Usage: code_owners <path>... [--paths=<list>]".to_string(),
+            });
+        }
+        Ok(())
+    }
+
+    fn execute(&mut self, args: &ToolArgs, state: &Arc<Mutex<ToolState>>) -> Result<ToolResult> {
+        let dir = state
+            .lock()
+            .map_err(|e| anyhow::anyhow!("Failed to lock state: {}", e))?
+            .working_directory
+            .clone();
+        // Outside git, the nearest directory with a CODEOWNERS file is the root
+        let root = repo_root(&dir).unwrap_or_else(|_| {
+            dir.ancestors()
+                .find(|ancestor| {
+                    CODEOWNERS_LOCATIONS
+                        .iter()
+                        .any(|location| ancestor.join(location).is_file())
+                })
+                .unwrap_or(&dir)
+                .to_path_buf()
+        });
+        let root = root.canonicalize().unwrap_or(root);
+        let Some(code_owners) = CodeOwners::load(&root)? else {
+            return Ok(ToolResult::error(format!(
+                "No CODEOWNERS file in {} (looked for {})",
+                root.display(),
+                CODEOWNERS_LOCATIONS.join(", ")
+            )));
+        };
+
+        let mut results = Vec::new();
+        let mut outside = Vec::new();
+        for path in Self::paths(args) {
+            let full = dir.join(&path);
+            match Self::relative_to(&root, &full) {
+                Some(relative) => results.push(code_owners.owners_of(&relative, full.is_dir())),
+                None => outside.push(path),
+            }
+        }
+
+        // Who to ask about the whole set
+        let mut by_owner: BTreeMap<&str, Vec<&str>> = BTreeMap::new();
+        for ownership in &results {
+            for owner in &ownership.owners {
+                by_owner.entry(owner).or_default().push(&ownership.path);
+            }
+        }
+        let unowned: Vec<&str> = results
+            .iter()
+            .filter(|ownership| ownership.owners.is_empty())
+            .map(|ownership| ownership.path.as_str())
+            .collect();
+
+        let mut message = format!("Owners from {}:\n", code_owners.file.display());
+        for ownership in &results {
+            message.push_str(&format!("  {}\n", Self::describe(ownership)));
+        }
+        if results.len() > 1 && !by_owner.is_empty() {
+            message.push_str("\nBy owner:\n");
+            for (owner, paths) in &by_owner {
+                message.push_str(&format!("  {}: {} path(s)\n", owner, paths.len()));
+            }
+        }
+        if !outside.is_empty() {
+            message.push_str(&format!(
+                "\nOutside the repository: {}\n",
+                outside.join(", ")
+            ));
+        }
+        if !code_owners.invalid_lines.is_empty() {
+            message.push_str(&format!(
+                "\nWarning: ignored unparseable lines {} of {}\n",
+                code_owners
+                    .invalid_lines
+                    .iter()
+                    .map(|line| line.to_string())
+                    .collect::<Vec<_>>()
+                    .join(", "),
+                code_owners.file.display()
+            ));
+        }
+
+        Ok(ToolResult::success_with_data(
+            message.trim_end().to_string(),
+            serde_json::json!({
+                "file": code_owners.file,
+                "results": results,
+                "by_owner": by_owner,
+                "unowned": unowned,
+                "outside": outside,
+                "invalid_lines": code_owners.invalid_lines,
+            }),
+        ))
+    }
+
+    fn get_parameters_schema(&self) -> serde_json::Value {
+        serde_json::json!({
+            "type": "object",
+            "properties": {
+                "path": {
+                    "type": "string",
+                    "description": "File or directory to look up, relative to the working directory"
+                },
+                "paths": {
+                    "type": "array",
+                    "items": {"type": "string"},
+                    "description": "Several files or directories to look up at once"
+                }
+            },
+            "required": []
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const CODEOWNERS: &str = r#"# Default owners
+*       @org/core
+*.js    @org/frontend alice@example.com
+/build/logs/ @doctocat
+docs/*  @org/docs
+apps/   @octocat
+/scripts/generated
+path\ with\ spaces/ @spaces
+
+[Database][2] @org/dba
+migrations/
+db/schema.rb @org/dba @bob
+"#;
+
+    fn owners_of(owners: &CodeOwners, path: &str) -> Vec<String> {
+        owners.owners_of(path, false).owners
+    }
+
+    #[test]
+    fn test_codeowners_rules() {
+        let owners = CodeOwners::parse(CODEOWNERS);
+        assert!(owners.invalid_lines.is_empty());
+        assert_eq!(owners.rules.len(), 9);
+        assert_eq!(owners_of(&owners, "src/main.rs"), vec!["@org/core"]);
+        assert_eq!(
+            owners_of(&owners, "web/app.js"),
+            vec!["@org/frontend", "alice@example.com"]
+        );
+        assert_eq!(
+            owners_of(&owners, "build/logs/2024/x.log"),
+            vec!["@doctocat"]
+        );
+        assert_eq!(owners_of(&owners, "docs/intro.md"), vec!["@org/docs"]);
+        // `docs/*` does not reach into subdirectories
+        assert_eq!(owners_of(&owners, "docs/api/ref.md"), vec!["@org/core"]);
+        assert_eq!(
+            owners_of(&owners, "nested/apps/ui/main.rs"),
+            vec!["@octocat"]
+        );
+        assert_eq!(
+            owners_of(&owners, "path with spaces/a.txt"),
+            vec!["@spaces"]
+        );
+
+        // An owner-less rule leaves the path unowned
+        let unowned = owners.owners_of("scripts/generated/out.sh", false);
+        assert!(unowned.owners.is_empty());
+        assert_eq!(unowned.rules[0].line, 7);
+
+        // Sections combine; rules without owners take the section's defaults
+        let migration = owners.owners_of("migrations/001_init.sql", false);
+        assert_eq!(migration.owners, vec!["@org/core", "@org/dba"]);
+        assert_eq!(migration.rules[1].section.as_deref(), Some("Database"));
+        assert_eq!(
+            owners_of(&owners, "db/schema.rb"),
+            vec!["@org/core", "@org/dba", "@bob"]
+        );
+    }
+
+    #[test]
+    #[cfg(feature = "git")]
+    fn test_code_owners_tool() {
+        let temp_dir = tempfile::TempDir::new().unwrap();
+        let root = temp_dir.path();
+        std::fs::create_dir_all(root.join(".github")).unwrap();
+        std::fs::create_dir_all(root.join("docs/api")).unwrap();
+        std::fs::write(root.join(".github/CODEOWNERS"), CODEOWNERS).unwrap();
+        let mut tool_state = ToolState::new();
+        tool_state.set_working_directory(root.join("docs"));
+        let state = Arc::new(Mutex::new(tool_state));
+
+        let mut tool = CodeOwnersTool::new();
+        assert!(tool.validate_args(&ToolArgs::from_args(&[])).is_err());
+        let args = ToolArgs::from_args(&[
+            "intro.md",
+            r#"--paths=["api", "../web/app.js", "/elsewhere"]"#,
+        ]);
+        let result = tool.execute(&args, &state).unwrap();
+        assert!(result.success, "{}", result.message);
+        assert!(result.message.starts_with(
+            "Owners from .github/CODEOWNERS:\n  docs/intro.md: @org/docs (line 5: docs/*)"
+        ));
+        let data = result.data.unwrap();
+        assert_eq!(data["results"][1]["path"], "docs/api");
+        assert_eq!(
+            data["results"][1]["owners"],
+            serde_json::json!(["@org/docs"])
+        );
+        assert_eq!(
+            data["by_owner"]["@org/frontend"],
+            serde_json::json!(["web/app.js"])
+        );
+        assert_eq!(data["outside"], serde_json::json!(["/elsewhere"]));
+    }
+
+    #[test]
+    fn test_codeowners_invalid_lines() {
+        let owners = CodeOwners::parse("src/ @team\nlib/ not-an-owner\n");
+        assert_eq!(owners.rules.len(), 1);
+        assert_eq!(owners.invalid_lines, vec![2]);
+        assert!(owners_of(&owners, "lib/a.rs").is_empty());
+    }
+}
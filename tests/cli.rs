@@ -0,0 +1,50 @@
+//! Exit codes and error kinds of the `cats` binary
+
+#![cfg(feature = "cli")]
+
+use assert_cmd::Command;
+use std::fs;
+use tempfile::TempDir;
+
+/// Run `cats --json` with `args` in `dir`, returning the exit code and the error kind
+/// printed on stderr
+fn run(dir: &TempDir, args: &[&str]) -> (i32, String) {
+    let output = Command::cargo_bin("cats")
+        .unwrap()
+        .current_dir(dir.path())
+        .arg("--json")
+        .args(args)
+        .output()
+        .unwrap();
+    let stderr = String::from_utf8_lossy(&output.stderr);
+    let kind = stderr
+        .lines()
+        .filter_map(|line| serde_json::from_str::<serde_json::Value>(line).ok())
+        .find_map(|error| error["kind"].as_str().map(str::to_string))
+        .unwrap_or_default();
+    (output.status.code().unwrap(), kind)
+}
+
+#[test]
+fn test_exit_codes_follow_the_error_class() {
+    let dir = TempDir::new().unwrap();
+    fs::write(dir.path().join("a.txt"), "alpha\n").unwrap();
+    fs::create_dir(dir.path().join("sub")).unwrap();
+
+    for (args, code, kind) in [
+        (&["open", "a.txt"][..], 0, ""),
+        (
+            &["replace_text", "a.txt", "beta", "gamma"],
+            1,
+            "tool_failed",
+        ),
+        (&["open", "--arg", "line"], 2, "invalid_args"),
+        (&["opne", "a.txt"], 3, "tool_not_found"),
+        (&["open", "missing.txt"], 4, "file_not_found"),
+        (&["open", "sub"], 6, "io"),
+        (&["find_file", "[", "."], 7, "regex"),
+        (&["update_callers", "--arg", "json={bad"], 8, "json"),
+    ] {
+        assert_eq!(run(&dir, args), (code, kind.to_string()), "{:?}", args);
+    }
+}